@@ -0,0 +1,137 @@
+//! Benchmarks of the hot paths of the algebra core: polynomial products, linear
+//! substitutions, determinants of polynomial matrices, and the elimination of
+//! canned geometry scenes. Run with `cargo bench --bench algebra`.
+
+use std::hint::black_box;
+use std::rc::Rc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use poly_algebra_core::determinant;
+use poly_algebra_core::elimination::Elimination;
+use poly_algebra_core::poly::{Poly, PolyOperations};
+
+/// Systems of equations of loci, eliminated down to the plane of a and b
+const SCENES: &[(&str, &[&str])] = &[
+    // The midpoint (a, b) of (2, 0) and a point (c, d) on the unit circle
+    ("midpoint", &["c^2 + d^2 - 1", "2*a - c - 2", "2*b - d"]),
+    // The orthocenter (a, b) of (-1, 0), (1, 0) and a point (c, d) on a circle
+    (
+        "orthocenter",
+        &["c^2 + d^2 - 2*d - 3", "a - c", "(a + 1)*(c - 1) + b*d"],
+    ),
+    // A limaçon: the points at distance 1 from (c, d) on the unit circle, on the
+    // line through (c, d) and (1, 0)
+    (
+        "limacon",
+        &[
+            "c^2 + d^2 - 1",
+            "(a - c)^2 + (b - d)^2 - 1",
+            "(a - 1)*d - b*(c - 1)",
+        ],
+    ),
+    // Watt's linkage: the midpoint of a bar of length 2 between two cranks of
+    // length 2 turning around (-2, 0) and (2, 0)
+    (
+        "watt",
+        &[
+            "(c + 2)^2 + d^2 - 4",
+            "(e - 2)^2 + f^2 - 4",
+            "(e - c)^2 + (f - d)^2 - 4",
+            "2*a - c - e",
+            "2*b - d - f",
+        ],
+    ),
+];
+
+fn poly(s: &str) -> Poly {
+    Poly::parse_equation(s).unwrap()
+}
+
+fn power(base: &Poly, exponent: u32) -> Poly {
+    (1..exponent).fold(base.clone(), |product, _| product.multiply(base))
+}
+
+fn bench_multiply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("multiply");
+    for exponent in [2, 4, 6] {
+        let a = power(&poly("a + b + c + 1"), exponent);
+        let b = power(&poly("a - 2*b + 3*c - 1"), exponent);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(exponent),
+            &(a, b),
+            |bench, (a, b)| bench.iter(|| black_box(a).multiply(black_box(b))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_substitute_linear(c: &mut Criterion) {
+    let mut group = c.benchmark_group("substitute_linear");
+    for exponent in [2, 4, 6] {
+        // b = (a + 2*c - 1) / 3 in a dense polynomial of a, b and c
+        let target = power(&poly("a + b + c + 1"), exponent);
+        let value = Rc::new(poly("a + 2*c - 1"));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(exponent),
+            &target,
+            |bench, target| bench.iter(|| black_box(target).substitute_linear(1, value.clone(), 3)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_determinant(c: &mut Criterion) {
+    let matrix = |rows: &[&[&str]]| -> Vec<Vec<Rc<Poly>>> {
+        rows.iter()
+            .map(|row| row.iter().map(|e| Rc::new(poly(e))).collect())
+            .collect()
+    };
+    let mut group = c.benchmark_group("determinant");
+    // Expanded by cofactors
+    let cofactors = matrix(&[
+        &["a", "b + 1", "c^2"],
+        &["a*b", "c - 2", "a + b"],
+        &["1", "a^2 - c", "b*c"],
+    ]);
+    group.bench_function("3x3", |bench| {
+        bench.iter(|| determinant::determinant(black_box(&cofactors)))
+    });
+    // Interpolated, like the Sylvester matrices of resultants
+    let interpolated = matrix(&[
+        &["a", "b + 1", "0", "2", "c"],
+        &["1", "a*b", "b^2", "0", "a - c"],
+        &["0", "-3", "a + b", "a^2", "1"],
+        &["b", "1", "0", "a - 1", "c^2"],
+        &["c", "0", "a*c", "b", "2"],
+    ]);
+    group.bench_function("5x5", |bench| {
+        bench.iter(|| determinant::determinant(black_box(&interpolated)))
+    });
+    group.finish();
+}
+
+fn bench_elimination(c: &mut Criterion) {
+    let mut group = c.benchmark_group("elimination");
+    for (name, equations) in SCENES {
+        let polys: Vec<Rc<Poly>> = equations.iter().map(|e| Rc::new(poly(e))).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &polys, |bench, polys| {
+            bench.iter(|| {
+                let mut elimination = Elimination::new(polys, 0, 1, true);
+                while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+                    elimination.eliminate_var(var_search_result);
+                }
+                elimination.polys
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_multiply,
+    bench_substitute_linear,
+    bench_determinant,
+    bench_elimination
+);
+criterion_main!(benches);
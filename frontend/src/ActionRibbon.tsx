@@ -32,7 +32,7 @@ export function ActionRibbon({
       return value === null || Object.values(ObjectType).includes(value as ObjectType);
     };
 
-    fetch('http://localhost:8080/actions')
+    fetch('http://localhost:8080/api/v1/actions')
       .then(res => res.json())
       .then((data: Partial<Action>[]) => {
         const validActions = data.filter(
@@ -73,7 +73,7 @@ async function deleteShape(
 ) {
   try {
     // First, check for dependents
-    const dependentsResponse = await fetch(`http://localhost:8080/scenes/${sceneId}/${shape.name}/dependents`);
+    const dependentsResponse = await fetch(`http://localhost:8080/api/v1/scenes/${sceneId}/${shape.name}/dependents`);
 
     if (!dependentsResponse.ok) {
       const text = await dependentsResponse.text();
@@ -117,7 +117,7 @@ async function performDeletion(
   setShapes: React.Dispatch<React.SetStateAction<Shape[]>>,
   setDisplayedPlotNames?: React.Dispatch<React.SetStateAction<Set<string>>>
 ) {
-  const response = await fetch(`http://localhost:8080/scenes/${sceneId}/${shape.name}`, {
+  const response = await fetch(`http://localhost:8080/api/v1/scenes/${sceneId}/${shape.name}`, {
     method: 'DELETE',
   });
 
@@ -207,7 +207,7 @@ function App() {
   }, [setStage, setStatusMessage, setEditedExpression]);
 
   useEffect(() => {
-    fetch('http://localhost:8080/scenes')
+    fetch('http://localhost:8080/api/v1/scenes')
       .then(res => res.json())
       .then((sceneInfos: SceneInfo[]) => {
         setScenes(sceneInfos);
@@ -221,7 +221,7 @@ function App() {
 
   const refreshScenes = async () => {
     try {
-      const response = await fetch('http://localhost:8080/scenes');
+      const response = await fetch('http://localhost:8080/api/v1/scenes');
       const sceneInfos: SceneInfo[] = await response.json();
       setScenes(sceneInfos);
     } catch (err) {
@@ -234,7 +234,7 @@ function App() {
     if (!newSceneName.trim()) return;
 
     try {
-      const response = await fetch('http://localhost:8080/scenes', {
+      const response = await fetch('http://localhost:8080/api/v1/scenes', {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
         body: JSON.stringify({ name: newSceneName.trim() }),
@@ -336,7 +336,7 @@ function App() {
   const fetchPlotPoints = useCallback(async (locusName: string) => {
     try {
       setStatusMessage("Computing the curve...");
-      const response = await fetch(`http://localhost:8080/scenes/${selectedSceneId}/plot/${locusName}?width=${window.innerWidth}&height=${window.innerHeight}&reduce_factors=true`);
+      const response = await fetch(`http://localhost:8080/api/v1/scenes/${selectedSceneId}/plot/${locusName}?width=${window.innerWidth}&height=${window.innerHeight}&reduce_factors=true`);
       if (!response.ok) {
         const text = await response.text();
         throw new Error(text || response.statusText);
@@ -356,7 +356,7 @@ function App() {
 
       // Update status message with point count, equation, and timing
       const equationText = plotData.formatted_equations.length > 0
-        ? plotData.formatted_equations.join(' × ')
+        ? plotData.formatted_equations.map((equation) => equation.text).join(' × ')
         : plotData.equation;
       setStatusMessage(`Computed the curve (point count: ${plotData.points.length}, equation: ${equationText}, time: ${plotData.time_taken.toFixed(3)}s)`);
     } catch (err) {
@@ -410,7 +410,7 @@ function App() {
       // Validate the expression
       const jsonString = JSON.stringify([expression]);
       const jsonParam = btoa(jsonString).replace(/\+/g, '-').replace(/\//g, '_').replace(/=/g, '');
-      const validationResponse = await fetch(`http://localhost:8080/scenes/${selectedSceneId}/validate?json=${jsonParam}`);
+      const validationResponse = await fetch(`http://localhost:8080/api/v1/scenes/${selectedSceneId}/validate?json=${jsonParam}`);
 
       if (!validationResponse.ok) {
         const text = await validationResponse.text();
@@ -432,7 +432,7 @@ function App() {
       }
 
       // Calculate the initial value of the expression
-      const response = await fetch(`http://localhost:8080/scenes/${selectedSceneId}/initial?json=${jsonParam}`);
+      const response = await fetch(`http://localhost:8080/api/v1/scenes/${selectedSceneId}/initial?json=${jsonParam}`);
 
       if (!response.ok) {
         const text = await response.text();
@@ -464,7 +464,7 @@ function App() {
         const dbObject = stage.getDBObject();
 
         try {
-          const res = await fetch(`http://localhost:8080/scenes/${selectedSceneId}/objects`, {
+          const res = await fetch(`http://localhost:8080/api/v1/scenes/${selectedSceneId}/objects`, {
             method: 'POST',
             headers: { 'Content-Type': 'application/json' },
             body: JSON.stringify(dbObject),
@@ -644,7 +644,7 @@ function App() {
         onClose={() => setIsSceneManagementModalOpen(false)}
         onSceneDeleted={async () => {
           // Refresh scenes and get the updated list
-          const response = await fetch('http://localhost:8080/scenes');
+          const response = await fetch('http://localhost:8080/api/v1/scenes');
           const updatedScenes: SceneInfo[] = await response.json();
           setScenes(updatedScenes);
 
@@ -49,14 +49,14 @@ function Legend({ displayedPlotNames, plotDataByLocusName, shapes }: LegendProps
                                 style={{ backgroundColor: color }}
                             />
                             <div className="legend-equation">
-                                {processEquationForWrapping(equation)}
+                                {processEquationForWrapping(equation.text)}
                             </div>
                         </div>
                     ));
                 } else {
                     // Single factor or fallback to equation
                     const equationText = plotData.formatted_equations.length > 0
-                        ? plotData.formatted_equations[0]
+                        ? plotData.formatted_equations[0].text
                         : plotData.equation;
 
                     return (
@@ -22,7 +22,7 @@ export interface SceneResponse {
 }
 
 async function fetchDBObjects(sceneId: number): Promise<SceneResponse> {
-  const response = await fetch(`http://localhost:8080/scenes/${sceneId}`);
+  const response = await fetch(`http://localhost:8080/api/v1/scenes/${sceneId}`);
   return response.json();
 }
 
@@ -192,7 +192,7 @@ function SceneCanvas(
       }
 
       // POST to backend
-      fetch(`http://localhost:8080/scenes/${sceneId}/objects`, {
+      fetch(`http://localhost:8080/api/v1/scenes/${sceneId}/objects`, {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
         body: JSON.stringify(dbObject),
@@ -47,7 +47,7 @@ export function SceneManagementModal({ isOpen, onClose, onSceneDeleted, onSceneS
         try {
             setIsLoading(true);
             setError(null);
-            const response = await fetch('http://localhost:8080/scenes');
+            const response = await fetch('http://localhost:8080/api/v1/scenes');
             if (!response.ok) {
                 throw new Error(`Failed to fetch scenes: ${response.statusText}`);
             }
@@ -103,7 +103,7 @@ export function SceneManagementModal({ isOpen, onClose, onSceneDeleted, onSceneS
             setIsLoading(true);
             setError(null);
 
-            const response = await fetch(`http://localhost:8080/scenes/${editingSceneId}`, {
+            const response = await fetch(`http://localhost:8080/api/v1/scenes/${editingSceneId}`, {
                 method: 'PATCH',
                 headers: {
                     'Content-Type': 'application/json',
@@ -170,7 +170,7 @@ export function SceneManagementModal({ isOpen, onClose, onSceneDeleted, onSceneS
 
                     // Delete each selected scene
                     const deletePromises = Array.from(selectedScenes).map(async (sceneId) => {
-                        const response = await fetch(`http://localhost:8080/scenes/${sceneId}`, {
+                        const response = await fetch(`http://localhost:8080/api/v1/scenes/${sceneId}`, {
                             method: 'DELETE',
                         });
                         if (!response.ok) {
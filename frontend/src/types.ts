@@ -147,10 +147,16 @@ export type ArgumentValue = (Vector2d | Shape)[];
 
 export type PlotPointElement = number | { r: number; g: number; b: number };
 
+export interface FormattedEquation {
+  text: string;
+  latex: string;
+  mathml: string;
+}
+
 export interface PlotData {
   points: PlotPointElement[][];
   equation: string;
-  formatted_equations: string[];
+  formatted_equations: FormattedEquation[];
   time_taken: number;
 }
 
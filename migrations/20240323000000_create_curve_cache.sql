@@ -0,0 +1,16 @@
+-- Create curve_cache table: curve equations of loci, keyed by a hash of the scene
+CREATE TABLE IF NOT EXISTS curve_cache (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    scene_id INTEGER NOT NULL,
+    locus_name TEXT NOT NULL,
+    scene_hash TEXT NOT NULL,
+    x_var TEXT NOT NULL,
+    y_var TEXT NOT NULL,
+    curve_equation TEXT NOT NULL,
+    factors TEXT NOT NULL,
+    warnings TEXT NOT NULL,
+    FOREIGN KEY (scene_id) REFERENCES scenes(id) ON DELETE CASCADE
+);
+
+-- Create unique index on the cache key
+CREATE UNIQUE INDEX idx_curve_cache_key ON curve_cache(scene_id, locus_name, scene_hash);
@@ -0,0 +1,5 @@
+-- Create pari_cache table: outputs of Pari/GP tasks, keyed by the task text
+CREATE TABLE IF NOT EXISTS pari_cache (
+    task TEXT PRIMARY KEY NOT NULL,
+    output TEXT NOT NULL
+);
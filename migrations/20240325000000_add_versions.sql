@@ -0,0 +1,4 @@
+-- Add versions for optimistic concurrency: a scene's version counts the changes to its
+-- objects, and an object's version is the scene version of its last change
+ALTER TABLE scenes ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE scene_objects ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
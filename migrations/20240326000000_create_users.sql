@@ -0,0 +1,16 @@
+-- Create users table: users of a shared server, who authenticate with an API token.
+-- Only the SHA-256 hash of the token is stored.
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    token_hash TEXT NOT NULL UNIQUE,
+    is_admin BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL
+);
+
+-- Add the owner of each scene; scenes created before users existed have none
+ALTER TABLE scenes ADD COLUMN user_id INTEGER REFERENCES users(id) ON DELETE CASCADE;
+
+-- Scene names only need to be unique among the scenes of a user
+DROP INDEX idx_scenes_name;
+CREATE UNIQUE INDEX idx_scenes_user_name ON scenes(user_id, name);
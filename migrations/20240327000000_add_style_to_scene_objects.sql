@@ -0,0 +1,2 @@
+-- Add the style of objects as JSON (see ObjectStyle); NULL for the default style
+ALTER TABLE scene_objects ADD COLUMN style TEXT;
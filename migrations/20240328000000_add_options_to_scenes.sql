@@ -0,0 +1,2 @@
+-- Add the options of plots of a scene as JSON (see StoredOptions); NULL for the defaults
+ALTER TABLE scenes ADD COLUMN options TEXT;
@@ -0,0 +1,6 @@
+-- Scene names are unique among the scenes without an owner too: the index on
+-- (user_id, name) tells NULL owners apart. Duplicate names get their scene id.
+UPDATE scenes SET name = name || ' (' || id || ')'
+WHERE user_id IS NULL
+  AND id NOT IN (SELECT MIN(id) FROM scenes WHERE user_id IS NULL GROUP BY name);
+CREATE UNIQUE INDEX idx_scenes_unowned_name ON scenes(name) WHERE user_id IS NULL;
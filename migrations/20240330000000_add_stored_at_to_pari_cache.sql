@@ -0,0 +1,3 @@
+-- Add the time the Pari/GP output was last stored, in milliseconds since the epoch,
+-- so that the table can be trimmed to the most recent entries
+ALTER TABLE pari_cache ADD COLUMN stored_at INTEGER NOT NULL DEFAULT 0;
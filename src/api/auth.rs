@@ -0,0 +1,112 @@
+//! Token authentication of API requests, for a server shared by several users.
+//! Clients send `Authorization: Bearer <token>` with a token from `poly_algebra add-user`.
+//! Handlers get the user as `web::ReqData<UserModel>`, and a request for a scene of
+//! another user fails like one for a missing scene. Admins can access every scene.
+//! Unless the server requires authentication (`AppState::requiring_auth`), requests
+//! without a token are let through and can access the scenes without an owner, as on
+//! a single-user server.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage};
+use sea_orm::EntityTrait;
+
+use super::error::ApiError;
+use super::AppState;
+use crate::db::{SceneEntity, UserModel};
+use crate::scene_object::SceneError;
+
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    match authenticated_user(&req).await {
+        Ok(user) => {
+            if let Some(user) = user {
+                req.extensions_mut().insert(user);
+            }
+            Ok(next.call(req).await?.map_into_left_body())
+        }
+        Err(e) => Ok(req.error_response(e).map_into_right_body()),
+    }
+}
+
+/// Checks that the request may access the scene of the `scene_id` segment, for the
+/// endpoints of a scene. It runs after routing, so the id is the one the handlers get,
+/// with percent-encoded characters decoded.
+pub async fn authorize_scene(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    match authorize(&req).await {
+        Ok(()) => Ok(next.call(req).await?.map_into_left_body()),
+        Err(e) => Ok(req.error_response(e).map_into_right_body()),
+    }
+}
+
+fn app_state(req: &ServiceRequest) -> Result<&web::Data<AppState>, ApiError> {
+    req.app_data::<web::Data<AppState>>()
+        .ok_or_else(|| ApiError::Internal("Missing application state".to_string()))
+}
+
+/// The user sending the request, None for anonymous requests
+async fn authenticated_user(req: &ServiceRequest) -> Result<Option<UserModel>, ApiError> {
+    let state = app_state(req)?;
+    match bearer_token(req)? {
+        Some(token) => Ok(Some(
+            UserModel::find_by_token(state.db(), &token)
+                .await?
+                .ok_or_else(|| ApiError::Unauthorized("Invalid API token".to_string()))?,
+        )),
+        None if state.requires_auth() => Err(ApiError::Unauthorized(
+            "An API token is required".to_string(),
+        )),
+        None => Ok(None),
+    }
+}
+
+async fn authorize(req: &ServiceRequest) -> Result<(), ApiError> {
+    let state = app_state(req)?;
+    let id = req
+        .match_info()
+        .get("scene_id")
+        .ok_or_else(|| ApiError::Internal("Missing scene id".to_string()))?;
+    let scene_id: i32 = id
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid scene id: {}", id)))?;
+    let user = req.extensions().get::<UserModel>().cloned();
+    let scene = SceneEntity::find_by_id(scene_id).one(state.db()).await?;
+    // Missing scenes are reported by the handlers
+    if scene.is_some_and(|scene| !may_access(user.as_ref(), scene.user_id)) {
+        return Err(SceneError::SceneNotFound(scene_id).into());
+    }
+    Ok(())
+}
+
+/// Whether a user may access the scenes of `owner`: their own and, for admins, all.
+/// Anonymous requests may only access the scenes without an owner.
+fn may_access(user: Option<&UserModel>, owner: Option<i32>) -> bool {
+    match user {
+        Some(user) => user.is_admin || owner == Some(user.id),
+        None => owner.is_none(),
+    }
+}
+
+/// Whether the request may act on every scene, as an admin
+pub fn is_admin(user: &Option<web::ReqData<UserModel>>) -> bool {
+    user.as_ref().is_some_and(|user| user.is_admin)
+}
+
+fn bearer_token(req: &ServiceRequest) -> Result<Option<String>, ApiError> {
+    let Some(value) = req.headers().get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| Some(token.trim().to_string()))
+        .ok_or_else(|| ApiError::Unauthorized("Expected a Bearer token".to_string()))
+}
@@ -0,0 +1,193 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::poly::PolyError;
+use crate::scene_object::SceneError;
+
+/// Body of every error response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    /// Machine-readable error kind, e.g. "object_not_found"
+    pub code: String,
+    pub message: String,
+    /// Current state of the scene, for a version conflict
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<serde_json::Value>,
+}
+
+impl ErrorResponse {
+    pub fn new(error: &ApiError) -> Self {
+        ErrorResponse {
+            code: error.code().to_string(),
+            message: error.to_string(),
+            current: match error {
+                ApiError::Conflict { current, .. } => Some(current.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Error returned by API handlers; decides the HTTP status of each failure
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    NotFound(String),
+    /// Missing or invalid API token
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error(transparent)]
+    Scene(#[from] SceneError),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+    #[error("{0}")]
+    Internal(String),
+    /// The scene is not at the version the client expected, see `AppState::load_scene_for_update`
+    #[error("{source}")]
+    Conflict {
+        source: SceneError,
+        /// The scene as it is now, for the client to redo its change on
+        current: serde_json::Value,
+    },
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::Scene(e) => match e {
+                SceneError::InvalidObjectType(_) => "invalid_object_type",
+                SceneError::InvalidProperties(_) => "invalid_properties",
+                SceneError::InvalidPointFormat(_) => "invalid_point_format",
+                SceneError::ObjectNotFound(_) => "object_not_found",
+                SceneError::SceneNotFound(_) => "scene_not_found",
+                SceneError::DependencyNotFound(_) => "dependency_not_found",
+                SceneError::DatabaseError(_) => "database_error",
+                SceneError::InvalidEquation(_) => "invalid_equation",
+                SceneError::Arithmetic(PolyError::Overflow) => "coefficient_overflow",
+                SceneError::Arithmetic(PolyError::MissingValue(_)) => "missing_value",
+                SceneError::Arithmetic(PolyError::DivisionByZero) => "division_by_zero",
+                SceneError::VersionConflict { .. } => "version_conflict",
+                SceneError::DependencyCycle(_) => "dependency_cycle",
+                SceneError::Python(_) => "python_error",
+                SceneError::Pari(_) => "pari_error",
+                SceneError::EliminationTimeout(_) => "elimination_timeout",
+                SceneError::Cancelled => "cancelled",
+                SceneError::BudgetExceeded(_) => "budget_exceeded",
+            },
+            ApiError::Database(_) => "database_error",
+            ApiError::Internal(_) => "internal",
+            ApiError::Conflict { .. } => "version_conflict",
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Scene(e) => match e {
+                SceneError::InvalidObjectType(_)
+                | SceneError::InvalidProperties(_)
+                | SceneError::InvalidPointFormat(_)
+                | SceneError::DependencyNotFound(_)
+                | SceneError::DependencyCycle(_) => StatusCode::BAD_REQUEST,
+                SceneError::ObjectNotFound(_) | SceneError::SceneNotFound(_) => {
+                    StatusCode::NOT_FOUND
+                }
+                // The scene is well-formed but the locus could not be computed
+                SceneError::DatabaseError(_)
+                | SceneError::InvalidEquation(_)
+                | SceneError::Python(_)
+                | SceneError::Pari(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                // Pari/GP and Singular run as external processes, like upstream servers
+                SceneError::EliminationTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+                // A limit of the exact arithmetic, not a server failure
+                SceneError::Arithmetic(_) | SceneError::BudgetExceeded(_) => {
+                    StatusCode::UNPROCESSABLE_ENTITY
+                }
+                SceneError::VersionConflict { .. } | SceneError::Cancelled => StatusCode::CONFLICT,
+            },
+            ApiError::Database(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Conflict { .. } => StatusCode::CONFLICT,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorResponse::new(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::{BudgetExceeded, BudgetLimit};
+
+    #[test]
+    fn test_scene_error_mapping() {
+        let error = ApiError::from(SceneError::DependencyNotFound("A".to_string()));
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(error.code(), "dependency_not_found");
+        assert_eq!(error.to_string(), "Referenced object not found: A");
+
+        let error = ApiError::from(SceneError::SceneNotFound(3));
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+
+        let error = ApiError::from(SceneError::InvalidEquation("overflow".to_string()));
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let error = ApiError::from(SceneError::from(PolyError::Overflow));
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error.code(), "coefficient_overflow");
+
+        let error = ApiError::from(SceneError::VersionConflict {
+            expected: 2,
+            current: 3,
+        });
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+        assert_eq!(error.code(), "version_conflict");
+
+        let error = ApiError::from(SceneError::from_task_error(
+            "Task timed out after 5 seconds".to_string(),
+        ));
+        assert_eq!(error.status_code(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(error.code(), "elimination_timeout");
+        assert_eq!(error.to_string(), "Task timed out after 5 seconds");
+        let error = ApiError::from(SceneError::from(BudgetExceeded {
+            limit: BudgetLimit::Terms,
+            max: 100,
+            value: 120,
+            context: "a product in a, b".to_string(),
+        }));
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error.code(), "budget_exceeded");
+        assert_eq!(
+            error.to_string(),
+            "The number of terms of a product in a, b is 120, above the limit of 100"
+        );
+
+        let error = ApiError::from(SceneError::from_task_error("Task cancelled".to_string()));
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+        assert_eq!(error.code(), "cancelled");
+        let error = ApiError::from(SceneError::from_task_error("Syntax error".to_string()));
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.code(), "pari_error");
+
+        let error = ApiError::from(SceneError::DependencyCycle("A".to_string()));
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(error.code(), "dependency_cycle");
+    }
+}
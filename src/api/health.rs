@@ -0,0 +1,113 @@
+//! Health check for deployments, reporting the external programs that solving for
+//! loci needs, so that a missing one shows up before users hit its errors
+
+use actix_web::{get, http::StatusCode, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::api::error::ApiError;
+use crate::api::AppState;
+use crate::gp_pari_service::get_pari_version;
+use crate::scene_utils::SceneUtils;
+
+/// Whether a dependency works, and its version if it has one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Check {
+    pub available: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    fn new(result: Result<Option<String>, impl Display>) -> Self {
+        match result {
+            Ok(version) => Check {
+                available: true,
+                version,
+                error: None,
+            },
+            Err(e) => Check {
+                available: false,
+                version: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    /// Whether all the dependencies are available
+    pub healthy: bool,
+    /// Version of the server
+    pub version: String,
+    pub database: Check,
+    /// Pari/GP, which the elimination and the factoring run on
+    pub gp: Check,
+    /// python3 with the equation processor, for the scenes the equation compiler
+    /// doesn't support
+    pub python: Check,
+}
+
+/// Reports the dependencies of the server, with status 503 if one is missing.
+/// Served without authentication, for load balancers and monitoring.
+#[get("/health")]
+async fn get_health(data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let database = Check::new(data.db().ping().await.map(|_| None));
+    let (gp, python) = web::block(|| {
+        (
+            Check::new(get_pari_version().map(Some)),
+            Check::new(SceneUtils::python_version().map(Some)),
+        )
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let healthy = database.available && gp.available && python.available;
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok(HttpResponse::build(status).json(HealthResponse {
+        healthy,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        database,
+        gp,
+        python,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use sea_orm::Database;
+
+    #[actix_web::test]
+    async fn test_health() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new(db).await.requiring_auth()))
+                .configure(crate::api::config),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/health").to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        let body: HealthResponse = test::read_body_json(resp).await;
+        assert!(body.database.available);
+        assert_eq!(body.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(body.gp.available, body.gp.version.is_some());
+        assert_eq!(body.gp.available, body.gp.error.is_none());
+        // Every check counts, and no API token is needed
+        assert_eq!(
+            status == StatusCode::OK,
+            body.database.available && body.gp.available && body.python.available
+        );
+        assert_eq!(body.healthy, status == StatusCode::OK);
+    }
+}
@@ -0,0 +1,205 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, Semaphore};
+
+use super::error::{ApiError, ErrorResponse};
+
+/// Number of jobs running at the same time, unless configured otherwise
+pub const DEFAULT_JOB_WORKERS: usize = 2;
+
+/// Finished jobs kept for polling; older ones are forgotten first
+const MAX_FINISHED_JOBS: usize = 256;
+
+/// State of a job, with its result or error once it is finished
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a free worker
+    Queued,
+    Running,
+    Succeeded {
+        result: serde_json::Value,
+    },
+    Failed {
+        error: ErrorResponse,
+    },
+    /// Stopped by DELETE /jobs/{job_id}
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded { .. } | JobStatus::Failed { .. } | JobStatus::Cancelled
+        )
+    }
+}
+
+/// A locus being solved in the background
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    pub scene_id: i32,
+    pub locus_name: String,
+    /// The user who submitted the job, who alone may poll it besides admins
+    #[serde(skip)]
+    pub user_id: Option<i32>,
+    #[serde(flatten)]
+    pub status: JobStatus,
+    /// Wakes `JobQueue::run` to drop the work of a cancelled job
+    #[serde(skip)]
+    cancel: Arc<Notify>,
+}
+
+/// Jobs submitted to the server, keyed by id in the order of submission. At most
+/// `workers` of them run at the same time; the others wait in the queue.
+pub struct JobQueue {
+    jobs: Mutex<IndexMap<u64, Job>>,
+    next_id: AtomicU64,
+    workers: Semaphore,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_JOB_WORKERS)
+    }
+}
+
+impl JobQueue {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            jobs: Mutex::new(IndexMap::new()),
+            next_id: AtomicU64::new(1),
+            workers: Semaphore::new(workers.max(1)),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, IndexMap<u64, Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Adds a queued job
+    pub fn submit(&self, scene_id: i32, locus_name: &str, user_id: Option<i32>) -> Job {
+        let job = Job {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            scene_id,
+            locus_name: locus_name.to_string(),
+            user_id,
+            status: JobStatus::Queued,
+            cancel: Arc::new(Notify::new()),
+        };
+        let mut jobs = self.lock();
+        let finished = jobs.values().filter(|job| job.status.is_finished()).count();
+        if finished >= MAX_FINISHED_JOBS {
+            if let Some(oldest) = jobs
+                .values()
+                .find(|job| job.status.is_finished())
+                .map(|job| job.id)
+            {
+                jobs.shift_remove(&oldest);
+            }
+        }
+        jobs.insert(job.id, job.clone());
+        job
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.lock().get(&id).cloned()
+    }
+
+    /// Updates the status of a job, unless it is already finished
+    fn set_status(&self, id: u64, status: JobStatus) {
+        if let Some(job) = self.lock().get_mut(&id) {
+            if !job.status.is_finished() {
+                job.status = status;
+            }
+        }
+    }
+
+    /// Cancels a queued or running job, leaving finished ones as they are. Its work is
+    /// dropped, which cancels the Pari/GP tasks and eliminations of the request.
+    pub fn cancel(&self, id: u64) -> Option<Job> {
+        let mut jobs = self.lock();
+        let job = jobs.get_mut(&id)?;
+        if !job.status.is_finished() {
+            job.status = JobStatus::Cancelled;
+            job.cancel.notify_one();
+        }
+        Some(job.clone())
+    }
+
+    /// Runs `work` for the job once a worker is free, and stores its result. If the
+    /// job is cancelled meanwhile, `work` is dropped where it is.
+    pub async fn run(
+        &self,
+        id: u64,
+        work: impl Future<Output = Result<serde_json::Value, ApiError>>,
+    ) {
+        let Some(cancel) = self.lock().get(&id).map(|job| job.cancel.clone()) else {
+            return;
+        };
+        let run = async {
+            let _permit = self.workers.acquire().await;
+            self.set_status(id, JobStatus::Running);
+            work.await
+        };
+        let status = tokio::select! {
+            result = run => match result {
+                Ok(result) => JobStatus::Succeeded { result },
+                Err(e) => JobStatus::Failed {
+                    error: ErrorResponse::new(&e),
+                },
+            },
+            _ = cancel.notified() => return,
+        };
+        self.set_status(id, status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_job_queue() {
+        let queue = JobQueue::new(1);
+        let job = queue.submit(1, "L1", Some(7));
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(queue.submit(1, "L2", None).id, job.id + 1);
+
+        queue
+            .run(job.id, async { Ok(serde_json::json!({"points": []})) })
+            .await;
+        assert_eq!(
+            queue.get(job.id).unwrap().status,
+            JobStatus::Succeeded {
+                result: serde_json::json!({"points": []})
+            }
+        );
+        queue
+            .run(job.id + 1, async {
+                Err(ApiError::NotFound("Locus not found: L2".to_string()))
+            })
+            .await;
+        let JobStatus::Failed { error } = queue.get(job.id + 1).unwrap().status else {
+            panic!("The job should have failed");
+        };
+        assert_eq!(error.code, "not_found");
+        assert!(queue.get(job.id + 2).is_none());
+
+        // A cancelled job stops waiting for its work, and finished jobs stay as they are
+        let job = queue.submit(1, "L3", None);
+        assert_eq!(queue.cancel(job.id).unwrap().status, JobStatus::Cancelled);
+        queue.run(job.id, std::future::pending()).await;
+        assert_eq!(queue.get(job.id).unwrap().status, JobStatus::Cancelled);
+        assert!(matches!(
+            queue.cancel(job.id - 1).unwrap().status,
+            JobStatus::Failed { .. }
+        ));
+        assert!(queue.cancel(job.id + 1).is_none());
+    }
+}
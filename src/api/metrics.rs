@@ -0,0 +1,45 @@
+//! Counters and timings of the computations for Prometheus, see `crate::metrics`
+
+use actix_web::{get, HttpResponse};
+
+/// Serves the metrics in the Prometheus text format. Like the health check, it needs
+/// no authentication, so that scrapers don't need an API token.
+#[get("/metrics")]
+async fn get_metrics() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(crate::metrics::render())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::AppState;
+    use actix_web::{test, web, App};
+    use sea_orm::Database;
+
+    #[actix_web::test]
+    async fn test_metrics() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new(db).await.requiring_auth()))
+                .configure(crate::api::config),
+        )
+        .await;
+        // No API token is needed
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let content_type = resp
+            .headers()
+            .get("content-type")
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(content_type.starts_with("text/plain"));
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("# TYPE poly_algebra_plot_requests_total counter"));
+        assert!(body.contains("poly_algebra_elimination_step_seconds_count "));
+        assert!(body.contains("poly_algebra_cache_hits_total{cache=\"curve\"} "));
+    }
+}
@@ -0,0 +1,144 @@
+use actix_web::{http::header, middleware::from_fn, web, HttpRequest};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+use crate::db::SceneEntity;
+use crate::scene::{Scene, SceneOptions};
+use crate::scene_object::SceneError;
+
+pub mod auth;
+pub mod error;
+pub mod health;
+pub mod jobs;
+pub mod metrics;
+pub mod progress;
+pub mod v1;
+
+use error::ApiError;
+use jobs::JobQueue;
+use progress::ProgressChannels;
+use sea_orm::EntityTrait;
+use v1::plots::PlotQuery;
+
+#[derive(Clone)]
+pub struct AppState {
+    db: Arc<DatabaseConnection>,
+    progress: Arc<ProgressChannels>,
+    jobs: Arc<JobQueue>,
+    /// Requests need an API token, see `auth`
+    require_auth: bool,
+}
+
+impl AppState {
+    pub async fn new(db: DatabaseConnection) -> Self {
+        Self {
+            db: Arc::new(db),
+            progress: Arc::new(ProgressChannels::default()),
+            jobs: Arc::new(JobQueue::default()),
+            require_auth: false,
+        }
+    }
+
+    /// Rejects the requests without an API token, so that users only access their scenes
+    pub fn requiring_auth(mut self) -> Self {
+        self.require_auth = true;
+        self
+    }
+
+    /// Runs at most `workers` background jobs at the same time
+    pub fn with_job_workers(mut self, workers: usize) -> Self {
+        self.jobs = Arc::new(JobQueue::new(workers));
+        self
+    }
+
+    pub fn requires_auth(&self) -> bool {
+        self.require_auth
+    }
+
+    pub fn db(&self) -> &DatabaseConnection {
+        &self.db
+    }
+
+    pub fn progress(&self) -> &ProgressChannels {
+        &self.progress
+    }
+
+    pub fn jobs(&self) -> &JobQueue {
+        &self.jobs
+    }
+
+    pub async fn load_scene(
+        &self,
+        scene_id: i32,
+        options: SceneOptions,
+    ) -> Result<Scene, ApiError> {
+        let mut scene = Scene::new(scene_id, options);
+        scene.load_objects_and_view(&self.db).await?;
+        Ok(scene)
+    }
+
+    /// Options of a plot request: the parameters of the query, over the options stored
+    /// with the scene
+    pub async fn scene_options(
+        &self,
+        scene_id: i32,
+        query: &PlotQuery,
+    ) -> Result<SceneOptions, ApiError> {
+        let scene_model = SceneEntity::find_by_id(scene_id)
+            .one(self.db())
+            .await?
+            .ok_or(SceneError::SceneNotFound(scene_id))?;
+        Ok(query.to_options(&scene_model.get_options()?)?)
+    }
+
+    /// Loads a scene to change its objects. With an If-Match header holding the version
+    /// the client last saw, a scene changed since then is a conflict that carries the
+    /// current state of the scene. Without the header, the change is made regardless.
+    pub async fn load_scene_for_update(
+        &self,
+        scene_id: i32,
+        req: &HttpRequest,
+    ) -> Result<Scene, ApiError> {
+        let expected = expected_version(req)?;
+        let scene = self.load_scene(scene_id, SceneOptions::default()).await?;
+        scene
+            .check_version(expected)
+            .map_err(|source| ApiError::Conflict {
+                source,
+                current: serde_json::to_value(v1::scenes::SceneResponse::new(&scene))
+                    .unwrap_or_default(),
+            })?;
+        Ok(scene)
+    }
+}
+
+/// The scene version in the If-Match header, e.g. `"3"`; None without the header or for `*`
+fn expected_version(req: &HttpRequest) -> Result<Option<i32>, ApiError> {
+    let Some(value) = req.headers().get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let invalid = || ApiError::BadRequest("If-Match must be a scene version".to_string());
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    let version = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+    version.parse().map(Some).map_err(|_| invalid())
+}
+
+/// Registers the health check, the metrics and all API versions. Version 1 is also served
+/// without a prefix for clients written before the API was versioned.
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(health::get_health)
+        .service(metrics::get_metrics)
+        .service(
+            web::scope("/api/v1")
+                .wrap(from_fn(auth::authenticate))
+                .configure(v1::config),
+        )
+        .service(
+            web::scope("")
+                .wrap(from_fn(auth::authenticate))
+                .configure(v1::config),
+        );
+}
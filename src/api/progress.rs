@@ -0,0 +1,104 @@
+use actix_web::web::Bytes;
+use futures_util::Stream;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use tokio::sync::broadcast;
+
+use crate::progress::Progress;
+
+/// Events kept for subscribers that fall behind
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Progress broadcast channels of the loci being solved, keyed by scene id and locus name
+#[derive(Default)]
+pub struct ProgressChannels {
+    channels: Mutex<HashMap<(i32, String), broadcast::Sender<Progress>>>,
+}
+
+impl ProgressChannels {
+    /// Returns the channel of the locus, creating it if needed. Subscribers may
+    /// connect before the computation starts.
+    pub fn sender(&self, scene_id: i32, locus_name: &str) -> broadcast::Sender<Progress> {
+        self.lock()
+            .entry((scene_id, locus_name.to_string()))
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .clone()
+    }
+
+    /// Subscribes to the channel of the locus. Channels that are neither sent to nor
+    /// listened to any more, e.g. of loci that were never plotted after their
+    /// subscribers left, are dropped first.
+    pub fn subscribe(&self, scene_id: i32, locus_name: &str) -> broadcast::Receiver<Progress> {
+        let mut channels = self.lock();
+        channels.retain(|_, sender| sender.receiver_count() > 0 || sender.strong_count() > 1);
+        channels
+            .entry((scene_id, locus_name.to_string()))
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Sends `Progress::Finished` and drops the channel; subscribers' streams end
+    /// once all senders are gone
+    pub fn finish(&self, scene_id: i32, locus_name: &str, error: Option<String>) {
+        let sender = self.lock().remove(&(scene_id, locus_name.to_string()));
+        if let Some(sender) = sender {
+            let _ = sender.send(Progress::Finished { error });
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<(i32, String), broadcast::Sender<Progress>>> {
+        self.channels.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Turns the events of a channel into a stream of server-sent events,
+/// ending after `Progress::Finished`
+pub fn sse_stream(
+    receiver: broadcast::Receiver<Progress>,
+) -> impl Stream<Item = Result<Bytes, actix_web::Error>> {
+    futures_util::stream::unfold(Some(receiver), |receiver| async move {
+        let mut receiver = receiver?;
+        loop {
+            match receiver.recv().await {
+                Ok(progress) => {
+                    let finished = matches!(progress, Progress::Finished { .. });
+                    let data = serde_json::to_string(&progress).unwrap_or_default();
+                    let event = Bytes::from(format!("data: {}\n\n", data));
+                    return Some((Ok(event), (!finished).then_some(receiver)));
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abandoned_channels_dropped() {
+        let channels = ProgressChannels::default();
+        let sender = channels.sender(1, "L2");
+        let receiver = channels.subscribe(1, "L3");
+        drop(channels.subscribe(1, "L1"));
+        assert_eq!(channels.lock().len(), 3);
+
+        // L1 has neither a subscriber nor a computation left
+        let _other = channels.subscribe(2, "L1");
+        let mut keys: Vec<_> = channels.lock().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                (1, "L2".to_string()),
+                (1, "L3".to_string()),
+                (2, "L1".to_string())
+            ]
+        );
+        drop((sender, receiver));
+        let _other = channels.subscribe(2, "L1");
+        assert_eq!(channels.lock().len(), 1);
+    }
+}
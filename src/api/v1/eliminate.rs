@@ -0,0 +1,138 @@
+use actix_web::{post, web};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use super::plots::PlotQuery;
+use crate::api::error::ApiError;
+use crate::gp_pari_service::{with_cancellation, with_task_limits, CancellationToken};
+use crate::poly::{Poly, PolyFormat};
+use crate::scene::{FormattedEquation, Plot, SceneOptions, StoredOptions};
+use crate::scene_utils::SceneUtils;
+use crate::warning::Warning;
+
+/// A polynomial system to be solved for the curve in the x and y variables
+#[derive(Debug, Deserialize)]
+pub struct EliminateRequest {
+    pub equations: Vec<String>,
+    pub x: String,
+    pub y: String,
+    /// Same options as the query of the plot endpoints; width and height are ignored
+    pub options: Option<PlotQuery>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EliminateResponse {
+    pub equation: String,
+    pub formatted_equation: FormattedEquation,
+    pub factors: Vec<FormattedEquation>,
+    pub warnings: Vec<Warning>,
+    pub time_taken: f64,
+}
+
+/// Eliminates all variables except x and y from the equations, without a scene
+#[post("/eliminate")]
+async fn eliminate(
+    request: web::Json<EliminateRequest>,
+) -> Result<web::Json<EliminateResponse>, ApiError> {
+    let request = request.into_inner();
+    let options = match &request.options {
+        Some(query) => query.to_options(&StoredOptions::default())?,
+        None => SceneOptions::new(false),
+    };
+    if request.equations.is_empty() {
+        return Err(ApiError::BadRequest("No equations given".to_string()));
+    }
+    for equation in &request.equations {
+        Poly::parse_equation(equation)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid equation {}: {}", equation, e)))?;
+    }
+    let plot = Plot {
+        name: String::new(),
+        x: request.x.trim().to_string(),
+        y: request.y.trim().to_string(),
+    };
+    let (x_var, y_var) = SceneUtils::parse_plot_vars(&plot)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid variable: {}", e)))?;
+    if x_var == y_var {
+        return Err(ApiError::BadRequest(
+            "x and y variables must be different".to_string(),
+        ));
+    }
+
+    let start_time = Instant::now();
+    let pari_limits = options.pari_limits;
+    // Pending Pari/GP tasks are cancelled if the client disconnects
+    let token = CancellationToken::new();
+    let cancel_guard = token.cancel_on_drop();
+    let result = web::block(move || {
+        with_cancellation(token, || {
+            with_task_limits(pari_limits, || {
+                let equations = request.equations.iter().map(String::as_str).collect();
+                let curve = SceneUtils::get_curve_equation_and_factors(equations, &plot, options)?;
+                Ok::<_, ApiError>(EliminateResponse {
+                    equation: PolyFormat::new().format(&curve.curve_equation),
+                    formatted_equation: FormattedEquation::new(&curve.curve_equation, x_var, y_var),
+                    factors: curve
+                        .factors
+                        .iter()
+                        .map(|factor| FormattedEquation::new(factor, x_var, y_var))
+                        .collect(),
+                    warnings: curve.warnings,
+                    time_taken: 0.0,
+                })
+            })
+        })
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()));
+    cancel_guard.disarm();
+
+    let mut response = result??;
+    response.time_taken = start_time.elapsed().as_secs_f64();
+    info!(
+        "Eliminated down to {} with {} factors in {:.3}s",
+        response.equation,
+        response.factors.len(),
+        response.time_taken
+    );
+    Ok(web::Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::error::ErrorResponse;
+    use actix_web::{http::StatusCode, test, App};
+    use serde_json::json;
+
+    #[actix_web::test]
+    async fn test_eliminate_rejects_invalid_requests() {
+        let app = test::init_service(App::new().service(eliminate)).await;
+        for (body, code) in [
+            (
+                json!({"equations": ["a^2 + b^2 - c", "c*(b + 1"], "x": "a", "y": "b"}),
+                "bad_request",
+            ),
+            (json!({"equations": [], "x": "a", "y": "b"}), "bad_request"),
+            (
+                json!({"equations": ["a + b"], "x": "a", "y": "a"}),
+                "bad_request",
+            ),
+            (
+                json!({"equations": ["a + b"], "x": "a", "y": "b",
+                       "options": {"smoothing": "blur"}}),
+                "invalid_properties",
+            ),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/eliminate")
+                .set_json(&body)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", body);
+            let error: ErrorResponse = test::read_body_json(resp).await;
+            assert_eq!(error.code, code);
+        }
+    }
+}
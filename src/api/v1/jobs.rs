@@ -0,0 +1,223 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use serde::Deserialize;
+
+use super::plots::{self, PlotPath, PlotQuery};
+use crate::api::auth::is_admin;
+use crate::api::error::ApiError;
+use crate::api::jobs::Job;
+use crate::api::AppState;
+use crate::db::UserModel;
+use crate::scene::SceneOptions;
+use crate::scene_object::SceneObject;
+
+#[derive(Debug, Deserialize)]
+pub struct JobPath {
+    pub job_id: u64,
+}
+
+/// Queues solving for a locus and drawing it, with the query of the plot endpoint,
+/// and answers 202 with the job to poll at /jobs/{job_id}. The job holds no request
+/// open, and its elimination runs on the blocking threads like that of a plot.
+#[post("/loci/{locus_name}/solve")]
+async fn solve_locus(
+    data: web::Data<AppState>,
+    user: Option<web::ReqData<UserModel>>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+) -> Result<HttpResponse, ApiError> {
+    // Mistakes in the request are reported right away rather than by the job
+    let path = path.into_inner();
+    data.scene_options(path.scene_id, &query).await?;
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+    if !matches!(
+        scene.objects.get(&path.locus_name),
+        Some(SceneObject::Locus(_))
+    ) {
+        return Err(ApiError::NotFound(format!(
+            "Locus not found: {}",
+            path.locus_name
+        )));
+    }
+
+    let job = data
+        .jobs()
+        .submit(path.scene_id, &path.locus_name, user.map(|user| user.id));
+    let id = job.id;
+    let query = query.into_inner();
+    actix_web::rt::spawn(async move {
+        let work = async {
+            let response = plots::plot(&data, path, &query).await?;
+            serde_json::to_value(response).map_err(|e| ApiError::Internal(e.to_string()))
+        };
+        data.jobs().run(id, work).await;
+    });
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+/// The status of a job, with the plot once it succeeded. Jobs of other users are
+/// reported as missing, like their scenes.
+#[get("/jobs/{job_id}")]
+async fn get_job(
+    data: web::Data<AppState>,
+    user: Option<web::ReqData<UserModel>>,
+    path: web::Path<JobPath>,
+) -> Result<web::Json<Job>, ApiError> {
+    get_job_of_user(&data, &user, path.job_id).map(web::Json)
+}
+
+/// The job, if the user submitted it or may access everything
+fn get_job_of_user(
+    data: &AppState,
+    user: &Option<web::ReqData<UserModel>>,
+    job_id: u64,
+) -> Result<Job, ApiError> {
+    data.jobs()
+        .get(job_id)
+        .filter(|job| is_admin(user) || job.user_id == user.as_ref().map(|user| user.id))
+        .ok_or_else(|| ApiError::NotFound(format!("Job not found: {}", job_id)))
+}
+
+/// Cancels a queued or running job, stopping its computation at the next checkpoint,
+/// and returns the job. Finished jobs are left as they are.
+#[delete("/jobs/{job_id}")]
+async fn cancel_job(
+    data: web::Data<AppState>,
+    user: Option<web::ReqData<UserModel>>,
+    path: web::Path<JobPath>,
+) -> Result<web::Json<Job>, ApiError> {
+    // The job is looked up first, so that users can't cancel the jobs of others
+    get_job_of_user(&data, &user, path.job_id)?;
+    data.jobs()
+        .cancel(path.job_id)
+        .map(web::Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Job not found: {}", path.job_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::jobs::JobStatus;
+    use crate::db::{
+        CurveCacheEntity, CurveCacheModel, SceneActiveModel, SceneEntity, SceneObjectEntity,
+    };
+    use crate::poly::Poly;
+    use crate::scene::{CurveEquationAndFactors, LocusCurve};
+    use crate::scene_object::ObjectType;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use sea_orm::{ActiveModelBehavior, ActiveModelTrait, ConnectionTrait, Schema};
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[actix_web::test]
+    async fn test_solve_job() {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let schema = Schema::new(db.get_database_backend());
+        let backend = db.get_database_backend();
+        for stmt in [
+            schema.create_table_from_entity(SceneEntity),
+            schema.create_table_from_entity(SceneObjectEntity),
+            schema.create_table_from_entity(CurveCacheEntity),
+        ] {
+            db.execute(backend.build(&stmt)).await.unwrap();
+        }
+        let scene_id = <SceneActiveModel as ActiveModelBehavior>::new()
+            .insert(&db)
+            .await
+            .unwrap()
+            .id;
+
+        let state = AppState::new(db).await;
+        let mut scene = state
+            .load_scene(scene_id, SceneOptions::default())
+            .await
+            .unwrap();
+        for (name, object_type, properties) in [
+            ("A", ObjectType::FreePoint, json!({"value": "1, 1"})),
+            ("L1", ObjectType::Locus, json!({"point": "A"})),
+        ] {
+            scene
+                .add_object(state.db(), name.to_string(), object_type, properties)
+                .await
+                .unwrap();
+        }
+        // The curve is cached, so that the job needs no Pari/GP
+        let locus_curve = LocusCurve {
+            x_var: 0,
+            y_var: 1,
+            curve: CurveEquationAndFactors {
+                curve_equation: Poly::new("a - b").unwrap(),
+                factors: vec![Poly::new("a - b").unwrap()],
+                warnings: vec![],
+                triangular_sets: None,
+            },
+        };
+        let entry =
+            CurveCacheModel::new_entry(scene_id, "L1", &scene.curve_hash(), &locus_curve).unwrap();
+        CurveCacheModel::save_curve(state.db(), entry)
+            .await
+            .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .service(web::scope("/scenes/{scene_id}").service(solve_locus))
+                .service(get_job)
+                .service(cancel_job),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/scenes/{}/loci/A/solve", scene_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/scenes/{}/loci/L1/solve?width=50&height=50",
+                scene_id
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        let job: Job = test::read_body_json(resp).await;
+        assert_eq!((job.scene_id, job.locus_name.as_str()), (scene_id, "L1"));
+
+        let mut status = job.status;
+        for _ in 0..500 {
+            if status.is_finished() {
+                break;
+            }
+            actix_web::rt::time::sleep(Duration::from_millis(10)).await;
+            let req = test::TestRequest::get()
+                .uri(&format!("/jobs/{}", job.id))
+                .to_request();
+            let job: Job = test::call_and_read_body_json(&app, req).await;
+            status = job.status;
+        }
+        let JobStatus::Succeeded { result } = status else {
+            panic!("The job should have succeeded: {:?}", status);
+        };
+        assert!(!result["points"].as_array().unwrap().is_empty());
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/jobs/{}", job.id + 1))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // Cancelling a finished job leaves its result
+        let req = test::TestRequest::delete()
+            .uri(&format!("/jobs/{}", job.id))
+            .to_request();
+        let cancelled: Job = test::call_and_read_body_json(&app, req).await;
+        assert!(matches!(cancelled.status, JobStatus::Succeeded { .. }));
+        let req = test::TestRequest::delete()
+            .uri(&format!("/jobs/{}", job.id + 1))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
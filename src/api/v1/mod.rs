@@ -0,0 +1,90 @@
+use actix_web::{http::header, middleware::from_fn, web, CustomizeResponder, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::api::auth;
+use crate::api::error::ApiError;
+
+pub mod eliminate;
+pub mod jobs;
+pub mod objects;
+pub mod plots;
+pub mod scenes;
+
+/// Path of endpoints addressing a whole scene
+#[derive(Debug, Deserialize)]
+pub struct ScenePath {
+    pub scene_id: i32,
+}
+
+/// Path of endpoints addressing one object of a scene
+#[derive(Debug, Deserialize)]
+pub struct ObjectPath {
+    pub scene_id: i32,
+    pub object_name: String,
+}
+
+/// A JSON response with the scene version as its ETag, for the If-Match header of the
+/// next change (see `AppState::load_scene_for_update`)
+pub fn with_version<T: Serialize>(body: T, version: i32) -> CustomizeResponder<web::Json<T>> {
+    web::Json(body)
+        .customize()
+        .insert_header((header::ETAG, format!("\"{}\"", version)))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    // Malformed paths, queries and bodies are reported like any other ApiError
+    cfg.app_data(
+        web::PathConfig::default().error_handler(|e, _| ApiError::BadRequest(e.to_string()).into()),
+    )
+    .app_data(
+        web::QueryConfig::default()
+            .error_handler(|e, _| ApiError::BadRequest(e.to_string()).into()),
+    )
+    .app_data(
+        web::JsonConfig::default().error_handler(|e, _| ApiError::BadRequest(e.to_string()).into()),
+    )
+    .service(objects::get_actions)
+    .service(
+        // Endpoints of one scene, which only its owner and admins may access
+        web::scope("/scenes/{scene_id}")
+            .wrap(from_fn(auth::authorize_scene))
+            .service(scenes::get_scene)
+            .service(objects::get_objects)
+            .service(objects::add_object)
+            .service(objects::add_objects)
+            .service(objects::update_object)
+            .service(objects::rename_object)
+            .service(objects::set_style)
+            .service(objects::delete_object)
+            .service(scenes::delete_scene)
+            .service(objects::get_dependents)
+            .service(plots::get_plot)
+            .service(plots::get_plot_progress)
+            .service(plots::get_plot_svg)
+            .service(plots::get_plot_png)
+            .service(plots::get_curve_analysis)
+            .service(plots::check_point)
+            .service(plots::get_tangent)
+            .service(plots::get_lattice_points)
+            .service(plots::get_configuration)
+            .service(plots::get_trace)
+            .service(plots::replay_plot)
+            .service(plots::update_view)
+            .service(jobs::solve_locus)
+            .service(scenes::clone_scene)
+            .service(scenes::rename_scene)
+            .service(scenes::get_scene_options)
+            .service(scenes::update_scene_options)
+            .service(scenes::get_initial_values)
+            .service(scenes::get_degrees_of_freedom)
+            .service(scenes::animate_scene)
+            .service(scenes::validate_expressions)
+            .service(scenes::validate_expression),
+    )
+    .service(jobs::get_job)
+    .service(jobs::cancel_job)
+    .service(scenes::create_scene)
+    .service(scenes::get_scenes)
+    .service(scenes::get_all_scenes)
+    .service(eliminate::eliminate);
+}
@@ -1,20 +1,12 @@
-use actix_web::{delete, get, patch, post, web, HttpResponse, Responder};
-use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
-use chrono::{DateTime, Utc};
-use log::info;
+use actix_web::{get, patch, post, put, routes, web, CustomizeResponder, HttpRequest};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::Arc;
-use std::time::Instant;
 
-use crate::db::{SceneActiveModel, SceneColumn, SceneEntity, SCENE_DEFAULT_NAME};
-use crate::poly_draw::Color;
-use crate::scene::{Scene, SceneOptions, View};
-use crate::scene_object::{ObjectType, SceneObject};
-use sea_orm::{
-    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryOrder, Set,
-};
+use super::{with_version, ObjectPath, ScenePath};
+use crate::api::error::ApiError;
+use crate::api::AppState;
+use crate::scene::{Scene, SceneOptions};
+use crate::scene_object::{ObjectStyle, ObjectType};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Argument {
@@ -33,62 +25,65 @@ pub struct Action {
     pub group: String,
 }
 
+/// An object as stored in a scene. `properties` depends on `object_type` and
+/// is validated by `SceneObject::from_properties`.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SceneObjectResponse {
     pub name: String,
     pub object_type: String,
     pub properties: serde_json::Value,
+    /// Version of the scene at the last change of the object; ignored in requests
+    #[serde(default)]
+    pub version: i32,
+    #[serde(default)]
+    pub style: ObjectStyle,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SceneResponse {
-    pub objects: Vec<SceneObjectResponse>,
-    pub view: View,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PlotPoint {
-    pub x: u32,
-    pub y: u32,
-    pub color: Color,
+impl SceneObjectResponse {
+    /// The object `name` of the scene
+    pub fn new(scene: &Scene, name: &str) -> Self {
+        let object = &scene.objects[name];
+        Self {
+            name: name.to_string(),
+            object_type: object.get_type().to_string(),
+            properties: object.get_properties(),
+            version: scene.object_version(name),
+            style: scene.style(name),
+        }
+    }
 }
 
+/// An object with the objects it references and the ones referencing it, if requested
 #[derive(Debug, Serialize, Deserialize)]
-pub struct PlotResponse {
-    pub points: Vec<(u32, u32, Color)>,
-    pub equation: String,
-    pub formatted_equations: Vec<String>,
-    pub time_taken: f64,
+pub struct ObjectWithDependencies {
+    #[serde(flatten)]
+    pub object: SceneObjectResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependents: Option<Vec<String>>,
 }
 
-#[derive(Debug)]
-pub enum SceneOrError {
-    Scene(Scene),
-    Error(HttpResponse),
+#[derive(Debug, Deserialize)]
+pub struct ObjectsQuery {
+    /// `dependencies` to list the direct dependencies and dependents of each object
+    pub include: Option<String>,
 }
 
-#[derive(Clone)]
-pub struct AppState {
-    db: Arc<DatabaseConnection>,
+/// New definition of an existing object, see `Scene::update_object`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ObjectUpdate {
+    pub object_type: String,
+    pub properties: serde_json::Value,
 }
 
-impl AppState {
-    pub async fn new(db: DatabaseConnection) -> Self {
-        Self { db: Arc::new(db) }
-    }
-
-    pub async fn load_scene(&self, scene_id: &str, options: SceneOptions) -> SceneOrError {
-        let scene_id = scene_id.parse::<i32>().unwrap();
-        let mut scene = Scene::new(scene_id, options);
-        match scene.load_objects_and_view(&self.db).await {
-            Ok(()) => SceneOrError::Scene(scene),
-            Err(e) => SceneOrError::Error(HttpResponse::InternalServerError().json(e.to_string())),
-        }
-    }
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ObjectRename {
+    pub name: String,
 }
 
 #[get("/actions")]
-async fn get_actions() -> impl Responder {
+async fn get_actions() -> web::Json<Vec<Action>> {
     let letters_a_to_d: Vec<String> = ('A'..='D').map(|c| c.to_string()).collect();
     let mut letters_m_to_n_then_e_to_k: Vec<String> = ('M'..='N').map(|c| c.to_string()).collect();
     letters_m_to_n_then_e_to_k.extend(('E'..='K').map(|c| c.to_string()));
@@ -253,7 +248,7 @@ async fn get_actions() -> impl Responder {
             description: "Scaled vector point: a point X defined by the vector relation AX = k AB for chosen A and B".to_string(),
             allowed_names: letters_m_to_n_then_e_to_k.clone(),
             group: "Points".to_string(),
-        },        
+        },
         Action {
             name: "ComputedPoint".to_string(),
             object_types: vec![ObjectType::ComputedPoint.to_string()],
@@ -272,7 +267,7 @@ async fn get_actions() -> impl Responder {
             description: "Computed point: a point defined by custom X and Y expressions".to_string(),
             allowed_names: letters_x_to_z_then_t_to_w.clone(),
             group: "Points".to_string(),
-        },        
+        },
         Action {
             name: "LineAB".to_string(),
             object_types: vec![ObjectType::LineAB.to_string()],
@@ -456,424 +451,201 @@ async fn get_actions() -> impl Responder {
         },
     ];
 
-    HttpResponse::Ok().json(actions)
+    web::Json(actions)
 }
 
-#[get("/scenes/{scene_id}")]
-async fn get_scene(data: web::Data<AppState>, scene_id: web::Path<String>) -> impl Responder {
-    match data
-        .load_scene(&scene_id.into_inner(), SceneOptions::default())
-        .await
-    {
-        SceneOrError::Scene(scene) => {
-            let objects: Vec<SceneObjectResponse> = scene
-                .objects
-                .iter()
-                .map(|obj| SceneObjectResponse {
-                    name: obj.0.clone(),
-                    object_type: obj.1.get_type().to_string(),
-                    properties: obj.1.get_properties(),
-                })
-                .collect();
+// The changes below take the scene version in If-Match and return the new one as ETag,
+// see `AppState::load_scene_for_update`
 
-            match scene.get_view(&data.db).await {
-                Ok(view) => {
-                    let response = SceneResponse { objects, view };
-                    HttpResponse::Ok().json(response)
-                }
-                Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
-            }
+/// Lists the objects of a scene in order, optionally with their dependency graph
+/// (see `Scene::dependency_graph`), e.g. to warn about what a deletion would remove
+#[get("/objects")]
+async fn get_objects(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    query: web::Query<ObjectsQuery>,
+) -> Result<CustomizeResponder<web::Json<Vec<ObjectWithDependencies>>>, ApiError> {
+    let include_dependencies = match query.include.as_deref() {
+        None => false,
+        Some("dependencies") => true,
+        Some(include) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown include: {}",
+                include
+            )))
         }
-        SceneOrError::Error(response) => response,
-    }
+    };
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+    let graph = include_dependencies.then(|| scene.dependency_graph());
+    let objects = scene
+        .objects
+        .keys()
+        .map(|name| ObjectWithDependencies {
+            object: SceneObjectResponse::new(&scene, name),
+            dependencies: graph.as_ref().map(|g| g.dependencies(name).to_vec()),
+            dependents: graph.as_ref().map(|g| g.dependents(name).to_vec()),
+        })
+        .collect();
+    Ok(with_version(objects, scene.version))
 }
 
-#[post("/scenes/{scene_id}/objects")]
+#[post("/objects")]
 async fn add_object(
     data: web::Data<AppState>,
-    path: web::Path<String>,
+    path: web::Path<ScenePath>,
+    req: HttpRequest,
     object: web::Json<SceneObjectResponse>,
-) -> impl Responder {
-    match data
-        .load_scene(&path.into_inner(), SceneOptions::default())
-        .await
-    {
-        SceneOrError::Scene(mut scene) => {
-            match scene
-                .add_object(
-                    &data.db,
-                    object.name.clone(),
-                    ObjectType::from_str(&object.object_type).unwrap(),
-                    object.properties.clone(),
-                )
-                .await
-            {
-                Ok(()) => HttpResponse::Ok().json(object.0),
-                Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
-            }
-        }
-        SceneOrError::Error(response) => response,
-    }
+) -> Result<CustomizeResponder<web::Json<SceneObjectResponse>>, ApiError> {
+    let object_type = ObjectType::from_str(&object.object_type)?;
+    let mut scene = data.load_scene_for_update(path.scene_id, &req).await?;
+    let SceneObjectResponse {
+        name,
+        properties,
+        style,
+        ..
+    } = object.into_inner();
+    scene
+        .add_objects(
+            data.db(),
+            vec![(name.clone(), object_type, properties, style)],
+        )
+        .await?;
+    Ok(with_version(
+        SceneObjectResponse::new(&scene, &name),
+        scene.version,
+    ))
 }
 
-#[delete("/scenes/{scene_id}/{object_name}")]
-async fn delete_object(
+/// Adds a list of objects in order and in one transaction, see `Scene::add_objects`
+#[post("/objects:batch")]
+async fn add_objects(
     data: web::Data<AppState>,
-    path: web::Path<(String, String)>,
-) -> impl Responder {
-    let (scene_id, object_name) = path.into_inner();
-    match data.load_scene(&scene_id, SceneOptions::default()).await {
-        SceneOrError::Scene(mut scene) => match scene.delete_object(&data.db, &object_name).await {
-            Ok(dependencies) => HttpResponse::Ok().json(dependencies),
-            Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
-        },
-        SceneOrError::Error(response) => response,
-    }
-}
-
-#[delete("/scenes/{scene_id}")]
-async fn delete_scene(data: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
-    let scene_id = path.into_inner();
-    match data.load_scene(&scene_id, SceneOptions::default()).await {
-        SceneOrError::Scene(mut scene) => match scene.delete_scene(&data.db).await {
-            Ok(()) => HttpResponse::Ok().finish(),
-            Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
-        },
-        SceneOrError::Error(response) => response,
-    }
-}
-
-#[get("/scenes/{scene_id}/{object_name}/dependents")]
-async fn get_dependents(
-    data: web::Data<AppState>,
-    path: web::Path<(String, String)>,
-) -> impl Responder {
-    let (scene_id, object_name) = path.into_inner();
-    match data.load_scene(&scene_id, SceneOptions::default()).await {
-        SceneOrError::Scene(scene) => {
-            let dependents = scene.collect_dependent_objects(&object_name);
-            HttpResponse::Ok().json(dependents)
-        }
-        SceneOrError::Error(response) => response,
-    }
-}
-
-#[get("/scenes/{scene_id}/plot/{locus_name}")]
-async fn get_plot(
-    data: web::Data<AppState>,
-    path: web::Path<(String, String)>,
-    query: web::Query<HashMap<String, String>>,
-) -> impl Responder {
-    let (scene_id, locus_name) = path.into_inner();
-
-    // Parse width and height from query parameters
-    let width = query
-        .get("width")
-        .and_then(|w| w.parse::<u32>().ok())
-        .unwrap_or(2000);
-    let height = query
-        .get("height")
-        .and_then(|h| h.parse::<u32>().ok())
-        .unwrap_or(2000);
-    let reduce_factors = query
-        .get("reduce_factors")
-        .and_then(|value| value.parse::<bool>().ok())
-        .unwrap_or(false);
-
-    match data
-        .load_scene(&scene_id, SceneOptions::new(reduce_factors))
-        .await
-    {
-        SceneOrError::Scene(scene) => {
-            if let Some(SceneObject::Locus(_locus)) = scene.objects.get(&locus_name) {
-                let start_time = Instant::now();
-                match scene.solve_and_plot(&locus_name, width, height) {
-                    Ok(plot_data) => {
-                        let elapsed = start_time.elapsed();
-                        let response = PlotResponse {
-                            points: plot_data.points,
-                            equation: plot_data.equation,
-                            formatted_equations: plot_data.formatted_equations,
-                            time_taken: elapsed.as_secs_f64(),
-                        };
-                        HttpResponse::Ok().json(response)
-                    }
-                    Err(e) => {
-                        let elapsed = start_time.elapsed();
-                        info!(
-                            "Failed to solve for locus {}: {} (took {:.3}s)",
-                            locus_name,
-                            e.to_string(),
-                            elapsed.as_secs_f64()
-                        );
-                        HttpResponse::InternalServerError().json(format!(
-                            "{} (took {:.3}s)",
-                            e.to_string(),
-                            elapsed.as_secs_f64()
-                        ))
-                    }
-                }
-            } else {
-                HttpResponse::NotFound().finish()
-            }
-        }
-        SceneOrError::Error(response) => response,
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateSceneRequest {
-    pub name: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct CreateSceneResponse {
-    pub id: i32,
-    pub name: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct RenameSceneRequest {
-    pub name: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct RenameSceneResponse {
-    pub id: i32,
-    pub name: String,
+    path: web::Path<ScenePath>,
+    req: HttpRequest,
+    objects: web::Json<Vec<SceneObjectResponse>>,
+) -> Result<CustomizeResponder<web::Json<Vec<SceneObjectResponse>>>, ApiError> {
+    let batch = objects
+        .iter()
+        .map(|object| {
+            Ok((
+                object.name.clone(),
+                ObjectType::from_str(&object.object_type)?,
+                object.properties.clone(),
+                object.style.clone(),
+            ))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    let mut scene = data.load_scene_for_update(path.scene_id, &req).await?;
+    scene.add_objects(data.db(), batch).await?;
+    let added = objects
+        .iter()
+        .map(|object| SceneObjectResponse::new(&scene, &object.name))
+        .collect();
+    Ok(with_version(added, scene.version))
 }
 
-#[post("/scenes")]
-async fn create_scene(
+#[put("/objects/{object_name}")]
+async fn update_object(
     data: web::Data<AppState>,
-    request: web::Json<CreateSceneRequest>,
-) -> impl Responder {
-    let db = &*data.db;
-
-    // Create new scene in database
-    let scene_name = request
-        .name
-        .as_deref()
-        .unwrap_or(SCENE_DEFAULT_NAME)
-        .to_string();
-    let scene = SceneActiveModel {
-        name: Set(scene_name.clone()),
-        ..Default::default()
-    };
-
-    match scene.insert(db).await {
-        Ok(scene) => {
-            // Update the name to "Scene <id>" if the provided name was empty or default
-            if request.name.is_none()
-                || request.name.as_ref().unwrap().is_empty()
-                || scene_name == SCENE_DEFAULT_NAME
-            {
-                let final_name = format!("Scene {}", scene.id);
-                let id = scene.id;
-                let mut update_scene = scene.into_active_model();
-                update_scene.name = Set(final_name.clone());
-
-                match update_scene.update(db).await {
-                    Ok(_) => HttpResponse::Ok().json(CreateSceneResponse {
-                        id,
-                        name: final_name,
-                    }),
-                    Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
-                }
-            } else {
-                HttpResponse::Ok().json(CreateSceneResponse {
-                    id: scene.id,
-                    name: scene.name,
-                })
-            }
-        }
-        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
-    }
+    path: web::Path<ObjectPath>,
+    req: HttpRequest,
+    update: web::Json<ObjectUpdate>,
+) -> Result<CustomizeResponder<web::Json<SceneObjectResponse>>, ApiError> {
+    let object_type = ObjectType::from_str(&update.object_type)?;
+    let mut scene = data.load_scene_for_update(path.scene_id, &req).await?;
+    scene
+        .update_object(
+            data.db(),
+            &path.object_name,
+            object_type,
+            update.into_inner().properties,
+        )
+        .await?;
+    Ok(with_version(
+        SceneObjectResponse::new(&scene, &path.object_name),
+        scene.version,
+    ))
 }
 
-#[patch("/scenes/{scene_id}")]
-async fn rename_scene(
+/// Renames an object and returns the objects whose references to it were rewritten
+#[patch("/objects/{object_name}")]
+async fn rename_object(
     data: web::Data<AppState>,
-    path: web::Path<String>,
-    request: web::Json<RenameSceneRequest>,
-) -> impl Responder {
-    let scene_id = path.into_inner();
-    let new_name = request.name.clone();
-
-    let db = &*data.db;
-
-    // Update the scene name in the database
-    let scene_id_int = scene_id.parse::<i32>().unwrap();
-    let scene_model = SceneEntity::find_by_id(scene_id_int)
-        .one(db)
-        .await
-        .unwrap_or(None);
-
-    match scene_model {
-        Some(scene_model) => {
-            let mut active_model = scene_model.into_active_model();
-            active_model.name = Set(new_name.clone());
-
-            match active_model.update(db).await {
-                Ok(updated_scene) => HttpResponse::Ok().json(RenameSceneResponse {
-                    id: updated_scene.id,
-                    name: updated_scene.name,
-                }),
-                Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
-            }
-        }
-        None => HttpResponse::NotFound().json("Scene not found"),
-    }
-}
-
-#[derive(Debug, Serialize, serde::Deserialize)]
-pub struct SceneInfo {
-    pub id: i32,
-    pub name: String,
-    created_at: DateTime<Utc>,
-}
-
-#[get("/scenes")]
-async fn get_scenes(data: web::Data<AppState>) -> impl Responder {
-    let scenes = SceneEntity::find()
-        .order_by_asc(SceneColumn::CreatedAt)
-        .all(&*data.db)
-        .await
-        .unwrap_or_default();
-    let scene_infos: Vec<SceneInfo> = scenes
-        .into_iter()
-        .map(|s| SceneInfo {
-            id: s.id,
-            name: s.name,
-            created_at: s.created_at,
-        })
+    path: web::Path<ObjectPath>,
+    req: HttpRequest,
+    rename: web::Json<ObjectRename>,
+) -> Result<CustomizeResponder<web::Json<Vec<SceneObjectResponse>>>, ApiError> {
+    let mut scene = data.load_scene_for_update(path.scene_id, &req).await?;
+    let rewritten = scene
+        .rename_object(data.db(), &path.object_name, &rename.name)
+        .await?;
+    let rewritten = rewritten
+        .iter()
+        .map(|name| SceneObjectResponse::new(&scene, name))
         .collect();
-    HttpResponse::Ok().json(scene_infos)
+    Ok(with_version(rewritten, scene.version))
 }
 
-#[derive(Debug, Serialize)]
-pub struct InitialValuesResponse {
-    pub values: Vec<f64>,
+/// Replaces the style of an object, see `Scene::set_style`
+#[put("/objects/{object_name}/style")]
+async fn set_style(
+    data: web::Data<AppState>,
+    path: web::Path<ObjectPath>,
+    req: HttpRequest,
+    style: web::Json<ObjectStyle>,
+) -> Result<CustomizeResponder<web::Json<SceneObjectResponse>>, ApiError> {
+    let mut scene = data.load_scene_for_update(path.scene_id, &req).await?;
+    scene
+        .set_style(data.db(), &path.object_name, style.into_inner())
+        .await?;
+    Ok(with_version(
+        SceneObjectResponse::new(&scene, &path.object_name),
+        scene.version,
+    ))
 }
 
-#[derive(Debug, Serialize)]
-pub struct ValidationResponse {
-    pub errors: Vec<String>,
+#[derive(Debug, Deserialize)]
+pub struct DeleteQuery {
+    /// Only report what would be deleted
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
-#[get("/scenes/{scene_id}/initial")]
-async fn get_initial_values(
+/// Deletes an object together with the objects depending on it, and returns the
+/// names of the latter. With `dry_run`, the scene is left unchanged.
+#[routes]
+#[delete("/{object_name}")]
+#[delete("/objects/{object_name}")]
+async fn delete_object(
     data: web::Data<AppState>,
-    path: web::Path<String>,
-    query: web::Query<HashMap<String, String>>,
-) -> impl Responder {
-    let scene_id = path.into_inner();
-
-    // Get the json parameter from query
-    let json_param = match query.get("json") {
-        Some(json_str) => json_str,
-        None => {
-            return HttpResponse::BadRequest().json("Missing 'json' query parameter");
-        }
-    };
-
-    // Decode the base64-encoded JSON
-    let decoded_json = match URL_SAFE_NO_PAD.decode(json_param) {
-        Ok(bytes) => match String::from_utf8(bytes) {
-            Ok(s) => s,
-            Err(_) => {
-                return HttpResponse::BadRequest().json("Invalid UTF-8 in decoded JSON");
-            }
-        },
-        Err(_) => {
-            return HttpResponse::BadRequest().json("Invalid base64 encoding");
-        }
-    };
-
-    // Parse the JSON array of expressions
-    let expressions: Vec<String> = match serde_json::from_str(&decoded_json) {
-        Ok(exprs) => exprs,
-        Err(_) => {
-            return HttpResponse::BadRequest()
-                .json("Invalid JSON format - expected array of strings");
-        }
-    };
-
-    // Load the scene
-    let scene_or_error = data.load_scene(&scene_id, SceneOptions::default()).await;
-    let scene = match scene_or_error {
-        SceneOrError::Scene(scene) => scene,
-        SceneOrError::Error(response) => return response,
-    };
-
-    match scene.evaluate_initial_values(&expressions) {
-        Ok(values) => HttpResponse::Ok().json(InitialValuesResponse { values }),
-        Err(e) => HttpResponse::InternalServerError()
-            .json(format!("Failed to evaluate initial values: {}", e)),
+    path: web::Path<ObjectPath>,
+    query: web::Query<DeleteQuery>,
+    req: HttpRequest,
+) -> Result<CustomizeResponder<web::Json<Vec<String>>>, ApiError> {
+    if query.dry_run {
+        let scene = data
+            .load_scene(path.scene_id, SceneOptions::default())
+            .await?;
+        let mut dependents = scene.collect_dependent_objects(&path.object_name);
+        dependents.retain(|name| *name != path.object_name);
+        return Ok(with_version(dependents, scene.version));
     }
+    let mut scene = data.load_scene_for_update(path.scene_id, &req).await?;
+    let dependencies = scene.delete_object(data.db(), &path.object_name).await?;
+    Ok(with_version(dependencies, scene.version))
 }
 
-#[get("/scenes/{scene_id}/validate")]
-async fn validate_expressions(
+#[get("/{object_name}/dependents")]
+async fn get_dependents(
     data: web::Data<AppState>,
-    path: web::Path<String>,
-    query: web::Query<HashMap<String, String>>,
-) -> impl Responder {
-    let scene_id = path.into_inner();
-
-    let json_param = match query.get("json") {
-        Some(json) => json,
-        None => {
-            return HttpResponse::BadRequest().json("Missing 'json' query parameter");
-        }
-    };
-
-    let decoded_json = match URL_SAFE_NO_PAD.decode(json_param) {
-        Ok(decoded) => decoded,
-        Err(_) => {
-            return HttpResponse::BadRequest().json("Invalid base64 encoding in 'json' parameter");
-        }
-    };
-
-    let expressions: Vec<String> =
-        match serde_json::from_str(&String::from_utf8_lossy(&decoded_json)) {
-            Ok(expressions) => expressions,
-            Err(e) => {
-                return HttpResponse::BadRequest().json(format!("Invalid JSON format: {}", e));
-            }
-        };
-
-    let scene_or_error = data.load_scene(&scene_id, SceneOptions::default()).await;
-    let scene = match scene_or_error {
-        SceneOrError::Scene(scene) => scene,
-        SceneOrError::Error(response) => {
-            return response;
-        }
-    };
-
-    // Validate each expression and collect all errors
-    let mut all_errors = Vec::new();
-    for (index, expression) in expressions.iter().enumerate() {
-        let errors = scene.validate_expression(expression.clone());
-        for error in errors {
-            all_errors.push(format!("Expression {}: {}", index + 1, error));
-        }
-    }
-
-    HttpResponse::Ok().json(ValidationResponse { errors: all_errors })
-}
-
-pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_actions)
-        .service(get_scene)
-        .service(add_object)
-        .service(delete_object)
-        .service(delete_scene)
-        .service(get_dependents)
-        .service(get_plot)
-        .service(create_scene)
-        .service(rename_scene)
-        .service(get_initial_values)
-        .service(validate_expressions)
-        .service(get_scenes);
+    path: web::Path<ObjectPath>,
+) -> Result<web::Json<Vec<String>>, ApiError> {
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+    Ok(web::Json(
+        scene.collect_dependent_objects(&path.object_name),
+    ))
 }
@@ -0,0 +1,1081 @@
+use actix_web::{get, patch, post, web, HttpResponse};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use super::ScenePath;
+use crate::api::error::ApiError;
+use crate::api::progress::sse_stream;
+use crate::api::AppState;
+use crate::curve_analysis::{
+    self, CurveAnalysis, CurveExtent, LatticePoints, PointCheck, SingularPoint, TangentAndNormal,
+};
+use crate::db::{CurveCacheModel, PariCacheModel};
+use crate::gp_pari_service::{with_cancellation, with_task_limits, CancellationToken};
+use crate::metrics;
+use crate::poly_draw::{self, Color, PlotStyle, XYPolyDraw};
+use crate::progress::with_progress;
+use crate::replay;
+use crate::scene::{
+    CenteredEquation, Configuration, FactorPlot, FormattedEquation, Frame, LocusCurve, LocusKind,
+    Parametrization, PlotData, Scene, SceneOptions, SmoothingAlgorithm, StoredOptions, View,
+};
+use crate::scene_object::{ObjectStyle, SceneError, SceneObject};
+use crate::trace::{self, TraceStep};
+use crate::variables::VariableRegistry;
+use crate::warning::Warning;
+
+#[derive(Debug, Deserialize)]
+pub struct PlotPath {
+    pub scene_id: i32,
+    pub locus_name: String,
+}
+
+/// Query of the plot endpoints. The options it leaves out are those stored with the
+/// scene (see `StoredOptions`), or the defaults.
+#[derive(Debug, Deserialize)]
+pub struct PlotQuery {
+    #[serde(default = "default_plot_size")]
+    pub width: u32,
+    #[serde(default = "default_plot_size")]
+    pub height: u32,
+    pub reduce_factors: Option<bool>,
+    pub elimination_method: Option<String>,
+    /// "min_degree" (default), "degree_product" or "brown"
+    pub elimination_order: Option<String>,
+    pub incremental: Option<bool>,
+    pub smoothing: Option<String>,
+    pub parametrize: Option<bool>,
+    /// "grid" (default), "adaptive", which refines cells up to `max_depth` times, or
+    /// "columns", which counts the crossings of the curve with the edges of cells exactly
+    pub tracer: Option<String>,
+    pub max_depth: Option<u32>,
+    /// Number of threads drawing the curve, at most one per available core
+    pub threads: Option<usize>,
+    /// Report the nodes, cusps and isolated points of the curve
+    pub singular_points: Option<bool>,
+    /// Draw each factor of a reducible curve in its own color
+    pub color_factors: Option<bool>,
+    /// Compute the elimination determinants modulo primes
+    pub modular_determinants: Option<bool>,
+    /// Timeout of each Pari/GP task in seconds
+    pub pari_timeout: Option<u64>,
+    /// Largest Pari/GP stack in megabytes
+    pub pari_stack_mb: Option<u64>,
+    pub frame_origin: Option<String>,
+    pub frame_axis: Option<String>,
+    /// Add the projective closure of the curve and its points at infinity to the analysis
+    #[serde(default)]
+    pub projective: bool,
+    /// Add the curvature of each factor and its inflection points in the view to the analysis
+    #[serde(default)]
+    pub inflection_points: bool,
+    /// Add the equations of the variables eliminated from each system to the analysis
+    #[serde(default)]
+    pub triangular_sets: bool,
+    /// If the elimination times out, return numerically found points of the locus
+    pub fallback_numeric: Option<bool>,
+    /// Limits of the elimination, on top of those of the server
+    pub max_total_degree: Option<u32>,
+    pub max_terms: Option<usize>,
+    pub max_elimination_steps: Option<usize>,
+    /// Also give the equation in coordinates centered on the view, with `centered_scale`
+    /// as their unit length (1 by default)
+    pub centered: Option<bool>,
+    pub centered_scale: Option<i64>,
+}
+
+fn default_plot_size() -> u32 {
+    2000
+}
+
+impl PlotQuery {
+    /// The options of the query, with the ones it leaves out taken from `stored`
+    pub(crate) fn to_options(&self, stored: &StoredOptions) -> Result<SceneOptions, SceneError> {
+        let mut options = StoredOptions {
+            reduce_factors: self.reduce_factors,
+            elimination_method: self.elimination_method.clone(),
+            elimination_order: self.elimination_order.clone(),
+            incremental: self.incremental,
+            smoothing: self.smoothing.clone(),
+            parametrize: self.parametrize,
+            tracer: self.tracer.clone(),
+            max_depth: self.max_depth,
+            singular_points: self.singular_points,
+            color_factors: self.color_factors,
+            modular_determinants: self.modular_determinants,
+            pari_timeout: self.pari_timeout,
+            pari_stack_mb: self.pari_stack_mb,
+            fallback_numeric: self.fallback_numeric,
+            max_total_degree: self.max_total_degree,
+            max_terms: self.max_terms,
+            max_elimination_steps: self.max_elimination_steps,
+            centered: self.centered,
+            centered_scale: self.centered_scale,
+        }
+        .or(stored)
+        .to_scene_options()?;
+        if let Some(threads) = self.threads {
+            options.threads = threads.clamp(1, poly_draw::max_threads());
+        }
+        options.frame = self.frame_origin.as_ref().map(|origin| Frame {
+            origin: origin.clone(),
+            axis: self.frame_axis.clone(),
+        });
+        Ok(options)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlotResponse {
+    pub points: Vec<(u32, u32, Color)>,
+    pub uncertain_points: Vec<(u32, u32)>,
+    pub factor_plots: Vec<FactorPlot>,
+    pub polylines: Option<Vec<Vec<(f64, f64)>>>,
+    pub equation: String,
+    pub formatted_equations: Vec<FormattedEquation>,
+    pub frame_equation: Option<String>,
+    pub frame_formatted_equations: Option<Vec<String>>,
+    pub centered_equation: Option<CenteredEquation>,
+    pub parametrization: Option<Parametrization>,
+    pub singular_points: Vec<SingularPoint>,
+    pub warnings: Vec<Warning>,
+    /// Whether the locus is a curve, or empty, the whole plane or isolated points
+    pub locus: LocusKind,
+    /// The points were found numerically after the elimination timed out
+    pub approximate: bool,
+    /// Style of the locus object, see `ObjectStyle`
+    pub style: ObjectStyle,
+    pub time_taken: f64,
+}
+
+impl PlotResponse {
+    fn new(plot_data: PlotData, time_taken: f64) -> Self {
+        Self {
+            points: plot_data.points,
+            uncertain_points: plot_data.uncertain_points,
+            factor_plots: plot_data.factor_plots,
+            polylines: plot_data.polylines,
+            equation: plot_data.equation,
+            formatted_equations: plot_data.formatted_equations,
+            frame_equation: plot_data.frame_equation,
+            frame_formatted_equations: plot_data.frame_formatted_equations,
+            centered_equation: plot_data.centered_equation,
+            parametrization: plot_data.parametrization,
+            singular_points: plot_data.singular_points,
+            warnings: plot_data.warnings,
+            locus: plot_data.kind,
+            approximate: plot_data.approximate,
+            style: plot_data.style,
+            time_taken,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocusPlotResponse {
+    pub locus_name: String,
+    #[serde(flatten)]
+    pub plot: PlotResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ViewResponse {
+    pub view: View,
+    /// Plots of the loci with solved curves, in scene order
+    pub plots: Vec<LocusPlotResponse>,
+}
+
+/// Solves for a locus on a blocking thread, publishing progress to subscribers,
+/// and passes the locus name and curve to `finish` on the same thread
+async fn solve_locus<R: Send + 'static>(
+    data: &AppState,
+    PlotPath {
+        scene_id,
+        locus_name,
+    }: PlotPath,
+    options: SceneOptions,
+    finish: impl FnOnce(&Scene, &str, &LocusCurve) -> Result<R, SceneError> + Send + 'static,
+) -> Result<R, ApiError> {
+    metrics::PLOT_REQUESTS.inc();
+    let pari_limits = options.pari_limits;
+    let scene = data.load_scene(scene_id, options).await?;
+    if !matches!(scene.objects.get(&locus_name), Some(SceneObject::Locus(_))) {
+        return Err(ApiError::NotFound(format!(
+            "Locus not found: {}",
+            locus_name
+        )));
+    }
+
+    // Curves only depend on the objects, so panning and zooming reuses them
+    let scene_hash = scene.curve_hash();
+    let cached_curve =
+        CurveCacheModel::find_curve(data.db(), scene_id, &locus_name, &scene_hash).await?;
+    metrics::CURVE_CACHE.record(cached_curve.is_some());
+
+    let start_time = Instant::now();
+    // Solve on a blocking thread; if the client disconnects, this future is
+    // dropped and the guard cancels the pending Pari/GP tasks
+    let token = CancellationToken::new();
+    let cancel_guard = token.cancel_on_drop();
+    let progress_sender = data.progress().sender(scene_id, &locus_name);
+    let locus = locus_name.clone();
+    let result = web::block(move || {
+        with_progress(
+            move |progress| {
+                let _ = progress_sender.send(progress);
+            },
+            || {
+                with_cancellation(token, || {
+                    with_task_limits(pari_limits, || {
+                        let (locus_curve, new_entry) = match cached_curve {
+                            Some(cached_curve) => (cached_curve.get_locus_curve()?, None),
+                            None => {
+                                let locus_curve = scene.solve_locus_curve(&locus)?;
+                                let new_entry = CurveCacheModel::new_entry(
+                                    scene_id,
+                                    &locus,
+                                    &scene_hash,
+                                    &locus_curve,
+                                )?;
+                                (locus_curve, Some(new_entry))
+                            }
+                        };
+                        let output = finish(&scene, &locus, &locus_curve)?;
+                        Ok::<_, SceneError>((output, new_entry))
+                    })
+                })
+            },
+        )
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()));
+    cancel_guard.disarm();
+    let error = match &result {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+    data.progress().finish(scene_id, &locus_name, error);
+
+    let (output, new_entry) = result?.map_err(|e| {
+        info!(
+            "Failed to solve for locus {}: {} (took {:.3}s)",
+            locus_name,
+            e,
+            start_time.elapsed().as_secs_f64()
+        );
+        ApiError::from(e)
+    })?;
+    if let Some(new_entry) = new_entry {
+        // The plot is still valid if the curve can't be cached
+        if let Err(e) = CurveCacheModel::save_curve(data.db(), new_entry).await {
+            info!("Failed to cache the curve of locus {}: {}", locus_name, e);
+        }
+    }
+    // New Pari/GP outputs are only collected if the cache is persisted
+    if let Ok(pool) = crate::gp_pari_service::get_gp_pari_pool() {
+        let entries = pool.take_new_cache_entries();
+        let capacity = pool.cache_capacity();
+        if let Err(e) = PariCacheModel::save_entries(data.db(), entries, capacity).await {
+            info!("Failed to persist the Pari/GP cache: {}", e);
+        }
+    }
+    Ok(output)
+}
+
+#[get("/plot/{locus_name}")]
+async fn get_plot(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+) -> Result<web::Json<PlotResponse>, ApiError> {
+    Ok(web::Json(plot(&data, path.into_inner(), &query).await?))
+}
+
+/// Solves for the curve of a locus and draws it, numerically if the elimination
+/// times out and the query allows it
+pub(super) async fn plot(
+    data: &AppState,
+    path: PlotPath,
+    query: &PlotQuery,
+) -> Result<PlotResponse, ApiError> {
+    let options = data.scene_options(path.scene_id, query).await?;
+    plot_with_options(data, path, query, options).await
+}
+
+async fn plot_with_options(
+    data: &AppState,
+    path: PlotPath,
+    query: &PlotQuery,
+    options: SceneOptions,
+) -> Result<PlotResponse, ApiError> {
+    let start_time = Instant::now();
+    let (width, height) = (query.width, query.height);
+    let (scene_id, locus_name) = (path.scene_id, path.locus_name.clone());
+    let result = solve_locus(
+        data,
+        path,
+        options.clone(),
+        move |scene, locus_name, curve| scene.plot_locus_curve(locus_name, curve, width, height),
+    )
+    .await;
+    let plot_data = match result {
+        Err(ApiError::Scene(e)) if options.fallback_numeric && e.is_timeout() => {
+            info!("Plotting locus {} numerically: {}", locus_name, e);
+            let scene = data.load_scene(scene_id, options).await?;
+            web::block(move || scene.plot_numeric_locus(&locus_name, width, height))
+                .await
+                .map_err(|e| ApiError::Internal(e.to_string()))??
+        }
+        result => result?,
+    };
+    Ok(PlotResponse::new(
+        plot_data,
+        start_time.elapsed().as_secs_f64(),
+    ))
+}
+
+/// Stores a panned or zoomed view of a scene and redraws the loci whose curves
+/// are already solved for it. Loci that were not plotted yet (with the same
+/// elimination options) are left out: they need a full plot request.
+#[patch("/view")]
+async fn update_view(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    query: web::Query<PlotQuery>,
+    view: web::Json<View>,
+) -> Result<web::Json<ViewResponse>, ApiError> {
+    let start_time = Instant::now();
+    let options = data.scene_options(path.scene_id, &query).await?;
+    let mut scene = data.load_scene(path.scene_id, options).await?;
+    let view = view.into_inner();
+    scene.save_view(data.db(), view.clone()).await?;
+
+    let mut cached_curves =
+        CurveCacheModel::find_curves(data.db(), scene.id, &scene.curve_hash()).await?;
+    // Keep the order of the loci in the scene
+    let locus_index = |name: &str| scene.objects.get_index_of(name);
+    cached_curves.retain(|cached_curve| {
+        matches!(
+            scene.objects.get(&cached_curve.locus_name),
+            Some(SceneObject::Locus(_))
+        )
+    });
+    cached_curves.sort_by_key(|cached_curve| locus_index(&cached_curve.locus_name));
+
+    let (width, height) = (query.width, query.height);
+    let response_view = view.clone();
+    let plots = web::block(move || {
+        let locus_curves = cached_curves
+            .iter()
+            .map(|cached_curve| {
+                Ok((
+                    cached_curve.locus_name.clone(),
+                    cached_curve.get_locus_curve()?,
+                ))
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+        scene.replot(view, &locus_curves, width, height)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))??;
+
+    let time_taken = start_time.elapsed().as_secs_f64();
+    Ok(web::Json(ViewResponse {
+        view: response_view,
+        plots: plots
+            .into_iter()
+            .map(|(locus_name, plot_data)| LocusPlotResponse {
+                locus_name,
+                plot: PlotResponse::new(plot_data, time_taken),
+            })
+            .collect(),
+    }))
+}
+
+/// Renders a locus as an SVG document of traced curve paths. The document spans
+/// `width` x `height` user units, which cover the current view of the scene.
+#[get("/loci/{locus_name}/svg")]
+async fn get_plot_svg(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let mut options = data.scene_options(path.scene_id, &query).await?;
+    options.smoothing = SmoothingAlgorithm::Trace;
+    let (width, height) = (query.width, query.height);
+    let plot_data = solve_locus(
+        &data,
+        path.into_inner(),
+        options,
+        move |scene, locus_name, curve| scene.plot_locus_curve(locus_name, curve, width, height),
+    )
+    .await?;
+    let paths = XYPolyDraw::to_svg_paths(&plot_data.polylines.unwrap_or_default(), 1.0);
+    Ok(HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .body(to_svg_document(&paths, width, height)))
+}
+
+/// Decorations of PNG plots, next to the `PlotQuery` parameters
+#[derive(Debug, Deserialize)]
+pub struct PngQuery {
+    /// Draw the coordinate axes (default true)
+    pub axes: Option<bool>,
+    /// Draw grid lines (default true)
+    pub grid: Option<bool>,
+    /// Distance between grid lines, chosen from the view by default
+    pub grid_step: Option<f64>,
+}
+
+impl PngQuery {
+    fn to_style(&self) -> Result<PlotStyle, ApiError> {
+        if self
+            .grid_step
+            .is_some_and(|step| !(step > 0.0 && step.is_finite()))
+        {
+            return Err(ApiError::BadRequest(
+                "Grid step must be a positive number".to_string(),
+            ));
+        }
+        let default_style = PlotStyle::default();
+        Ok(PlotStyle {
+            grid: default_style.grid.filter(|_| self.grid.unwrap_or(true)),
+            grid_step: self.grid_step,
+            axes: default_style.axes.filter(|_| self.axes.unwrap_or(true)),
+            ..default_style
+        })
+    }
+}
+
+/// Renders a locus as a PNG image of the current view of the scene, with axes and grid lines
+#[get("/loci/{locus_name}/png")]
+async fn get_plot_png(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+    png_query: web::Query<PngQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let options = data.scene_options(path.scene_id, &query).await?;
+    let style = png_query.to_style()?;
+    let (width, height) = (query.width, query.height);
+    let (plot_data, plot_view) = solve_locus(
+        &data,
+        path.into_inner(),
+        options,
+        move |scene, locus_name, curve| {
+            let plot_data = scene.plot_locus_curve(locus_name, curve, width, height)?;
+            Ok((plot_data, scene.view.plot_view(width, height)))
+        },
+    )
+    .await?;
+    // With colored factors, their points replace the ones of the whole curve
+    let points = if plot_data.factor_plots.is_empty() {
+        plot_data.points
+    } else {
+        plot_data
+            .factor_plots
+            .into_iter()
+            .flat_map(|factor_plot| factor_plot.points)
+            .collect()
+    };
+    let png = XYPolyDraw::render_points_png(&points, width, height, &plot_view, &style)
+        .map_err(|e| ApiError::Internal(format!("Failed to encode the plot: {}", e)))?;
+    Ok(HttpResponse::Ok().content_type("image/png").body(png))
+}
+
+fn to_svg_document(paths: &[String], width: u32, height: u32) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+        width, height
+    );
+    for path in paths {
+        svg.push_str(&format!(
+            "  <path d=\"{}\" fill=\"none\" stroke=\"red\" stroke-width=\"1\" stroke-linejoin=\"round\"/>\n",
+            path
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Steps of solving for a locus curve, see `trace`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TraceResponse {
+    pub steps: Vec<TraceStep>,
+    /// The curve equation, unless solving failed with `error`
+    pub equation: Option<String>,
+    pub error: Option<String>,
+    /// The objects and coordinates the variables in the steps stand for
+    pub variables: VariableRegistry,
+}
+
+/// Solves for the curve of a locus again, bypassing the curve cache, and returns
+/// every step taken. The steps are returned even if solving fails.
+#[get("/loci/{locus_name}/trace")]
+async fn get_trace(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+) -> Result<web::Json<TraceResponse>, ApiError> {
+    let PlotPath {
+        scene_id,
+        locus_name,
+    } = path.into_inner();
+    let options = data.scene_options(scene_id, &query).await?;
+    metrics::PLOT_REQUESTS.inc();
+    let pari_limits = options.pari_limits;
+    let scene = data.load_scene(scene_id, options).await?;
+    if !matches!(scene.objects.get(&locus_name), Some(SceneObject::Locus(_))) {
+        return Err(ApiError::NotFound(format!(
+            "Locus not found: {}",
+            locus_name
+        )));
+    }
+
+    let token = CancellationToken::new();
+    let cancel_guard = token.cancel_on_drop();
+    let (result, steps, variables) = web::block(move || {
+        with_cancellation(token, || {
+            with_task_limits(pari_limits, || {
+                let (result, steps) = trace::with_trace(|| scene.solve_locus_curve(&locus_name));
+                let result = result
+                    .map(|locus_curve| locus_curve.curve.curve_equation.to_string())
+                    .map_err(|e| e.to_string());
+                let variables = scene.variable_registry().unwrap_or_default();
+                (result, steps, variables)
+            })
+        })
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
+    cancel_guard.disarm();
+    Ok(web::Json(TraceResponse {
+        steps,
+        equation: result.as_ref().ok().cloned(),
+        error: result.err(),
+        variables,
+    }))
+}
+
+/// A trace saved from the trace endpoint, e.g. its whole response
+#[derive(Debug, Deserialize)]
+pub struct ReplayRequest {
+    pub steps: Vec<TraceStep>,
+}
+
+/// Plots a locus like `get_plot`, eliminating the variables in the order of a trace
+/// saved before, e.g. before a fixed point was moved, instead of searching for each
+/// one. If the system no longer matches the trace, the search takes over with a
+/// warning.
+#[post("/loci/{locus_name}/replay")]
+async fn replay_plot(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+    request: web::Json<ReplayRequest>,
+) -> Result<web::Json<PlotResponse>, ApiError> {
+    let order = replay::elimination_order(&request.steps).map_err(ApiError::BadRequest)?;
+    if order.is_empty() {
+        return Err(ApiError::BadRequest(
+            "The trace does not eliminate any variable".to_string(),
+        ));
+    }
+    let mut options = data.scene_options(path.scene_id, &query).await?;
+    options.replay = Some(order);
+    Ok(web::Json(
+        plot_with_options(&data, path.into_inner(), &query, options).await?,
+    ))
+}
+
+/// Point to test against the curve of a locus, in world coordinates
+#[derive(Debug, Deserialize)]
+pub struct CheckPointRequest {
+    pub x: f64,
+    pub y: f64,
+    /// Largest |F(x, y)| accepted as on the curve
+    #[serde(default = "default_point_tolerance")]
+    pub tolerance: f64,
+}
+
+fn default_point_tolerance() -> f64 {
+    1e-9
+}
+
+/// Certifies with interval arithmetic whether a point lies on the curve of a locus
+/// and on each of its factors, e.g. to debug constructions that look off the curve
+#[post("/loci/{locus_name}/check-point")]
+async fn check_point(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+    request: web::Json<CheckPointRequest>,
+) -> Result<web::Json<PointCheck>, ApiError> {
+    let CheckPointRequest { x, y, tolerance } = request.into_inner();
+    if !(tolerance >= 0.0 && tolerance.is_finite()) {
+        return Err(ApiError::BadRequest(
+            "Tolerance must be a non-negative number".to_string(),
+        ));
+    }
+    let options = data.scene_options(path.scene_id, &query).await?;
+    let check = solve_locus(&data, path.into_inner(), options, move |_, _, curve| {
+        curve_analysis::check_point(&curve.curve, curve.x_var, curve.y_var, (x, y), tolerance)
+            .map_err(SceneError::InvalidEquation)
+    })
+    .await?;
+    Ok(web::Json(check))
+}
+
+/// Point near the curve of a locus, in world coordinates
+#[derive(Debug, Deserialize)]
+pub struct TangentRequest {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// The tangent and the normal of the curve of a locus at the point of the curve
+/// nearest to (x, y), as line equations for the frontend to draw
+#[post("/loci/{locus_name}/tangent")]
+async fn get_tangent(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+    request: web::Json<TangentRequest>,
+) -> Result<web::Json<TangentAndNormal>, ApiError> {
+    let TangentRequest { x, y } = request.into_inner();
+    let options = data.scene_options(path.scene_id, &query).await?;
+    let lines = solve_locus(&data, path.into_inner(), options, move |_, _, curve| {
+        curve_analysis::tangent_and_normal(&curve.curve, curve.x_var, curve.y_var, (x, y))
+            .map_err(SceneError::InvalidEquation)
+    })
+    .await?;
+    lines.map(web::Json).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "No regular point of the curve was found near ({}, {})",
+            x, y
+        ))
+    })
+}
+
+/// Point of a locus, in world coordinates
+#[derive(Debug, Deserialize)]
+pub struct ConfigurationRequest {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Finds the positions of the objects of a scene that put the point of a locus at
+/// (x, y), so that the construction can be shown at a clicked point of the curve.
+/// The constraints are solved numerically, without the curve equation.
+#[post("/loci/{locus_name}/configuration")]
+async fn get_configuration(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    request: web::Json<ConfigurationRequest>,
+) -> Result<web::Json<Configuration>, ApiError> {
+    let PlotPath {
+        scene_id,
+        locus_name,
+    } = path.into_inner();
+    let scene = data.load_scene(scene_id, SceneOptions::default()).await?;
+    if !matches!(scene.objects.get(&locus_name), Some(SceneObject::Locus(_))) {
+        return Err(ApiError::NotFound(format!(
+            "Locus not found: {}",
+            locus_name
+        )));
+    }
+    let ConfigurationRequest { x, y } = request.into_inner();
+    let locus = locus_name.clone();
+    let configuration = web::block(move || scene.configuration_at(&locus, (x, y)))
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))??;
+    configuration.map(web::Json).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "No configuration of the scene puts the point of {} at ({}, {})",
+            locus_name, x, y
+        ))
+    })
+}
+
+/// Reports the degree, the irreducible factors and their genera of the curve of a locus,
+/// its extent with a view of the requested size showing all of it, with `projective` its
+/// projective closure, with `inflection_points` the curvature of its factors and
+/// their inflection points in the view, and with `triangular_sets` the equations of the
+/// variables eliminated to find it
+#[get("/loci/{locus_name}/analysis")]
+async fn get_curve_analysis(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+) -> Result<web::Json<CurveAnalysis>, ApiError> {
+    let options = data.scene_options(path.scene_id, &query).await?;
+    let projective = query.projective;
+    let inflection_points = query.inflection_points;
+    let triangular_sets = query.triangular_sets;
+    let (width, height) = (query.width, query.height);
+    let analysis = solve_locus(
+        &data,
+        path.into_inner(),
+        options,
+        move |scene, locus, curve| {
+            let mut analysis = curve_analysis::analyze_curve(
+                &curve.curve,
+                curve.x_var,
+                curve.y_var,
+                scene.variable_registry()?,
+            );
+            if let Some(CurveExtent::Bounded(bounding_box)) = &analysis.extent {
+                analysis.suggested_view = Some(View::fitting(bounding_box, width, height));
+            }
+            if projective {
+                analysis.projective = Some(
+                    curve_analysis::projective_closure(
+                        &curve.curve.curve_equation,
+                        curve.x_var,
+                        curve.y_var,
+                    )
+                    .map_err(SceneError::InvalidEquation)?,
+                );
+            }
+            if inflection_points {
+                let plot_view = scene.view.plot_view(width, height);
+                analysis.curvature = Some(curve_analysis::analyze_curvature(
+                    &curve.curve,
+                    curve.x_var,
+                    curve.y_var,
+                    (plot_view.x_min, plot_view.x_max),
+                    (plot_view.y_min, plot_view.y_max),
+                    &mut analysis.warnings,
+                ));
+            }
+            if triangular_sets {
+                // Cached curves don't keep their triangular sets, so they are solved again
+                let solved;
+                let curve = match curve.curve.triangular_sets {
+                    Some(_) => curve,
+                    None => {
+                        solved = scene.solve_locus_curve(locus)?;
+                        &solved
+                    }
+                };
+                analysis.triangular_sets = curve_analysis::triangular_sets(&curve.curve);
+            }
+            Ok(analysis)
+        },
+    )
+    .await?;
+    Ok(web::Json(analysis))
+}
+
+/// Denominators of the points searched for by the lattice points endpoint
+#[derive(Debug, Deserialize)]
+pub struct LatticePointsQuery {
+    /// Largest denominator of the coordinates, 1 for integer points
+    #[serde(default = "default_max_denominator")]
+    pub max_denominator: i64,
+}
+
+fn default_max_denominator() -> i64 {
+    1
+}
+
+/// Finds the points of the curve of a locus in the current view whose coordinates are
+/// integers, or fractions with denominators up to `max_denominator`
+#[get("/loci/{locus_name}/lattice-points")]
+async fn get_lattice_points(
+    data: web::Data<AppState>,
+    path: web::Path<PlotPath>,
+    query: web::Query<PlotQuery>,
+    lattice_query: web::Query<LatticePointsQuery>,
+) -> Result<web::Json<LatticePoints>, ApiError> {
+    let options = data.scene_options(path.scene_id, &query).await?;
+    let (width, height) = (query.width, query.height);
+    let max_denominator = lattice_query.max_denominator;
+    // Checked before solving, which may take long
+    if !(1..=curve_analysis::MAX_LATTICE_DENOMINATOR).contains(&max_denominator) {
+        return Err(ApiError::BadRequest(format!(
+            "Largest denominator must be between 1 and {}",
+            curve_analysis::MAX_LATTICE_DENOMINATOR
+        )));
+    }
+    let points = solve_locus(&data, path.into_inner(), options, move |scene, _, curve| {
+        let plot_view = scene.view.plot_view(width, height);
+        curve_analysis::lattice_points(
+            &curve.curve.curve_equation,
+            curve.x_var,
+            curve.y_var,
+            (plot_view.x_min, plot_view.x_max),
+            (plot_view.y_min, plot_view.y_max),
+            max_denominator,
+        )
+        .map_err(SceneError::InvalidProperties)
+    })
+    .await?;
+    Ok(web::Json(points))
+}
+
+/// Streams the progress of the next or running computation of a locus as server-sent events
+#[get("/loci/{locus_name}/progress")]
+async fn get_plot_progress(data: web::Data<AppState>, path: web::Path<PlotPath>) -> HttpResponse {
+    let receiver = data.progress().subscribe(path.scene_id, &path.locus_name);
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(sse_stream(receiver))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::Budget;
+    use crate::elimination::EliminationOrder;
+    use crate::gp_pari_service::TaskLimits;
+    use crate::scene::{CurveTracer, EliminationMethod};
+    use std::time::Duration;
+
+    #[test]
+    fn test_plot_query_options() {
+        let query = web::Query::<PlotQuery>::from_query(
+            "width=100&elimination_method=groebner&smoothing=trace&parametrize=true&frame_origin=A",
+        )
+        .unwrap();
+        assert_eq!((query.width, query.height), (100, 2000));
+        let options = query.to_options(&StoredOptions::default()).unwrap();
+        assert_eq!(options.elimination_method, EliminationMethod::Groebner);
+        assert_eq!(options.smoothing, SmoothingAlgorithm::Trace);
+        assert!(options.parametrize);
+        assert!(!options.singular_points);
+        assert_eq!(
+            options.frame,
+            Some(Frame {
+                origin: "A".to_string(),
+                axis: None
+            })
+        );
+
+        let query = web::Query::<PlotQuery>::from_query("smoothing=blur").unwrap();
+        assert!(query.to_options(&StoredOptions::default()).is_err());
+        let query = web::Query::<PlotQuery>::from_query("elimination_order=brown").unwrap();
+        assert_eq!(
+            query
+                .to_options(&StoredOptions::default())
+                .unwrap()
+                .elimination_order,
+            EliminationOrder::Brown
+        );
+        let query = web::Query::<PlotQuery>::from_query("elimination_order=random").unwrap();
+        assert!(query.to_options(&StoredOptions::default()).is_err());
+
+        let query =
+            web::Query::<PlotQuery>::from_query("pari_timeout=60&pari_stack_mb=512").unwrap();
+        assert_eq!(
+            query
+                .to_options(&StoredOptions::default())
+                .unwrap()
+                .pari_limits,
+            TaskLimits {
+                timeout: Some(Duration::from_secs(60)),
+                max_stack_size: Some(512 << 20),
+            }
+        );
+        let query =
+            web::Query::<PlotQuery>::from_query("max_terms=500&max_elimination_steps=40").unwrap();
+        assert_eq!(
+            query.to_options(&StoredOptions::default()).unwrap().budget,
+            Budget {
+                max_total_degree: None,
+                max_terms: Some(500),
+                max_elimination_steps: Some(40),
+            }
+        );
+
+        let query = web::Query::<PlotQuery>::from_query("pari_timeout=0").unwrap();
+        assert!(query.to_options(&StoredOptions::default()).is_err());
+        let query = web::Query::<PlotQuery>::from_query("pari_stack_mb=100000").unwrap();
+        assert!(query.to_options(&StoredOptions::default()).is_err());
+
+        let query = web::Query::<PlotQuery>::from_query("tracer=adaptive&max_depth=6").unwrap();
+        let options = query.to_options(&StoredOptions::default()).unwrap();
+        assert_eq!(options.tracer, CurveTracer::Adaptive { max_depth: 6 });
+        let query = web::Query::<PlotQuery>::from_query("tracer=columns").unwrap();
+        assert_eq!(
+            query.to_options(&StoredOptions::default()).unwrap().tracer,
+            CurveTracer::Columns
+        );
+        assert_eq!(options.threads, 1);
+        let query = web::Query::<PlotQuery>::from_query("threads=4").unwrap();
+        assert_eq!(
+            query.to_options(&StoredOptions::default()).unwrap().threads,
+            4.min(poly_draw::max_threads())
+        );
+        let query = web::Query::<PlotQuery>::from_query("threads=100000").unwrap();
+        assert_eq!(
+            query.to_options(&StoredOptions::default()).unwrap().threads,
+            poly_draw::max_threads()
+        );
+        let query = web::Query::<PlotQuery>::from_query("singular_points=true").unwrap();
+        assert!(
+            query
+                .to_options(&StoredOptions::default())
+                .unwrap()
+                .singular_points
+        );
+        let query = web::Query::<PlotQuery>::from_query("color_factors=true").unwrap();
+        assert!(
+            query
+                .to_options(&StoredOptions::default())
+                .unwrap()
+                .color_factors
+        );
+        let query = web::Query::<PlotQuery>::from_query("tracer=adaptive&max_depth=7").unwrap();
+        assert!(query.to_options(&StoredOptions::default()).is_err());
+
+        let query = web::Query::<PlotQuery>::from_query("centered=true").unwrap();
+        let options = query.to_options(&StoredOptions::default()).unwrap();
+        assert_eq!(options.centered_scale, Some(1));
+        let query = web::Query::<PlotQuery>::from_query("centered_scale=10").unwrap();
+        let stored = StoredOptions {
+            centered: Some(true),
+            ..StoredOptions::default()
+        };
+        assert_eq!(query.to_options(&stored).unwrap().centered_scale, Some(10));
+        let query = web::Query::<PlotQuery>::from_query("centered=true&centered_scale=0").unwrap();
+        assert!(query.to_options(&StoredOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_png_query_style() {
+        let query = web::Query::<PngQuery>::from_query("width=100&grid=false").unwrap();
+        let style = query.to_style().unwrap();
+        assert_eq!(style.grid, None);
+        assert_eq!(style.axes, PlotStyle::default().axes);
+        let query = web::Query::<PngQuery>::from_query("grid_step=0").unwrap();
+        assert!(query.to_style().is_err());
+    }
+
+    #[test]
+    fn test_svg_document() {
+        let svg = to_svg_document(&["M0.00,0.00 L1.00,1.00".to_string()], 20, 10);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"10\" viewBox=\"0 0 20 10\">"));
+        assert!(svg.contains("<path d=\"M0.00,0.00 L1.00,1.00\" fill=\"none\""));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[actix_web::test]
+    async fn test_plot_progress_stream() {
+        use crate::progress::Progress;
+        use actix_web::{test, App};
+
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let state = AppState::new(db).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state.clone()))
+                .service(web::scope("/scenes/{scene_id}").service(get_plot_progress)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/scenes/1/loci/L1/progress")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "text/event-stream"
+        );
+
+        let sender = state.progress().sender(1, "L1");
+        sender.send(Progress::Equations { count: 3 }).unwrap();
+        drop(sender);
+        state.progress().finish(1, "L1", None);
+
+        let body = test::read_body(resp).await;
+        assert_eq!(
+            body,
+            "data: {\"stage\":\"equations\",\"count\":3}\n\n\
+             data: {\"stage\":\"finished\",\"error\":null}\n\n"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_update_view_replots_cached_curves() {
+        use crate::db::{CurveCacheEntity, SceneActiveModel, SceneEntity, SceneObjectEntity};
+        use crate::poly::Poly;
+        use crate::scene::{Center, CurveEquationAndFactors, LocusCurve};
+        use crate::scene_object::ObjectType;
+        use actix_web::{test, App};
+        use sea_orm::{ActiveModelBehavior, ActiveModelTrait, ConnectionTrait, Schema};
+        use serde_json::json;
+
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let schema = Schema::new(db.get_database_backend());
+        let backend = db.get_database_backend();
+        for stmt in [
+            schema.create_table_from_entity(SceneEntity),
+            schema.create_table_from_entity(SceneObjectEntity),
+            schema.create_table_from_entity(CurveCacheEntity),
+        ] {
+            db.execute(backend.build(&stmt)).await.unwrap();
+        }
+        let scene_id = <SceneActiveModel as ActiveModelBehavior>::new()
+            .insert(&db)
+            .await
+            .unwrap()
+            .id;
+
+        let state = AppState::new(db).await;
+        let mut scene = state
+            .load_scene(scene_id, SceneOptions::default())
+            .await
+            .unwrap();
+        for (name, object_type, properties) in [
+            ("A", ObjectType::FreePoint, json!({"value": "1, 1"})),
+            ("L1", ObjectType::Locus, json!({"point": "A"})),
+            ("L2", ObjectType::Locus, json!({"point": "A"})),
+        ] {
+            scene
+                .add_object(state.db(), name.to_string(), object_type, properties)
+                .await
+                .unwrap();
+        }
+        // Only L1 has been plotted: its curve is the line x = y
+        let locus_curve = LocusCurve {
+            x_var: 0,
+            y_var: 1,
+            curve: CurveEquationAndFactors {
+                curve_equation: Poly::new("a - b").unwrap(),
+                factors: vec![Poly::new("a - b").unwrap()],
+                warnings: vec![],
+                triangular_sets: None,
+            },
+        };
+        let entry =
+            CurveCacheModel::new_entry(scene_id, "L1", &scene.curve_hash(), &locus_curve).unwrap();
+        CurveCacheModel::save_curve(state.db(), entry)
+            .await
+            .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state.clone()))
+                .service(web::scope("/scenes/{scene_id}").service(update_view)),
+        )
+        .await;
+        let req = test::TestRequest::patch()
+            .uri(&format!("/scenes/{}/view?width=50&height=50", scene_id))
+            .set_json(json!({"center": {"x": 100.0, "y": 100.0}, "diagonal": 10.0}))
+            .to_request();
+        let resp: ViewResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.plots.len(), 1);
+        assert_eq!(resp.plots[0].locus_name, "L1");
+        assert!(!resp.plots[0].plot.points.is_empty());
+
+        let scene = state
+            .load_scene(scene_id, SceneOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(scene.view.diagonal, 10.0);
+        assert!(matches!(scene.view.center, Center { x, y } if x == 100.0 && y == 100.0));
+    }
+}
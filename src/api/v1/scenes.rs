@@ -0,0 +1,484 @@
+use actix_web::{
+    delete, get, patch, post, put, web, CustomizeResponder, HttpRequest, HttpResponse,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::objects::SceneObjectResponse;
+use super::{with_version, ScenePath};
+use crate::api::auth;
+use crate::api::error::ApiError;
+use crate::api::AppState;
+use crate::db::{
+    SceneActiveModel, SceneColumn, SceneEntity, SceneModel, UserEntity, UserModel,
+    SCENE_DEFAULT_NAME,
+};
+use crate::dof_analysis::DegreesOfFreedom;
+use crate::expression_checker::{Diagnostic, ExpressionType};
+use crate::scene::{Animation, Scene, SceneOptions, StoredOptions, View};
+use crate::scene_object::SceneError;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SceneResponse {
+    pub objects: Vec<SceneObjectResponse>,
+    pub view: View,
+    /// Version of the objects, to send in the If-Match header of changes
+    pub version: i32,
+}
+
+impl SceneResponse {
+    pub fn new(scene: &Scene) -> Self {
+        Self {
+            objects: scene
+                .objects
+                .keys()
+                .map(|name| SceneObjectResponse::new(scene, name))
+                .collect(),
+            view: scene.view.clone(),
+            version: scene.version,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateSceneRequest {
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateSceneResponse {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenameSceneRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenameSceneResponse {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, serde::Deserialize)]
+pub struct SceneInfo {
+    pub id: i32,
+    pub name: String,
+    created_at: DateTime<Utc>,
+}
+
+/// A scene in the listing of all scenes, with the name of its owner if it has one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OwnedSceneInfo {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub owner: Option<String>,
+}
+
+/// Query of the expression endpoints: a JSON array of strings, base64url-encoded
+#[derive(Debug, Deserialize)]
+pub struct ExpressionsQuery {
+    pub json: String,
+}
+
+impl ExpressionsQuery {
+    fn decode(&self) -> Result<Vec<String>, ApiError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(&self.json)
+            .map_err(|_| ApiError::BadRequest("Invalid base64 encoding".to_string()))?;
+        let decoded_json = String::from_utf8(bytes)
+            .map_err(|_| ApiError::BadRequest("Invalid UTF-8 in decoded JSON".to_string()))?;
+        serde_json::from_str(&decoded_json).map_err(|e| {
+            ApiError::BadRequest(format!(
+                "Invalid JSON format - expected array of strings: {}",
+                e
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InitialValuesResponse {
+    pub values: Vec<f64>,
+}
+
+/// Frames of an animation, 60 unless given
+#[derive(Debug, Deserialize)]
+pub struct AnimateQuery {
+    #[serde(default = "default_animation_steps")]
+    pub steps: usize,
+}
+
+fn default_animation_steps() -> usize {
+    60
+}
+
+/// Largest number of frames of an animation
+const MAX_ANIMATION_STEPS: usize = 10000;
+
+#[derive(Debug, Serialize)]
+pub struct ValidationResponse {
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidateExpressionRequest {
+    pub expression: String,
+    /// Type the expression must have, e.g. "scalar" for Invariant formulas
+    pub expected_type: Option<ExpressionType>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidateExpressionResponse {
+    pub valid: bool,
+    /// Type of the expression, if it is valid
+    #[serde(rename = "type")]
+    pub expression_type: Option<ExpressionType>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[get("")]
+async fn get_scene(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+) -> Result<CustomizeResponder<web::Json<SceneResponse>>, ApiError> {
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+    Ok(with_version(SceneResponse::new(&scene), scene.version))
+}
+
+#[delete("")]
+async fn delete_scene(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let mut scene = data.load_scene_for_update(path.scene_id, &req).await?;
+    scene.delete_scene(data.db()).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/scenes")]
+async fn create_scene(
+    data: web::Data<AppState>,
+    user: Option<web::ReqData<UserModel>>,
+    request: web::Json<CreateSceneRequest>,
+) -> Result<web::Json<CreateSceneResponse>, ApiError> {
+    let db = data.db();
+
+    // Create new scene in database
+    let scene_name = request
+        .name
+        .as_deref()
+        .unwrap_or(SCENE_DEFAULT_NAME)
+        .to_string();
+    let scene = SceneActiveModel {
+        name: Set(scene_name.clone()),
+        user_id: Set(user.map(|user| user.id)),
+        ..Default::default()
+    };
+    let scene = scene.insert(db).await?;
+
+    // Update the name to "Scene <id>" if the provided name was empty or default
+    if request.name.as_deref().is_none_or(str::is_empty) || scene_name == SCENE_DEFAULT_NAME {
+        let final_name = format!("Scene {}", scene.id);
+        let id = scene.id;
+        let mut update_scene = scene.into_active_model();
+        update_scene.name = Set(final_name.clone());
+        update_scene.update(db).await?;
+        Ok(web::Json(CreateSceneResponse {
+            id,
+            name: final_name,
+        }))
+    } else {
+        Ok(web::Json(CreateSceneResponse {
+            id: scene.id,
+            name: scene.name,
+        }))
+    }
+}
+
+/// Copies a scene with its objects and view into a new scene. Without a name in
+/// the request, the copy is named after the original.
+#[post("/clone")]
+async fn clone_scene(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    request: Option<web::Json<CreateSceneRequest>>,
+) -> Result<web::Json<CreateSceneResponse>, ApiError> {
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+    let name = request.and_then(|request| request.into_inner().name);
+    let (id, name) = scene.duplicate(data.db(), name).await?;
+    Ok(web::Json(CreateSceneResponse { id, name }))
+}
+
+#[patch("")]
+async fn rename_scene(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    request: web::Json<RenameSceneRequest>,
+) -> Result<web::Json<RenameSceneResponse>, ApiError> {
+    let db = data.db();
+
+    // Update the scene name in the database
+    let scene_model = SceneEntity::find_by_id(path.scene_id)
+        .one(db)
+        .await?
+        .ok_or(SceneError::SceneNotFound(path.scene_id))?;
+    let mut active_model = scene_model.into_active_model();
+    active_model.name = Set(request.name.clone());
+    let updated_scene = active_model.update(db).await?;
+
+    Ok(web::Json(RenameSceneResponse {
+        id: updated_scene.id,
+        name: updated_scene.name,
+    }))
+}
+
+/// Options of the plots of a scene, which plot requests use unless their query says
+/// otherwise. Options that are not set have their default values.
+#[get("/options")]
+async fn get_scene_options(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+) -> Result<web::Json<StoredOptions>, ApiError> {
+    let scene_model = SceneEntity::find_by_id(path.scene_id)
+        .one(data.db())
+        .await?
+        .ok_or(SceneError::SceneNotFound(path.scene_id))?;
+    Ok(web::Json(scene_model.get_options()?))
+}
+
+/// Replaces the options of the plots of a scene, after checking them
+#[put("/options")]
+async fn update_scene_options(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    options: web::Json<StoredOptions>,
+) -> Result<web::Json<StoredOptions>, ApiError> {
+    let options = options.into_inner();
+    options.to_scene_options()?;
+    SceneModel::update_options(data.db(), path.scene_id, &options).await?;
+    Ok(web::Json(options))
+}
+
+/// Scenes of the user, or the scenes without an owner for anonymous requests
+#[get("/scenes")]
+async fn get_scenes(
+    data: web::Data<AppState>,
+    user: Option<web::ReqData<UserModel>>,
+) -> Result<web::Json<Vec<SceneInfo>>, ApiError> {
+    let owner = match user {
+        Some(user) => SceneColumn::UserId.eq(user.id),
+        None => SceneColumn::UserId.is_null(),
+    };
+    let scenes = SceneEntity::find()
+        .filter(owner)
+        .order_by_asc(SceneColumn::CreatedAt)
+        .all(data.db())
+        .await?;
+    let scene_infos: Vec<SceneInfo> = scenes
+        .into_iter()
+        .map(|s| SceneInfo {
+            id: s.id,
+            name: s.name,
+            created_at: s.created_at,
+        })
+        .collect();
+    Ok(web::Json(scene_infos))
+}
+
+/// Scenes of all users with their owners, for admins
+#[get("/admin/scenes")]
+async fn get_all_scenes(
+    data: web::Data<AppState>,
+    user: Option<web::ReqData<UserModel>>,
+) -> Result<web::Json<Vec<OwnedSceneInfo>>, ApiError> {
+    if !auth::is_admin(&user) {
+        return Err(ApiError::Forbidden(
+            "Only admins can list all scenes".to_string(),
+        ));
+    }
+    let scenes = SceneEntity::find()
+        .order_by_asc(SceneColumn::CreatedAt)
+        .all(data.db())
+        .await?;
+    let owners: HashMap<i32, String> = UserEntity::find()
+        .all(data.db())
+        .await?
+        .into_iter()
+        .map(|user| (user.id, user.name))
+        .collect();
+    Ok(web::Json(
+        scenes
+            .into_iter()
+            .map(|s| OwnedSceneInfo {
+                id: s.id,
+                name: s.name,
+                created_at: s.created_at,
+                owner: s.user_id.and_then(|id| owners.get(&id).cloned()),
+            })
+            .collect(),
+    ))
+}
+
+#[get("/initial")]
+async fn get_initial_values(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    query: web::Query<ExpressionsQuery>,
+) -> Result<web::Json<InitialValuesResponse>, ApiError> {
+    let expressions = query.decode()?;
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+
+    let values = scene
+        .evaluate_initial_values(&expressions)
+        .map_err(|e| ApiError::Internal(format!("Failed to evaluate initial values: {}", e)))?;
+    Ok(web::Json(InitialValuesResponse { values }))
+}
+
+/// Degrees of freedom of the constraints of a scene at the initial values, to tell
+/// why a locus is a point or fills the plane
+#[get("/dof")]
+async fn get_degrees_of_freedom(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+) -> Result<web::Json<DegreesOfFreedom>, ApiError> {
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+    Ok(web::Json(scene.degrees_of_freedom()?))
+}
+
+/// Positions of the points of a scene along its one-dimensional motion, e.g. a free
+/// point on a circle, for the frontend to animate the construction with its loci
+#[get("/animate")]
+async fn animate_scene(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    query: web::Query<AnimateQuery>,
+) -> Result<web::Json<Animation>, ApiError> {
+    let steps = query.steps;
+    if !(1..=MAX_ANIMATION_STEPS).contains(&steps) {
+        return Err(ApiError::BadRequest(format!(
+            "Steps must be between 1 and {}",
+            MAX_ANIMATION_STEPS
+        )));
+    }
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+    let animation = web::block(move || scene.animate(steps))
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))??;
+    animation.map(web::Json).ok_or_else(|| {
+        ApiError::BadRequest(
+            "The scene can't be animated: it has no motion with one degree of freedom".to_string(),
+        )
+    })
+}
+
+#[get("/validate")]
+async fn validate_expressions(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    query: web::Query<ExpressionsQuery>,
+) -> Result<web::Json<ValidationResponse>, ApiError> {
+    let expressions = query.decode()?;
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+
+    // Validate each expression and collect all errors
+    let mut all_errors = Vec::new();
+    for (index, expression) in expressions.iter().enumerate() {
+        let errors = scene.validate_expression(expression.clone());
+        for error in errors {
+            all_errors.push(format!("Expression {}: {}", index + 1, error));
+        }
+    }
+
+    Ok(web::Json(ValidationResponse { errors: all_errors }))
+}
+
+#[post("/validate-expression")]
+async fn validate_expression(
+    data: web::Data<AppState>,
+    path: web::Path<ScenePath>,
+    request: web::Json<ValidateExpressionRequest>,
+) -> Result<web::Json<ValidateExpressionResponse>, ApiError> {
+    let scene = data
+        .load_scene(path.scene_id, SceneOptions::default())
+        .await?;
+
+    let response = match scene.check_expression(&request.expression, request.expected_type) {
+        Ok(expression_type) => ValidateExpressionResponse {
+            valid: true,
+            expression_type: Some(expression_type),
+            diagnostics: Vec::new(),
+        },
+        Err(diagnostics) => ValidateExpressionResponse {
+            valid: false,
+            expression_type: None,
+            diagnostics,
+        },
+    };
+    Ok(web::Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_expressions() {
+        let query = ExpressionsQuery {
+            json: URL_SAFE_NO_PAD.encode(r#"["d(A, B)", "1 + 2"]"#),
+        };
+        assert_eq!(query.decode().unwrap(), vec!["d(A, B)", "1 + 2"]);
+
+        let query = ExpressionsQuery {
+            json: "not base64!".to_string(),
+        };
+        assert!(matches!(query.decode(), Err(ApiError::BadRequest(_))));
+
+        let query = ExpressionsQuery {
+            json: URL_SAFE_NO_PAD.encode(r#"{"a": 1}"#),
+        };
+        assert!(matches!(query.decode(), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_validate_expression_request() {
+        let request: ValidateExpressionRequest =
+            serde_json::from_str(r#"{"expression": "d(A, B)", "expected_type": "scalar"}"#)
+                .unwrap();
+        assert_eq!(request.expected_type, Some(ExpressionType::Scalar));
+        let request: ValidateExpressionRequest =
+            serde_json::from_str(r#"{"expression": "A"}"#).unwrap();
+        assert_eq!(request.expected_type, None);
+
+        let response = ValidateExpressionResponse {
+            valid: true,
+            expression_type: Some(ExpressionType::Point),
+            diagnostics: Vec::new(),
+        };
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({"valid": true, "type": "point", "diagnostics": []})
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use log::info;
+use std::path::Path;
+
+use crate::poly::{ParseError, Poly, PolyConversion, PolyFormat, RatFunc, Var};
+use crate::poly_draw::{PlotStyle, XYPolyDraw};
+use crate::replay;
+use crate::scene::{LocusCurve, Plot, Scene, SceneOptions, View};
+use crate::scene_utils::SceneUtils;
+use crate::trace::{self, TraceStep};
+
+/// Where and how the curve found by `run_elimination` is drawn
+pub struct PlotOutput<'a> {
+    pub path: &'a Path,
+    pub view: View,
+    pub size: u32,
+}
+
+/// Reads a polynomial system, one equation per line. `lhs = rhs` stands for
+/// lhs - rhs = 0; blank lines and lines starting with # are skipped. Equations with
+/// division are multiplied by their denominators.
+pub fn parse_system(text: &str) -> Result<Vec<Poly>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let error = |e: ParseError| format!("Invalid equation {}: {}", line, e);
+            match line.split_once('=') {
+                Some((lhs, rhs)) => {
+                    let lhs = RatFunc::parse(lhs).map_err(error)?;
+                    let rhs = RatFunc::parse(rhs).map_err(error)?;
+                    Ok((&lhs - &rhs).into_constraint())
+                }
+                None => Poly::parse_equation(line).map_err(error),
+            }
+        })
+        .collect()
+}
+
+/// Reads the order in which the variables were eliminated from a trace written by
+/// `run_elimination`, to replay it
+pub fn read_elimination_order(path: &Path) -> Result<Vec<Var>, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let steps: Vec<TraceStep> = serde_json::from_str(&text)
+        .map_err(|e| format!("Invalid trace in {}: {}", path.display(), e))?;
+    Ok(replay::elimination_order(&steps)?)
+}
+
+/// Eliminates all variables except x and y from the system in `input` like a locus
+/// of a scene, printing the curve equation and its factors, and optionally plotting it.
+/// With `trace_path`, the steps of the elimination are written there as JSON, also
+/// if it fails. Runs without the web server and the database.
+pub fn run_elimination(
+    input: &Path,
+    x: &str,
+    y: &str,
+    options: SceneOptions,
+    plot_output: Option<PlotOutput>,
+    trace_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(input)
+        .map_err(|e| format!("Failed to read {}: {}", input.display(), e))?;
+    let equations: Vec<String> = parse_system(&text)?
+        .iter()
+        .map(|poly| poly.to_string())
+        .collect();
+    if equations.is_empty() {
+        return Err(format!("No equations in {}", input.display()).into());
+    }
+    println!("Equations:");
+    for equation in &equations {
+        println!("  {} = 0", equation);
+    }
+
+    let plot = Plot {
+        name: "curve".to_string(),
+        x: x.to_string(),
+        y: y.to_string(),
+    };
+    let (x_var, y_var) = SceneUtils::parse_plot_vars(&plot)?;
+    let (curve, steps) = trace::with_trace(|| {
+        SceneUtils::get_curve_equation_and_factors(
+            equations.iter().map(String::as_str).collect(),
+            &plot,
+            options.clone(),
+        )
+    });
+    if let Some(trace_path) = trace_path {
+        std::fs::write(trace_path, serde_json::to_string_pretty(&steps)?)?;
+        println!(
+            "Trace of {} steps written to {}",
+            steps.len(),
+            trace_path.display()
+        );
+    }
+    let curve = curve?;
+    println!(
+        "Curve equation: {} = 0",
+        PolyFormat::new().format(&curve.curve_equation)
+    );
+    for factor in &curve.factors {
+        println!("  factor: {}", factor.as_formatted_equation(x_var, y_var));
+    }
+    for warning in &curve.warnings {
+        println!("  warning: {}", warning);
+    }
+
+    if let Some(PlotOutput { path, view, size }) = plot_output {
+        let mut scene = Scene::new(0, options);
+        scene.view = view;
+        let locus_curve = LocusCurve {
+            x_var,
+            y_var,
+            curve,
+        };
+        let plot_data = scene.plot_locus_curve(&plot.name, &locus_curve, size, size)?;
+        let png = XYPolyDraw::render_points_png(
+            &plot_data.points,
+            size,
+            size,
+            &scene.view.plot_view(size, size),
+            &PlotStyle::default(),
+        )?;
+        std::fs::write(path, &png)?;
+        info!("Wrote {} bytes to {}", png.len(), path.display());
+        println!("Plot written to {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_system() {
+        let text = "# midpoint of (c, 0) and a point on the unit circle\n\
+                    d^2 + e^2 - 1\n\
+                    \n\
+                    2*a = c + d\n\
+                    2*b - e\n\
+                    c/(a - 1) = 2/d\n";
+        let expected = ["d^2 + e^2 - 1", "2*a - c - d", "2*b - e", "c*d - 2*a + 2"]
+            .map(|equation| Poly::new(equation).unwrap());
+        assert_eq!(parse_system(text), Ok(expected.to_vec()));
+        assert_eq!(parse_system("# nothing\n\n"), Ok(vec![]));
+        assert!(parse_system("a^2 = (b").is_err());
+    }
+}
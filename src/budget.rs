@@ -0,0 +1,248 @@
+//! Limits on the size of a computation, protecting a server from scenes whose
+//! elimination would blow up. `with_budget` installs limits on the current thread;
+//! polynomial products and the elimination steps are checked against them, and the
+//! first limit hit is reported like a coefficient overflow in `detect_overflow`.
+
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::sync::RwLock;
+
+use crate::poly::Poly;
+
+/// Limits of a computation; None leaves a quantity unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Budget {
+    /// Largest total degree of a polynomial
+    pub max_total_degree: Option<u32>,
+    /// Largest number of terms of a polynomial
+    pub max_terms: Option<usize>,
+    /// Largest number of pseudo-remainder steps of an elimination
+    pub max_elimination_steps: Option<usize>,
+}
+
+impl Budget {
+    /// The tighter of the limits of self and other, for each quantity
+    pub fn within(self, other: Budget) -> Budget {
+        fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Budget {
+            max_total_degree: min(self.max_total_degree, other.max_total_degree),
+            max_terms: min(self.max_terms, other.max_terms),
+            max_elimination_steps: min(self.max_elimination_steps, other.max_elimination_steps),
+        }
+    }
+}
+
+/// A quantity limited by a `Budget`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    TotalDegree,
+    Terms,
+    EliminationSteps,
+}
+
+impl fmt::Display for BudgetLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetLimit::TotalDegree => write!(f, "total degree"),
+            BudgetLimit::Terms => write!(f, "number of terms"),
+            BudgetLimit::EliminationSteps => write!(f, "number of elimination steps"),
+        }
+    }
+}
+
+/// The first limit a computation hit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetExceeded {
+    pub limit: BudgetLimit,
+    pub max: usize,
+    /// The value that exceeded the limit
+    pub value: usize,
+    /// What was computed, e.g. "a product" or "the elimination of c"
+    pub context: String,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The {} of {} is {}, above the limit of {}",
+            self.limit, self.context, self.value, self.max
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+const UNLIMITED: Budget = Budget {
+    max_total_degree: None,
+    max_terms: None,
+    max_elimination_steps: None,
+};
+
+// Limits applying to every computation, set by the server's configuration
+static GLOBAL_BUDGET: RwLock<Budget> = RwLock::new(UNLIMITED);
+
+pub fn set_global_budget(budget: Budget) {
+    *GLOBAL_BUDGET.write().unwrap_or_else(|e| e.into_inner()) = budget;
+}
+
+pub fn global_budget() -> Budget {
+    *GLOBAL_BUDGET.read().unwrap_or_else(|e| e.into_inner())
+}
+
+thread_local! {
+    static CURRENT_BUDGET: Cell<Budget> = const { Cell::new(UNLIMITED) };
+    static ELIMINATION_STEPS: Cell<usize> = const { Cell::new(0) };
+    static EXCEEDED: RefCell<Option<BudgetExceeded>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` within `budget` and the global budget, failing with the first limit hit
+/// on this thread meanwhile, since the computation was then cut short
+pub fn with_budget<R>(budget: Budget, f: impl FnOnce() -> R) -> Result<R, BudgetExceeded> {
+    let budget = budget.within(global_budget());
+    let previous_budget = CURRENT_BUDGET.with(|current| current.replace(budget));
+    let previous_steps = ELIMINATION_STEPS.with(|steps| steps.replace(0));
+    let previous_exceeded = EXCEEDED.with(|exceeded| exceeded.take());
+    let result = f();
+    CURRENT_BUDGET.with(|current| current.set(previous_budget));
+    ELIMINATION_STEPS.with(|steps| steps.set(previous_steps));
+    match EXCEEDED.with(|exceeded| exceeded.replace(previous_exceeded)) {
+        Some(exceeded) => Err(exceeded),
+        None => Ok(result),
+    }
+}
+
+/// The first limit hit within the innermost `with_budget`, if any
+pub fn exceeded() -> Option<BudgetExceeded> {
+    EXCEEDED.with(|exceeded| exceeded.borrow().clone())
+}
+
+pub fn is_exceeded() -> bool {
+    EXCEEDED.with(|exceeded| exceeded.borrow().is_some())
+}
+
+fn record(limit: BudgetLimit, max: usize, value: usize, context: impl FnOnce() -> String) {
+    EXCEEDED.with(|exceeded| {
+        let mut exceeded = exceeded.borrow_mut();
+        if exceeded.is_none() {
+            *exceeded = Some(BudgetExceeded {
+                limit,
+                max,
+                value,
+                context: context(),
+            });
+        }
+    });
+}
+
+/// Checks the size of a polynomial computed on this thread. `context` is only
+/// evaluated if a limit is exceeded.
+pub fn check_poly(poly: &Poly, context: impl FnOnce() -> String) {
+    let budget = CURRENT_BUDGET.with(Cell::get);
+    if (budget.max_terms.is_none() && budget.max_total_degree.is_none()) || is_exceeded() {
+        return;
+    }
+    let (terms, total_degree) = size(poly);
+    if let Some(max) = budget.max_terms.filter(|&max| terms > max) {
+        record(BudgetLimit::Terms, max, terms, context);
+    } else if let Some(max) = budget.max_total_degree.filter(|&max| total_degree > max) {
+        record(
+            BudgetLimit::TotalDegree,
+            max as usize,
+            total_degree as usize,
+            context,
+        );
+    }
+}
+
+/// Counts a pseudo-remainder step of an elimination
+pub fn count_elimination_step(context: impl FnOnce() -> String) {
+    let steps = ELIMINATION_STEPS.with(|steps| {
+        steps.set(steps.get() + 1);
+        steps.get()
+    });
+    let budget = CURRENT_BUDGET.with(Cell::get);
+    if let Some(max) = budget.max_elimination_steps.filter(|&max| steps > max) {
+        record(BudgetLimit::EliminationSteps, max, steps, context);
+    }
+}
+
+/// The number of terms and the total degree of a polynomial, without listing its terms
+fn size(poly: &Poly) -> (usize, u32) {
+    match poly {
+        Poly::Constant(0) => (0, 0),
+        Poly::Constant(_) => (1, 0),
+        Poly::Nested(_, polys) => polys.iter().enumerate().fold(
+            (0, 0),
+            |(terms, degree), (power, coefficient)| match size(coefficient) {
+                (0, _) => (terms, degree),
+                (t, d) => (terms + t, degree.max(d + power as u32)),
+            },
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget() {
+        let poly = Poly::new("a^3*b + a*b - 2").unwrap();
+        assert_eq!(size(&poly), (3, 4));
+        assert_eq!(size(&poly), (poly.to_terms().len(), poly.total_degree()));
+
+        let budget = Budget {
+            max_total_degree: Some(3),
+            max_terms: Some(5),
+            ..Budget::default()
+        };
+        assert_eq!(
+            with_budget(budget, || check_poly(
+                &Poly::new("a*b").unwrap(),
+                String::new
+            )),
+            Ok(())
+        );
+        let exceeded = with_budget(budget, || {
+            check_poly(&poly, || "a product".to_string());
+            // Only the first limit hit is reported
+            check_poly(&Poly::new("a + b + c + d + e + f").unwrap(), String::new);
+        })
+        .unwrap_err();
+        assert_eq!(exceeded.limit, BudgetLimit::TotalDegree);
+        assert_eq!((exceeded.max, exceeded.value), (3, 4));
+        assert_eq!(
+            exceeded.to_string(),
+            "The total degree of a product is 4, above the limit of 3"
+        );
+        assert!(!is_exceeded());
+
+        let steps = Budget {
+            max_elimination_steps: Some(2),
+            ..Budget::default()
+        };
+        let exceeded = with_budget(steps, || {
+            for _ in 0..3 {
+                count_elimination_step(|| "the elimination of c".to_string());
+            }
+        })
+        .unwrap_err();
+        assert_eq!(exceeded.limit, BudgetLimit::EliminationSteps);
+        assert_eq!(
+            budget.within(steps),
+            Budget {
+                max_total_degree: Some(3),
+                max_terms: Some(5),
+                max_elimination_steps: Some(2),
+            }
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Cooperative cancellation of computations. A request installs a token on the thread
+//! that computes for it with `with_cancellation`; long loops, like the elimination of a
+//! variable, call `is_cancelled` between steps and Pari/GP tasks stop their process.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag telling the computations started on behalf of a request to give up
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Returns a guard that cancels the token when dropped, e.g. together with
+    /// the future of an HTTP handler whose client disconnected
+    pub fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop {
+            token: self.clone(),
+            armed: true,
+        }
+    }
+}
+
+pub struct CancelOnDrop {
+    token: CancellationToken,
+    /// Cleared by `disarm`
+    armed: bool,
+}
+
+impl CancelOnDrop {
+    /// Leaves the token as it is when the guard is dropped, e.g. once the response has
+    /// been produced
+    pub fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if self.armed {
+            self.token.cancel();
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT_CANCELLATION: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `token` as the cancellation token of the eliminations and of the
+/// Pari/GP tasks started on this thread
+pub fn with_cancellation<R>(token: CancellationToken, f: impl FnOnce() -> R) -> R {
+    let _restore =
+        RestoreCancellation(CURRENT_CANCELLATION.with(|current| current.replace(Some(token))));
+    f()
+}
+
+/// Puts the previous token back when `with_cancellation` returns, also if `f` panics
+struct RestoreCancellation(Option<CancellationToken>);
+
+impl Drop for RestoreCancellation {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT_CANCELLATION.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+pub fn current_cancellation() -> Option<CancellationToken> {
+    CURRENT_CANCELLATION.with(|current| current.borrow().clone())
+}
+
+/// Whether the token installed on this thread, if any, was cancelled
+pub fn is_cancelled() -> bool {
+    CURRENT_CANCELLATION.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_cancellation() {
+        assert!(!is_cancelled());
+        let token = CancellationToken::new();
+        with_cancellation(token.clone(), || {
+            assert!(!is_cancelled());
+            drop(token.cancel_on_drop());
+            assert!(is_cancelled());
+            // The innermost token counts
+            with_cancellation(CancellationToken::new(), || assert!(!is_cancelled()));
+            assert!(is_cancelled());
+        });
+        assert!(!is_cancelled());
+        assert!(current_cancellation().is_none());
+
+        // A disarmed guard neither cancels the token nor keeps it alive
+        let token = CancellationToken::new();
+        token.cancel_on_drop().disarm();
+        assert!(!token.is_cancelled());
+        assert_eq!(Arc::strong_count(&token.0), 1);
+    }
+
+    #[test]
+    fn test_with_cancellation_restores_after_panic() {
+        let outer = CancellationToken::new();
+        with_cancellation(outer.clone(), || {
+            let result = std::panic::catch_unwind(|| {
+                with_cancellation(CancellationToken::new(), || panic!("computation failed"))
+            });
+            assert!(result.is_err());
+            assert!(Arc::ptr_eq(&current_cancellation().unwrap().0, &outer.0));
+        });
+        assert!(current_cancellation().is_none());
+    }
+}
@@ -0,0 +1,156 @@
+//! Computer algebra systems used for factoring and polynomial gcds.
+//!
+//! The rest of the code only talks to the `CasBackend` selected with `set_backend`.
+//! Pari/GP is the default and keeps its pool of processes and cache of outputs.
+//! Singular is started once per task and covers the same operations, so the server
+//! also works on machines without gp; only the genus and the rational parametrization
+//! of curves still need Pari/GP. Without the `pari` feature (e.g. in the browser) the
+//! native backend is used, which doesn't factor.
+
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use crate::determinant;
+use crate::groebner;
+use crate::modular_determinant;
+use crate::poly::{Poly, PolyOperations, Var};
+
+#[cfg(feature = "pari")]
+mod external;
+#[cfg(feature = "pari")]
+pub use external::{PariBackend, SingularBackend};
+
+// Backend used by Poly::factor and Poly::reduce_by_gcd
+static BACKEND: RwLock<Option<Arc<dyn CasBackend>>> = RwLock::new(None);
+
+/// Operations delegated to a computer algebra system
+pub trait CasBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Factors of the polynomial over the integers, with their multiplicities. The
+    /// product of the factors equals the polynomial up to sign.
+    fn factor(&self, poly: &Poly) -> Result<Vec<(Poly, u32)>, String>;
+
+    /// Greatest common divisor, up to sign
+    fn gcd(&self, poly1: &Poly, poly2: &Poly) -> Result<Poly, String>;
+
+    /// Resultant of the polynomials with respect to v
+    fn resultant(&self, poly1: &Poly, poly2: &Poly, v: Var) -> Result<Poly, String>;
+
+    /// Generators of the ideal of `polys` intersected with the polynomials in
+    /// x_var and y_var, the smallest one first
+    fn eliminate(&self, polys: &[Rc<Poly>], x_var: Var, y_var: Var) -> Result<Vec<Poly>, String>;
+}
+
+/// Select the backend, replacing any previously selected one
+pub fn set_backend(backend: Arc<dyn CasBackend>) {
+    *BACKEND.write().unwrap_or_else(|e| e.into_inner()) = Some(backend);
+}
+
+/// The selected backend; unless another one was selected, Pari/GP with the `pari`
+/// feature and the native backend without it
+pub fn backend() -> Arc<dyn CasBackend> {
+    BACKEND
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(default_backend)
+}
+
+#[cfg(feature = "pari")]
+fn default_backend() -> Arc<dyn CasBackend> {
+    Arc::new(PariBackend)
+}
+
+#[cfg(not(feature = "pari"))]
+fn default_backend() -> Arc<dyn CasBackend> {
+    Arc::new(NativeBackend)
+}
+
+/// The algorithms of this crate, without an external process. Polynomials are not
+/// factored: every polynomial is its only factor.
+pub struct NativeBackend;
+
+impl CasBackend for NativeBackend {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn factor(&self, poly: &Poly) -> Result<Vec<(Poly, u32)>, String> {
+        Ok(vec![(poly.clone(), 1)])
+    }
+
+    fn gcd(&self, poly1: &Poly, poly2: &Poly) -> Result<Poly, String> {
+        Ok(Poly::gcd(poly1, poly2))
+    }
+
+    /// Determinant of the Sylvester matrix
+    fn resultant(&self, poly1: &Poly, poly2: &Poly, v: Var) -> Result<Poly, String> {
+        let coefficients = |poly: &Poly| -> Vec<Rc<Poly>> {
+            Rc::new(poly.clone())
+                .decompose(v)
+                .into_iter()
+                .map(|c| {
+                    let mut c = (*c).clone();
+                    c.cleanup();
+                    Rc::new(c)
+                })
+                .collect()
+        };
+        let (coefficients1, coefficients2) = (coefficients(poly1), coefficients(poly2));
+        let (m, n) = (coefficients1.len() - 1, coefficients2.len() - 1);
+        if m + n == 0 {
+            return Ok(Poly::Constant(1));
+        }
+
+        // n shifted rows of poly1, then m shifted rows of poly2, highest powers first
+        let zero = Rc::new(Poly::Constant(0));
+        let mut matrix = Vec::with_capacity(m + n);
+        for (coefficients, degree, count) in [(&coefficients1, m, n), (&coefficients2, n, m)] {
+            for shift in 0..count {
+                let row = (0..m + n)
+                    .map(|j| match j.checked_sub(shift) {
+                        Some(k) if k <= degree => coefficients[degree - k].clone(),
+                        _ => zero.clone(),
+                    })
+                    .collect();
+                matrix.push(row);
+            }
+        }
+        modular_determinant::determinant(&matrix)
+            .or_else(|e| determinant::interpolate_determinant(&matrix).ok_or(e))
+    }
+
+    fn eliminate(&self, polys: &[Rc<Poly>], x_var: Var, y_var: Var) -> Result<Vec<Poly>, String> {
+        groebner::eliminate(polys, x_var, y_var)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_backend() {
+        let backend = NativeBackend;
+        let circle = Poly::new("a^2 + b^2 - 1").unwrap();
+        let line = Poly::new("a - b").unwrap();
+        // The circle at b = a
+        assert_eq!(
+            backend.resultant(&circle, &line, 1),
+            Ok(Poly::new("2*a^2 - 1").unwrap())
+        );
+        // A polynomial of degree 0 in v: 3^2
+        assert_eq!(
+            backend.resultant(&Poly::new("3").unwrap(), &circle, 1),
+            Ok(Poly::Constant(9))
+        );
+
+        let poly1 = Poly::new("a^2 - b^2").unwrap();
+        let poly2 = Poly::new("a^2 + 2*a*b + b^2").unwrap();
+        let gcd = backend.gcd(&poly1, &poly2).unwrap();
+        assert!(gcd == Poly::new("a + b").unwrap() || gcd == Poly::new("-a - b").unwrap());
+
+        assert_eq!(backend.factor(&poly1), Ok(vec![(poly1.clone(), 1)]));
+    }
+}
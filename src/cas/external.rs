@@ -0,0 +1,422 @@
+//! Backends running Pari/GP and Singular as external processes
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::CasBackend;
+use crate::gp_pari_service;
+use crate::groebner;
+use crate::poly::{Poly, PolyConversion, PolyOperations, Var, VarSet};
+
+/// How often a running Singular process is checked for completion and cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Pari/GP, run on the shared process pool
+pub struct PariBackend;
+
+impl PariBackend {
+    fn run(task: String) -> Result<Vec<String>, String> {
+        gp_pari_service::get_gp_pari_pool()?.run_cached_task(task)
+    }
+
+    fn parse_single_poly(output_lines: &[String]) -> Result<Poly, String> {
+        match output_lines {
+            [line] => Poly::from_poly_expression(line.trim()),
+            _ => Err(format!(
+                "Expected 1 line of output from Pari/GP. Output: {:?}",
+                output_lines
+            )),
+        }
+    }
+
+    /// Parses the output of `Vec(factor(..))`: the factors as "[<poly1>,..,<polyN>]~",
+    /// then their multiplicities as "[<degree1>,..,<degreeN>]~"
+    fn parse_factorization(output_lines: &[String]) -> Result<Vec<(Poly, u32)>, String> {
+        if output_lines.len() < 2 {
+            return Err(format!(
+                "Expected at least 2 lines of output from Pari/GP. Output: {:?}",
+                output_lines
+            ));
+        }
+        let factor_strings = Self::parse_column(output_lines[0].trim(), "factors")?;
+        let degree_strings = Self::parse_column(output_lines[1].trim(), "degrees")?;
+        if factor_strings.len() != degree_strings.len() {
+            return Err(format!(
+                "Mismatch between factors ({}) and degrees ({})",
+                factor_strings.len(),
+                degree_strings.len()
+            ));
+        }
+
+        factor_strings
+            .into_iter()
+            .zip(degree_strings)
+            .map(|(factor_str, degree_str)| {
+                let factor = Poly::from_poly_expression(factor_str)
+                    .map_err(|e| format!("Failed to parse factor '{}': {}", factor_str, e))?;
+                let degree = degree_str
+                    .parse::<u32>()
+                    .map_err(|e| format!("Failed to parse degree '{}': {}", degree_str, e))?;
+                Ok((factor, degree))
+            })
+            .collect()
+    }
+
+    fn parse_column<'a>(line: &'a str, what: &str) -> Result<Vec<&'a str>, String> {
+        if !line.starts_with('[') || !line.ends_with("]~") {
+            return Err(format!("Invalid {} line format: {}", what, line));
+        }
+        // Remove "[...]~"
+        Ok(line[1..line.len() - 2]
+            .split(',')
+            .map(|s| s.trim())
+            .collect())
+    }
+}
+
+impl CasBackend for PariBackend {
+    fn name(&self) -> &'static str {
+        "Pari/GP"
+    }
+
+    fn factor(&self, poly: &Poly) -> Result<Vec<(Poly, u32)>, String> {
+        let task = format!(
+            "{{expr = Vec(factor({:#}));print(expr[1]);print(expr[2]);print(\"Done\")}}",
+            poly
+        );
+        Self::parse_factorization(&Self::run(task)?)
+    }
+
+    fn gcd(&self, poly1: &Poly, poly2: &Poly) -> Result<Poly, String> {
+        let task = format!("{{print(gcd([{:#}, {:#}]));print(\"Done\")}}", poly1, poly2);
+        Self::parse_single_poly(&Self::run(task)?)
+    }
+
+    fn resultant(&self, poly1: &Poly, poly2: &Poly, v: Var) -> Result<Poly, String> {
+        let task = format!(
+            "{{print(polresultant({:#}, {:#}, {}));print(\"Done\")}}",
+            poly1,
+            poly2,
+            Poly::var_to_string(v)
+        );
+        Self::parse_single_poly(&Self::run(task)?)
+    }
+
+    /// Pari/GP has no Groebner bases. Two equations in one more variable are
+    /// eliminated by their resultant when the leading coefficients have no common
+    /// factor: the resultant then vanishes on the same curve as the elimination ideal,
+    /// apart from finitely many points. Everything else uses `groebner::eliminate`.
+    fn eliminate(&self, polys: &[Rc<Poly>], x_var: Var, y_var: Var) -> Result<Vec<Poly>, String> {
+        let mut vars = VarSet::new();
+        for poly in polys {
+            poly.fill_in_variables(&mut vars);
+        }
+        vars.remove(x_var);
+        vars.remove(y_var);
+        if let ([poly1, poly2], Some(v), 1) = (polys, vars.first(), vars.len()) {
+            let leading = |poly: &Rc<Poly>| {
+                let mut coefficient = (*poly.decompose(v).pop().unwrap()).clone();
+                coefficient.cleanup();
+                coefficient
+            };
+            let gcd = self.gcd(&leading(poly1), &leading(poly2))?;
+            if matches!(gcd, Poly::Constant(_)) && poly1.has_var(v) && poly2.has_var(v) {
+                let resultant = self.resultant(poly1, poly2, v)?;
+                return Ok(vec![resultant]);
+            }
+        }
+        groebner::eliminate(polys, x_var, y_var)
+    }
+}
+
+/// Singular, started for every task
+pub struct SingularBackend {
+    executable_path: String,
+    /// Used when the request sets no timeout, see `gp_pari_service::with_task_limits`
+    task_timeout: Duration,
+}
+
+impl SingularBackend {
+    pub fn new(executable_path: String, task_timeout: Duration) -> Self {
+        Self {
+            executable_path,
+            task_timeout,
+        }
+    }
+
+    /// Script declaring a rational polynomial ring in the variables of `polys`, and
+    /// the polynomials as p1, p2, ..
+    fn script_header(polys: &[&Poly], extra_vars: &[Var]) -> String {
+        let mut vars = VarSet::new();
+        for poly in polys {
+            poly.fill_in_variables(&mut vars);
+        }
+        for &v in extra_vars {
+            vars.insert(v);
+        }
+        // Singular rings need at least one variable
+        if vars.is_empty() {
+            vars.insert(0);
+        }
+        let var_names: Vec<String> = vars.iter().map(Poly::var_to_string).collect();
+        let mut script = format!("ring r = 0, ({}), dp;\nshort = 0;\n", var_names.join(", "));
+        for (i, poly) in polys.iter().enumerate() {
+            script += &format!("poly p{} = {:#};\n", i + 1, poly);
+        }
+        script
+    }
+
+    /// Runs the script and returns the non-empty lines it printed
+    fn run(&self, mut script: String) -> Result<Vec<String>, String> {
+        script += "quit;\n";
+        let mut child = Command::new(&self.executable_path)
+            .args(["-q", "-t", "--no-rc"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start Singular ({}): {}", self.executable_path, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(script.as_bytes())
+                .map_err(|e| format!("Failed to write to Singular: {}", e))?;
+        }
+        // Read on a separate thread, so that a long output can't fill the pipe and block
+        let mut stdout = child.stdout.take().unwrap();
+        let reader = thread::spawn(move || {
+            let mut output = String::new();
+            stdout.read_to_string(&mut output).map(|_| output)
+        });
+
+        let timeout = gp_pari_service::current_limits()
+            .timeout
+            .unwrap_or(self.task_timeout);
+        let cancellation = gp_pari_service::current_cancellation();
+        let deadline = Instant::now() + timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break,
+                Ok(None) => {}
+                Err(e) => return Err(format!("Failed to wait for Singular: {}", e)),
+            }
+            let cancelled = cancellation.as_ref().is_some_and(|c| c.is_cancelled());
+            if cancelled || Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(if cancelled {
+                    "Singular task was cancelled".to_string()
+                } else {
+                    format!("Singular task timed out after {:?}", timeout)
+                });
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        let output = reader
+            .join()
+            .map_err(|_| "Failed to read the output of Singular".to_string())?
+            .map_err(|e| format!("Failed to read the output of Singular: {}", e))?;
+        Self::output_lines(&output)
+    }
+
+    /// Non-empty lines of the output, or the error messages Singular printed ("? ...")
+    fn output_lines(output: &str) -> Result<Vec<String>, String> {
+        let lines: Vec<String> = output
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let errors: Vec<&str> = lines
+            .iter()
+            .filter(|line| line.starts_with('?'))
+            .map(|line| line.as_str())
+            .collect();
+        if !errors.is_empty() {
+            return Err(format!("Singular error: {}", errors.join("; ")));
+        }
+        Ok(lines)
+    }
+
+    fn parse_single_poly(output_lines: &[String]) -> Result<Poly, String> {
+        match output_lines {
+            [line] => Poly::from_poly_expression(line),
+            _ => Err(format!(
+                "Expected 1 line of output from Singular. Output: {:?}",
+                output_lines
+            )),
+        }
+    }
+
+    /// Parses the factors and multiplicities printed on alternating lines. The constant
+    /// factor Singular always puts first is dropped when it is 1 or -1.
+    fn parse_factorization(output_lines: &[String]) -> Result<Vec<(Poly, u32)>, String> {
+        if output_lines.is_empty() || !output_lines.len().is_multiple_of(2) {
+            return Err(format!(
+                "Expected factors and multiplicities from Singular. Output: {:?}",
+                output_lines
+            ));
+        }
+        let mut factors = Vec::new();
+        for pair in output_lines.chunks(2) {
+            let factor = Poly::from_poly_expression(&pair[0])
+                .map_err(|e| format!("Failed to parse factor '{}': {}", pair[0], e))?;
+            let degree = pair[1]
+                .parse::<u32>()
+                .map_err(|e| format!("Failed to parse degree '{}': {}", pair[1], e))?;
+            if !matches!(factor, Poly::Constant(1) | Poly::Constant(-1)) {
+                factors.push((factor, degree));
+            }
+        }
+        Ok(factors)
+    }
+}
+
+impl CasBackend for SingularBackend {
+    fn name(&self) -> &'static str {
+        "Singular"
+    }
+
+    fn factor(&self, poly: &Poly) -> Result<Vec<(Poly, u32)>, String> {
+        let mut script = Self::script_header(&[poly], &[]);
+        script += "list l = factorize(p1);\nint i;\n";
+        script += "for (i = 1; i <= size(l[1]); i++) { print(l[1][i]); print(l[2][i]); }\n";
+        Self::parse_factorization(&self.run(script)?)
+    }
+
+    fn gcd(&self, poly1: &Poly, poly2: &Poly) -> Result<Poly, String> {
+        let mut script = Self::script_header(&[poly1, poly2], &[]);
+        script += "print(gcd(p1, p2));\n";
+        Self::parse_single_poly(&self.run(script)?)
+    }
+
+    fn resultant(&self, poly1: &Poly, poly2: &Poly, v: Var) -> Result<Poly, String> {
+        let mut script = Self::script_header(&[poly1, poly2], &[v]);
+        script += &format!("print(resultant(p1, p2, {}));\n", Poly::var_to_string(v));
+        Self::parse_single_poly(&self.run(script)?)
+    }
+
+    fn eliminate(&self, polys: &[Rc<Poly>], x_var: Var, y_var: Var) -> Result<Vec<Poly>, String> {
+        let poly_refs: Vec<&Poly> = polys.iter().map(|p| p.as_ref()).collect();
+        let mut script = Self::script_header(&poly_refs, &[x_var, y_var]);
+        let mut vars = VarSet::new();
+        for poly in polys {
+            poly.fill_in_variables(&mut vars);
+        }
+        vars.remove(x_var);
+        vars.remove(y_var);
+        let generators: Vec<String> = (1..=polys.len()).map(|i| format!("p{}", i)).collect();
+        script += &format!("ideal i = {};\n", generators.join(", "));
+        if vars.is_empty() {
+            script += "ideal e = std(i);\n";
+        } else {
+            let product: Vec<String> = vars.iter().map(Poly::var_to_string).collect();
+            script += &format!("ideal e = std(eliminate(i, {}));\n", product.join("*"));
+        }
+        script += "int k;\nfor (k = 1; k <= size(e); k++) { print(e[k]); }\n";
+
+        let mut result = self
+            .run(script)?
+            .iter()
+            .map(|line| Poly::from_poly_expression(line))
+            .collect::<Result<Vec<Poly>, String>>()?;
+        result.retain(|p| *p != Poly::Constant(0));
+        result.sort_by_key(|p| {
+            let terms = p.to_terms();
+            let degree = terms
+                .iter()
+                .map(|term| term.vars.iter().map(|(_, d)| d).sum::<u32>())
+                .max()
+                .unwrap_or(0);
+            (degree, terms.len())
+        });
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(output: &str) -> Vec<String> {
+        SingularBackend::output_lines(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse_pari_factorization() {
+        let output = vec!["[a - b, a + b]~".to_string(), "[2, 1]~".to_string()];
+        let factors = PariBackend::parse_factorization(&output).unwrap();
+        assert_eq!(
+            factors,
+            vec![
+                (Poly::new("a - b").unwrap(), 2),
+                (Poly::new("a + b").unwrap(), 1)
+            ]
+        );
+        assert!(PariBackend::parse_factorization(&["[a]~".to_string()]).is_err());
+        let mismatch = vec!["[a, b]~".to_string(), "[1]~".to_string()];
+        assert!(PariBackend::parse_factorization(&mismatch).is_err());
+
+        // Factors with the content pulled out as a fraction, as printed by gp
+        let output = vec![
+            "[1/6, a - b, 1/2*a^2 + (b - 1/3)*a + 1]~".to_string(),
+            "[1, 2, 1]~".to_string(),
+        ];
+        let factors = PariBackend::parse_factorization(&output).unwrap();
+        assert_eq!(
+            factors,
+            vec![
+                (Poly::Constant(1), 1),
+                (Poly::new("a - b").unwrap(), 2),
+                (Poly::new("3*a^2 + 6*a*b - 2*a + 6").unwrap(), 1)
+            ]
+        );
+        let gcd = PariBackend::parse_single_poly(&["-2/3*a*b + 1/3\n".to_string()]);
+        assert_eq!(gcd, Ok(Poly::new("-2*a*b + 1").unwrap()));
+    }
+
+    #[test]
+    fn test_singular_scripts_and_output() {
+        let poly1 = Poly::new("a^2*c - 2*b1").unwrap();
+        let poly2 = Poly::new("3").unwrap();
+        assert_eq!(
+            SingularBackend::script_header(&[&poly1, &poly2], &[1]),
+            "ring r = 0, (a, b, c, b1), dp;\nshort = 0;\npoly p1 = -2*b1 + c*a^2;\npoly p2 = 3;\n"
+        );
+        assert!(SingularBackend::script_header(&[&poly2], &[]).starts_with("ring r = 0, (a), dp;"));
+
+        let factors =
+            SingularBackend::parse_factorization(&lines("-1\n1\na^2-2*a*b+b^2\n1\n  a+b\n3\n"))
+                .unwrap();
+        assert_eq!(
+            factors,
+            vec![
+                (Poly::new("a^2 - 2*a*b + b^2").unwrap(), 1),
+                (Poly::new("a + b").unwrap(), 3)
+            ]
+        );
+        // The content of a polynomial is kept as a factor
+        let factors = SingularBackend::parse_factorization(&lines("2\n1\na+1\n1\n")).unwrap();
+        assert_eq!(factors[0], (Poly::Constant(2), 1));
+
+        assert!(
+            SingularBackend::output_lines("   ? `x` is undefined\n   ? error occurred\n")
+                .unwrap_err()
+                .contains("`x` is undefined")
+        );
+        assert!(SingularBackend::parse_single_poly(&lines("a\nb\n")).is_err());
+    }
+
+    #[test]
+    fn test_missing_singular_executable() {
+        let backend =
+            SingularBackend::new("/nonexistent/Singular".to_string(), Duration::from_secs(1));
+        let poly = Poly::new("a^2 - 1").unwrap();
+        assert!(backend
+            .factor(&poly)
+            .unwrap_err()
+            .contains("Failed to start"));
+    }
+}
@@ -0,0 +1,113 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_BIND: &str = "127.0.0.1:8080";
+pub const DEFAULT_DB_PATH: &str = "scenes.db";
+pub const DEFAULT_ALLOWED_ORIGIN: &str = "http://localhost:5174";
+/// Config file read from the working directory when no other one is given
+pub const DEFAULT_CONFIG_PATH: &str = "poly_algebra.json";
+
+/// Contents of the JSON config file; every setting is optional
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub bind: Option<String>,
+    pub db: Option<String>,
+    pub allowed_origins: Option<Vec<String>>,
+}
+
+impl ConfigFile {
+    pub fn parse(content: &str) -> Result<ConfigFile, String> {
+        serde_json::from_str(content).map_err(|e| format!("Invalid config file: {}", e))
+    }
+
+    /// Reads the config file at `path`. Without an explicit path the default file
+    /// is used if it exists.
+    pub fn load(path: Option<&str>) -> Result<ConfigFile, String> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => DEFAULT_CONFIG_PATH,
+            None => return Ok(ConfigFile::default()),
+        };
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path, e))?;
+        Self::parse(&content)
+    }
+}
+
+/// Server settings, taken from the command line or environment first, then from
+/// the config file, then from the defaults of the development setup
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub db_path: String,
+    pub allowed_origins: Vec<String>,
+}
+
+impl ServerConfig {
+    pub fn resolve(
+        bind: Option<String>,
+        db_path: Option<String>,
+        allowed_origins: Vec<String>,
+        file: ConfigFile,
+    ) -> ServerConfig {
+        let allowed_origins = if !allowed_origins.is_empty() {
+            allowed_origins
+        } else {
+            file.allowed_origins
+                .unwrap_or_else(|| vec![DEFAULT_ALLOWED_ORIGIN.to_string()])
+        };
+        ServerConfig {
+            bind: bind
+                .or(file.bind)
+                .unwrap_or_else(|| DEFAULT_BIND.to_string()),
+            db_path: db_path
+                .or(file.db)
+                .unwrap_or_else(|| DEFAULT_DB_PATH.to_string()),
+            allowed_origins,
+        }
+    }
+
+    /// Connection URL of the SQLite database, created if missing
+    pub fn database_url(&self) -> String {
+        format!("sqlite://{}?mode=rwc", self.db_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_server_config() {
+        let defaults = ServerConfig::resolve(None, None, vec![], ConfigFile::default());
+        assert_eq!(defaults.bind, DEFAULT_BIND);
+        assert_eq!(defaults.database_url(), "sqlite://scenes.db?mode=rwc");
+        assert_eq!(defaults.allowed_origins, vec![DEFAULT_ALLOWED_ORIGIN]);
+
+        let file = ConfigFile::parse(
+            r#"{"bind": "0.0.0.0:80", "db": "/var/lib/poly/scenes.db",
+                "allowed_origins": ["https://a.example", "https://b.example"]}"#,
+        )
+        .unwrap();
+        let config = ServerConfig::resolve(None, None, vec![], file.clone());
+        assert_eq!(config.bind, "0.0.0.0:80");
+        assert_eq!(config.db_path, "/var/lib/poly/scenes.db");
+        assert_eq!(config.allowed_origins.len(), 2);
+
+        // Command line values take precedence over the file
+        let config = ServerConfig::resolve(
+            Some("[::1]:9000".to_string()),
+            None,
+            vec!["https://c.example".to_string()],
+            file,
+        );
+        assert_eq!(config.bind, "[::1]:9000");
+        assert_eq!(config.db_path, "/var/lib/poly/scenes.db");
+        assert_eq!(config.allowed_origins, vec!["https://c.example"]);
+
+        assert!(ConfigFile::parse(r#"{"port": 80}"#).is_err());
+        assert_eq!(ConfigFile::parse("{}").unwrap(), ConfigFile::default());
+    }
+}
@@ -0,0 +1,1595 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gcd::Gcd;
+
+use serde::{Deserialize, Serialize};
+
+use crate::elimination::Elimination;
+use crate::fint::FInt;
+use crate::poly::{
+    detect_overflow, Poly, PolyConversion, PolyFormat, PolyOperations, Term, TermOrder, Var,
+};
+use crate::roots;
+use crate::scene::{CurveEquationAndFactors, LocusKind, View};
+use crate::variables::VariableRegistry;
+use crate::warning::{Warning, WarningCode};
+use crate::x_poly::XPoly;
+
+/// Total degree above which the singular points are not searched: the resultant of
+/// the partial derivatives has degree up to (d - 1)^2, too high for f64 root finding
+pub const MAX_SINGULAR_POINTS_DEGREE: u32 = 12;
+
+/// Total degree above which the inflection points are not searched: the resultant of F
+/// and the numerator of its curvature has degree up to d (3d - 4)
+pub const MAX_INFLECTION_POINTS_DEGREE: u32 = 6;
+
+/// Total degree above which the extent of a curve is not computed: the resultants of F
+/// and its partial derivatives have degree up to d (d - 1)
+pub const MAX_EXTENT_DEGREE: u32 = 10;
+
+/// Relative size below which a value counts as zero, compared to the sum of the
+/// absolute values of the terms it is made of
+const ZERO_TOLERANCE: f64 = 1e-6;
+
+/// Largest denominator of the coordinates of the points `lattice_points` searches for
+pub const MAX_LATTICE_DENOMINATOR: i64 = 1000;
+/// Most values of x that `lattice_points` tries, to bound the time it takes
+pub const MAX_LATTICE_COLUMNS: usize = 20_000;
+
+/// Kind of a singular point, from the sign of the Hessian determinant of the curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SingularPointKind {
+    /// Two real branches cross
+    Node,
+    /// The Hessian is degenerate: a cusp, a tacnode or a point of higher multiplicity
+    Cusp,
+    /// No real branch passes through the point
+    Isolated,
+}
+
+/// Point of the curve F(x, y) = 0 where F_x and F_y vanish as well, in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SingularPoint {
+    pub x: f64,
+    pub y: f64,
+    pub kind: SingularPointKind,
+}
+
+/// Degree and genus of one irreducible factor of a curve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactorAnalysis {
+    pub equation: String,
+    pub degree: u32,
+    /// Geometric genus, None if it could not be computed
+    pub genus: Option<u32>,
+}
+
+/// Point of the curve F(x, y) = 0 where its curvature vanishes, in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InflectionPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Curvature of one irreducible factor F(x, y) = 0 of a curve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactorCurvature {
+    /// The curve N = 0 for the numerator N of the curvature of the factor (see
+    /// `curvature_numerator`), which meets the factor at its inflection points
+    pub inflection_curve: String,
+    /// Inflection points in the view of the scene
+    pub inflection_points: Vec<InflectionPoint>,
+}
+
+/// Smallest rectangle containing the real points of a curve, in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+/// Extent of the real points of a curve
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CurveExtent {
+    /// The curve has no real points
+    Empty,
+    Bounded(BoundingBox),
+    /// The curve has real points at infinity
+    Unbounded,
+}
+
+/// Summary of a curve equation and its irreducible factors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurveAnalysis {
+    pub degree: u32,
+    pub irreducible_factors: usize,
+    pub factors: Vec<FactorAnalysis>,
+    pub warnings: Vec<Warning>,
+    /// The objects and coordinates the variables in the warnings stand for
+    pub variables: VariableRegistry,
+    /// None if it could not be computed
+    pub extent: Option<CurveExtent>,
+    /// A view showing the whole curve in a plot of the requested size, if it is bounded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_view: Option<View>,
+    /// Only computed on request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projective: Option<ProjectiveClosure>,
+    /// The curvature of each factor, in the order of `factors`; only computed on request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curvature: Option<Vec<FactorCurvature>>,
+    /// The equations of the eliminated variables of each system; only computed on request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub triangular_sets: Option<Vec<Vec<TriangularEquation>>>,
+}
+
+/// The equation a variable was eliminated with, as a polynomial in the variable whose
+/// coefficients only depend on the variables listed before it, x and y
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriangularEquation {
+    pub variable: String,
+    pub equation: String,
+}
+
+/// The triangular sets of a solved curve, with the variables eliminated last first, so
+/// that their values follow one after the other from a point (x, y) of the curve. None
+/// if the curve was not solved for, e.g. if it was cached.
+pub fn triangular_sets(curve: &CurveEquationAndFactors) -> Option<Vec<Vec<TriangularEquation>>> {
+    let sets = curve.triangular_sets.as_ref()?;
+    Some(
+        sets.iter()
+            .map(|set| {
+                set.iter()
+                    .rev()
+                    .map(|eliminated| {
+                        let variable = Poly::var_to_string(eliminated.var);
+                        TriangularEquation {
+                            equation: PolyFormat::new()
+                                .with_order(TermOrder::Lex)
+                                .with_var_name(eliminated.var, &variable)
+                                .format(&eliminated.equation),
+                            variable,
+                        }
+                    })
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+/// A real point at infinity [x : y : 0] of a curve, the direction of its asymptotes
+/// or parabolic branches, with x^2 + y^2 = 1 and the first non-zero coordinate positive
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PointAtInfinity {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// The closure of a curve in the projective plane with coordinates [x : y : z], where
+/// the affine plane is z = 1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectiveClosure {
+    /// The homogenized equation, like "x^2 + y^2 - z^2 = 0"
+    pub equation: String,
+    pub points_at_infinity: Vec<PointAtInfinity>,
+}
+
+/// Reports the degrees and genera of a solved curve. The warnings of the curve are
+/// kept, and factors whose genus could not be computed add a warning of their own.
+pub fn analyze_curve(
+    curve: &CurveEquationAndFactors,
+    x_var: Var,
+    y_var: Var,
+    variables: VariableRegistry,
+) -> CurveAnalysis {
+    let mut warnings = curve.warnings.clone();
+    let factors = curve
+        .factors
+        .iter()
+        .map(|factor| {
+            let genus = match get_genus(factor, x_var, y_var) {
+                Ok(genus) => Some(genus),
+                Err(e) => {
+                    warnings.push(Warning::new(
+                        WarningCode::GenusUnknown,
+                        format!("Failed to compute the genus of {}: {}", factor, e),
+                    ));
+                    None
+                }
+            };
+            FactorAnalysis {
+                equation: factor.as_formatted_equation(x_var, y_var),
+                degree: factor.total_degree(),
+                genus,
+            }
+        })
+        .collect();
+    let extent = match curve_equation_extent(curve, x_var, y_var) {
+        Ok(extent) => Some(extent),
+        Err(e) => {
+            warnings.push(Warning::new(
+                WarningCode::ExtentUnknown,
+                format!("Failed to compute the extent of the curve: {}", e),
+            ));
+            None
+        }
+    };
+    CurveAnalysis {
+        degree: curve.curve_equation.total_degree(),
+        irreducible_factors: curve.factors.len(),
+        factors,
+        warnings,
+        variables,
+        extent,
+        suggested_view: None,
+        projective: None,
+        curvature: None,
+        triangular_sets: None,
+    }
+}
+
+/// Real zeros of one factor of a locus
+#[derive(Debug, PartialEq)]
+enum FactorZeros {
+    None,
+    Point(f64, f64),
+    Curve,
+}
+
+/// Finds the factors with no or a single real zero: a quadratic whose quadratic part is
+/// definite has its extremum at the center, and vanishes only there if the extremum is
+/// 0. Other factors count as curves.
+fn factor_zeros(factor: &Poly, x_var: Var, y_var: Var) -> FactorZeros {
+    if let Poly::Constant(c) = factor {
+        return if *c == 0 {
+            FactorZeros::Curve
+        } else {
+            FactorZeros::None
+        };
+    }
+    // Coefficients of x^2, x*y, y^2, x, y and 1
+    let mut coefficients = [0i128; 6];
+    for term in factor.to_terms() {
+        let mut degrees = (0, 0);
+        for &(var, degree) in &term.vars {
+            if var == x_var {
+                degrees.0 = degree;
+            } else if var == y_var {
+                degrees.1 = degree;
+            } else {
+                return FactorZeros::Curve;
+            }
+        }
+        let index = match degrees {
+            (2, 0) => 0,
+            (1, 1) => 1,
+            (0, 2) => 2,
+            (1, 0) => 3,
+            (0, 1) => 4,
+            (0, 0) => 5,
+            _ => return FactorZeros::Curve,
+        };
+        coefficients[index] = term.constant as i128;
+    }
+    let [a, b, c, d, e, g] = coefficients;
+    // The center solves 2a x + b y = -d, b x + 2c y = -e; 2 * determinant * the value of
+    // the factor at the center is `value`
+    let extremum = || -> Option<(i128, i128, i128, i128)> {
+        let determinant = (4 * a * c).checked_sub(b * b)?;
+        let x_numerator = (b * e).checked_sub(2 * c * d)?;
+        let y_numerator = (b * d).checked_sub(2 * a * e)?;
+        let value = (2 * g)
+            .checked_mul(determinant)?
+            .checked_add(d.checked_mul(x_numerator)?)?
+            .checked_add(e.checked_mul(y_numerator)?)?;
+        Some((determinant, x_numerator, y_numerator, value))
+    };
+    match extremum() {
+        Some((determinant, x, y, 0)) if determinant > 0 => {
+            FactorZeros::Point(x as f64 / determinant as f64, y as f64 / determinant as f64)
+        }
+        Some((determinant, _, _, value)) if determinant > 0 && value.signum() == a.signum() => {
+            FactorZeros::None
+        }
+        _ => FactorZeros::Curve,
+    }
+}
+
+/// Classifies the real solutions of a curve equation: the whole plane for 0, empty for
+/// a nonzero constant or factors without real zeros, points if the factors with real
+/// zeros all vanish at a single point
+pub fn classify_locus(curve: &CurveEquationAndFactors, x_var: Var, y_var: Var) -> LocusKind {
+    match curve.curve_equation {
+        Poly::Constant(0) => return LocusKind::FullPlane,
+        Poly::Constant(_) => return LocusKind::Empty,
+        _ => {}
+    }
+    let mut points = Vec::new();
+    for factor in &curve.factors {
+        match factor_zeros(factor, x_var, y_var) {
+            FactorZeros::None => {}
+            FactorZeros::Point(x, y) => points.push((x, y)),
+            FactorZeros::Curve => return LocusKind::Curve,
+        }
+    }
+    if points.is_empty() {
+        LocusKind::Empty
+    } else {
+        LocusKind::Points { points }
+    }
+}
+
+/// Writes a homogeneous polynomial in x, y and z as an equation with those names
+fn format_homogeneous(poly: &Poly, names: [(Var, char); 3]) -> String {
+    let degree = |term: &Term, var: Var| {
+        term.vars
+            .iter()
+            .find(|(v, _)| *v == var)
+            .map_or(0, |(_, d)| *d)
+    };
+    let mut terms = poly.to_terms();
+    // Descending powers of x, then of y
+    terms.sort_by_key(|term| {
+        (
+            std::cmp::Reverse(degree(term, names[0].0)),
+            std::cmp::Reverse(degree(term, names[1].0)),
+        )
+    });
+    let mut equation = String::new();
+    for term in terms {
+        let mut monomial: Vec<String> = Vec::new();
+        for (var, name) in names {
+            match term.vars.iter().find(|(v, _)| *v == var) {
+                Some((_, 1)) => monomial.push(name.to_string()),
+                Some((_, degree)) => monomial.push(format!("{}^{}", name, degree)),
+                None => {}
+            }
+        }
+        let coefficient = term.constant.unsigned_abs();
+        if coefficient != 1 || monomial.is_empty() {
+            monomial.insert(0, coefficient.to_string());
+        }
+        let sign = match (equation.is_empty(), term.constant < 0) {
+            (true, false) => "",
+            (true, true) => "-",
+            (false, false) => " + ",
+            (false, true) => " - ",
+        };
+        equation.push_str(sign);
+        equation.push_str(&monomial.join("*"));
+    }
+    if equation.is_empty() {
+        equation.push('0');
+    }
+    equation + " = 0"
+}
+
+/// Homogenizes the curve poly = 0 and finds its real points at infinity, the real
+/// zeros of the terms of the highest degree. They are searched for as [1 : t : 0]
+/// and [s : 1 : 0] with t, s in [-2, 2], so that both searches are bounded and overlap.
+pub fn projective_closure(
+    poly: &Poly,
+    x_var: Var,
+    y_var: Var,
+) -> Result<ProjectiveClosure, String> {
+    let degree = poly.total_degree();
+    if degree == 0 {
+        return Err("The equation has no curve".to_string());
+    }
+    let z_var = (0..).find(|&v| v != x_var && v != y_var).unwrap();
+    let homogeneous = poly.homogenize(z_var);
+    let equation = format_homogeneous(&homogeneous, [(x_var, 'x'), (y_var, 'y'), (z_var, 'z')]);
+
+    // Coefficients of x^(degree - k) * y^k in the leading form
+    let mut coefficients = vec![0.0; degree as usize + 1];
+    for term in poly.leading_form().to_terms() {
+        let k = term
+            .vars
+            .iter()
+            .find(|(v, _)| *v == y_var)
+            .map_or(0, |(_, d)| *d);
+        coefficients[k as usize] += term.constant as f64;
+    }
+    let chart = |mut coefficients: Vec<f64>| {
+        while coefficients.last() == Some(&0.0) {
+            coefficients.pop();
+        }
+        if coefficients.len() < 2 {
+            return vec![];
+        }
+        XPoly::new(coefficients.into_iter().map(FInt::new).collect()).get_roots(-2.0, 2.0)
+    };
+    let mut points_at_infinity: Vec<PointAtInfinity> = Vec::new();
+    let directions = chart(coefficients.clone())
+        .into_iter()
+        .map(|t| (1.0, t.midpoint()))
+        .chain(
+            chart(coefficients.into_iter().rev().collect())
+                .into_iter()
+                .map(|s| (s.midpoint(), 1.0)),
+        );
+    for (x, y) in directions {
+        let norm = x.hypot(y);
+        let sign = if x < 0.0 || (x == 0.0 && y < 0.0) {
+            -1.0
+        } else {
+            1.0
+        };
+        let point = PointAtInfinity {
+            x: sign * x / norm,
+            y: sign * y / norm,
+        };
+        let is_duplicate = points_at_infinity.iter().any(|p| {
+            (p.x - point.x).abs() <= ZERO_TOLERANCE && (p.y - point.y).abs() <= ZERO_TOLERANCE
+        });
+        if !is_duplicate {
+            points_at_infinity.push(point);
+        }
+    }
+    // By angle, from [0 : -1 : 0] up
+    points_at_infinity.sort_by(|p, q| p.y.total_cmp(&q.y));
+    Ok(ProjectiveClosure {
+        equation,
+        points_at_infinity,
+    })
+}
+
+/// Geometric genus of the irreducible curve poly = 0, computed with Pari/GP as
+/// (d - 1)(d - 2) / 2 minus m(m - 1) / 2 for every singular point of multiplicity m
+/// over the algebraic closure. This is exact when the singular points are ordinary
+/// or simple cusps, and an upper bound otherwise (tacnodes and other singularities
+/// with infinitely near singular points).
+/// The curve is moved by a projective transformation that makes its points at
+/// infinity smooth and its singular points differ in x; the computation fails
+/// for the rare curves that are still special after it.
+pub fn get_genus(poly: &Poly, x_var: Var, y_var: Var) -> Result<u32, String> {
+    let degree = poly.total_degree();
+    if degree == 0 {
+        return Err("The equation has no curve".to_string());
+    }
+    // Lines and irreducible conics are rational
+    if degree <= 2 {
+        return Ok(0);
+    }
+    let arithmetic_genus = (degree - 1) * (degree - 2) / 2;
+
+    let task = genus_task(
+        &format!("{:#}", poly),
+        degree,
+        &Poly::var_to_string(x_var),
+        &Poly::var_to_string(y_var),
+    );
+    let output_lines = crate::gp_pari_service::get_gp_pari_pool()?.run_task(task)?;
+    match output_lines.as_slice() {
+        [none] if none.trim() == "None" => {
+            Err("The singular points are not in general position".to_string())
+        }
+        [delta] => match delta.trim().parse::<u32>() {
+            Ok(delta) if delta <= arithmetic_genus => Ok(arithmetic_genus - delta),
+            Ok(delta) => Err(format!(
+                "The singular points lower the genus by {}, the curve is reducible",
+                delta
+            )),
+            Err(e) => Err(format!("Unexpected output from Pari/GP: {}", e)),
+        },
+        _ => Err(format!(
+            "Unexpected output from Pari/GP: {:?}",
+            output_lines
+        )),
+    }
+}
+
+/// Prints the sum of m(m - 1) / 2 over the singular points, or None. The singular
+/// points of pG are found over each irreducible factor pq of the resultant of the
+/// partial derivatives, where their y coordinate is the common root of pG, pG_x and
+/// pG_y in the number field of pq.
+fn genus_task(poly: &str, degree: u32, x: &str, y: &str) -> String {
+    // Helper variables have two-letter names, which never clash with scene variables
+    format!(
+        "{{my(pF = {poly}, pd = {degree}, pw, pG, pB, pR, pE, pq, pg, py, pm, pz, pS = 0, pf = 0, pX = varlower(\"pX\"));
+pw = 1 + 2 * {x} + 3 * {y};
+pG = pw^pd * substvec(pF, [{x}, {y}], [({x} + 5 * {y}) / pw, {y} / pw]);
+pB = subst(polcoef(substvec(pG, [{x}, {y}], ['pt * {x}, 'pt * {y}]), pd, 'pt), {y}, 1);
+pR = polresultant(deriv(pG, {x}), deriv(pG, {y}), {y});
+if(pR == 0 || poldegree(pB, {x}) < pd - 1 || !issquarefree(pB), pf = 1,
+    pE = factor(pR)[, 1];
+    for(ii = 1, #pE, if(pf == 0 && poldegree(pE[ii], {x}) > 0,
+        pq = subst(pE[ii], {x}, pX);
+        pg = subst(pG, {x}, Mod(pX, pq));
+        pg = gcd(gcd(pg, deriv(pg, {y})), subst(deriv(pG, {x}), {x}, Mod(pX, pq)));
+        if(poldegree(pg, {y}) > 1, pf = 1);
+        if(poldegree(pg, {y}) == 1,
+            py = -polcoef(pg, 0, {y}) / polcoef(pg, 1, {y});
+            pm = 0;
+            for(kk = 2, pd, if(pm == 0, for(jj = 0, kk, if(pm == 0,
+                pz = pG;
+                for(ll = 1, jj, pz = deriv(pz, {x}));
+                for(ll = 1, kk - jj, pz = deriv(pz, {y}));
+                if(substvec(pz, [{x}, {y}], [Mod(pX, pq), py]) != 0, pm = kk)))));
+            pS += poldegree(pq, pX) * pm * (pm - 1) / 2))));
+if(pf, print(\"None\"), print(pS));
+print(\"Done\")}}"
+    )
+    .replace('\n', " ")
+}
+
+/// Polynomial in x and one other variable, evaluated at f64 points
+struct CurvePoly<'a> {
+    poly: &'a Poly,
+    x_var: Var,
+}
+
+impl CurvePoly<'_> {
+    /// Returns the value and the sum of the absolute values of the terms at (x, y),
+    /// taking every variable other than x as y
+    fn evaluate(&self, x: f64, y: f64) -> (f64, f64) {
+        let mut value = 0.0;
+        let mut scale = 0.0;
+        for term in self.poly.to_terms() {
+            let mut term_value = term.constant as f64;
+            for &(var, degree) in &term.vars {
+                let base = if var == self.x_var { x } else { y };
+                term_value *= base.powi(degree as i32);
+            }
+            value += term_value;
+            scale += term_value.abs();
+        }
+        (value, scale)
+    }
+
+    fn vanishes_at(&self, x: f64, y: f64) -> bool {
+        let (value, scale) = self.evaluate(x, y);
+        value.abs() <= ZERO_TOLERANCE * scale.max(1.0)
+    }
+}
+
+/// The resultant of p and q in x and y with respect to y, computed with the
+/// pseudo-remainder chains of `Elimination`. None if it is constant, and p and q have
+/// no common zeros, or 0, and their common zeros form a curve rather than isolated
+/// points.
+fn resultant_in(p: &Poly, q: &Poly, x_var: Var) -> Option<Rc<Poly>> {
+    // Passing x_var as both plot variables makes y the only variable that can be
+    // eliminated
+    let polys = vec![Rc::new(p.clone()), Rc::new(q.clone())];
+    let mut elimination = Elimination::new(&polys, x_var, x_var, false);
+    while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+        elimination.eliminate_var(var_search_result);
+    }
+    elimination
+        .polys
+        .first()
+        .filter(|resultant| !matches!(***resultant, Poly::Constant(_)))
+        .cloned()
+}
+
+/// Common real zeros of p and q inside the given ranges. The x coordinates are the
+/// real roots of the resultant of p and q with respect to y, isolated with `roots`;
+/// the y coordinates are the roots of q at each of them where p vanishes as well.
+fn common_zeros(
+    p: &Poly,
+    q: &Poly,
+    x_var: Var,
+    y_var: Var,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+) -> Result<Vec<(f64, f64)>, String> {
+    let Some(resultant) = resultant_in(p, q, x_var) else {
+        return Ok(Vec::new());
+    };
+
+    let p_curve = CurvePoly { poly: p, x_var };
+    let q_xy = q.as_xy_poly(x_var, y_var)?;
+    let mut zeros: Vec<(f64, f64)> = Vec::new();
+    for x in roots::real_roots(&resultant, x_var, x_range.0, x_range.1)? {
+        let x = x.interval.midpoint();
+        for y in q_xy.points_at_fixed_x(x, y_range.0, y_range.1) {
+            let y = y.midpoint();
+            let is_duplicate = zeros.iter().any(|&(x1, y1)| {
+                (x1 - x).abs() <= ZERO_TOLERANCE * x.abs().max(1.0)
+                    && (y1 - y).abs() <= ZERO_TOLERANCE * y.abs().max(1.0)
+            });
+            if p_curve.vanishes_at(x, y) && !is_duplicate {
+                zeros.push((x, y));
+            }
+        }
+    }
+    Ok(zeros)
+}
+
+/// Finds the singular points of the curve poly = 0 inside the given ranges, the
+/// common zeros of F_x and F_y where F vanishes, so nonsingular critical points of F
+/// are not reported.
+/// Fails for curves of degree above `MAX_SINGULAR_POINTS_DEGREE`.
+pub fn find_singular_points(
+    poly: &Poly,
+    x_var: Var,
+    y_var: Var,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+) -> Result<Vec<SingularPoint>, String> {
+    let degree = poly.total_degree();
+    if degree > MAX_SINGULAR_POINTS_DEGREE {
+        return Err(format!(
+            "Curve degree {} exceeds {}",
+            degree, MAX_SINGULAR_POINTS_DEGREE
+        ));
+    }
+
+    let f_x = poly.get_derivative(x_var);
+    let f_y = poly.get_derivative(y_var);
+    if matches!(f_x, Poly::Constant(_)) || matches!(f_y, Poly::Constant(_)) {
+        // A gradient component never vanishes (or F only depends on one variable,
+        // where singular points are not isolated)
+        return Ok(Vec::new());
+    }
+
+    let curve = CurvePoly { poly, x_var };
+    let second_derivatives = [
+        f_x.get_derivative(x_var),
+        f_x.get_derivative(y_var),
+        f_y.get_derivative(y_var),
+    ];
+    let points = common_zeros(&f_x, &f_y, x_var, y_var, x_range, y_range)?
+        .into_iter()
+        .filter(|&(x, y)| curve.vanishes_at(x, y))
+        .map(|(x, y)| {
+            let [(f_xx, scale_xx), (f_xy, scale_xy), (f_yy, scale_yy)] = second_derivatives
+                .each_ref()
+                .map(|p| CurvePoly { poly: p, x_var }.evaluate(x, y));
+            let hessian = f_xx * f_yy - f_xy * f_xy;
+            let hessian_scale = scale_xx * scale_yy + scale_xy * scale_xy;
+            let kind = if hessian.abs() <= ZERO_TOLERANCE * hessian_scale {
+                SingularPointKind::Cusp
+            } else if hessian < 0.0 {
+                SingularPointKind::Node
+            } else {
+                SingularPointKind::Isolated
+            };
+            SingularPoint { x, y, kind }
+        })
+        .collect();
+    Ok(points)
+}
+
+/// The numerator N = F_y^2 F_xx - 2 F_x F_y F_xy + F_x^2 F_yy of the signed curvature
+/// N / (F_x^2 + F_y^2)^(3/2) of the curve F(x, y) = 0. The curve N = 0 meets F = 0 at
+/// its inflection points and its singular points.
+pub fn curvature_numerator(poly: &Poly, x_var: Var, y_var: Var) -> Poly {
+    let f_x = poly.get_derivative(x_var);
+    let f_y = poly.get_derivative(y_var);
+    let f_xx = f_x.get_derivative(x_var);
+    let f_xy = f_x.get_derivative(y_var);
+    let f_yy = f_y.get_derivative(y_var);
+
+    let mut numerator = f_y.multiply(&f_y).multiply(&f_xx);
+    numerator.add_poly_scaled(&f_x.multiply(&f_y).multiply(&f_xy), -2);
+    numerator.add_poly_scaled(&f_x.multiply(&f_x).multiply(&f_yy), 1);
+    numerator
+}
+
+/// Finds the real inflection points of the curve poly = 0 inside the given ranges,
+/// the common zeros of F and the curvature numerator that are not singular. Points
+/// where the curvature vanishes without changing sign, like the vertex of y = x^4,
+/// are included.
+/// Fails for curves of degree above `MAX_INFLECTION_POINTS_DEGREE`.
+pub fn find_inflection_points(
+    poly: &Poly,
+    x_var: Var,
+    y_var: Var,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+) -> Result<Vec<InflectionPoint>, String> {
+    let degree = poly.total_degree();
+    if degree > MAX_INFLECTION_POINTS_DEGREE {
+        return Err(format!(
+            "Curve degree {} exceeds {}",
+            degree, MAX_INFLECTION_POINTS_DEGREE
+        ));
+    }
+
+    let numerator = curvature_numerator(poly, x_var, y_var);
+    if !numerator.has_var(x_var) && !numerator.has_var(y_var) {
+        // Lines have no curvature, and conics have no inflection points
+        return Ok(Vec::new());
+    }
+    let f_x = CurvePoly {
+        poly: &poly.get_derivative(x_var),
+        x_var,
+    };
+    let f_y = poly.get_derivative(y_var);
+    let f_y = CurvePoly { poly: &f_y, x_var };
+    let points = common_zeros(&numerator, poly, x_var, y_var, x_range, y_range)?
+        .into_iter()
+        .filter(|&(x, y)| !(f_x.vanishes_at(x, y) && f_y.vanishes_at(x, y)))
+        .map(|(x, y)| InflectionPoint { x, y })
+        .collect();
+    Ok(points)
+}
+
+/// The curvature of each factor of a curve, with its inflection points inside the
+/// given ranges. Factors whose inflection points could not be searched for add a
+/// warning and report none.
+pub fn analyze_curvature(
+    curve: &CurveEquationAndFactors,
+    x_var: Var,
+    y_var: Var,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    warnings: &mut Vec<Warning>,
+) -> Vec<FactorCurvature> {
+    curve
+        .factors
+        .iter()
+        .map(|factor| {
+            let inflection_points = find_inflection_points(factor, x_var, y_var, x_range, y_range)
+                .unwrap_or_else(|e| {
+                    warnings.push(Warning::new(
+                        WarningCode::InflectionPointsSkipped,
+                        format!(
+                            "Inflection points of {} were not searched for: {}",
+                            factor, e
+                        ),
+                    ));
+                    Vec::new()
+                });
+            FactorCurvature {
+                inflection_curve: curvature_numerator(factor, x_var, y_var)
+                    .as_formatted_equation(x_var, y_var),
+                inflection_points,
+            }
+        })
+        .collect()
+}
+
+/// The extent of the real points of the curve poly = 0. Curves with real points at
+/// infinity count as unbounded, though a few like x^2 y^2 + 1 = 0 have no real branch
+/// going there. A bounded curve lies between its leftmost and rightmost points, where
+/// F_y vanishes, and its lowest and highest points, where F_x vanishes: the resultants
+/// of F and its partial derivatives bound them, and they are searched for within
+/// these bounds.
+/// Fails for curves of degree above `MAX_EXTENT_DEGREE`.
+pub fn curve_extent(poly: &Poly, x_var: Var, y_var: Var) -> Result<CurveExtent, String> {
+    match poly {
+        Poly::Constant(0) => return Ok(CurveExtent::Unbounded),
+        Poly::Constant(_) => return Ok(CurveExtent::Empty),
+        _ => {}
+    }
+    let degree = poly.total_degree();
+    if degree > MAX_EXTENT_DEGREE {
+        return Err(format!(
+            "Curve degree {} exceeds {}",
+            degree, MAX_EXTENT_DEGREE
+        ));
+    }
+    if !projective_closure(poly, x_var, y_var)?
+        .points_at_infinity
+        .is_empty()
+    {
+        return Ok(CurveExtent::Unbounded);
+    }
+
+    let f_x = poly.get_derivative(x_var);
+    let f_y = poly.get_derivative(y_var);
+    let bound = |p: &Poly, q: &Poly, var: Var| -> Result<f64, String> {
+        let bound = match resultant_in(p, q, var) {
+            Some(resultant) => resultant.as_x_poly(var)?.root_bound(),
+            None => 0.0,
+        };
+        if bound.is_finite() {
+            Ok(bound)
+        } else {
+            Err("The extremes of the curve could not be bounded".to_string())
+        }
+    };
+    let x_bound = bound(poly, &f_y, x_var)?;
+    let y_bound = bound(poly, &f_x, y_var)?;
+    let x_range = (-x_bound, x_bound);
+    let y_range = (-y_bound, y_bound);
+    // The lowest and highest points are searched for with the roles of x and y
+    // swapped, since F_x may vanish on a whole vertical line through them
+    let leftmost_and_rightmost = common_zeros(poly, &f_y, x_var, y_var, x_range, y_range)?;
+    let lowest_and_highest = common_zeros(poly, &f_x, y_var, x_var, y_range, x_range)?;
+    let extremes: Vec<(f64, f64)> = leftmost_and_rightmost
+        .into_iter()
+        .chain(lowest_and_highest.into_iter().map(|(y, x)| (x, y)))
+        .collect();
+    if extremes.is_empty() {
+        return Ok(CurveExtent::Empty);
+    }
+    let (xs, ys): (Vec<f64>, Vec<f64>) = extremes.into_iter().unzip();
+    let min = |values: &[f64]| values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = |values: &[f64]| values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    Ok(CurveExtent::Bounded(BoundingBox {
+        x_min: min(&xs),
+        x_max: max(&xs),
+        y_min: min(&ys),
+        y_max: max(&ys),
+    }))
+}
+
+/// The extent of a curve, from those of its factors (or of its equation if it has none)
+pub fn curve_equation_extent(
+    curve: &CurveEquationAndFactors,
+    x_var: Var,
+    y_var: Var,
+) -> Result<CurveExtent, String> {
+    let factors = if curve.factors.is_empty() {
+        std::slice::from_ref(&curve.curve_equation)
+    } else {
+        curve.factors.as_slice()
+    };
+    let mut extent = CurveExtent::Empty;
+    for factor in factors {
+        extent = match (extent, curve_extent(factor, x_var, y_var)?) {
+            (CurveExtent::Unbounded, _) | (_, CurveExtent::Unbounded) => {
+                return Ok(CurveExtent::Unbounded)
+            }
+            (CurveExtent::Empty, other) | (other, CurveExtent::Empty) => other,
+            (CurveExtent::Bounded(a), CurveExtent::Bounded(b)) => {
+                CurveExtent::Bounded(BoundingBox {
+                    x_min: a.x_min.min(b.x_min),
+                    x_max: a.x_max.max(b.x_max),
+                    y_min: a.y_min.min(b.y_min),
+                    y_max: a.y_max.max(b.y_max),
+                })
+            }
+        };
+    }
+    Ok(extent)
+}
+
+/// Whether a point lies on a curve F(x, y) = 0, as certified by the interval of F(x, y)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointMembership {
+    /// The whole interval is within the tolerance
+    OnCurve,
+    /// The interval is outside the tolerance, so the point is certainly off the curve
+    OffCurve,
+    /// The interval overlaps the tolerance without being inside it
+    Undecided,
+}
+
+/// Bounds of F(x, y) at a point, evaluated with interval arithmetic
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Residual {
+    pub lower: f64,
+    pub upper: f64,
+    pub membership: PointMembership,
+}
+
+impl Residual {
+    fn new(value: FInt, tolerance: f64) -> Self {
+        let (lower, upper) = (value.lower_bound(), value.upper_bound());
+        let membership = if upper < -tolerance || lower > tolerance {
+            PointMembership::OffCurve
+        } else if -tolerance <= lower && upper <= tolerance {
+            PointMembership::OnCurve
+        } else {
+            PointMembership::Undecided
+        };
+        Residual {
+            lower,
+            upper,
+            membership,
+        }
+    }
+}
+
+/// Residuals of a point on a curve and on each of its factors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointCheck {
+    pub residual: Residual,
+    /// |F| / |grad F| at the point, the distance to the curve to first order;
+    /// None where the gradient vanishes
+    pub distance_estimate: Option<f64>,
+    pub factors: Vec<Residual>,
+}
+
+/// Evaluates the curve equation and its factors at (x, y) with interval arithmetic.
+/// The point is on the curve if |F(x, y)| provably doesn't exceed `tolerance`.
+pub fn check_point(
+    curve: &CurveEquationAndFactors,
+    x_var: Var,
+    y_var: Var,
+    (x, y): (f64, f64),
+    tolerance: f64,
+) -> Result<PointCheck, String> {
+    let point = HashMap::from([(x_var, FInt::new(x)), (y_var, FInt::new(y))]);
+    let evaluate = |poly: &Poly| poly.eval_interval(&point).map_err(|e| e.to_string());
+    let value = evaluate(&curve.curve_equation)?;
+    let f_x = evaluate(&curve.curve_equation.get_derivative(x_var))?.midpoint();
+    let f_y = evaluate(&curve.curve_equation.get_derivative(y_var))?.midpoint();
+    let gradient = f_x.hypot(f_y);
+    let distance_estimate = (gradient > 0.0).then(|| value.midpoint().abs() / gradient);
+    Ok(PointCheck {
+        residual: Residual::new(value, tolerance),
+        distance_estimate,
+        factors: curve
+            .factors
+            .iter()
+            .map(|factor| Ok(Residual::new(evaluate(factor)?, tolerance)))
+            .collect::<Result<_, String>>()?,
+    })
+}
+
+/// A point with rational coordinates, as numerators and denominators in lowest terms
+/// with positive denominators, e.g. (3, 2) for 3/2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RationalPoint {
+    pub x: (i64, i64),
+    pub y: (i64, i64),
+}
+
+/// Points of a curve with rational coordinates of bounded denominators
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LatticePoints {
+    /// In increasing order of x, then of y
+    pub points: Vec<RationalPoint>,
+    /// Values of x where the whole vertical line is part of the curve
+    pub vertical_lines: Vec<(i64, i64)>,
+    /// Values of x that were skipped because the coefficients overflowed
+    pub skipped_columns: usize,
+}
+
+/// The fractions p / q in [low, high] with 0 < q <= max_denominator in lowest terms,
+/// in increasing order
+fn fractions_between(low: f64, high: f64, max_denominator: i64) -> Vec<(i64, i64)> {
+    let mut fractions: Vec<(i64, i64)> = (1..=max_denominator)
+        .flat_map(|q| {
+            let first = (low * q as f64).ceil() as i64;
+            let last = (high * q as f64).floor() as i64;
+            (first..=last)
+                .filter(move |p| p.unsigned_abs().gcd(q as u64) == 1)
+                .map(move |p| (p, q))
+        })
+        .collect();
+    fractions
+        .sort_by(|&(p1, q1), &(p2, q2)| (p1 as i128 * q2 as i128).cmp(&(p2 as i128 * q1 as i128)));
+    fractions
+}
+
+/// Whether poly, a polynomial in v alone, vanishes at p / q
+fn vanishes_at_fraction(poly: &Poly, v: Var, (p, q): (i64, i64)) -> Result<bool, ()> {
+    detect_overflow(|| poly.substitute_linear(v, Rc::new(Poly::Constant(p)), q))
+        .map(|value| value == Poly::Constant(0))
+        .map_err(|_| ())
+}
+
+/// Finds the points of the curve poly = 0 in the given ranges whose coordinates are
+/// integers, or fractions with denominators up to `max_denominator`. For each such value
+/// of x, the real roots of poly(x, y) are isolated with `roots`, and the fractions
+/// of bounded denominators near them are tested exactly.
+pub fn lattice_points(
+    poly: &Poly,
+    x_var: Var,
+    y_var: Var,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    max_denominator: i64,
+) -> Result<LatticePoints, String> {
+    if !(1..=MAX_LATTICE_DENOMINATOR).contains(&max_denominator) {
+        return Err(format!(
+            "Largest denominator must be between 1 and {}",
+            MAX_LATTICE_DENOMINATOR
+        ));
+    }
+    let columns = (x_range.1 - x_range.0) * (max_denominator * max_denominator) as f64;
+    if !columns.is_finite() || columns > MAX_LATTICE_COLUMNS as f64 {
+        return Err(format!(
+            "Too many values of x to try (more than {}): zoom in or lower the largest denominator",
+            MAX_LATTICE_COLUMNS
+        ));
+    }
+
+    let mut result = LatticePoints::default();
+    for x in fractions_between(x_range.0, x_range.1, max_denominator) {
+        // poly(x, y) times a power of the denominator of x, a polynomial in y
+        let Ok(column) =
+            detect_overflow(|| poly.substitute_linear(x_var, Rc::new(Poly::Constant(x.0)), x.1))
+        else {
+            result.skipped_columns += 1;
+            continue;
+        };
+        if column == Poly::Constant(0) {
+            result.vertical_lines.push(x);
+            continue;
+        }
+        if column.get_degree(y_var) == 0 {
+            continue;
+        }
+        let mut ys: Vec<(i64, i64)> = Vec::new();
+        let mut overflowed = false;
+        for root in roots::real_roots(&column, y_var, y_range.0, y_range.1)? {
+            let (low, high) = (root.interval.lower_bound(), root.interval.upper_bound());
+            for y in fractions_between(low.max(y_range.0), high.min(y_range.1), max_denominator) {
+                match vanishes_at_fraction(&column, y_var, y) {
+                    Ok(true) if !ys.contains(&y) => ys.push(y),
+                    Ok(_) => {}
+                    Err(()) => overflowed = true,
+                }
+            }
+        }
+        if overflowed {
+            result.skipped_columns += 1;
+        }
+        result
+            .points
+            .extend(ys.into_iter().map(|y| RationalPoint { x, y }));
+    }
+    Ok(result)
+}
+
+/// The line a * x + b * y + c = 0, with a^2 + b^2 = 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LineEquation {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl LineEquation {
+    /// The line through `point` with the normal vector (a, b)
+    fn through((x, y): (f64, f64), (a, b): (f64, f64)) -> Self {
+        let norm = a.hypot(b);
+        let (a, b) = (a / norm, b / norm);
+        LineEquation {
+            a,
+            b,
+            c: -(a * x + b * y),
+        }
+    }
+}
+
+/// Tangent and normal of a curve at one of its points, in world coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TangentAndNormal {
+    /// The point of the curve near the requested point where the lines meet
+    pub x: f64,
+    pub y: f64,
+    /// Index of the factor of the curve equation the point lies on
+    pub factor: usize,
+    /// Unit vector along the tangent, (F_y, -F_x) normalized
+    pub direction: (f64, f64),
+    pub tangent: LineEquation,
+    pub normal: LineEquation,
+}
+
+/// The tangent and the normal of a curve at the point nearest to `point`, from the
+/// gradient of the factor it lies on: F_x (X - x) + F_y (Y - y) = 0 is the tangent.
+/// Factors are used rather than the curve equation, whose gradient vanishes where
+/// factors meet. None if `point` leads to no regular point of any factor.
+pub fn tangent_and_normal(
+    curve: &CurveEquationAndFactors,
+    x_var: Var,
+    y_var: Var,
+    point: (f64, f64),
+) -> Result<Option<TangentAndNormal>, String> {
+    let factors = if curve.factors.is_empty() {
+        std::slice::from_ref(&curve.curve_equation)
+    } else {
+        curve.factors.as_slice()
+    };
+    let mut nearest: Option<(usize, (f64, f64))> = None;
+    for (i, factor) in factors.iter().enumerate() {
+        if !factor.has_var(x_var) && !factor.has_var(y_var) {
+            continue;
+        }
+        let Some(projected) = factor
+            .project_to_curve(x_var, y_var, point)
+            .map_err(|e| e.to_string())?
+        else {
+            continue;
+        };
+        let distance = |(x, y): (f64, f64)| (x - point.0).hypot(y - point.1);
+        if nearest.is_none_or(|(_, nearest)| distance(projected) < distance(nearest)) {
+            nearest = Some((i, projected));
+        }
+    }
+    let Some((factor, (x, y))) = nearest else {
+        return Ok(None);
+    };
+
+    let (f_x, f_y) = factors[factor]
+        .gradient(x_var, y_var, (x, y))
+        .map_err(|e| e.to_string())?;
+    let norm = f_x.hypot(f_y);
+    if norm == 0.0 || norm.is_nan() {
+        return Ok(None);
+    }
+    Ok(Some(TangentAndNormal {
+        x,
+        y,
+        factor,
+        direction: (f_y / norm, -f_x / norm),
+        tangent: LineEquation::through((x, y), (f_x, f_y)),
+        normal: LineEquation::through((x, y), (f_y, -f_x)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::EliminatedVariable;
+    use crate::variables::VariableOrigin;
+
+    fn singular_points(poly: &str) -> Vec<SingularPoint> {
+        let poly = Poly::new(poly).unwrap();
+        find_singular_points(&poly, 0, 1, (-3.0, 3.0), (-3.0, 3.0)).unwrap()
+    }
+
+    fn assert_point(point: &SingularPoint, x: f64, y: f64, kind: SingularPointKind) {
+        assert!(
+            (point.x - x).abs() < 1e-6 && (point.y - y).abs() < 1e-6,
+            "{:?}",
+            point
+        );
+        assert_eq!(point.kind, kind);
+    }
+
+    #[test]
+    fn test_node() {
+        // Nodal cubic y^2 = x^2 (x + 1), crossing itself at the origin
+        let points = singular_points("b^2 - a^3 - a^2");
+        assert_eq!(points.len(), 1);
+        assert_point(&points[0], 0.0, 0.0, SingularPointKind::Node);
+    }
+
+    #[test]
+    fn test_cusp_and_isolated_point() {
+        // Cuspidal cubic y^2 = x^3, shifted to (1, 2)
+        let points = singular_points("b^2 - 4*b + 4 - a^3 + 3*a^2 - 3*a + 1");
+        assert_eq!(points.len(), 1);
+        assert_point(&points[0], 1.0, 2.0, SingularPointKind::Cusp);
+
+        // y^2 = x^2 (x - 1) has an isolated real point at the origin
+        let points = singular_points("b^2 - a^3 + a^2");
+        assert_eq!(points.len(), 1);
+        assert_point(&points[0], 0.0, 0.0, SingularPointKind::Isolated);
+    }
+
+    #[test]
+    fn test_smooth_curves() {
+        // The center of the circle is a critical point of F, but not on the curve
+        assert!(singular_points("a^2 + b^2 - 1").is_empty());
+        assert!(singular_points("b - a^2").is_empty());
+        // The singular point of this nodal cubic is outside the range
+        let poly = Poly::new("b^2 - a^3 - a^2").unwrap();
+        let points = find_singular_points(&poly, 0, 1, (1.0, 2.0), (-3.0, 3.0)).unwrap();
+        assert!(points.is_empty());
+
+        let poly = Poly::new("a^13 + b^2 - 1").unwrap();
+        assert!(find_singular_points(&poly, 0, 1, (-3.0, 3.0), (-3.0, 3.0)).is_err());
+    }
+
+    fn inflection_points(poly: &str) -> Vec<InflectionPoint> {
+        let poly = Poly::new(poly).unwrap();
+        find_inflection_points(&poly, 0, 1, (-3.0, 3.0), (-3.0, 3.0)).unwrap()
+    }
+
+    #[test]
+    fn test_inflection_points() {
+        // The curvature of the unit circle is 8 (a^2 + b^2) / 8 (a^2 + b^2)^(3/2) = 1
+        let circle = Poly::new("a^2 + b^2 - 1").unwrap();
+        assert_eq!(
+            curvature_numerator(&circle, 0, 1),
+            Poly::new("8*a^2 + 8*b^2").unwrap()
+        );
+        assert!(inflection_points("a^2 + b^2 - 1").is_empty());
+        assert!(inflection_points("2*a - b + 1").is_empty());
+
+        // y = x^3
+        let cubic = Poly::new("b - a^3").unwrap();
+        assert_eq!(
+            curvature_numerator(&cubic, 0, 1),
+            Poly::new("-6*a").unwrap()
+        );
+        let points = inflection_points("b - a^3");
+        assert_eq!(points.len(), 1);
+        assert!(points[0].x.abs() < 1e-6 && points[0].y.abs() < 1e-6);
+
+        // The elliptic curve y^2 = x^3 - x has two real inflection points, at
+        // x = sqrt(1 + 2 / sqrt(3)); the flex at infinity is not in the plane
+        let mut points = inflection_points("b^2 - a^3 + a");
+        points.sort_by(|p, q| p.y.total_cmp(&q.y));
+        let x = (1.0 + 2.0 / 3f64.sqrt()).sqrt();
+        let y = (x * x * x - x).sqrt();
+        assert_eq!(points.len(), 2);
+        for (point, y) in points.iter().zip([-y, y]) {
+            assert!(
+                (point.x - x).abs() < 1e-6 && (point.y - y).abs() < 1e-6,
+                "{:?}",
+                point
+            );
+        }
+
+        // The node of y^2 = x^2 (x + 1) is on the curve N = 0, but it is singular
+        assert!(inflection_points("b^2 - a^3 - a^2")
+            .iter()
+            .all(|p| p.x.abs() > 1e-6 || p.y.abs() > 1e-6));
+
+        let curve = CurveEquationAndFactors {
+            curve_equation: Poly::new("a^7 + b^2 - 1").unwrap(),
+            factors: vec![Poly::new("a^7 + b^2 - 1").unwrap()],
+            warnings: Vec::new(),
+            triangular_sets: None,
+        };
+        let mut warnings = Vec::new();
+        let curvature = analyze_curvature(&curve, 0, 1, (-3.0, 3.0), (-3.0, 3.0), &mut warnings);
+        assert_eq!(curvature.len(), 1);
+        assert!(curvature[0].inflection_points.is_empty());
+        assert_eq!(warnings[0].code, WarningCode::InflectionPointsSkipped);
+    }
+
+    fn assert_bounding_box(extent: CurveExtent, expected: [f64; 4]) {
+        let CurveExtent::Bounded(b) = extent else {
+            panic!("{:?} is not bounded", extent);
+        };
+        let actual = [b.x_min, b.x_max, b.y_min, b.y_max];
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (a - e).abs() < 1e-6),
+            "{:?}",
+            actual
+        );
+    }
+
+    #[test]
+    fn test_curve_extent() {
+        let extent = |poly: &str| curve_extent(&Poly::new(poly).unwrap(), 0, 1).unwrap();
+        // The circle of radius 2 centered at (1, 2)
+        assert_bounding_box(extent("a^2 - 2*a + b^2 - 4*b + 1"), [-1.0, 3.0, 0.0, 4.0]);
+        // The lemniscate of Bernoulli (x^2 + y^2)^2 = 2 (x^2 - y^2), whose node is a
+        // critical point but not an extreme
+        assert_bounding_box(
+            extent("a^4 + 2*a^2*b^2 + b^4 - 2*a^2 + 2*b^2"),
+            [-2f64.sqrt(), 2f64.sqrt(), -0.5, 0.5],
+        );
+        // An isolated point
+        assert_bounding_box(extent("a^2 + b^2"), [0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(extent("a^2 + b^2 + 1"), CurveExtent::Empty);
+        assert_eq!(extent("b - a^2"), CurveExtent::Unbounded);
+        assert_eq!(extent("a*b - 1"), CurveExtent::Unbounded);
+        assert!(curve_extent(&Poly::new("a^12 + b^12 - 1").unwrap(), 0, 1).is_err());
+
+        // The union of the extents of the factors
+        let factors = vec![
+            Poly::new("a^2 + b^2 - 1").unwrap(),
+            Poly::new("a^2 + 4*b^2 - 4").unwrap(),
+            Poly::new("a^2 + b^2 + 1").unwrap(),
+        ];
+        let curve = CurveEquationAndFactors {
+            curve_equation: factors[0].multiply(&factors[1]).multiply(&factors[2]),
+            factors,
+            warnings: Vec::new(),
+            triangular_sets: None,
+        };
+        assert_bounding_box(
+            curve_equation_extent(&curve, 0, 1).unwrap(),
+            [-2.0, 2.0, -1.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn test_analyze_curve() {
+        // Lines and conics don't need Pari/GP
+        let curve = CurveEquationAndFactors {
+            curve_equation: Poly::new("a^3 + a*b^2 - a - b^3 - a^2*b + b").unwrap(),
+            factors: vec![
+                Poly::new("a - b").unwrap(),
+                Poly::new("a^2 + b^2 - 1").unwrap(),
+            ],
+            warnings: Vec::new(),
+            triangular_sets: None,
+        };
+        let mut variables = VariableRegistry::new();
+        variables.insert(
+            0,
+            VariableOrigin {
+                object: "P".to_string(),
+                coordinate: Some("x".to_string()),
+                auxiliary: false,
+            },
+        );
+        let analysis = analyze_curve(&curve, 0, 1, variables);
+        assert_eq!(analysis.degree, 3);
+        assert_eq!(analysis.irreducible_factors, 2);
+        assert_eq!(
+            analysis
+                .factors
+                .iter()
+                .map(|f| (f.degree, f.genus))
+                .collect::<Vec<_>>(),
+            vec![(1, Some(0)), (2, Some(0))]
+        );
+        assert!(analysis.warnings.is_empty());
+        assert_eq!(analysis.extent, Some(CurveExtent::Unbounded));
+        let json = serde_json::to_value(&analysis).unwrap();
+        assert_eq!(json["variables"]["a"]["object"], "P");
+        assert_eq!(json["extent"]["kind"], "unbounded");
+        assert!(get_genus(&Poly::new("5").unwrap(), 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_projective_closure() {
+        // The hyperbola x^2 - y^2 = 1 has its asymptotes' directions at infinity
+        let hyperbola = Poly::new("a^2 - b^2 - 1").unwrap();
+        let closure = projective_closure(&hyperbola, 0, 1).unwrap();
+        assert_eq!(closure.equation, "x^2 - y^2 - z^2 = 0");
+        let r = 0.5f64.sqrt();
+        assert_eq!(closure.points_at_infinity.len(), 2);
+        for (point, y) in closure.points_at_infinity.iter().zip([-r, r]) {
+            assert!(
+                (point.x - r).abs() < 1e-9 && (point.y - y).abs() < 1e-9,
+                "{:?}",
+                point
+            );
+        }
+
+        // The parabola y = x^2 touches the line at infinity at [0 : 1 : 0]
+        let parabola = Poly::new("b - a^2").unwrap();
+        let closure = projective_closure(&parabola, 0, 1).unwrap();
+        assert_eq!(closure.equation, "-x^2 + y*z = 0");
+        let [point] = closure.points_at_infinity[..] else {
+            panic!("{:?}", closure.points_at_infinity);
+        };
+        assert!(
+            point.x.abs() < 1e-9 && (point.y - 1.0).abs() < 1e-9,
+            "{:?}",
+            point
+        );
+
+        // A circle has no real points at infinity
+        let circle = Poly::new("a^2 + b^2 - 1").unwrap();
+        assert!(projective_closure(&circle, 0, 1)
+            .unwrap()
+            .points_at_infinity
+            .is_empty());
+        assert!(projective_closure(&Poly::Constant(3), 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_tangent_and_normal() {
+        // The line y = x and the unit circle, which meet at (r, r)
+        let curve = CurveEquationAndFactors {
+            curve_equation: Poly::new("a^3 + a*b^2 - a - b^3 - a^2*b + b").unwrap(),
+            factors: vec![
+                Poly::new("a - b").unwrap(),
+                Poly::new("a^2 + b^2 - 1").unwrap(),
+            ],
+            warnings: Vec::new(),
+            triangular_sets: None,
+        };
+        let lines = tangent_and_normal(&curve, 0, 1, (0.1, 1.05))
+            .unwrap()
+            .unwrap();
+        assert_eq!(lines.factor, 1);
+        assert!((lines.x.hypot(lines.y) - 1.0).abs() < 1e-12);
+        // The tangent of the unit circle at (x, y) is x X + y Y = 1
+        let tangent = lines.tangent;
+        assert!((tangent.a - lines.x).abs() < 1e-12 && (tangent.b - lines.y).abs() < 1e-12);
+        assert!((tangent.c + 1.0).abs() < 1e-12);
+        // The normal passes through the center
+        assert!(lines.normal.c.abs() < 1e-12);
+        assert!((lines.direction.0 * tangent.a + lines.direction.1 * tangent.b).abs() < 1e-12);
+
+        let r = 0.5f64.sqrt();
+        let lines = tangent_and_normal(&curve, 0, 1, (r + 0.05, r + 0.04))
+            .unwrap()
+            .unwrap();
+        assert_eq!(lines.factor, 0);
+        assert!((lines.x - lines.y).abs() < 1e-12);
+        // (F_y, -F_x) for F = a - b
+        assert!((lines.direction.0 + r).abs() < 1e-12 && (lines.direction.1 + r).abs() < 1e-12);
+
+        // The center of a circle leads nowhere
+        let circle = CurveEquationAndFactors {
+            curve_equation: Poly::new("a^2 + b^2 - 1").unwrap(),
+            factors: vec![],
+            warnings: Vec::new(),
+            triangular_sets: None,
+        };
+        assert_eq!(tangent_and_normal(&circle, 0, 1, (0.0, 0.0)), Ok(None));
+    }
+
+    #[test]
+    fn test_lattice_points() {
+        let circle = Poly::new("a^2 + b^2 - 25").unwrap();
+        let found = lattice_points(&circle, 0, 1, (-6.0, 6.0), (-6.0, 6.0), 1).unwrap();
+        assert_eq!(found.points.len(), 12);
+        assert_eq!(
+            found.points[..3],
+            [
+                RationalPoint {
+                    x: (-5, 1),
+                    y: (0, 1)
+                },
+                RationalPoint {
+                    x: (-4, 1),
+                    y: (-3, 1)
+                },
+                RationalPoint {
+                    x: (-4, 1),
+                    y: (3, 1)
+                },
+            ]
+        );
+        // Only the points in the ranges
+        let found = lattice_points(&circle, 0, 1, (0.0, 6.0), (0.5, 6.0), 1).unwrap();
+        assert_eq!(found.points.len(), 3);
+
+        // Rational points of the unit circle, e.g. (3/5, 4/5)
+        let circle = Poly::new("a^2 + b^2 - 1").unwrap();
+        let found = lattice_points(&circle, 0, 1, (0.1, 0.9), (0.0, 2.0), 5).unwrap();
+        assert_eq!(
+            found.points,
+            [
+                RationalPoint {
+                    x: (3, 5),
+                    y: (4, 5)
+                },
+                RationalPoint {
+                    x: (4, 5),
+                    y: (3, 5)
+                },
+            ]
+        );
+        assert_eq!(found.skipped_columns, 0);
+
+        // The curve contains the line x = 2
+        let poly = Poly::new("a*b - 2*b").unwrap();
+        let found = lattice_points(&poly, 0, 1, (-1.0, 3.0), (-1.0, 1.0), 1).unwrap();
+        assert_eq!(found.vertical_lines, [(2, 1)]);
+        assert_eq!(found.points.len(), 4);
+
+        assert!(lattice_points(&circle, 0, 1, (-1e6, 1e6), (-1.0, 1.0), 1).is_err());
+        assert!(lattice_points(&circle, 0, 1, (-1.0, 1.0), (-1.0, 1.0), 0).is_err());
+    }
+
+    #[test]
+    fn test_check_point() {
+        let curve = CurveEquationAndFactors {
+            curve_equation: Poly::new("a^3 + a*b^2 - a - b^3 - a^2*b + b").unwrap(),
+            factors: vec![
+                Poly::new("a - b").unwrap(),
+                Poly::new("a^2 + b^2 - 1").unwrap(),
+            ],
+            warnings: Vec::new(),
+            triangular_sets: None,
+        };
+        // Interval arithmetic widens the residuals of exact points by a few ulps
+        let check = check_point(&curve, 0, 1, (0.0, 1.0), 1e-12).unwrap();
+        assert_eq!(check.residual.membership, PointMembership::OnCurve);
+        assert_eq!(check.factors[0].membership, PointMembership::OffCurve);
+        assert_eq!(check.factors[1].membership, PointMembership::OnCurve);
+
+        // Near the point where the line meets the circle, 1.4e-10 off the circle
+        let r = 0.5f64.sqrt();
+        let check = check_point(&curve, 0, 1, (r + 1e-10, r), 1e-9).unwrap();
+        assert_eq!(check.factors[1].membership, PointMembership::OnCurve);
+        let check = check_point(&curve, 0, 1, (r + 1e-10, r), 1e-10).unwrap();
+        assert_eq!(check.factors[1].membership, PointMembership::OffCurve);
+        assert_eq!(
+            Residual::new(FInt::new_with_bounds(-1.0, 3.0), 2.0).membership,
+            PointMembership::Undecided
+        );
+
+        let check = check_point(&curve, 0, 1, (2.0, 0.0), 1e-9).unwrap();
+        assert_eq!(check.residual.membership, PointMembership::OffCurve);
+        assert!(check.residual.lower <= 6.0 && 6.0 <= check.residual.upper);
+        // F = a^3 - a near (2, 0): |F| / |grad F| = 6 / hypot(11, -3)
+        let distance = check.distance_estimate.unwrap();
+        assert!((distance - 6.0 / 130f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_get_genus() {
+        if crate::gp_pari_service::get_gp_pari_pool().is_err() {
+            return;
+        }
+        // Smooth cubic and quartic
+        assert_eq!(
+            get_genus(&Poly::new("b^2 - a^3 - a - 1").unwrap(), 0, 1),
+            Ok(1)
+        );
+        assert_eq!(get_genus(&Poly::new("a^4 + b^4 - 1").unwrap(), 0, 1), Ok(3));
+        // A node and a cusp make a cubic rational
+        assert_eq!(
+            get_genus(&Poly::new("b^2 - a^3 - a^2").unwrap(), 0, 1),
+            Ok(0)
+        );
+        assert_eq!(get_genus(&Poly::new("b^2 - a^3").unwrap(), 0, 1), Ok(0));
+        // A quartic with a node at the origin
+        assert_eq!(
+            get_genus(&Poly::new("a^4 + b^4 - a^2 + b^2").unwrap(), 0, 1),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn test_classify_locus() {
+        let curve = |factors: &[&str]| {
+            let factors: Vec<Poly> = factors.iter().map(|f| Poly::new(f).unwrap()).collect();
+            let mut curve_equation = Poly::Constant(1);
+            for factor in &factors {
+                curve_equation = curve_equation.multiply(factor);
+            }
+            CurveEquationAndFactors {
+                curve_equation,
+                factors,
+                warnings: Vec::new(),
+                triangular_sets: None,
+            }
+        };
+        assert_eq!(classify_locus(&curve(&["0"]), 0, 1), LocusKind::FullPlane);
+        assert_eq!(classify_locus(&curve(&[]), 0, 1), LocusKind::Empty);
+        assert_eq!(
+            classify_locus(&curve(&["a^2 + b^2 + 1"]), 0, 1),
+            LocusKind::Empty
+        );
+        assert_eq!(
+            classify_locus(&curve(&["a^2 + b^2 - 1"]), 0, 1),
+            LocusKind::Curve
+        );
+        // (a - 1)^2 + (b + 2)^2 vanishes only at (1, -2)
+        assert_eq!(
+            classify_locus(&curve(&["a^2 - 2*a + b^2 + 4*b + 5"]), 0, 1),
+            LocusKind::Points {
+                points: vec![(1.0, -2.0)]
+            }
+        );
+        assert_eq!(
+            classify_locus(&curve(&["a^2 + b^2", "a^2 + b^2 + 1"]), 0, 1),
+            LocusKind::Points {
+                points: vec![(0.0, 0.0)]
+            }
+        );
+        assert_eq!(
+            classify_locus(&curve(&["a^2 + b^2", "a - b"]), 0, 1),
+            LocusKind::Curve
+        );
+    }
+
+    #[test]
+    fn test_triangular_sets() {
+        let mut curve = CurveEquationAndFactors {
+            curve_equation: Poly::new("a^2 + b^2 - 1").unwrap(),
+            factors: vec![Poly::new("a^2 + b^2 - 1").unwrap()],
+            warnings: Vec::new(),
+            triangular_sets: None,
+        };
+        assert_eq!(triangular_sets(&curve), None);
+
+        // d was eliminated with the equation of c, then c
+        curve.triangular_sets = Some(vec![vec![
+            EliminatedVariable {
+                var: 3,
+                equation: Poly::new("c*d - a + 2*d").unwrap(),
+            },
+            EliminatedVariable {
+                var: 2,
+                equation: Poly::new("a + a*c^2 - 1 + c^2").unwrap(),
+            },
+        ]]);
+        assert_eq!(
+            triangular_sets(&curve),
+            Some(vec![vec![
+                TriangularEquation {
+                    variable: "c".to_string(),
+                    equation: "c^2*a + c^2 + a - 1".to_string(),
+                },
+                TriangularEquation {
+                    variable: "d".to_string(),
+                    equation: "d*c + 2*d - a".to_string(),
+                },
+            ]])
+        );
+    }
+}
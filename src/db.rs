@@ -1,11 +1,19 @@
+pub use curve_cache::Entity as CurveCacheEntity;
+pub use curve_cache::Model as CurveCacheModel;
+pub use pari_cache::Model as PariCacheModel;
 pub use scene::ActiveModel as SceneActiveModel;
 pub use scene::Column as SceneColumn;
 pub use scene::Entity as SceneEntity;
-// pub use scene::Model as SceneModel;
+pub use scene::Model as SceneModel;
 pub use scene::SCENE_DEFAULT_NAME;
 // pub use scene_object::ActiveModel as SceneObjectActiveModel;
 pub use scene_object::Column as SceneObjectColumn;
 pub use scene_object::Entity as SceneObjectEntity;
 pub use scene_object::Model as SceneObjectModel;
+pub use user::Entity as UserEntity;
+pub use user::Model as UserModel;
+mod curve_cache;
+mod pari_cache;
 mod scene;
 mod scene_object;
+mod user;
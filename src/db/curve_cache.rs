@@ -0,0 +1,235 @@
+use crate::poly::Poly;
+use crate::scene::{CurveEquationAndFactors, LocusCurve};
+use crate::scene_object::SceneError;
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Set};
+
+/// Curve equation of a locus, valid as long as the scene hashes to `scene_hash`
+/// (see `Scene::curve_hash`). `factors` and `warnings` are JSON arrays.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "curve_cache")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub scene_id: i32,
+    pub locus_name: String,
+    pub scene_hash: String,
+    pub x_var: String,
+    pub y_var: String,
+    pub curve_equation: String,
+    pub factors: String,
+    pub warnings: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::scene::Entity",
+        from = "Column::SceneId",
+        to = "super::scene::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Scene,
+}
+
+impl Related<super::scene::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Scene.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn get_locus_curve(&self) -> Result<LocusCurve, SceneError> {
+        let parse_poly =
+            |s: &str| Poly::new(s).map_err(|e| SceneError::DatabaseError(format!("{:?}", e)));
+        let parse_var =
+            |s: &str| Poly::parse_var(s).map_err(|e| SceneError::DatabaseError(format!("{:?}", e)));
+        let factors: Vec<String> = serde_json::from_str(&self.factors)
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+
+        Ok(LocusCurve {
+            x_var: parse_var(&self.x_var)?,
+            y_var: parse_var(&self.y_var)?,
+            curve: CurveEquationAndFactors {
+                curve_equation: parse_poly(&self.curve_equation)?,
+                factors: factors
+                    .iter()
+                    .map(|factor| parse_poly(factor))
+                    .collect::<Result<_, _>>()?,
+                warnings: serde_json::from_str(&self.warnings)
+                    .map_err(|e| SceneError::DatabaseError(e.to_string()))?,
+                triangular_sets: None,
+            },
+        })
+    }
+
+    pub async fn find_curve(
+        db: &DatabaseConnection,
+        scene_id: i32,
+        locus_name: &str,
+        scene_hash: &str,
+    ) -> Result<Option<Model>, SceneError> {
+        Entity::find()
+            .filter(Column::SceneId.eq(scene_id))
+            .filter(Column::LocusName.eq(locus_name))
+            .filter(Column::SceneHash.eq(scene_hash))
+            .one(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))
+    }
+
+    /// All curves cached for the scene with the given hash
+    pub async fn find_curves(
+        db: &DatabaseConnection,
+        scene_id: i32,
+        scene_hash: &str,
+    ) -> Result<Vec<Model>, SceneError> {
+        Entity::find()
+            .filter(Column::SceneId.eq(scene_id))
+            .filter(Column::SceneHash.eq(scene_hash))
+            .all(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))
+    }
+
+    /// Serializes a curve into a cache entry. Polynomials can't leave the thread
+    /// that computed them, so this is done before handing the curve over to `save_curve`.
+    pub fn new_entry(
+        scene_id: i32,
+        locus_name: &str,
+        scene_hash: &str,
+        locus_curve: &LocusCurve,
+    ) -> Result<Model, SceneError> {
+        let curve = &locus_curve.curve;
+        let factors: Vec<String> = curve.factors.iter().map(|f| f.to_string()).collect();
+        Ok(Model {
+            id: 0,
+            scene_id,
+            locus_name: locus_name.to_string(),
+            scene_hash: scene_hash.to_string(),
+            x_var: Poly::var_to_string(locus_curve.x_var),
+            y_var: Poly::var_to_string(locus_curve.y_var),
+            curve_equation: curve.curve_equation.to_string(),
+            factors: serde_json::to_string(&factors)
+                .map_err(|e| SceneError::DatabaseError(e.to_string()))?,
+            warnings: serde_json::to_string(&curve.warnings)
+                .map_err(|e| SceneError::DatabaseError(e.to_string()))?,
+        })
+    }
+
+    pub async fn save_curve(db: &DatabaseConnection, entry: Model) -> Result<(), SceneError> {
+        // Replace any entry stored under the same key
+        Entity::delete_many()
+            .filter(Column::SceneId.eq(entry.scene_id))
+            .filter(Column::LocusName.eq(&entry.locus_name))
+            .filter(Column::SceneHash.eq(&entry.scene_hash))
+            .exec(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+
+        let model = ActiveModel {
+            id: NotSet,
+            scene_id: Set(entry.scene_id),
+            locus_name: Set(entry.locus_name),
+            scene_hash: Set(entry.scene_hash),
+            x_var: Set(entry.x_var),
+            y_var: Set(entry.y_var),
+            curve_equation: Set(entry.curve_equation),
+            factors: Set(entry.factors),
+            warnings: Set(entry.warnings),
+        };
+        model
+            .insert(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Removes all cached curves of a scene, to be called whenever its objects change
+    pub async fn invalidate(db: &DatabaseConnection, scene_id: i32) -> Result<(), SceneError> {
+        Entity::delete_many()
+            .filter(Column::SceneId.eq(scene_id))
+            .exec(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warning::{Warning, WarningCode};
+    use chrono::Utc;
+    use sea_orm::{ConnectionTrait, Database, Schema};
+
+    async fn setup_test_db() -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+
+        let schema = Schema::new(db.get_database_backend());
+        let stmt = schema.create_table_from_entity(crate::db::scene::Entity);
+        db.execute(db.get_database_backend().build(&stmt))
+            .await
+            .unwrap();
+        let stmt = schema.create_table_from_entity(Entity);
+        db.execute(db.get_database_backend().build(&stmt))
+            .await
+            .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_curve_cache_operations() {
+        let db = setup_test_db().await;
+        let scene = crate::db::SceneActiveModel {
+            id: Set(1),
+            created_at: Set(Utc::now()),
+            view: Set("{}".to_string()),
+            name: Set("Scene 1".to_string()),
+            version: Set(0),
+            user_id: Set(None),
+            options: Set(None),
+        };
+        let scene = scene.insert(&db).await.unwrap();
+
+        let locus_curve = LocusCurve {
+            x_var: 0,
+            y_var: 27,
+            curve: CurveEquationAndFactors {
+                curve_equation: Poly::new("a^2*b1^3 - 2*a + 1").unwrap(),
+                factors: vec![Poly::new("a^2*b1^3 - 2*a + 1").unwrap()],
+                warnings: vec![Warning::new(
+                    WarningCode::ParametrizationFailed,
+                    "test".to_string(),
+                )],
+                triangular_sets: None,
+            },
+        };
+        let entry = Model::new_entry(scene.id, "L1", "abc", &locus_curve).unwrap();
+        Model::save_curve(&db, entry.clone()).await.unwrap();
+        // Storing the same key again replaces the entry
+        Model::save_curve(&db, entry).await.unwrap();
+
+        let found = Model::find_curve(&db, scene.id, "L1", "abc")
+            .await
+            .unwrap()
+            .unwrap()
+            .get_locus_curve()
+            .unwrap();
+        assert_eq!((found.x_var, found.y_var), (0, 27));
+        assert_eq!(found.curve.curve_equation, locus_curve.curve.curve_equation);
+        assert_eq!(found.curve.factors, locus_curve.curve.factors);
+        assert_eq!(found.curve.warnings, locus_curve.curve.warnings);
+
+        let other_hash = Model::find_curve(&db, scene.id, "L1", "def").await.unwrap();
+        assert!(other_hash.is_none());
+
+        Model::invalidate(&db, scene.id).await.unwrap();
+        let invalidated = Model::find_curve(&db, scene.id, "L1", "abc").await.unwrap();
+        assert!(invalidated.is_none());
+    }
+}
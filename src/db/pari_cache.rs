@@ -0,0 +1,159 @@
+use crate::scene_object::SceneError;
+use chrono::Utc;
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, QueryOrder, QuerySelect, Set};
+
+/// Output of a Pari/GP task, persisting the cache of `GpPariPool` across restarts.
+/// `output` is a JSON array of the output lines, `stored_at` the time it was last
+/// saved in milliseconds since the epoch.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "pari_cache")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub task: String,
+    pub output: String,
+    pub stored_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Loads the `limit` most recently stored entries, oldest first, so that the
+    /// newest ones are the last to be evicted when preloading a cache
+    pub async fn load_recent(
+        db: &DatabaseConnection,
+        limit: usize,
+    ) -> Result<Vec<(String, Vec<String>)>, SceneError> {
+        let mut models = Entity::find()
+            .order_by_desc(Column::StoredAt)
+            .limit(limit as u64)
+            .all(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        models.reverse();
+        models
+            .into_iter()
+            .map(|model| {
+                let output = serde_json::from_str(&model.output)
+                    .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+                Ok((model.task, output))
+            })
+            .collect()
+    }
+
+    /// Stores the entries, replacing the outputs stored for the same tasks, and drops
+    /// the oldest ones beyond `capacity`, the size of the cache they are loaded into
+    pub async fn save_entries(
+        db: &DatabaseConnection,
+        entries: Vec<(String, Vec<String>)>,
+        capacity: usize,
+    ) -> Result<(), SceneError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let stored_at = Utc::now().timestamp_millis();
+        let models = entries
+            .into_iter()
+            .map(|(task, output)| {
+                Ok(ActiveModel {
+                    task: Set(task),
+                    output: Set(serde_json::to_string(&output)
+                        .map_err(|e| SceneError::DatabaseError(e.to_string()))?),
+                    stored_at: Set(stored_at),
+                })
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+        Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::column(Column::Task)
+                    .update_columns([Column::Output, Column::StoredAt])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+
+        let expired: Vec<String> = Entity::find()
+            .select_only()
+            .column(Column::Task)
+            .order_by_desc(Column::StoredAt)
+            .offset(capacity as u64)
+            // SQLite only takes an offset after a limit
+            .limit(i64::MAX as u64)
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        if !expired.is_empty() {
+            Entity::delete_many()
+                .filter(Column::Task.is_in(expired))
+                .exec(db)
+                .await
+                .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::Migrator;
+    use sea_orm::Database;
+    use sea_orm_migration::MigratorTrait;
+
+    #[tokio::test]
+    async fn test_pari_cache_operations() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let entry = |task: &str, output: &[&str]| {
+            (
+                task.to_string(),
+                output.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            )
+        };
+        Model::save_entries(
+            &db,
+            vec![entry("factor(x^2-1)", &["[x - 1, x + 1]~", "[1, 1]~"])],
+            10,
+        )
+        .await
+        .unwrap();
+        Model::save_entries(
+            &db,
+            vec![entry("factor(x^2-1)", &["replaced"]), entry("gcd", &[])],
+            10,
+        )
+        .await
+        .unwrap();
+        Model::save_entries(&db, vec![], 10).await.unwrap();
+
+        let mut entries = Model::load_recent(&db, 10).await.unwrap();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![entry("factor(x^2-1)", &["replaced"]), entry("gcd", &[])]
+        );
+
+        // The table keeps the most recent entries up to the capacity of the cache
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        Model::save_entries(&db, vec![entry("newer", &["1"])], 2)
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        Model::save_entries(&db, vec![entry("newest", &["2"])], 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            Model::load_recent(&db, 10).await.unwrap(),
+            vec![entry("newer", &["1"]), entry("newest", &["2"])]
+        );
+        assert_eq!(
+            Model::load_recent(&db, 1).await.unwrap(),
+            vec![entry("newest", &["2"])]
+        );
+    }
+}
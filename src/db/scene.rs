@@ -1,5 +1,8 @@
 use chrono::{DateTime, Utc};
-use sea_orm::{entity::prelude::*, Set};
+use sea_orm::{entity::prelude::*, sea_query::Expr, Set};
+
+use crate::scene::StoredOptions;
+use crate::scene_object::SceneError;
 
 pub const SCENE_DEFAULT_NAME: &str = "New Scene";
 pub const SCENE_DEFAULT_VIEW: &str = r#"{"center": {"x": 0.0, "y": 0.0}, "diagonal": 25.0}"#;
@@ -12,6 +15,13 @@ pub struct Model {
     pub created_at: DateTime<Utc>,
     pub view: String,
     pub name: String,
+    /// Number of changes to the objects of the scene, for optimistic concurrency
+    #[sea_orm(default_value = 0)]
+    pub version: i32,
+    /// Owner of the scene, see `api::auth`; None for scenes created without a user
+    pub user_id: Option<i32>,
+    /// Options of the plots of the scene as JSON (see `StoredOptions`); None for the defaults
+    pub options: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -24,6 +34,72 @@ impl ActiveModelBehavior for ActiveModel {
             created_at: Set(Utc::now()),
             view: Set(SCENE_DEFAULT_VIEW.to_string()),
             name: Set(SCENE_DEFAULT_NAME.to_string()),
+            version: Set(0),
+            user_id: Set(None),
+            options: Set(None),
+        }
+    }
+}
+
+impl Model {
+    pub fn get_options(&self) -> Result<StoredOptions, SceneError> {
+        match &self.options {
+            Some(options) => serde_json::from_str(options).map_err(|e| {
+                SceneError::DatabaseError(format!("Failed to parse options JSON: {}", e))
+            }),
+            None => Ok(StoredOptions::default()),
+        }
+    }
+
+    fn options_column(options: &StoredOptions) -> Option<String> {
+        (*options != StoredOptions::default()).then(|| serde_json::to_string(options).unwrap())
+    }
+
+    /// Stores the options of the plots of a scene. They don't change its objects, so the
+    /// version of the scene stays the same.
+    pub async fn update_options(
+        db: &impl ConnectionTrait,
+        scene_id: i32,
+        options: &StoredOptions,
+    ) -> Result<(), SceneError> {
+        let result = Entity::update_many()
+            .col_expr(Column::Options, Expr::value(Self::options_column(options)))
+            .filter(Column::Id.eq(scene_id))
+            .exec(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        if result.rows_affected == 0 {
+            return Err(SceneError::SceneNotFound(scene_id));
+        }
+        Ok(())
+    }
+
+    /// Moves a scene from `version` to the next version and returns it. Fails with
+    /// `VersionConflict` if the scene is no longer at `version`, i.e. someone else
+    /// changed it since it was read.
+    pub async fn bump_version(
+        db: &impl ConnectionTrait,
+        scene_id: i32,
+        version: i32,
+    ) -> Result<i32, SceneError> {
+        let result = Entity::update_many()
+            .col_expr(Column::Version, Expr::col(Column::Version).add(1))
+            .filter(Column::Id.eq(scene_id))
+            .filter(Column::Version.eq(version))
+            .exec(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        if result.rows_affected == 1 {
+            return Ok(version + 1);
         }
+        let scene = Entity::find_by_id(scene_id)
+            .one(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?
+            .ok_or(SceneError::SceneNotFound(scene_id))?;
+        Err(SceneError::VersionConflict {
+            expected: version,
+            current: scene.version,
+        })
     }
 }
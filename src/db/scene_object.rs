@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
-use crate::scene_object::{ObjectType, SceneError, SceneObject};
-use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Set};
+use crate::scene_object::{ObjectStyle, ObjectType, SceneError, SceneObject};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveValue::NotSet, Set, TransactionTrait};
 use serde_json::Value;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
@@ -13,6 +13,11 @@ pub struct Model {
     pub object_type: String,
     pub object_name: String,
     pub properties: String,
+    /// Version of the scene at the last change of the object
+    #[sea_orm(default_value = 0)]
+    pub version: i32,
+    /// `ObjectStyle` as JSON, None for the default style
+    pub style: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -44,12 +49,26 @@ impl Model {
         SceneObject::from_properties(object_type, properties)
     }
 
+    pub fn get_style(&self) -> Result<ObjectStyle, SceneError> {
+        match &self.style {
+            Some(style) => serde_json::from_str(style)
+                .map_err(|e| SceneError::InvalidProperties(e.to_string())),
+            None => Ok(ObjectStyle::default()),
+        }
+    }
+
+    fn style_column(style: &ObjectStyle) -> Option<String> {
+        (!style.is_default()).then(|| serde_json::to_string(style).unwrap())
+    }
+
     pub async fn save_object(
-        db: &DatabaseConnection,
+        db: &impl ConnectionTrait,
         scene_id: i32,
         name: &str,
         object_type: ObjectType,
         properties: Value,
+        style: &ObjectStyle,
+        version: i32,
     ) -> Result<(), SceneError> {
         let model = ActiveModel {
             id: NotSet,
@@ -57,6 +76,8 @@ impl Model {
             object_type: Set(object_type.to_string()),
             object_name: Set(name.to_string()),
             properties: Set(properties.to_string()),
+            version: Set(version),
+            style: Set(Self::style_column(style)),
         };
 
         model
@@ -67,6 +88,98 @@ impl Model {
         Ok(())
     }
 
+    /// Replaces the type and properties of an object, keeping its row (and so its
+    /// position in the scene)
+    pub async fn update_object(
+        db: &DatabaseConnection,
+        scene_id: i32,
+        name: &str,
+        object_type: ObjectType,
+        properties: Value,
+        version: i32,
+    ) -> Result<(), SceneError> {
+        let model = Entity::find()
+            .filter(Column::SceneId.eq(scene_id))
+            .filter(Column::ObjectName.eq(name))
+            .one(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| SceneError::ObjectNotFound(name.to_string()))?;
+
+        let mut model: ActiveModel = model.into();
+        model.object_type = Set(object_type.to_string());
+        model.properties = Set(properties.to_string());
+        model.version = Set(version);
+        model
+            .update(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub async fn update_style(
+        db: &DatabaseConnection,
+        scene_id: i32,
+        name: &str,
+        style: &ObjectStyle,
+        version: i32,
+    ) -> Result<(), SceneError> {
+        let result = Entity::update_many()
+            .col_expr(Column::Style, Expr::value(Self::style_column(style)))
+            .col_expr(Column::Version, Expr::value(version))
+            .filter(Column::SceneId.eq(scene_id))
+            .filter(Column::ObjectName.eq(name))
+            .exec(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        if result.rows_affected == 0 {
+            return Err(SceneError::ObjectNotFound(name.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Renames an object and stores the rewritten properties of the objects that
+    /// reference it, in one transaction
+    pub async fn rename_object(
+        db: &DatabaseConnection,
+        scene_id: i32,
+        old_name: &str,
+        new_name: &str,
+        dependents: &[(String, Value)],
+        version: i32,
+    ) -> Result<(), SceneError> {
+        let txn = db
+            .begin()
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+
+        Entity::update_many()
+            .col_expr(Column::ObjectName, Expr::value(new_name))
+            .col_expr(Column::Version, Expr::value(version))
+            .filter(Column::SceneId.eq(scene_id))
+            .filter(Column::ObjectName.eq(old_name))
+            .exec(&txn)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        for (name, properties) in dependents {
+            Entity::update_many()
+                .col_expr(Column::Properties, Expr::value(properties.to_string()))
+                .col_expr(Column::Version, Expr::value(version))
+                .filter(Column::SceneId.eq(scene_id))
+                .filter(Column::ObjectName.eq(name))
+                .exec(&txn)
+                .await
+                .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
     pub async fn delete_object(
         db: &DatabaseConnection,
         scene_id: i32,
@@ -137,6 +250,9 @@ mod tests {
             created_at: Set(Utc::now()),
             view: Set("{}".to_string()),
             name: Set("Scene 1".to_string()),
+            version: Set(0),
+            user_id: Set(None),
+            options: Set(None),
         };
         let scene = scene.insert(&db).await.unwrap();
 
@@ -150,6 +266,8 @@ mod tests {
             "P1",
             ObjectType::FixedPoint,
             properties.clone(),
+            &ObjectStyle::default(),
+            0,
         )
         .await
         .unwrap();
@@ -170,6 +288,27 @@ mod tests {
             }
             _ => panic!("Expected FixedPoint"),
         }
+        assert_eq!(saved.style, None);
+
+        // Test updating the style
+        let style = ObjectStyle {
+            color: Some("#0000ff".to_string()),
+            ..ObjectStyle::default()
+        };
+        Model::update_style(&db, scene.id, "P1", &style, 1)
+            .await
+            .unwrap();
+        let saved = Entity::find()
+            .filter(Column::SceneId.eq(scene.id))
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.get_style().unwrap(), style);
+        assert_eq!(saved.version, 1);
+        assert!(Model::update_style(&db, scene.id, "P2", &style, 1)
+            .await
+            .is_err());
 
         // Test deleting the object
         Model::delete_object(&db, scene.id, "P1").await.unwrap();
@@ -0,0 +1,100 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Set};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::scene_object::SceneError;
+
+/// User of a shared server, who authenticates with an API token (see `api::auth`)
+#[derive(Clone, Debug, PartialEq, Serialize, DeriveEntityModel)]
+#[sea_orm(table_name = "users")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub name: String,
+    /// SHA-256 of the token, in hex; the token itself is only shown when it is created
+    #[sea_orm(unique)]
+    #[serde(skip)]
+    pub token_hash: String,
+    /// Admins can see and change the scenes of all users
+    pub is_admin: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Adds a user with a new random token. Returns the user and the token.
+    pub async fn create(
+        db: &DatabaseConnection,
+        name: &str,
+        is_admin: bool,
+    ) -> Result<(Model, String), SceneError> {
+        let mut bytes = [0u8; 32];
+        rand::rng().fill_bytes(&mut bytes);
+        let token = URL_SAFE_NO_PAD.encode(bytes);
+        let user = ActiveModel {
+            id: NotSet,
+            name: Set(name.to_string()),
+            token_hash: Set(hash_token(&token)),
+            is_admin: Set(is_admin),
+            created_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await
+        .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        Ok((user, token))
+    }
+
+    /// The user with the token, if any
+    pub async fn find_by_token(
+        db: &DatabaseConnection,
+        token: &str,
+    ) -> Result<Option<Model>, SceneError> {
+        Entity::find()
+            .filter(Column::TokenHash.eq(hash_token(token)))
+            .one(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ConnectionTrait, Database, Schema};
+
+    #[tokio::test]
+    async fn test_tokens() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        let schema = Schema::new(db.get_database_backend());
+        let stmt = schema.create_table_from_entity(Entity);
+        db.execute(db.get_database_backend().build(&stmt))
+            .await
+            .unwrap();
+
+        let (alice, token) = Model::create(&db, "alice", false).await.unwrap();
+        let (_, other_token) = Model::create(&db, "bob", true).await.unwrap();
+        assert_ne!(token, other_token);
+        assert_ne!(alice.token_hash, token);
+        assert_eq!(
+            Model::find_by_token(&db, &token).await.unwrap(),
+            Some(alice)
+        );
+        assert_eq!(Model::find_by_token(&db, "guess").await.unwrap(), None);
+        assert!(Model::create(&db, "alice", false).await.is_err());
+    }
+}
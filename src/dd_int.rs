@@ -0,0 +1,343 @@
+use std::ops;
+
+use crate::fint::FInt;
+use crate::poly::{Poly, Var};
+use crate::x_poly::SignChange;
+
+/// Relative bound of the rounding error of one double-double operation, with a wide margin
+const RELATIVE_ERROR: f64 = 1.0 / (1u128 << 96) as f64;
+/// Absolute bound covering the underflow of the low parts
+const ABSOLUTE_ERROR: f64 = 1e-300;
+
+/// Number hi + lo represented by two doubles with |lo| <= ulp(hi) / 2, which gives
+/// about 106 bits of precision
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DD {
+    hi: f64,
+    lo: f64,
+}
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+impl DD {
+    pub const ZERO: DD = DD { hi: 0.0, lo: 0.0 };
+
+    fn normalized((hi, lo): (f64, f64)) -> DD {
+        let (hi, lo) = quick_two_sum(hi, lo);
+        DD { hi, lo }
+    }
+
+    pub fn from_f64(value: f64) -> DD {
+        DD { hi: value, lo: 0.0 }
+    }
+
+    /// Exact for all i64 values
+    pub fn from_i64(value: i64) -> DD {
+        let hi = value as f64;
+        let lo = (value as i128 - hi as i128) as f64;
+        Self::normalized((hi, lo))
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.hi.is_nan() || self.lo.is_nan()
+    }
+
+    fn error_bound(self) -> f64 {
+        self.hi.abs() * RELATIVE_ERROR + ABSOLUTE_ERROR
+    }
+
+    /// A number not greater than the exact result of the operation that gave `self`
+    fn round_down(self) -> DD {
+        self + DD::from_f64(-self.error_bound())
+    }
+
+    /// A number not less than the exact result of the operation that gave `self`
+    fn round_up(self) -> DD {
+        self + DD::from_f64(self.error_bound())
+    }
+
+    fn min(self, other: DD) -> DD {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn max(self, other: DD) -> DD {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl PartialOrd for DD {
+    fn partial_cmp(&self, other: &DD) -> Option<std::cmp::Ordering> {
+        match self.hi.partial_cmp(&other.hi) {
+            Some(std::cmp::Ordering::Equal) => self.lo.partial_cmp(&other.lo),
+            ordering => ordering,
+        }
+    }
+}
+
+impl ops::Neg for DD {
+    type Output = DD;
+
+    fn neg(self) -> DD {
+        DD {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl ops::Add<DD> for DD {
+    type Output = DD;
+
+    fn add(self, x: DD) -> DD {
+        let (s, e) = two_sum(self.hi, x.hi);
+        let (t, f) = two_sum(self.lo, x.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        Self::normalized((s, e + f))
+    }
+}
+
+impl ops::Mul<DD> for DD {
+    type Output = DD;
+
+    fn mul(self, x: DD) -> DD {
+        let (p, e) = two_prod(self.hi, x.hi);
+        Self::normalized((p, e + (self.hi * x.lo + self.lo * x.hi)))
+    }
+}
+
+/// Interval with double-double bounds. Slower than `FInt`, but tight enough to
+/// decide signs of polynomials with huge coefficients, where the f64 rounding
+/// errors of the individual terms exceed the value.
+/// NaN bounds mark an undefined result, which contains every value.
+#[derive(Debug, Clone, Copy)]
+pub struct DDInt(DD, DD);
+
+impl DDInt {
+    pub fn new(value: DD) -> DDInt {
+        DDInt(value, value)
+    }
+
+    /// The same interval as `x`, whose f64 bounds are exact double-doubles
+    pub fn from_fint(x: FInt) -> DDInt {
+        DDInt(DD::from_f64(x.lower_bound()), DD::from_f64(x.upper_bound()))
+    }
+
+    pub fn lower_bound(&self) -> f64 {
+        self.0.to_f64()
+    }
+
+    pub fn upper_bound(&self) -> f64 {
+        self.1.to_f64()
+    }
+
+    pub fn always_positive(&self) -> bool {
+        self.0 > DD::ZERO
+    }
+
+    pub fn always_negative(&self) -> bool {
+        self.1 < DD::ZERO
+    }
+
+    pub fn contains_zero(&self) -> bool {
+        !self.always_positive() && !self.always_negative()
+    }
+}
+
+impl ops::Neg for DDInt {
+    type Output = DDInt;
+
+    fn neg(self) -> DDInt {
+        DDInt(-self.1, -self.0)
+    }
+}
+
+impl ops::Add<DDInt> for DDInt {
+    type Output = DDInt;
+
+    fn add(self, x: DDInt) -> DDInt {
+        DDInt((self.0 + x.0).round_down(), (self.1 + x.1).round_up())
+    }
+}
+
+impl ops::Sub<DDInt> for DDInt {
+    type Output = DDInt;
+
+    fn sub(self, x: DDInt) -> DDInt {
+        self + -x
+    }
+}
+
+impl ops::Mul<DDInt> for DDInt {
+    type Output = DDInt;
+
+    fn mul(self, x: DDInt) -> DDInt {
+        let products = [self.0 * x.0, self.0 * x.1, self.1 * x.0, self.1 * x.1];
+        if products.iter().any(|p| p.is_nan()) {
+            let nan = DD::from_f64(f64::NAN);
+            return DDInt(nan, nan);
+        }
+        let lower = products.iter().fold(products[0], |m, &p| m.min(p));
+        let upper = products.iter().fold(products[0], |m, &p| m.max(p));
+        DDInt(lower.round_down(), upper.round_up())
+    }
+}
+
+/// Polynomial in x and y with exact integer coefficients, evaluated with `DDInt`.
+/// `coefficients[i][j]` is the coefficient of x^i y^j.
+#[derive(Debug, Clone)]
+pub struct DDXYPoly {
+    coefficients: Vec<Vec<DD>>,
+}
+
+impl DDXYPoly {
+    /// Total degree above which f64 intervals are often too coarse to trace a curve
+    const MAX_F64_DEGREE: u32 = 20;
+
+    pub fn from_poly(poly: &Poly, x_var: Var, y_var: Var) -> Result<Self, String> {
+        let mut coefficients: Vec<Vec<DD>> = Vec::new();
+        for term in poly.to_terms() {
+            let (mut i, mut j) = (0, 0);
+            for &(var, degree) in &term.vars {
+                if var == x_var {
+                    i = degree as usize;
+                } else if var == y_var {
+                    j = degree as usize;
+                } else {
+                    return Err(format!(
+                        "Polynomial must be in terms of variables {} and {}",
+                        x_var, y_var
+                    ));
+                }
+            }
+            if coefficients.len() <= i {
+                coefficients.resize(i + 1, Vec::new());
+            }
+            if coefficients[i].len() <= j {
+                coefficients[i].resize(j + 1, DD::ZERO);
+            }
+            coefficients[i][j] = DD::from_i64(term.constant);
+        }
+        Ok(DDXYPoly { coefficients })
+    }
+
+    /// Whether tracing the curve of `poly` should fall back to double-double
+    /// intervals: for high degrees or coefficients that f64 can't represent exactly
+    pub fn is_needed_for(poly: &Poly) -> bool {
+        const MAX_EXACT_F64: i64 = 1 << f64::MANTISSA_DIGITS;
+        poly.to_terms().iter().any(|term| {
+            term.constant.unsigned_abs() > MAX_EXACT_F64 as u64
+                || term.vars.iter().map(|(_, d)| d).sum::<u32>() > Self::MAX_F64_DEGREE
+        })
+    }
+
+    pub fn evaluate(&self, x: DDInt, y: DDInt) -> DDInt {
+        let zero = DDInt::new(DD::ZERO);
+        self.coefficients.iter().rev().fold(zero, |result, row| {
+            let row_value = row
+                .iter()
+                .rev()
+                .fold(zero, |value, &c| value * y + DDInt::new(c));
+            result * x + row_value
+        })
+    }
+
+    /// Same as `XYPoly::sign_change_at_corners_and_center`, with double-double samples
+    pub fn sign_change_at_corners_and_center(&self, x_region: FInt, y_region: FInt) -> SignChange {
+        let samples = [
+            (x_region.lower_bound(), y_region.lower_bound()),
+            (x_region.lower_bound(), y_region.upper_bound()),
+            (x_region.upper_bound(), y_region.lower_bound()),
+            (x_region.upper_bound(), y_region.upper_bound()),
+            (x_region.midpoint(), y_region.midpoint()),
+        ];
+        let values: Vec<DDInt> = samples
+            .iter()
+            .map(|&(x, y)| self.evaluate(DDInt::new(DD::from_f64(x)), DDInt::new(DD::from_f64(y))))
+            .collect();
+        if values.iter().all(|v| v.always_positive()) || values.iter().all(|v| v.always_negative())
+        {
+            SignChange::None
+        } else if values.iter().any(|v| v.always_positive())
+            && values.iter().any(|v| v.always_negative())
+        {
+            // The polynomial is continuous, so it vanishes somewhere in the region
+            SignChange::Certain
+        } else {
+            SignChange::Likely
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poly::PolyConversion;
+
+    #[test]
+    fn test_dd_arithmetic() {
+        let big = DD::from_i64(i64::MAX);
+        assert_eq!(big.hi, 9223372036854775808.0);
+        assert_eq!(big.lo, -1.0);
+
+        // 0.1 * 3 - 0.3 is not zero in f64, and the double-double error is far smaller
+        let value = DD::from_f64(0.1) * DD::from_f64(3.0) + DD::from_f64(-0.3);
+        assert!(value.to_f64().abs() < 1e-16);
+        assert!((value.to_f64() - (0.1f64.mul_add(3.0, -0.3))).abs() < 1e-30);
+
+        let interval = DDInt::new(DD::from_f64(0.1)) * DDInt::new(DD::from_f64(3.0))
+            - DDInt::new(DD::from_f64(0.3));
+        assert!(interval.lower_bound() <= value.to_f64());
+        assert!(interval.upper_bound() >= value.to_f64());
+        assert!(interval.upper_bound() - interval.lower_bound() < 1e-28);
+    }
+
+    #[test]
+    fn test_huge_coefficients() {
+        // (2^30 (x - y))^2 + 1 is positive everywhere, but its terms are about 10^17 at (0.3, 0.3)
+        let poly = Poly::new(
+            "1152921504606846976*a^2 - 2305843009213693952*a*b + 1152921504606846976*b^2 + 1",
+        )
+        .unwrap();
+        assert!(DDXYPoly::is_needed_for(&poly));
+        assert!(!DDXYPoly::is_needed_for(
+            &Poly::new("a^2 + b^2 - 1").unwrap()
+        ));
+
+        let point = FInt::new_with_bounds(0.3, 0.3);
+        let value = poly.as_xy_poly(0, 1).unwrap().evaluate(point, point);
+        assert!(value == FInt::new(0.0));
+
+        let dd_poly = DDXYPoly::from_poly(&poly, 0, 1).unwrap();
+        let value = dd_poly.evaluate(DDInt::from_fint(point), DDInt::from_fint(point));
+        assert!(value.always_positive());
+        assert!((value.lower_bound() - 1.0).abs() < 1e-6);
+
+        assert!(DDXYPoly::from_poly(&poly, 0, 2).is_err());
+    }
+}
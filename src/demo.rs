@@ -0,0 +1,189 @@
+use log::info;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, Database, DatabaseConnection, Schema, Set};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::db::{CurveCacheEntity, SceneActiveModel, SceneEntity, SceneObjectEntity};
+use crate::poly_draw::XYPolyDraw;
+use crate::scene::{Scene, SceneOptions};
+use crate::scene_object::ObjectType;
+use crate::scene_utils::SceneUtils;
+
+const DEMO_PLOT_SIZE: u32 = 400;
+
+/// A built-in construction used by the demo mode
+pub struct Demo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub objects: Vec<(&'static str, ObjectType, Value)>,
+    pub locus: &'static str,
+}
+
+pub fn get_demos() -> Vec<Demo> {
+    vec![
+        Demo {
+            name: "circle",
+            description: "Midpoint of B and a point X moving on a circle centered at O",
+            objects: vec![
+                ("O", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+                ("B", ObjectType::FixedPoint, json!({"value": "6, 0"})),
+                ("X", ObjectType::FreePoint, json!({"value": "3, 4"})),
+                (
+                    "I1",
+                    ObjectType::TwoPointDistanceInvariant,
+                    json!({"point1": "O", "point2": "X"}),
+                ),
+                (
+                    "M",
+                    ObjectType::Midpoint,
+                    json!({"point1": "X", "point2": "B"}),
+                ),
+                ("L1", ObjectType::Locus, json!({"point": "M"})),
+            ],
+            locus: "L1",
+        },
+        Demo {
+            name: "ellipse",
+            description: "Midpoint of a point X on a circle and its projection onto a line",
+            objects: vec![
+                ("O", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+                ("A", ObjectType::FixedPoint, json!({"value": "-4, 0"})),
+                ("B", ObjectType::FixedPoint, json!({"value": "4, 0"})),
+                ("X", ObjectType::FreePoint, json!({"value": "3, 4"})),
+                (
+                    "I1",
+                    ObjectType::TwoPointDistanceInvariant,
+                    json!({"point1": "O", "point2": "X"}),
+                ),
+                (
+                    "L",
+                    ObjectType::LineAB,
+                    json!({"point1": "A", "point2": "B"}),
+                ),
+                (
+                    "H",
+                    ObjectType::Projection,
+                    json!({"point": "X", "line": "L"}),
+                ),
+                (
+                    "M",
+                    ObjectType::Midpoint,
+                    json!({"point1": "X", "point2": "H"}),
+                ),
+                ("L1", ObjectType::Locus, json!({"point": "M"})),
+            ],
+            locus: "L1",
+        },
+    ]
+}
+
+async fn setup_in_memory_db() -> Result<DatabaseConnection, Box<dyn std::error::Error>> {
+    let db = Database::connect("sqlite::memory:").await?;
+    let schema = Schema::new(db.get_database_backend());
+    let stmt = schema.create_table_from_entity(SceneEntity);
+    db.execute(db.get_database_backend().build(&stmt)).await?;
+    let stmt = schema.create_table_from_entity(SceneObjectEntity);
+    db.execute(db.get_database_backend().build(&stmt)).await?;
+    let stmt = schema.create_table_from_entity(CurveCacheEntity);
+    db.execute(db.get_database_backend().build(&stmt)).await?;
+    Ok(db)
+}
+
+/// Runs the built-in constructions in memory, printing the derived equations
+/// and writing the plots as BMP files to `output_dir`.
+/// Returns an error if any of the demos failed.
+pub async fn run_demos(output_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    match crate::gp_pari_service::get_pari_executable_path() {
+        Ok(path) => println!("Pari/GP: found ({})", path),
+        Err(e) => println!("Pari/GP: not available ({})", e),
+    }
+
+    let db = setup_in_memory_db().await?;
+    let mut failed = Vec::new();
+    for (i, demo) in get_demos().into_iter().enumerate() {
+        println!("\n=== Demo '{}': {}", demo.name, demo.description);
+        if let Err(e) = run_demo(&db, i as i32 + 1, &demo, output_dir).await {
+            println!("Demo '{}' failed: {}", demo.name, e);
+            failed.push(demo.name);
+        }
+    }
+
+    if failed.is_empty() {
+        println!("\nAll demos completed successfully");
+        Ok(())
+    } else {
+        Err(format!("Failed demos: {}", failed.join(", ")).into())
+    }
+}
+
+async fn run_demo(
+    db: &DatabaseConnection,
+    scene_id: i32,
+    demo: &Demo,
+    output_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let scene_model = SceneActiveModel {
+        id: Set(scene_id),
+        name: Set(demo.name.to_string()),
+        ..Default::default()
+    };
+    scene_model.insert(db).await?;
+
+    let mut scene = Scene::new(scene_id, SceneOptions::default());
+    for (name, object_type, properties) in &demo.objects {
+        scene
+            .add_object(db, name.to_string(), *object_type, properties.clone())
+            .await?;
+    }
+
+    let (equations, _plots) = SceneUtils::to_equations(scene.to_python())?;
+    println!("Equations:");
+    for equation in &equations {
+        println!("  {} = 0", equation);
+    }
+
+    let plot_data = scene.solve_and_plot(demo.locus, DEMO_PLOT_SIZE, DEMO_PLOT_SIZE)?;
+    println!("Curve equation: {} = 0", plot_data.equation);
+    for formatted_equation in &plot_data.formatted_equations {
+        println!("  factor: {}", formatted_equation.text);
+    }
+    for warning in &plot_data.warnings {
+        println!("  warning: {}", warning);
+    }
+
+    let filename = output_dir.join(format!("demo_{}.bmp", demo.name));
+    let filename = filename.to_string_lossy();
+    XYPolyDraw::write_bmp(&plot_data.points, DEMO_PLOT_SIZE, DEMO_PLOT_SIZE, &filename)?;
+    info!("Wrote demo plot to {}", filename);
+    println!("Plot written to {}", filename);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene_object::SceneObject;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_demos_are_well_formed() {
+        for demo in get_demos() {
+            let mut names = HashSet::new();
+            for (name, object_type, properties) in &demo.objects {
+                let object = SceneObject::from_properties(*object_type, properties.clone())
+                    .unwrap_or_else(|e| panic!("{} in demo {}: {}", name, demo.name, e));
+                // Objects must be listed after their dependencies
+                for dependency in object.get_dependencies() {
+                    assert!(
+                        names.contains(&dependency),
+                        "{} in demo {}",
+                        name,
+                        demo.name
+                    );
+                }
+                names.insert(name.to_string());
+            }
+            assert!(names.contains(demo.locus));
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Exact determinants of polynomial matrices by evaluation and interpolation.
+//!
+//! The matrix is evaluated on a grid of small integer points, bounded by the degree
+//! of the determinant in each variable. The numeric determinants are computed with
+//! fraction-free Bareiss elimination, and the polynomial is recovered by Newton
+//! interpolation along one variable at a time. All arithmetic is checked, so an
+//! overflow makes `determinant` fall back to the cofactor expansion.
+
+use log::info;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::poly::{Poly, PolyOperations, Term, Var, VarSet};
+
+/// Number of grid points above which the interpolation is considered too slow
+pub const MAX_POINTS: u64 = 1 << 16;
+
+/// Determinant of a square matrix of polynomials, interpolated for matrices larger
+/// than 3x3 and otherwise expanded by cofactors along the first row
+pub fn determinant(matrix: &[Vec<Rc<Poly>>]) -> Rc<Poly> {
+    let n = matrix.len();
+    if n == 0 {
+        return Rc::new(Poly::Constant(0));
+    }
+    if n == 1 {
+        return matrix[0][0].clone();
+    }
+    if n == 2 {
+        // For 2x2 matrix: det = a*d - b*c
+        let mut result = matrix[0][0].multiply(&matrix[1][1]);
+        result.add_poly_scaled(&matrix[0][1].multiply(&matrix[1][0]), -1);
+        return Rc::new(result);
+    }
+
+    // Cofactor expansion is exponential in the size, so larger matrices are
+    // evaluated at integer points and interpolated when that doesn't overflow
+    if n > 3 {
+        if let Some(determinant) = interpolate_determinant(matrix) {
+            return Rc::new(determinant);
+        }
+        info!("Interpolating the determinant failed, expanding by cofactors");
+    }
+
+    let mut result = Poly::Constant(0);
+    for j in 0..n {
+        let cofactor = if j % 2 == 0 { 1 } else { -1 };
+        let term = matrix[0][j].multiply(&determinant(&minor(matrix, 0, j)));
+        result.add_poly_scaled(&term, cofactor);
+    }
+    Rc::new(result)
+}
+
+/// The matrix without the given row and column
+pub fn minor(matrix: &[Vec<Rc<Poly>>], row: usize, col: usize) -> Vec<Vec<Rc<Poly>>> {
+    matrix
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != row)
+        .map(|(_, entries)| {
+            entries
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != col)
+                .map(|(_, entry)| entry.clone())
+                .collect()
+        })
+        .collect()
+}
+
+/// Upper bound of the degree of the determinant in each variable of the matrix:
+/// the smaller of the sums of the largest degrees in each row and in each column
+pub fn degree_bounds(matrix: &[Vec<Rc<Poly>>]) -> Vec<(Var, u32)> {
+    let mut vars = VarSet::new();
+    for entry in matrix.iter().flatten() {
+        vars.union_with(&entry.get_variables());
+    }
+    let n = matrix.len();
+    vars.iter()
+        .map(|v| {
+            let row_bound: u32 = matrix
+                .iter()
+                .map(|row| row.iter().map(|e| e.get_degree(v)).max().unwrap_or(0))
+                .sum();
+            let column_bound: u32 = (0..n)
+                .map(|j| {
+                    matrix
+                        .iter()
+                        .map(|row| row[j].get_degree(v))
+                        .max()
+                        .unwrap_or(0)
+                })
+                .sum();
+            (v, row_bound.min(column_bound))
+        })
+        .collect()
+}
+
+/// Determinant of a square matrix of polynomials, or None if the grid is too large
+/// or an intermediate value overflows
+pub fn interpolate_determinant(matrix: &[Vec<Rc<Poly>>]) -> Option<Poly> {
+    if matrix.is_empty() {
+        return Some(Poly::Constant(0));
+    }
+    let bounds = degree_bounds(matrix);
+    // Index of a grid point: sum of point_v * stride_v, with point_v in 0..=degree_v
+    let mut strides = Vec::with_capacity(bounds.len());
+    let mut point_count: u64 = 1;
+    for &(_, degree) in &bounds {
+        strides.push(point_count as usize);
+        point_count = point_count
+            .checked_mul(degree as u64 + 1)
+            .filter(|&count| count <= MAX_POINTS)?;
+    }
+
+    let mut values = Vec::with_capacity(point_count as usize);
+    for index in 0..point_count as usize {
+        let var_values: HashMap<Var, i128> = bounds
+            .iter()
+            .zip(&strides)
+            .map(|(&(v, degree), &stride)| (v, ((index / stride) % (degree as usize + 1)) as i128))
+            .collect();
+        let numeric_matrix = matrix
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|e| evaluate(e, &var_values))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+        values.push(bareiss_determinant(numeric_matrix)?);
+    }
+
+    // Turn the values along each axis into coefficients of that variable
+    for (&(_, degree), &stride) in bounds.iter().zip(&strides) {
+        let length = degree as usize + 1;
+        for start in 0..values.len() {
+            if (start / stride) % length != 0 {
+                continue;
+            }
+            let line: Vec<i128> = (0..length).map(|i| values[start + i * stride]).collect();
+            for (i, c) in newton_interpolation(line)?.into_iter().enumerate() {
+                values[start + i * stride] = c;
+            }
+        }
+    }
+
+    let mut terms = Vec::new();
+    for (index, &value) in values.iter().enumerate() {
+        if value == 0 {
+            continue;
+        }
+        let vars = bounds
+            .iter()
+            .zip(&strides)
+            .map(|(&(v, degree), &stride)| (v, ((index / stride) % (degree as usize + 1)) as u32))
+            .filter(|&(_, d)| d > 0)
+            .collect();
+        terms.push(Term {
+            constant: i64::try_from(value).ok()?,
+            vars,
+        });
+    }
+    Some(Poly::from_term_list(&terms))
+}
+
+fn evaluate(poly: &Poly, var_values: &HashMap<Var, i128>) -> Option<i128> {
+    match poly {
+        Poly::Constant(c) => Some(*c as i128),
+        Poly::Nested(v, coefficients) => {
+            let x = var_values[v];
+            coefficients.iter().rev().try_fold(0i128, |result, c| {
+                result.checked_mul(x)?.checked_add(evaluate(c, var_values)?)
+            })
+        }
+    }
+}
+
+/// Fraction-free Gaussian elimination: every division is exact, and the last pivot
+/// is the determinant
+pub fn bareiss_determinant(mut matrix: Vec<Vec<i128>>) -> Option<i128> {
+    let n = matrix.len();
+    if n == 0 {
+        return Some(0);
+    }
+    let mut sign = 1;
+    let mut previous_pivot = 1i128;
+    for k in 0..n - 1 {
+        if matrix[k][k] == 0 {
+            let Some(swap_row) = (k + 1..n).find(|&r| matrix[r][k] != 0) else {
+                return Some(0);
+            };
+            matrix.swap(k, swap_row);
+            sign = -sign;
+        }
+        for i in k + 1..n {
+            for j in k + 1..n {
+                let value = matrix[i][j]
+                    .checked_mul(matrix[k][k])?
+                    .checked_sub(matrix[i][k].checked_mul(matrix[k][j])?)?;
+                matrix[i][j] = value / previous_pivot;
+            }
+        }
+        previous_pivot = matrix[k][k];
+    }
+    matrix[n - 1][n - 1].checked_mul(sign)
+}
+
+/// Coefficients of the polynomial taking the given values at 0, 1, ..., n - 1
+fn newton_interpolation(values: Vec<i128>) -> Option<Vec<i128>> {
+    // Divided differences; the points are consecutive integers, so the
+    // denominators are k and every division is exact for integer polynomials
+    let mut differences = values;
+    let n = differences.len();
+    for k in 1..n {
+        for i in (k..n).rev() {
+            let difference = differences[i].checked_sub(differences[i - 1])?;
+            if difference % k as i128 != 0 {
+                return None;
+            }
+            differences[i] = difference / k as i128;
+        }
+    }
+
+    // Expand c_0 + (x - 0)(c_1 + (x - 1)(c_2 + ...)) from the innermost term
+    let mut coefficients = vec![differences[n - 1]];
+    for j in (0..n - 1).rev() {
+        // coefficients * (x - j) + c_j
+        let mut shifted = vec![0i128; coefficients.len() + 1];
+        for (i, &c) in coefficients.iter().enumerate() {
+            shifted[i + 1] = shifted[i + 1].checked_add(c)?;
+            shifted[i] = shifted[i].checked_sub(c.checked_mul(j as i128)?)?;
+        }
+        shifted[0] = shifted[0].checked_add(differences[j])?;
+        coefficients = shifted;
+    }
+    Some(coefficients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bareiss_determinant() {
+        let matrix = vec![vec![2, -1, 0], vec![-1, 2, -1], vec![0, -1, 2]];
+        assert_eq!(bareiss_determinant(matrix), Some(4));
+        // A zero pivot needs a row swap, which flips the sign
+        let matrix = vec![vec![0, 1, 2], vec![1, 0, 3], vec![4, -3, 8]];
+        assert_eq!(bareiss_determinant(matrix), Some(-2));
+        let singular = vec![vec![1, 2, 3], vec![0, 0, 1], vec![0, 0, 5]];
+        assert_eq!(bareiss_determinant(singular), Some(0));
+        assert_eq!(
+            bareiss_determinant(vec![vec![i128::MAX, 2], vec![2, 2]]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_minor() {
+        let matrix: Vec<Vec<Rc<Poly>>> = (0..3)
+            .map(|i| {
+                (1..=3)
+                    .map(|j| Rc::new(Poly::Constant(3 * i + j)))
+                    .collect()
+            })
+            .collect();
+        let minor = minor(&matrix, 0, 0);
+        let values: Vec<Vec<Poly>> = minor
+            .iter()
+            .map(|row| row.iter().map(|e| (**e).clone()).collect())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                vec![Poly::Constant(5), Poly::Constant(6)],
+                vec![Poly::Constant(8), Poly::Constant(9)]
+            ]
+        );
+        assert_eq!(*determinant(&matrix), Poly::Constant(0));
+    }
+
+    #[test]
+    fn test_newton_interpolation() {
+        // 3x^3 - x + 2 at 0, 1, 2, 3
+        let values = vec![2, 4, 24, 80];
+        assert_eq!(newton_interpolation(values), Some(vec![2, -1, 0, 3]));
+    }
+
+    #[test]
+    fn test_interpolate_determinant() {
+        let matrix: Vec<Vec<Rc<Poly>>> = [
+            ["a", "b + 1", "0", "2"],
+            ["1", "a*b", "b^2", "0"],
+            ["0", "-3", "a + b", "a^2"],
+            ["b", "1", "0", "a - 1"],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|e| Rc::new(Poly::new(e).unwrap())).collect())
+        .collect();
+        assert_eq!(degree_bounds(&matrix), vec![(0, 5), (1, 4)]);
+
+        let determinant = interpolate_determinant(&matrix).unwrap();
+        assert_eq!(
+            Ok(determinant),
+            crate::modular_determinant::determinant(&matrix)
+        );
+
+        let matrix: Vec<Vec<Rc<Poly>>> = [["a", "b"], ["b", "a"]]
+            .iter()
+            .map(|row| row.iter().map(|e| Rc::new(Poly::new(e).unwrap())).collect())
+            .collect();
+        assert_eq!(
+            interpolate_determinant(&matrix),
+            Some(Poly::new("a^2 - b^2").unwrap())
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::equation_compiler::InitialConfiguration;
+use crate::poly::{Poly, PolyOperations, Var, VarSet};
+use crate::scene_object::SceneError;
+use crate::scene_utils::SceneUtils;
+
+/// Relative size below which a pivot counts as zero in the rank computation, compared
+/// to the largest entry of its row
+const RANK_TOLERANCE: f64 = 1e-9;
+
+/// Dimension of a locus near the point the plot starts from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocusFreedom {
+    pub locus_name: String,
+    /// 0 for isolated points, 1 for a curve and 2 for a region of the plane
+    pub dimension: usize,
+}
+
+/// Degrees of freedom of the constraints of a scene, from the rank of the Jacobian
+/// of its equations at the initial values of the variables. A configuration where
+/// the constraints are singular can show more freedom than nearby ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DegreesOfFreedom {
+    pub variables: usize,
+    pub equations: usize,
+    pub rank: usize,
+    /// Variables minus rank: the dimension of the configurations near the initial one
+    pub degrees_of_freedom: usize,
+    /// Free and sliding points that the constraints don't let move
+    pub fixed_points: Vec<String>,
+    pub loci: Vec<LocusFreedom>,
+}
+
+/// Computes the degrees of freedom of the system of `configuration`. The points in
+/// `movable_points` are reported as fixed if both of their coordinates are.
+pub fn degrees_of_freedom(
+    configuration: &InitialConfiguration,
+    movable_points: &[String],
+) -> Result<DegreesOfFreedom, SceneError> {
+    let polys = configuration
+        .equations
+        .iter()
+        .map(|s| Poly::parse_equation(s).map_err(|e| SceneError::InvalidEquation(e.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Variables without equations, like the coordinates of a free point, count as well
+    let mut var_set: VarSet = configuration.values.keys().copied().collect();
+    for poly in &polys {
+        poly.fill_in_variables(&mut var_set);
+    }
+    let vars: Vec<Var> = var_set.iter().collect();
+    if let Some(var) = vars
+        .iter()
+        .find(|var| !configuration.values.contains_key(var))
+    {
+        return Err(SceneError::InvalidEquation(format!(
+            "No initial value of {}",
+            configuration.variables.describe(*var)
+        )));
+    }
+    let columns: HashMap<Var, usize> = vars
+        .iter()
+        .enumerate()
+        .map(|(column, &var)| (var, column))
+        .collect();
+
+    let mut jacobian = Vec::with_capacity(polys.len());
+    for poly in &polys {
+        let mut row = vec![0.0; vars.len()];
+        for var in poly.get_variables().iter() {
+            row[columns[&var]] = poly.get_derivative(var).eval(&configuration.values)?;
+        }
+        jacobian.push(row);
+    }
+    let rank = matrix_rank(jacobian.clone());
+
+    // A set of variables is fixed if the constraints already determine their
+    // differentials: adding them as rows doesn't raise the rank
+    let rank_with = |fixed: &[Var]| {
+        let mut matrix = jacobian.clone();
+        for var in fixed {
+            let mut row = vec![0.0; vars.len()];
+            row[columns[var]] = 1.0;
+            matrix.push(row);
+        }
+        matrix_rank(matrix)
+    };
+
+    let fixed_points = movable_points
+        .iter()
+        .filter(|name| {
+            let is_coordinate = |var: Var| {
+                let origin = configuration.variables.get(var);
+                origin.is_some_and(|origin| {
+                    &origin.object == *name && origin.coordinate.is_some() && !origin.auxiliary
+                })
+            };
+            let coordinates: Vec<Var> =
+                vars.iter().copied().filter(|&v| is_coordinate(v)).collect();
+            !coordinates.is_empty() && rank_with(&coordinates) == rank
+        })
+        .cloned()
+        .collect();
+
+    let loci = configuration
+        .plots
+        .iter()
+        .map(|plot| {
+            let (x_var, y_var) = SceneUtils::parse_plot_vars(plot)?;
+            let plot_vars: Vec<Var> = [x_var, y_var]
+                .into_iter()
+                .filter(|var| columns.contains_key(var))
+                .collect();
+            Ok(LocusFreedom {
+                locus_name: plot.name.clone(),
+                dimension: rank_with(&plot_vars) - rank,
+            })
+        })
+        .collect::<Result<_, SceneError>>()?;
+
+    Ok(DegreesOfFreedom {
+        variables: vars.len(),
+        equations: polys.len(),
+        rank,
+        degrees_of_freedom: vars.len() - rank,
+        fixed_points,
+        loci,
+    })
+}
+
+/// Numerical rank of a matrix, by Gaussian elimination with partial pivoting on the
+/// rows scaled to a largest entry of 1
+fn matrix_rank(mut rows: Vec<Vec<f64>>) -> usize {
+    for row in &mut rows {
+        let max = row.iter().fold(0.0f64, |max, x| max.max(x.abs()));
+        if max > 0.0 {
+            row.iter_mut().for_each(|x| *x /= max);
+        }
+    }
+    let columns = rows.first().map_or(0, Vec::len);
+    let mut rank = 0;
+    for column in 0..columns {
+        if rank == rows.len() {
+            break;
+        }
+        let (pivot, value) = rows
+            .iter()
+            .enumerate()
+            .skip(rank)
+            .map(|(i, row)| (i, row[column].abs()))
+            .fold((rank, 0.0), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+        if value <= RANK_TOLERANCE {
+            continue;
+        }
+        rows.swap(rank, pivot);
+        let pivot_row = rows[rank].clone();
+        for row in rows.iter_mut().skip(rank + 1) {
+            let factor = row[column] / pivot_row[column];
+            if factor != 0.0 {
+                for (x, p) in row.iter_mut().zip(&pivot_row).skip(column) {
+                    *x -= factor * p;
+                }
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equation_compiler::EquationCompiler;
+
+    fn analyze(code: &[&str], movable_points: &[&str]) -> DegreesOfFreedom {
+        let configuration = EquationCompiler::compile_at_initial_values(&code.join("\n")).unwrap();
+        let movable_points: Vec<String> = movable_points.iter().map(|s| s.to_string()).collect();
+        degrees_of_freedom(&configuration, &movable_points).unwrap()
+    }
+
+    #[test]
+    fn test_matrix_rank() {
+        assert_eq!(matrix_rank(vec![]), 0);
+        assert_eq!(matrix_rank(vec![vec![0.0, 0.0]]), 0);
+        assert_eq!(
+            matrix_rank(vec![vec![1.0, 2.0], vec![2.0, 4.0], vec![3.0, 6.0 + 1e-12]]),
+            1
+        );
+        assert_eq!(
+            matrix_rank(vec![vec![1.0, 2.0, 3.0], vec![0.0, 1e-3, 1.0]]),
+            2
+        );
+    }
+
+    #[test]
+    fn test_circle() {
+        // X moves on a circle around A
+        let dof = analyze(
+            &[
+                "A = FixedPoint(0, 0)",
+                "X = FreePoint(3, 4)",
+                "plot(\"P1\", X)",
+                "is_constant(d_sqr(A, X))",
+            ],
+            &["X"],
+        );
+        assert_eq!(dof.degrees_of_freedom, 1);
+        assert!(dof.fixed_points.is_empty());
+        assert_eq!(
+            dof.loci,
+            vec![LocusFreedom {
+                locus_name: "P1".to_string(),
+                dimension: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_fixed_and_free_points() {
+        // X is fixed by its distances to A and B, Y is unconstrained
+        let dof = analyze(
+            &[
+                "A = FixedPoint(0, 0)",
+                "B = FixedPoint(6, 0)",
+                "X = FreePoint(3, 4)",
+                "Y = FreePoint(1, 1)",
+                "plot(\"P1\", X)",
+                "plot(\"P2\", Y)",
+                "is_constant(d_sqr(A, X))",
+                "is_constant(d_sqr(B, X))",
+            ],
+            &["X", "Y"],
+        );
+        assert_eq!(dof.degrees_of_freedom, 2);
+        assert_eq!(dof.fixed_points, vec!["X"]);
+        let dimensions: Vec<usize> = dof.loci.iter().map(|l| l.dimension).collect();
+        assert_eq!(dimensions, vec![0, 2]);
+    }
+}
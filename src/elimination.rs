@@ -1,12 +1,175 @@
+use crate::budget;
+use crate::cancellation;
+use crate::metrics;
 use crate::modular_poly::ModularPoly;
-use crate::poly::{Poly, PolyOperations, VarSearchResult};
+use crate::poly::{Poly, PolyOperations, Term, Var, VarSearchResult, VarSet};
+use crate::progress::{self, Progress};
+use crate::replay;
+use crate::sampling::{self, Sampler};
+use crate::trace::{self, TraceStep};
+use crate::variables;
+use crate::warning::{Warning, WarningCode};
+use indexmap::IndexMap;
 use log::info;
 use rand::Rng;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
 use std::{collections::HashMap, rc::Rc};
 
+/// Maximum number of pseudo-remainder chains kept by the incremental mode
+const MAX_CACHED_CHAINS: usize = 1000;
+
+/// Number of starting points of the numeric factor check
+const SAMPLING_ATTEMPTS: usize = 32;
+
+/// Fewest real solutions from which a factor vanishing at none of them is rejected
+const MIN_SAMPLED_SOLUTIONS: usize = 8;
+
+/// Highest degree from which two polynomials are eliminated with subresultants
+const SUBRESULTANT_MIN_DEGREE: u32 = 3;
+
+/// Key of a pseudo-remainder chain: the eliminated variable, reduce_factors and both input polys
+type ChainKey = (Var, bool, String, String);
+
+/// Cached chains by key, from the least recently used
+type ChainCache = IndexMap<ChainKey, (Arc<SharedStep>, usize)>;
+
+/// Results of pseudo-remainder chains, with the number of steps in which coefficients were
+/// reduced, used by the incremental mode. Moving a point only changes some of the polynomials,
+/// so the chains between unchanged polynomials can be reused on the next plot, whichever
+/// thread computes it. The least recently used chains are evicted first.
+fn chain_cache() -> MutexGuard<'static, ChainCache> {
+    static CHAIN_CACHE: OnceLock<Mutex<ChainCache>> = OnceLock::new();
+    CHAIN_CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// How a variable is eliminated from two polynomials
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultantMethod {
+    /// Cancels the leading terms one degree at a time. The extraneous factors this
+    /// introduces are left to the factoring of the result.
+    PseudoRemainder,
+    /// Subresultant polynomial remainder sequence: full pseudo-remainders, with the
+    /// predictable extraneous factors divided out after each step. The intermediate
+    /// polynomials stay much smaller for high degrees.
+    Subresultant,
+}
+
+impl ResultantMethod {
+    pub fn for_degrees(degree1: u32, degree2: u32) -> Self {
+        if degree1.max(degree2) >= SUBRESULTANT_MIN_DEGREE {
+            ResultantMethod::Subresultant
+        } else {
+            ResultantMethod::PseudoRemainder
+        }
+    }
+}
+
+/// Strategy choosing the next variable to eliminate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EliminationOrder {
+    /// The variable with the smallest degree in some polynomial, see `Poly::get_min_degree_var`
+    MinDegree,
+    /// The variable with the smallest product of its degrees in the polynomials containing it.
+    /// The degrees of the chained resultants grow with this product.
+    DegreeProduct,
+    /// Brown's heuristic: the smallest highest degree of the variable, then the smallest
+    /// total degree of the terms containing it, then the fewest such terms
+    Brown,
+}
+
+impl FromStr for EliminationOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "min_degree" => Ok(EliminationOrder::MinDegree),
+            "degree_product" => Ok(EliminationOrder::DegreeProduct),
+            "brown" => Ok(EliminationOrder::Brown),
+            _ => Err(format!("Unknown elimination order: {}", s)),
+        }
+    }
+}
+
+impl EliminationOrder {
+    pub fn choose_var(
+        &self,
+        polys: &[Rc<Poly>],
+        x_var: Var,
+        y_var: Var,
+    ) -> Option<VarSearchResult> {
+        if *self == EliminationOrder::MinDegree {
+            return Poly::get_min_degree_var(polys, x_var, y_var);
+        }
+        let mut candidate_vars = VarSet::new();
+        for poly in polys {
+            poly.fill_in_variables(&mut candidate_vars);
+        }
+        candidate_vars.remove(x_var);
+        candidate_vars.remove(y_var);
+        let terms: Vec<_> = polys.iter().map(|p| p.to_terms()).collect();
+
+        candidate_vars
+            .iter()
+            .filter_map(|var| {
+                let degrees: Vec<u32> = polys.iter().map(|p| p.get_degree(var)).collect();
+                let (poly_index, &min_degree) = degrees
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &d)| d > 0)
+                    .min_by_key(|(_, &d)| d)?;
+                let key = match self {
+                    EliminationOrder::MinDegree => unreachable!(),
+                    EliminationOrder::DegreeProduct => {
+                        let present = degrees.iter().filter(|&&d| d > 0);
+                        (
+                            present
+                                .clone()
+                                .fold(1u64, |product, &d| product.saturating_mul(d as u64)),
+                            present.count() as u64,
+                            min_degree as u64,
+                        )
+                    }
+                    EliminationOrder::Brown => {
+                        let var_terms = terms
+                            .iter()
+                            .flatten()
+                            .filter(|term| term.vars.iter().any(|&(v, _)| v == var));
+                        (
+                            degrees.iter().copied().max().unwrap_or(0) as u64,
+                            var_terms
+                                .clone()
+                                .map(|term| term.vars.iter().map(|&(_, d)| d as u64).sum::<u64>())
+                                .max()
+                                .unwrap_or(0),
+                            var_terms.count() as u64,
+                        )
+                    }
+                };
+                Some((
+                    key,
+                    VarSearchResult {
+                        var,
+                        min_degree,
+                        poly_index,
+                    },
+                ))
+            })
+            .min_by_key(|(key, _)| *key)
+            .map(|(_, result)| result)
+    }
+}
+
+fn power(poly: &Poly, exponent: u32) -> Poly {
+    (0..exponent).fold(Poly::Constant(1), |result, _| result.multiply(poly))
+}
+
 #[derive(Debug, Clone)]
 struct EliminationStep {
-    pub v: u8,
+    pub v: Var,
     pub poly1: Rc<Poly>,
     pub poly2: Rc<Poly>,
     pub p_factor_1a: Rc<Poly>,
@@ -17,10 +180,79 @@ struct EliminationStep {
     pub poly_b: Rc<Poly>, // poly1 * p_factor_1b + poly2 * p_factor_2b
     pub degree_a: u32,
     pub degree_b: u32,
+    pub coefficients_reduced: bool, // true if poly_b had its coefficients divided by their gcd
+    pub method: ResultantMethod,
+    // Subresultant sequence state: the leading coefficient g of poly_a and the factor h
+    // that the next pseudo-remainder is divided by (together with g)
+    pub g: Rc<Poly>,
+    pub h: Rc<Poly>,
+}
+
+/// An `EliminationStep` with its polynomials as terms, which can be shared between threads
+#[derive(Debug)]
+struct SharedStep {
+    v: Var,
+    /// poly1, poly2, p_factor_1a, p_factor_2a, p_factor_1b, p_factor_2b, poly_a, poly_b, g, h
+    polys: [Vec<Term>; 10],
+    degree_a: u32,
+    degree_b: u32,
+    coefficients_reduced: bool,
+    method: ResultantMethod,
+}
+
+impl From<&EliminationStep> for SharedStep {
+    fn from(step: &EliminationStep) -> Self {
+        SharedStep {
+            v: step.v,
+            polys: [
+                &step.poly1,
+                &step.poly2,
+                &step.p_factor_1a,
+                &step.p_factor_2a,
+                &step.p_factor_1b,
+                &step.p_factor_2b,
+                &step.poly_a,
+                &step.poly_b,
+                &step.g,
+                &step.h,
+            ]
+            .map(|poly| poly.to_terms()),
+            degree_a: step.degree_a,
+            degree_b: step.degree_b,
+            coefficients_reduced: step.coefficients_reduced,
+            method: step.method,
+        }
+    }
+}
+
+impl From<&SharedStep> for EliminationStep {
+    fn from(step: &SharedStep) -> Self {
+        let [poly1, poly2, p_factor_1a, p_factor_2a, p_factor_1b, p_factor_2b, poly_a, poly_b, g, h] =
+            step.polys
+                .each_ref()
+                .map(|terms| Rc::new(Poly::from_term_list(terms)));
+        EliminationStep {
+            v: step.v,
+            poly1,
+            poly2,
+            p_factor_1a,
+            p_factor_2a,
+            p_factor_1b,
+            p_factor_2b,
+            poly_a,
+            poly_b,
+            degree_a: step.degree_a,
+            degree_b: step.degree_b,
+            coefficients_reduced: step.coefficients_reduced,
+            method: step.method,
+            g,
+            h,
+        }
+    }
 }
 
 impl EliminationStep {
-    pub fn new(v: u8, poly1: Rc<Poly>, poly2: Rc<Poly>) -> Self {
+    pub fn new(v: Var, poly1: Rc<Poly>, poly2: Rc<Poly>) -> Self {
         let degree1 = poly1.get_degree(v);
         let degree2 = poly2.get_degree(v);
         let (x_poly_1, x_poly_2, x_degree_1, x_degree_2) = if degree1 >= degree2 {
@@ -40,6 +272,10 @@ impl EliminationStep {
             poly_b: x_poly_2.clone(),
             degree_a: x_degree_1,
             degree_b: x_degree_2,
+            coefficients_reduced: false,
+            method: ResultantMethod::for_degrees(x_degree_1, x_degree_2),
+            g: Rc::new(Poly::Constant(1)),
+            h: Rc::new(Poly::Constant(1)),
         }
     }
 
@@ -62,7 +298,7 @@ impl EliminationStep {
         let mut new_poly_b = pa2.multiply(&pb1);
         let temp = pa1.multiply(&pb2);
         new_poly_b.add_poly_scaled(&temp, -1);
-        new_poly_b.reduce_coefficients_if_large();
+        let coefficients_reduced = new_poly_b.reduce_coefficients_if_large();
 
         // Compute new factors
         let mut p_factor_1b = self.p_factor_1a.multiply(&pb1);
@@ -88,6 +324,10 @@ impl EliminationStep {
             poly_b: Rc::new(new_poly_b),
             degree_a: self.degree_b,
             degree_b,
+            coefficients_reduced,
+            method: self.method,
+            g: self.g.clone(),
+            h: self.h.clone(),
         })
     }
 
@@ -118,7 +358,7 @@ impl EliminationStep {
         let mut new_poly_b = pa2.multiply(&pb1);
         let temp = pa1.multiply(&pb2);
         new_poly_b.add_poly_scaled(&temp, -1);
-        new_poly_b.reduce_coefficients_if_large();
+        let coefficients_reduced = new_poly_b.reduce_coefficients_if_large();
 
         // Compute new factors
         let mut p_factor_1b = self.p_factor_1a.multiply(&pb1);
@@ -144,11 +384,94 @@ impl EliminationStep {
             poly_b: Rc::new(new_poly_b),
             degree_a: self.degree_b,
             degree_b,
+            coefficients_reduced,
+            method: self.method,
+            g: self.g.clone(),
+            h: self.h.clone(),
+        })
+    }
+
+    /// One step of the subresultant PRS: poly_b becomes the pseudo-remainder of poly_a
+    /// by poly_b, divided by g * h^delta. If a division turns out not to be exact
+    /// (e.g. after the coefficients overflowed), the rest of the chain falls back to
+    /// plain pseudo-remainders.
+    fn get_next_step_subresultant(&self) -> Option<Self> {
+        if self.degree_b == 0 {
+            return None;
+        }
+
+        info!("\n(A) {}\n(B) {}", self.poly_a, self.poly_b);
+        let delta = self.degree_a - self.degree_b;
+        let (quotient, remainder) = self.poly_a.pseudo_divide(&self.poly_b, self.v);
+        let mut leading = self.poly_b.decompose(self.v)[self.degree_b as usize].clone();
+        Rc::make_mut(&mut leading).cleanup();
+        let scale = power(&leading, delta + 1);
+        let divisor = self.g.multiply(&power(&self.h, delta));
+
+        // The cofactors follow the same recurrence as the polynomials
+        let next_factor = |factor_a: &Poly, factor_b: &Poly| {
+            let mut factor = factor_a.multiply(&scale);
+            factor.add_poly_scaled(&factor_b.multiply(&quotient), -1);
+            factor.divide_exact(&divisor)
+        };
+        let h = if delta == 0 {
+            Some((*self.h).clone())
+        } else {
+            power(&leading, delta).divide_exact(&power(&self.h, delta - 1))
+        };
+        let (Some(new_poly_b), Some(p_factor_1b), Some(p_factor_2b), Some(h)) = (
+            remainder.divide_exact(&divisor),
+            next_factor(&self.p_factor_1a, &self.p_factor_1b),
+            next_factor(&self.p_factor_2a, &self.p_factor_2b),
+            h,
+        ) else {
+            info!("Inexact subresultant division, falling back to pseudo-remainders");
+            let mut step = self.clone();
+            step.method = ResultantMethod::PseudoRemainder;
+            return step.get_next_step_no_reduction();
+        };
+
+        info!("(B'){}", new_poly_b);
+        let degree_b = new_poly_b.get_degree(self.v);
+
+        Some(Self {
+            v: self.v,
+            poly1: self.poly1.clone(),
+            poly2: self.poly2.clone(),
+            p_factor_1a: self.p_factor_1b.clone(),
+            p_factor_2a: self.p_factor_2b.clone(),
+            p_factor_1b: Rc::new(p_factor_1b),
+            p_factor_2b: Rc::new(p_factor_2b),
+            poly_a: self.poly_b.clone(),
+            poly_b: Rc::new(new_poly_b),
+            degree_a: self.degree_b,
+            degree_b,
+            coefficients_reduced: false,
+            method: self.method,
+            g: leading,
+            h: Rc::new(h),
         })
     }
 
+    /// Divides poly_b by its content in v, a product of extraneous factors (such as
+    /// powers of leading coefficients) that the rest of the chain would carry into the
+    /// result
+    fn remove_content(&mut self) {
+        if self.degree_b == 0 {
+            return;
+        }
+        let content = self.poly_b.content(self.v);
+        if let Poly::Constant(_) = content {
+            return;
+        }
+        info!("Removing content {} from (B')", content);
+        self.poly_b = Rc::new(self.poly_b.primitive_part(self.v));
+    }
+
     pub fn get_next_step(&self, reduce_factors: bool) -> Option<Self> {
-        if reduce_factors {
+        if self.method == ResultantMethod::Subresultant {
+            self.get_next_step_subresultant()
+        } else if reduce_factors {
             self.get_next_step_with_reduction()
         } else {
             self.get_next_step_no_reduction()
@@ -159,20 +482,20 @@ impl EliminationStep {
     /// Returns None if the equation can never be satisfied (e.g., it results in "0 * v = non-zero")
     pub fn express_var_as_modular_poly(
         &self,
-        var_replacements: &HashMap<u8, (ModularPoly, u8)>,
+        var_replacements: &HashMap<Var, (ModularPoly, u8)>,
         q: &ModularPoly,
     ) -> Result<(Option<ModularPoly>, u8), String> {
         let degree = self.poly_a.get_degree(self.v);
         info!(
             "Finding {} from {}",
-            Poly::var_to_string(self.v),
+            variables::describe(self.v),
             self.poly_a
         );
         let (factor, remainder) = self.poly_a.extract_factor_and_remainder(self.v, degree);
         if factor.has_var(self.v) {
             return Err(format!(
                 "{}^{} in {} has the factor {} ({:?})",
-                Poly::var_to_string(self.v),
+                variables::describe(self.v),
                 degree,
                 self.poly_a,
                 factor,
@@ -182,7 +505,7 @@ impl EliminationStep {
         if remainder.has_var(self.v) {
             return Err(format!(
                 "{}^{} in {} has the remainder {}",
-                Poly::var_to_string(self.v),
+                variables::describe(self.v),
                 degree,
                 self.poly_a,
                 remainder,
@@ -196,7 +519,7 @@ impl EliminationStep {
         if modular_factor.is_zero() && modular_remainder.is_zero() {
             info!(
                 "Using a random polynomial for {} because it turns out to be 0/0",
-                Poly::var_to_string(self.v)
+                variables::describe(self.v)
             );
             return Ok((Some(ModularPoly::random(1, q.p)), 1));
         }
@@ -204,7 +527,7 @@ impl EliminationStep {
             Some(inv) => {
                 let product = (&modular_remainder * &inv).remainder(q);
                 let result = &ModularPoly::new(vec![0], q.p) - &product;
-                info!("{}^{} = {}", Poly::var_to_string(self.v), degree, result);
+                info!("{}^{} = {}", variables::describe(self.v), degree, result);
                 Ok((Some(result), degree as u8))
             }
             None => {
@@ -215,20 +538,78 @@ impl EliminationStep {
     }
 }
 
+/// Outcome of checking a factor of the eliminated polynomial against the original system
+#[derive(Debug, Clone, PartialEq)]
+pub enum FactorCheck {
+    /// All original equations hold on the factor
+    Verified,
+    /// The factor vanishes identically on the random test line, so nothing can be concluded
+    Inconclusive,
+    /// Back-substitution leads to 0 * v = non-zero: the factor comes from a leading
+    /// coefficient that vanished during the elimination of v
+    NoSolutionForVar { var: Var },
+    /// An original equation does not vanish modulo the factor (mod p),
+    /// e.g. an extraneous component introduced by squaring
+    EquationNotSatisfied { equation: String, p: u64 },
+    /// An original equation could not be evaluated modulo the factor
+    SubstitutionFailed { equation: String, error: String },
+    /// The factor vanishes at none of the sampled real solutions of the original system,
+    /// see `Elimination::check_factor_numerically`
+    NotOnSampledSolutions { samples: usize },
+}
+
+impl std::fmt::Display for FactorCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FactorCheck::Verified => write!(f, "verified"),
+            FactorCheck::Inconclusive => {
+                write!(f, "check inconclusive (factor vanishes on the test line)")
+            }
+            FactorCheck::NoSolutionForVar { var } => write!(
+                f,
+                "no solution for {} (vanishing leading coefficient)",
+                variables::describe(*var)
+            ),
+            FactorCheck::EquationNotSatisfied { equation, p } => write!(
+                f,
+                "equation {} does not hold modulo the factor (mod {})",
+                equation, p
+            ),
+            FactorCheck::SubstitutionFailed { equation, error } => {
+                write!(
+                    f,
+                    "failed to substitute into equation {}: {}",
+                    equation, error
+                )
+            }
+            FactorCheck::NotOnSampledSolutions { samples } => write!(
+                f,
+                "vanishes at none of {} sampled solutions of the system",
+                samples
+            ),
+        }
+    }
+}
+
 pub struct Elimination<'a> {
     initial_polys: &'a Vec<Rc<Poly>>,
     pub polys: Vec<Rc<Poly>>,
     resolved_steps: Vec<EliminationStep>,
-    x_var: u8,
-    y_var: u8,
-    reduce_factors: bool, // If true, divide by gcd before multiplying
+    x_var: Var,
+    y_var: Var,
+    reduce_factors: bool,  // If true, divide by gcd before multiplying
+    pub incremental: bool, // If true, reuse chains cached by previous eliminations
+    pub cache_hits: usize,
+    order: EliminationOrder,
+    pub warnings: Vec<Warning>,
+    cancelled: bool,
 }
 
 impl<'a> Elimination<'a> {
     pub fn new(
         initial_polys: &'a Vec<Rc<Poly>>,
-        x_var: u8,
-        y_var: u8,
+        x_var: Var,
+        y_var: Var,
         reduce_factors: bool,
     ) -> Self {
         let polys = initial_polys.clone();
@@ -239,18 +620,109 @@ impl<'a> Elimination<'a> {
             x_var,
             y_var,
             reduce_factors,
+            incremental: false,
+            cache_hits: 0,
+            order: EliminationOrder::MinDegree,
+            warnings: Vec::new(),
+            cancelled: false,
         }
     }
 
+    /// Eliminates the variables in the order chosen by `order` instead of `MinDegree`
+    pub fn with_strategy(mut self, order: EliminationOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// The next variable to eliminate, None once all are eliminated, the elimination
+    /// was cancelled or a limit of the `budget` was hit
     pub fn get_var_to_eliminate(&self) -> Option<VarSearchResult> {
-        Poly::get_min_degree_var(&self.polys, self.x_var, self.y_var)
+        if self.cancelled || budget::is_exceeded() {
+            return None;
+        }
+        let mut candidate_vars = VarSet::new();
+        for poly in &self.polys {
+            poly.fill_in_variables(&mut candidate_vars);
+        }
+        candidate_vars.remove(self.x_var);
+        candidate_vars.remove(self.y_var);
+        // Only a variable left to eliminate moves a replay on
+        candidate_vars.first()?;
+        if let Some(var) = replay::next_var(&candidate_vars) {
+            info!("Replaying the elimination of {}", variables::describe(var));
+            return self.search_var(var);
+        }
+        let result = self.order.choose_var(&self.polys, self.x_var, self.y_var);
+        if let Some(result) = &result {
+            info!(
+                "{:?} order chose {} (minimal degree {})",
+                self.order,
+                variables::describe(result.var),
+                result.min_degree
+            );
+        }
+        result
+    }
+
+    /// The polynomial of the lowest positive degree in v, to eliminate it with
+    fn search_var(&self, v: Var) -> Option<VarSearchResult> {
+        let (poly_index, min_degree) = self
+            .polys
+            .iter()
+            .map(|poly| poly.get_degree(v))
+            .enumerate()
+            .filter(|&(_, degree)| degree > 0)
+            .min_by_key(|&(_, degree)| degree)?;
+        Some(VarSearchResult {
+            var: v,
+            min_degree,
+            poly_index,
+        })
+    }
+
+    /// Whether `eliminate_var` stopped because the token installed by
+    /// `cancellation::with_cancellation` was cancelled. The polynomials are then those
+    /// left by the last variable eliminated completely.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Variables eliminated so far, in order
+    pub fn eliminated_vars(&self) -> Vec<Var> {
+        self.resolved_steps.iter().map(|step| step.v).collect()
+    }
+
+    /// The polynomial left with each variable eliminated so far, in order: the last one
+    /// of positive degree in the variable in the chains eliminating it. It only depends
+    /// on the variables eliminated later, x and y.
+    pub fn pivots(&self) -> Vec<(Var, Rc<Poly>)> {
+        self.resolved_steps
+            .iter()
+            .map(|step| (step.v, step.poly_a.clone()))
+            .collect()
     }
 
     pub fn eliminate_var(&mut self, var_search_result: VarSearchResult) {
+        if cancellation::is_cancelled() {
+            self.cancelled = true;
+            return;
+        }
+        let _timer = metrics::ELIMINATION.start();
+        progress::report(|| Progress::EliminatingVariable {
+            var: Poly::var_to_string(var_search_result.var),
+            polys: self.polys.len(),
+            terms: self.polys.iter().map(|p| p.to_terms().len()).sum(),
+        });
         let mut new_polys = Vec::new();
         let mut final_step = None;
         let mut poly_with_var = self.polys[var_search_result.poly_index].clone();
-        for (i, poly) in self.polys.iter().enumerate() {
+        trace::record(|| TraceStep::Pivot {
+            var: Poly::var_to_string(var_search_result.var),
+            equation: trace::to_string(&poly_with_var),
+        });
+        let mut reduced_steps = 0;
+        let polys = self.polys.clone();
+        for (i, poly) in polys.iter().enumerate() {
             if i == var_search_result.poly_index {
                 continue;
             }
@@ -258,12 +730,25 @@ impl<'a> Elimination<'a> {
                 new_polys.push(poly.clone());
                 continue;
             }
+            // Each chain can take long, so cancellation is checked between them
+            if cancellation::is_cancelled() {
+                self.cancelled = true;
+                return;
+            }
 
-            let mut elimination_step =
-                EliminationStep::new(var_search_result.var, poly.clone(), poly_with_var.clone());
-            while let Some(next_step) = elimination_step.get_next_step(self.reduce_factors) {
-                elimination_step = next_step;
+            let (elimination_step, chain_reduced_steps) =
+                self.run_chain(var_search_result.var, poly.clone(), poly_with_var.clone());
+            // The chain was cut short, so the system is left as it was
+            if budget::is_exceeded() {
+                return;
             }
+            reduced_steps += chain_reduced_steps;
+            trace::record(|| TraceStep::Resultant {
+                var: Poly::var_to_string(var_search_result.var),
+                poly1: trace::to_string(poly),
+                poly2: trace::to_string(&poly_with_var),
+                resultant: trace::to_string(&elimination_step.poly_b),
+            });
             if *elimination_step.poly_b != Poly::Constant(0) {
                 new_polys.push(elimination_step.poly_b.clone());
             }
@@ -277,11 +762,78 @@ impl<'a> Elimination<'a> {
                 new_polys[0].clone(),
             ));
         }
+        if reduced_steps > 0 {
+            self.warnings.push(Warning::new(
+                WarningCode::CoefficientsReduced,
+                format!(
+                    "Coefficients were reduced by their gcd in {} step(s) while eliminating {}",
+                    reduced_steps,
+                    variables::describe(var_search_result.var)
+                ),
+            ));
+        }
         self.resolved_steps.push(final_step.unwrap());
         self.polys = new_polys;
     }
 
-    pub fn check_factor(&self, factor: &Poly) -> Result<bool, String> {
+    /// Runs the pseudo-remainder chain eliminating v from the two polynomials.
+    /// Returns the final step and the number of steps in which coefficients were reduced,
+    /// or the step at which a limit of the `budget` was hit.
+    fn run_chain(&mut self, v: Var, poly1: Rc<Poly>, poly2: Rc<Poly>) -> (EliminationStep, usize) {
+        // Formatting the polynomials is only worth it if the chain may be reused
+        let key = self
+            .incremental
+            .then(|| (v, self.reduce_factors, poly1.to_string(), poly2.to_string()));
+        if let Some(key) = &key {
+            let cached = {
+                let mut cache = chain_cache();
+                cache.get_index_of(key).map(|index| {
+                    let last = cache.len() - 1;
+                    cache.move_index(index, last);
+                    cache[last].clone()
+                })
+            };
+            if let Some((step, reduced_steps)) = cached {
+                info!("Reusing cached chain for {}", variables::describe(v));
+                self.cache_hits += 1;
+                metrics::CHAIN_CACHE.record(true);
+                return (EliminationStep::from(&*step), reduced_steps);
+            }
+            metrics::CHAIN_CACHE.record(false);
+        }
+
+        let mut elimination_step = EliminationStep::new(v, poly1, poly2);
+        let mut reduced_steps = 0;
+        while let Some(mut next_step) = elimination_step.get_next_step(self.reduce_factors) {
+            budget::count_elimination_step(|| {
+                format!("the elimination of {}", variables::describe(v))
+            });
+            if budget::is_exceeded() {
+                return (next_step, reduced_steps);
+            }
+            if next_step.coefficients_reduced {
+                reduced_steps += 1;
+            }
+            // The subresultant steps rely on the exact remainders
+            if next_step.method == ResultantMethod::PseudoRemainder {
+                next_step.remove_content();
+            }
+            elimination_step = next_step;
+        }
+
+        if let Some(key) = key {
+            let shared = Arc::new(SharedStep::from(&elimination_step));
+            let mut cache = chain_cache();
+            cache.shift_remove(&key);
+            while cache.len() >= MAX_CACHED_CHAINS {
+                cache.shift_remove_index(0);
+            }
+            cache.insert(key, (shared, reduced_steps));
+        }
+        (elimination_step, reduced_steps)
+    }
+
+    pub fn check_factor(&self, factor: &Poly) -> Result<FactorCheck, String> {
         // Choose modulus p as one of the specified large random numbers
         let modulus_options = [
             u64::MAX - 58,
@@ -311,11 +863,11 @@ impl<'a> Elimination<'a> {
                 break;
             }
         }
-        info!("{} = {}", Poly::var_to_string(self.x_var), x_poly);
-        info!("{} = {}", Poly::var_to_string(self.y_var), y_poly);
+        info!("{} = {}", variables::describe(self.x_var), x_poly);
+        info!("{} = {}", variables::describe(self.y_var), y_poly);
 
         // Initialize var_replacements with x_var and y_var
-        let mut var_replacements: HashMap<u8, (ModularPoly, u8)> = HashMap::new();
+        let mut var_replacements: HashMap<Var, (ModularPoly, u8)> = HashMap::new();
         var_replacements.insert(self.x_var, (x_poly, 1));
         var_replacements.insert(self.y_var, (y_poly, 1));
 
@@ -323,37 +875,69 @@ impl<'a> Elimination<'a> {
         let q = factor.substitute_modular_polys(&var_replacements)?;
         info!("q: {}", q);
         if q.is_zero() {
-            info!("q is zero - test is inconclusive! Returning false");
-            return Ok(false);
+            info!("q is zero - test is inconclusive!");
+            return Ok(FactorCheck::Inconclusive);
         }
         let x_poly = var_replacements.get(&self.x_var).unwrap().0.remainder(&q);
         let y_poly = var_replacements.get(&self.y_var).unwrap().0.remainder(&q);
-        info!("{} = {}", Poly::var_to_string(self.x_var), x_poly);
-        info!("{} = {}", Poly::var_to_string(self.y_var), y_poly);
+        info!("{} = {}", variables::describe(self.x_var), x_poly);
+        info!("{} = {}", variables::describe(self.y_var), y_poly);
         var_replacements.insert(self.x_var, (x_poly, 1));
         var_replacements.insert(self.y_var, (y_poly, 1));
 
         // Iterate over resolved_steps in reversed order
         for step in self.resolved_steps.iter().rev() {
             let (var_poly, var_degree) = step.express_var_as_modular_poly(&var_replacements, &q)?;
-            if var_poly.is_none() {
-                return Ok(false);
+            match var_poly {
+                Some(var_poly) => {
+                    var_replacements.insert(step.v, (var_poly, var_degree));
+                }
+                None => return Ok(FactorCheck::NoSolutionForVar { var: step.v }),
             }
-            var_replacements.insert(step.v, (var_poly.unwrap(), var_degree));
         }
 
         // Verify that equations hold
         Ok(self.verify_equations_hold(&var_replacements, &q))
     }
 
+    /// Random real solutions of the original system, for `check_factor_numerically`
+    pub fn sample_solutions(&self, rng: &mut impl Rng) -> Vec<HashMap<Var, f64>> {
+        let solutions = Sampler::new(self.initial_polys).sample(SAMPLING_ATTEMPTS, rng);
+        info!(
+            "Found {} real solutions from {} starting points",
+            solutions.len(),
+            SAMPLING_ATTEMPTS
+        );
+        solutions
+    }
+
+    /// Complement of `check_factor` for when it is inconclusive or fails: Verified if
+    /// the factor vanishes at one of the solutions, NotOnSampledSolutions if there are
+    /// enough solutions and it vanishes at none of them, Inconclusive otherwise
+    pub fn check_factor_numerically(
+        &self,
+        factor: &Poly,
+        solutions: &[HashMap<Var, f64>],
+    ) -> FactorCheck {
+        if solutions.iter().any(|s| sampling::vanishes_at(factor, s)) {
+            FactorCheck::Verified
+        } else if solutions.len() >= MIN_SAMPLED_SOLUTIONS {
+            FactorCheck::NotOnSampledSolutions {
+                samples: solutions.len(),
+            }
+        } else {
+            FactorCheck::Inconclusive
+        }
+    }
+
     /// Verify that the equations hold with the given variable replacements and q
     /// For each polynomial from self.initial_polys, substitute the variables with modular polynomials (mod q)
     /// and verify that the result is always 0.
     fn verify_equations_hold(
         &self,
-        var_replacements: &HashMap<u8, (ModularPoly, u8)>,
+        var_replacements: &HashMap<Var, (ModularPoly, u8)>,
         q: &ModularPoly,
-    ) -> bool {
+    ) -> FactorCheck {
         for poly in self.initial_polys {
             // Substitute variables with modular polynomials
             match poly.substitute_modular_polys(var_replacements) {
@@ -366,18 +950,24 @@ impl<'a> Elimination<'a> {
                             "Equation {} = {} (mod {}) is not zero, remainder: {}",
                             poly, substituted_poly, q, remainder
                         );
-                        return false;
+                        return FactorCheck::EquationNotSatisfied {
+                            equation: poly.to_string(),
+                            p: q.p,
+                        };
                     }
                 }
                 Err(e) => {
                     info!("Error substituting variables in {}: {}", poly, e);
-                    return false;
+                    return FactorCheck::SubstitutionFailed {
+                        equation: poly.to_string(),
+                        error: e,
+                    };
                 }
             }
         }
 
         // All equations hold
-        true
+        FactorCheck::Verified
     }
 }
 
@@ -446,6 +1036,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_elimination_step_subresultant() {
+        let poly1 = Poly::new("c^3 + a*c + 1").unwrap();
+        let poly2 = Poly::new("c^2 - b").unwrap();
+        let v = 2; // c
+
+        let mut step = EliminationStep::new(v, Rc::new(poly1), Rc::new(poly2));
+        assert_eq!(step.method, ResultantMethod::Subresultant);
+        while let Some(next_step) = step.get_next_step(false) {
+            assert_eq!(next_step.method, ResultantMethod::Subresultant);
+            step = next_step;
+        }
+        assert_eq!(step.degree_a, 1);
+        // The resultant is (1 + (b + a) c)(1 - (b + a) c) with c^2 = b
+        assert_eq!(
+            *step.poly_b,
+            Poly::new("1 - b^3 - 2*a*b^2 - a^2*b").unwrap()
+        );
+
+        let mut p_b = step.poly1.multiply(&step.p_factor_1b);
+        p_b.add_poly_scaled(&step.poly2.multiply(&step.p_factor_2b), 1);
+        assert_eq!(p_b, *step.poly_b);
+
+        // Low degrees keep the one-term pseudo-remainder steps
+        assert_eq!(
+            ResultantMethod::for_degrees(2, 2),
+            ResultantMethod::PseudoRemainder
+        );
+    }
+
     #[test]
     fn test_elimination_step_with_reduction() {
         let poly1 = Poly::new("a*b*c + c + a + b").unwrap(); // c  (ab + 1) + (a + b) = 0
@@ -476,12 +1096,314 @@ mod tests {
         // Eliminate variable c
         elimination.eliminate_var(var_search_result);
 
-        // Test check_factor for polynomial "b" - should return false
+        // Test check_factor for polynomial "a + 1" - should not be verified
         let wrong_factor = Poly::new("a + 1").unwrap();
-        assert_eq!(elimination.check_factor(&wrong_factor).unwrap(), false);
+        assert_ne!(
+            elimination.check_factor(&wrong_factor).unwrap(),
+            FactorCheck::Verified
+        );
 
-        // Test check_factor for polynomial "a^2 + b^2 - 1" - should return true
+        // Test check_factor for polynomial "a^2 + b^2 - 1" - should be verified
         let correct_factor = Poly::new("a^2 + b^2 - 1").unwrap();
-        assert_eq!(elimination.check_factor(&correct_factor).unwrap(), true);
+        assert_eq!(
+            elimination.check_factor(&correct_factor).unwrap(),
+            FactorCheck::Verified
+        );
+    }
+
+    #[test]
+    fn test_pivots() {
+        let polys = vec![
+            Rc::new(Poly::new("a + a*c^2 - 1 + c^2").unwrap()),
+            Rc::new(Poly::new("b + b*c^2 - 2*c").unwrap()),
+        ];
+        let mut elimination = Elimination::new(&polys, 0, 1, false);
+        assert!(elimination.pivots().is_empty());
+        let var_search_result = elimination.get_var_to_eliminate().unwrap();
+        elimination.eliminate_var(var_search_result);
+
+        let pivots = elimination.pivots();
+        assert_eq!(pivots.len(), 1);
+        let (v, pivot) = &pivots[0];
+        assert_eq!(*v, 2);
+        assert!(pivot.get_degree(2) > 0);
+        // c = 1/2 is the parameter of (3/5, 4/5) on the circle
+        let values = HashMap::from([(0, 0.6), (1, 0.8), (2, 0.5)]);
+        assert!(pivot.eval(&values).unwrap().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_check_factor_inconclusive() {
+        let poly1 = Poly::new("a + a*c^2 - 1 + c^2").unwrap();
+        let poly2 = Poly::new("b + b*c^2 - 2*c").unwrap();
+        let initial_polys = vec![Rc::new(poly1), Rc::new(poly2)];
+        let mut elimination = Elimination::new(&initial_polys, 0, 1, false);
+        let var_search_result = elimination.get_var_to_eliminate().unwrap();
+        elimination.eliminate_var(var_search_result);
+        assert!(elimination.warnings.is_empty());
+
+        // A zero factor vanishes identically, so the check cannot tell anything
+        assert_eq!(
+            elimination.check_factor(&Poly::Constant(0)).unwrap(),
+            FactorCheck::Inconclusive
+        );
+    }
+
+    #[test]
+    fn test_cancelled_elimination() {
+        use crate::cancellation::{with_cancellation, CancellationToken};
+
+        let poly1 = Poly::new("a + a*c^2 - 1 + c^2").unwrap();
+        let poly2 = Poly::new("b + b*c^2 - 2*c").unwrap();
+        let initial_polys = vec![Rc::new(poly1), Rc::new(poly2)];
+        let mut elimination = Elimination::new(&initial_polys, 0, 1, false);
+        let var_search_result = elimination.get_var_to_eliminate().unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        with_cancellation(token, || elimination.eliminate_var(var_search_result));
+        // The system is left as it was, and no variable is offered anymore
+        assert!(elimination.is_cancelled());
+        assert_eq!(elimination.polys, initial_polys);
+        assert!(elimination.eliminated_vars().is_empty());
+        assert!(elimination.get_var_to_eliminate().is_none());
+    }
+
+    #[test]
+    fn test_elimination_budget() {
+        use crate::budget::{with_budget, Budget, BudgetLimit};
+
+        let poly1 = Poly::new("a + a*c^2 - 1 + c^2").unwrap();
+        let poly2 = Poly::new("b + b*c^2 - 2*c").unwrap();
+        let initial_polys = vec![Rc::new(poly1), Rc::new(poly2)];
+        let eliminate = |budget: Budget| {
+            with_budget(budget, || {
+                let mut elimination = Elimination::new(&initial_polys, 0, 1, false);
+                while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+                    elimination.eliminate_var(var_search_result);
+                }
+                elimination.polys
+            })
+        };
+        assert_eq!(eliminate(Budget::default()).unwrap().len(), 1);
+
+        let exceeded = eliminate(Budget {
+            max_elimination_steps: Some(1),
+            ..Budget::default()
+        })
+        .unwrap_err();
+        assert_eq!(exceeded.limit, BudgetLimit::EliminationSteps);
+        assert_eq!(exceeded.context, "the elimination of c");
+
+        let exceeded = eliminate(Budget {
+            max_total_degree: Some(3),
+            ..Budget::default()
+        })
+        .unwrap_err();
+        assert_eq!(exceeded.limit, BudgetLimit::TotalDegree);
+        assert!(exceeded.value > 3);
+    }
+
+    #[test]
+    fn test_incremental_elimination() {
+        let run = |polys: &Vec<Rc<Poly>>| {
+            let mut elimination = Elimination::new(polys, 0, 1, false);
+            elimination.incremental = true;
+            while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+                elimination.eliminate_var(var_search_result);
+            }
+            (elimination.polys.clone(), elimination.cache_hits)
+        };
+
+        let polys = vec![
+            Rc::new(Poly::new("a + a*c^2 - 1 + c^2").unwrap()),
+            Rc::new(Poly::new("b + b*c^2 - 2*c").unwrap()),
+            Rc::new(Poly::new("d^2 - c - 1").unwrap()),
+        ];
+        let (result, cache_hits) = run(&polys);
+        assert_eq!(cache_hits, 0);
+
+        // Rerunning the same system reuses every chain and gives the same result
+        let (cached_result, cache_hits) = run(&polys);
+        assert_eq!(cached_result, result);
+        assert!(cache_hits > 0);
+
+        // Changing a constant in one equation only reuses the unaffected chains
+        let mut changed_polys = polys.clone();
+        changed_polys[1] = Rc::new(Poly::new("b + b*c^2 - 3*c").unwrap());
+        let mut elimination = Elimination::new(&changed_polys, 0, 1, false);
+        while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+            elimination.eliminate_var(var_search_result);
+        }
+        let (incremental_result, _) = run(&changed_polys);
+        assert_eq!(incremental_result, elimination.polys);
+
+        // The chains are shared by all threads
+        let result: Vec<String> = result.iter().map(|poly| poly.to_string()).collect();
+        let (thread_result, cache_hits) = std::thread::spawn(move || {
+            let polys = ["a + a*c^2 - 1 + c^2", "b + b*c^2 - 2*c", "d^2 - c - 1"]
+                .map(|p| Rc::new(Poly::new(p).unwrap()))
+                .to_vec();
+            let (result, cache_hits) = run(&polys);
+            let result: Vec<String> = result.iter().map(|poly| poly.to_string()).collect();
+            (result, cache_hits)
+        })
+        .join()
+        .unwrap();
+        assert_eq!(thread_result, result);
+        assert!(cache_hits > 0);
+    }
+
+    #[test]
+    fn test_elimination_order() {
+        let polys: Vec<Rc<Poly>> = ["c + a*d^2", "c^4 + d^2 + b", "a*c^4 + d^2 - 1"]
+            .iter()
+            .map(|p| Rc::new(Poly::new(p).unwrap()))
+            .collect();
+        // c has degree 1 in the first polynomial, but the product of its degrees is 16,
+        // against 8 for d
+        let choose = |order: EliminationOrder| order.choose_var(&polys, 0, 1).unwrap();
+        assert_eq!(choose(EliminationOrder::MinDegree).var, 2);
+        assert_eq!(
+            choose(EliminationOrder::DegreeProduct),
+            VarSearchResult {
+                var: 3,
+                min_degree: 2,
+                poly_index: 0
+            }
+        );
+        assert_eq!(choose(EliminationOrder::Brown).var, 3);
+
+        for order in [EliminationOrder::DegreeProduct, EliminationOrder::Brown] {
+            let mut elimination = Elimination::new(&polys, 0, 1, false).with_strategy(order);
+            while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+                elimination.eliminate_var(var_search_result);
+            }
+            assert_eq!(elimination.eliminated_vars(), vec![3, 2]);
+            assert_eq!(elimination.polys.len(), 1);
+        }
+
+        // Same highest degrees: Brown prefers the variable in terms of lower total degree
+        let polys: Vec<Rc<Poly>> = ["c^2 + a*b*d^2", "c^2 - d^2 + a"]
+            .iter()
+            .map(|p| Rc::new(Poly::new(p).unwrap()))
+            .collect();
+        assert_eq!(
+            EliminationOrder::Brown
+                .choose_var(&polys, 0, 1)
+                .unwrap()
+                .var,
+            2
+        );
+        let polys = vec![Rc::new(Poly::new("a*b - 1").unwrap())];
+        assert_eq!(EliminationOrder::Brown.choose_var(&polys, 0, 1), None);
+    }
+
+    #[test]
+    fn test_elimination_trace() {
+        let polys: Vec<Rc<Poly>> = ["a - c", "b - c^2"]
+            .iter()
+            .map(|p| Rc::new(Poly::new(p).unwrap()))
+            .collect();
+        let ((), steps) = trace::with_trace(|| {
+            let mut elimination = Elimination::new(&polys, 0, 1, false);
+            while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+                elimination.eliminate_var(var_search_result);
+            }
+        });
+        assert_eq!(
+            steps[0],
+            TraceStep::Pivot {
+                var: "c".to_string(),
+                equation: "a - c".to_string()
+            }
+        );
+        assert!(steps.len() > 1);
+        for step in &steps[1..] {
+            let TraceStep::Resultant { var, resultant, .. } = step else {
+                panic!("Unexpected step {:?}", step);
+            };
+            assert_eq!(var, "c");
+            assert!(!resultant.contains('c'), "{}", resultant);
+        }
+    }
+
+    #[test]
+    fn test_replayed_elimination() {
+        let polys: Vec<Rc<Poly>> = ["a - c", "c - d^2", "b - d^3"]
+            .iter()
+            .map(|p| Rc::new(Poly::new(p).unwrap()))
+            .collect();
+        let eliminate = || {
+            let mut elimination = Elimination::new(&polys, 0, 1, false);
+            while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+                elimination.eliminate_var(var_search_result);
+            }
+            (elimination.eliminated_vars(), elimination.polys)
+        };
+        let ((vars, result), steps) = trace::with_trace(eliminate);
+        assert_eq!(vars, vec![2, 3]);
+        assert_eq!(replay::elimination_order(&steps), Ok(vars));
+
+        // The trace of another order is followed, to the same curve up to a constant
+        let ((replayed_vars, replayed_result), followed) =
+            replay::with_replay(Some(&[3, 2]), eliminate);
+        assert_eq!(replayed_vars, vec![3, 2]);
+        assert_eq!(followed, Some(true));
+        assert_eq!(replayed_result.len(), 1);
+        assert_eq!(replayed_result[0].normalized(), result[0].normalized());
+    }
+
+    #[test]
+    fn test_elimination_removes_content() {
+        let polys = vec![
+            Rc::new(Poly::new("a*c^2 + b").unwrap()),
+            Rc::new(Poly::new("b*c^2 + a*c + 1").unwrap()),
+        ];
+        let mut elimination = Elimination::new(&polys, 0, 1, false);
+        while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+            elimination.eliminate_var(var_search_result);
+        }
+        // The resultant is (a - b^2)^2 + a^3*b
+        let result = elimination.polys[0].clone();
+        assert!(result.is_proportional(
+            &Poly::new("a^2 - 2*a*b^2 + b^4 + a^3*b").unwrap(),
+            &mut None
+        ));
+
+        // Without removing the content a of an intermediate remainder, the chain ends
+        // with an extraneous factor b
+        let mut step = EliminationStep::new(2, polys[0].clone(), polys[1].clone());
+        while let Some(next_step) = step.get_next_step(false) {
+            step = next_step;
+        }
+        let extraneous = Poly::new("b").unwrap().multiply(&result);
+        assert!(step.poly_b.is_proportional(&extraneous, &mut None));
+    }
+
+    #[test]
+    fn test_check_factor_numerically() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let polys: Vec<Rc<Poly>> = ["c^2 + d^2 - 1", "a - c - d", "b - c + d"]
+            .iter()
+            .map(|p| Rc::new(Poly::new(p).unwrap()))
+            .collect();
+        let elimination = Elimination::new(&polys, 0, 1, false);
+        let solutions = elimination.sample_solutions(&mut StdRng::seed_from_u64(7));
+        let check = |factor: &str| {
+            elimination.check_factor_numerically(&Poly::new(factor).unwrap(), &solutions)
+        };
+        assert_eq!(check("a^2 + b^2 - 2"), FactorCheck::Verified);
+        assert_eq!(
+            check("a - 3"),
+            FactorCheck::NotOnSampledSolutions {
+                samples: solutions.len()
+            }
+        );
+        assert_eq!(
+            elimination.check_factor_numerically(&Poly::new("a - 3").unwrap(), &[]),
+            FactorCheck::Inconclusive
+        );
     }
 }
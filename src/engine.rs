@@ -0,0 +1,255 @@
+//! Elimination and plotting of a polynomial system without scenes, for the
+//! JavaScript API and other users of the algebra core.
+//!
+//! This is the plain path of the server: the resultant chains of `Elimination`,
+//! factoring by the selected CAS backend (none in the browser), and the traced
+//! rasterization of `XYPolyDraw`. The factors are not checked against the system.
+
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dd_int::DDXYPoly;
+use crate::elimination::Elimination;
+use crate::fint::FInt;
+use crate::poly::{detect_overflow, Poly, PolyConversion, PolyFormat, PolyOperations, Var};
+use crate::poly_draw::XYPolyDraw;
+use crate::variables;
+use crate::warning::{Warning, WarningCode};
+
+/// Curve cells per pixel in each direction
+const CELLS_PER_PIXEL: u32 = 4;
+
+/// Equations of a system, and the variables of the plane its solutions are projected to
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EliminationRequest {
+    pub equations: Vec<String>,
+    pub x: String,
+    pub y: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EliminationResponse {
+    pub equation: String,
+    pub factors: Vec<String>,
+    pub warnings: Vec<Warning>,
+}
+
+/// The part of the plane shown in a plot of `width` x `height` pixels
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PlotRequest {
+    pub equation: String,
+    pub x: String,
+    pub y: String,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The curve as polylines in pixel coordinates, y growing downwards
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlotResponse {
+    pub polylines: Vec<Vec<(f64, f64)>>,
+}
+
+/// An equation in x and y obtained by elimination, with its factors
+pub struct EliminatedCurve {
+    pub equation: Poly,
+    pub factors: Vec<Poly>,
+    pub warnings: Vec<Warning>,
+}
+
+fn parse_var(name: &str) -> Result<Var, String> {
+    Poly::parse_var(name.trim()).map_err(|e| e.to_string())
+}
+
+fn parse_poly(equation: &str) -> Result<Poly, String> {
+    Poly::parse_equation(equation).map_err(|e| format!("Invalid equation {}: {}", equation, e))
+}
+
+/// Eliminates all variables except x_var and y_var from the system polys = 0
+pub fn eliminate(polys: Vec<Rc<Poly>>, x_var: Var, y_var: Var) -> Result<EliminatedCurve, String> {
+    if x_var == y_var {
+        return Err("x and y variables must be different".to_string());
+    }
+    let elimination = detect_overflow(|| {
+        let mut elimination = Elimination::new(&polys, x_var, y_var, true);
+        while let Some(var_search_result) = elimination.get_var_to_eliminate() {
+            elimination.eliminate_var(var_search_result);
+        }
+        elimination
+    })
+    .map_err(|e| e.to_string())?;
+    let [result] = elimination.polys.as_slice() else {
+        return Err(format!(
+            "Expected exactly one equation after elimination, got {}",
+            elimination.polys.len()
+        ));
+    };
+    let mut vars = result.get_variables();
+    vars.remove(x_var);
+    vars.remove(y_var);
+    if let Some(v) = vars.first() {
+        return Err(format!(
+            "Remaining equation depends on variable {}",
+            variables::describe(v)
+        ));
+    }
+
+    let mut equation = (**result).clone();
+    equation.reduce_coefficients_if_above(1);
+    let mut warnings = elimination.warnings.clone();
+    let factors = match equation.factor() {
+        Ok(factors) => factors,
+        Err(e) => {
+            warnings.push(Warning::new(
+                WarningCode::FactoringFailed,
+                format!(
+                    "Factoring failed, treating the equation as irreducible: {}",
+                    e
+                ),
+            ));
+            vec![equation.clone()]
+        }
+    };
+    Ok(EliminatedCurve {
+        equation,
+        factors,
+        warnings,
+    })
+}
+
+pub fn eliminate_request(request: &EliminationRequest) -> Result<EliminationResponse, String> {
+    let polys = request
+        .equations
+        .iter()
+        .map(|equation| parse_poly(equation).map(Rc::new))
+        .collect::<Result<Vec<_>, _>>()?;
+    let curve = eliminate(polys, parse_var(&request.x)?, parse_var(&request.y)?)?;
+    Ok(EliminationResponse {
+        equation: PolyFormat::new().format(&curve.equation),
+        factors: curve
+            .factors
+            .iter()
+            .map(|factor| PolyFormat::new().format(factor))
+            .collect(),
+        warnings: curve.warnings,
+    })
+}
+
+/// Traces the curve poly = 0 over x_interval x y_interval, shown in `width` x `height` pixels
+pub fn plot(
+    poly: &Poly,
+    x_var: Var,
+    y_var: Var,
+    x_interval: FInt,
+    y_interval: FInt,
+    width: u32,
+    height: u32,
+) -> Result<Vec<Vec<(f64, f64)>>, String> {
+    let mut drawer = XYPolyDraw::new(poly.as_xy_poly(x_var, y_var)?);
+    if DDXYPoly::is_needed_for(poly) {
+        drawer = drawer.with_double_double(DDXYPoly::from_poly(poly, x_var, y_var)?);
+    }
+    let points = drawer.get_curve_points(
+        x_interval,
+        y_interval,
+        width * CELLS_PER_PIXEL,
+        height * CELLS_PER_PIXEL,
+    );
+    // Cell centers, scaled down to the plot size
+    let scale = CELLS_PER_PIXEL as f64;
+    Ok(XYPolyDraw::trace_polylines(&points)
+        .iter()
+        .map(|chain| {
+            chain
+                .iter()
+                .map(|&(x, y)| ((x as f64 + 0.5) / scale, (y as f64 + 0.5) / scale))
+                .collect()
+        })
+        .collect())
+}
+
+pub fn plot_request(request: &PlotRequest) -> Result<PlotResponse, String> {
+    if request.x_min >= request.x_max || request.y_min >= request.y_max {
+        return Err("The plotted rectangle is empty".to_string());
+    }
+    let poly = parse_poly(&request.equation)?;
+    let polylines = plot(
+        &poly,
+        parse_var(&request.x)?,
+        parse_var(&request.y)?,
+        FInt::new_with_bounds(request.x_min, request.x_max),
+        FInt::new_with_bounds(request.y_min, request.y_max),
+        request.width,
+        request.height,
+    )?;
+    Ok(PlotResponse { polylines })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eliminate_request() {
+        // The midpoint (a, b) of (c, 0) and a point (d, e) on the unit circle
+        let request: EliminationRequest = serde_json::from_str(
+            r#"{"equations": ["d^2 + e^2 - 1", "2*a - c - d", "2*b - e", "c - 2"],
+                "x": "a", "y": "b"}"#,
+        )
+        .unwrap();
+        let response = eliminate_request(&request).unwrap();
+        let equation = Poly::new(&response.equation).unwrap();
+        // (2a - 2)^2 + (2b)^2 = 1, up to sign
+        let expected = Poly::new("4*a^2 - 8*a + 4*b^2 + 3").unwrap();
+        let mut negated = expected.clone();
+        negated.scale(-1);
+        assert!(equation == expected || equation == negated);
+
+        let request = EliminationRequest {
+            equations: vec!["a + b".to_string()],
+            x: "a".to_string(),
+            y: "a".to_string(),
+        };
+        assert!(eliminate_request(&request).is_err());
+    }
+
+    #[test]
+    fn test_eliminate_overflow() {
+        // The resultant in c has coefficients far beyond i64
+        let polys = ["10000000000*c^2 + a*c + 1", "10000000000*c^2 + b*c + 3"]
+            .map(|equation| Rc::new(Poly::new(equation).unwrap()));
+        let result = eliminate(polys.to_vec(), 0, 1);
+        assert!(
+            result.as_ref().is_err_and(|e| e.contains("overflow")),
+            "{:?}",
+            result.map(|curve| curve.equation.to_string())
+        );
+    }
+
+    #[test]
+    fn test_plot_request() {
+        let request: PlotRequest = serde_json::from_str(
+            r#"{"equation": "a^2 + b^2 - 1", "x": "a", "y": "b",
+                "x_min": -2, "x_max": 2, "y_min": -2, "y_max": 2, "width": 40, "height": 40}"#,
+        )
+        .unwrap();
+        let response = plot_request(&request).unwrap();
+        assert!(!response.polylines.is_empty());
+        // The unit circle has radius 10 pixels around the center (20, 20)
+        for &(x, y) in response.polylines.iter().flatten() {
+            let radius = ((x - 20.0).powi(2) + (y - 20.0).powi(2)).sqrt();
+            assert!((radius - 10.0).abs() < 1.0, "{} {}", x, y);
+        }
+
+        let empty = PlotRequest {
+            x_max: -2.0,
+            ..request
+        };
+        assert!(plot_request(&empty).is_err());
+    }
+}
@@ -0,0 +1,1986 @@
+//! Rust port of src/py/equation_processor.py.
+//!
+//! The scene is described by the Python-like code produced by `SceneObject::to_python`
+//! (statements such as `X = FreePoint(3, 4)`, `is_constant(d(A, X))` or `plot("P1", X)`).
+//! `EquationCompiler` parses that subset of Python and evaluates it the same way as the
+//! Python module does, allocating variables in the same order, so the resulting equations
+//! are identical to the ones printed by equation_processor.py.
+
+use crate::poly::Var;
+use crate::scene::Plot;
+use crate::variables::{VariableOrigin, VariableRegistry};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CompileError {
+    #[error("Syntax error: {0}")]
+    Syntax(String),
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    Runtime(String),
+}
+
+impl CompileError {
+    /// Whether the code goes beyond the Python subset understood by the compiler,
+    /// so that the Python equation processor may still be able to run it
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self, CompileError::Syntax(_) | CompileError::Unsupported(_))
+    }
+}
+
+type CompileResult<T> = Result<T, CompileError>;
+
+fn runtime_error<T>(message: impl Into<String>) -> CompileResult<T> {
+    Err(CompileError::Runtime(message.into()))
+}
+
+fn checked(value: Option<i64>) -> CompileResult<i64> {
+    value.ok_or_else(|| CompileError::Unsupported("Integer overflow".to_string()))
+}
+
+fn int_pow(base: i64, exponent: i64) -> CompileResult<i64> {
+    let exponent = u32::try_from(exponent)
+        .map_err(|_| CompileError::Unsupported("Integer overflow".to_string()))?;
+    checked(base.checked_pow(exponent))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Initial {
+    Int(i64),
+    Value(Rc<Value>),
+}
+
+impl fmt::Display for Initial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Initial::Int(x) => write!(f, "{}", x),
+            Initial::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+// Types of Value (as in equation_processor.py):
+// - integer constant: var = None, initial = integer value
+// - unknown constant (constant value bound by equations): var = Some, initial = None
+// - variable: var = Some, initial = integer constant or unknown constant
+#[derive(Debug, Clone, PartialEq)]
+struct Value {
+    var: Option<usize>,
+    initial: Option<Initial>,
+    float_initial: Option<f64>,
+    // (n, d) for the rational constants n/d created by q(n, d)
+    rational: Option<(i64, i64)>,
+}
+
+impl Value {
+    fn new(var: Option<usize>, initial: Option<Initial>, float_initial: Option<f64>) -> Self {
+        Value {
+            var,
+            initial,
+            float_initial,
+            rational: None,
+        }
+    }
+
+    fn constant(x: i64, float_initial: Option<f64>) -> Self {
+        Value::new(None, Some(Initial::Int(x)), float_initial)
+    }
+
+    fn maybe_int(self) -> Initial {
+        match (self.var, &self.initial) {
+            (None, Some(Initial::Int(x))) => Initial::Int(*x),
+            _ => Initial::Value(Rc::new(self)),
+        }
+    }
+
+    fn initial_as_int(&self) -> CompileResult<i64> {
+        match self.initial {
+            Some(Initial::Int(x)) => Ok(x),
+            _ => runtime_error(format!("Integer initial value not found in {}", self)),
+        }
+    }
+
+    fn float_initial_as_float(&self) -> CompileResult<f64> {
+        self.float_initial.ok_or_else(|| {
+            CompileError::Runtime(format!("Float initial value not found in {}", self))
+        })
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.var, &self.initial) {
+            (None, Some(initial)) => write!(f, "{}", initial),
+            (None, None) => write!(f, "None"),
+            (Some(var), _) => {
+                let letter = (b'a' + (var % 26) as u8) as char;
+                match var / 26 {
+                    0 => write!(f, "{}", letter),
+                    suffix => write!(f, "{}{}", letter, suffix),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Point {
+    x: Value,
+    y: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Vector {
+    x: Value,
+    y: Value,
+}
+
+/// The line through `o` with the normal vector `n`
+#[derive(Debug, Clone, PartialEq)]
+struct Line {
+    o: Point,
+    n: Vector,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Lambda {
+    params: Vec<String>,
+    body: Expr,
+}
+
+/// The curve f(x, y) = 0, given by f and its partial derivatives
+#[derive(Debug, Clone, PartialEq)]
+struct Curve {
+    f: Rc<Lambda>,
+    f_x: Rc<Lambda>,
+    f_y: Rc<Lambda>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    None,
+    Int(i64),
+    Str(String),
+    Value(Value),
+    Point(Point),
+    Vector(Vector),
+    Line(Line),
+    Curve(Curve),
+    Lambda(Rc<Lambda>),
+}
+
+impl Object {
+    /// The variables of the coordinates of the object, with their paths like "x" or "n.y"
+    fn coordinate_vars(&self) -> Vec<(usize, Option<String>)> {
+        let point = |prefix: &str, x: &Value, y: &Value| {
+            [(x, "x"), (y, "y")]
+                .into_iter()
+                .filter_map(|(value, name)| {
+                    value
+                        .var
+                        .map(|var| (var, Some(format!("{}{}", prefix, name))))
+                })
+                .collect::<Vec<_>>()
+        };
+        match self {
+            Object::Value(value) => value.var.map(|var| (var, None)).into_iter().collect(),
+            Object::Point(p) => point("", &p.x, &p.y),
+            Object::Vector(v) => point("", &v.x, &v.y),
+            Object::Line(line) => {
+                let mut vars = point("o.", &line.o.x, &line.o.y);
+                vars.extend(point("n.", &line.n.x, &line.n.y));
+                vars
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Object::None => "None",
+            Object::Int(_) => "int",
+            Object::Str(_) => "str",
+            Object::Value(_) => "Value",
+            Object::Point(_) => "Point",
+            Object::Vector(_) => "Vector",
+            Object::Line(_) => "Line",
+            Object::Curve(_) => "Curve",
+            Object::Lambda(_) => "function",
+        }
+    }
+
+    fn type_error<T>(&self, expected: &str) -> CompileResult<T> {
+        runtime_error(format!("Expected {}, got {}", expected, self.type_name()))
+    }
+
+    fn into_int(self) -> CompileResult<i64> {
+        match self {
+            Object::Int(x) => Ok(x),
+            other => other.type_error("int"),
+        }
+    }
+
+    fn into_str(self) -> CompileResult<String> {
+        match self {
+            Object::Str(s) => Ok(s),
+            other => other.type_error("str"),
+        }
+    }
+
+    fn into_value(self) -> CompileResult<Value> {
+        match self {
+            Object::Value(value) => Ok(value),
+            other => other.type_error("Value"),
+        }
+    }
+
+    fn into_point(self) -> CompileResult<Point> {
+        match self {
+            Object::Point(point) => Ok(point),
+            other => other.type_error("Point"),
+        }
+    }
+
+    fn into_vector(self) -> CompileResult<Vector> {
+        match self {
+            Object::Vector(vector) => Ok(vector),
+            other => other.type_error("Vector"),
+        }
+    }
+
+    fn into_line(self) -> CompileResult<Line> {
+        match self {
+            Object::Line(line) => Ok(line),
+            other => other.type_error("Line"),
+        }
+    }
+
+    fn into_curve(self) -> CompileResult<Curve> {
+        match self {
+            Object::Curve(curve) => Ok(curve),
+            other => other.type_error("Curve"),
+        }
+    }
+
+    fn into_lambda(self) -> CompileResult<Rc<Lambda>> {
+        match self {
+            Object::Lambda(lambda) => Ok(lambda),
+            other => other.type_error("function"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Int(i64),
+    Str(String),
+    Name(String),
+    Attribute(Box<Expr>, String),
+    Call(String, Vec<Expr>),
+    MethodCall(Box<Expr>, String, Vec<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Lambda(Rc<Lambda>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Statement {
+    Assign(String, Expr),
+    Expr(Expr),
+    Raise(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Name(String),
+    Str(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 11] = ["**", "(", ")", ",", ".", "=", "+", "-", "*", "/", ":"];
+
+fn tokenize(line: &str) -> CompileResult<Vec<Token>> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        let c = chars[pos];
+        if c.is_whitespace() {
+            pos += 1;
+        } else if c == '#' {
+            break;
+        } else if c.is_ascii_digit() {
+            let start = pos;
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            if pos < chars.len() && (chars[pos] == '.' || chars[pos].is_alphabetic()) {
+                return Err(CompileError::Unsupported(format!(
+                    "Number literal in '{}'",
+                    line
+                )));
+            }
+            let literal: String = chars[start..pos].iter().collect();
+            let value = literal
+                .parse()
+                .map_err(|_| CompileError::Unsupported(format!("Integer literal {}", literal)))?;
+            tokens.push(Token::Int(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = pos;
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            tokens.push(Token::Name(chars[start..pos].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let mut s = String::new();
+            pos += 1;
+            loop {
+                match chars.get(pos) {
+                    None => {
+                        return Err(CompileError::Syntax(format!(
+                            "Unterminated string in '{}'",
+                            line
+                        )))
+                    }
+                    Some(&q) if q == c => break,
+                    Some('\\') => {
+                        pos += 1;
+                        match chars.get(pos) {
+                            Some('n') => s.push('\n'),
+                            Some(&escaped) => s.push(escaped),
+                            None => {}
+                        }
+                    }
+                    Some(&other) => s.push(other),
+                }
+                pos += 1;
+            }
+            pos += 1;
+            tokens.push(Token::Str(s));
+        } else {
+            let rest: String = chars[pos..].iter().take(2).collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| {
+                    CompileError::Syntax(format!("Unexpected character '{}' in '{}'", c, line))
+                })?;
+            pos += op.len();
+            tokens.push(Token::Op(op));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_op(&self, op: &str) -> bool {
+        matches!(self.peek(), Some(Token::Op(o)) if *o == op)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect_op(&mut self, op: &str) -> CompileResult<()> {
+        match self.next() {
+            Some(Token::Op(o)) if o == op => Ok(()),
+            token => Err(CompileError::Syntax(format!(
+                "Expected '{}', got {:?}",
+                op, token
+            ))),
+        }
+    }
+
+    fn parse_statement(&mut self) -> CompileResult<Statement> {
+        let statement = match (self.tokens.first(), self.tokens.get(1)) {
+            (Some(Token::Name(name)), _) if name == "raise" => {
+                self.pos = 1;
+                Statement::Raise(self.parse_expr()?)
+            }
+            (Some(Token::Name(name)), Some(Token::Op("="))) => {
+                let name = name.clone();
+                self.pos = 2;
+                Statement::Assign(name, self.parse_expr()?)
+            }
+            _ => Statement::Expr(self.parse_expr()?),
+        };
+        match self.peek() {
+            None => Ok(statement),
+            Some(token) => Err(CompileError::Syntax(format!(
+                "Unexpected token {:?}",
+                token
+            ))),
+        }
+    }
+
+    fn parse_expr(&mut self) -> CompileResult<Expr> {
+        if matches!(self.peek(), Some(Token::Name(name)) if name == "lambda") {
+            self.pos += 1;
+            let mut params = Vec::new();
+            while !self.peek_op(":") {
+                match self.next() {
+                    Some(Token::Name(param)) => params.push(param),
+                    token => {
+                        return Err(CompileError::Syntax(format!(
+                            "Unexpected lambda parameter {:?}",
+                            token
+                        )))
+                    }
+                }
+                if !self.peek_op(":") {
+                    self.expect_op(",")?;
+                }
+            }
+            self.expect_op(":")?;
+            let body = self.parse_expr()?;
+            return Ok(Expr::Lambda(Rc::new(Lambda { params, body })));
+        }
+        self.parse_sum()
+    }
+
+    fn parse_sum(&mut self) -> CompileResult<Expr> {
+        let mut expr = self.parse_product()?;
+        loop {
+            let op = if self.peek_op("+") {
+                BinaryOp::Add
+            } else if self.peek_op("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(expr);
+            };
+            self.pos += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.parse_product()?));
+        }
+    }
+
+    fn parse_product(&mut self) -> CompileResult<Expr> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            let op = if self.peek_op("*") {
+                BinaryOp::Mul
+            } else if self.peek_op("/") {
+                BinaryOp::Div
+            } else {
+                return Ok(expr);
+            };
+            self.pos += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> CompileResult<Expr> {
+        if self.peek_op("-") {
+            self.pos += 1;
+            // Negative literals are plain Python ints
+            return Ok(match self.parse_unary()? {
+                Expr::Int(x) => Expr::Int(checked(x.checked_neg())?),
+                expr => Expr::Neg(Box::new(expr)),
+            });
+        }
+        if self.peek_op("+") {
+            self.pos += 1;
+            return self.parse_unary();
+        }
+        self.parse_power()
+    }
+
+    fn parse_power(&mut self) -> CompileResult<Expr> {
+        let base = self.parse_postfix()?;
+        if self.peek_op("**") {
+            self.pos += 1;
+            let exponent = self.parse_unary()?;
+            return Ok(Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn parse_postfix(&mut self) -> CompileResult<Expr> {
+        let mut expr = self.parse_atom()?;
+        loop {
+            if self.peek_op(".") {
+                self.pos += 1;
+                let name = match self.next() {
+                    Some(Token::Name(name)) => name,
+                    token => {
+                        return Err(CompileError::Syntax(format!(
+                            "Expected attribute name, got {:?}",
+                            token
+                        )))
+                    }
+                };
+                if self.peek_op("(") {
+                    self.pos += 1;
+                    let args = self.parse_args()?;
+                    expr = Expr::MethodCall(Box::new(expr), name, args);
+                } else {
+                    expr = Expr::Attribute(Box::new(expr), name);
+                }
+            } else if self.peek_op("(") {
+                self.pos += 1;
+                let args = self.parse_args()?;
+                expr = match expr {
+                    Expr::Name(name) => Expr::Call(name, args),
+                    _ => {
+                        return Err(CompileError::Unsupported(
+                            "Only named functions and methods can be called".to_string(),
+                        ))
+                    }
+                };
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Parses the arguments of a call after the opening parenthesis
+    fn parse_args(&mut self) -> CompileResult<Vec<Expr>> {
+        let mut args = Vec::new();
+        while !self.peek_op(")") {
+            args.push(self.parse_expr()?);
+            if !self.peek_op(")") {
+                self.expect_op(",")?;
+            }
+        }
+        self.expect_op(")")?;
+        Ok(args)
+    }
+
+    fn parse_atom(&mut self) -> CompileResult<Expr> {
+        match self.next() {
+            Some(Token::Int(x)) => Ok(Expr::Int(x)),
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::Name(name)) if !["lambda", "raise"].contains(&name.as_str()) => {
+                Ok(Expr::Name(name))
+            }
+            Some(Token::Op("(")) => {
+                let expr = self.parse_expr()?;
+                self.expect_op(")")?;
+                Ok(expr)
+            }
+            token => Err(CompileError::Syntax(format!(
+                "Unexpected token {:?}",
+                token
+            ))),
+        }
+    }
+}
+
+fn parse_line(line: &str) -> CompileResult<Option<Statement>> {
+    let tokens = tokenize(line)?;
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+    if line.starts_with(char::is_whitespace) {
+        return Err(CompileError::Syntax(format!(
+            "Unexpected indent in '{}'",
+            line
+        )));
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    parser.parse_statement().map(Some)
+}
+
+type UnaryEquation<'a> = &'a dyn Fn(&Value, &Value) -> CompileResult<String>;
+type BinaryEquation<'a> = &'a dyn Fn(&Value, &Value, &Value) -> CompileResult<String>;
+
+/// The system of a scene together with the values of its variables in the initial
+/// configuration, where the system is satisfied
+pub struct InitialConfiguration {
+    pub equations: Vec<String>,
+    pub plots: Vec<Plot>,
+    pub variables: VariableRegistry,
+    pub values: HashMap<Var, f64>,
+}
+
+/// Evaluates scene code, collecting the equations between the variables and the plots
+pub struct EquationCompiler {
+    current_var: usize,
+    equations: Vec<String>,
+    plots: Vec<Plot>,
+    compute_float_initial: bool,
+    names: HashMap<String, Object>,
+    variables: VariableRegistry,
+    float_initials: HashMap<Var, f64>,
+}
+
+impl EquationCompiler {
+    pub fn new(compute_float_initial: bool) -> Self {
+        EquationCompiler {
+            current_var: 0,
+            equations: Vec::new(),
+            plots: Vec::new(),
+            compute_float_initial,
+            names: HashMap::new(),
+            variables: VariableRegistry::new(),
+            float_initials: HashMap::new(),
+        }
+    }
+
+    /// Compiles scene code into equations and plots, with the objects the variables
+    /// of the equations come from
+    pub fn compile(code: &str) -> Result<(Vec<String>, Vec<Plot>, VariableRegistry), CompileError> {
+        let mut compiler = EquationCompiler::new(false);
+        compiler.run(code)?;
+        Ok((compiler.equations, compiler.plots, compiler.variables))
+    }
+
+    /// Like `compile`, also returning the values of the variables in the initial
+    /// configuration of the scene
+    pub fn compile_at_initial_values(code: &str) -> Result<InitialConfiguration, CompileError> {
+        let mut compiler = EquationCompiler::new(true);
+        compiler.run(code)?;
+        Ok(InitialConfiguration {
+            equations: compiler.equations,
+            plots: compiler.plots,
+            variables: compiler.variables,
+            values: compiler.float_initials,
+        })
+    }
+
+    /// Runs the statements of `code`. The whole code is parsed first, so that
+    /// unsupported syntax is reported before anything is evaluated.
+    pub fn run(&mut self, code: &str) -> Result<(), CompileError> {
+        let mut statements = Vec::new();
+        for line in code.lines() {
+            if let Some(statement) = parse_line(line)? {
+                statements.push(statement);
+            }
+        }
+        for statement in statements {
+            match statement {
+                Statement::Assign(name, expr) => {
+                    let first_var = self.current_var;
+                    let value = self.eval(&expr)?;
+                    self.register_variables(&name, &value, first_var);
+                    self.names.insert(name, value);
+                }
+                Statement::Expr(expr) => {
+                    self.eval(&expr)?;
+                }
+                Statement::Raise(expr) => {
+                    let message = match self.eval(&expr)? {
+                        Object::Str(message) => message,
+                        other => other.type_name().to_string(),
+                    };
+                    return runtime_error(message);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates an expression in the scope of the code run so far and returns
+    /// its float initial value (requires `compute_float_initial`)
+    pub fn evaluate_float(&mut self, expression: &str) -> Result<f64, CompileError> {
+        let expr = match parse_line(expression)? {
+            Some(Statement::Expr(expr)) => expr,
+            _ => {
+                return Err(CompileError::Syntax(format!(
+                    "Expected an expression: '{}'",
+                    expression
+                )))
+            }
+        };
+        self.eval(&expr)?.into_value()?.float_initial_as_float()
+    }
+
+    fn eval(&mut self, expr: &Expr) -> CompileResult<Object> {
+        match expr {
+            Expr::Int(x) => Ok(Object::Int(*x)),
+            Expr::Str(s) => Ok(Object::Str(s.clone())),
+            Expr::Name(name) => self
+                .names
+                .get(name)
+                .cloned()
+                .ok_or_else(|| CompileError::Runtime(format!("Name '{}' is not defined", name))),
+            Expr::Lambda(lambda) => Ok(Object::Lambda(lambda.clone())),
+            Expr::Attribute(object, name) => {
+                let object = self.eval(object)?;
+                Self::get_attribute(object, name)
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<CompileResult<Vec<_>>>()?;
+                match self.names.get(name).cloned() {
+                    Some(Object::Lambda(lambda)) => self.call_lambda(&lambda, args),
+                    Some(other) => {
+                        runtime_error(format!("'{}' object is not callable", other.type_name()))
+                    }
+                    None => self.call_function(name, args),
+                }
+            }
+            Expr::MethodCall(object, name, args) => {
+                let object = self.eval(object)?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<CompileResult<Vec<_>>>()?;
+                self.call_method(object, name, args)
+            }
+            Expr::Neg(expr) => match self.eval(expr)? {
+                Object::Int(x) => Ok(Object::Int(checked(x.checked_neg())?)),
+                Object::Value(value) => Ok(Object::Value(self.neg(&value)?)),
+                other => other.type_error("Value"),
+            },
+            Expr::Binary(op, left, right) => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                self.binary_operation(*op, left, right)
+            }
+        }
+    }
+
+    fn get_attribute(object: Object, name: &str) -> CompileResult<Object> {
+        match (object, name) {
+            (Object::Point(p), "x") => Ok(Object::Value(p.x)),
+            (Object::Point(p), "y") => Ok(Object::Value(p.y)),
+            (Object::Vector(v), "x") => Ok(Object::Value(v.x)),
+            (Object::Vector(v), "y") => Ok(Object::Value(v.y)),
+            (Object::Line(line), "o") => Ok(Object::Point(line.o)),
+            (Object::Line(line), "n") => Ok(Object::Vector(line.n)),
+            (Object::Curve(curve), "f") => Ok(Object::Lambda(curve.f)),
+            (Object::Curve(curve), "f_x") => Ok(Object::Lambda(curve.f_x)),
+            (Object::Curve(curve), "f_y") => Ok(Object::Lambda(curve.f_y)),
+            (object, _) => Err(CompileError::Unsupported(format!(
+                "Attribute '{}' of {}",
+                name,
+                object.type_name()
+            ))),
+        }
+    }
+
+    fn call_lambda(&mut self, lambda: &Lambda, args: Vec<Object>) -> CompileResult<Object> {
+        if args.len() != lambda.params.len() {
+            return runtime_error(format!(
+                "Function takes {} arguments, got {}",
+                lambda.params.len(),
+                args.len()
+            ));
+        }
+        let saved: Vec<(String, Option<Object>)> = lambda
+            .params
+            .iter()
+            .zip(args)
+            .map(|(param, arg)| (param.clone(), self.names.insert(param.clone(), arg)))
+            .collect();
+        let result = self.eval(&lambda.body);
+        for (param, previous) in saved {
+            match previous {
+                Some(object) => self.names.insert(param, object),
+                None => self.names.remove(&param),
+            };
+        }
+        result
+    }
+
+    fn call_function(&mut self, name: &str, args: Vec<Object>) -> CompileResult<Object> {
+        let expected_args = match name {
+            "distance_sum" => args.len().max(2),
+            "i" | "new_var" | "sqrt" | "radical" | "is_constant" | "is_zero" | "is_zero_vector"
+            | "Exception" => 1,
+            "AngleBisector" | "Curve" | "ScaledVectorPoint" => 3,
+            "PointOnSegmentRatio" => 4,
+            "q" | "Point" | "Vector" | "Line" | "FixedPoint" | "FreePoint" | "FixedVector"
+            | "FreeVector" | "Midpoint" | "IntersectionPoint" | "LineIntersection"
+            | "Projection" | "Reflection" | "LineAB" | "PpBisector" | "LLAngleBisector"
+            | "PpToLine" | "PlToLine" | "TangentLine" | "d" | "d_sqr" | "cot" | "plot" => 2,
+            _ => {
+                return Err(CompileError::Unsupported(format!(
+                    "Unknown function: {}",
+                    name
+                )))
+            }
+        };
+        if args.len() != expected_args {
+            return runtime_error(format!(
+                "{}() takes {} arguments, got {}",
+                name,
+                expected_args,
+                args.len()
+            ));
+        }
+        let mut args = args.into_iter();
+        let mut arg = || args.next().unwrap();
+        let result = match name {
+            "i" => Object::Value(self.to_value(arg())?),
+            "q" => {
+                let n = arg().into_int()?;
+                Object::Value(self.rational(n, arg().into_int()?)?)
+            }
+            "new_var" => Object::Value(self.new_var(arg().into_int()?)),
+            "sqrt" => Object::Value(self.sqrt(&arg().into_value()?)?),
+            "radical" => Object::Value(self.radical(&arg().into_value()?)?),
+            "Point" => Object::Point(Point {
+                x: arg().into_value()?,
+                y: arg().into_value()?,
+            }),
+            "Vector" => Object::Vector(Vector {
+                x: arg().into_value()?,
+                y: arg().into_value()?,
+            }),
+            "Line" => Object::Line(Line {
+                o: arg().into_point()?,
+                n: arg().into_vector()?,
+            }),
+            "FixedPoint" => Object::Point(Point {
+                x: self.to_value(arg())?,
+                y: self.to_value(arg())?,
+            }),
+            "FreePoint" => Object::Point(Point {
+                x: self.new_var(arg().into_int()?),
+                y: self.new_var(arg().into_int()?),
+            }),
+            "FixedVector" => Object::Vector(Vector {
+                x: self.to_value(arg())?,
+                y: self.to_value(arg())?,
+            }),
+            "FreeVector" => Object::Vector(Vector {
+                x: self.new_var(arg().into_int()?),
+                y: self.new_var(arg().into_int()?),
+            }),
+            "Midpoint" => {
+                let point1 = arg().into_point()?;
+                Object::Point(self.midpoint(&point1, &arg().into_point()?)?)
+            }
+            "PointOnSegmentRatio" => {
+                let point1 = arg().into_point()?;
+                let point2 = arg().into_point()?;
+                let n = arg().into_int()?;
+                let d = arg().into_int()?;
+                Object::Point(self.point_on_segment_ratio(&point1, &point2, n, d)?)
+            }
+            "IntersectionPoint" => {
+                let line1 = arg().into_line()?;
+                Object::Point(self.intersection_point(&line1, &arg().into_line()?)?)
+            }
+            "LineIntersection" => {
+                let line1 = arg().into_line()?;
+                Object::Point(self.line_intersection(&line1, &arg().into_line()?)?)
+            }
+            "Projection" => {
+                let point = arg().into_point()?;
+                Object::Point(self.projection(&point, &arg().into_line()?)?)
+            }
+            "Reflection" => {
+                let point = arg().into_point()?;
+                Object::Point(self.reflection(&point, &arg().into_line()?)?)
+            }
+            "ScaledVectorPoint" => {
+                let k = arg().into_value()?;
+                let point1 = arg().into_point()?;
+                let point2 = arg().into_point()?;
+                Object::Point(self.scaled_vector_point(&k, &point1, &point2)?)
+            }
+            "LineAB" => {
+                let a = arg().into_point()?;
+                let b = arg().into_point()?;
+                let ab = self.point_sub(&b, &a)?;
+                Object::Line(Line {
+                    o: a,
+                    n: self.rotated90(&ab)?,
+                })
+            }
+            "PpBisector" => {
+                let a = arg().into_point()?;
+                Object::Line(self.pp_bisector(&a, &arg().into_point()?)?)
+            }
+            "AngleBisector" => {
+                let a = arg().into_point()?;
+                let vertex = arg().into_point()?;
+                let b = arg().into_point()?;
+                Object::Line(self.angle_bisector(&a, &vertex, &b)?)
+            }
+            "LLAngleBisector" => {
+                let line1 = arg().into_line()?;
+                Object::Line(self.ll_angle_bisector(&line1, &arg().into_line()?)?)
+            }
+            "PpToLine" => {
+                let point = arg().into_point()?;
+                let line = arg().into_line()?;
+                Object::Line(Line {
+                    o: point,
+                    n: self.rotated90(&line.n)?,
+                })
+            }
+            "PlToLine" => {
+                let point = arg().into_point()?;
+                let line = arg().into_line()?;
+                Object::Line(Line {
+                    o: point,
+                    n: line.n,
+                })
+            }
+            "Curve" => Object::Curve(Curve {
+                f: arg().into_lambda()?,
+                f_x: arg().into_lambda()?,
+                f_y: arg().into_lambda()?,
+            }),
+            "TangentLine" => {
+                // The point lies on the curve (f = 0), and the gradient of f is normal to the tangent
+                let curve = arg().into_curve()?;
+                let point = arg().into_point()?;
+                self.curve_contains(&curve, &point)?;
+                let gradient = self.curve_gradient(&curve, &point)?;
+                Object::Line(Line {
+                    o: point,
+                    n: gradient,
+                })
+            }
+            "d" => {
+                let a = arg();
+                Object::Value(self.d(a, arg())?)
+            }
+            "d_sqr" => {
+                let a = arg();
+                Object::Value(self.d_sqr(a, arg())?)
+            }
+            "distance_sum" => {
+                let point = arg();
+                let mut result = self.d(arg(), point.clone())?;
+                for _ in 2..expected_args {
+                    let distance = self.d(arg(), point.clone())?;
+                    result = self.add(&result, &distance)?;
+                }
+                Object::Value(result)
+            }
+            "cot" => {
+                let a = arg().into_vector()?;
+                Object::Value(self.cot(&a, &arg().into_vector()?)?)
+            }
+            "is_constant" => {
+                self.is_constant(&arg().into_value()?)?;
+                Object::None
+            }
+            "is_zero" => {
+                self.is_zero(&arg().into_value()?)?;
+                Object::None
+            }
+            "is_zero_vector" => {
+                let v = arg().into_vector()?;
+                self.is_zero(&v.x)?;
+                self.is_zero(&v.y)?;
+                Object::None
+            }
+            "plot" => {
+                let name = arg().into_str()?;
+                let point = arg().into_point()?;
+                self.plots.push(Plot {
+                    name,
+                    x: point.x.to_string(),
+                    y: point.y.to_string(),
+                });
+                Object::None
+            }
+            "Exception" => Object::Str(arg().into_str()?),
+            _ => unreachable!("Function {} is checked above", name),
+        };
+        Ok(result)
+    }
+
+    fn call_method(
+        &mut self,
+        object: Object,
+        name: &str,
+        args: Vec<Object>,
+    ) -> CompileResult<Object> {
+        let mut args = args.into_iter();
+        let result = match (object, name, args.next()) {
+            (Object::Value(value), "abs", None) => Object::Value(self.abs(&value)?),
+            (Object::Vector(v), "rotated90", None) => Object::Vector(self.rotated90(&v)?),
+            (Object::Vector(v), "length_sqr", None) => Object::Value(self.dot(&v, &v)?),
+            (Object::Vector(v), "length", None) => Object::Value(self.length(&v)?),
+            (Object::Line(line), "contains", Some(Object::Point(p))) => {
+                self.line_contains(&line, &p)?;
+                Object::None
+            }
+            (Object::Line(line), "distance_to_point", Some(Object::Point(p))) => {
+                Object::Value(self.distance_to_point(&line, &p)?)
+            }
+            (Object::Line(line), "distance_to_point_sqr", Some(Object::Point(p))) => {
+                Object::Value(self.distance_to_point_sqr(&line, &p)?)
+            }
+            (Object::Curve(curve), "contains", Some(Object::Point(p))) => {
+                self.curve_contains(&curve, &p)?;
+                Object::None
+            }
+            (Object::Curve(curve), "gradient", Some(Object::Point(p))) => {
+                Object::Vector(self.curve_gradient(&curve, &p)?)
+            }
+            (object, _, _) => {
+                return Err(CompileError::Unsupported(format!(
+                    "Method '{}' of {}",
+                    name,
+                    object.type_name()
+                )))
+            }
+        };
+        if args.next().is_some() {
+            return runtime_error(format!("Too many arguments for {}()", name));
+        }
+        Ok(result)
+    }
+
+    fn binary_operation(
+        &mut self,
+        op: BinaryOp,
+        left: Object,
+        right: Object,
+    ) -> CompileResult<Object> {
+        let result = match (op, left, right) {
+            (BinaryOp::Add, Object::Int(a), Object::Int(b)) => {
+                Object::Int(checked(a.checked_add(b))?)
+            }
+            (BinaryOp::Sub, Object::Int(a), Object::Int(b)) => {
+                Object::Int(checked(a.checked_sub(b))?)
+            }
+            (BinaryOp::Mul, Object::Int(a), Object::Int(b)) => {
+                Object::Int(checked(a.checked_mul(b))?)
+            }
+            (BinaryOp::Add, Object::Value(a), Object::Value(b)) => Object::Value(self.add(&a, &b)?),
+            (BinaryOp::Sub, Object::Value(a), Object::Value(b)) => Object::Value(self.sub(&a, &b)?),
+            (BinaryOp::Mul, Object::Value(a), Object::Value(b)) => Object::Value(self.mul(&a, &b)?),
+            (BinaryOp::Div, Object::Value(a), Object::Value(b)) => Object::Value(self.div(&a, &b)?),
+            (BinaryOp::Pow, Object::Value(a), Object::Value(b)) => Object::Value(self.pow(&a, &b)?),
+            (BinaryOp::Mul, Object::Value(k), Object::Vector(v))
+            | (BinaryOp::Mul, Object::Vector(v), Object::Value(k)) => {
+                Object::Vector(self.scale(&v, &k)?)
+            }
+            (BinaryOp::Div, Object::Vector(v), Object::Value(k)) => {
+                Object::Vector(self.vector_div(&v, &k)?)
+            }
+            (BinaryOp::Mul, Object::Vector(a), Object::Vector(b)) => {
+                Object::Value(self.dot(&a, &b)?)
+            }
+            (BinaryOp::Add, Object::Vector(a), Object::Vector(b)) => {
+                Object::Vector(self.vector_add(&a, &b)?)
+            }
+            (BinaryOp::Sub, Object::Vector(a), Object::Vector(b)) => {
+                Object::Vector(self.vector_sub(&a, &b)?)
+            }
+            (BinaryOp::Add, Object::Point(p), Object::Vector(v)) => {
+                Object::Point(self.point_add(&p, &v)?)
+            }
+            (BinaryOp::Sub, Object::Point(a), Object::Point(b)) => {
+                Object::Vector(self.point_sub(&a, &b)?)
+            }
+            (op, left, right) => {
+                return runtime_error(format!(
+                    "Unsupported operand types for {:?}: {} and {}",
+                    op,
+                    left.type_name(),
+                    right.type_name()
+                ))
+            }
+        };
+        Ok(result)
+    }
+
+    /// Attributes the variables created by the assignment to `name`: the coordinates
+    /// of `object`, and the intermediate results from `first_var` on
+    fn register_variables(&mut self, name: &str, object: &Object, first_var: usize) {
+        let coordinates = object.coordinate_vars();
+        let auxiliary_vars = (first_var..self.current_var)
+            .filter(|var| !coordinates.iter().any(|(v, _)| v == var))
+            .map(|var| (var, None, true));
+        let vars = coordinates
+            .iter()
+            .map(|(var, coordinate)| (*var, coordinate.clone(), false))
+            .chain(auxiliary_vars);
+        for (var, coordinate, auxiliary) in vars {
+            // Polynomials have at most 65536 variables, the rest can't be described
+            if let Ok(var) = Var::try_from(var) {
+                self.variables.insert(
+                    var,
+                    VariableOrigin {
+                        object: name.to_string(),
+                        coordinate,
+                        auxiliary,
+                    },
+                );
+            }
+        }
+    }
+
+    fn next_var(&mut self) -> usize {
+        let var = self.current_var;
+        self.current_var += 1;
+        var
+    }
+
+    fn record_float_initial(&mut self, var: usize, float_initial: Option<f64>) {
+        if let (Some(value), Ok(var)) = (float_initial, Var::try_from(var)) {
+            self.float_initials.insert(var, value);
+        }
+    }
+
+    /// A new variable, recording its float initial value for `compile_at_initial_values`
+    fn variable(&mut self, initial: Option<Initial>, float_initial: Option<f64>) -> Value {
+        let var = self.next_var();
+        self.record_float_initial(var, float_initial);
+        Value::new(Some(var), initial, float_initial)
+    }
+
+    fn maybe_float_initial(
+        &self,
+        f: impl FnOnce() -> CompileResult<f64>,
+    ) -> CompileResult<Option<f64>> {
+        if self.compute_float_initial {
+            Ok(Some(f()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// i(x): an integer constant; values are passed through
+    fn to_value(&self, object: Object) -> CompileResult<Value> {
+        match object {
+            Object::Int(x) => Ok(self.int(x)),
+            Object::Value(value) => Ok(value),
+            other => other.type_error("int"),
+        }
+    }
+
+    fn int(&self, x: i64) -> Value {
+        let float_initial = self.compute_float_initial.then_some(x as f64);
+        Value::constant(x, float_initial)
+    }
+
+    fn initial_value(&self, initial: &Initial) -> Value {
+        match initial {
+            Initial::Int(x) => self.int(*x),
+            Initial::Value(value) => (**value).clone(),
+        }
+    }
+
+    fn new_var(&mut self, x: i64) -> Value {
+        let float_initial = self.compute_float_initial.then_some(x as f64);
+        self.variable(Some(Initial::Int(x)), float_initial)
+    }
+
+    /// q(n, d): an unknown constant bound by d * q - n = 0
+    fn rational(&mut self, n: i64, d: i64) -> CompileResult<Value> {
+        if d == 0 {
+            return runtime_error("division by zero");
+        }
+        let mut value = self.variable(None, Some(n as f64 / d as f64));
+        value.rational = Some((n, d));
+        self.equations.push(format!("{}*{} - {}", d, value, n));
+        Ok(value)
+    }
+
+    fn integer_valued_operation(
+        &mut self,
+        a: &Value,
+        eq: UnaryEquation,
+        v: &dyn Fn(i64) -> CompileResult<i64>,
+        vf: &dyn Fn(f64) -> CompileResult<f64>,
+    ) -> CompileResult<Value> {
+        let float_initial = self.maybe_float_initial(|| vf(a.float_initial_as_float()?))?;
+        if a.var.is_none() {
+            return Ok(Value::constant(v(a.initial_as_int()?)?, float_initial));
+        }
+        let initial = match &a.initial {
+            None => None,
+            Some(initial) => {
+                let initial = self.initial_value(initial);
+                Some(
+                    self.integer_valued_operation(&initial, eq, v, vf)?
+                        .maybe_int(),
+                )
+            }
+        };
+        let result = self.variable(initial, float_initial);
+        self.equations.push(eq(a, &result)?);
+        Ok(result)
+    }
+
+    fn non_integer_valued_operation(
+        &mut self,
+        a: &Value,
+        eq: UnaryEquation,
+        vf: &dyn Fn(f64) -> CompileResult<f64>,
+    ) -> CompileResult<Value> {
+        let float_initial = self.maybe_float_initial(|| vf(a.float_initial_as_float()?))?;
+        let initial = match (a.var, &a.initial) {
+            (Some(_), Some(initial)) => {
+                let initial = self.initial_value(initial);
+                Some(
+                    self.non_integer_valued_operation(&initial, eq, vf)?
+                        .maybe_int(),
+                )
+            }
+            _ => None,
+        };
+        let result = self.variable(initial, float_initial);
+        self.equations.push(eq(a, &result)?);
+        Ok(result)
+    }
+
+    /// An unknown constant combined with a variable is elevated to a variable
+    /// whose initial is the unknown constant itself
+    fn elevate_unknown_constants(a: &Value, b: &Value) -> (Value, Value) {
+        let elevate = |x: &Value| {
+            Value::new(
+                x.var,
+                Some(Initial::Value(Rc::new(x.clone()))),
+                x.float_initial,
+            )
+        };
+        let mut arg1 = a.clone();
+        let mut arg2 = b.clone();
+        if arg1.initial.is_none() && arg2.initial.is_some() && arg2.var.is_some() {
+            arg1 = elevate(&arg1);
+        }
+        if arg2.initial.is_none() && arg1.initial.is_some() && arg1.var.is_some() {
+            arg2 = elevate(&arg2);
+        }
+        (arg1, arg2)
+    }
+
+    // Valid combinations:
+    // - constant + constant -> constant
+    // - constant + unknown -> unknown (with equation)
+    // - unknown + unknown -> unknown (with equation)
+    // - constant + variable -> variable (with equation)
+    // - variable + variable -> variable (with equation)
+    fn integer_valued_binary_operation(
+        &mut self,
+        a: &Value,
+        b: &Value,
+        eq: BinaryEquation,
+        v: &dyn Fn(i64, i64) -> CompileResult<i64>,
+        vf: &dyn Fn(f64, f64) -> CompileResult<f64>,
+    ) -> CompileResult<Value> {
+        let float_initial = self
+            .maybe_float_initial(|| vf(a.float_initial_as_float()?, b.float_initial_as_float()?))?;
+        if a.var.is_none() && b.var.is_none() {
+            return Ok(Value::constant(
+                v(a.initial_as_int()?, b.initial_as_int()?)?,
+                float_initial,
+            ));
+        }
+        let (arg1, arg2) = Self::elevate_unknown_constants(a, b);
+        let initial = match (&arg1.initial, &arg2.initial) {
+            (Some(initial1), Some(initial2)) => {
+                let initial1 = self.initial_value(initial1);
+                let initial2 = self.initial_value(initial2);
+                Some(
+                    self.integer_valued_binary_operation(&initial1, &initial2, eq, v, vf)?
+                        .maybe_int(),
+                )
+            }
+            _ => None,
+        };
+        let result = self.variable(initial, float_initial);
+        self.equations.push(eq(a, b, &result)?);
+        Ok(result)
+    }
+
+    fn non_integer_valued_binary_operation(
+        &mut self,
+        a: &Value,
+        b: &Value,
+        eq: BinaryEquation,
+        vf: &dyn Fn(f64, f64) -> CompileResult<f64>,
+    ) -> CompileResult<Value> {
+        let float_initial = self
+            .maybe_float_initial(|| vf(a.float_initial_as_float()?, b.float_initial_as_float()?))?;
+        let (arg1, arg2) = Self::elevate_unknown_constants(a, b);
+        let initial = match (&arg1.initial, &arg2.initial) {
+            (Some(initial1), Some(initial2)) if arg1.var.is_some() || arg2.var.is_some() => {
+                let initial1 = self.initial_value(initial1);
+                let initial2 = self.initial_value(initial2);
+                Some(
+                    self.non_integer_valued_binary_operation(&initial1, &initial2, eq, vf)?
+                        .maybe_int(),
+                )
+            }
+            _ => None,
+        };
+        let result = self.variable(initial, float_initial);
+        self.equations.push(eq(&arg1, &arg2, &result)?);
+        Ok(result)
+    }
+
+    fn neg(&mut self, a: &Value) -> CompileResult<Value> {
+        self.integer_valued_operation(
+            a,
+            &|a, b| Ok(format!("{} + {}", a, b)),
+            &|a| checked(a.checked_neg()),
+            &|a| Ok(-a),
+        )
+    }
+
+    fn abs(&mut self, a: &Value) -> CompileResult<Value> {
+        self.integer_valued_operation(
+            a,
+            &|a, b| Ok(format!("{}^2 - {}^2", a, b)),
+            &|a| checked(a.checked_abs()),
+            &|a| Ok(a.abs()),
+        )
+    }
+
+    fn add(&mut self, a: &Value, b: &Value) -> CompileResult<Value> {
+        self.integer_valued_binary_operation(
+            a,
+            b,
+            &|a, b, c| Ok(format!("{} + {} - {}", a, b, c)),
+            &|a, b| checked(a.checked_add(b)),
+            &|a, b| Ok(a + b),
+        )
+    }
+
+    fn sub(&mut self, a: &Value, b: &Value) -> CompileResult<Value> {
+        self.integer_valued_binary_operation(
+            a,
+            b,
+            &|a, b, c| Ok(format!("{} - {} - {}", a, b, c)),
+            &|a, b| checked(a.checked_sub(b)),
+            &|a, b| Ok(a - b),
+        )
+    }
+
+    fn mul(&mut self, a: &Value, b: &Value) -> CompileResult<Value> {
+        self.integer_valued_binary_operation(
+            a,
+            b,
+            &|a, b, c| Ok(format!("{}*{} - {}", a, b, c)),
+            &|a, b| checked(a.checked_mul(b)),
+            &|a, b| Ok(a * b),
+        )
+    }
+
+    fn div(&mut self, a: &Value, b: &Value) -> CompileResult<Value> {
+        self.non_integer_valued_binary_operation(
+            a,
+            b,
+            &|a, b, c| Ok(format!("{} - {}*{}", a, b, c)),
+            &|a, b| {
+                if b == 0.0 {
+                    return runtime_error("float division by zero");
+                }
+                Ok(a / b)
+            },
+        )
+    }
+
+    fn pow(&mut self, a: &Value, power: &Value) -> CompileResult<Value> {
+        let vf = |a: f64| -> CompileResult<f64> { Ok(a.powf(power.float_initial_as_float()?)) };
+        if let Some((n, d)) = power.rational {
+            return if n > 0 {
+                self.non_integer_valued_operation(
+                    a,
+                    &|a, b| match a.var {
+                        Some(_) => Ok(format!("{}^{} - {}^{}", a, n, b, d)),
+                        None => Ok(format!(
+                            "{} - {}^{}",
+                            int_pow(a.initial_as_int()?, n)?,
+                            b,
+                            d
+                        )),
+                    },
+                    &vf,
+                )
+            } else {
+                self.non_integer_valued_operation(
+                    a,
+                    &|a, b| match a.var {
+                        Some(_) => Ok(format!("1 - {}^{}*{}^{}", a, -n, b, d)),
+                        None => Ok(format!(
+                            "1 - {}*{}^{}",
+                            int_pow(a.initial_as_int()?, -n)?,
+                            b,
+                            d
+                        )),
+                    },
+                    &vf,
+                )
+            };
+        }
+        if power.var.is_some() {
+            return runtime_error("Only constant integer powers are supported");
+        }
+        let p = power.initial_as_int()?;
+        if p > 0 {
+            self.integer_valued_operation(
+                a,
+                &|a, b| Ok(format!("{}^{} - {}", a, p, b)),
+                &|a| int_pow(a, p),
+                &vf,
+            )
+        } else {
+            let minus_p = checked(p.checked_neg())?;
+            self.non_integer_valued_operation(
+                a,
+                &|a, b| match a.var {
+                    Some(_) => Ok(format!("1 - {}^{}*{}", a, minus_p, b)),
+                    None => Ok(format!(
+                        "1 - {}*{}",
+                        int_pow(a.initial_as_int()?, minus_p)?,
+                        b
+                    )),
+                },
+                &vf,
+            )
+        }
+    }
+
+    fn sqrt(&mut self, x: &Value) -> CompileResult<Value> {
+        let half = self.rational(1, 2)?;
+        self.pow(x, &half)
+    }
+
+    /// A new variable r with r^2 = x
+    fn radical(&mut self, x: &Value) -> CompileResult<Value> {
+        self.non_integer_valued_operation(x, &|a, b| Ok(format!("{} - {}^2", a, b)), &|a| {
+            if a < 0.0 {
+                return runtime_error("math domain error");
+            }
+            Ok(a.sqrt())
+        })
+    }
+
+    fn is_constant(&mut self, x: &Value) -> CompileResult<()> {
+        if x.var.is_none() {
+            return Ok(());
+        }
+        match &x.initial {
+            None => runtime_error("is_constant() call for an \"unknown value\" is not allowed"),
+            Some(initial) => {
+                self.equations.push(format!("{} - {}", x, initial));
+                Ok(())
+            }
+        }
+    }
+
+    fn is_zero(&mut self, x: &Value) -> CompileResult<()> {
+        if matches!(x.initial, Some(Initial::Int(v)) if v != 0) {
+            return runtime_error(format!(
+                "is_zero() call for a non-zero constant {} is not allowed",
+                x
+            ));
+        }
+        if x.var.is_none() {
+            return Ok(());
+        }
+        self.equations.push(x.to_string());
+        if let Some(initial @ Initial::Value(_)) = &x.initial {
+            self.equations.push(initial.to_string());
+        }
+        Ok(())
+    }
+
+    fn point_add(&mut self, p: &Point, v: &Vector) -> CompileResult<Point> {
+        let x = self.add(&p.x, &v.x)?;
+        let y = self.add(&p.y, &v.y)?;
+        Ok(Point { x, y })
+    }
+
+    fn point_sub(&mut self, a: &Point, b: &Point) -> CompileResult<Vector> {
+        let x = self.sub(&a.x, &b.x)?;
+        let y = self.sub(&a.y, &b.y)?;
+        Ok(Vector { x, y })
+    }
+
+    fn vector_add(&mut self, a: &Vector, b: &Vector) -> CompileResult<Vector> {
+        let x = self.add(&a.x, &b.x)?;
+        let y = self.add(&a.y, &b.y)?;
+        Ok(Vector { x, y })
+    }
+
+    fn vector_sub(&mut self, a: &Vector, b: &Vector) -> CompileResult<Vector> {
+        let x = self.sub(&a.x, &b.x)?;
+        let y = self.sub(&a.y, &b.y)?;
+        Ok(Vector { x, y })
+    }
+
+    fn scale(&mut self, v: &Vector, k: &Value) -> CompileResult<Vector> {
+        let x = self.mul(&v.x, k)?;
+        let y = self.mul(&v.y, k)?;
+        Ok(Vector { x, y })
+    }
+
+    fn vector_div(&mut self, v: &Vector, k: &Value) -> CompileResult<Vector> {
+        let inverse = self.div(&self.int(1), k)?;
+        self.scale(v, &inverse)
+    }
+
+    fn dot(&mut self, a: &Vector, b: &Vector) -> CompileResult<Value> {
+        let x = self.mul(&a.x, &b.x)?;
+        let y = self.mul(&a.y, &b.y)?;
+        self.add(&x, &y)
+    }
+
+    fn rotated90(&mut self, v: &Vector) -> CompileResult<Vector> {
+        Ok(Vector {
+            x: v.y.clone(),
+            y: self.neg(&v.x)?,
+        })
+    }
+
+    fn length(&mut self, v: &Vector) -> CompileResult<Value> {
+        let length_sqr = self.dot(v, v)?;
+        self.sqrt(&length_sqr)
+    }
+
+    fn line_contains(&mut self, line: &Line, p: &Point) -> CompileResult<()> {
+        let op = self.point_sub(p, &line.o)?;
+        let v = self.dot(&op, &line.n)?;
+        self.push_zero(&v);
+        Ok(())
+    }
+
+    /// Adds the equation v = 0, and the same for its initial value
+    fn push_zero(&mut self, v: &Value) {
+        self.equations.push(v.to_string());
+        if let Some(initial) = &v.initial {
+            self.equations.push(initial.to_string());
+        }
+    }
+
+    fn distance_to_point_sqr(&mut self, line: &Line, p: &Point) -> CompileResult<Value> {
+        let op = self.point_sub(p, &line.o)?;
+        let dot = self.dot(&op, &line.n)?;
+        let dot_sqr = self.pow(&dot, &self.int(2))?;
+        let length_sqr = self.dot(&line.n, &line.n)?;
+        self.div(&dot_sqr, &length_sqr)
+    }
+
+    fn distance_to_point(&mut self, line: &Line, p: &Point) -> CompileResult<Value> {
+        let op = self.point_sub(p, &line.o)?;
+        let dot = self.dot(&op, &line.n)?;
+        let length = self.length(&line.n)?;
+        self.div(&dot, &length)
+    }
+
+    fn d(&mut self, a: Object, b: Object) -> CompileResult<Value> {
+        match (a, b) {
+            (Object::Line(line), Object::Point(p)) | (Object::Point(p), Object::Line(line)) => {
+                self.distance_to_point(&line, &p)
+            }
+            (a @ Object::Point(_), b @ Object::Point(_)) => {
+                let d_sqr = self.d_sqr(a, b)?;
+                self.radical(&d_sqr)
+            }
+            _ => runtime_error("d() cannot be called with two lines"),
+        }
+    }
+
+    fn d_sqr(&mut self, a: Object, b: Object) -> CompileResult<Value> {
+        match (a, b) {
+            (Object::Line(line), Object::Point(p)) | (Object::Point(p), Object::Line(line)) => {
+                self.distance_to_point_sqr(&line, &p)
+            }
+            (Object::Point(a), Object::Point(b)) => {
+                let dx = self.sub(&a.x, &b.x)?;
+                let dx_sqr = self.pow(&dx, &self.int(2))?;
+                let dy = self.sub(&a.y, &b.y)?;
+                let dy_sqr = self.pow(&dy, &self.int(2))?;
+                self.add(&dx_sqr, &dy_sqr)
+            }
+            _ => runtime_error("d() cannot be called with two lines"),
+        }
+    }
+
+    fn cot(&mut self, a: &Vector, b: &Vector) -> CompileResult<Value> {
+        let dot = self.dot(a, b)?;
+        let xy = self.mul(&a.x, &b.y)?;
+        let yx = self.mul(&a.y, &b.x)?;
+        let cross = self.sub(&xy, &yx)?;
+        self.div(&dot, &cross)
+    }
+
+    fn midpoint(&mut self, point1: &Point, point2: &Point) -> CompileResult<Point> {
+        let x = self.add(&point1.x, &point2.x)?;
+        let x = self.div(&x, &self.int(2))?;
+        let y = self.add(&point1.y, &point2.y)?;
+        let y = self.div(&y, &self.int(2))?;
+        Ok(Point { x, y })
+    }
+
+    fn point_on_segment_ratio(
+        &mut self,
+        point1: &Point,
+        point2: &Point,
+        n: i64,
+        d: i64,
+    ) -> CompileResult<Point> {
+        // point1 + n/d (point2 - point1) = ((d - n) point1 + n point2) / d
+        let d_minus_n = checked(d.checked_sub(n))?;
+        let mut coordinate = |c1: &Value, c2: &Value| {
+            let a = self.mul(&self.int(d_minus_n), c1)?;
+            let b = self.mul(&self.int(n), c2)?;
+            let sum = self.add(&a, &b)?;
+            self.div(&sum, &self.int(d))
+        };
+        let x = coordinate(&point1.x, &point2.x)?;
+        let y = coordinate(&point1.y, &point2.y)?;
+        Ok(Point { x, y })
+    }
+
+    fn intersection_point(&mut self, line1: &Line, line2: &Line) -> CompileResult<Point> {
+        // Let line1 := (x - a) * n = 0, line2 := (x - b) * m = 0
+        // Then with x = a + n' t, (a - b) * m + t (n' * m) = 0 => t = (b - a) * m / (n' * m)
+        // intersection = a + n' * (b - a) * m / (n' * m)
+        let n_prime = self.rotated90(&line1.n)?;
+        let ba = self.point_sub(&line2.o, &line1.o)?;
+        let numerator = self.dot(&ba, &line2.n)?;
+        let denominator = self.dot(&n_prime, &line2.n)?;
+        let factor = self.div(&numerator, &denominator)?;
+        let dx = self.mul(&n_prime.x, &factor)?;
+        let x = self.add(&line1.o.x, &dx)?;
+        let dy = self.mul(&n_prime.y, &factor)?;
+        let y = self.add(&line1.o.y, &dy)?;
+        Ok(Point { x, y })
+    }
+
+    fn line_intersection(&mut self, line1: &Line, line2: &Line) -> CompileResult<Point> {
+        // Unlike intersection_point, no division by n1 x n2 is needed: the coordinates
+        // are new variables bound by (p - o1) * n1 = 0 and (p - o2) * n2 = 0
+        let float_coords = || -> CompileResult<(f64, f64)> {
+            let (o1x, o1y) = (
+                line1.o.x.float_initial_as_float()?,
+                line1.o.y.float_initial_as_float()?,
+            );
+            let (n1x, n1y) = (
+                line1.n.x.float_initial_as_float()?,
+                line1.n.y.float_initial_as_float()?,
+            );
+            let (o2x, o2y) = (
+                line2.o.x.float_initial_as_float()?,
+                line2.o.y.float_initial_as_float()?,
+            );
+            let (n2x, n2y) = (
+                line2.n.x.float_initial_as_float()?,
+                line2.n.y.float_initial_as_float()?,
+            );
+            let c1 = o1x * n1x + o1y * n1y;
+            let c2 = o2x * n2x + o2y * n2y;
+            let det = n1x * n2y - n1y * n2x;
+            if det == 0.0 {
+                return runtime_error("float division by zero");
+            }
+            Ok(((c1 * n2y - c2 * n1y) / det, (n1x * c2 - n2x * c1) / det))
+        };
+        let x_float = self.maybe_float_initial(|| Ok(float_coords()?.0))?;
+        let y_float = self.maybe_float_initial(|| Ok(float_coords()?.1))?;
+        let mut coordinate = |float_initial: Option<f64>| {
+            let var = self.next_var();
+            self.record_float_initial(var, float_initial);
+            let unknown = self.variable(None, float_initial);
+            Value::new(
+                Some(var),
+                Some(Initial::Value(Rc::new(unknown))),
+                float_initial,
+            )
+        };
+        let point = Point {
+            x: coordinate(x_float),
+            y: coordinate(y_float),
+        };
+        self.line_contains(line1, &point)?;
+        self.line_contains(line2, &point)?;
+        Ok(point)
+    }
+
+    fn projection(&mut self, point: &Point, line: &Line) -> CompileResult<Point> {
+        // proj = a - n ((a - p) * n) / (n * n) (p = point, (a, n) = line)
+        let op = self.point_sub(point, &line.o)?;
+        let dot = self.dot(&op, &line.n)?;
+        let length_sqr = self.dot(&line.n, &line.n)?;
+        let factor = self.div(&dot, &length_sqr)?;
+        self.subtract_normal(point, &line.n, &factor)
+    }
+
+    fn reflection(&mut self, point: &Point, line: &Line) -> CompileResult<Point> {
+        // reflection = a - 2 n ((a - p) * n) / (n * n) (p = point, (a, n) = line)
+        let op = self.point_sub(point, &line.o)?;
+        let dot = self.dot(&op, &line.n)?;
+        let double_dot = self.mul(&self.int(2), &dot)?;
+        let length_sqr = self.dot(&line.n, &line.n)?;
+        let factor = self.div(&double_dot, &length_sqr)?;
+        self.subtract_normal(point, &line.n, &factor)
+    }
+
+    /// point - n * factor
+    fn subtract_normal(
+        &mut self,
+        point: &Point,
+        n: &Vector,
+        factor: &Value,
+    ) -> CompileResult<Point> {
+        let shift = self.scale(n, factor)?;
+        let position = Vector {
+            x: point.x.clone(),
+            y: point.y.clone(),
+        };
+        let result = self.vector_sub(&position, &shift)?;
+        Ok(Point {
+            x: result.x,
+            y: result.y,
+        })
+    }
+
+    fn scaled_vector_point(
+        &mut self,
+        k: &Value,
+        point1: &Point,
+        point2: &Point,
+    ) -> CompileResult<Point> {
+        // svp = point1 + k * (point2 - point1)
+        let mut coordinate = |c1: &Value, c2: &Value| {
+            let diff = self.sub(c2, c1)?;
+            let scaled = self.mul(k, &diff)?;
+            self.add(c1, &scaled)
+        };
+        let x = coordinate(&point1.x, &point2.x)?;
+        let y = coordinate(&point1.y, &point2.y)?;
+        Ok(Point { x, y })
+    }
+
+    fn pp_bisector(&mut self, a: &Point, b: &Point) -> CompileResult<Line> {
+        let ab = self.point_sub(b, a)?;
+        let half = self.vector_div(&ab, &self.int(2))?;
+        let o = self.point_add(a, &half)?;
+        let n = self.point_sub(b, a)?;
+        Ok(Line { o, n })
+    }
+
+    fn unit_vector(&mut self, from: &Point, to: &Point) -> CompileResult<Vector> {
+        let v = self.point_sub(to, from)?;
+        let w = self.point_sub(to, from)?;
+        let length = self.length(&w)?;
+        self.vector_div(&v, &length)
+    }
+
+    fn angle_bisector(&mut self, a: &Point, vertex: &Point, b: &Point) -> CompileResult<Line> {
+        // The bisector of the angle a-vertex-b runs along e_a + e_b, the unit vectors
+        // towards a and b. The lengths are square roots of squared distances, which are
+        // only fixed up to sign, so the equations describe both bisectors.
+        let e_a = self.unit_vector(vertex, a)?;
+        let e_b = self.unit_vector(vertex, b)?;
+        let sum = self.vector_add(&e_a, &e_b)?;
+        Ok(Line {
+            o: vertex.clone(),
+            n: self.rotated90(&sum)?,
+        })
+    }
+
+    fn ll_angle_bisector(&mut self, line1: &Line, line2: &Line) -> CompileResult<Line> {
+        // Points p of the bisector are equidistant from both lines:
+        // (p - o1) * n1 / |n1| = (p - o2) * n2 / |n2|
+        let o = self.intersection_point(line1, line2)?;
+        let length1 = self.length(&line1.n)?;
+        let e1 = self.vector_div(&line1.n, &length1)?;
+        let length2 = self.length(&line2.n)?;
+        let e2 = self.vector_div(&line2.n, &length2)?;
+        let n = self.vector_sub(&e1, &e2)?;
+        Ok(Line { o, n })
+    }
+
+    fn call_curve_function(&mut self, f: &Lambda, p: &Point) -> CompileResult<Value> {
+        let args = vec![Object::Value(p.x.clone()), Object::Value(p.y.clone())];
+        self.call_lambda(f, args)?.into_value()
+    }
+
+    fn curve_contains(&mut self, curve: &Curve, p: &Point) -> CompileResult<()> {
+        let v = self.call_curve_function(&curve.f, p)?;
+        self.push_zero(&v);
+        Ok(())
+    }
+
+    fn curve_gradient(&mut self, curve: &Curve, p: &Point) -> CompileResult<Vector> {
+        let x = self.call_curve_function(&curve.f_x, p)?;
+        let y = self.call_curve_function(&curve.f_y, p)?;
+        Ok(Vector { x, y })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene_utils::SceneUtils;
+
+    #[test]
+    fn test_compile_distance() {
+        let code = [
+            "A = FixedPoint(0, 0)",
+            "X = FreePoint(3, 4)",
+            "plot(\"P1\", X)",
+            "is_constant(d_sqr(A, X))",
+        ]
+        .join("\n");
+        let (equations, plots, _) = EquationCompiler::compile(&code).unwrap();
+        assert_eq!(
+            equations,
+            vec![
+                "0 - a - c",
+                "c^2 - d",
+                "0 - b - e",
+                "e^2 - f",
+                "d + f - g",
+                "g - 25"
+            ]
+        );
+        assert_eq!(
+            plots,
+            vec![Plot {
+                name: "P1".to_string(),
+                x: "a".to_string(),
+                y: "b".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_compile_variables() {
+        let code = [
+            "A = FreePoint(0, 0)",
+            "X = FreePoint(3, 4)",
+            "M = Midpoint(A, X)",
+            "is_constant(d_sqr(A, X))",
+            "B = A",
+        ]
+        .join("\n");
+        let (equations, _, variables) = EquationCompiler::compile(&code).unwrap();
+        let described: Vec<String> = (0..12).map(|var| variables.describe(var)).collect();
+        assert_eq!(
+            described,
+            vec![
+                "a (A.x)",
+                "b (A.y)",
+                "c (X.x)",
+                "d (X.y)",
+                "e (auxiliary variable of M)",
+                "f (auxiliary variable of M)",
+                "g (M.x)",
+                "h (auxiliary variable of M)",
+                "i (auxiliary variable of M)",
+                "j (M.y)",
+                // Created by a statement that is not an assignment
+                "k",
+                "l",
+            ]
+        );
+        assert!(equations.iter().any(|equation| equation.contains('k')));
+    }
+
+    #[test]
+    fn test_matches_python_processor() {
+        let scenes = [
+            vec![
+                "A = FixedPoint(-1, 0)",
+                "B = FixedPoint(1, 0)",
+                "X = FreePoint(0, 2)",
+                "is_constant(distance_sum(X, A, B))",
+                "plot(\"E\", X)",
+            ],
+            vec![
+                "A = FreePoint(1, 2)",
+                "B = FixedPoint(3, -1)",
+                "L1 = LineAB(A, B)",
+                "L2 = PpBisector(A, FixedPoint(0, 0))",
+                "L3 = PpToLine(B, L1)",
+                "L4 = PlToLine(FixedPoint(1, 1), L2)",
+                "P = IntersectionPoint(L1, L2)",
+                "Q = LineIntersection(L3, L4)",
+                "M = Midpoint(P, Q)",
+                "R = Reflection(M, L1)",
+                "S = Projection(R, L2)",
+                "T = ScaledVectorPoint(i(2) ** q(1, 3), S, A)",
+                "U = PointOnSegmentRatio(T, B, 1, 3)",
+                "is_constant(cot(L1.n, L2.n).abs())",
+                "is_constant(d(U, L3) + (d(A, B) - i(2)) / i(3))",
+                "plot(\"U\", U)",
+            ],
+            vec![
+                "X = FreePoint(2, 1)",
+                "L1 = AngleBisector(FixedPoint(1, 0), X, FixedPoint(0, 1))",
+                "L2 = LLAngleBisector(L1, LineAB(FixedPoint(0, 0), FixedPoint(1, 2)))",
+                "P = FreePoint(5, 0)",
+                "L2.contains(P)",
+                "C = Point(-P.x + P.y * i(2), P.x ** i(-2) + (X - FixedPoint(0, 0)).length())",
+                "T = TangentLine(Curve(lambda x, y: x ** i(2) + y ** i(2) - i(25), lambda x, y: i(2) * x, lambda x, y: i(2) * y), FreePoint(3, 4))",
+                "is_zero(d_sqr(C, T) - d(T.o, L2))",
+                "plot(\"C\", C)",
+            ],
+        ];
+        for scene in scenes {
+            let code = scene.join("\n");
+            let (equations, plots, _) = EquationCompiler::compile(&code).unwrap();
+            assert_eq!(
+                (equations, plots),
+                SceneUtils::to_equations_python(code.clone()).unwrap(),
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_float_initial() {
+        let mut compiler = EquationCompiler::new(true);
+        compiler
+            .run("X = FreePoint(1, 2)\nY = LineIntersection(LineAB(X, FixedPoint(0, 0)), LineAB(FixedPoint(0, 1), FixedPoint(1, 1)))")
+            .unwrap();
+        let value = compiler.evaluate_float("i(2) ** q(1, 2)").unwrap();
+        assert!((value - 2f64.sqrt()).abs() < 1e-12);
+        assert_eq!(compiler.evaluate_float("X.x + X.y").unwrap(), 3.0);
+        assert!((compiler.evaluate_float("Y.x").unwrap() - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = EquationCompiler::compile("raise Exception(\"The curve is not computed\")")
+            .unwrap_err();
+        assert_eq!(
+            error,
+            CompileError::Runtime("The curve is not computed".to_string())
+        );
+        assert!(!error.is_unsupported());
+
+        let error = EquationCompiler::compile("X = FreePoint(1, 2)\nis_constant(q(1, 2))");
+        assert!(matches!(error, Err(CompileError::Runtime(_))));
+
+        for code in [
+            "import os",
+            "X = [1, 2]",
+            "X = FixedPoint(1.5, 2)",
+            "print(i(1))",
+        ] {
+            let error = EquationCompiler::compile(code).unwrap_err();
+            assert!(error.is_unsupported(), "{}: {:?}", code, error);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Where the polynomial systems of scenes come from. Scenes normally compile their
+//! objects into equations, see `PythonEquationSource`; tests can provide a fixed system
+//! with `StaticEquationSource` instead.
+
+use std::fmt::Debug;
+
+use crate::scene::{Plot, Scene};
+use crate::scene_object::SceneError;
+use crate::scene_utils::SceneUtils;
+use crate::variables::VariableRegistry;
+
+/// Equations of the constraints of a scene, in the variables named by `Poly::parse_var`,
+/// the loci to plot, and the objects the variables stand for
+pub type SceneEquations = (Vec<String>, Vec<Plot>, VariableRegistry);
+
+pub trait EquationSource: Debug + Send + Sync {
+    fn equations(&self, scene: &Scene) -> Result<SceneEquations, SceneError>;
+}
+
+/// Compiles the Python code of the objects of the scene with `EquationCompiler`,
+/// falling back to running it with python3 for code the compiler doesn't support
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PythonEquationSource;
+
+impl EquationSource for PythonEquationSource {
+    fn equations(&self, scene: &Scene) -> Result<SceneEquations, SceneError> {
+        SceneUtils::to_equations_with_variables(scene.to_python_with_curves()?)
+    }
+}
+
+/// The same system for any scene, with variables named after their letters only
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct StaticEquationSource {
+    pub equations: Vec<String>,
+    pub plots: Vec<Plot>,
+}
+
+#[cfg(test)]
+impl StaticEquationSource {
+    pub fn new(equations: Vec<String>, plots: Vec<Plot>) -> Self {
+        Self { equations, plots }
+    }
+}
+
+#[cfg(test)]
+impl EquationSource for StaticEquationSource {
+    fn equations(&self, _scene: &Scene) -> Result<SceneEquations, SceneError> {
+        Ok((
+            self.equations.clone(),
+            self.plots.clone(),
+            VariableRegistry::new(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::SceneOptions;
+    use crate::scene_object::{ObjectType, SceneObject};
+    use serde_json::json;
+
+    #[test]
+    fn test_equation_sources() {
+        let mut scene = Scene::new(1, SceneOptions::default());
+        let objects = [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "3, 4"})),
+            ("I", ObjectType::Invariant, json!({"formula": "d(A, X)"})),
+            ("L", ObjectType::Locus, json!({"point": "X"})),
+        ];
+        for (name, object_type, properties) in objects {
+            let object = SceneObject::from_properties(object_type, properties).unwrap();
+            scene.objects.insert(name.to_string(), object);
+        }
+        let (equations, plots, registry) = PythonEquationSource.equations(&scene).unwrap();
+        assert!(!equations.is_empty());
+        assert_eq!(plots.len(), 1);
+        assert_eq!(plots[0].name, "L");
+        assert!(!registry.is_empty());
+
+        // The objects are ignored
+        let plot = Plot {
+            name: "C".to_string(),
+            x: "a".to_string(),
+            y: "b".to_string(),
+        };
+        let source = StaticEquationSource::new(vec!["a^2 + b^2 - 25".to_string()], vec![plot]);
+        let scene = scene.with_equation_source(source.clone());
+        let (equations, plots, _) = source.equations(&scene).unwrap();
+        assert_eq!(equations, ["a^2 + b^2 - 25"]);
+        assert_eq!(plots[0].name, "C");
+        assert!(scene.variable_registry().unwrap().is_empty());
+        assert!(matches!(
+            scene.solve_locus_curve("L"),
+            Err(SceneError::DependencyNotFound(name)) if name == "L"
+        ));
+    }
+}
@@ -0,0 +1,1011 @@
+//! Parser and type checker for the expression language of Invariant formulas and
+//! computed points (e.g. `d(A, X)^2 + L1.n.length()`). Errors are reported as
+//! diagnostics with the character range of the offending part of the expression.
+
+use crate::scene_object::{ObjectType, SceneObject};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpressionType {
+    Scalar,
+    Point,
+    Vector,
+    Line,
+}
+
+impl fmt::Display for ExpressionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExpressionType::Scalar => "a Scalar",
+            ExpressionType::Point => "a Point",
+            ExpressionType::Vector => "a Vector",
+            ExpressionType::Line => "a Line",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl ExpressionType {
+    /// The type of the value an object contributes to expressions, or None for
+    /// objects that cannot be referenced (invariants and loci)
+    pub fn of_object(object_type: ObjectType) -> Option<ExpressionType> {
+        match object_type {
+            ObjectType::FixedPoint
+            | ObjectType::FreePoint
+            | ObjectType::Midpoint
+            | ObjectType::PointOnSegmentRatio
+            | ObjectType::IntersectionPoint
+            | ObjectType::LineIntersection
+            | ObjectType::SlidingPoint
+            | ObjectType::Projection
+            | ObjectType::Reflection
+            | ObjectType::ScaledVectorPoint
+            | ObjectType::ComputedPoint => Some(ExpressionType::Point),
+            ObjectType::LineAB
+            | ObjectType::PpBisector
+            | ObjectType::AngleBisector
+            | ObjectType::PpToLine
+            | ObjectType::PlToLine
+            | ObjectType::TangentLine => Some(ExpressionType::Line),
+            ObjectType::Parameter => Some(ExpressionType::Scalar),
+            ObjectType::TwoPointDistanceInvariant
+            | ObjectType::PointToLineDistanceInvariant
+            | ObjectType::TwoLineAngleInvariant
+            | ObjectType::Invariant
+            | ObjectType::Locus => None,
+        }
+    }
+}
+
+/// A problem found in an expression; `start` and `end` are character offsets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(message: String, span: Span) -> Self {
+        Diagnostic {
+            message,
+            start: span.start,
+            end: span.end,
+            suggestion: None,
+        }
+    }
+
+    fn with_suggestion(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+impl Span {
+    fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Pow => "^",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ExprKind {
+    Number(i64),
+    Object(String),
+    Field(Box<Expr>, String, Span),
+    Call(String, Span, Vec<Expr>),
+    MethodCall(Box<Expr>, String, Span, Vec<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Expr {
+    kind: ExprKind,
+    span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(i64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+const SYMBOLS: [&str; 10] = ["**", "^", "(", ")", ",", ".", "+", "-", "*", "/"];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, Diagnostic> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        let start = pos;
+        let c = chars[pos];
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+        let kind = if c.is_ascii_digit() {
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            if pos + 1 < chars.len() && chars[pos] == '.' && chars[pos + 1].is_ascii_digit() {
+                while pos + 1 < chars.len() && (chars[pos + 1].is_ascii_digit()) {
+                    pos += 1;
+                }
+                let span = Span {
+                    start,
+                    end: pos + 1,
+                };
+                return Err(
+                    Diagnostic::new("Decimal numbers are not supported".to_string(), span)
+                        .with_suggestion(Some("Use a fraction such as (3/2)".to_string())),
+                );
+            }
+            let literal: String = chars[start..pos].iter().collect();
+            let value = literal.parse().map_err(|_| {
+                Diagnostic::new(
+                    format!("Number {} is too large", literal),
+                    Span { start, end: pos },
+                )
+            })?;
+            TokenKind::Number(value)
+        } else if c.is_alphabetic() || c == '_' {
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            TokenKind::Name(chars[start..pos].iter().collect())
+        } else {
+            let rest: String = chars[pos..].iter().take(2).collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(*symbol))
+                .ok_or_else(|| {
+                    Diagnostic::new(
+                        format!("Unexpected character '{}'", c),
+                        Span {
+                            start,
+                            end: start + 1,
+                        },
+                    )
+                })?;
+            pos += symbol.len();
+            TokenKind::Symbol(if *symbol == "**" { "^" } else { symbol })
+        };
+        tokens.push(Token {
+            kind,
+            span: Span { start, end: pos },
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    length: usize,
+}
+
+impl Parser {
+    fn peek_symbol(&self, symbol: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token { kind: TokenKind::Symbol(s), .. }) if *s == symbol)
+    }
+
+    fn end_span(&self) -> Span {
+        Span {
+            start: self.length,
+            end: self.length,
+        }
+    }
+
+    fn unexpected(&self) -> Diagnostic {
+        match self.tokens.get(self.pos) {
+            Some(token) => Diagnostic::new(
+                format!("Unexpected '{}'", Self::token_text(&token.kind)),
+                token.span,
+            ),
+            None => Diagnostic::new("Unexpected end of expression".to_string(), self.end_span()),
+        }
+    }
+
+    fn token_text(kind: &TokenKind) -> String {
+        match kind {
+            TokenKind::Number(x) => x.to_string(),
+            TokenKind::Name(name) => name.clone(),
+            TokenKind::Symbol(symbol) => symbol.to_string(),
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str, opening: Span) -> Result<Span, Diagnostic> {
+        if self.peek_symbol(symbol) {
+            self.pos += 1;
+            return Ok(self.tokens[self.pos - 1].span);
+        }
+        let mut diagnostic = self.unexpected();
+        diagnostic.message = format!("Expected '{}'", symbol);
+        if symbol == ")" {
+            diagnostic = diagnostic.with_suggestion(Some(format!(
+                "Close the parenthesis opened at {}",
+                opening.start
+            )));
+        }
+        Err(diagnostic)
+    }
+
+    fn parse(mut self) -> Result<Expr, Diagnostic> {
+        if self.tokens.is_empty() {
+            return Err(Diagnostic::new(
+                "Empty expression".to_string(),
+                self.end_span(),
+            ));
+        }
+        let expr = self.parse_sum()?;
+        if self.pos < self.tokens.len() {
+            return Err(self.unexpected());
+        }
+        Ok(expr)
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr, Diagnostic> {
+        let mut expr = self.parse_product()?;
+        loop {
+            let op = if self.peek_symbol("+") {
+                BinaryOp::Add
+            } else if self.peek_symbol("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(expr);
+            };
+            self.pos += 1;
+            let right = self.parse_product()?;
+            expr = Self::binary(op, expr, right);
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, Diagnostic> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            let op = if self.peek_symbol("*") {
+                BinaryOp::Mul
+            } else if self.peek_symbol("/") {
+                BinaryOp::Div
+            } else {
+                return Ok(expr);
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            expr = Self::binary(op, expr, right);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, Diagnostic> {
+        if self.peek_symbol("-") {
+            let span = self.tokens[self.pos].span;
+            self.pos += 1;
+            let expr = self.parse_unary()?;
+            return Ok(Expr {
+                span: span.to(expr.span),
+                kind: ExprKind::Neg(Box::new(expr)),
+            });
+        }
+        self.parse_power()
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, Diagnostic> {
+        let base = self.parse_postfix()?;
+        if self.peek_symbol("^") {
+            self.pos += 1;
+            let exponent = self.parse_unary()?;
+            return Ok(Self::binary(BinaryOp::Pow, base, exponent));
+        }
+        Ok(base)
+    }
+
+    fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+        Expr {
+            span: left.span.to(right.span),
+            kind: ExprKind::Binary(op, Box::new(left), Box::new(right)),
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, Diagnostic> {
+        let mut expr = self.parse_atom()?;
+        while self.peek_symbol(".") {
+            self.pos += 1;
+            let (name, name_span) = self.parse_name()?;
+            if self.peek_symbol("(") {
+                let (args, end) = self.parse_args()?;
+                expr = Expr {
+                    span: expr.span.to(end),
+                    kind: ExprKind::MethodCall(Box::new(expr), name, name_span, args),
+                };
+            } else {
+                expr = Expr {
+                    span: expr.span.to(name_span),
+                    kind: ExprKind::Field(Box::new(expr), name, name_span),
+                };
+            }
+        }
+        Ok(expr)
+    }
+
+    fn parse_name(&mut self) -> Result<(String, Span), Diagnostic> {
+        match self.tokens.get(self.pos) {
+            Some(Token {
+                kind: TokenKind::Name(name),
+                span,
+            }) => {
+                self.pos += 1;
+                Ok((name.clone(), *span))
+            }
+            _ => {
+                let mut diagnostic = self.unexpected();
+                diagnostic.message = "Expected a field or method name".to_string();
+                Err(diagnostic)
+            }
+        }
+    }
+
+    /// Parses a parenthesized argument list, returning the arguments and the span
+    /// of the closing parenthesis
+    fn parse_args(&mut self) -> Result<(Vec<Expr>, Span), Diagnostic> {
+        let opening = self.tokens[self.pos].span;
+        self.pos += 1;
+        let mut args = Vec::new();
+        if self.peek_symbol(")") {
+            self.pos += 1;
+            return Ok((args, self.tokens[self.pos - 1].span));
+        }
+        loop {
+            args.push(self.parse_sum()?);
+            if self.peek_symbol(",") {
+                self.pos += 1;
+            } else {
+                let end = self.expect_symbol(")", opening)?;
+                return Ok((args, end));
+            }
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, Diagnostic> {
+        let token = match self.tokens.get(self.pos) {
+            Some(token) => token.clone(),
+            None => return Err(self.unexpected()),
+        };
+        match token.kind {
+            TokenKind::Number(x) => {
+                self.pos += 1;
+                Ok(Expr {
+                    kind: ExprKind::Number(x),
+                    span: token.span,
+                })
+            }
+            TokenKind::Name(name) => {
+                self.pos += 1;
+                if self.peek_symbol("(") {
+                    let (args, end) = self.parse_args()?;
+                    Ok(Expr {
+                        kind: ExprKind::Call(name, token.span, args),
+                        span: token.span.to(end),
+                    })
+                } else {
+                    Ok(Expr {
+                        kind: ExprKind::Object(name),
+                        span: token.span,
+                    })
+                }
+            }
+            TokenKind::Symbol("(") => {
+                self.pos += 1;
+                let expr = self.parse_sum()?;
+                let end = self.expect_symbol(")", token.span)?;
+                Ok(Expr {
+                    kind: expr.kind,
+                    span: token.span.to(end),
+                })
+            }
+            TokenKind::Symbol(_) => Err(self.unexpected()),
+        }
+    }
+}
+
+const FUNCTIONS: [&str; 8] = [
+    "sqrt",
+    "d",
+    "d_sqr",
+    "distance_sum",
+    "cot",
+    "Point",
+    "Line",
+    "Vector",
+];
+
+/// Levenshtein distance between two names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// "Did you mean ...?" for the candidate closest to `name`, if it is close enough
+fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= 2 && *distance < candidate.len().max(2))
+        .min()
+        .map(|(_, candidate)| format!("Did you mean '{}'?", candidate))
+}
+
+/// How to get a value of type `expected` from a value of type `actual`, if there is an idiom
+fn conversion_hint(actual: ExpressionType, expected: ExpressionType) -> Option<String> {
+    match (actual, expected) {
+        (ExpressionType::Point, ExpressionType::Scalar) => {
+            Some("Use the coordinates .x and .y of the point".to_string())
+        }
+        (ExpressionType::Vector, ExpressionType::Scalar) => {
+            Some("Use .length(), .length_sqr(), .x or .y of the vector".to_string())
+        }
+        (ExpressionType::Line, ExpressionType::Point) => {
+            Some("Use .o for a point of the line".to_string())
+        }
+        (ExpressionType::Line, ExpressionType::Vector) => {
+            Some("Use .n for the normal vector of the line".to_string())
+        }
+        (ExpressionType::Point, ExpressionType::Vector) => {
+            Some("Subtract two points to get a vector".to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Checks expressions against the objects of a scene
+pub struct ExpressionChecker<'a> {
+    objects: &'a IndexMap<String, SceneObject>,
+}
+
+impl<'a> ExpressionChecker<'a> {
+    pub fn new(objects: &'a IndexMap<String, SceneObject>) -> Self {
+        ExpressionChecker { objects }
+    }
+
+    /// Parses and type checks an expression, optionally requiring its type
+    pub fn check(
+        &self,
+        expression: &str,
+        expected_type: Option<ExpressionType>,
+    ) -> Result<ExpressionType, Vec<Diagnostic>> {
+        let tokens = tokenize(expression).map_err(|d| vec![d])?;
+        let parser = Parser {
+            tokens,
+            pos: 0,
+            length: expression.chars().count(),
+        };
+        let expr = parser.parse().map_err(|d| vec![d])?;
+
+        let mut diagnostics = Vec::new();
+        let expression_type = self.infer(&expr, &mut diagnostics);
+        if let (Some(actual), Some(expected)) = (expression_type, expected_type) {
+            if actual != expected {
+                diagnostics.push(
+                    Diagnostic::new(format!("Expected {}, got {}", expected, actual), expr.span)
+                        .with_suggestion(conversion_hint(actual, expected)),
+                );
+            }
+        }
+        match expression_type {
+            Some(expression_type) if diagnostics.is_empty() => Ok(expression_type),
+            _ => Err(diagnostics),
+        }
+    }
+
+    /// Returns the type of the expression, or None if it cannot be determined
+    /// because of the errors added to `diagnostics`
+    fn infer(&self, expr: &Expr, diagnostics: &mut Vec<Diagnostic>) -> Option<ExpressionType> {
+        match &expr.kind {
+            ExprKind::Number(_) => Some(ExpressionType::Scalar),
+            ExprKind::Object(name) => self.infer_object(name, expr.span, diagnostics),
+            ExprKind::Field(object, name, name_span) => {
+                let object_type = self.infer(object, diagnostics)?;
+                let field_type = match (object_type, name.as_str()) {
+                    (ExpressionType::Point | ExpressionType::Vector, "x" | "y") => {
+                        Some(ExpressionType::Scalar)
+                    }
+                    (ExpressionType::Line, "o") => Some(ExpressionType::Point),
+                    (ExpressionType::Line, "n") => Some(ExpressionType::Vector),
+                    _ => None,
+                };
+                if field_type.is_none() {
+                    let fields: &[&str] = match object_type {
+                        ExpressionType::Point | ExpressionType::Vector => &["x", "y"],
+                        ExpressionType::Line => &["o", "n"],
+                        ExpressionType::Scalar => &[],
+                    };
+                    diagnostics.push(
+                        Diagnostic::new(
+                            format!("No field '{}' on {}", name, object_type),
+                            *name_span,
+                        )
+                        .with_suggestion(did_you_mean(name, fields.iter().copied())),
+                    );
+                }
+                field_type
+            }
+            ExprKind::MethodCall(object, name, name_span, args) => {
+                let object_type = self.infer(object, diagnostics);
+                let arg_types = self.infer_args(args, diagnostics);
+                let object_type = object_type?;
+                let methods: &[&str] = match object_type {
+                    ExpressionType::Scalar => &["abs"],
+                    ExpressionType::Vector => &["length", "length_sqr", "rotated90"],
+                    ExpressionType::Point | ExpressionType::Line => &[],
+                };
+                if !methods.contains(&name.as_str()) {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            format!("No method '{}' on {}", name, object_type),
+                            *name_span,
+                        )
+                        .with_suggestion(did_you_mean(name, methods.iter().copied())),
+                    );
+                    return None;
+                }
+                self.check_arity(name, *name_span, args, 0, 0, diagnostics)?;
+                arg_types?;
+                match name.as_str() {
+                    "rotated90" => Some(ExpressionType::Vector),
+                    _ => Some(ExpressionType::Scalar),
+                }
+            }
+            ExprKind::Call(name, name_span, args) => {
+                let arg_types = self.infer_args(args, diagnostics);
+                self.infer_call(name, *name_span, args, arg_types, diagnostics)
+            }
+            ExprKind::Neg(operand) => {
+                let operand_type = self.infer(operand, diagnostics)?;
+                if operand_type != ExpressionType::Scalar {
+                    diagnostics.push(Diagnostic::new(
+                        format!("Cannot negate {}", operand_type),
+                        expr.span,
+                    ));
+                    return None;
+                }
+                Some(ExpressionType::Scalar)
+            }
+            ExprKind::Binary(op, left, right) => {
+                let left_type = self.infer(left, diagnostics);
+                let right_type = self.infer(right, diagnostics);
+                if *op == BinaryOp::Pow && !Self::is_constant_exponent(right) {
+                    diagnostics.push(
+                        Diagnostic::new("The exponent must be a constant".to_string(), right.span)
+                            .with_suggestion(Some(
+                                "Use an integer or a fraction such as (1/2)".to_string(),
+                            )),
+                    );
+                    return None;
+                }
+                Self::infer_binary(*op, left_type?, right_type?, expr.span, diagnostics)
+            }
+        }
+    }
+
+    fn infer_object(
+        &self,
+        name: &str,
+        span: Span,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<ExpressionType> {
+        match self.objects.get(name) {
+            Some(object) => {
+                let object_type = object.get_type();
+                let expression_type = ExpressionType::of_object(object_type);
+                if expression_type.is_none() {
+                    diagnostics.push(Diagnostic::new(
+                        format!(
+                            "Object '{}' has type '{:?}' which is not allowed in expressions",
+                            name, object_type
+                        ),
+                        span,
+                    ));
+                }
+                expression_type
+            }
+            None => {
+                let suggestion = if FUNCTIONS.contains(&name) {
+                    Some(format!(
+                        "'{}' is a function, call it as {}(...)",
+                        name, name
+                    ))
+                } else {
+                    did_you_mean(name, self.objects.keys().map(|key| key.as_str()))
+                };
+                diagnostics.push(
+                    Diagnostic::new(format!("Object '{}' not found in scene", name), span)
+                        .with_suggestion(suggestion),
+                );
+                None
+            }
+        }
+    }
+
+    /// Infers the types of all arguments; None if any of them is unknown
+    fn infer_args(
+        &self,
+        args: &[Expr],
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<Vec<ExpressionType>> {
+        let types: Vec<Option<ExpressionType>> = args
+            .iter()
+            .map(|arg| self.infer(arg, diagnostics))
+            .collect();
+        types.into_iter().collect()
+    }
+
+    fn check_arity(
+        &self,
+        name: &str,
+        span: Span,
+        args: &[Expr],
+        min: usize,
+        max: usize,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<()> {
+        if args.len() >= min && args.len() <= max {
+            return Some(());
+        }
+        let expected = if min == max {
+            min.to_string()
+        } else if max == usize::MAX {
+            format!("at least {}", min)
+        } else {
+            format!("{} to {}", min, max)
+        };
+        let plural = if min == 1 && max == 1 { "" } else { "s" };
+        diagnostics.push(Diagnostic::new(
+            format!(
+                "'{}' takes {} argument{}, got {}",
+                name,
+                expected,
+                plural,
+                args.len()
+            ),
+            span,
+        ));
+        None
+    }
+
+    fn infer_call(
+        &self,
+        name: &str,
+        name_span: Span,
+        args: &[Expr],
+        arg_types: Option<Vec<ExpressionType>>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<ExpressionType> {
+        use ExpressionType::*;
+
+        let (min, max) = match name {
+            "sqrt" => (1, 1),
+            "distance_sum" => (2, usize::MAX),
+            _ if FUNCTIONS.contains(&name) => (2, 2),
+            _ => {
+                diagnostics.push(
+                    Diagnostic::new(format!("Unknown function '{}'", name), name_span)
+                        .with_suggestion(did_you_mean(name, FUNCTIONS).or_else(|| {
+                            Some(format!("Allowed functions are: {}", FUNCTIONS.join(", ")))
+                        })),
+                );
+                return None;
+            }
+        };
+        self.check_arity(name, name_span, args, min, max, diagnostics)?;
+        let arg_types = arg_types?;
+
+        // The allowed types of each argument and the result type
+        let (allowed, result): (Vec<&[ExpressionType]>, ExpressionType) = match name {
+            "sqrt" => (vec![&[Scalar]], Scalar),
+            "d" | "d_sqr" => (vec![&[Point, Line], &[Point, Line]], Scalar),
+            "distance_sum" => (vec![&[Point]; args.len()], Scalar),
+            "cot" => (vec![&[Vector], &[Vector]], Scalar),
+            "Point" => (vec![&[Scalar], &[Scalar]], Point),
+            "Vector" => (vec![&[Scalar], &[Scalar]], Vector),
+            _ => (vec![&[Point], &[Vector]], Line),
+        };
+        let mut valid = true;
+        for (index, ((arg, arg_type), allowed)) in
+            args.iter().zip(&arg_types).zip(allowed).enumerate()
+        {
+            if !allowed.contains(arg_type) {
+                let expected: Vec<String> = allowed.iter().map(|t| t.to_string()).collect();
+                diagnostics.push(
+                    Diagnostic::new(
+                        format!(
+                            "Argument {} of '{}' must be {}, got {}",
+                            index + 1,
+                            name,
+                            expected.join(" or "),
+                            arg_type
+                        ),
+                        arg.span,
+                    )
+                    .with_suggestion(conversion_hint(*arg_type, allowed[0])),
+                );
+                valid = false;
+            }
+        }
+        if valid && (name == "d" || name == "d_sqr") && arg_types == [Line, Line] {
+            diagnostics.push(Diagnostic::new(
+                format!("'{}' cannot be called with two lines", name),
+                name_span,
+            ));
+            valid = false;
+        }
+        valid.then_some(result)
+    }
+
+    fn infer_binary(
+        op: BinaryOp,
+        left: ExpressionType,
+        right: ExpressionType,
+        span: Span,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<ExpressionType> {
+        use ExpressionType::*;
+
+        let result = match (op, left, right) {
+            (_, Scalar, Scalar) => Some(Scalar),
+            (BinaryOp::Sub, Point, Point) => Some(Vector),
+            (BinaryOp::Add, Point, Vector) => Some(Point),
+            (BinaryOp::Add | BinaryOp::Sub, Vector, Vector) => Some(Vector),
+            (BinaryOp::Mul, Vector, Vector) => Some(Scalar),
+            (BinaryOp::Mul, Vector, Scalar) | (BinaryOp::Mul, Scalar, Vector) => Some(Vector),
+            (BinaryOp::Div, Vector, Scalar) => Some(Vector),
+            _ => None,
+        };
+        if result.is_none() {
+            let suggestion = match (op, left, right) {
+                (BinaryOp::Add, Point, Point) => {
+                    Some("Points cannot be added; add a vector (B - A) to a point".to_string())
+                }
+                (BinaryOp::Sub, Vector, Point) => {
+                    Some("Subtract points from points, and vectors from vectors".to_string())
+                }
+                (_, Point | Vector | Line, _) => conversion_hint(left, Scalar),
+                _ => conversion_hint(right, Scalar),
+            };
+            diagnostics.push(
+                Diagnostic::new(
+                    format!("Cannot apply '{}' to {} and {}", op, left, right),
+                    span,
+                )
+                .with_suggestion(suggestion),
+            );
+        }
+        result
+    }
+
+    /// Exponents are integer constants, or fractions of integers for roots
+    fn is_constant_exponent(expr: &Expr) -> bool {
+        fn is_integer_constant(expr: &Expr) -> bool {
+            match &expr.kind {
+                ExprKind::Number(_) => true,
+                ExprKind::Neg(operand) => is_integer_constant(operand),
+                ExprKind::Binary(BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul, left, right) => {
+                    is_integer_constant(left) && is_integer_constant(right)
+                }
+                _ => false,
+            }
+        }
+        match &expr.kind {
+            ExprKind::Binary(BinaryOp::Div, left, right) => {
+                matches!(left.kind, ExprKind::Number(_))
+                    && matches!(right.kind, ExprKind::Number(_))
+            }
+            _ => is_integer_constant(expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn objects() -> IndexMap<String, SceneObject> {
+        let mut objects = IndexMap::new();
+        for (name, object_type, properties) in [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "1, 2"})),
+            (
+                "L1",
+                ObjectType::LineAB,
+                json!({"point1": "A", "point2": "X"}),
+            ),
+            ("I1", ObjectType::Invariant, json!({"formula": "d(A, X)"})),
+        ] {
+            objects.insert(
+                name.to_string(),
+                SceneObject::from_properties(object_type, properties).unwrap(),
+            );
+        }
+        objects
+    }
+
+    #[test]
+    fn test_valid_expressions() {
+        let objects = objects();
+        let checker = ExpressionChecker::new(&objects);
+        for (expression, expected) in [
+            ("d(A, X)^2 + 1", ExpressionType::Scalar),
+            ("-X.x * (1/2) + L1.n.length()", ExpressionType::Scalar),
+            ("2^(1/2)", ExpressionType::Scalar),
+            ("A + (X - A) / 2", ExpressionType::Point),
+            ("L1.n.rotated90()", ExpressionType::Vector),
+            ("Line(L1.o, Vector(1, 0))", ExpressionType::Line),
+            ("distance_sum(X, A, Point(1, 0))", ExpressionType::Scalar),
+            ("cot(L1.n, X - A).abs()", ExpressionType::Scalar),
+        ] {
+            assert_eq!(
+                checker.check(expression, None),
+                Ok(expected),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let objects = objects();
+        let checker = ExpressionChecker::new(&objects);
+
+        let errors = checker.check("d(A, X", None).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Expected ')'");
+        assert_eq!((errors[0].start, errors[0].end), (6, 6));
+        assert_eq!(
+            errors[0].suggestion.as_deref(),
+            Some("Close the parenthesis opened at 1")
+        );
+
+        let errors = checker.check("A.x + * 2", None).unwrap_err();
+        assert_eq!(errors[0].message, "Unexpected '*'");
+        assert_eq!((errors[0].start, errors[0].end), (6, 7));
+
+        let errors = checker.check("X.x * 1.5", None).unwrap_err();
+        assert_eq!((errors[0].start, errors[0].end), (6, 9));
+        assert!(errors[0].suggestion.is_some());
+
+        assert!(checker.check("  ", None).is_err());
+        assert!(checker.check("A.x $ 2", None).is_err());
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let objects = objects();
+        let checker = ExpressionChecker::new(&objects);
+
+        let errors = checker.check("d(L1, L1) + A", None).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "'d' cannot be called with two lines");
+
+        let errors = checker.check("cot(A, X)", None).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].message,
+            "Argument 1 of 'cot' must be a Vector, got a Point"
+        );
+        assert_eq!((errors[1].start, errors[1].end), (7, 8));
+
+        let errors = checker.check("A + X", None).unwrap_err();
+        assert_eq!(errors[0].message, "Cannot apply '+' to a Point and a Point");
+        assert_eq!((errors[0].start, errors[0].end), (0, 5));
+
+        let errors = checker
+            .check("X", Some(ExpressionType::Scalar))
+            .unwrap_err();
+        assert_eq!(errors[0].message, "Expected a Scalar, got a Point");
+        assert!(errors[0].suggestion.is_some());
+
+        let errors = checker.check("X^(1/2)", None).unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "Cannot apply '^' to a Point and a Scalar"
+        );
+
+        let errors = checker.check("X.x^X.y", None).unwrap_err();
+        assert_eq!(errors[0].message, "The exponent must be a constant");
+        assert_eq!((errors[0].start, errors[0].end), (4, 7));
+    }
+
+    #[test]
+    fn test_name_errors_and_suggestions() {
+        let objects = objects();
+        let checker = ExpressionChecker::new(&objects);
+
+        let errors = checker
+            .check("dsqr(A, Y) + L1.m.x + X.z + I1", None)
+            .unwrap_err();
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Object 'Y' not found in scene",
+                "Unknown function 'dsqr'",
+                "No field 'm' on a Line",
+                "No field 'z' on a Point",
+                "Object 'I1' has type 'Invariant' which is not allowed in expressions",
+            ]
+        );
+        assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'A'?"));
+        assert_eq!(
+            errors[1].suggestion.as_deref(),
+            Some("Did you mean 'd_sqr'?")
+        );
+        assert_eq!(errors[2].suggestion.as_deref(), Some("Did you mean 'n'?"));
+        assert_eq!((errors[1].start, errors[1].end), (0, 4));
+
+        let errors = checker.check("L1.n.lenght()", None).unwrap_err();
+        assert_eq!(errors[0].message, "No method 'lenght' on a Vector");
+        assert_eq!(
+            errors[0].suggestion.as_deref(),
+            Some("Did you mean 'length'?")
+        );
+
+        let errors = checker.check("sqrt(1, 2)", None).unwrap_err();
+        assert_eq!(errors[0].message, "'sqrt' takes 1 argument, got 2");
+    }
+}
@@ -4,6 +4,7 @@ use std::{
 };
 
 use float_next_after::NextAfter;
+use serde::{Deserialize, Serialize};
 
 pub const ZERO_FINT: FInt = FInt(0.0, 0.0);
 
@@ -22,8 +23,22 @@ impl NextBeforeOrAfter for f64 {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Interval [lower, upper] of f64 values, serialized as the pair of its bounds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "(f64, f64)")]
 pub struct FInt(f64, f64);
+
+impl TryFrom<(f64, f64)> for FInt {
+    type Error = String;
+
+    fn try_from((lower, upper): (f64, f64)) -> std::result::Result<Self, Self::Error> {
+        if lower > upper {
+            return Err(format!("Wrong interval! {} > {}", lower, upper));
+        }
+        Ok(FInt(lower, upper))
+    }
+}
+
 impl Display for FInt {
     fn fmt(&self, f: &mut Formatter) -> Result {
         let mean = self.midpoint();
@@ -1,19 +1,152 @@
+use std::cell::RefCell;
 use std::io::{BufRead, BufReader, Write};
 use std::process::{Child, ChildStdin, Command, Stdio};
 use std::sync::mpsc::{channel, Receiver};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use indexmap::IndexMap;
 use log::info;
 
+use crate::metrics;
+
+pub use crate::cancellation::{
+    current_cancellation, with_cancellation, CancelOnDrop, CancellationToken,
+};
+
+/// Default time a single Pari/GP task may run before its process is killed
+pub const DEFAULT_TASK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest timeout a request may ask for
+pub const MAX_TASK_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Largest Pari stack a request may ask for
+pub const MAX_STACK_SIZE: u64 = 8 << 30;
+
+/// Default number of task outputs kept by the cache of the pool
+pub const DEFAULT_CACHE_CAPACITY: usize = 1000;
+
+// Resolved Pari/GP executable path, shared by all threads
+static PARI_EXECUTABLE_PATH: RwLock<Option<String>> = RwLock::new(None);
+
+// Pool of Pari/GP processes used by the Pari/GP backend and the curve analysis
+static GP_PARI_POOL: RwLock<Option<Arc<GpPariPool>>> = RwLock::new(None);
+
+/// How often waiting tasks check for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Get the Pari/GP executable path: the one set with `set_pari_executable_path`,
+/// or else `gp` found in the system PATH
+pub fn get_pari_executable_path() -> Result<String, String> {
+    // Check if we have a cached path
+    if let Some(ref path) = *PARI_EXECUTABLE_PATH
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+    {
+        return Ok(path.clone());
+    }
+
+    // Try to find gp executable in system PATH
+    let gp_names = if cfg!(target_os = "windows") {
+        vec!["gp.exe", "gp"]
+    } else {
+        vec!["gp", "gp.exe"]
+    };
+
+    for name in gp_names {
+        match Command::new(name).arg("--version").output() {
+            Ok(_) => {
+                let path = name.to_string();
+                set_pari_executable_path(path.clone());
+                return Ok(path);
+            }
+            Err(_) => continue,
+        }
+    }
+
+    Err("Pari/GP executable not found. Please install Pari/GP or specify the path with --gp-executable".to_string())
+}
+
+/// Version of the Pari/GP executable, e.g. "2.15.4"
+pub fn get_pari_version() -> Result<String, String> {
+    let output = Command::new(get_pari_executable_path()?)
+        .arg("--version-short")
+        .output()
+        .map_err(|e| format!("Failed to run Pari/GP: {}", e))?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || version.is_empty() {
+        return Err(format!(
+            "Pari/GP gave no version: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(version)
+}
+
+/// Set the Pari/GP executable path (from the command line, for testing or manual override)
+pub fn set_pari_executable_path(path: String) {
+    *PARI_EXECUTABLE_PATH
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = Some(path);
+}
+
+/// Initialize the shared GpPariPool, replacing any previously created one
+pub fn init_gp_pari_pool(size: usize, task_timeout: Duration) -> Result<(), String> {
+    let executable_path = get_pari_executable_path()?;
+    let pool = GpPariPool::new(executable_path, size, task_timeout);
+    *GP_PARI_POOL.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(pool));
+    Ok(())
+}
+
+/// Get the shared GpPariPool
+pub fn get_gp_pari_pool() -> Result<Arc<GpPariPool>, String> {
+    GP_PARI_POOL
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .ok_or_else(|| "GpPariPool not initialized".to_string())
+}
+
+/// Resource limits of a Pari/GP task; unset limits fall back to the defaults of the pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskLimits {
+    /// Time after which the process running the task is killed
+    pub timeout: Option<Duration>,
+    /// Largest size in bytes the Pari stack may grow to (`parisizemax`).
+    /// Without it, tasks exceeding the initial stack fail.
+    pub max_stack_size: Option<u64>,
+}
+
+thread_local! {
+    static CURRENT_LIMITS: RefCell<TaskLimits> = const {
+        RefCell::new(TaskLimits {
+            timeout: None,
+            max_stack_size: None,
+        })
+    };
+}
+
+/// Runs `f` with `limits` applying to all pool tasks started on this thread
+pub fn with_task_limits<R>(limits: TaskLimits, f: impl FnOnce() -> R) -> R {
+    let previous = CURRENT_LIMITS.with(|current| current.replace(limits));
+    let result = f();
+    CURRENT_LIMITS.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+pub fn current_limits() -> TaskLimits {
+    CURRENT_LIMITS.with(|current| *current.borrow())
+}
+
 /// Service for managing a persistent Pari/GP process
 pub struct GpPariService {
     process: Option<Child>,
     stdin: Option<ChildStdin>,
     stdout_receiver: Option<Receiver<String>>,
     executable_path: String,
-    task_mutex: Arc<Mutex<()>>,
+    /// parisizemax of the running process, 0 if the stack can't grow
+    max_stack_size: u64,
 }
 
 impl GpPariService {
@@ -24,7 +157,7 @@ impl GpPariService {
             stdin: None,
             stdout_receiver: None,
             executable_path,
-            task_mutex: Arc::new(Mutex::new(())),
+            max_stack_size: 0,
         }
     }
 
@@ -80,21 +213,36 @@ impl GpPariService {
         }
         self.stdin = None;
         self.stdout_receiver = None;
+        self.max_stack_size = 0;
     }
 
-    /// Run a task on the Pari/GP process
+    /// Run a task on the Pari/GP process with the default limits
     pub fn run_task(&mut self, task: String) -> Result<Vec<String>, String> {
-        // Clone the Arc to avoid borrowing issues
-        let task_mutex = self.task_mutex.clone();
-
-        // Acquire the mutex to ensure only one task runs at a time
-        let _guard = task_mutex
-            .lock()
-            .map_err(|e| format!("Failed to acquire task mutex: {}", e))?;
+        self.run_task_with_limits(task, TaskLimits::default(), None)
+    }
 
+    /// Run a task on the Pari/GP process. The process is stopped if the task
+    /// exceeds the timeout of `limits` (by default `DEFAULT_TASK_TIMEOUT`) or
+    /// `cancellation` is cancelled while waiting for output.
+    pub fn run_task_with_limits(
+        &mut self,
+        task: String,
+        limits: TaskLimits,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<Vec<String>, String> {
+        let timeout = limits.timeout.unwrap_or(DEFAULT_TASK_TIMEOUT);
         // Start the process if needed
         self.start_process()?;
 
+        // The stack limit persists in the process, so it's only sent when it changes
+        let max_stack_size = limits.max_stack_size.unwrap_or(0);
+        let task = if max_stack_size != self.max_stack_size {
+            self.max_stack_size = max_stack_size;
+            format!("default(parisizemax, {}); {}", max_stack_size, task)
+        } else {
+            task
+        };
+
         // Get stdin and stdout receiver
         let stdin = self.stdin.as_mut().ok_or("No stdin available")?;
         let stdout_receiver = self
@@ -112,18 +260,25 @@ impl GpPariService {
 
         // Collect output lines
         let mut output_lines = Vec::new();
-        let timeout = Duration::from_secs(5);
-        let start_time = std::time::Instant::now();
+        let start_time = Instant::now();
 
         loop {
             // Check for timeout
             if start_time.elapsed() > timeout {
                 self.stop_process();
-                return Err("Task timed out after 5 seconds".to_string());
+                metrics::PARI_TIMEOUTS.inc();
+                return Err(format!(
+                    "Task timed out after {} seconds",
+                    timeout.as_secs_f64()
+                ));
+            }
+            if cancellation.is_some_and(|token| token.is_cancelled()) {
+                self.stop_process();
+                return Err("Task cancelled".to_string());
             }
 
             // Try to receive output with a short timeout
-            match stdout_receiver.recv_timeout(Duration::from_millis(100)) {
+            match stdout_receiver.recv_timeout(POLL_INTERVAL) {
                 Ok(line) => {
                     output_lines.push(line.clone());
 
@@ -164,10 +319,211 @@ impl Drop for GpPariService {
     }
 }
 
+/// Outputs of the least recently used tasks, keyed by the task text. Factorization
+/// and gcd tasks format their polynomials canonically, so repeating the same
+/// computation gives the same key.
+pub struct TaskCache {
+    entries: IndexMap<String, Vec<String>>,
+    capacity: usize,
+    /// Entries added since the last `take_new_entries`, if the cache is persisted
+    new_entries: Option<Vec<(String, Vec<String>)>>,
+}
+
+impl TaskCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: IndexMap::new(),
+            capacity,
+            new_entries: None,
+        }
+    }
+
+    pub fn get(&mut self, task: &str) -> Option<Vec<String>> {
+        let index = self.entries.get_index_of(task)?;
+        let last = self.entries.len() - 1;
+        self.entries.move_index(index, last);
+        Some(self.entries[last].clone())
+    }
+
+    pub fn insert(&mut self, task: String, output: Vec<String>) {
+        if let Some(new_entries) = &mut self.new_entries {
+            new_entries.push((task.clone(), output.clone()));
+        }
+        self.store(task, output);
+    }
+
+    /// Adds entries loaded from storage, which don't count as new
+    pub fn preload(&mut self, entries: impl IntoIterator<Item = (String, Vec<String>)>) {
+        for (task, output) in entries {
+            self.store(task, output);
+        }
+    }
+
+    /// Starts or stops recording the entries to be persisted
+    pub fn set_persisted(&mut self, persisted: bool) {
+        self.new_entries = persisted.then(Vec::new);
+    }
+
+    pub fn take_new_entries(&mut self) -> Vec<(String, Vec<String>)> {
+        self.new_entries
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn store(&mut self, task: String, output: Vec<String>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.shift_remove(&task);
+        while self.entries.len() >= self.capacity {
+            self.entries.shift_remove_index(0);
+        }
+        self.entries.insert(task, output);
+    }
+}
+
+/// A fixed-size pool of Pari/GP processes shared by all threads.
+/// Tasks wait in a queue until a process is free; processes are started lazily.
+pub struct GpPariPool {
+    services: Vec<Mutex<GpPariService>>,
+    idle: Mutex<Vec<usize>>,
+    available: Condvar,
+    task_timeout: Duration,
+    cache: Mutex<TaskCache>,
+}
+
+impl GpPariPool {
+    pub fn new(executable_path: String, size: usize, task_timeout: Duration) -> Self {
+        let size = size.max(1);
+        Self {
+            services: (0..size)
+                .map(|_| Mutex::new(GpPariService::new(executable_path.clone())))
+                .collect(),
+            idle: Mutex::new((0..size).rev().collect()),
+            available: Condvar::new(),
+            task_timeout,
+            cache: Mutex::new(TaskCache::new(DEFAULT_CACHE_CAPACITY)),
+        }
+    }
+
+    /// Replaces the task cache by an empty one with the given capacity. If it is
+    /// persisted, new entries are collected for `take_new_cache_entries`.
+    pub fn configure_cache(&self, capacity: usize, persisted: bool) {
+        let mut cache = TaskCache::new(capacity);
+        cache.set_persisted(persisted);
+        *self.lock_cache() = cache;
+    }
+
+    pub fn preload_cache(&self, entries: impl IntoIterator<Item = (String, Vec<String>)>) {
+        self.lock_cache().preload(entries);
+    }
+
+    pub fn take_new_cache_entries(&self) -> Vec<(String, Vec<String>)> {
+        self.lock_cache().take_new_entries()
+    }
+
+    pub fn cache_capacity(&self) -> usize {
+        self.lock_cache().capacity
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, TaskCache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn size(&self) -> usize {
+        self.services.len()
+    }
+
+    pub fn task_timeout(&self) -> Duration {
+        self.task_timeout
+    }
+
+    /// Run a task on the first free process, blocking until one is available.
+    /// The task is cancelled through the token installed by `with_cancellation`, if any,
+    /// and runs with the limits installed by `with_task_limits`.
+    pub fn run_task(&self, task: String) -> Result<Vec<String>, String> {
+        self.run_task_with_cancellation(task, current_cancellation().as_ref())
+    }
+
+    /// Like `run_task`, but reuses the output of an earlier run of the same task.
+    /// Only for tasks whose output depends on nothing but their text.
+    pub fn run_cached_task(&self, task: String) -> Result<Vec<String>, String> {
+        if let Some(output) = self.lock_cache().get(&task) {
+            metrics::PARI_CACHE.record(true);
+            return Ok(output);
+        }
+        metrics::PARI_CACHE.record(false);
+        let output = self.run_task(task.clone())?;
+        self.lock_cache().insert(task, output.clone());
+        Ok(output)
+    }
+
+    pub fn run_task_with_cancellation(
+        &self,
+        task: String,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<Vec<String>, String> {
+        let _timer = metrics::PARI_TASKS.start();
+        let index = self.checkout(cancellation)?;
+        let result = {
+            // A panic in a previous task leaves the process stopped or reusable
+            let mut service = self.services[index]
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let limits = current_limits();
+            let limits = TaskLimits {
+                timeout: limits.timeout.or(Some(self.task_timeout)),
+                ..limits
+            };
+            service.run_task_with_limits(task, limits, cancellation)
+        };
+        self.idle
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(index);
+        self.available.notify_one();
+        result
+    }
+
+    /// Run a task without blocking the async runtime. Dropping the returned future
+    /// cancels the task.
+    pub async fn run_task_async(self: Arc<Self>, task: String) -> Result<Vec<String>, String> {
+        let token = CancellationToken::new();
+        let guard = token.cancel_on_drop();
+        let limits = current_limits();
+        let result = tokio::task::spawn_blocking(move || {
+            with_task_limits(limits, || {
+                self.run_task_with_cancellation(task, Some(&token))
+            })
+        })
+        .await
+        .map_err(|e| format!("Pari/GP task failed: {}", e));
+        guard.disarm();
+        result?
+    }
+
+    fn checkout(&self, cancellation: Option<&CancellationToken>) -> Result<usize, String> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if cancellation.is_some_and(|token| token.is_cancelled()) {
+                return Err("Task cancelled".to_string());
+            }
+            if let Some(index) = idle.pop() {
+                return Ok(index);
+            }
+            idle = self
+                .available
+                .wait_timeout(idle, POLL_INTERVAL)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{get_pari_executable_path, init_gp_pari_service, set_pari_executable_path};
     use ctor::ctor;
     use test_log::test;
 
@@ -178,13 +534,10 @@ mod tests {
     fn test_init() {
         println!("Initializing test environment (gp_pari_service.rs)...");
         set_pari_executable_path(GP_PATH.to_string());
-        if let Err(e) = init_gp_pari_service() {
-            println!(
-                "Warning: Failed to initialize GpPariService for tests: {}",
-                e
-            );
+        if let Err(e) = init_gp_pari_pool(2, DEFAULT_TASK_TIMEOUT) {
+            println!("Warning: Failed to initialize GpPariPool for tests: {}", e);
         } else {
-            println!("GpPariService initialized successfully for tests");
+            println!("GpPariPool initialized successfully for tests");
         }
     }
 
@@ -374,9 +727,8 @@ mod tests {
         for i in 0..3 {
             let task = format!(r#"{{print("Task {}"); print("Done")}}"#, i);
             let handle = thread::spawn(move || {
-                // We can't actually share the mutable reference across threads,
-                // but this test demonstrates the mutex protection at the singleton level
-                // In practice, the singleton access is what's protected
+                // The service itself is not shared across threads here;
+                // concurrent callers go through GpPariPool instead
                 task
             });
             handles.push(handle);
@@ -401,4 +753,90 @@ mod tests {
             }
         }
     }
+
+    fn is_unavailable(e: &str) -> bool {
+        e.contains("Failed to spawn") || e.contains("not initialized")
+    }
+
+    #[test]
+    fn test_pool_from_multiple_threads() {
+        let pool = Arc::new(GpPariPool::new(
+            get_pari_executable_path().unwrap(),
+            2,
+            DEFAULT_TASK_TIMEOUT,
+        ));
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    let task = format!(r#"{{print("Task {}"); print("Done")}}"#, i);
+                    pool.run_task(task)
+                })
+            })
+            .collect();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            match handle.join().unwrap() {
+                Ok(output) => assert_eq!(output, vec![format!("Task {}", i)]),
+                Err(e) if is_unavailable(&e) => println!("Pari/GP not available: {}", e),
+                Err(e) => panic!("Unexpected error: {}", e),
+            }
+        }
+        // All processes are returned to the pool
+        assert_eq!(pool.idle.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_pool_cancellation() {
+        let pool = GpPariPool::new(get_pari_executable_path().unwrap(), 1, DEFAULT_TASK_TIMEOUT);
+        let token = CancellationToken::new();
+        drop(token.cancel_on_drop());
+        assert!(token.is_cancelled());
+
+        let result = with_cancellation(token, || {
+            pool.run_task(r#"{print("Hello"); print("Done")}"#.to_string())
+        });
+        assert_eq!(result, Err("Task cancelled".to_string()));
+        assert!(current_cancellation().is_none());
+    }
+
+    #[test]
+    fn test_task_limits_scope() {
+        let limits = TaskLimits {
+            timeout: Some(Duration::from_secs(30)),
+            max_stack_size: Some(1 << 30),
+        };
+        let inner = with_task_limits(limits, current_limits);
+        assert_eq!(inner, limits);
+        assert_eq!(current_limits(), TaskLimits::default());
+    }
+
+    #[test]
+    fn test_task_cache() {
+        let output = |s: &str| vec![s.to_string()];
+        let mut cache = TaskCache::new(2);
+        cache.set_persisted(true);
+        cache.preload([("a".to_string(), output("1"))]);
+        cache.insert("b".to_string(), output("2"));
+        // Reading "a" makes "b" the least recently used entry
+        assert_eq!(cache.get("a"), Some(output("1")));
+        cache.insert("c".to_string(), output("3"));
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), Some(output("3")));
+
+        // Preloaded entries are already stored
+        assert_eq!(
+            cache.take_new_entries(),
+            vec![
+                ("b".to_string(), output("2")),
+                ("c".to_string(), output("3"))
+            ]
+        );
+        assert!(cache.take_new_entries().is_empty());
+
+        let mut disabled = TaskCache::new(0);
+        disabled.insert("a".to_string(), output("1"));
+        assert_eq!(disabled.get("a"), None);
+    }
 }
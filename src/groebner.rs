@@ -0,0 +1,374 @@
+use crate::poly::{Poly, Term, Var, VarSet};
+use gcd::Gcd;
+use log::info;
+use std::rc::Rc;
+
+/// Maximum number of S-polynomials reduced before giving up
+const MAX_PAIRS: usize = 20000;
+
+/// A polynomial in distributive form: terms sorted by decreasing monomial in lex order.
+/// Exponents are indexed by the position of the variable in the variable ordering.
+#[derive(Debug, Clone, PartialEq)]
+struct DistributivePoly {
+    terms: Vec<(Vec<u32>, i128)>,
+}
+
+fn overflow_error() -> String {
+    "Coefficient overflow in Groebner basis computation".to_string()
+}
+
+fn gcd_i128(a: i128, b: i128) -> i128 {
+    a.unsigned_abs().gcd(b.unsigned_abs()) as i128
+}
+
+fn divides(m1: &[u32], m2: &[u32]) -> bool {
+    m1.iter().zip(m2).all(|(a, b)| a <= b)
+}
+
+fn lcm(m1: &[u32], m2: &[u32]) -> Vec<u32> {
+    m1.iter().zip(m2).map(|(a, b)| *a.max(b)).collect()
+}
+
+fn quotient(m1: &[u32], m2: &[u32]) -> Vec<u32> {
+    m1.iter().zip(m2).map(|(a, b)| a - b).collect()
+}
+
+fn total_degree(m: &[u32]) -> u32 {
+    m.iter().sum()
+}
+
+impl DistributivePoly {
+    fn from_poly(poly: &Poly, vars: &[Var]) -> Self {
+        let mut terms = Vec::new();
+        for term in poly.to_terms() {
+            let mut monomial = vec![0; vars.len()];
+            for (v, degree) in &term.vars {
+                let index = vars.iter().position(|var| var == v).unwrap();
+                monomial[index] += degree;
+            }
+            terms.push((monomial, term.constant as i128));
+        }
+        let mut result = Self { terms };
+        result.normalize();
+        result
+    }
+
+    fn to_poly(&self, vars: &[Var]) -> Result<Poly, String> {
+        let mut terms = Vec::new();
+        for (monomial, c) in &self.terms {
+            terms.push(Term {
+                constant: i64::try_from(*c).map_err(|_| overflow_error())?,
+                vars: monomial
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, degree)| **degree > 0)
+                    .map(|(i, degree)| (vars[i], *degree))
+                    .collect(),
+            });
+        }
+        Ok(Poly::from_term_list(&terms))
+    }
+
+    /// Sorts the terms, merges equal monomials and drops zero terms
+    fn normalize(&mut self) {
+        self.terms.sort_by(|a, b| b.0.cmp(&a.0));
+        let mut merged: Vec<(Vec<u32>, i128)> = Vec::with_capacity(self.terms.len());
+        for (monomial, c) in self.terms.drain(..) {
+            match merged.last_mut() {
+                Some((last, last_c)) if *last == monomial => *last_c += c,
+                _ => merged.push((monomial, c)),
+            }
+        }
+        merged.retain(|(_, c)| *c != 0);
+        self.terms = merged;
+    }
+
+    fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    fn leading_monomial(&self) -> &[u32] {
+        &self.terms[0].0
+    }
+
+    fn leading_coefficient(&self) -> i128 {
+        self.terms[0].1
+    }
+
+    /// Divides by the content and makes the leading coefficient positive
+    fn make_primitive(&mut self) {
+        if self.is_zero() {
+            return;
+        }
+        let mut content = 0;
+        for (_, c) in &self.terms {
+            content = gcd_i128(content, *c);
+        }
+        if self.leading_coefficient() < 0 {
+            content = -content;
+        }
+        for (_, c) in self.terms.iter_mut() {
+            *c /= content;
+        }
+    }
+
+    /// Multiplies by the monomial m (the order of the terms is preserved)
+    fn shift(&self, m: &[u32]) -> Self {
+        Self {
+            terms: self
+                .terms
+                .iter()
+                .map(|(monomial, c)| (monomial.iter().zip(m).map(|(d, e)| d + e).collect(), *c))
+                .collect(),
+        }
+    }
+
+    /// Computes a * self - b * m * other, where m is a monomial
+    fn combine(&self, a: i128, other: &Self, b: i128, m: &[u32]) -> Result<Self, String> {
+        let mut result = Vec::with_capacity(self.terms.len() + other.terms.len());
+        let mut i = 0;
+        let mut j = 0;
+        let shifted = |(monomial, _): &(Vec<u32>, i128)| -> Vec<u32> {
+            monomial.iter().zip(m).map(|(d, e)| d + e).collect()
+        };
+        while i < self.terms.len() || j < other.terms.len() {
+            let other_monomial = other.terms.get(j).map(shifted);
+            let take_self = match (&self.terms.get(i), &other_monomial) {
+                (Some((monomial, _)), Some(other_monomial)) => monomial >= other_monomial,
+                (Some(_), None) => true,
+                _ => false,
+            };
+            if take_self {
+                let (monomial, c) = &self.terms[i];
+                let c = c.checked_mul(a).ok_or_else(overflow_error)?;
+                if other_monomial.as_ref() == Some(monomial) {
+                    let d = other.terms[j].1.checked_mul(b).ok_or_else(overflow_error)?;
+                    let c = c.checked_sub(d).ok_or_else(overflow_error)?;
+                    if c != 0 {
+                        result.push((monomial.clone(), c));
+                    }
+                    j += 1;
+                } else {
+                    result.push((monomial.clone(), c));
+                }
+                i += 1;
+            } else {
+                let d = other.terms[j].1.checked_mul(b).ok_or_else(overflow_error)?;
+                result.push((other_monomial.unwrap(), -d));
+                j += 1;
+            }
+        }
+        Ok(Self { terms: result })
+    }
+
+    /// Fully reduces the polynomial modulo the basis (fraction-free, keeping it primitive)
+    fn reduce(&self, basis: &[Self]) -> Result<Self, String> {
+        let mut f = self.clone();
+        let mut i = 0;
+        while i < f.terms.len() {
+            let (monomial, c) = f.terms[i].clone();
+            match basis
+                .iter()
+                .find(|g| divides(g.leading_monomial(), &monomial))
+            {
+                Some(g) => {
+                    let d = gcd_i128(c, g.leading_coefficient());
+                    let m = quotient(&monomial, g.leading_monomial());
+                    // The terms above i are not affected, so the scan continues at i
+                    f = f.combine(g.leading_coefficient() / d, g, c / d, &m)?;
+                    f.make_primitive();
+                }
+                None => i += 1,
+            }
+        }
+        Ok(f)
+    }
+
+    fn s_poly(&self, other: &Self) -> Result<Self, String> {
+        let l = lcm(self.leading_monomial(), other.leading_monomial());
+        let d = gcd_i128(self.leading_coefficient(), other.leading_coefficient());
+        let a = other.leading_coefficient() / d;
+        let b = self.leading_coefficient() / d;
+        let m1 = quotient(&l, self.leading_monomial());
+        let m2 = quotient(&l, other.leading_monomial());
+        // a * m1 * self - b * m2 * other
+        let mut result = self.shift(&m1).combine(a, other, b, &m2)?;
+        result.make_primitive();
+        Ok(result)
+    }
+}
+
+/// Computes the reduced Groebner basis (lex order) of the given polynomials
+fn groebner_basis(polys: Vec<DistributivePoly>) -> Result<Vec<DistributivePoly>, String> {
+    let mut basis: Vec<DistributivePoly> = Vec::new();
+    for mut poly in polys {
+        poly.make_primitive();
+        if !poly.is_zero() {
+            basis.push(poly);
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for j in 0..basis.len() {
+        for i in 0..j {
+            pairs.push((i, j));
+        }
+    }
+
+    let mut pair_count = 0;
+    while !pairs.is_empty() {
+        // Normal selection strategy: the pair with the lowest lcm degree first
+        let (index, _) = pairs
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (i, j))| {
+                total_degree(&lcm(
+                    basis[*i].leading_monomial(),
+                    basis[*j].leading_monomial(),
+                ))
+            })
+            .unwrap();
+        let (i, j) = pairs.swap_remove(index);
+
+        // Buchberger's first criterion: coprime leading monomials reduce to zero
+        let l = lcm(basis[i].leading_monomial(), basis[j].leading_monomial());
+        if total_degree(&l)
+            == total_degree(basis[i].leading_monomial()) + total_degree(basis[j].leading_monomial())
+        {
+            continue;
+        }
+
+        pair_count += 1;
+        if pair_count > MAX_PAIRS {
+            return Err(format!(
+                "Groebner basis computation exceeded {} S-polynomials",
+                MAX_PAIRS
+            ));
+        }
+
+        let remainder = basis[i].s_poly(&basis[j])?.reduce(&basis)?;
+        if !remainder.is_zero() {
+            let k = basis.len();
+            for m in 0..k {
+                pairs.push((m, k));
+            }
+            basis.push(remainder);
+        }
+    }
+    info!("Groebner basis: {} S-polynomials reduced", pair_count);
+
+    // Minimize: drop elements whose leading monomial is divisible by another one
+    let mut minimal: Vec<DistributivePoly> = Vec::new();
+    for (i, g) in basis.iter().enumerate() {
+        let redundant = basis.iter().enumerate().any(|(j, h)| {
+            j != i
+                && divides(h.leading_monomial(), g.leading_monomial())
+                && (h.leading_monomial() != g.leading_monomial() || j < i)
+        });
+        if !redundant {
+            minimal.push(g.clone());
+        }
+    }
+
+    // Interreduce
+    let mut reduced = Vec::with_capacity(minimal.len());
+    for i in 0..minimal.len() {
+        let others: Vec<DistributivePoly> = minimal
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, g)| g.clone())
+            .collect();
+        reduced.push(minimal[i].reduce(&others)?);
+    }
+    reduced.sort_by(|a, b| a.leading_monomial().cmp(b.leading_monomial()));
+    Ok(reduced)
+}
+
+/// Eliminates all variables except x_var and y_var by computing a Groebner basis
+/// with respect to a lex order where the eliminated variables come first.
+/// Returns the generators of the elimination ideal (polynomials in x_var and y_var only),
+/// sorted by increasing leading monomial.
+pub fn eliminate(polys: &[Rc<Poly>], x_var: Var, y_var: Var) -> Result<Vec<Poly>, String> {
+    let mut used_vars = VarSet::new();
+    for poly in polys {
+        poly.fill_in_variables(&mut used_vars);
+    }
+    used_vars.remove(x_var);
+    used_vars.remove(y_var);
+    let mut vars: Vec<Var> = used_vars.iter().collect();
+    let eliminated_count = vars.len();
+    vars.push(x_var);
+    vars.push(y_var);
+
+    let distributive_polys = polys
+        .iter()
+        .map(|poly| DistributivePoly::from_poly(poly, &vars))
+        .collect();
+    let basis = groebner_basis(distributive_polys)?;
+
+    if basis
+        .iter()
+        .any(|g| total_degree(g.leading_monomial()) == 0)
+    {
+        return Err("The system of equations is inconsistent".to_string());
+    }
+
+    basis
+        .iter()
+        .filter(|g| {
+            g.leading_monomial()[..eliminated_count]
+                .iter()
+                .all(|d| *d == 0)
+        })
+        .map(|g| g.to_poly(&vars))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rc_polys(polys: &[&str]) -> Vec<Rc<Poly>> {
+        polys
+            .iter()
+            .map(|s| Rc::new(Poly::new(s).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_reduce() {
+        let vars = vec![0, 1];
+        let f = DistributivePoly::from_poly(&Poly::new("a^2*b + a*b^2").unwrap(), &vars);
+        let g = DistributivePoly::from_poly(&Poly::new("a*b - 1").unwrap(), &vars);
+        // a^2*b + a*b^2 = (a + b) * (a*b - 1) + a + b
+        let r = f.reduce(&[g]).unwrap();
+        assert_eq!(r.to_poly(&vars).unwrap(), Poly::new("a + b").unwrap());
+    }
+
+    #[test]
+    fn test_eliminate_circle() {
+        // a = (1 - c^2) / (1 + c^2), b = 2c / (1 + c^2)
+        let polys = rc_polys(&["a + a*c^2 - 1 + c^2", "b + b*c^2 - 2*c"]);
+        let result = eliminate(&polys, 0, 1).unwrap();
+        assert_eq!(result.len(), 1);
+        let mut factor = None;
+        assert!(result[0].is_proportional(&Poly::new("a^2 + b^2 - 1").unwrap(), &mut factor));
+    }
+
+    #[test]
+    fn test_eliminate_two_variables() {
+        // a = c + d, b = c - d, c*d = 1  =>  a^2 - b^2 = 4
+        let polys = rc_polys(&["a - c - d", "b - c + d", "c*d - 1"]);
+        let result = eliminate(&polys, 0, 1).unwrap();
+        assert_eq!(result.len(), 1);
+        let mut factor = None;
+        assert!(result[0].is_proportional(&Poly::new("a^2 - b^2 - 4").unwrap(), &mut factor));
+    }
+
+    #[test]
+    fn test_eliminate_inconsistent() {
+        let polys = rc_polys(&["c - 1", "c - 2", "a - b"]);
+        assert!(eliminate(&polys, 0, 1).is_err());
+    }
+}
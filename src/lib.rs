@@ -0,0 +1,32 @@
+//! The algebra core: polynomials, variable elimination and curve rasterization.
+//!
+//! It has no process, database or web dependencies unless the `pari` feature is
+//! enabled, so it also builds for wasm32-unknown-unknown (see `wasm` for the
+//! JavaScript API). The web server in main.rs builds scenes on top of it.
+
+pub mod budget;
+pub mod cancellation;
+pub mod cas;
+pub mod dd_int;
+pub mod determinant;
+pub mod elimination;
+pub mod engine;
+pub mod fint;
+#[cfg(feature = "pari")]
+pub mod gp_pari_service;
+pub mod groebner;
+pub mod metrics;
+pub mod modular_determinant;
+pub mod modular_poly;
+pub mod poly;
+pub mod poly_draw;
+pub mod progress;
+pub mod replay;
+pub mod roots;
+pub mod sampling;
+pub mod trace;
+pub mod variables;
+pub mod warning;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod x_poly;
@@ -1,34 +1,51 @@
+mod api;
+mod batch;
+mod config;
+mod curve_analysis;
 mod db;
-mod elimination;
-mod fint;
-mod gp_pari_service;
-mod modular_poly;
-mod poly;
-mod poly_draw;
+mod demo;
+mod dof_analysis;
+mod equation_compiler;
+mod equation_source;
+mod expression_checker;
+mod migration;
 mod scene;
 mod scene_object;
 mod scene_utils;
-mod service;
-mod x_poly;
+
+// The algebra core, at the same paths as the server's own modules
+pub use poly_algebra_core::{
+    budget, cancellation, cas, dd_int, determinant, elimination, fint, gp_pari_service, groebner,
+    metrics, modular_determinant, modular_poly, poly, poly_draw, progress, replay, roots, sampling,
+    trace, variables, warning, x_poly,
+};
 
 use chrono::Utc;
 use clap::{Parser, Subcommand};
 use log::info;
-use sea_orm::{ActiveModelTrait, ConnectOptions, ConnectionTrait, Set};
-use sea_orm::{Database, DatabaseConnection, Statement};
-use std::fs;
+use sea_orm::{ActiveModelTrait, ConnectOptions, EntityTrait, PaginatorTrait, Set};
+use sea_orm::{Database, DatabaseConnection};
+use sea_orm_migration::MigratorTrait;
 use std::path::Path;
-use std::process::Command;
-
-use crate::db::SceneActiveModel;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{ConfigFile, ServerConfig};
+use crate::db::{PariCacheModel, SceneActiveModel, SceneEntity, UserModel};
+use crate::migration::Migrator;
+use crate::scene::{Center, EliminationMethod, SceneOptions, View};
 use actix_cors::Cors;
 use actix_web::{web, App, HttpServer};
 
-// Global variable to store the Pari/GP executable path
-static mut PARI_EXECUTABLE_PATH: Option<String> = None;
-
-// Global singleton for GpPariService
-static mut GP_PARI_SERVICE: Option<gp_pari_service::GpPariService> = None;
+/// Computer algebra systems selectable with --cas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum CasKind {
+    Pari,
+    Singular,
+    /// The crate's own algorithms, without factoring
+    Native,
+}
 
 #[derive(Parser)]
 #[command(name = "poly_algebra")]
@@ -40,128 +57,176 @@ struct Cli {
     /// Specify Pari/GP executable path
     #[arg(long, value_name = "PATH")]
     gp_executable: Option<String>,
+
+    /// Number of Pari/GP processes running tasks in parallel
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    gp_pool_size: usize,
+
+    /// Time a single Pari/GP or Singular task may take, in seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+    gp_task_timeout: u64,
+
+    /// Number of Pari/GP task outputs kept in memory, 0 to disable the cache
+    #[arg(long, value_name = "N", default_value_t = gp_pari_service::DEFAULT_CACHE_CAPACITY)]
+    gp_cache_size: usize,
+
+    /// Store the cached Pari/GP outputs in the database, so that they survive restarts
+    #[arg(long, env = "POLY_ALGEBRA_PERSIST_GP_CACHE")]
+    persist_gp_cache: bool,
+
+    /// Largest total degree of the polynomials of an elimination
+    #[arg(long, value_name = "DEGREE", env = "POLY_ALGEBRA_MAX_TOTAL_DEGREE")]
+    max_total_degree: Option<u32>,
+
+    /// Largest number of terms of the polynomials of an elimination
+    #[arg(long, value_name = "N", env = "POLY_ALGEBRA_MAX_TERMS")]
+    max_terms: Option<usize>,
+
+    /// Largest number of pseudo-remainder steps of an elimination
+    #[arg(long, value_name = "N", env = "POLY_ALGEBRA_MAX_ELIMINATION_STEPS")]
+    max_elimination_steps: Option<usize>,
+
+    /// Number of loci solved at the same time by jobs of POST /scenes/{id}/loci/{name}/solve
+    #[arg(long, value_name = "N", default_value_t = api::jobs::DEFAULT_JOB_WORKERS)]
+    job_workers: usize,
+
+    /// Require an API token with every request, so that users only access their own
+    /// scenes; tokens are created with add-user
+    #[arg(long, env = "POLY_ALGEBRA_AUTH")]
+    auth: bool,
+
+    /// Computer algebra system used for factoring and gcds
+    #[arg(long, value_enum, default_value_t = CasKind::Pari, env = "POLY_ALGEBRA_CAS")]
+    cas: CasKind,
+
+    /// Specify Singular executable path, used with --cas singular
+    #[arg(long, value_name = "PATH", default_value = "Singular")]
+    singular_executable: String,
+
+    /// JSON config file with the bind, db and allowed_origins settings
+    /// [default: poly_algebra.json, if present]
+    #[arg(long, value_name = "PATH", env = "POLY_ALGEBRA_CONFIG")]
+    config: Option<String>,
+
+    /// Address the web server listens on [default: 127.0.0.1:8080]
+    #[arg(long, value_name = "HOST:PORT", env = "POLY_ALGEBRA_BIND")]
+    bind: Option<String>,
+
+    /// Path of the SQLite database [default: scenes.db]
+    #[arg(long, value_name = "PATH", env = "POLY_ALGEBRA_DB")]
+    db: Option<String>,
+
+    /// Origin allowed to call the API, may be repeated [default: http://localhost:5174]
+    #[arg(
+        long = "allowed-origin",
+        value_name = "ORIGIN",
+        env = "POLY_ALGEBRA_ALLOWED_ORIGINS",
+        value_delimiter = ','
+    )]
+    allowed_origins: Vec<String>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Initialize database
+    /// Create the database if needed, apply pending migrations and add a first scene
     Init,
+    /// Upgrade the database schema in place, keeping existing data
+    Migrate,
     /// Start web server
     Start,
+    /// Add a user of a shared server and print their API token
+    AddUser {
+        name: String,
+        /// Let the user see and change the scenes of all users
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Run built-in constructions in memory and write their plots
+    Demo {
+        /// Directory for the demo plots
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        output_dir: String,
+    },
+    /// Eliminate the variables of a polynomial system read from a file, without the
+    /// web server and the database
+    Eliminate {
+        /// File with one equation per line, `lhs = rhs` or a polynomial equal to 0;
+        /// lines starting with # are skipped
+        #[arg(long, value_name = "FILE")]
+        input: String,
+        /// Variable of the x coordinate
+        #[arg(long, value_name = "VAR")]
+        x: String,
+        /// Variable of the y coordinate
+        #[arg(long, value_name = "VAR")]
+        y: String,
+        /// "resultant" or "groebner"
+        #[arg(long, value_name = "METHOD", default_value = "resultant")]
+        elimination_method: String,
+        /// Write the curve to a PNG file
+        #[arg(long, value_name = "FILE")]
+        plot: Option<String>,
+        /// Width and height of the plot in pixels
+        #[arg(long, value_name = "PIXELS", default_value_t = 800)]
+        size: u32,
+        /// Center of the plotted view, as "x,y"
+        #[arg(
+            long,
+            value_name = "X,Y",
+            default_value = "0,0",
+            allow_hyphen_values = true
+        )]
+        center: String,
+        /// Diagonal of the plotted view
+        #[arg(long, default_value_t = 25.0)]
+        diagonal: f64,
+        /// Write the steps of the elimination to a JSON file
+        #[arg(long, value_name = "FILE")]
+        trace: Option<String>,
+        /// Eliminate the variables in the order of a trace written by --trace, e.g. for
+        /// the same system with other coefficients
+        #[arg(long, value_name = "FILE")]
+        replay: Option<String>,
+    },
 }
 
-/// Get the Pari/GP executable path, resolving it from command line arguments or system PATH
-pub fn get_pari_executable_path() -> Result<String, String> {
-    // Check if we have a cached path
-    unsafe {
-        if let Some(ref path) = PARI_EXECUTABLE_PATH {
-            return Ok(path.clone());
-        }
-    }
-
-    // Get the CLI arguments
-    let cli = Cli::parse();
-
-    // Check if --gp-executable was provided
-    if let Some(path) = cli.gp_executable {
-        // Validate that the executable exists
-        if Path::new(&path).exists() {
-            unsafe {
-                PARI_EXECUTABLE_PATH = Some(path.clone());
-            }
-            return Ok(path);
-        } else {
-            return Err(format!("Pari/GP executable not found at: {}", path));
-        }
+/// Connects to the database, creating it if needed, and applies all pending migrations
+async fn migrate_database(
+    config: &ServerConfig,
+) -> Result<DatabaseConnection, Box<dyn std::error::Error>> {
+    let mut connect_options = ConnectOptions::new(config.database_url());
+    connect_options.sqlx_logging(false);
+    let db = Database::connect(connect_options).await?;
+
+    let pending = Migrator::get_pending_migrations(&db).await?;
+    for migration in &pending {
+        info!("Applying migration: {}", migration.name());
     }
+    Migrator::up(&db, None).await?;
 
-    // If no explicit path provided, try to find gp executable in system PATH
-    let gp_names = if cfg!(target_os = "windows") {
-        vec!["gp.exe", "gp"]
-    } else {
-        vec!["gp", "gp.exe"]
-    };
-
-    for name in gp_names {
-        match Command::new(name).arg("--version").output() {
-            Ok(_) => {
-                let path = name.to_string();
-                unsafe {
-                    PARI_EXECUTABLE_PATH = Some(path.clone());
-                }
-                return Ok(path);
-            }
-            Err(_) => continue,
-        }
-    }
-
-    Err("Pari/GP executable not found. Please install Pari/GP or specify the path with --gp-executable".to_string())
-}
-
-/// Set the Pari/GP executable path (for testing or manual override)
-pub fn set_pari_executable_path(path: String) {
-    unsafe {
-        PARI_EXECUTABLE_PATH = Some(path);
-    }
-}
-
-/// Initialize the global GpPariService singleton
-pub fn init_gp_pari_service() -> Result<(), String> {
-    let executable_path = get_pari_executable_path()?;
-    unsafe {
-        GP_PARI_SERVICE = Some(gp_pari_service::GpPariService::new(executable_path));
-    }
-    Ok(())
+    info!(
+        "Database at {:?} is up to date ({} migrations applied)",
+        config.db_path,
+        pending.len()
+    );
+    Ok(db)
 }
 
-/// Get a mutable reference to the global GpPariService singleton
-pub fn get_gp_pari_service() -> Result<&'static mut gp_pari_service::GpPariService, String> {
-    unsafe {
-        if let Some(ref mut service) = GP_PARI_SERVICE {
-            Ok(service)
-        } else {
-            Err("GpPariService not initialized".to_string())
-        }
+/// View centered at "x,y" with the given diagonal
+fn parse_view(center: &str, diagonal: f64) -> Result<View, Box<dyn std::error::Error>> {
+    let (x, y) = center
+        .split_once(',')
+        .ok_or_else(|| format!("Invalid view center {}, expected x,y", center))?;
+    if diagonal <= 0.0 {
+        return Err("The view diagonal must be positive".into());
     }
-}
-
-async fn init_database() -> Result<DatabaseConnection, Box<dyn std::error::Error>> {
-    // Database file path
-    let db_path = "scenes.db";
-
-    // Remove existing database if it exists
-    if Path::new(db_path).exists() {
-        fs::remove_file(db_path)?;
-    }
-
-    // Connect to database (this will create the file)
-    let db = Database::connect(format!("sqlite://{}?mode=rwc", db_path)).await?;
-
-    // Read all migration files from migrations folder
-    let migrations_dir = Path::new("migrations");
-    let mut migration_files: Vec<_> = fs::read_dir(migrations_dir)?
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.path().extension().and_then(|s| s.to_str()) == Some("sql"))
-        .collect();
-
-    // Sort migration files by filename to ensure correct order
-    migration_files.sort_by_key(|a| a.file_name());
-
-    // Apply each migration in order
-    for migration_file in migration_files {
-        let migration_content = fs::read_to_string(migration_file.path())?;
-        info!("Applying migration: {:?}", migration_file.file_name());
-
-        // Execute migration
-        db.execute(Statement::from_string(
-            sea_orm::DatabaseBackend::Sqlite,
-            migration_content,
-        ))
-        .await?;
-    }
-
-    info!("Database initialized successfully at {:?}", db_path);
-    Ok(db)
+    Ok(View {
+        center: Center {
+            x: x.trim().parse()?,
+            y: y.trim().parse()?,
+        },
+        diagonal,
+    })
 }
 
 #[actix_web::main]
@@ -172,7 +237,15 @@ async fn main() -> std::io::Result<()> {
     let cli = Cli::parse();
 
     // Initialize Pari/GP executable path
-    match get_pari_executable_path() {
+    let pari_executable_path = match &cli.gp_executable {
+        Some(path) if Path::new(path).exists() => {
+            gp_pari_service::set_pari_executable_path(path.clone());
+            Ok(path.clone())
+        }
+        Some(path) => Err(format!("Pari/GP executable not found at: {}", path)),
+        None => gp_pari_service::get_pari_executable_path(),
+    };
+    match &pari_executable_path {
         Ok(path) => info!("Using Pari/GP executable: {}", path),
         Err(e) => {
             eprintln!("Warning: {}", e);
@@ -180,54 +253,207 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
-    // Initialize GpPariService singleton
-    if let Err(e) = init_gp_pari_service() {
-        eprintln!("Warning: Failed to initialize GpPariService: {}", e);
+    // Initialize the Pari/GP process pool
+    let pool_result = pari_executable_path.and_then(|_| {
+        gp_pari_service::init_gp_pari_pool(
+            cli.gp_pool_size,
+            Duration::from_secs(cli.gp_task_timeout),
+        )
+    });
+    if let Err(e) = pool_result {
+        eprintln!("Warning: Failed to initialize GpPariPool: {}", e);
         eprintln!("Pari/GP functionality will be limited");
     } else {
-        info!("GpPariService initialized successfully");
+        info!(
+            "GpPariPool initialized with {} processes",
+            cli.gp_pool_size.max(1)
+        );
+        if let Ok(pool) = gp_pari_service::get_gp_pari_pool() {
+            pool.configure_cache(cli.gp_cache_size, cli.persist_gp_cache);
+        }
     }
 
+    match cli.cas {
+        CasKind::Pari => {}
+        CasKind::Singular => cas::set_backend(Arc::new(cas::SingularBackend::new(
+            cli.singular_executable.clone(),
+            Duration::from_secs(cli.gp_task_timeout),
+        ))),
+        CasKind::Native => cas::set_backend(Arc::new(cas::NativeBackend)),
+    }
+    info!("Using {} for factoring and gcds", cas::backend().name());
+
+    budget::set_global_budget(budget::Budget {
+        max_total_degree: cli.max_total_degree,
+        max_terms: cli.max_terms,
+        max_elimination_steps: cli.max_elimination_steps,
+    });
+
+    let config_file = match ConfigFile::load(cli.config.as_deref()) {
+        Ok(config_file) => config_file,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(std::io::Error::other(e));
+        }
+    };
+    let config = ServerConfig::resolve(cli.bind, cli.db, cli.allowed_origins, config_file);
+    let persist_gp_cache = cli.persist_gp_cache;
+    let gp_cache_size = cli.gp_cache_size;
+    let require_auth = cli.auth;
+    let job_workers = cli.job_workers;
+
     match cli.command {
         Commands::Init => {
-            let db = init_database().await.unwrap();
-            let scene = SceneActiveModel {
-                id: Set(1),
-                created_at: Set(Utc::now()),
-                view: Set("{\"center\": {\"x\": 0.0, \"y\": 0.0}, \"diagonal\": 25.0}".to_string()),
-                name: Set("Scene 1".to_string()),
-            };
-
-            match scene.insert(&db).await {
-                Ok(_) => (),
-                Err(e) => eprintln!("Failed to add an object to the database: {}", e),
+            let db = migrate_database(&config).await.unwrap();
+            // Add a first scene unless the database already has some
+            let scene_count = SceneEntity::find().count(&db).await.unwrap_or(0);
+            if scene_count == 0 {
+                let scene = SceneActiveModel {
+                    id: Set(1),
+                    created_at: Set(Utc::now()),
+                    view: Set(
+                        "{\"center\": {\"x\": 0.0, \"y\": 0.0}, \"diagonal\": 25.0}".to_string()
+                    ),
+                    name: Set("Scene 1".to_string()),
+                    version: Set(0),
+                    user_id: Set(None),
+                    options: Set(None),
+                };
+
+                match scene.insert(&db).await {
+                    Ok(_) => (),
+                    Err(e) => eprintln!("Failed to add a scene to the database: {}", e),
+                }
+            }
+            return Ok(());
+        }
+        Commands::Migrate => {
+            if let Err(e) = migrate_database(&config).await {
+                eprintln!("Migration failed: {}", e);
+                return Err(std::io::Error::other(e.to_string()));
             }
             return Ok(());
         }
         Commands::Start => {
-            let mut connect_options = ConnectOptions::new("sqlite://scenes.db?mode=rwc");
+            let mut connect_options = ConnectOptions::new(config.database_url());
             connect_options.sqlx_logging(false);
             let db = Database::connect(connect_options).await.unwrap();
-            let app_state = service::AppState::new(db).await;
+            match Migrator::get_pending_migrations(&db).await {
+                Ok(pending) if !pending.is_empty() => eprintln!(
+                    "Warning: the database has {} pending migrations, run `poly_algebra migrate`",
+                    pending.len()
+                ),
+                Ok(_) => (),
+                Err(e) => eprintln!("Warning: failed to check database migrations: {}", e),
+            }
+            if persist_gp_cache {
+                if let Ok(pool) = gp_pari_service::get_gp_pari_pool() {
+                    match PariCacheModel::load_recent(&db, gp_cache_size).await {
+                        Ok(entries) => {
+                            info!("Loaded {} cached Pari/GP outputs", entries.len());
+                            pool.preload_cache(entries);
+                        }
+                        Err(e) => eprintln!("Warning: failed to load the Pari/GP cache: {}", e),
+                    }
+                }
+            }
+            let mut app_state = api::AppState::new(db).await.with_job_workers(job_workers);
+            if require_auth {
+                info!("Requests need an API token");
+                app_state = app_state.requiring_auth();
+            }
 
+            let allowed_origins = config.allowed_origins.clone();
+            info!(
+                "Listening on {}, allowing origins {:?}",
+                config.bind, allowed_origins
+            );
             HttpServer::new(move || {
+                let cors = allowed_origins
+                    .iter()
+                    .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+                    .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
+                    .allowed_headers(vec![
+                        actix_web::http::header::CONTENT_TYPE,
+                        actix_web::http::header::AUTHORIZATION,
+                        actix_web::http::header::IF_MATCH,
+                    ])
+                    .expose_headers(vec![actix_web::http::header::ETAG])
+                    .supports_credentials();
                 App::new()
-                    .wrap(
-                        Cors::default()
-                            .allowed_origin("http://localhost:5174")
-                            .allowed_methods(vec![
-                                "GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS",
-                            ])
-                            .allowed_header(actix_web::http::header::CONTENT_TYPE)
-                            .supports_credentials(),
-                    )
+                    .wrap(cors)
                     .app_data(web::Data::new(app_state.clone()))
-                    .configure(service::config)
+                    .configure(api::config)
             })
-            .bind(("127.0.0.1", 8080))?
+            .bind(&config.bind)?
             .run()
             .await?;
         }
+        Commands::AddUser { name, admin } => {
+            let db = match migrate_database(&config).await {
+                Ok(db) => db,
+                Err(e) => {
+                    eprintln!("Migration failed: {}", e);
+                    return Err(std::io::Error::other(e.to_string()));
+                }
+            };
+            match UserModel::create(&db, &name, admin).await {
+                Ok((user, token)) => {
+                    println!("Added user {} (id {})", user.name, user.id);
+                    println!("API token, shown only once: {}", token);
+                }
+                Err(e) => {
+                    eprintln!("Failed to add user {}: {}", name, e);
+                    return Err(std::io::Error::other(e.to_string()));
+                }
+            }
+        }
+        Commands::Demo { output_dir } => {
+            if let Err(e) = demo::run_demos(Path::new(&output_dir)).await {
+                eprintln!("{}", e);
+                return Err(std::io::Error::other(e.to_string()));
+            }
+        }
+        Commands::Eliminate {
+            input,
+            x,
+            y,
+            elimination_method,
+            plot,
+            size,
+            center,
+            diagonal,
+            trace,
+            replay,
+        } => {
+            let result = parse_view(&center, diagonal).and_then(|view| {
+                let options = SceneOptions {
+                    elimination_method: EliminationMethod::from_str(&elimination_method)?,
+                    replay: replay
+                        .as_ref()
+                        .map(|path| batch::read_elimination_order(Path::new(path)))
+                        .transpose()?,
+                    ..SceneOptions::default()
+                };
+                let plot_output = plot.as_ref().map(|path| batch::PlotOutput {
+                    path: Path::new(path),
+                    view,
+                    size,
+                });
+                batch::run_elimination(
+                    Path::new(&input),
+                    &x,
+                    &y,
+                    options,
+                    plot_output,
+                    trace.as_deref().map(Path::new),
+                )
+            });
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                return Err(std::io::Error::other(e.to_string()));
+            }
+        }
     }
 
     Ok(())
@@ -0,0 +1,270 @@
+//! Process-wide counters and timers of the computations, for monitoring the server.
+//! `render` writes them in the Prometheus text format, which GET /metrics serves.
+//!
+//! The values only grow; rates and averages are left to the monitoring system.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Prefix of the names of all metrics
+const PREFIX: &str = "poly_algebra";
+
+/// A count of events
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    value: AtomicU64,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    pub fn inc(&self) {
+        self.value.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    fn render(&self, out: &mut String) {
+        let name = format!("{}_{}_total", PREFIX, self.name);
+        let _ = writeln!(out, "# HELP {} {}", name, self.help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        let _ = writeln!(out, "{} {}", name, self.get());
+    }
+}
+
+/// The number and total duration of a computation, a Prometheus summary without
+/// quantiles
+pub struct Timer {
+    name: &'static str,
+    help: &'static str,
+    count: AtomicU64,
+    micros: AtomicU64,
+}
+
+impl Timer {
+    pub const fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            count: AtomicU64::new(0),
+            micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Times the computation until the returned guard is dropped
+    pub fn start(&self) -> TimerGuard<'_> {
+        TimerGuard {
+            timer: self,
+            start: clock::now(),
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn total(&self) -> Duration {
+        Duration::from_micros(self.micros.load(Ordering::Relaxed))
+    }
+
+    fn render(&self, out: &mut String) {
+        let name = format!("{}_{}_seconds", PREFIX, self.name);
+        let _ = writeln!(out, "# HELP {} {}", name, self.help);
+        let _ = writeln!(out, "# TYPE {} summary", name);
+        let _ = writeln!(out, "{}_sum {}", name, self.total().as_secs_f64());
+        let _ = writeln!(out, "{}_count {}", name, self.count());
+    }
+}
+
+/// Observes the time since `Timer::start` when dropped, so early returns count too
+pub struct TimerGuard<'a> {
+    timer: &'a Timer,
+    start: Option<clock::Instant>,
+}
+
+impl Drop for TimerGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            self.timer.observe(start.elapsed());
+        }
+    }
+}
+
+/// Hits and misses of a cache, labelled by the name of the cache
+pub struct CacheCounter {
+    cache: &'static str,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheCounter {
+    pub const fn new(cache: &'static str) -> Self {
+        Self {
+            cache,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+// The clock of the standard library panics in the browser, so durations are not
+// measured there
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod clock {
+    pub use std::time::Instant;
+
+    pub fn now() -> Option<Instant> {
+        Some(Instant::now())
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod clock {
+    #[derive(Clone, Copy)]
+    pub struct Instant;
+
+    impl Instant {
+        pub fn elapsed(&self) -> std::time::Duration {
+            std::time::Duration::ZERO
+        }
+    }
+
+    pub fn now() -> Option<Instant> {
+        None
+    }
+}
+
+/// Requests for the curve of a locus, as JSON, SVG, PNG or a curve analysis
+pub static PLOT_REQUESTS: Counter = Counter::new(
+    "plot_requests",
+    "Requests for the curve of a locus, in any format",
+);
+
+/// Eliminations of one variable by `Elimination::eliminate_var`
+pub static ELIMINATION: Timer = Timer::new(
+    "elimination_step",
+    "Time spent eliminating one variable from the equations of a locus",
+);
+
+/// Tasks run on the Pari/GP processes, whether they succeed or not
+pub static PARI_TASKS: Timer = Timer::new(
+    "pari_task",
+    "Time spent running tasks on Pari/GP processes, including waiting for one",
+);
+
+pub static PARI_TIMEOUTS: Counter = Counter::new(
+    "pari_timeouts",
+    "Pari/GP tasks stopped for exceeding their timeout",
+);
+
+/// Rasterizations of curves by `XYPolyDraw`
+pub static CURVE_DRAWING: Timer = Timer::new(
+    "curve_drawing",
+    "Time spent finding the cells of the image that curves pass through",
+);
+
+/// Solved curves reused from the database by plot requests
+pub static CURVE_CACHE: CacheCounter = CacheCounter::new("curve");
+
+/// Task outputs reused by `GpPariPool::run_cached_task`
+pub static PARI_CACHE: CacheCounter = CacheCounter::new("pari");
+
+/// Pseudo-remainder chains reused by incremental eliminations
+pub static CHAIN_CACHE: CacheCounter = CacheCounter::new("chain");
+
+/// All metrics in the Prometheus text exposition format (version 0.0.4)
+pub fn render() -> String {
+    let mut out = String::new();
+    PLOT_REQUESTS.render(&mut out);
+    ELIMINATION.render(&mut out);
+    PARI_TASKS.render(&mut out);
+    PARI_TIMEOUTS.render(&mut out);
+    CURVE_DRAWING.render(&mut out);
+
+    let caches = [&CURVE_CACHE, &PARI_CACHE, &CHAIN_CACHE];
+    for (hits, help) in [
+        (true, "Lookups that found an entry in a cache"),
+        (false, "Lookups that found no entry in a cache"),
+    ] {
+        let name = format!(
+            "{}_cache_{}_total",
+            PREFIX,
+            if hits { "hits" } else { "misses" }
+        );
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        for cache in caches {
+            let value = if hits { cache.hits() } else { cache.misses() };
+            let _ = writeln!(out, "{}{{cache=\"{}\"}} {}", name, cache.cache, value);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let counter = Counter::new("test_events", "Events of the test");
+        counter.inc();
+        counter.inc();
+        let timer = Timer::new("test_step", "Steps of the test");
+        timer.observe(Duration::from_millis(1500));
+        drop(timer.start());
+
+        let mut out = String::new();
+        counter.render(&mut out);
+        timer.render(&mut out);
+        assert!(out.contains("# TYPE poly_algebra_test_events_total counter\n"));
+        assert!(out.contains("\npoly_algebra_test_events_total 2\n"));
+        assert!(out.contains("# TYPE poly_algebra_test_step_seconds summary\n"));
+        assert!(out.contains("\npoly_algebra_test_step_seconds_count 2\n"));
+        assert!(timer.total() >= Duration::from_millis(1500));
+
+        // The statics may be updated by other tests, so only the format is checked
+        CHAIN_CACHE.record(true);
+        let rendered = render();
+        assert!(rendered.contains("poly_algebra_plot_requests_total "));
+        assert!(rendered.contains("poly_algebra_pari_task_seconds_sum "));
+        assert!(rendered.contains("poly_algebra_cache_hits_total{cache=\"chain\"} "));
+        assert!(rendered.contains("poly_algebra_cache_misses_total{cache=\"curve\"} "));
+        assert!(rendered.lines().all(|line| {
+            line.starts_with("# ")
+                || line
+                    .split(' ')
+                    .nth(1)
+                    .is_some_and(|v| v.parse::<f64>().is_ok())
+        }));
+    }
+}
@@ -0,0 +1,133 @@
+//! Versioned schema migrations. Each migration applies one of the SQL files in
+//! `migrations/`; the applied ones are recorded in the `seaql_migrations` table,
+//! so `Migrator::up` upgrades a database in place.
+//! Databases created before the migrations were tracked already contain some of
+//! the changes, which the migrations detect and skip.
+
+use sea_orm_migration::prelude::*;
+
+mod m20240320_000000_create_tables;
+mod m20240321_000000_add_view_to_scenes;
+mod m20240322_000000_add_name_to_scenes;
+mod m20240323_000000_create_curve_cache;
+mod m20240324_000000_create_pari_cache;
+mod m20240325_000000_add_versions;
+mod m20240326_000000_create_users;
+mod m20240327_000000_add_style_to_scene_objects;
+mod m20240328_000000_add_options_to_scenes;
+mod m20240329_000000_unique_unowned_scene_names;
+mod m20240330_000000_add_stored_at_to_pari_cache;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20240320_000000_create_tables::Migration),
+            Box::new(m20240321_000000_add_view_to_scenes::Migration),
+            Box::new(m20240322_000000_add_name_to_scenes::Migration),
+            Box::new(m20240323_000000_create_curve_cache::Migration),
+            Box::new(m20240324_000000_create_pari_cache::Migration),
+            Box::new(m20240325_000000_add_versions::Migration),
+            Box::new(m20240326_000000_create_users::Migration),
+            Box::new(m20240327_000000_add_style_to_scene_objects::Migration),
+            Box::new(m20240328_000000_add_options_to_scenes::Migration),
+            Box::new(m20240329_000000_unique_unowned_scene_names::Migration),
+            Box::new(m20240330_000000_add_stored_at_to_pari_cache::Migration),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CurveCacheModel, SceneActiveModel, SceneEntity};
+    use sea_orm::{
+        ActiveModelBehavior, ActiveModelTrait, ConnectionTrait, Database, EntityTrait, Set,
+    };
+
+    #[tokio::test]
+    async fn test_migrations() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        assert!(Migrator::get_pending_migrations(&db)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let scene = SceneActiveModel {
+            name: Set("Scene 1".to_string()),
+            ..<SceneActiveModel as ActiveModelBehavior>::new()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        CurveCacheModel::invalidate(&db, scene.id).await.unwrap();
+
+        // Every migration can be reverted and applied again
+        Migrator::down(&db, Some(10)).await.unwrap();
+        let manager = SchemaManager::new(&db);
+        assert!(!manager.has_column("scenes", "view").await.unwrap());
+        assert!(!manager.has_column("scenes", "version").await.unwrap());
+        assert!(!manager.has_column("scenes", "options").await.unwrap());
+        assert!(!manager.has_column("scene_objects", "style").await.unwrap());
+        assert!(!manager.has_table("users").await.unwrap());
+        assert!(!manager.has_table("curve_cache").await.unwrap());
+        assert!(!manager.has_table("pari_cache").await.unwrap());
+        Migrator::up(&db, None).await.unwrap();
+        let scenes = SceneEntity::find().all(&db).await.unwrap();
+        assert_eq!(scenes.len(), 1);
+        assert_eq!(scenes[0].name, "Scene 1");
+    }
+
+    #[tokio::test]
+    async fn test_unique_unowned_scene_names() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, Some(9)).await.unwrap();
+        let insert = "INSERT INTO scenes (created_at, name) VALUES ('2024-03-29 00:00:00', 'A');";
+        db.execute_unprepared(insert).await.unwrap();
+        db.execute_unprepared(insert).await.unwrap();
+
+        // The older scene keeps its name
+        Migrator::up(&db, None).await.unwrap();
+        let names: Vec<String> = SceneEntity::find()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|scene| scene.name)
+            .collect();
+        assert_eq!(names, vec!["A", "A (2)"]);
+        assert!(db.execute_unprepared(insert).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_untracked_database() {
+        // A database set up by replaying some of the SQL files, without the
+        // migrations table
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        for sql in [
+            include_str!("../migrations/20240320000000_create_tables.sql"),
+            include_str!("../migrations/20240321000000_add_view_to_scenes.sql"),
+            include_str!("../migrations/20240322000000_add_name_to_scenes.sql"),
+            "INSERT INTO scenes (created_at, name) VALUES ('2024-03-22 00:00:00', 'Old scene');",
+        ] {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+
+        assert_eq!(
+            Migrator::get_pending_migrations(&db).await.unwrap().len(),
+            11
+        );
+        Migrator::up(&db, None).await.unwrap();
+        let scenes = SceneEntity::find().all(&db).await.unwrap();
+        assert_eq!(scenes.len(), 1);
+        assert_eq!(scenes[0].name, "Old scene");
+        assert_eq!(scenes[0].version, 0);
+        assert!(SchemaManager::new(&db)
+            .has_table("curve_cache")
+            .await
+            .unwrap());
+    }
+}
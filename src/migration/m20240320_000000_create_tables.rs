@@ -0,0 +1,27 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The tables are created only if missing, as in databases set up before
+        // migrations were tracked
+        manager
+            .get_connection()
+            .execute_unprepared(include_str!(
+                "../../migrations/20240320000000_create_tables.sql"
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP TABLE scene_objects; DROP TABLE scenes;")
+            .await?;
+        Ok(())
+    }
+}
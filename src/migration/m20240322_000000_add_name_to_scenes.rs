@@ -0,0 +1,28 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.has_column("scenes", "name").await? {
+            return Ok(());
+        }
+        manager
+            .get_connection()
+            .execute_unprepared(include_str!(
+                "../../migrations/20240322000000_add_name_to_scenes.sql"
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP INDEX idx_scenes_name; ALTER TABLE scenes DROP COLUMN name;")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.has_table("curve_cache").await? {
+            return Ok(());
+        }
+        manager
+            .get_connection()
+            .execute_unprepared(include_str!(
+                "../../migrations/20240323000000_create_curve_cache.sql"
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP TABLE curve_cache;")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(include_str!(
+                "../../migrations/20240326000000_create_users.sql"
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DROP INDEX idx_scenes_user_name; \
+                 CREATE UNIQUE INDEX idx_scenes_name ON scenes(name); \
+                 ALTER TABLE scenes DROP COLUMN user_id; \
+                 DROP TABLE users;",
+            )
+            .await?;
+        Ok(())
+    }
+}
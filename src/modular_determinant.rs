@@ -0,0 +1,273 @@
+//! Determinants of polynomial matrices computed modulo word-size primes.
+//!
+//! The exact cofactor expansion multiplies polynomials with growing coefficients.
+//! Here the matrix is instead evaluated at points of the Kronecker substitution
+//! v_k = t^(stride_k), the scalar determinants are computed modulo a prime and
+//! interpolated as a polynomial in t, and the integer coefficients are reconstructed
+//! from the residues by the Chinese Remainder Theorem. The degree bounds of the
+//! determinant make the substitution reversible, and a bound on its coefficients
+//! decides how many primes are needed.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::determinant;
+use crate::modular_poly::ModularPoly;
+use crate::poly::{Poly, Term, Var};
+
+/// The largest primes below 2^64
+const PRIMES: [u64; 3] = [u64::MAX - 58, u64::MAX - 82, u64::MAX - 94];
+
+/// Largest modulus the reconstructed coefficients can be represented with
+const MAX_MODULUS_BITS: f64 = 128.0;
+
+/// Determinant of a square matrix of polynomials. Fails if the determinant has too
+/// many potential terms or coefficients that don't fit into i64.
+pub fn determinant(matrix: &[Vec<Rc<Poly>>]) -> Result<Poly, String> {
+    let n = matrix.len();
+    if n == 0 {
+        return Ok(Poly::Constant(0));
+    }
+
+    // Kronecker strides from the degree bounds of the determinant
+    let mut strides = Vec::new();
+    let mut point_count: u64 = 1;
+    for (v, degree) in determinant::degree_bounds(matrix) {
+        strides.push((v, point_count, degree));
+        point_count = point_count
+            .checked_mul(degree as u64 + 1)
+            .filter(|&count| count <= determinant::MAX_POINTS)
+            .ok_or_else(|| "Too many terms for a modular determinant".to_string())?;
+    }
+
+    // Every coefficient of the determinant is bounded by the product of the row sums
+    // of the 1-norms of the entries
+    let mut log2_bound = 0.0;
+    for row in matrix {
+        let mut row_norm = 0.0;
+        for entry in row {
+            entry.observe_coefficients(|c| row_norm += (c as f64).abs());
+        }
+        if row_norm == 0.0 {
+            return Ok(Poly::Constant(0));
+        }
+        log2_bound += row_norm.log2();
+    }
+    // Coefficients lie in (-bound, bound), so the moduli must exceed 2 * bound
+    let mut primes = Vec::new();
+    let mut log2_modulus = 0.0;
+    for &p in PRIMES.iter() {
+        if log2_modulus > log2_bound + 1.0 {
+            break;
+        }
+        if log2_modulus + (p as f64).log2() > MAX_MODULUS_BITS {
+            return Err("Coefficients of the determinant are too large".to_string());
+        }
+        primes.push(p);
+        log2_modulus += (p as f64).log2();
+    }
+
+    let residues: Vec<Vec<u64>> = primes
+        .iter()
+        .map(|&p| interpolate_determinant(matrix, &strides, point_count, p))
+        .collect();
+
+    let mut terms = Vec::new();
+    for e in 0..point_count as usize {
+        let coefficient = reconstruct(residues.iter().map(|r| r[e]), &primes);
+        let constant = i64::try_from(coefficient)
+            .map_err(|_| "Coefficients of the determinant don't fit into i64".to_string())?;
+        if constant == 0 {
+            continue;
+        }
+        let term_vars = strides
+            .iter()
+            .map(|&(v, stride, degree)| (v, ((e as u64 / stride) % (degree as u64 + 1)) as u32))
+            .filter(|&(_, d)| d > 0)
+            .collect();
+        terms.push(Term {
+            constant,
+            vars: term_vars,
+        });
+    }
+    Ok(Poly::from_term_list(&terms))
+}
+
+/// Coefficients modulo p of the determinant after the Kronecker substitution
+fn interpolate_determinant(
+    matrix: &[Vec<Rc<Poly>>],
+    strides: &[(Var, u64, u32)],
+    point_count: u64,
+    p: u64,
+) -> Vec<u64> {
+    let points: Vec<u64> = (0..point_count).collect();
+    let values: Vec<u64> = points
+        .iter()
+        .map(|&t| {
+            let var_values: HashMap<Var, u64> = strides
+                .iter()
+                .map(|&(v, stride, _)| (v, power_mod(t, stride, p)))
+                .collect();
+            let scalar_matrix: Vec<Vec<u64>> = matrix
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|e| evaluate_mod(e, &var_values, p))
+                        .collect()
+                })
+                .collect();
+            determinant_mod(scalar_matrix, p)
+        })
+        .collect();
+
+    // Newton divided differences, then the Newton form expanded from the highest term
+    let mut differences = values;
+    for k in 1..differences.len() {
+        for i in (k..differences.len()).rev() {
+            let numerator = ModularPoly::sub_mod(differences[i], differences[i - 1], p);
+            let denominator = ModularPoly::sub_mod(points[i], points[i - k], p);
+            let inverse = ModularPoly::mod_inverse(denominator, p).unwrap();
+            differences[i] = ModularPoly::mul_mod(numerator, inverse, p);
+        }
+    }
+    let mut result = ModularPoly::zero(p);
+    for k in (0..differences.len()).rev() {
+        let factor = ModularPoly::new(vec![p - points[k] % p, 1], p);
+        result = &(&result * &factor) + &ModularPoly::constant(differences[k], p);
+    }
+
+    let mut coefficients = result.coeffs;
+    coefficients.resize(point_count as usize, 0);
+    coefficients
+}
+
+fn power_mod(base: u64, exponent: u64, p: u64) -> u64 {
+    let mut result = 1 % p;
+    let mut base = base % p;
+    let mut exponent = exponent;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = ModularPoly::mul_mod(result, base, p);
+        }
+        base = ModularPoly::mul_mod(base, base, p);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn evaluate_mod(poly: &Poly, var_values: &HashMap<Var, u64>, p: u64) -> u64 {
+    match poly {
+        Poly::Constant(c) => ModularPoly::from_i64(*c, p),
+        Poly::Nested(v, coefficients) => {
+            let x = var_values[v];
+            coefficients.iter().rev().fold(0, |result, c| {
+                ModularPoly::add_mod(
+                    ModularPoly::mul_mod(result, x, p),
+                    evaluate_mod(c, var_values, p),
+                    p,
+                )
+            })
+        }
+    }
+}
+
+/// Gaussian elimination over Z/pZ
+fn determinant_mod(mut matrix: Vec<Vec<u64>>, p: u64) -> u64 {
+    let n = matrix.len();
+    let mut result = 1;
+    for i in 0..n {
+        let Some(pivot_row) = (i..n).find(|&r| matrix[r][i] != 0) else {
+            return 0;
+        };
+        if pivot_row != i {
+            matrix.swap(pivot_row, i);
+            result = ModularPoly::sub_mod(0, result, p);
+        }
+        result = ModularPoly::mul_mod(result, matrix[i][i], p);
+        let inverse = ModularPoly::mod_inverse(matrix[i][i], p).unwrap();
+        let (upper, lower) = matrix.split_at_mut(i + 1);
+        let pivot = &upper[i];
+        for row in lower.iter_mut() {
+            let factor = ModularPoly::mul_mod(row[i], inverse, p);
+            if factor == 0 {
+                continue;
+            }
+            for (entry, &pivot_entry) in row[i..].iter_mut().zip(&pivot[i..]) {
+                let product = ModularPoly::mul_mod(factor, pivot_entry, p);
+                *entry = ModularPoly::sub_mod(*entry, product, p);
+            }
+        }
+    }
+    result
+}
+
+/// The integer in (-M/2, M/2] with the given residues, M being the product of the primes
+/// (Garner's algorithm; M must fit into u128)
+fn reconstruct(residues: impl Iterator<Item = u64>, primes: &[u64]) -> i128 {
+    let mut value: u128 = 0;
+    let mut modulus: u128 = 1;
+    for (r, &p) in residues.zip(primes) {
+        // value + modulus * k = r (mod p)
+        let value_mod_p = (value % p as u128) as u64;
+        let modulus_mod_p = (modulus % p as u128) as u64;
+        let k = ModularPoly::mul_mod(
+            ModularPoly::sub_mod(r, value_mod_p, p),
+            ModularPoly::mod_inverse(modulus_mod_p, p).unwrap(),
+            p,
+        );
+        value += modulus * k as u128;
+        modulus *= p as u128;
+    }
+    if value > modulus / 2 {
+        -((modulus - value) as i128)
+    } else {
+        value as i128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(entries: &[&[&str]]) -> Vec<Vec<Rc<Poly>>> {
+        entries
+            .iter()
+            .map(|row| row.iter().map(|e| Rc::new(Poly::new(e).unwrap())).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_determinant() {
+        let m = matrix(&[&["a", "b"], &["c", "d"]]);
+        assert_eq!(determinant(&m), Ok(Poly::new("a*d - b*c").unwrap()));
+
+        let m = matrix(&[
+            &["a^2 + 1", "b", "0"],
+            &["3", "a*b - 2", "b^2"],
+            &["-1", "7", "a"],
+        ]);
+        // (a^2 + 1)((a b - 2) a - 7 b^2) - b (3 a + b^2) + 0
+        let expected =
+            Poly::new("a^4*b + a^2*b - 2*a^3 - 2*a - 7*a^2*b^2 - 7*b^2 - 3*a*b - b^3").unwrap();
+        assert_eq!(determinant(&m), Ok(expected));
+
+        let m = matrix(&[&["2", "4"], &["1", "2"]]);
+        assert_eq!(determinant(&m), Ok(Poly::Constant(0)));
+        assert_eq!(determinant(&[]), Ok(Poly::Constant(0)));
+    }
+
+    #[test]
+    fn test_large_coefficients() {
+        // Needs two primes to tell the sign of a coefficient near the i64 limit
+        let big = 3_000_000_000i64;
+        let m = matrix(&[&[&format!("{}*a", big), "-1"], &["1", &big.to_string()]]);
+        assert_eq!(
+            determinant(&m),
+            Ok(Poly::new(&format!("{}*a + 1", big * big)).unwrap())
+        );
+        assert_eq!(reconstruct([PRIMES[0] - 5].into_iter(), &PRIMES[..1]), -5);
+
+        let m = matrix(&[&["4000000000*a", "0"], &["0", "4000000000"]]);
+        assert!(determinant(&m).is_err());
+    }
+}
@@ -2,6 +2,9 @@ use std::ops::{Add, Mul, Sub};
 
 use log::info;
 
+mod factorization;
+mod multiplication;
+
 /// A single-variable polynomial with coefficients in Z/pZ
 #[derive(Debug, Clone, PartialEq)]
 pub struct ModularPoly {
@@ -79,7 +82,7 @@ impl ModularPoly {
     }
 
     /// Add two coefficients modulo p
-    fn add_mod(a: u64, b: u64, p: u64) -> u64 {
+    pub fn add_mod(a: u64, b: u64, p: u64) -> u64 {
         let sum = a as i128 + b as i128;
         if sum >= p as i128 {
             (sum - p as i128) as u64
@@ -89,7 +92,7 @@ impl ModularPoly {
     }
 
     /// Subtract two coefficients modulo p
-    fn sub_mod(a: u64, b: u64, p: u64) -> u64 {
+    pub fn sub_mod(a: u64, b: u64, p: u64) -> u64 {
         if a >= b {
             a - b
         } else {
@@ -98,13 +101,13 @@ impl ModularPoly {
     }
 
     /// Multiply two coefficients modulo p
-    fn mul_mod(a: u64, b: u64, p: u64) -> u64 {
+    pub fn mul_mod(a: u64, b: u64, p: u64) -> u64 {
         let product = (a as u128) * (b as u128);
         (product % (p as u128)) as u64
     }
 
     /// Find the multiplicative inverse of a modulo p
-    fn mod_inverse(a: u64, p: u64) -> Option<u64> {
+    pub fn mod_inverse(a: u64, p: u64) -> Option<u64> {
         if a == 0 {
             info!("Modular inverse of 0 modulo {} is undefined", p);
             return None;
@@ -369,17 +372,10 @@ impl Mul for &ModularPoly {
             return ModularPoly::zero(self.p);
         }
 
-        let result_len = self.coeffs.len() + other.coeffs.len() - 1;
-        let mut result = vec![0; result_len];
-
-        for i in 0..self.coeffs.len() {
-            for j in 0..other.coeffs.len() {
-                let product = ModularPoly::mul_mod(self.coeffs[i], other.coeffs[j], self.p);
-                result[i + j] = ModularPoly::add_mod(result[i + j], product, self.p);
-            }
-        }
-
-        ModularPoly::new(result, self.p)
+        ModularPoly::new(
+            multiplication::multiply(&self.coeffs, &other.coeffs, self.p),
+            self.p,
+        )
     }
 }
 
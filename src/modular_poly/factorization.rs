@@ -0,0 +1,303 @@
+//! Greatest common divisors, factorization into irreducible factors and roots of
+//! polynomials over Z/pZ, by the square-free, distinct-degree and equal-degree
+//! (Cantor-Zassenhaus) factorizations.
+
+use rand::Rng;
+
+use crate::modular_poly::ModularPoly;
+
+impl ModularPoly {
+    fn x(p: u64) -> Self {
+        Self::new(vec![0, 1], p)
+    }
+
+    fn is_one(&self) -> bool {
+        self.coeffs == [1]
+    }
+
+    /// The polynomial divided by its leading coefficient; zero stays zero
+    pub fn monic(&self) -> ModularPoly {
+        let leading = *self.coeffs.last().unwrap_or(&0);
+        if leading == 0 || leading == 1 {
+            return self.clone();
+        }
+        let inverse = Self::mod_inverse(leading, self.p).expect("p is prime");
+        let coeffs = self
+            .coeffs
+            .iter()
+            .map(|&c| Self::mul_mod(c, inverse, self.p))
+            .collect();
+        Self::new(coeffs, self.p)
+    }
+
+    pub fn derivative(&self) -> ModularPoly {
+        let coeffs = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| Self::mul_mod(c, i as u64 % self.p, self.p))
+            .collect::<Vec<_>>();
+        if coeffs.is_empty() {
+            return Self::zero(self.p);
+        }
+        Self::new(coeffs, self.p)
+    }
+
+    /// The monic greatest common divisor, zero if both polynomials are zero
+    pub fn gcd(&self, other: &ModularPoly) -> ModularPoly {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let remainder = a.remainder(&b);
+            a = b;
+            b = remainder;
+        }
+        a.monic()
+    }
+
+    /// self^exponent modulo `modulus`
+    pub fn pow_mod(&self, mut exponent: u64, modulus: &ModularPoly) -> ModularPoly {
+        let mut base = self.remainder(modulus);
+        let mut result = Self::constant(1, self.p).remainder(modulus);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = (&result * &base).remainder(modulus);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = (&base * &base).remainder(modulus);
+            }
+        }
+        result
+    }
+
+    /// Monic square-free polynomials with multiplicities whose product is the monic
+    /// form of the polynomial
+    pub fn square_free_factorization(&self) -> Vec<(ModularPoly, u32)> {
+        let p = self.p;
+        let mut result = Vec::new();
+        let f = self.monic();
+        if f.is_constant() {
+            return result;
+        }
+        let mut c = f.gcd(&f.derivative());
+        let mut w = f.quotient(&c);
+        let mut multiplicity = 1;
+        while !w.is_one() {
+            let y = w.gcd(&c);
+            let factor = w.quotient(&y);
+            if !factor.is_one() {
+                result.push((factor, multiplicity));
+            }
+            c = c.quotient(&y);
+            w = y;
+            multiplicity += 1;
+        }
+        if !c.is_one() {
+            // c is a polynomial in x^p; its p-th root has the same coefficients in Z/pZ
+            let root = Self::new(c.coeffs.iter().step_by(p as usize).copied().collect(), p);
+            for (factor, m) in root.square_free_factorization() {
+                result.push((factor, m * p as u32));
+            }
+        }
+        result
+    }
+
+    /// Splits a monic square-free polynomial into products of the irreducible factors
+    /// of the same degree, returned with that degree
+    pub fn distinct_degree_factorization(&self) -> Vec<(ModularPoly, usize)> {
+        let p = self.p;
+        let mut result = Vec::new();
+        let mut rest = self.clone();
+        // x^(p^degree) modulo rest
+        let mut h = Self::x(p).remainder(&rest);
+        let mut degree = 1;
+        while rest.degree() >= 2 * degree {
+            h = h.pow_mod(p, &rest);
+            let g = rest.gcd(&(&h - &Self::x(p)));
+            if !g.is_one() {
+                rest = rest.quotient(&g);
+                h = h.remainder(&rest);
+                result.push((g, degree));
+            }
+            degree += 1;
+        }
+        if !rest.is_constant() {
+            let degree = rest.degree();
+            result.push((rest, degree));
+        }
+        result
+    }
+
+    /// Splits a monic product of distinct irreducible factors of degree `degree` into
+    /// the factors, with random splitting polynomials
+    pub fn equal_degree_factorization(&self, degree: usize) -> Vec<ModularPoly> {
+        let p = self.p;
+        let n = self.degree();
+        if n <= degree {
+            return vec![self.clone()];
+        }
+        let mut rng = rand::rng();
+        loop {
+            let a = Self::new((0..n).map(|_| rng.random_range(0..p)).collect(), p);
+            if a.is_constant() {
+                continue;
+            }
+            let g = self.gcd(&a);
+            let split = if !g.is_one() {
+                g
+            } else if p == 2 {
+                // The trace a + a^2 + ... + a^(2^(degree - 1)) is 0 or 1 on each factor
+                let mut power = a.clone();
+                let mut trace = a.clone();
+                for _ in 1..degree {
+                    power = (&power * &power).remainder(self);
+                    trace = &trace + &power;
+                }
+                self.gcd(&trace)
+            } else {
+                // a^((p^degree - 1) / 2) is 1 or -1 modulo each factor; the exponent is
+                // (1 + p + ... + p^(degree - 1)) * (p - 1) / 2
+                let mut power = a.remainder(self);
+                let mut norm = power.clone();
+                for _ in 1..degree {
+                    power = power.pow_mod(p, self);
+                    norm = (&norm * &power).remainder(self);
+                }
+                let b = &norm.pow_mod((p - 1) / 2, self) - &Self::constant(1, p);
+                self.gcd(&b)
+            };
+            if !split.is_constant() && split.degree() < n {
+                let mut factors = split.equal_degree_factorization(degree);
+                factors.extend(self.quotient(&split).equal_degree_factorization(degree));
+                return factors;
+            }
+        }
+    }
+
+    /// Monic irreducible factors with their multiplicities, sorted by degree and
+    /// coefficients. The leading coefficient of the polynomial is left out.
+    pub fn factor(&self) -> Vec<(ModularPoly, u32)> {
+        let mut factors: Vec<(ModularPoly, u32)> = self
+            .square_free_factorization()
+            .into_iter()
+            .flat_map(|(square_free, multiplicity)| {
+                square_free
+                    .distinct_degree_factorization()
+                    .into_iter()
+                    .flat_map(|(product, degree)| product.equal_degree_factorization(degree))
+                    .map(move |factor| (factor, multiplicity))
+            })
+            .collect();
+        factors.sort_by(|(f1, _), (f2, _)| {
+            f1.degree()
+                .cmp(&f2.degree())
+                .then_with(|| f1.coeffs.iter().rev().cmp(f2.coeffs.iter().rev()))
+        });
+        factors
+    }
+
+    /// The distinct roots in Z/pZ of a non-zero polynomial, in increasing order
+    pub fn roots(&self) -> Vec<u64> {
+        let p = self.p;
+        let f = self.monic();
+        if f.is_constant() {
+            return vec![];
+        }
+        // The product of x - r over the roots r is gcd(f, x^p - x)
+        let x_to_p = Self::x(p).pow_mod(p, &f);
+        let linear = f.gcd(&(&x_to_p - &Self::x(p)));
+        if linear.is_constant() {
+            return vec![];
+        }
+        let mut roots: Vec<u64> = linear
+            .equal_degree_factorization(1)
+            .iter()
+            .map(|factor| Self::sub_mod(0, factor.coeffs[0], p))
+            .collect();
+        roots.sort_unstable();
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(factors: &[(ModularPoly, u32)], p: u64) -> ModularPoly {
+        factors
+            .iter()
+            .fold(ModularPoly::constant(1, p), |result, (factor, m)| {
+                (0..*m).fold(result, |result, _| &result * factor)
+            })
+    }
+
+    #[test]
+    fn test_gcd() {
+        let p = 7;
+        // (x - 1)(x - 2) and 3(x - 1)(x + 1)
+        let a = ModularPoly::new(vec![2, 4, 1], p);
+        let b = ModularPoly::new(vec![4, 0, 3], p);
+        assert_eq!(a.gcd(&b), ModularPoly::new(vec![6, 1], p));
+        assert_eq!(a.gcd(&ModularPoly::zero(p)), a);
+        assert_eq!(
+            a.gcd(&ModularPoly::new(vec![1, 1], p)),
+            ModularPoly::constant(1, p)
+        );
+    }
+
+    #[test]
+    fn test_factor() {
+        let p = 7;
+        let x_minus = |r: u64| ModularPoly::new(vec![p - r, 1], p);
+        // x^2 + 1 is irreducible since -1 is not a square modulo 7
+        let quadratic = ModularPoly::new(vec![1, 0, 1], p);
+        let cubic = ModularPoly::new(vec![2, 0, 0, 1], p);
+        let expected = vec![
+            (x_minus(3), 1),
+            (x_minus(1), 2),
+            (quadratic.clone(), 1),
+            (cubic.clone(), 3),
+        ];
+        let mut poly = product(&expected, p);
+        poly = &poly * &ModularPoly::constant(5, p);
+        assert_eq!(poly.factor(), expected);
+        assert_eq!(poly.roots(), vec![1, 3]);
+        assert_eq!(quadratic.roots(), Vec::<u64>::new());
+        assert_eq!(cubic.factor(), vec![(cubic, 1)]);
+
+        // x^3 - 1 = (x - 1)^3 modulo 3 has a zero derivative
+        let poly = ModularPoly::new(vec![2, 0, 0, 1], 3);
+        assert_eq!(poly.factor(), vec![(ModularPoly::new(vec![2, 1], 3), 3)]);
+
+        // Modulo 2: (x + 1)^2 (x^2 + x + 1)
+        let poly = ModularPoly::new(vec![1, 1, 0, 1, 1], 2);
+        assert_eq!(
+            poly.factor(),
+            vec![
+                (ModularPoly::new(vec![1, 1], 2), 2),
+                (ModularPoly::new(vec![1, 1, 1], 2), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_roots_large_prime() {
+        let p = u64::MAX - 58;
+        let roots = [3, 1 << 40, p - 5, 123456789];
+        let mut poly = ModularPoly::constant(1, p);
+        for &r in &roots {
+            poly = &poly * &ModularPoly::new(vec![p - r, 1], p);
+        }
+        // Times an irreducible quadratic, or a product of two linear factors
+        poly = &poly * &ModularPoly::new(vec![1, 1, 1], p);
+        let found = poly.roots();
+        for r in roots {
+            assert!(found.contains(&r));
+        }
+        let factors = poly.factor();
+        assert_eq!(product(&factors, p), poly);
+        assert!(factors.iter().all(|(_, m)| *m == 1));
+    }
+}
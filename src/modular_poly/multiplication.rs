@@ -0,0 +1,201 @@
+//! Products of coefficient vectors modulo p: schoolbook for short operands,
+//! Karatsuba above `KARATSUBA_THRESHOLD`, and a number-theoretic transform for long
+//! operands when p - 1 is divisible by a large enough power of two.
+
+use crate::modular_poly::ModularPoly;
+
+/// Length of the shorter operand below which the schoolbook product is faster
+const KARATSUBA_THRESHOLD: usize = 32;
+
+/// Length of the shorter operand from which the transform is used, if p allows it
+const NTT_THRESHOLD: usize = 256;
+
+/// Coefficients of the product of the polynomials with coefficients `a` and `b`
+pub(super) fn multiply(a: &[u64], b: &[u64], p: u64) -> Vec<u64> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let shorter = a.len().min(b.len());
+    if shorter < KARATSUBA_THRESHOLD {
+        return schoolbook(a, b, p);
+    }
+    if shorter >= NTT_THRESHOLD {
+        let size = (a.len() + b.len() - 1).next_power_of_two();
+        if let Some(root) = root_of_unity(size, p) {
+            return ntt_multiply(a, b, p, root);
+        }
+    }
+    karatsuba(a, b, p)
+}
+
+fn schoolbook(a: &[u64], b: &[u64], p: u64) -> Vec<u64> {
+    let mut result = vec![0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            let product = ModularPoly::mul_mod(x, y, p);
+            result[i + j] = ModularPoly::add_mod(result[i + j], product, p);
+        }
+    }
+    result
+}
+
+/// Adds `b` to `a`, starting at the coefficient `offset` of `a`
+fn add_at(a: &mut [u64], b: &[u64], offset: usize, p: u64) {
+    for (x, &y) in a[offset..].iter_mut().zip(b) {
+        *x = ModularPoly::add_mod(*x, y, p);
+    }
+}
+
+fn sum(a: &[u64], b: &[u64], p: u64) -> Vec<u64> {
+    let (longer, shorter) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = longer.to_vec();
+    add_at(&mut result, shorter, 0, p);
+    result
+}
+
+fn karatsuba(a: &[u64], b: &[u64], p: u64) -> Vec<u64> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if b.len() < KARATSUBA_THRESHOLD {
+        return schoolbook(a, b, p);
+    }
+    let m = a.len().div_ceil(2);
+    let (a0, a1) = a.split_at(m);
+    let mut result = vec![0; a.len() + b.len() - 1];
+    if b.len() <= m {
+        // a0 * b + x^m * a1 * b, with b too short to be split
+        add_at(&mut result, &karatsuba(a0, b, p), 0, p);
+        add_at(&mut result, &karatsuba(a1, b, p), m, p);
+        return result;
+    }
+    let (b0, b1) = b.split_at(m);
+    let low = karatsuba(a0, b0, p);
+    let high = karatsuba(a1, b1, p);
+    // (a0 + a1) * (b0 + b1) - a0 * b0 - a1 * b1 = a0 * b1 + a1 * b0
+    let mut middle = karatsuba(&sum(a0, a1, p), &sum(b0, b1, p), p);
+    for (i, x) in middle.iter_mut().enumerate() {
+        let subtrahend = ModularPoly::add_mod(
+            low.get(i).copied().unwrap_or(0),
+            high.get(i).copied().unwrap_or(0),
+            p,
+        );
+        *x = ModularPoly::sub_mod(*x, subtrahend, p);
+    }
+    add_at(&mut result, &low, 0, p);
+    add_at(&mut result, &middle, m, p);
+    add_at(&mut result, &high, 2 * m, p);
+    result
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, p: u64) -> u64 {
+    let mut result = 1 % p;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = ModularPoly::mul_mod(result, base, p);
+        }
+        base = ModularPoly::mul_mod(base, base, p);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// A primitive root of unity of order `size` (a power of two) modulo the prime p,
+/// if p - 1 is divisible by `size`
+fn root_of_unity(size: usize, p: u64) -> Option<u64> {
+    let size = size as u64;
+    if size < 2 || !(p - 1).is_multiple_of(size) {
+        return None;
+    }
+    // root = g^((p - 1) / size) has order dividing size, and exactly size if
+    // root^(size / 2) = g^((p - 1) / 2) is -1, i.e. if g is a quadratic non-residue
+    (2..100u64)
+        .map(|g| pow_mod(g, (p - 1) / size, p))
+        .find(|&root| root != 0 && pow_mod(root, size / 2, p) == p - 1)
+}
+
+/// In-place transform of `values` (of length a power of two) with the root of unity
+/// `root` of that order
+fn ntt(values: &mut [u64], root: u64, p: u64) {
+    let n = values.len();
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+    let mut length = 2;
+    while length <= n {
+        let step = pow_mod(root, (n / length) as u64, p);
+        for start in (0..n).step_by(length) {
+            let mut w = 1;
+            for k in 0..length / 2 {
+                let u = values[start + k];
+                let v = ModularPoly::mul_mod(values[start + k + length / 2], w, p);
+                values[start + k] = ModularPoly::add_mod(u, v, p);
+                values[start + k + length / 2] = ModularPoly::sub_mod(u, v, p);
+                w = ModularPoly::mul_mod(w, step, p);
+            }
+        }
+        length <<= 1;
+    }
+}
+
+fn ntt_multiply(a: &[u64], b: &[u64], p: u64, root: u64) -> Vec<u64> {
+    let result_len = a.len() + b.len() - 1;
+    let size = result_len.next_power_of_two();
+    let mut fa = a.to_vec();
+    fa.resize(size, 0);
+    let mut fb = b.to_vec();
+    fb.resize(size, 0);
+    ntt(&mut fa, root, p);
+    ntt(&mut fb, root, p);
+    for (x, &y) in fa.iter_mut().zip(&fb) {
+        *x = ModularPoly::mul_mod(*x, y, p);
+    }
+    // The inverse transform uses the inverse root and divides by the size
+    let inverse_root = ModularPoly::mod_inverse(root, p).expect("Root of unity is invertible");
+    ntt(&mut fa, inverse_root, p);
+    let inverse_size =
+        ModularPoly::mod_inverse(size as u64 % p, p).expect("Transform size is invertible");
+    fa.truncate(result_len);
+    for x in fa.iter_mut() {
+        *x = ModularPoly::mul_mod(*x, inverse_size, p);
+    }
+    fa
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn random_coeffs(len: usize, p: u64) -> Vec<u64> {
+        let mut rng = rand::rng();
+        (0..len).map(|_| rng.random_range(0..p)).collect()
+    }
+
+    #[test]
+    fn test_fast_multiplication() {
+        // A prime with no roots of unity of order 2^k for k > 2, then 119 * 2^23 + 1
+        // and 2^64 - 2^32 + 1, both with large ones
+        let large_prime = u64::MAX - 58;
+        for p in [large_prime, 998244353, 0xFFFF_FFFF_0000_0001] {
+            for (len_a, len_b) in [(40, 40), (100, 33), (33, 250), (300, 280), (700, 260)] {
+                let a = random_coeffs(len_a, p);
+                let b = random_coeffs(len_b, p);
+                let expected = schoolbook(&a, &b, p);
+                assert_eq!(karatsuba(&a, &b, p), expected, "{} {} {}", p, len_a, len_b);
+                assert_eq!(multiply(&a, &b, p), expected, "{} {} {}", p, len_a, len_b);
+            }
+        }
+        assert_eq!(root_of_unity(1024, large_prime), None);
+        let root = root_of_unity(1024, 998244353).unwrap();
+        assert_eq!(pow_mod(root, 512, 998244353), 998244352);
+    }
+}
@@ -1,20 +1,40 @@
 use gcd::Gcd;
+pub use overflow::{checked, detect_overflow};
 pub use poly_conversion::PolyConversion;
+pub use poly_format::{PolyFormat, TermOrder};
 pub use poly_operations::PolyOperations;
 pub use poly_operations::SingleOutResult;
+pub use rat_func::RatFunc;
+pub use rat_poly::{RatPoly, Rational};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::{fmt, mem, rc::Rc};
+pub use var_set::VarSet;
 
 use crate::modular_poly::ModularPoly;
 
+mod overflow;
 mod poly_conversion;
+mod poly_evaluation;
+mod poly_format;
 mod poly_operations;
+mod poly_serde;
+mod projective;
+mod rat_func;
+mod rat_poly;
+#[cfg(any(test, feature = "proptest"))]
+pub mod strategies;
+mod var_set;
+
+/// Index of a variable: 0 is a, 25 is z, 26 is a1 and so on
+pub type Var = u16;
 
 /// Result of searching for the variable with minimum degree across polynomials
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VarSearchResult {
     /// The variable index with minimum degree
-    pub var: u8,
+    pub var: Var,
     /// The minimum degree of this variable across all polynomials
     pub min_degree: u32,
     /// The index of the polynomial that contains this variable with the minimum degree
@@ -24,7 +44,7 @@ pub struct VarSearchResult {
 #[derive(Debug, Clone)]
 pub struct Term {
     pub constant: i64,
-    pub vars: Vec<(u8, u32)>, // (variable index, degree)
+    pub vars: Vec<(Var, u32)>, // (variable index, degree)
 }
 
 #[derive(Debug)]
@@ -44,10 +64,38 @@ impl fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
+/// Failure of polynomial arithmetic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolyError {
+    /// A coefficient or an intermediate value did not fit in 64 (or 128) bits
+    Overflow,
+    /// A polynomial was evaluated without a value for the variable
+    MissingValue(Var),
+    /// A quotient with the zero polynomial as its denominator
+    DivisionByZero,
+}
+
+impl fmt::Display for PolyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolyError::Overflow => write!(
+                f,
+                "Coefficient overflow: the equations are too large for exact integer arithmetic"
+            ),
+            PolyError::DivisionByZero => write!(f, "Division by zero"),
+            PolyError::MissingValue(v) => {
+                write!(f, "No value for variable {}", Poly::var_to_string(*v))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolyError {}
+
 #[derive(Clone)]
 pub enum Poly {
     Constant(i64),
-    Nested(u8, Vec<Rc<Poly>>),
+    Nested(Var, Vec<Rc<Poly>>),
 }
 
 impl PartialEq for Poly {
@@ -62,8 +110,48 @@ impl PartialEq for Poly {
 
 impl Eq for Poly {}
 
+impl Hash for Poly {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Poly::Constant(n) => {
+                0u8.hash(state);
+                n.hash(state);
+            }
+            Poly::Nested(v, polys) => {
+                1u8.hash(state);
+                v.hash(state);
+                for poly in polys {
+                    poly.hash(state);
+                }
+            }
+        }
+    }
+}
+
+/// Constants come first, ordered by value. Polynomials in a variable are ordered by the
+/// variable, then by the degree in it, then by the coefficients from the leading one down.
+impl Ord for Poly {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Poly::Constant(n1), Poly::Constant(n2)) => n1.cmp(n2),
+            (Poly::Constant(_), Poly::Nested(..)) => Ordering::Less,
+            (Poly::Nested(..), Poly::Constant(_)) => Ordering::Greater,
+            (Poly::Nested(v1, polys1), Poly::Nested(v2, polys2)) => v1
+                .cmp(v2)
+                .then(polys1.len().cmp(&polys2.len()))
+                .then_with(|| polys1.iter().rev().cmp(polys2.iter().rev())),
+        }
+    }
+}
+
+impl PartialOrd for Poly {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Poly {
-    pub fn parse_var(s: &str) -> Result<u8, ParseError> {
+    pub fn parse_var(s: &str) -> Result<Var, ParseError> {
         if s.is_empty() {
             return Err(ParseError::InvalidVariable(s.to_string()));
         }
@@ -71,19 +159,20 @@ impl Poly {
         if !first_char.is_ascii_alphabetic() {
             return Err(ParseError::InvalidVariable(s.to_string()));
         }
-        let base = (first_char.to_ascii_lowercase() as u8) - b'a';
+        let base = ((first_char.to_ascii_lowercase() as u8) - b'a') as Var;
         if s.len() == 1 {
             return Ok(base);
         }
-        if let Ok(num) = s[1..].parse::<u8>() {
-            Ok(base + num * 26)
-        } else {
-            Err(ParseError::InvalidVariable(s.to_string()))
-        }
+        s[1..]
+            .parse::<Var>()
+            .ok()
+            .and_then(|num| num.checked_mul(26))
+            .and_then(|offset| offset.checked_add(base))
+            .ok_or_else(|| ParseError::InvalidVariable(s.to_string()))
     }
 
     fn parse_term(term_str: &str) -> Result<Term, ParseError> {
-        let mut constant = 1;
+        let mut constant = 1i64;
         let mut degrees = HashMap::new();
         let term_str_to_use = if term_str.len() > 1
             && term_str.starts_with('-')
@@ -147,7 +236,11 @@ impl Poly {
                     ParseError::InvalidTerm(format!("Invalid constant '{}'", num_str))
                 })?;
 
-                constant *= if part.starts_with('-') { -num } else { num };
+                constant = constant
+                    .checked_mul(if part.starts_with('-') { -num } else { num })
+                    .ok_or_else(|| {
+                        ParseError::InvalidTerm(format!("Constant too large in '{}'", term_str))
+                    })?;
             }
         }
 
@@ -157,23 +250,19 @@ impl Poly {
         })
     }
 
-    fn from_terms(terms: &[Term], used_vars: &[bool], var_index: u8) -> Self {
+    fn from_terms(terms: &[Term], remaining_vars: VarSet) -> Self {
         // Find the next used variable
-        let next_var = used_vars
-            .iter()
-            .enumerate()
-            .skip(var_index as usize)
-            .find(|(_, &used)| used)
-            .map(|(i, _)| i as u8);
+        let next_var = remaining_vars.first();
 
         match next_var {
             None => {
                 // No more variables, this is a constant
-                let sum: i64 = terms
+                let sum = terms
                     .iter()
                     .filter(|term| term.vars.is_empty())
-                    .map(|term| term.constant)
-                    .sum();
+                    .fold(0i64, |sum, term| {
+                        checked(sum.overflowing_add(term.constant))
+                    });
                 Poly::Constant(sum)
             }
             Some(v) => {
@@ -200,11 +289,13 @@ impl Poly {
                 }
 
                 // Convert each group to a polynomial
+                let mut vars_after_v = remaining_vars;
+                vars_after_v.remove(v);
                 let mut polys = Vec::new();
                 for degree in 0..=max_degree {
                     let terms = &terms_by_degree[degree as usize];
                     if !terms.is_empty() {
-                        polys.push(Rc::new(Self::from_terms(terms, used_vars, v + 1)));
+                        polys.push(Rc::new(Self::from_terms(terms, vars_after_v.clone())));
                     } else {
                         polys.push(Rc::new(Poly::Constant(0)));
                     }
@@ -242,16 +333,28 @@ impl Poly {
             terms.push(term);
         }
 
-        // Step 2: Find used variables
-        let mut used_vars = [false; 256];
-        for term in &terms {
-            for (var, _) in &term.vars {
-                used_vars[*var as usize] = true;
-            }
+        // Step 2: Convert terms to polynomial
+        Ok(Self::from_term_list(&terms))
+    }
+
+    /// Parses an equation = 0 of a system: a polynomial in expanded form, or an
+    /// expression with parentheses and division (see `RatFunc::parse`), which is
+    /// cleared of its denominator
+    pub fn parse_equation(s: &str) -> Result<Self, ParseError> {
+        if s.contains(['(', '/']) {
+            Ok(RatFunc::parse(s)?.into_constraint())
+        } else {
+            Self::new(s)
         }
+    }
 
-        // Step 3: Convert terms to polynomial
-        Ok(Self::from_terms(&terms, &used_vars, 0))
+    /// Builds a polynomial from a list of terms; terms with the same monomial are added up
+    pub fn from_term_list(terms: &[Term]) -> Self {
+        let used_vars: VarSet = terms
+            .iter()
+            .flat_map(|term| term.vars.iter().map(|(var, _)| *var))
+            .collect();
+        Self::from_terms(terms, used_vars)
     }
 
     pub fn cleanup(&mut self) {
@@ -314,10 +417,10 @@ impl Poly {
         }
     }
 
-    pub fn var_to_string(var_idx: u8) -> String {
+    pub fn var_to_string(var_idx: Var) -> String {
         let base = var_idx / 26;
         let offset = var_idx % 26;
-        let c = (b'a' + offset) as char;
+        let c = (b'a' + offset as u8) as char;
         if base == 0 {
             c.to_string()
         } else {
@@ -325,7 +428,7 @@ impl Poly {
         }
     }
 
-    pub fn get_degree(&self, v: u8) -> u32 {
+    pub fn get_degree(&self, v: Var) -> u32 {
         match self {
             Poly::Constant(_) => 0,
             Poly::Nested(v1, polys) => {
@@ -340,18 +443,25 @@ impl Poly {
         }
     }
 
-    pub fn has_var(&self, v: u8) -> bool {
+    pub fn has_var(&self, v: Var) -> bool {
         match self {
             Poly::Constant(_) => false,
             Poly::Nested(v1, polys) => *v1 == v || (*v1 < v && polys.iter().any(|p| p.has_var(v))),
         }
     }
 
-    pub fn fill_in_variables(&self, vars: &mut [bool; 256]) {
+    /// Returns the set of variables used in the polynomial
+    pub fn get_variables(&self) -> VarSet {
+        let mut vars = VarSet::new();
+        self.fill_in_variables(&mut vars);
+        vars
+    }
+
+    pub fn fill_in_variables(&self, vars: &mut VarSet) {
         match self {
             Poly::Constant(_) => {}
             Poly::Nested(v, polys) => {
-                vars[*v as usize] = true;
+                vars.insert(*v);
                 for poly in polys {
                     poly.fill_in_variables(vars);
                 }
@@ -413,8 +523,9 @@ impl Poly {
     }
 
     /// Reduces coefficients by dividing by their GCD if the largest coefficient is above 10000
-    pub fn reduce_coefficients_if_large(&mut self) {
-        self.reduce_coefficients_if_above(10000);
+    /// Returns true if the coefficients were divided by their common factor
+    pub fn reduce_coefficients_if_large(&mut self) -> bool {
+        self.reduce_coefficients_if_above(10000)
     }
 
     pub fn get_coefficient_gcd(&self) -> i64 {
@@ -434,7 +545,7 @@ impl Poly {
         gcd_value as i64
     }
 
-    pub fn reduce_coefficients_if_above(&mut self, threshold: i64) {
+    pub fn reduce_coefficients_if_above(&mut self, threshold: i64) -> bool {
         // Find the largest absolute value using observe_coefficients
         let mut max_abs_coeff = 0;
         self.observe_coefficients(|x| {
@@ -443,7 +554,7 @@ impl Poly {
 
         // Only proceed if the largest coefficient is above 10000
         if max_abs_coeff <= threshold {
-            return;
+            return false;
         }
 
         // Find GCD of all coefficients using observe_coefficients
@@ -451,67 +562,33 @@ impl Poly {
 
         // If GCD is 1, no reduction needed
         if gcd_value == 1 {
-            return;
+            return false;
         }
 
         // Divide all coefficients by GCD using apply_to_coefficients
         self.apply_to_coefficients(|x| x / (gcd_value as i64));
+        true
     }
 
     /// Retains only the polynomials that are needed for finding the equation F(x, y) = 0
-    pub fn retain_relevant_polys(polys: Vec<Rc<Poly>>, x_var: u8, y_var: u8) -> Vec<Rc<Poly>> {
+    pub fn retain_relevant_polys(polys: Vec<Rc<Poly>>, x_var: Var, y_var: Var) -> Vec<Rc<Poly>> {
         // Find variables used in each polynomial
-        let mut vars_used_in_poly: Vec<[bool; 256]> = Vec::new();
-        for poly in &polys {
-            let mut vars = [false; 256];
-            poly.fill_in_variables(&mut vars);
-            vars_used_in_poly.push(vars);
-        }
+        let vars_used_in_poly: Vec<VarSet> =
+            polys.iter().map(|poly| poly.get_variables()).collect();
 
         // Initialize poly_needed to false for each poly
         let mut poly_needed = vec![false; polys.len()];
 
-        // Initialize vars_needed to true for x_var and y_var, false otherwise
-        let mut vars_needed = [false; 256];
-        vars_needed[x_var as usize] = true;
-        vars_needed[y_var as usize] = true;
-
-        // Iteratively find relevant polynomials
-        loop {
-            // Find the first index i for which poly_needed[i] is false and
-            // vars_used_in_poly[i] intersects vars_needed
-            let mut found_index = None;
-            for (i, &needed) in poly_needed.iter().enumerate() {
-                if !needed {
-                    // Check if vars_used_in_poly[i] intersects vars_needed
-                    let mut has_intersection = false;
-                    for (j, is_needed) in vars_needed.iter().enumerate() {
-                        if vars_used_in_poly[i][j] && *is_needed {
-                            has_intersection = true;
-                            break;
-                        }
-                    }
-                    if has_intersection {
-                        found_index = Some(i);
-                        break;
-                    }
-                }
-            }
-
-            // If no intersection found, break the loop
-            if found_index.is_none() {
-                break;
-            }
-
-            let i = found_index.unwrap();
+        // Initialize vars_needed to x_var and y_var
+        let mut vars_needed: VarSet = [x_var, y_var].into_iter().collect();
 
-            // Set poly_needed[i] to true
+        // Iteratively find relevant polynomials: take the first poly that is not needed yet
+        // and whose variables intersect vars_needed, until there are no such polys left
+        while let Some(i) = (0..polys.len())
+            .find(|i| !poly_needed[*i] && vars_used_in_poly[*i].intersects(&vars_needed))
+        {
             poly_needed[i] = true;
-
-            // Set vars_needed to the union of vars_needed and vars_used_in_poly[i]
-            for (j, is_needed) in vars_needed.iter_mut().enumerate() {
-                *is_needed = *is_needed || vars_used_in_poly[i][j];
-            }
+            vars_needed.union_with(&vars_used_in_poly[i]);
         }
 
         // Return just the polys for which poly_needed[i] is true
@@ -530,7 +607,7 @@ impl Poly {
     /// returns f(p1(t), p2(t), ..., pn(t)) as a modular polynomial.
     pub fn substitute_modular_polys(
         &self,
-        var_polys: &HashMap<u8, (ModularPoly, u8)>,
+        var_polys: &HashMap<Var, (ModularPoly, u8)>,
     ) -> Result<ModularPoly, String> {
         let p = var_polys.values().next().map(|poly| poly.0.p).unwrap();
         match self {
@@ -582,20 +659,19 @@ impl Poly {
     /// for which this minimal degree is the smallest.
     /// If there are no variables mentioned in the polynomials except `x_var` and `y_var`,
     /// returns `None`.
-    pub fn get_min_degree_var(polys: &[Rc<Poly>], x_var: u8, y_var: u8) -> Option<VarSearchResult> {
-        // Collect all variables used in the polynomials
-        let mut all_vars = [false; 256];
+    pub fn get_min_degree_var(
+        polys: &[Rc<Poly>],
+        x_var: Var,
+        y_var: Var,
+    ) -> Option<VarSearchResult> {
+        // Collect all variables used in the polynomials, except x_var and y_var
+        let mut all_vars = VarSet::new();
         for poly in polys {
             poly.fill_in_variables(&mut all_vars);
         }
-
-        // Find variables that are not x_var or y_var
-        let mut candidate_vars = Vec::new();
-        for (var_idx, &used) in all_vars.iter().enumerate() {
-            if used && var_idx != x_var as usize && var_idx != y_var as usize {
-                candidate_vars.push(var_idx as u8);
-            }
-        }
+        all_vars.remove(x_var);
+        all_vars.remove(y_var);
+        let candidate_vars: Vec<Var> = all_vars.iter().collect();
 
         // If no candidate variables, return None
         if candidate_vars.is_empty() {
@@ -643,6 +719,29 @@ impl Poly {
         }
     }
 
+    /// The coefficient of the greatest term: the leading coefficient of the leading
+    /// coefficient and so on
+    pub fn leading_coefficient(&self) -> i64 {
+        match self {
+            Poly::Constant(n) => *n,
+            Poly::Nested(_, polys) => polys.last().map_or(0, |poly| poly.leading_coefficient()),
+        }
+    }
+
+    /// The polynomial divided by the gcd of its coefficients, with a positive leading
+    /// coefficient. Polynomials are proportional exactly when their normalized forms are
+    /// equal, so these can be hashed to find duplicates up to a constant factor.
+    pub fn normalized(&self) -> Poly {
+        let gcd = self.get_coefficient_gcd();
+        if gcd == 0 {
+            return self.clone();
+        }
+        let divisor = gcd * self.leading_coefficient().signum();
+        let mut result = self.clone();
+        result.apply_to_coefficients(|x| x / divisor);
+        result
+    }
+
     pub fn is_proportional(&self, other: &Poly, factor: &mut Option<(i64, i64)>) -> bool {
         match (self, other) {
             (Poly::Constant(n1), Poly::Constant(n2)) => {
@@ -659,7 +758,7 @@ impl Poly {
                     }
                     Some((f1, f2)) => {
                         // Check if n1/n2 matches f1/f2, i.e., f1 * n2 == f2 * n1
-                        *f1 * *n2 == *f2 * *n1
+                        *f1 as i128 * *n2 as i128 == *f2 as i128 * *n1 as i128
                     }
                 }
             }
@@ -994,6 +1093,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_many_variables() {
+        for var in [0, 25, 26, 255, 256, 1000, Var::MAX] {
+            assert_eq!(Poly::parse_var(&Poly::var_to_string(var)).unwrap(), var);
+        }
+        assert_eq!(Poly::var_to_string(300), "o11");
+        assert!(Poly::parse_var("a2521").is_err());
+
+        let poly = Poly::new("o11^2*b - a20*b + 3").unwrap();
+        assert_eq!(poly.to_string(), "3 - a20*b + o11^2*b");
+        assert_eq!(
+            poly.get_variables().iter().collect::<Vec<_>>(),
+            vec![1, 300, 520]
+        );
+        assert_eq!(poly.get_degree(300), 2);
+    }
+
     #[test]
     fn test_invalid_degrees() {
         assert_eq!(
@@ -1298,12 +1414,12 @@ mod tests {
         // Test case 1: Coefficients below threshold (should not change)
         let mut poly = Poly::new("100*a + 200*b + 300*c").unwrap();
         let original = format!("{}", poly);
-        poly.reduce_coefficients_if_large();
+        assert!(!poly.reduce_coefficients_if_large());
         assert_eq!(format!("{}", poly), original);
 
         // Test case 2: Coefficients above threshold with common factor
         let mut poly = Poly::new("20000*a + 30000*b + 40000*c").unwrap();
-        poly.reduce_coefficients_if_large();
+        assert!(poly.reduce_coefficients_if_large());
         assert_eq!(format!("{}", poly), "4*c + 3*b + 2*a");
 
         // Test case 3: Coefficients above threshold but no common factor (GCD = 1)
@@ -1870,6 +1986,46 @@ mod tests {
         assert_eq!(factor, Some((8, 4)));
     }
 
+    #[test]
+    fn test_normalized_hash_and_order() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let poly = Poly::new("-6*a^2*b + 4*b - 2").unwrap();
+        assert_eq!(poly.leading_coefficient(), -6);
+        assert_eq!(poly.normalized(), Poly::new("3*a^2*b - 2*b + 1").unwrap());
+        assert_eq!(Poly::Constant(0).normalized(), Poly::Constant(0));
+        assert_eq!(Poly::Constant(-5).normalized(), Poly::Constant(1));
+
+        let polys = [
+            "a^2 - b",
+            "3*b - 3*a^2",
+            "a + b",
+            "-2*a - 2*b",
+            "a^2 - b + 1",
+        ]
+        .map(|s| Poly::new(s).unwrap());
+        let unique: HashSet<Poly> = polys.iter().map(Poly::normalized).collect();
+        assert_eq!(unique.len(), 3);
+        for poly1 in &polys {
+            for poly2 in &polys {
+                assert_eq!(
+                    poly1.normalized() == poly2.normalized(),
+                    poly1.is_proportional(poly2, &mut None)
+                );
+            }
+        }
+
+        let parse = |polys: [&str; 7]| polys.map(|s| Poly::new(s).unwrap());
+        let ordered: Vec<Poly> = parse(["b", "a^2", "2", "a", "a^2 + 1", "-1", "a*b"])
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            ordered,
+            parse(["-1", "2", "a", "a*b", "a^2", "a^2 + 1", "b"])
+        );
+    }
     #[test]
     fn test_is_proportional_mixed_types() {
         // Test case 1: Constant vs Nested
@@ -0,0 +1,47 @@
+use std::cell::Cell;
+
+use crate::poly::PolyError;
+
+thread_local! {
+    static OVERFLOWED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The result of an `overflowing_*` operation on coefficients. An overflow is
+/// recorded for `detect_overflow`, and the wrapped value is used meanwhile.
+pub fn checked<T>((value, overflowed): (T, bool)) -> T {
+    if overflowed {
+        OVERFLOWED.with(|flag| flag.set(true));
+    }
+    value
+}
+
+/// Runs `f`, failing with `PolyError::Overflow` if a coefficient overflowed on this
+/// thread meanwhile, since the result is then wrong. Overflows inside nested calls
+/// are also reported to the enclosing one.
+pub fn detect_overflow<R>(f: impl FnOnce() -> R) -> Result<R, PolyError> {
+    let previous = OVERFLOWED.with(|flag| flag.replace(false));
+    let result = f();
+    let overflowed = OVERFLOWED.with(|flag| flag.replace(previous || flag.get()));
+    if overflowed {
+        Err(PolyError::Overflow)
+    } else {
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_overflow() {
+        assert_eq!(detect_overflow(|| checked(2i64.overflowing_mul(3))), Ok(6));
+        let outer = detect_overflow(|| {
+            let inner = detect_overflow(|| checked(i64::MAX.overflowing_add(1)));
+            assert_eq!(inner, Err(PolyError::Overflow));
+        });
+        assert_eq!(outer, Err(PolyError::Overflow));
+        // The flag is reset for the next computation
+        assert_eq!(detect_overflow(|| ()), Ok(()));
+    }
+}
@@ -1,16 +1,20 @@
 use crate::fint::FInt;
-use crate::poly::{Poly, PolyOperations};
+use crate::poly::{Poly, PolyOperations, RatPoly, Rational, TermOrder, Var};
 use crate::x_poly::{XPoly, XYPoly};
 
 pub trait PolyConversion {
-    fn as_x_poly(&self, v: u8) -> Result<XPoly, String>;
-    fn as_xy_poly(&self, xv: u8, yv: u8) -> Result<XYPoly, String>;
+    fn as_x_poly(&self, v: Var) -> Result<XPoly, String>;
+    fn as_xy_poly(&self, xv: Var, yv: Var) -> Result<XYPoly, String>;
     fn from_poly_expression(s: &str) -> Result<Poly, String>;
-    fn as_formatted_equation(&self, x_var: u8, y_var: u8) -> String;
+    fn as_formatted_equation(&self, x_var: Var, y_var: Var) -> String;
+    /// The equation of `as_formatted_equation` in LaTeX math mode, without delimiters
+    fn to_latex(&self, x_var: Var, y_var: Var) -> String;
+    /// The equation of `as_formatted_equation` as a MathML `<math>` element
+    fn to_mathml(&self, x_var: Var, y_var: Var) -> String;
 }
 
 impl PolyConversion for Poly {
-    fn as_x_poly(&self, v: u8) -> Result<XPoly, String> {
+    fn as_x_poly(&self, v: Var) -> Result<XPoly, String> {
         match self {
             Poly::Constant(n) => Ok(XPoly::new(vec![FInt::new(*n as f64)])),
             Poly::Nested(v1, polys) => {
@@ -29,7 +33,7 @@ impl PolyConversion for Poly {
         }
     }
 
-    fn as_xy_poly(&self, xv: u8, yv: u8) -> Result<XYPoly, String> {
+    fn as_xy_poly(&self, xv: Var, yv: Var) -> Result<XYPoly, String> {
         if xv == yv {
             return Err("x and y variables must be different".to_string());
         }
@@ -74,126 +78,102 @@ impl PolyConversion for Poly {
             return Ok(Poly::Constant(0));
         }
 
+        // Pari/GP and Singular print rational coefficients as fractions, like
+        // "(1/2*b + 1)*a - 1/3"; the result is cleared of their denominators
+        if s.contains('/') {
+            let (result, _) = Self::parse_rational_expression(s, 0)?;
+            return Ok(result.to_poly());
+        }
+
         let (result, _) = Self::parse_expression(s, 0)?;
         Ok(result)
     }
 
-    fn as_formatted_equation(&self, x_var: u8, y_var: u8) -> String {
-        let terms = self.to_terms();
-
-        // Separate constant term from variable terms
-        let mut constant_term: Option<i64> = None;
-        let mut variable_terms = Vec::new();
-
-        for term in terms {
-            let mut x_degree = 0;
-            let mut y_degree = 0;
+    fn as_formatted_equation(&self, x_var: Var, y_var: Var) -> String {
+        let (terms, constant) = self.equation_sides(x_var, y_var);
+        let poly_parts: Vec<(i64, String)> = terms
+            .into_iter()
+            .map(|(coeff, x_deg, y_deg)| {
+                let mut monomial = String::new();
 
-            for (var_idx, degree) in &term.vars {
-                if *var_idx == x_var {
-                    x_degree = *degree;
-                } else if *var_idx == y_var {
-                    y_degree = *degree;
-                } else {
-                    panic!(
-                        "Polynomial contains variable {} which is not x or y",
-                        Poly::var_to_string(*var_idx)
-                    );
+                // Add coefficient if not 1
+                if coeff.abs() != 1 {
+                    monomial.push_str(&coeff.abs().to_string());
                 }
-            }
-
-            if x_degree == 0 && y_degree == 0 {
-                // This is a constant term
-                constant_term = Some(term.constant);
-            } else {
-                // This is a variable term
-                variable_terms.push((term.constant, x_degree, y_degree));
-            }
-        }
-
-        // Sort variable terms by total degree (descending), then by x degree (descending)
-        variable_terms.sort_by(|(_, x1, y1), (_, x2, y2)| {
-            let total1 = x1 + y1;
-            let total2 = x2 + y2;
-            total2.cmp(&total1).then_with(|| x2.cmp(x1))
-        });
-
-        // Build the polynomial part
-        let mut poly_parts = Vec::new();
-        for (coeff, x_deg, y_deg) in variable_terms {
-            let mut monomial = String::new();
-
-            // Add coefficient if not 1
-            if coeff.abs() != 1 {
-                monomial.push_str(&coeff.abs().to_string());
-            }
-
-            // Add x part
-            if x_deg > 0 {
-                monomial.push('x');
-                if x_deg > 1 {
-                    monomial.push_str(&Self::degree_to_superscript(x_deg));
+                for (var, degree) in [('x', x_deg), ('y', y_deg)] {
+                    if degree > 0 {
+                        monomial.push(var);
+                        if degree > 1 {
+                            monomial.push_str(&Self::degree_to_superscript(degree));
+                        }
+                    }
                 }
-            }
+                (coeff, monomial)
+            })
+            .collect();
+
+        format!(
+            "{} = {}",
+            Self::format_polynomial_parts(&poly_parts),
+            constant
+        )
+    }
 
-            // Add y part
-            if y_deg > 0 {
-                monomial.push('y');
-                if y_deg > 1 {
-                    monomial.push_str(&Self::degree_to_superscript(y_deg));
+    fn to_latex(&self, x_var: Var, y_var: Var) -> String {
+        let (terms, constant) = self.equation_sides(x_var, y_var);
+        let poly_parts: Vec<(i64, String)> = terms
+            .into_iter()
+            .map(|(coeff, x_deg, y_deg)| {
+                let mut monomial = String::new();
+                if coeff.abs() != 1 {
+                    monomial.push_str(&coeff.abs().to_string());
                 }
-            }
-
-            // Handle coefficient of 1 with no variables
-            if x_deg == 0 && y_deg == 0 {
-                monomial = coeff.abs().to_string();
-            }
-
-            poly_parts.push((coeff, monomial));
-        }
-
-        // Build the final equation
-        let mut equation = String::new();
-
-        match constant_term {
-            Some(c) if c > 0 => {
-                // Format: (-p) = c
-                if !poly_parts.is_empty() {
-                    let poly_parts: Vec<(i64, String)> = poly_parts
-                        .iter()
-                        .map(|(coeff, monomial)| (-coeff, monomial.clone()))
-                        .collect();
-                    equation.push_str(&Self::format_polynomial_parts(&poly_parts));
-                    equation.push_str(" = ");
-                    equation.push_str(&c.to_string());
-                } else {
-                    equation.push_str("0 = ");
-                    equation.push_str(&c.to_string());
+                for (var, degree) in [('x', x_deg), ('y', y_deg)] {
+                    if degree > 0 {
+                        monomial.push(var);
+                        if degree > 1 {
+                            monomial.push_str(&format!("^{{{}}}", degree));
+                        }
+                    }
                 }
+                (coeff, monomial)
+            })
+            .collect();
+
+        format!(
+            "{} = {}",
+            Self::format_polynomial_parts(&poly_parts),
+            constant
+        )
+    }
+
+    fn to_mathml(&self, x_var: Var, y_var: Var) -> String {
+        let (terms, constant) = self.equation_sides(x_var, y_var);
+        let mut row = String::new();
+        for (i, &(coeff, x_deg, y_deg)) in terms.iter().enumerate() {
+            if coeff < 0 {
+                row.push_str("<mo>-</mo>");
+            } else if i > 0 {
+                row.push_str("<mo>+</mo>");
             }
-            Some(c) if c < 0 => {
-                // Format: p = -c
-                if !poly_parts.is_empty() {
-                    equation.push_str(&Self::format_polynomial_parts(&poly_parts));
-                    equation.push_str(" = ");
-                    equation.push_str(&(-c).to_string());
-                } else {
-                    equation.push_str("0 = ");
-                    equation.push_str(&(-c).to_string());
-                }
+            if coeff.abs() != 1 {
+                row.push_str(&format!("<mn>{}</mn>", coeff.abs()));
             }
-            Some(_) | None => {
-                // Format: p = 0
-                if !poly_parts.is_empty() {
-                    equation.push_str(&Self::format_polynomial_parts(&poly_parts));
-                    equation.push_str(" = 0");
-                } else {
-                    equation.push_str("0 = 0");
+            for (var, degree) in [('x', x_deg), ('y', y_deg)] {
+                match degree {
+                    0 => {}
+                    1 => row.push_str(&format!("<mi>{}</mi>", var)),
+                    _ => row.push_str(&format!("<msup><mi>{}</mi><mn>{}</mn></msup>", var, degree)),
                 }
             }
         }
-
-        equation
+        if terms.is_empty() {
+            row.push_str("<mn>0</mn>");
+        }
+        format!(
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow>{}<mo>=</mo><mn>{}</mn></mrow></math>",
+            row, constant
+        )
     }
 }
 
@@ -216,6 +196,52 @@ impl std::fmt::Debug for Poly {
 }
 
 impl Poly {
+    /// Splits the equation self = 0 of a curve into the terms (coefficient, x degree,
+    /// y degree) of the left side, by descending total degree and x degree, and a
+    /// non-negative constant on the right side
+    fn equation_sides(&self, x_var: Var, y_var: Var) -> (Vec<(i64, u32, u32)>, i64) {
+        let mut constant_term = 0;
+        let mut variable_terms = Vec::new();
+
+        for term in self.to_terms() {
+            let mut x_degree = 0;
+            let mut y_degree = 0;
+
+            for (var_idx, degree) in &term.vars {
+                if *var_idx == x_var {
+                    x_degree = *degree;
+                } else if *var_idx == y_var {
+                    y_degree = *degree;
+                } else {
+                    panic!(
+                        "Polynomial contains variable {} which is not x or y",
+                        Poly::var_to_string(*var_idx)
+                    );
+                }
+            }
+
+            if x_degree == 0 && y_degree == 0 {
+                constant_term = term.constant;
+            } else {
+                variable_terms.push((term.constant, x_degree, y_degree));
+            }
+        }
+
+        // Greatest term first in the grevlex order with x before y, i.e. by total degree
+        // (descending), then by x degree (descending)
+        variable_terms.sort_by(|(_, x1, y1), (_, x2, y2)| {
+            TermOrder::Grevlex.compare(&[*x2, *y2], &[*x1, *y1])
+        });
+
+        // A positive constant is moved to the right side as is: (-p) = c
+        if constant_term > 0 {
+            for term in &mut variable_terms {
+                term.0 = -term.0;
+            }
+        }
+        (variable_terms, constant_term.abs())
+    }
+
     /// Convert a degree to Unicode superscript
     fn degree_to_superscript(degree: u32) -> String {
         let superscript_chars = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
@@ -371,16 +397,29 @@ impl Poly {
         Ok((result, pos))
     }
 
-    fn extract_monomial(s: &str, mut pos: usize) -> Result<(Poly, usize), String> {
+    fn extract_monomial(s: &str, pos: usize) -> Result<(Poly, usize), String> {
+        let (start_pos, pos) = Self::monomial_span(s, pos);
+
+        // Extract the monomial string
+        let monomial_str = &s[start_pos..pos].trim();
+
+        // Convert the whole part to Poly via Poly::new()
+        if monomial_str.is_empty() {
+            Ok((Poly::Constant(1), pos))
+        } else {
+            let poly = Poly::new(monomial_str)
+                .map_err(|e| format!("Invalid monomial '{}': {:?}", monomial_str, e))?;
+            Ok((poly, pos))
+        }
+    }
+
+    /// Start and end of the monomial at pos, after leading whitespace
+    fn monomial_span(s: &str, mut pos: usize) -> (usize, usize) {
         // Skip leading whitespace
         while pos < s.len() && s.chars().nth(pos).unwrap().is_whitespace() {
             pos += 1;
         }
 
-        if pos >= s.len() {
-            return Ok((Poly::Constant(1), pos));
-        }
-
         let start_pos = pos;
 
         // Keep increasing the index until +, -, ), or the end of the line is encountered
@@ -391,18 +430,74 @@ impl Poly {
             }
             pos += 1;
         }
+        (start_pos, pos)
+    }
 
-        // Extract the monomial string
-        let monomial_str = &s[start_pos..pos].trim();
+    /// `parse_expression` for expressions with fractions among the coefficients
+    fn parse_rational_expression(s: &str, mut pos: usize) -> Result<(RatPoly, usize), String> {
+        let mut result = RatPoly::zero();
+        let mut current_sign = Rational::from(1);
 
-        // Convert the whole part to Poly via Poly::new()
-        if monomial_str.is_empty() {
-            Ok((Poly::Constant(1), pos))
-        } else {
-            let poly = Poly::new(monomial_str)
-                .map_err(|e| format!("Invalid monomial '{}': {:?}", monomial_str, e))?;
-            Ok((poly, pos))
+        while pos < s.len() {
+            let ch = s.chars().nth(pos).unwrap();
+
+            if ch == ')' {
+                return Ok((result, pos + 1));
+            } else if ch == '(' {
+                let (sub_poly, new_pos) = Self::parse_rational_expression(s, pos + 1)?;
+                pos = new_pos;
+
+                let product = if pos < s.len() && s.chars().nth(pos).unwrap() == '*' {
+                    let (monomial, final_pos) = Self::extract_rational_monomial(s, pos + 1)?;
+                    pos = final_pos;
+                    sub_poly.multiply(&monomial)
+                } else {
+                    sub_poly
+                };
+
+                result.add_scaled(&product, current_sign);
+            } else if ch == '+' || ch == '-' {
+                current_sign = Rational::from(if ch == '+' { 1 } else { -1 });
+                pos += 1;
+            } else if ch.is_whitespace() {
+                pos += 1;
+            } else {
+                let (monomial, new_pos) = Self::extract_rational_monomial(s, pos)?;
+                pos = new_pos;
+                result.add_scaled(&monomial, current_sign);
+            }
         }
+
+        Ok((result, pos))
+    }
+
+    /// `extract_monomial` for monomials whose factors may be fractions, like "-3/4*a^2"
+    fn extract_rational_monomial(s: &str, pos: usize) -> Result<(RatPoly, usize), String> {
+        let (start_pos, end_pos) = Self::monomial_span(s, pos);
+        let mut monomial = RatPoly::constant(Rational::from(1));
+        for factor in s[start_pos..end_pos].split('*').map(str::trim) {
+            let factor = match factor.split_once('/') {
+                _ if factor.is_empty() => continue,
+                Some((num, den)) => {
+                    let parse = |n: &str| {
+                        n.trim()
+                            .parse::<i128>()
+                            .map_err(|e| format!("Invalid fraction '{}': {}", factor, e))
+                    };
+                    let den = parse(den)?;
+                    if den == 0 {
+                        return Err(format!("Invalid fraction '{}': zero denominator", factor));
+                    }
+                    RatPoly::constant(Rational::new(parse(num)?, den))
+                }
+                None => RatPoly::from_poly(
+                    &Poly::new(factor)
+                        .map_err(|e| format!("Invalid monomial '{}': {:?}", factor, e))?,
+                ),
+            };
+            monomial = monomial.multiply(&factor);
+        }
+        Ok((monomial, end_pos))
     }
 }
 
@@ -586,6 +681,27 @@ mod tests {
         assert_eq!(format!("{}", result), "b");
     }
 
+    #[test]
+    fn test_from_poly_expression_fractions() {
+        // gp> 1/2*x^2 - 1/3*y (in the variables a and b)
+        let result = Poly::from_poly_expression("1/2*a^2 - 1/3*b").unwrap();
+        assert_eq!(result, Poly::new("3*a^2 - 2*b").unwrap());
+
+        // gp> (x^2 - 1) / 6 + y * (x / 4 + 1), printed nested in the variable order
+        let result = Poly::from_poly_expression("1/6*a^2 + (1/4*b - 1/6)*a + b").unwrap();
+        assert_eq!(result, Poly::new("2*a^2 + 3*a*b - 2*a + 12*b").unwrap());
+
+        // gp> content(6*x + 4) / 4
+        let result = Poly::from_poly_expression("1/2").unwrap();
+        assert_eq!(result, Poly::Constant(1));
+
+        let result = Poly::from_poly_expression("(-2/3*b^2 + 1)*a^2 + 4/9").unwrap();
+        assert_eq!(result, Poly::new("-6*a^2*b^2 + 9*a^2 + 4").unwrap());
+
+        assert!(Poly::from_poly_expression("1/0*a").is_err());
+        assert!(Poly::from_poly_expression("a/b").is_err());
+    }
+
     #[test]
     fn test_from_poly_expression_complex_case() {
         let input = "((3*y1^2 - 3*y1)*b*a + (3*y1^3 - 3*y1 - 2))*x^2 + ((3*y1^2 - 3*y1 - 2)*b*a + (d + (3*y1^3 - 2*y1 - 1)))*x + ((d + (y1 + 1))*b*a + (d + (y1 + 1)))";
@@ -660,6 +776,27 @@ mod tests {
         assert_eq!(result, "y⁴ = 0");
     }
 
+    #[test]
+    fn test_to_latex_and_mathml() {
+        let poly = Poly::new("a^2 + b^2 - 25").unwrap();
+        assert_eq!(poly.to_latex(0, 1), "x^{2} + y^{2} = 25");
+        let poly = Poly::new("3*a^10*b - a*b + 4").unwrap();
+        assert_eq!(poly.to_latex(0, 1), "-3x^{10}y + xy = 4");
+        assert_eq!(
+            poly.to_mathml(0, 1),
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow>\
+             <mo>-</mo><mn>3</mn><msup><mi>x</mi><mn>10</mn></msup><mi>y</mi>\
+             <mo>+</mo><mi>x</mi><mi>y</mi><mo>=</mo><mn>4</mn></mrow></math>"
+        );
+        // Plot variables may come in any order
+        let poly = Poly::new("c - b^2").unwrap();
+        assert_eq!(poly.to_latex(2, 1), "-y^{2} + x = 0");
+        assert_eq!(
+            Poly::new("5").unwrap().to_mathml(0, 1),
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow><mn>0</mn><mo>=</mo><mn>5</mn></mrow></math>"
+        );
+    }
+
     #[test]
     #[should_panic(expected = "variable z which is not x or y")]
     fn test_as_formatted_equation_error_cases() {
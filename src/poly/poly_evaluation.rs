@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::ops::{Add, Mul};
+
+use crate::fint::FInt;
+use crate::poly::{checked, Poly, PolyError, PolyOperations, RatPoly, Rational, Term, Var};
+
+/// Newton steps taken by `project_to_curve` before giving up
+const MAX_PROJECTION_STEPS: usize = 50;
+
+/// Length of the last Newton step of `project_to_curve`, relative to the distance of
+/// the point from the origin (plus 1), below which the point counts as on the curve
+const PROJECTION_TOLERANCE: f64 = 1e-12;
+
+impl Poly {
+    /// Value of the polynomial with the variables set to `values`
+    pub fn eval(&self, values: &HashMap<Var, f64>) -> Result<f64, PolyError> {
+        self.eval_with(values, &|n| n as f64)
+    }
+
+    /// Interval containing the values of the polynomial with the variables ranging
+    /// over `values`, rounding errors included
+    pub fn eval_interval(&self, values: &HashMap<Var, FInt>) -> Result<FInt, PolyError> {
+        self.eval_with(values, &|n| FInt::new(n as f64))
+    }
+
+    /// The polynomial with the variables in `values` replaced by the numbers; the
+    /// other variables are kept
+    pub fn substitute_values(&self, values: &HashMap<Var, i64>) -> Poly {
+        let terms: Vec<Term> = self
+            .to_terms()
+            .into_iter()
+            .map(|term| {
+                let mut constant = term.constant;
+                let mut vars = Vec::new();
+                for (v, degree) in term.vars {
+                    match values.get(&v) {
+                        Some(&value) => {
+                            let power = checked(value.overflowing_pow(degree));
+                            constant = checked(constant.overflowing_mul(power));
+                        }
+                        None => vars.push((v, degree)),
+                    }
+                }
+                Term { constant, vars }
+            })
+            .collect();
+        Poly::from_term_list(&terms)
+    }
+
+    /// The partial derivatives (F_x, F_y) of the curve F(x, y) = 0 at a point
+    pub fn gradient(
+        &self,
+        x_var: Var,
+        y_var: Var,
+        (x, y): (f64, f64),
+    ) -> Result<(f64, f64), PolyError> {
+        let point = HashMap::from([(x_var, x), (y_var, y)]);
+        Ok((
+            self.get_derivative(x_var).eval(&point)?,
+            self.get_derivative(y_var).eval(&point)?,
+        ))
+    }
+
+    /// The point of the curve F(x, y) = 0 reached from `point` by Newton steps along
+    /// the gradient, close to the nearest point of the curve. None if the steps reach
+    /// a critical point of F or don't converge.
+    pub fn project_to_curve(
+        &self,
+        x_var: Var,
+        y_var: Var,
+        point: (f64, f64),
+    ) -> Result<Option<(f64, f64)>, PolyError> {
+        let (f_x, f_y) = (self.get_derivative(x_var), self.get_derivative(y_var));
+        let (mut x, mut y) = point;
+        for _ in 0..MAX_PROJECTION_STEPS {
+            let point = HashMap::from([(x_var, x), (y_var, y)]);
+            let gradient = (f_x.eval(&point)?, f_y.eval(&point)?);
+            let norm = gradient.0 * gradient.0 + gradient.1 * gradient.1;
+            if !(norm > 0.0 && norm.is_finite()) {
+                return Ok(None);
+            }
+            let step = self.eval(&point)? / norm;
+            let (dx, dy) = (step * gradient.0, step * gradient.1);
+            x -= dx;
+            y -= dy;
+            if dx.hypot(dy) <= PROJECTION_TOLERANCE * (1.0 + x.hypot(y)) {
+                return Ok(Some((x, y)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Like `substitute_values` with fractions, multiplied by a positive integer so
+    /// that the coefficients are integers again
+    pub fn substitute_rational_values(&self, values: &HashMap<Var, Rational>) -> Poly {
+        let mut result = RatPoly::from_poly(self);
+        for (&v, &value) in values {
+            result = result.substitute(v, &RatPoly::constant(value));
+        }
+        result.to_poly()
+    }
+
+    fn eval_with<T>(
+        &self,
+        values: &HashMap<Var, T>,
+        constant: &impl Fn(i64) -> T,
+    ) -> Result<T, PolyError>
+    where
+        T: Copy + Add<Output = T> + Mul<Output = T>,
+    {
+        match self {
+            Poly::Constant(n) => Ok(constant(*n)),
+            Poly::Nested(v, polys) => {
+                let x = *values.get(v).ok_or(PolyError::MissingValue(*v))?;
+                // Horner's scheme from the leading coefficient down
+                let (leading, rest) = polys.split_last().expect("Nested poly without terms");
+                rest.iter()
+                    .rev()
+                    .try_fold(leading.eval_with(values, constant)?, |result, poly| {
+                        Ok(result * x + poly.eval_with(values, constant)?)
+                    })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let poly = Poly::new("a^2*b - 3*b + 2*c^3 - 1").unwrap();
+        let values = HashMap::from([(0, 2.0), (1, -1.0), (2, 0.5)]);
+        assert_eq!(poly.eval(&values), Ok(-4.0 + 3.0 + 0.25 - 1.0));
+        assert_eq!(Poly::Constant(7).eval(&HashMap::new()), Ok(7.0));
+        assert_eq!(
+            poly.eval(&HashMap::from([(0, 2.0), (1, -1.0)])),
+            Err(PolyError::MissingValue(2))
+        );
+
+        let values = HashMap::from([
+            (0, FInt::new_with_bounds(1.0, 2.0)),
+            (1, FInt::new(-1.0)),
+            (2, FInt::new(0.5)),
+        ]);
+        let value = poly.eval_interval(&values).unwrap();
+        // a^2 ranges over [1, 4], so the value over [-4, -1] + 3 - 0.75
+        assert!(value.lower_bound() <= -1.75 && value.upper_bound() >= 1.25);
+        assert!(value.lower_bound() > -1.8 && value.upper_bound() < 1.3);
+    }
+
+    #[test]
+    fn test_project_to_curve() {
+        // The circle of radius 2 around (1, 0)
+        let circle = Poly::new("a^2 - 2*a + b^2 - 3").unwrap();
+        assert_eq!(circle.gradient(0, 1, (3.0, 0.0)), Ok((4.0, 0.0)));
+        let (x, y) = circle.project_to_curve(0, 1, (3.5, 0.2)).unwrap().unwrap();
+        assert!(((x - 1.0).hypot(y) - 2.0).abs() < 1e-12);
+        // The gradient is radial, so the point is the nearest one, in the direction of
+        // (2.5, 0.2) from the center
+        assert!((y / (x - 1.0) - 0.08).abs() < 1e-12);
+
+        // The center is a critical point of F
+        assert_eq!(circle.project_to_curve(0, 1, (1.0, 0.0)), Ok(None));
+        assert_eq!(
+            circle.project_to_curve(0, 2, (3.0, 0.0)),
+            Err(PolyError::MissingValue(1))
+        );
+    }
+
+    #[test]
+    fn test_substitute_values() {
+        let poly = Poly::new("a^2*b - 3*b + 2*c^3*a - 1").unwrap();
+        assert_eq!(
+            poly.substitute_values(&HashMap::from([(0, 2), (2, -1)])),
+            Poly::new("b - 5").unwrap()
+        );
+        assert_eq!(
+            poly.substitute_values(&HashMap::from([(1, 0)])),
+            Poly::new("2*c^3*a - 1").unwrap()
+        );
+        assert_eq!(poly.substitute_values(&HashMap::new()), poly);
+
+        // a = 1/2: b/4 - 3*b + c^3 - 1, times 4
+        assert_eq!(
+            poly.substitute_rational_values(&HashMap::from([(0, Rational::new(1, 2))])),
+            Poly::new("4*c^3 - 11*b - 4").unwrap()
+        );
+    }
+}
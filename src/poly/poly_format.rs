@@ -0,0 +1,179 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use super::{Poly, Term, Var};
+
+/// Order of the terms of a formatted polynomial, which starts with the greatest term.
+/// Variables are ranked as in `PolyFormat::ranking`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TermOrder {
+    /// By the degree in the first variable, then in the second one and so on
+    Lex,
+    /// By total degree, then by the lowest degree in the last variable, then in the
+    /// one before it and so on
+    #[default]
+    Grevlex,
+}
+
+impl TermOrder {
+    /// Compares two terms by their degrees in the ranked variables
+    pub fn compare(&self, a: &[u32], b: &[u32]) -> Ordering {
+        match self {
+            TermOrder::Lex => a.cmp(b),
+            TermOrder::Grevlex => {
+                let total = |degrees: &[u32]| degrees.iter().sum::<u32>();
+                total(a)
+                    .cmp(&total(b))
+                    .then_with(|| b.iter().rev().cmp(a.iter().rev()))
+            }
+        }
+    }
+}
+
+/// Formats polynomials for users, with the greatest term first and variable names of
+/// their choice, e.g. "x^2 + 3*x*y - 2*y + 1". The output uses the syntax of `Poly::new`,
+/// so it parses back as long as the names are those of `Poly::var_to_string`.
+#[derive(Debug, Clone, Default)]
+pub struct PolyFormat {
+    order: TermOrder,
+    /// Renamed variables, in the order given
+    names: Vec<(Var, String)>,
+}
+
+impl PolyFormat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_order(mut self, order: TermOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Prints v as `name`, and ranks it after the variables named before
+    pub fn with_var_name(mut self, v: Var, name: &str) -> Self {
+        self.names.retain(|(var, _)| *var != v);
+        self.names.push((v, name.to_string()));
+        self
+    }
+
+    pub fn var_name(&self, v: Var) -> String {
+        self.names
+            .iter()
+            .find(|(var, _)| *var == v)
+            .map_or_else(|| Poly::var_to_string(v), |(_, name)| name.clone())
+    }
+
+    /// The variables of the terms from the most significant one: the named ones in the
+    /// order they were named, then the others by index (a, b, c, ...)
+    fn ranking(&self, terms: &[Term]) -> Vec<Var> {
+        let vars: BTreeSet<Var> = terms
+            .iter()
+            .flat_map(|term| term.vars.iter().map(|&(v, _)| v))
+            .collect();
+        let named = self
+            .names
+            .iter()
+            .map(|&(v, _)| v)
+            .filter(|v| vars.contains(v));
+        let others = vars
+            .iter()
+            .copied()
+            .filter(|v| !self.names.iter().any(|(var, _)| var == v));
+        named.chain(others).collect()
+    }
+
+    pub fn format(&self, poly: &Poly) -> String {
+        let terms: Vec<Term> = poly
+            .to_terms()
+            .into_iter()
+            .filter(|term| term.constant != 0)
+            .collect();
+        let ranking = self.ranking(&terms);
+        let mut terms: Vec<(i64, Vec<u32>)> = terms
+            .into_iter()
+            .map(|term| {
+                let mut degrees = vec![0; ranking.len()];
+                for (v, degree) in term.vars {
+                    let i = ranking.iter().position(|&var| var == v).unwrap();
+                    degrees[i] = degree;
+                }
+                (term.constant, degrees)
+            })
+            .collect();
+        if terms.is_empty() {
+            return "0".to_string();
+        }
+        terms.sort_by(|(_, a), (_, b)| self.order.compare(b, a));
+
+        let mut result = String::new();
+        for (i, (constant, degrees)) in terms.iter().enumerate() {
+            match (i, *constant < 0) {
+                (0, true) => result.push('-'),
+                (0, false) => {}
+                (_, true) => result.push_str(" - "),
+                (_, false) => result.push_str(" + "),
+            }
+            let mut factors = Vec::new();
+            if constant.abs() != 1 || degrees.iter().all(|&degree| degree == 0) {
+                factors.push(constant.abs().to_string());
+            }
+            for (&v, &degree) in ranking.iter().zip(degrees) {
+                match degree {
+                    0 => {}
+                    1 => factors.push(self.var_name(v)),
+                    _ => factors.push(format!("{}^{}", self.var_name(v), degree)),
+                }
+            }
+            result.push_str(&factors.join("*"));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_term_orders() {
+        let poly = Poly::new("3*b + 2*a^2 - a*b^2 + b^3 + a - 7").unwrap();
+        assert_eq!(
+            PolyFormat::new().format(&poly),
+            "-a*b^2 + b^3 + 2*a^2 + a + 3*b - 7"
+        );
+        assert_eq!(
+            PolyFormat::new().with_order(TermOrder::Lex).format(&poly),
+            "2*a^2 - a*b^2 + a + b^3 + 3*b - 7"
+        );
+        // Ranking b before a
+        assert_eq!(
+            PolyFormat::new()
+                .with_order(TermOrder::Lex)
+                .with_var_name(1, "b")
+                .format(&poly),
+            "b^3 - b^2*a + 3*b + 2*a^2 + a - 7"
+        );
+
+        // Grevlex breaks ties with the last variable: a*c < b^2
+        let poly = Poly::new("a*c + b^2 + a*b").unwrap();
+        assert_eq!(PolyFormat::new().format(&poly), "a*b + b^2 + a*c");
+        assert_eq!(PolyFormat::new().format(&Poly::Constant(0)), "0");
+        assert_eq!(PolyFormat::new().format(&Poly::Constant(-4)), "-4");
+    }
+
+    #[test]
+    fn test_var_names() {
+        let poly = Poly::new("c^2 - d^2*b + 1").unwrap();
+        let format = PolyFormat::new()
+            .with_var_name(2, "x")
+            .with_var_name(3, "y");
+        assert_eq!(format.format(&poly), "-y^2*b + x^2 + 1");
+        assert_eq!(format.var_name(1), "b");
+
+        // With the default names, the output parses back
+        let poly = Poly::new("3*b + 2*a^2 - a*b^2 + a27*b - 7").unwrap();
+        let formatted = PolyFormat::new().format(&poly);
+        assert_eq!(Poly::new(&formatted).unwrap(), poly);
+    }
+}
@@ -1,6 +1,9 @@
+use gcd::Gcd;
 use log::info;
 
-use crate::poly::{Poly, PolyConversion};
+use crate::budget;
+use crate::poly::{checked, Poly, RatPoly, Rational, Var};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
@@ -21,19 +24,23 @@ pub trait PolyOperations {
     fn scale(&mut self, factor: i64);
     fn add_poly_scaled(&mut self, poly: &Poly, factor: i64);
     fn multiply(&self, poly: &Poly) -> Poly;
-    fn extract_factor_and_remainder(self: &Rc<Self>, v: u8, degree: u32) -> (Rc<Poly>, Rc<Poly>);
-    fn decompose(self: &Rc<Self>, v: u8) -> Vec<Rc<Poly>>;
-    fn single_out(&self, v: u8) -> SingleOutResult;
-    fn substitute_linear(&self, v: u8, poly: Rc<Poly>, k: i64) -> Poly;
-    fn get_derivative(&self, v: u8) -> Poly;
+    fn extract_factor_and_remainder(self: &Rc<Self>, v: Var, degree: u32) -> (Rc<Poly>, Rc<Poly>);
+    fn decompose(self: &Rc<Self>, v: Var) -> Vec<Rc<Poly>>;
+    fn single_out(&self, v: Var) -> SingleOutResult;
+    fn substitute_linear(&self, v: Var, poly: Rc<Poly>, k: i64) -> Poly;
+    fn get_derivative(&self, v: Var) -> Poly;
     fn factor(&self) -> Result<Vec<Poly>, String>;
     fn reduce_by_gcd(poly1: Rc<Poly>, poly2: Rc<Poly>) -> ReductionResult;
+    fn divide_exact(&self, divisor: &Poly) -> Option<Poly>;
+    fn pseudo_divide(&self, divisor: &Poly, v: Var) -> (Poly, Poly);
+    fn content(&self, v: Var) -> Poly;
+    fn primitive_part(&self, v: Var) -> Poly;
 }
 
 impl PolyOperations for Poly {
     fn scale(&mut self, factor: i64) {
         match self {
-            Poly::Constant(n) => *n *= factor,
+            Poly::Constant(n) => *n = checked(n.overflowing_mul(factor)),
             Poly::Nested(_, polys) => {
                 for poly in polys.iter_mut() {
                     let poly_mut = Rc::make_mut(poly);
@@ -47,7 +54,7 @@ impl PolyOperations for Poly {
         match (&mut *self, poly) {
             // Both are constants
             (Poly::Constant(n1), Poly::Constant(n2)) => {
-                *n1 += factor * n2;
+                *n1 = checked(n1.overflowing_add(checked(factor.overflowing_mul(*n2))));
             }
             // Self is nested with variable v, poly is constant or has higher variable
             (Poly::Nested(_, polys), Poly::Constant(_)) => {
@@ -98,6 +105,8 @@ impl PolyOperations for Poly {
 
     fn multiply(&self, poly: &Poly) -> Poly {
         match (self, poly) {
+            // Scaling by 0 would leave the zero coefficients of a nested polynomial
+            (Poly::Constant(0), _) | (_, Poly::Constant(0)) => Poly::Constant(0),
             // If either is constant, scale the other
             (Poly::Constant(n), _) => {
                 let mut result = poly.clone();
@@ -128,6 +137,7 @@ impl PolyOperations for Poly {
                     Poly::Nested(*v1, new_polys)
                 };
                 result.cleanup();
+                budget::check_poly(&result, || Self::describe_product(&result));
                 result
             }
             // Both are nested with the same variable
@@ -147,6 +157,7 @@ impl PolyOperations for Poly {
                 }
                 let mut result = Poly::Nested(*v, result_polys);
                 result.cleanup();
+                budget::check_poly(&result, || Self::describe_product(&result));
                 result
             }
             // This case should never occur
@@ -154,7 +165,7 @@ impl PolyOperations for Poly {
         }
     }
 
-    fn extract_factor_and_remainder(self: &Rc<Self>, v: u8, degree: u32) -> (Rc<Poly>, Rc<Poly>) {
+    fn extract_factor_and_remainder(self: &Rc<Self>, v: Var, degree: u32) -> (Rc<Poly>, Rc<Poly>) {
         match &**self {
             Poly::Constant(_) => (Rc::new(Poly::Constant(0)), self.clone()),
             Poly::Nested(v1, _) if *v1 > v => (Rc::new(Poly::Constant(0)), self.clone()),
@@ -192,7 +203,7 @@ impl PolyOperations for Poly {
         }
     }
 
-    fn decompose(self: &Rc<Self>, v: u8) -> Vec<Rc<Poly>> {
+    fn decompose(self: &Rc<Self>, v: Var) -> Vec<Rc<Poly>> {
         match &**self {
             Poly::Constant(_) => {
                 // For constants, return the constant as the only projection
@@ -238,7 +249,7 @@ impl PolyOperations for Poly {
         }
     }
 
-    fn single_out(&self, v: u8) -> SingleOutResult {
+    fn single_out(&self, v: Var) -> SingleOutResult {
         match self {
             Poly::Constant(_) => SingleOutResult::Constant,
             Poly::Nested(v1, polys) => {
@@ -292,27 +303,16 @@ impl PolyOperations for Poly {
         }
     }
 
-    fn substitute_linear(&self, v: u8, poly: Rc<Poly>, k: i64) -> Poly {
-        let d = self.get_degree(v);
-        let mut factors = vec![Rc::new(Poly::Constant(0)); d as usize + 1];
-        self.compute_factors(v, &mut factors);
-        let mut factor0 = factors[0].clone();
-
-        let result = Rc::make_mut(&mut factor0);
-        result.scale(k.pow(d));
-        let mut poly_power = poly.clone();
-
-        for i in 1..=d {
-            let product = factors[i as usize].multiply(&poly_power);
-            result.add_poly_scaled(&product, k.pow(d - i));
-            poly_power = Rc::new(poly_power.multiply(&poly));
-        }
-        result.cleanup();
+    fn substitute_linear(&self, v: Var, poly: Rc<Poly>, k: i64) -> Poly {
+        // Substitute v = poly / k over the rationals, then clear the denominators
+        let mut value = RatPoly::from_poly(&poly);
+        value.scale(Rational::new(1, k as i128));
+        let mut result = RatPoly::from_poly(self).substitute(v, &value).to_poly();
         result.reduce_coefficients_if_large();
-        result.clone()
+        result
     }
 
-    fn get_derivative(&self, v: u8) -> Poly {
+    fn get_derivative(&self, v: Var) -> Poly {
         match self {
             Poly::Constant(_) => Poly::Constant(0),
             Poly::Nested(v1, polys) => {
@@ -343,223 +343,386 @@ impl PolyOperations for Poly {
     }
 
     fn factor(&self) -> Result<Vec<Poly>, String> {
-        // Get the GpPariService singleton
-        let service = crate::get_gp_pari_service()?;
-
-        // Create the Pari/GP factoring task
-        let poly_str = format!("{:#}", self);
-        let pari_task = format!(
-            "{{expr = Vec(factor({}));print(expr[1]);print(expr[2]);print(\"Done\")}}",
-            poly_str
-        );
+        let (factors, degrees): (Vec<Poly>, Vec<u32>) =
+            crate::cas::backend().factor(self)?.into_iter().unzip();
 
-        // Execute the task using the singleton service
-        let output_lines = service.run_task(pari_task)?;
+        // Reconstruct the polynomial and verify it matches the original
+        let mut reconstructed = Poly::Constant(1);
+        for (factor, &degree) in factors.iter().zip(degrees.iter()) {
+            let mut factor_power = factor.clone();
+            for _ in 1..degree {
+                factor_power = factor_power.multiply(factor);
+            }
+            reconstructed = reconstructed.multiply(&factor_power);
+        }
 
-        if output_lines.len() < 2 {
+        // Factors printed with fractions are cleared of their denominators, so the
+        // product may differ from self by a constant factor
+        if !reconstructed.is_proportional(self, &mut None) {
             return Err(format!(
-                "Expected at least 2 lines of output from Pari/GP. Output: {:?}",
-                output_lines
+                "Factorization verification failed. Original: {}, Reconstructed: {}",
+                self, reconstructed
             ));
         }
 
-        // Parse the first line as "[<poly1>,<poly2>,..<polyN>]~"
-        let factors_line = output_lines[0].trim();
-        if !factors_line.starts_with('[') || !factors_line.ends_with("]~") {
-            return Err(format!("Invalid factors line format: {}", factors_line));
-        }
-
-        let factors_content = &factors_line[1..factors_line.len() - 2]; // Remove "[...]~"
-        let factor_strings: Vec<&str> = Self::parse_pari_list(factors_content)?;
+        Ok(factors)
+    }
 
-        // Parse the second line as "[<degree1>,<degree2>,..,<degreeN>]~"
-        let degrees_line = output_lines[1].trim();
-        if !degrees_line.starts_with('[') || !degrees_line.ends_with("]~") {
-            return Err(format!("Invalid degrees line format: {}", degrees_line));
+    fn reduce_by_gcd(poly1: Rc<Poly>, poly2: Rc<Poly>) -> ReductionResult {
+        let unreduced = |poly1, poly2| ReductionResult {
+            reduced1: poly1,
+            reduced2: poly2,
+            gcd: Rc::new(Poly::Constant(1)),
+        };
+        let gcd = match crate::cas::backend().gcd(&poly1, &poly2) {
+            Ok(gcd) => gcd,
+            Err(e) => {
+                info!("Error computing the gcd, assuming gcd = 1: {}", e);
+                return unreduced(poly1, poly2);
+            }
+        };
+        if gcd == Poly::Constant(1) {
+            return unreduced(poly1, poly2);
         }
+        info!("Found GCD: {}", gcd);
+
+        match (poly1.divide_exact(&gcd), poly2.divide_exact(&gcd)) {
+            (Some(reduced1), Some(reduced2)) => ReductionResult {
+                reduced1: Rc::new(reduced1),
+                reduced2: Rc::new(reduced2),
+                gcd: Rc::new(gcd),
+            },
+            _ => {
+                info!("The gcd {} does not divide the polynomials", gcd);
+                unreduced(poly1, poly2)
+            }
+        }
+    }
 
-        let degrees_content = &degrees_line[1..degrees_line.len() - 2]; // Remove "[...]~"
-        let degree_strings: Vec<&str> = Self::parse_pari_list(degrees_content)?;
-
-        if factor_strings.len() != degree_strings.len() {
-            return Err(format!(
-                "Mismatch between factors ({}) and degrees ({})",
-                factor_strings.len(),
-                degree_strings.len()
-            ));
+    /// Quotient of the division by `divisor`, or None if the division is not exact
+    fn divide_exact(&self, divisor: &Poly) -> Option<Poly> {
+        match (self, divisor) {
+            (_, Poly::Constant(0)) => None,
+            (Poly::Constant(0), _) => Some(Poly::Constant(0)),
+            (_, Poly::Constant(d)) => {
+                let mut divisible = true;
+                self.observe_coefficients(|n| divisible &= n % d == 0);
+                if !divisible {
+                    return None;
+                }
+                let mut result = self.clone();
+                result.apply_to_coefficients(|n| n / d);
+                Some(result)
+            }
+            (Poly::Constant(_), Poly::Nested(_, _)) => None,
+            // The divisor doesn't depend on v, so each coefficient is divided separately
+            (Poly::Nested(v, polys), Poly::Nested(v1, _)) if v < v1 => {
+                let quotients = polys
+                    .iter()
+                    .map(|p| p.divide_exact(divisor).map(Rc::new))
+                    .collect::<Option<Vec<_>>>()?;
+                let mut result = Poly::Nested(*v, quotients);
+                result.cleanup();
+                Some(result)
+            }
+            (Poly::Nested(v, _), Poly::Nested(v1, _)) if v > v1 => None,
+            // Long division in v, whose coefficients only depend on later variables
+            (Poly::Nested(v, polys), Poly::Nested(_, divisor_polys)) => {
+                if polys.len() < divisor_polys.len() {
+                    return None;
+                }
+                let m = divisor_polys.len() - 1;
+                let mut remainder: Vec<Poly> = polys.iter().map(|p| (**p).clone()).collect();
+                let mut quotient: Vec<Rc<Poly>> = (m..polys.len())
+                    .map(|_| Rc::new(Poly::Constant(0)))
+                    .collect();
+                for k in (0..quotient.len()).rev() {
+                    let q = remainder[k + m].divide_exact(&divisor_polys[m])?;
+                    for (j, d) in divisor_polys.iter().enumerate() {
+                        remainder[k + j].add_poly_scaled(&q.multiply(d), -1);
+                    }
+                    quotient[k] = Rc::new(q);
+                }
+                if remainder.iter().any(|r| *r != Poly::Constant(0)) {
+                    return None;
+                }
+                let mut result = Poly::Nested(*v, quotient);
+                result.cleanup();
+                Some(result)
+            }
         }
+    }
 
-        // Convert factor strings to Poly objects
-        let mut factors: Vec<Poly> = Vec::new();
-        for factor_str in factor_strings {
-            let poly = Poly::from_poly_expression(factor_str)
-                .map_err(|e| format!("Failed to parse factor '{}': {}", factor_str, e))?;
-            factors.push(poly);
+    /// Pseudo-division in v: returns (q, r) with lc^(d1 - d2 + 1) * self = q * divisor + r,
+    /// where lc is the leading coefficient of `divisor` in v, d1 and d2 are the degrees
+    /// in v and r has a lower degree than `divisor`. `divisor` must not be zero.
+    fn pseudo_divide(&self, divisor: &Poly, v: Var) -> (Poly, Poly) {
+        let degree1 = self.get_degree(v) as usize;
+        let degree2 = divisor.get_degree(v) as usize;
+        if degree1 < degree2 {
+            return (Poly::Constant(0), self.clone());
+        }
+        let coefficients = |poly: &Poly| -> Vec<Poly> {
+            Rc::new(poly.clone())
+                .decompose(v)
+                .iter()
+                .map(|p| {
+                    let mut p = (**p).clone();
+                    p.cleanup();
+                    p
+                })
+                .collect()
+        };
+        let divisor_coefficients = coefficients(divisor);
+        let leading = &divisor_coefficients[degree2];
+        let mut remainder = coefficients(self);
+        let mut quotient = vec![Poly::Constant(0); degree1 - degree2 + 1];
+        let mut missing_steps = degree1 - degree2 + 1;
+
+        while remainder.len() > degree2 {
+            let d = remainder.len() - 1;
+            let s = remainder[d].clone();
+            // quotient = lc * quotient + s * v^(d - degree2)
+            for q in quotient.iter_mut() {
+                *q = q.multiply(leading);
+            }
+            quotient[d - degree2].add_poly_scaled(&s, 1);
+            // remainder = lc * remainder - s * v^(d - degree2) * divisor
+            for r in remainder.iter_mut() {
+                *r = r.multiply(leading);
+            }
+            for (j, c) in divisor_coefficients.iter().enumerate() {
+                remainder[d - degree2 + j].add_poly_scaled(&s.multiply(c), -1);
+            }
+            while remainder.last() == Some(&Poly::Constant(0)) {
+                remainder.pop();
+            }
+            missing_steps -= 1;
         }
 
-        // Parse degrees
-        let mut degrees: Vec<u32> = Vec::new();
-        for degree_str in degree_strings {
-            let degree = degree_str
-                .parse::<u32>()
-                .map_err(|e| format!("Failed to parse degree '{}': {}", degree_str, e))?;
-            degrees.push(degree);
+        // Skipped steps (zero coefficients) still count in the power of lc
+        let mut scale = Poly::Constant(1);
+        for _ in 0..missing_steps {
+            scale = scale.multiply(leading);
         }
+        (
+            Poly::from_coefficients(v, &quotient).multiply(&scale),
+            Poly::from_coefficients(v, &remainder).multiply(&scale),
+        )
+    }
 
-        // Reconstruct the polynomial and verify it matches the original
-        let mut reconstructed = Poly::Constant(1);
-        for (factor, &degree) in factors.iter().zip(degrees.iter()) {
-            let mut factor_power = factor.clone();
-            for _ in 1..degree {
-                factor_power = factor_power.multiply(factor);
-            }
-            reconstructed = reconstructed.multiply(&factor_power);
+    /// Greatest common divisor of the coefficients of `self` as a polynomial in v,
+    /// including their integer content. The sign is chosen so that the primitive part
+    /// has the same leading coefficient sign as `self`.
+    fn content(&self, v: Var) -> Poly {
+        let mut content = Poly::Constant(0);
+        for coefficient in Rc::new(self.clone()).decompose(v).iter() {
+            let mut coefficient = (**coefficient).clone();
+            coefficient.cleanup();
+            content = Poly::gcd(&content, &coefficient);
+        }
+        if (content.leading_constant() < 0) != (self.leading_constant() < 0) {
+            content.scale(-1);
         }
+        content
+    }
 
-        if reconstructed != *self {
-            reconstructed.apply_to_coefficients(|x| -x);
-            if reconstructed != *self {
-                return Err(format!(
-                    "Factorization verification failed. Original: {}, Reconstructed: {}",
-                    self, reconstructed
-                ));
-            }
+    /// `self` divided by its content in v
+    fn primitive_part(&self, v: Var) -> Poly {
+        if *self == Poly::Constant(0) {
+            return Poly::Constant(0);
         }
+        self.divide_exact(&self.content(v))
+            .expect("the content divides the polynomial")
+    }
+}
 
-        Ok(factors)
+impl Poly {
+    /// How a product over the budget is reported, e.g. "a product in a, b"
+    fn describe_product(product: &Poly) -> String {
+        let vars: Vec<String> = product
+            .get_variables()
+            .iter()
+            .map(Poly::var_to_string)
+            .collect();
+        format!("a product in {}", vars.join(", "))
     }
 
-    fn reduce_by_gcd(poly1: Rc<Poly>, poly2: Rc<Poly>) -> ReductionResult {
-        // Get the GpPariService singleton
-        let service = match crate::get_gp_pari_service() {
-            Ok(service) => service,
-            Err(_) => {
-                // If service is not available, return default result
-                return ReductionResult {
-                    reduced1: poly1,
-                    reduced2: poly2,
-                    gcd: Rc::new(Poly::Constant(1)),
-                };
+    /// self^exponent by repeated squaring; self^0 = 1
+    pub fn pow(&self, exponent: u32) -> Poly {
+        let mut result = Poly::Constant(1);
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result *= &base;
             }
-        };
-
-        // Create the Pari/GP task for GCD computation
-        let poly1_str = format!("{:#}", *poly1);
-        let poly2_str = format!("{:#}", *poly2);
-        let pari_task = format!(
-            "{{pp = {}; qq = {}; gg = gcd([pp, qq]); print(gg); print(pp / gg); print(qq / gg); print(\"Done\")}}",
-            poly1_str, poly2_str
-        );
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
 
-        // Execute the task using the singleton service
-        let output_lines = match service.run_task(pari_task) {
-            Ok(lines) => lines,
-            Err(e) => {
-                info!("Error running Pari/GP task, assuming gcd = 1: {}", e);
-                // If task execution fails, return default result
-                return ReductionResult {
-                    reduced1: poly1,
-                    reduced2: poly2,
-                    gcd: Rc::new(Poly::Constant(1)),
-                };
+    /// Greatest common divisor up to sign, by primitive pseudo-remainder sequences
+    /// in the outermost variable, with the contents handled recursively
+    pub(crate) fn gcd(a: &Poly, b: &Poly) -> Poly {
+        let v = match (a, b) {
+            (Poly::Constant(0), _) => return b.clone(),
+            (_, Poly::Constant(0)) => return a.clone(),
+            (Poly::Constant(n), p) | (p, Poly::Constant(n)) => {
+                let gcd = n.unsigned_abs().gcd(p.get_coefficient_gcd().unsigned_abs());
+                return Poly::Constant(gcd as i64);
             }
+            (Poly::Nested(v1, _), Poly::Nested(v2, _)) => *v1.min(v2),
         };
+        let content = Poly::gcd(&a.content(v), &b.content(v));
+        let (mut r0, mut r1) = (a.primitive_part(v), b.primitive_part(v));
+        if r0.get_degree(v) < r1.get_degree(v) {
+            std::mem::swap(&mut r0, &mut r1);
+        }
+        while r1 != Poly::Constant(0) {
+            let (_, remainder) = r0.pseudo_divide(&r1, v);
+            r0 = r1;
+            r1 = remainder.primitive_part(v);
+        }
+        content.multiply(&r0)
+    }
 
-        // Check if we got exactly 3 lines of output
-        if output_lines.len() != 3 {
-            info!(
-                "Expected 3 lines of output from Pari/GP, got {}",
-                output_lines.len()
-            );
-            return ReductionResult {
-                reduced1: poly1,
-                reduced2: poly2,
-                gcd: Rc::new(Poly::Constant(1)),
-            };
+    /// Coefficient of the lexicographically highest term
+    fn leading_constant(&self) -> i64 {
+        match self {
+            Poly::Constant(n) => *n,
+            Poly::Nested(_, polys) => polys.last().map_or(0, |p| p.leading_constant()),
         }
+    }
 
-        // Parse the first line (GCD)
-        let gcd_str = output_lines[0].trim();
-        if gcd_str == "1" {
-            // If GCD is 1, return the original polynomials
-            return ReductionResult {
-                reduced1: poly1,
-                reduced2: poly2,
-                gcd: Rc::new(Poly::Constant(1)),
-            };
+    /// Sum of coefficients[i] * v^i
+    fn from_coefficients(v: Var, coefficients: &[Poly]) -> Poly {
+        let mut result = Poly::Constant(0);
+        for (i, c) in coefficients.iter().enumerate() {
+            let mut power: Vec<Rc<Poly>> = (0..i).map(|_| Rc::new(Poly::Constant(0))).collect();
+            power.push(Rc::new(Poly::Constant(1)));
+            let mut power = Poly::Nested(v, power);
+            power.cleanup();
+            result.add_poly_scaled(&c.multiply(&power), 1);
         }
-        info!("Found GCD: {}", gcd_str);
+        result
+    }
+}
 
-        // Parse the GCD
-        let gcd = Poly::from_poly_expression(gcd_str).unwrap();
-        let reduced1 = Poly::from_poly_expression(output_lines[1].trim()).unwrap();
-        let reduced2 = Poly::from_poly_expression(output_lines[2].trim()).unwrap();
+// Arithmetic operators. Like add_poly_scaled, they return cleaned up polynomials.
 
-        ReductionResult {
-            reduced1: Rc::new(reduced1),
-            reduced2: Rc::new(reduced2),
-            gcd: Rc::new(gcd),
-        }
+impl AddAssign<&Poly> for Poly {
+    fn add_assign(&mut self, other: &Poly) {
+        self.add_poly_scaled(other, 1);
     }
 }
 
-impl Poly {
-    // self = factors[0] + v * factors[1] + ... + v^d * factors[d]
-    fn compute_factors(&self, v: u8, factors: &mut [Rc<Poly>]) {
-        match self {
-            Poly::Constant(_) => {
-                let factor0 = Rc::make_mut(&mut factors[0]);
-                factor0.add_poly_scaled(self, 1);
+impl SubAssign<&Poly> for Poly {
+    fn sub_assign(&mut self, other: &Poly) {
+        self.add_poly_scaled(other, -1);
+    }
+}
+
+impl MulAssign<&Poly> for Poly {
+    fn mul_assign(&mut self, other: &Poly) {
+        *self = &*self * other;
+    }
+}
+
+impl Mul<&Poly> for &Poly {
+    type Output = Poly;
+
+    fn mul(self, other: &Poly) -> Poly {
+        // Multiplying by a constant only scales, which may leave zeros behind
+        let mut result = self.multiply(other);
+        result.cleanup();
+        result
+    }
+}
+
+impl Neg for &Poly {
+    type Output = Poly;
+
+    fn neg(self) -> Poly {
+        let mut result = self.clone();
+        result.scale(-1);
+        result
+    }
+}
+
+impl Neg for Poly {
+    type Output = Poly;
+
+    fn neg(mut self) -> Poly {
+        self.scale(-1);
+        self
+    }
+}
+
+/// The by-value and mixed forms of an operator, in terms of the compound assignment
+macro_rules! forward_binary_op {
+    ($op:ident, $method:ident, $op_assign:ident, $method_assign:ident) => {
+        impl $op_assign<Poly> for Poly {
+            fn $method_assign(&mut self, other: Poly) {
+                $op_assign::$method_assign(self, &other);
             }
-            Poly::Nested(v1, polys) => {
-                if *v1 > v {
-                    let factor0 = Rc::make_mut(&mut factors[0]);
-                    factor0.add_poly_scaled(self, 1);
-                } else if *v1 == v {
-                    for (i, poly) in polys.iter().enumerate() {
-                        if i < factors.len() {
-                            let factor = Rc::make_mut(&mut factors[i]);
-                            factor.add_poly_scaled(poly, 1);
-                        }
-                    }
-                } else if *v1 < v {
-                    let mut inner_factors = Vec::new();
-                    let mut d_max = 0;
-                    for (i, poly) in polys.iter().enumerate() {
-                        let d = poly.get_degree(v);
-                        d_max = d_max.max(d);
-                        inner_factors.push(vec![Rc::new(Poly::Constant(0)); (d + 1) as usize]);
-                        poly.compute_factors(v, &mut inner_factors[i]);
-                    }
-                    for j in 0..(d_max as usize) + 1 {
-                        let inner_polys: Vec<Rc<Poly>> = inner_factors
-                            .iter()
-                            .map(|ps| {
-                                if j >= ps.len() {
-                                    Rc::new(Poly::Constant(0))
-                                } else {
-                                    ps[j].clone()
-                                }
-                            })
-                            .collect();
-                        let poly1 = Poly::Nested(*v1, inner_polys);
-                        let factor = Rc::make_mut(&mut factors[j]);
-                        factor.add_poly_scaled(&poly1, 1);
-                    }
-                }
+        }
+
+        impl $op<&Poly> for Poly {
+            type Output = Poly;
+
+            fn $method(mut self, other: &Poly) -> Poly {
+                $op_assign::$method_assign(&mut self, other);
+                self
             }
         }
+
+        impl $op<Poly> for Poly {
+            type Output = Poly;
+
+            fn $method(mut self, other: Poly) -> Poly {
+                $op_assign::$method_assign(&mut self, &other);
+                self
+            }
+        }
+
+        impl $op<Poly> for &Poly {
+            type Output = Poly;
+
+            fn $method(self, other: Poly) -> Poly {
+                $op::$method(self.clone(), &other)
+            }
+        }
+    };
+}
+
+forward_binary_op!(Add, add, AddAssign, add_assign);
+forward_binary_op!(Sub, sub, SubAssign, sub_assign);
+forward_binary_op!(Mul, mul, MulAssign, mul_assign);
+
+impl Add<&Poly> for &Poly {
+    type Output = Poly;
+
+    fn add(self, other: &Poly) -> Poly {
+        self.clone() + other
     }
+}
 
-    fn parse_pari_list(content: &str) -> Result<Vec<&str>, String> {
-        let result: Vec<&str> = content.split(',').map(|s| s.trim()).collect();
-        Ok(result)
+impl Sub<&Poly> for &Poly {
+    type Output = Poly;
+
+    fn sub(self, other: &Poly) -> Poly {
+        self.clone() - other
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::poly::PolyConversion;
 
     #[test]
     fn test_scale() {
@@ -712,6 +875,7 @@ mod tests {
         let p2 = Poly::new("1 + 2*a").unwrap();
         let result = p1.multiply(&p2);
         assert_eq!(format!("{}", result), "3 + 6*a");
+        assert_eq!(p2.multiply(&Poly::Constant(0)), Poly::Constant(0));
     }
 
     #[test]
@@ -776,6 +940,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_operators() {
+        let p = |s: &str| Poly::new(s).unwrap();
+        let a = p("a + b");
+        let b = p("a - b");
+        assert_eq!(&a + &b, p("2*a"));
+        assert_eq!(&a - &b, p("2*b"));
+        assert_eq!(a.clone() * b.clone(), p("a^2 - b^2"));
+        assert_eq!(-&a, p("-a - b"));
+        assert_eq!(&a - a.clone(), Poly::Constant(0));
+        // Multiplying by zero leaves no zero coefficients behind
+        assert_eq!(&Poly::Constant(0) * &a, Poly::Constant(0));
+
+        let mut c = a.clone();
+        c += &b;
+        c -= p("a");
+        c *= b.clone();
+        assert_eq!(c, p("a^2 - a*b"));
+
+        assert_eq!(a.pow(0), Poly::Constant(1));
+        assert_eq!(a.pow(1), a);
+        assert_eq!(a.pow(3), &a * &(&a * &a));
+        assert_eq!(Poly::Constant(-2).pow(5), Poly::Constant(-32));
+    }
+
     #[test]
     fn test_extract_factor_and_remainder_constant() {
         let p = Rc::new(Poly::new("5").unwrap());
@@ -860,94 +1049,12 @@ mod tests {
         let sub = Poly::new("c + d").unwrap();
         let result = poly.substitute_linear(1, Rc::new(sub), 2);
         assert_eq!(format!("{}", result), "d^2 + 2*d*c + c^2 + 2*d*a + 2*c*a");
-    }
 
-    #[test]
-    fn test_compute_factors() {
-        // Helper function to create factors array
-        fn create_factors() -> Vec<Rc<Poly>> {
-            vec![Rc::new(Poly::Constant(0)); 5]
-        }
-
-        // Test case 1: a^2 + a + 1
-        let poly = Poly::new("a^2 + a + 1").unwrap();
-        let mut factors = create_factors();
-        poly.compute_factors(1, &mut factors);
-        assert_eq!(format!("{}", factors[0]), "1 + a + a^2");
-        assert_eq!(format!("{}", factors[1]), "0");
-        assert_eq!(format!("{}", factors[2]), "0");
-        assert_eq!(format!("{}", factors[3]), "0");
-        assert_eq!(format!("{}", factors[4]), "0");
-
-        // Test case 2: b^2 + b + 1
-        let poly = Poly::new("b^2 + b + 1").unwrap();
-        let mut factors = create_factors();
-        poly.compute_factors(1, &mut factors);
-        assert_eq!(format!("{}", factors[0]), "1");
-        assert_eq!(format!("{}", factors[1]), "1");
-        assert_eq!(format!("{}", factors[2]), "1");
-        assert_eq!(format!("{}", factors[3]), "0");
-        assert_eq!(format!("{}", factors[4]), "0");
-
-        // Test case 3: a*b
-        let poly = Poly::new("a*b").unwrap();
-        let mut factors = create_factors();
-        poly.compute_factors(1, &mut factors);
-        assert_eq!(format!("{}", factors[0]), "0");
-        assert_eq!(format!("{}", factors[1]), "a");
-        assert_eq!(format!("{}", factors[2]), "0");
-        assert_eq!(format!("{}", factors[3]), "0");
-        assert_eq!(format!("{}", factors[4]), "0");
-
-        // Test case 4: a^2*b + a*b^2 + 2*b + 1 + a^3
-        let poly = Poly::new("a^2*b + a*b^2 + 2*b + 1 + a^3").unwrap();
-        let mut factors = create_factors();
-        poly.compute_factors(1, &mut factors);
-        assert_eq!(format!("{}", factors[0]), "1 + a^3");
-        assert_eq!(format!("{}", factors[1]), "2 + a^2");
-        assert_eq!(format!("{}", factors[2]), "a");
-        assert_eq!(format!("{}", factors[3]), "0");
-        assert_eq!(format!("{}", factors[4]), "0");
-
-        // Test case 5: c^2 + c + 1
-        let poly = Poly::new("c^2 + c + 1").unwrap();
-        let mut factors = create_factors();
-        poly.compute_factors(1, &mut factors);
-        assert_eq!(format!("{}", factors[0]), "1 + c + c^2");
-        assert_eq!(format!("{}", factors[1]), "0");
-        assert_eq!(format!("{}", factors[2]), "0");
-        assert_eq!(format!("{}", factors[3]), "0");
-        assert_eq!(format!("{}", factors[4]), "0");
-
-        // Test case 6: b*c
-        let poly = Poly::new("b*c").unwrap();
-        let mut factors = create_factors();
-        poly.compute_factors(1, &mut factors);
-        assert_eq!(format!("{}", factors[0]), "0");
-        assert_eq!(format!("{}", factors[1]), "c");
-        assert_eq!(format!("{}", factors[2]), "0");
-        assert_eq!(format!("{}", factors[3]), "0");
-        assert_eq!(format!("{}", factors[4]), "0");
-
-        // Test case 7: c^2*b + c*b^2 + 2*b + 1 + c^3
-        let poly = Poly::new("c^2*b + c*b^2 + 2*b + 1 + c^3").unwrap();
-        let mut factors = create_factors();
-        poly.compute_factors(1, &mut factors);
-        assert_eq!(format!("{}", factors[0]), "1 + c^3");
-        assert_eq!(format!("{}", factors[1]), "2 + c^2");
-        assert_eq!(format!("{}", factors[2]), "c");
-        assert_eq!(format!("{}", factors[3]), "0");
-        assert_eq!(format!("{}", factors[4]), "0");
-
-        // Test case 8: a*b*c + a^2*b^2*c^2
-        let poly = Poly::new("a*b*c + a^2*b^2*c^2").unwrap();
-        let mut factors = create_factors();
-        poly.compute_factors(1, &mut factors);
-        assert_eq!(format!("{}", factors[0]), "0");
-        assert_eq!(format!("{}", factors[1]), "c*a");
-        assert_eq!(format!("{}", factors[2]), "c^2*a^2");
-        assert_eq!(format!("{}", factors[3]), "0");
-        assert_eq!(format!("{}", factors[4]), "0");
+        // Only the denominators that remain are cleared, not k^degree
+        let poly = Poly::new("a + b^2").unwrap();
+        let sub = Poly::new("2*c").unwrap();
+        let result = poly.substitute_linear(1, Rc::new(sub), 2);
+        assert_eq!(format!("{}", result), "c^2 + a");
     }
 
     #[test]
@@ -1131,6 +1238,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_divide_exact() {
+        let product = Poly::new("a^2 - b^2").unwrap();
+        let divisor = Poly::new("a + b").unwrap();
+        assert_eq!(
+            product.divide_exact(&divisor),
+            Some(Poly::new("a - b").unwrap())
+        );
+        // The divisor only depends on a later variable
+        let product = Poly::new("2*a*c - 2*a*b*c + 6 - 6*b").unwrap();
+        assert_eq!(
+            product.divide_exact(&Poly::new("1 - b").unwrap()),
+            Some(Poly::new("2*a*c + 6").unwrap())
+        );
+        assert_eq!(product.divide_exact(&Poly::Constant(2)), {
+            Some(Poly::new("a*c - a*b*c + 3 - 3*b").unwrap())
+        });
+        assert_eq!(product.divide_exact(&Poly::Constant(4)), None);
+        assert_eq!(product.divide_exact(&Poly::new("a + 1").unwrap()), None);
+        assert_eq!(product.divide_exact(&Poly::Constant(0)), None);
+        assert_eq!(
+            Poly::Constant(0).divide_exact(&divisor),
+            Some(Poly::Constant(0))
+        );
+    }
+
+    #[test]
+    fn test_pseudo_divide() {
+        // Divide by b*c + 1 in c
+        let poly = Poly::new("a*c^2 + c + b").unwrap();
+        let divisor = Poly::new("b*c + 1").unwrap();
+        let (quotient, remainder) = poly.pseudo_divide(&divisor, 2);
+        assert_eq!(remainder.get_degree(2), 0);
+
+        let mut lhs = poly.multiply(&Poly::new("b^2").unwrap());
+        lhs.add_poly_scaled(&quotient.multiply(&divisor), -1);
+        assert_eq!(lhs, remainder);
+        assert_eq!(remainder, Poly::new("a - b + b^3").unwrap());
+
+        // A missing term still counts in the power of the leading coefficient
+        let (quotient, remainder) = Poly::new("c^2 + a").unwrap().pseudo_divide(&divisor, 2);
+        assert_eq!(quotient, Poly::new("b*c - 1").unwrap());
+        assert_eq!(remainder, Poly::new("1 + a*b^2").unwrap());
+    }
+
+    #[test]
+    fn test_content_and_primitive_part() {
+        // (b + 1) * (a^2*c - a)
+        let poly = Poly::new("a^2*b*c + a^2*c - a*b - a").unwrap();
+        assert_eq!(poly.content(0), Poly::new("b + 1").unwrap());
+        assert_eq!(poly.primitive_part(0), Poly::new("a^2*c - a").unwrap());
+        assert_eq!(poly.content(2), Poly::new("a*b + a").unwrap());
+        assert_eq!(poly.primitive_part(2), Poly::new("a*c - 1").unwrap());
+
+        let poly = Poly::new("-6*a*b - 4*b").unwrap();
+        assert_eq!(poly.content(0), Poly::new("-2*b").unwrap());
+        assert_eq!(poly.primitive_part(0), Poly::new("3*a + 2").unwrap());
+        assert_eq!(Poly::Constant(0).primitive_part(0), Poly::Constant(0));
+
+        let gcd = Poly::gcd(
+            &Poly::new("a^2 - b^2").unwrap(),
+            &Poly::new("a^2 + 2*a*b + b^2").unwrap(),
+        );
+        assert!(gcd.is_proportional(&Poly::new("a + b").unwrap(), &mut None));
+        assert_eq!(
+            Poly::gcd(
+                &Poly::new("a*b + a").unwrap(),
+                &Poly::new("b^2 + 3").unwrap()
+            ),
+            Poly::Constant(1)
+        );
+    }
+
     #[test]
     fn test_reduce_by_gcd() {
         // Test case 1: Polynomials with common factor
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+use crate::poly::{Poly, Term};
+
+/// A term as serialized: the coefficient and the degrees of the variables by name,
+/// like {"coefficient": -3, "degrees": {"a": 2, "b1": 1}}
+#[derive(Serialize, Deserialize)]
+struct SerializedTerm {
+    coefficient: i64,
+    degrees: BTreeMap<String, u32>,
+}
+
+/// Poly is serialized as the list of its terms, in the order of `to_terms`
+impl Serialize for Poly {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.to_terms().into_iter().map(|term| {
+            SerializedTerm {
+                coefficient: term.constant,
+                degrees: term
+                    .vars
+                    .into_iter()
+                    .map(|(v, degree)| (Poly::var_to_string(v), degree))
+                    .collect(),
+            }
+        }))
+    }
+}
+
+impl<'de> Deserialize<'de> for Poly {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let terms = Vec::<SerializedTerm>::deserialize(deserializer)?
+            .into_iter()
+            .map(|term| {
+                let vars = term
+                    .degrees
+                    .into_iter()
+                    .filter(|&(_, degree)| degree > 0)
+                    .map(|(name, degree)| {
+                        Poly::parse_var(&name)
+                            .map(|v| (v, degree))
+                            .map_err(serde::de::Error::custom)
+                    })
+                    .collect::<Result<_, D::Error>>()?;
+                Ok(Term {
+                    constant: term.coefficient,
+                    vars,
+                })
+            })
+            .collect::<Result<Vec<_>, D::Error>>()?;
+        Ok(Poly::from_term_list(&terms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_poly_serde() {
+        let poly = Poly::new("3*a^2*b1 - b1 + 7").unwrap();
+        let value = serde_json::to_value(&poly).unwrap();
+        let terms = value.as_array().unwrap();
+        assert_eq!(terms.len(), 3);
+        assert!(terms.contains(&json!({"coefficient": 3, "degrees": {"a": 2, "b1": 1}})));
+        assert!(terms.contains(&json!({"coefficient": 7, "degrees": {}})));
+        let parsed: Poly = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, poly);
+
+        // Like terms are added up, as in the string form
+        let parsed: Poly = serde_json::from_value(json!([
+            {"coefficient": 2, "degrees": {"c": 1}},
+            {"coefficient": -2, "degrees": {"c": 1}},
+            {"coefficient": 5, "degrees": {"a": 0}}
+        ]))
+        .unwrap();
+        assert_eq!(parsed, Poly::Constant(5));
+        assert_eq!(serde_json::to_value(Poly::Constant(0)).unwrap(), json!([]));
+        assert!(serde_json::from_value::<Poly>(json!([
+            {"coefficient": 1, "degrees": {"?": 1}}
+        ]))
+        .is_err());
+    }
+}
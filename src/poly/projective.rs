@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::poly::{Poly, Term, Var};
+
+impl Poly {
+    /// Largest sum of the degrees of the variables in a term
+    pub fn total_degree(&self) -> u32 {
+        self.to_terms()
+            .iter()
+            .map(|term| term.vars.iter().map(|(_, d)| d).sum::<u32>())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The homogeneous polynomial of the same total degree whose terms are the terms of
+    /// self multiplied by powers of z_var, which must not occur in self. Its zeros are
+    /// the projective closure of the zeros of self.
+    pub fn homogenize(&self, z_var: Var) -> Poly {
+        assert!(
+            !self.has_var(z_var),
+            "Homogenizing with {}, which is a variable of {}",
+            Poly::var_to_string(z_var),
+            self
+        );
+        let degree = self.total_degree();
+        let terms: Vec<Term> = self
+            .to_terms()
+            .into_iter()
+            .map(|mut term| {
+                let term_degree: u32 = term.vars.iter().map(|(_, d)| d).sum();
+                if term_degree < degree {
+                    term.vars.push((z_var, degree - term_degree));
+                }
+                term
+            })
+            .collect();
+        Poly::from_term_list(&terms)
+    }
+
+    /// The polynomial with z_var set to 1, the inverse of `homogenize` for polynomials
+    /// not divisible by z_var
+    pub fn dehomogenize(&self, z_var: Var) -> Poly {
+        self.substitute_values(&HashMap::from([(z_var, 1)]))
+    }
+
+    /// The sum of the terms of the highest total degree
+    pub fn leading_form(&self) -> Poly {
+        let degree = self.total_degree();
+        let terms: Vec<Term> = self
+            .to_terms()
+            .into_iter()
+            .filter(|term| term.vars.iter().map(|(_, d)| d).sum::<u32>() == degree)
+            .collect();
+        Poly::from_term_list(&terms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_homogenize() {
+        let poly = Poly::new("a^3 - a*b + 2*b - 5").unwrap();
+        assert_eq!(poly.total_degree(), 3);
+        let homogeneous = poly.homogenize(2);
+        assert_eq!(
+            homogeneous,
+            Poly::new("a^3 - a*b*c + 2*b*c^2 - 5*c^3").unwrap()
+        );
+        assert_eq!(homogeneous.dehomogenize(2), poly);
+        assert_eq!(poly.leading_form(), Poly::new("a^3").unwrap());
+        assert_eq!(Poly::Constant(4).homogenize(2), Poly::Constant(4));
+    }
+}
@@ -0,0 +1,335 @@
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::cas;
+use crate::poly::{ParseError, Poly, PolyError, PolyOperations};
+
+/// A quotient of polynomials num / den, kept in lowest terms with a positive leading
+/// coefficient of den. Used where constraints involve division, like slopes; the
+/// constraint num / den = 0 is cleared to num = 0 by `into_constraint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RatFunc {
+    num: Poly,
+    den: Poly,
+}
+
+impl RatFunc {
+    /// num / den in lowest terms. The common factor of non-constant polynomials is
+    /// found by the selected CAS backend, or natively if that fails.
+    pub fn new(num: Poly, den: Poly) -> Result<Self, PolyError> {
+        if den == Poly::Constant(0) {
+            return Err(PolyError::DivisionByZero);
+        }
+        if num == Poly::Constant(0) {
+            return Ok(RatFunc::from(num));
+        }
+        let gcd = match (&num, &den) {
+            (Poly::Nested(..), Poly::Nested(..)) => cas::backend()
+                .gcd(&num, &den)
+                .unwrap_or_else(|_| Poly::gcd(&num, &den)),
+            _ => Poly::gcd(&num, &den),
+        };
+        let (mut num, mut den) = match (num.divide_exact(&gcd), den.divide_exact(&gcd)) {
+            (Some(num), Some(den)) => (num, den),
+            _ => (num, den),
+        };
+        if den.leading_coefficient() < 0 {
+            num = -num;
+            den = -den;
+        }
+        Ok(RatFunc { num, den })
+    }
+
+    pub fn numerator(&self) -> &Poly {
+        &self.num
+    }
+
+    pub fn denominator(&self) -> &Poly {
+        &self.den
+    }
+
+    /// The polynomial equation equivalent to self = 0 where the denominator doesn't
+    /// vanish: the numerator
+    pub fn into_constraint(self) -> Poly {
+        self.num
+    }
+
+    /// self / other, failing if other is zero
+    pub fn divide(&self, other: &RatFunc) -> Result<RatFunc, PolyError> {
+        RatFunc::new(&self.num * &other.den, &self.den * &other.num)
+    }
+
+    /// Parses an expression built from polynomials with +, -, *, / and integer powers,
+    /// like "(a^2 - b)/(c + 1) - 2*a/c"
+    pub fn parse(s: &str) -> Result<RatFunc, ParseError> {
+        let mut parser = Parser {
+            source: s,
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+        let result = parser.parse_sum()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(result),
+            Some(token) => Err(parser.error(&format!("unexpected '{}'", token))),
+        }
+    }
+}
+
+impl From<Poly> for RatFunc {
+    fn from(num: Poly) -> Self {
+        RatFunc {
+            num,
+            den: Poly::Constant(1),
+        }
+    }
+}
+
+impl fmt::Display for RatFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == Poly::Constant(1) {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "({})/({})", self.num, self.den)
+        }
+    }
+}
+
+impl Add<&RatFunc> for &RatFunc {
+    type Output = RatFunc;
+
+    fn add(self, other: &RatFunc) -> RatFunc {
+        let num = &(&self.num * &other.den) + &(&other.num * &self.den);
+        RatFunc::new(num, &self.den * &other.den).expect("Denominators are non-zero")
+    }
+}
+
+impl Sub<&RatFunc> for &RatFunc {
+    type Output = RatFunc;
+
+    fn sub(self, other: &RatFunc) -> RatFunc {
+        self + &-other
+    }
+}
+
+impl Mul<&RatFunc> for &RatFunc {
+    type Output = RatFunc;
+
+    fn mul(self, other: &RatFunc) -> RatFunc {
+        RatFunc::new(&self.num * &other.num, &self.den * &other.den)
+            .expect("Denominators are non-zero")
+    }
+}
+
+impl Neg for &RatFunc {
+    type Output = RatFunc;
+
+    fn neg(self) -> RatFunc {
+        RatFunc {
+            num: -&self.num,
+            den: self.den.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Var(String),
+    Op(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Var(name) => write!(f, "{}", name),
+            Token::Op(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                digits.push(d);
+                chars.next();
+            }
+            let n = digits
+                .parse()
+                .map_err(|_| ParseError::InvalidTerm(format!("Constant too large in '{}'", s)))?;
+            tokens.push(Token::Number(n));
+        } else if c.is_ascii_alphabetic() {
+            let mut name = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_alphanumeric()) {
+                name.push(d);
+                chars.next();
+            }
+            tokens.push(Token::Var(name));
+        } else if "+-*/^()".contains(c) {
+            tokens.push(Token::Op(c));
+            chars.next();
+        } else {
+            return Err(ParseError::InvalidTerm(format!(
+                "Unexpected '{}' in '{}'",
+                c, s
+            )));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::InvalidTerm(format!("{} in '{}'", message, self.source))
+    }
+
+    fn next_op(&mut self, ops: &str) -> Option<char> {
+        match self.tokens.get(self.position) {
+            Some(&Token::Op(c)) if ops.contains(c) => {
+                self.position += 1;
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_sum(&mut self) -> Result<RatFunc, ParseError> {
+        let mut result = self.parse_product()?;
+        while let Some(op) = self.next_op("+-") {
+            let term = self.parse_product()?;
+            result = if op == '+' {
+                &result + &term
+            } else {
+                &result - &term
+            };
+        }
+        Ok(result)
+    }
+
+    fn parse_product(&mut self) -> Result<RatFunc, ParseError> {
+        let mut result = self.parse_unary()?;
+        while let Some(op) = self.next_op("*/") {
+            let factor = self.parse_unary()?;
+            result = if op == '*' {
+                &result * &factor
+            } else {
+                result
+                    .divide(&factor)
+                    .map_err(|e| self.error(&e.to_string()))?
+            };
+        }
+        Ok(result)
+    }
+
+    fn parse_unary(&mut self) -> Result<RatFunc, ParseError> {
+        match self.next_op("-+") {
+            Some('-') => Ok(-&self.parse_unary()?),
+            Some(_) => self.parse_unary(),
+            None => self.parse_power(),
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<RatFunc, ParseError> {
+        let base = self.parse_atom()?;
+        if self.next_op("^").is_none() {
+            return Ok(base);
+        }
+        let exponent = match self.tokens.get(self.position) {
+            Some(&Token::Number(n)) => {
+                u32::try_from(n).map_err(|_| self.error("power too large"))?
+            }
+            _ => return Err(self.error("expected an integer power")),
+        };
+        self.position += 1;
+        Ok(RatFunc {
+            num: base.num.pow(exponent),
+            den: base.den.pow(exponent),
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<RatFunc, ParseError> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Number(n)) => Ok(RatFunc::from(Poly::Constant(n))),
+            Some(Token::Var(name)) => Ok(RatFunc::from(Poly::new(&name)?)),
+            Some(Token::Op('(')) => {
+                let result = self.parse_sum()?;
+                match self.next_op(")") {
+                    Some(_) => Ok(result),
+                    None => Err(self.error("missing ')'")),
+                }
+            }
+            Some(token) => Err(self.error(&format!("unexpected '{}'", token))),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly(s: &str) -> Poly {
+        Poly::new(s).unwrap()
+    }
+
+    #[test]
+    fn test_rat_func() {
+        // (a^2 - b^2) / (2*b - 2*a) = -(a + b) / 2
+        let f = RatFunc::new(poly("a^2 - b^2"), poly("2*b - 2*a")).unwrap();
+        assert_eq!(f.numerator(), &poly("-a - b"));
+        assert_eq!(f.denominator(), &poly("2"));
+        assert_eq!(
+            RatFunc::new(poly("a"), Poly::Constant(0)),
+            Err(PolyError::DivisionByZero)
+        );
+
+        let g = RatFunc::new(poly("1"), poly("a - b")).unwrap();
+        let sum = &f + &g;
+        assert_eq!(sum.denominator(), &poly("2*a - 2*b"));
+        assert_eq!(sum.numerator(), &poly("2 - a^2 + b^2"));
+        assert_eq!(&(&sum - &g), &f);
+        assert_eq!(
+            &f * &g,
+            RatFunc::new(poly("a + b"), poly("2*b - 2*a")).unwrap()
+        );
+        assert_eq!(
+            f.divide(&RatFunc::from(poly("a + b"))).unwrap(),
+            RatFunc::from(poly("-1"))
+                .divide(&RatFunc::from(poly("2")))
+                .unwrap()
+        );
+        assert_eq!(f.to_string(), "(-b - a)/(2)");
+    }
+
+    #[test]
+    fn test_parse() {
+        let f = RatFunc::parse("(a^2 - b^2)/(a - b) - 2*(a + 1)").unwrap();
+        assert_eq!(f, RatFunc::from(poly("b - a - 2")));
+
+        // The slope of the line through (0, 0) and (a, b) equals 3
+        let f = RatFunc::parse("b/a - 3").unwrap();
+        assert_eq!(f.into_constraint(), poly("b - 3*a"));
+
+        let f = RatFunc::parse("-(1/(a + 1))^2").unwrap();
+        assert_eq!(f.numerator(), &poly("-1"));
+        assert_eq!(f.denominator(), &poly("a^2 + 2*a + 1"));
+
+        for invalid in ["a/(b - b)", "(a + b", "a * * b", "a^b", "a % 2", ""] {
+            assert!(RatFunc::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use gcd::Gcd;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::poly::{checked, Poly, Term, Var};
+
+/// An exact fraction num / den with den > 0 and gcd(num, den) = 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "Rational with zero denominator");
+        let g = (num.unsigned_abs().gcd(den.unsigned_abs()) as i128).max(1);
+        let sign = den.signum();
+        Rational {
+            num: sign * num / g,
+            den: sign * den / g,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Rational { num: 0, den: 1 }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Self {
+        Rational {
+            num: n as i128,
+            den: 1,
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, other: Rational) -> Rational {
+        let num1 = checked(self.num.overflowing_mul(other.den));
+        let num2 = checked(other.num.overflowing_mul(self.den));
+        Rational::new(
+            checked(num1.overflowing_add(num2)),
+            checked(self.den.overflowing_mul(other.den)),
+        )
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, other: Rational) -> Rational {
+        self + (-other)
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, other: Rational) -> Rational {
+        Rational::new(
+            checked(self.num.overflowing_mul(other.num)),
+            checked(self.den.overflowing_mul(other.den)),
+        )
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    fn div(self, other: Rational) -> Rational {
+        Rational::new(
+            checked(self.num.overflowing_mul(other.den)),
+            checked(self.den.overflowing_mul(other.num)),
+        )
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+/// A monomial as (variable index, degree) pairs sorted by variable index
+type Monomial = Vec<(Var, u32)>;
+
+/// A multivariate polynomial with rational coefficients, stored as a map from
+/// monomials to non-zero coefficients. Used to carry out substitutions and
+/// divisions exactly; `to_polys` clears the denominators at the end.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RatPoly {
+    terms: BTreeMap<Monomial, Rational>,
+}
+
+impl RatPoly {
+    pub fn zero() -> Self {
+        RatPoly::default()
+    }
+
+    pub fn constant(c: Rational) -> Self {
+        let mut poly = RatPoly::zero();
+        poly.add_term(Vec::new(), c);
+        poly
+    }
+
+    pub fn from_poly(poly: &Poly) -> Self {
+        let mut result = RatPoly::zero();
+        for term in poly.to_terms() {
+            let mut monomial = term.vars;
+            monomial.sort();
+            result.add_term(monomial, Rational::from(term.constant));
+        }
+        result
+    }
+
+    fn add_term(&mut self, monomial: Monomial, c: Rational) {
+        let sum = self
+            .terms
+            .get(&monomial)
+            .copied()
+            .unwrap_or_else(Rational::zero)
+            + c;
+        if sum.is_zero() {
+            self.terms.remove(&monomial);
+        } else {
+            self.terms.insert(monomial, sum);
+        }
+    }
+
+    /// Adds poly * factor to self
+    pub fn add_scaled(&mut self, poly: &RatPoly, factor: Rational) {
+        for (monomial, c) in &poly.terms {
+            self.add_term(monomial.clone(), *c * factor);
+        }
+    }
+
+    pub fn scale(&mut self, factor: Rational) {
+        if factor.is_zero() {
+            self.terms.clear();
+        } else {
+            for c in self.terms.values_mut() {
+                *c = *c * factor;
+            }
+        }
+    }
+
+    pub fn multiply(&self, other: &RatPoly) -> RatPoly {
+        let mut result = RatPoly::zero();
+        for (m1, c1) in &self.terms {
+            for (m2, c2) in &other.terms {
+                let mut monomial: BTreeMap<Var, u32> = m1.iter().copied().collect();
+                for &(v, d) in m2 {
+                    *monomial.entry(v).or_insert(0) += d;
+                }
+                result.add_term(monomial.into_iter().collect(), *c1 * *c2);
+            }
+        }
+        result
+    }
+
+    /// Replaces the variable v with `value`
+    pub fn substitute(&self, v: Var, value: &RatPoly) -> RatPoly {
+        // Group the terms by the degree of v
+        let mut by_degree: Vec<RatPoly> = Vec::new();
+        for (monomial, c) in &self.terms {
+            let degree = monomial
+                .iter()
+                .find(|(var, _)| *var == v)
+                .map_or(0, |(_, d)| *d as usize);
+            while by_degree.len() <= degree {
+                by_degree.push(RatPoly::zero());
+            }
+            let rest = monomial.iter().filter(|(var, _)| *var != v).copied();
+            by_degree[degree].add_term(rest.collect(), *c);
+        }
+
+        // Horner's scheme
+        let mut result = RatPoly::zero();
+        for coefficient in by_degree.iter().rev() {
+            result = result.multiply(value);
+            result.add_scaled(coefficient, Rational::from(1));
+        }
+        result
+    }
+
+    /// Converts polynomials to integer polynomials, multiplying all of them by the least
+    /// common multiple of their denominators. The results are proportional to the inputs
+    /// with one common factor, so relations between them are preserved.
+    pub fn to_polys(polys: &[RatPoly]) -> Vec<Poly> {
+        let lcm = polys
+            .iter()
+            .flat_map(|poly| poly.terms.values())
+            .fold(1i128, |lcm, c| {
+                let gcd = lcm.unsigned_abs().gcd(c.den.unsigned_abs()) as i128;
+                checked((lcm / gcd).overflowing_mul(c.den))
+            });
+        polys
+            .iter()
+            .map(|poly| {
+                let terms: Vec<Term> = poly
+                    .terms
+                    .iter()
+                    .map(|(monomial, c)| {
+                        let constant = checked(c.num.overflowing_mul(lcm / c.den));
+                        Term {
+                            constant: checked((constant as i64, i64::try_from(constant).is_err())),
+                            vars: monomial.clone(),
+                        }
+                    })
+                    .collect();
+                Poly::from_term_list(&terms)
+            })
+            .collect()
+    }
+
+    pub fn to_poly(&self) -> Poly {
+        Self::to_polys(std::slice::from_ref(self)).remove(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rational_arithmetic() {
+        let half = Rational::new(1, 2);
+        let third = Rational::new(-2, -6);
+        assert_eq!(third, Rational::new(1, 3));
+        assert_eq!(half + third, Rational::new(5, 6));
+        assert_eq!(half - third, Rational::new(1, 6));
+        assert_eq!(half * third, Rational::new(1, 6));
+        assert_eq!(half / third, Rational::new(3, 2));
+        assert_eq!(Rational::new(3, -4).to_string(), "-3/4");
+        assert_eq!(Rational::new(4, 2).to_string(), "2");
+    }
+
+    #[test]
+    fn test_substitute_and_clear_denominators() {
+        // a^2 + b with a = (b + 1) / 2 gives (b^2 + 6*b + 1) / 4
+        let poly = RatPoly::from_poly(&Poly::new("a^2 + b").unwrap());
+        let mut value = RatPoly::from_poly(&Poly::new("b + 1").unwrap());
+        value.scale(Rational::new(1, 2));
+        let result = poly.substitute(0, &value);
+        assert_eq!(result.to_poly(), Poly::new("b^2 + 6*b + 1").unwrap());
+
+        // Common denominators keep the ratio between the polynomials
+        let p1 = RatPoly::constant(Rational::new(1, 2));
+        let mut p2 = RatPoly::from_poly(&Poly::new("a").unwrap());
+        p2.scale(Rational::new(2, 3));
+        assert_eq!(
+            RatPoly::to_polys(&[p1, p2]),
+            vec![Poly::new("3").unwrap(), Poly::new("4*a").unwrap()]
+        );
+        assert!(RatPoly::zero().to_poly() == Poly::Constant(0));
+    }
+}
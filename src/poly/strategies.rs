@@ -0,0 +1,162 @@
+//! Proptest strategies generating random polynomials, and naive reference versions of
+//! some operations, for property-based tests of `PolyOperations` and the code built
+//! on it. They are compiled for the tests of this crate, and for other crates with
+//! the `proptest` feature.
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::rc::Rc;
+
+use super::{Poly, PolyOperations, Term, Var};
+
+/// Bounds of the generated polynomials. The defaults keep the coefficients of
+/// products of a few polynomials far from overflowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolyParams {
+    /// The polynomials are in the variables 0..vars, i.e. a, b, ...
+    pub vars: Var,
+    /// Largest degree in each variable
+    pub max_degree: u32,
+    pub max_terms: usize,
+    /// Largest absolute value of a coefficient
+    pub max_coefficient: i64,
+}
+
+impl Default for PolyParams {
+    fn default() -> Self {
+        Self {
+            vars: 3,
+            max_degree: 3,
+            max_terms: 6,
+            max_coefficient: 20,
+        }
+    }
+}
+
+/// A term with a coefficient and degrees within `params`; the coefficient may be 0
+pub fn term(params: PolyParams) -> impl Strategy<Value = Term> {
+    (
+        -params.max_coefficient..=params.max_coefficient,
+        vec(0..=params.max_degree, params.vars as usize),
+    )
+        .prop_map(|(constant, degrees)| Term {
+            constant,
+            vars: (0..)
+                .zip(degrees)
+                .filter(|&(_, degree)| degree > 0)
+                .collect(),
+        })
+}
+
+/// A sum of up to `params.max_terms` terms, which shrinks by dropping terms
+pub fn poly_with(params: PolyParams) -> impl Strategy<Value = Poly> {
+    vec(term(params), 0..=params.max_terms).prop_map(|terms| Poly::from_term_list(&terms))
+}
+
+/// A polynomial within the default `PolyParams`
+pub fn poly() -> impl Strategy<Value = Poly> {
+    poly_with(PolyParams::default())
+}
+
+/// One of the variables of the polynomials generated with `params`
+pub fn var(params: PolyParams) -> impl Strategy<Value = Var> {
+    0..params.vars
+}
+
+/// The polynomial v^degree
+pub fn var_power(v: Var, degree: u32) -> Poly {
+    Poly::from_term_list(&[Term {
+        constant: 1,
+        vars: vec![(v, degree)],
+    }])
+}
+
+/// The polynomial Sum projections[i] * v^i, the inverse of `PolyOperations::decompose`
+pub fn recompose(projections: &[Rc<Poly>], v: Var) -> Poly {
+    let mut result = Poly::Constant(0);
+    for (i, projection) in projections.iter().enumerate() {
+        result.add_poly_scaled(&projection.multiply(&var_power(v, i as u32)), 1);
+    }
+    result
+}
+
+/// `PolyOperations::substitute_linear` by expanding each term: poly with v replaced
+/// by value / k, times k^d for the degree d of poly in v. `substitute_linear` may
+/// clear fewer denominators, so the results are equal up to a constant factor.
+pub fn substitute_linear_naive(poly: &Poly, v: Var, value: &Poly, k: i64) -> Poly {
+    let degree = poly.get_degree(v);
+    let mut result = Poly::Constant(0);
+    for mut term in poly.to_terms() {
+        let power = term
+            .vars
+            .iter()
+            .find(|(var, _)| *var == v)
+            .map_or(0, |&(_, d)| d);
+        term.vars.retain(|(var, _)| *var != v);
+        term.constant *= k.pow(degree - power);
+        let mut expanded = Poly::from_term_list(&[term]);
+        for _ in 0..power {
+            expanded = expanded.multiply(value);
+        }
+        result.add_poly_scaled(&expanded, 1);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum(a: &Poly, b: &Poly) -> Poly {
+        let mut result = a.clone();
+        result.add_poly_scaled(b, 1);
+        result
+    }
+
+    proptest! {
+        #[test]
+        fn test_ring_axioms(a in poly(), b in poly(), c in poly()) {
+            prop_assert_eq!(sum(&a, &b), sum(&b, &a));
+            prop_assert_eq!(sum(&sum(&a, &b), &c), sum(&a, &sum(&b, &c)));
+            prop_assert_eq!(a.multiply(&b), b.multiply(&a));
+            prop_assert_eq!(a.multiply(&b).multiply(&c), a.multiply(&b.multiply(&c)));
+            prop_assert_eq!(a.multiply(&sum(&b, &c)), sum(&a.multiply(&b), &a.multiply(&c)));
+
+            prop_assert_eq!(&sum(&a, &Poly::Constant(0)), &a);
+            prop_assert_eq!(&a.multiply(&Poly::Constant(1)), &a);
+            prop_assert_eq!(a.multiply(&Poly::Constant(0)), Poly::Constant(0));
+            let mut difference = a.clone();
+            difference.add_poly_scaled(&a, -1);
+            prop_assert_eq!(difference, Poly::Constant(0));
+        }
+
+        #[test]
+        fn test_substitute_linear(
+            p in poly(),
+            v in var(PolyParams::default()),
+            value in poly_with(PolyParams {
+                max_degree: 1,
+                max_terms: 3,
+                max_coefficient: 5,
+                ..PolyParams::default()
+            }),
+            k in prop_oneof![-3i64..=-1, 1i64..=3],
+        ) {
+            let result = p.substitute_linear(v, Rc::new(value.clone()), k);
+            let naive = substitute_linear_naive(&p, v, &value, k);
+            prop_assert!(
+                result.is_proportional(&naive, &mut None),
+                "{} and {} differ by more than a factor",
+                result,
+                naive
+            );
+        }
+
+        #[test]
+        fn test_decompose_round_trip(p in poly(), v in var(PolyParams::default())) {
+            let projections = Rc::new(p.clone()).decompose(v);
+            prop_assert!(projections.iter().all(|projection| !projection.has_var(v)));
+            prop_assert_eq!(recompose(&projections, v), p);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+
+use crate::poly::{Poly, Var};
+
+/// A set of variable indices, stored as a bitset growing with the largest variable.
+/// Trailing zero words are dropped, so that equal sets have equal representations.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct VarSet(Vec<u64>);
+
+impl VarSet {
+    pub fn new() -> Self {
+        VarSet(Vec::new())
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    pub fn insert(&mut self, v: Var) {
+        let index = (v >> 6) as usize;
+        if index >= self.0.len() {
+            self.0.resize(index + 1, 0);
+        }
+        self.0[index] |= 1 << (v & 63);
+    }
+
+    pub fn remove(&mut self, v: Var) {
+        if let Some(word) = self.0.get_mut((v >> 6) as usize) {
+            *word &= !(1 << (v & 63));
+            self.trim();
+        }
+    }
+
+    pub fn contains(&self, v: Var) -> bool {
+        self.0
+            .get((v >> 6) as usize)
+            .is_some_and(|word| word & (1 << (v & 63)) != 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn union(&self, other: &VarSet) -> VarSet {
+        let mut result = self.clone();
+        result.union_with(other);
+        result
+    }
+
+    pub fn union_with(&mut self, other: &VarSet) {
+        if other.0.len() > self.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (word, other_word) in self.0.iter_mut().zip(other.0.iter()) {
+            *word |= other_word;
+        }
+    }
+
+    pub fn intersection(&self, other: &VarSet) -> VarSet {
+        let mut result = VarSet(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(word, other_word)| word & other_word)
+                .collect(),
+        );
+        result.trim();
+        result
+    }
+
+    pub fn difference(&self, other: &VarSet) -> VarSet {
+        let mut result = self.clone();
+        for (word, other_word) in result.0.iter_mut().zip(other.0.iter()) {
+            *word &= !other_word;
+        }
+        result.trim();
+        result
+    }
+
+    pub fn intersects(&self, other: &VarSet) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .any(|(word, other_word)| word & other_word != 0)
+    }
+
+    /// The smallest variable in the set
+    pub fn first(&self) -> Option<Var> {
+        self.iter().next()
+    }
+
+    /// Iterates over the variables in increasing order
+    pub fn iter(&self) -> VarSetIter<'_> {
+        VarSetIter {
+            words: &self.0,
+            index: 0,
+            word: self.0.first().copied().unwrap_or(0),
+        }
+    }
+}
+
+pub struct VarSetIter<'a> {
+    words: &'a [u64],
+    index: usize,
+    /// The bits of the word at `index` not returned yet
+    word: u64,
+}
+
+impl Iterator for VarSetIter<'_> {
+    type Item = Var;
+
+    fn next(&mut self) -> Option<Var> {
+        while self.index < self.words.len() {
+            if self.word != 0 {
+                let bit = self.word.trailing_zeros() as usize;
+                self.word &= self.word - 1;
+                return Some((self.index * 64 + bit) as Var);
+            }
+            self.index += 1;
+            self.word = self.words.get(self.index).copied().unwrap_or(0);
+        }
+        None
+    }
+}
+
+impl FromIterator<Var> for VarSet {
+    fn from_iter<I: IntoIterator<Item = Var>>(iter: I) -> Self {
+        let mut result = VarSet::new();
+        for v in iter {
+            result.insert(v);
+        }
+        result
+    }
+}
+
+impl fmt::Debug for VarSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.iter().map(Poly::var_to_string))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_var_set_operations() {
+        let mut set = VarSet::new();
+        assert!(set.is_empty());
+        set.insert(0);
+        set.insert(63);
+        set.insert(64);
+        set.insert(255);
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(63) && set.contains(64) && set.contains(255));
+        assert!(!set.contains(1));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 63, 64, 255]);
+        assert_eq!(set.first(), Some(0));
+
+        set.remove(0);
+        assert_eq!(set.first(), Some(63));
+
+        let other: VarSet = [1, 64, 200].into_iter().collect();
+        assert!(set.intersects(&other));
+        assert_eq!(
+            set.intersection(&other).iter().collect::<Vec<_>>(),
+            vec![64]
+        );
+        assert_eq!(
+            set.union(&other).iter().collect::<Vec<_>>(),
+            vec![1, 63, 64, 200, 255]
+        );
+        assert_eq!(
+            set.difference(&other).iter().collect::<Vec<_>>(),
+            vec![63, 255]
+        );
+        assert_eq!(format!("{:?}", other), "{\"b\", \"m2\", \"s7\"}");
+
+        // Sets grow past 256 variables, and shrink back
+        let mut large: VarSet = [3, 1000].into_iter().collect();
+        assert_eq!(large.iter().collect::<Vec<_>>(), vec![3, 1000]);
+        assert!(!large.contains(999) && !large.contains(5000));
+        large.remove(1000);
+        assert_eq!(large, [3].into_iter().collect());
+        assert_eq!(large.union(&set).len(), 4);
+        assert!(large.intersection(&set).is_empty());
+    }
+}
@@ -1,11 +1,43 @@
 use log::info;
 use serde::{Deserialize, Serialize};
 
+use crate::dd_int::{DDInt, DDXYPoly};
 use crate::fint::FInt;
-use crate::x_poly::XYPoly;
-use std::collections::HashMap;
+use crate::metrics;
+use crate::x_poly::{SignChange, SturmSequence, XYPoly};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+/// Regions (and chunks of curve points) up to this size are processed on one thread
+const PARALLEL_MIN_CELLS: u32 = 4096;
+
+/// The most threads a drawing may use, one per available core
+pub fn max_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// The pool of `threads` threads shared by the drawings using that many, created on
+/// first use. There is at most one pool per size up to `max_threads`.
+fn shared_pool(threads: usize) -> Option<&'static ThreadPool> {
+    static POOLS: OnceLock<Mutex<HashMap<usize, Option<&'static ThreadPool>>>> = OnceLock::new();
+    let mut pools = POOLS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    *pools.entry(threads).or_insert_with(|| {
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(move |i| format!("poly-draw-{}-{}", threads, i))
+            .build()
+            .ok()
+            .map(|pool| &*Box::leak(Box::new(pool)))
+    })
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Rectangle {
@@ -38,11 +70,92 @@ impl Rectangle {
 
 pub struct XYPolyDraw {
     pub xy_poly: XYPoly,
+    /// Exact version of `xy_poly` evaluated with double-double intervals where
+    /// f64 intervals can't tell the sign, see `with_double_double`
+    dd_poly: Option<DDXYPoly>,
+    /// How many times an undecided grid cell may be split, see `with_refinement`
+    max_refinement_depth: u32,
+    /// Whether undecided cells count the crossings of the curve with their vertical
+    /// edges exactly, see `with_column_crossings`
+    column_crossings: bool,
+    /// Pool running the rasterization in parallel, see `with_threads`
+    thread_pool: Option<&'static ThreadPool>,
 }
 
 impl XYPolyDraw {
     pub fn new(xy_poly: XYPoly) -> Self {
-        XYPolyDraw { xy_poly }
+        XYPolyDraw {
+            xy_poly,
+            dd_poly: None,
+            max_refinement_depth: 0,
+            column_crossings: false,
+            thread_pool: None,
+        }
+    }
+
+    /// Evaluates the regions of the view and the smoothing in parallel on `threads`
+    /// threads, at most `max_threads`, of a pool shared with the drawings using as many.
+    /// With one thread (or if the pool can't be created), everything runs on the caller's
+    /// thread.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        let threads = threads.min(max_threads());
+        self.thread_pool = if threads > 1 {
+            shared_pool(threads)
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Splits grid cells whose corner and center samples don't show a sign change
+    /// into quadrants, up to `max_depth` times. This finds thin features (small ovals,
+    /// nearby branches) that pass between the samples, and drops cells that the
+    /// interval evaluation of the quadrants excludes.
+    pub fn with_refinement(mut self, max_depth: u32) -> Self {
+        self.max_refinement_depth = max_depth;
+        self
+    }
+
+    /// Counts the roots of p(x, y) in y on the vertical edges of the cells without a sign
+    /// change at their samples, with Sturm sequences. A cell is then on the curve if the
+    /// curve crosses one of its edges, even when it enters and leaves the cell through the
+    /// same edge, like next to a vertical tangent, where the 2D interval tests see no sign
+    /// change.
+    pub fn with_column_crossings(mut self) -> Self {
+        self.column_crossings = true;
+        self
+    }
+
+    /// Rechecks the regions and cells that f64 intervals keep with double-double
+    /// intervals, so that high-degree curves with huge coefficients are not drawn
+    /// as thick bands of false curve cells
+    pub fn with_double_double(mut self, dd_poly: DDXYPoly) -> Self {
+        self.dd_poly = Some(dd_poly);
+        self
+    }
+
+    fn may_vanish(&self, x_interval: FInt, y_interval: FInt) -> bool {
+        if self.xy_poly.evaluate(x_interval, y_interval) != FInt::new(0.0) {
+            return false;
+        }
+        match &self.dd_poly {
+            Some(dd_poly) => dd_poly
+                .evaluate(DDInt::from_fint(x_interval), DDInt::from_fint(y_interval))
+                .contains_zero(),
+            None => true,
+        }
+    }
+
+    fn sign_change(&self, x_interval: FInt, y_interval: FInt) -> SignChange {
+        let sign_change = self
+            .xy_poly
+            .sign_change_at_corners_and_center(x_interval, y_interval);
+        match (sign_change, &self.dd_poly) {
+            (SignChange::Likely, Some(dd_poly)) => {
+                dd_poly.sign_change_at_corners_and_center(x_interval, y_interval)
+            }
+            _ => sign_change,
+        }
     }
 
     pub fn get_curve_points(
@@ -52,14 +165,59 @@ impl XYPolyDraw {
         x_count: u32,
         y_count: u32,
     ) -> Vec<(u32, u32)> {
+        self.get_classified_curve_points(x_interval, y_interval, x_count, y_count)
+            .into_iter()
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    /// Like `get_curve_points`, but also tells whether the curve provably passes through each cell
+    pub fn get_classified_curve_points(
+        &self,
+        x_interval: FInt,
+        y_interval: FInt,
+        x_count: u32,
+        y_count: u32,
+    ) -> Vec<(u32, u32, Certainty)> {
+        let _timer = metrics::CURVE_DRAWING.start();
+        let rect = Rectangle::new(0, 0, x_count, y_count);
+        match &self.thread_pool {
+            Some(pool) => {
+                pool.install(|| self.inspect_region_parallel(x_interval, y_interval, rect, y_count))
+            }
+            None => {
+                let mut points = Vec::new();
+                self.inspect_region(x_interval, y_interval, rect, &mut points, y_count);
+                points
+            }
+        }
+    }
+
+    /// Same as `inspect_region`, with the quadrants of large regions inspected in parallel.
+    /// The points come out in the same order.
+    fn inspect_region_parallel(
+        &self,
+        x_interval: FInt,
+        y_interval: FInt,
+        rect: Rectangle,
+        y_count: u32,
+    ) -> Vec<(u32, u32, Certainty)> {
         let mut points = Vec::new();
-        self.inspect_region(
-            x_interval,
-            y_interval,
-            Rectangle::new(0, 0, x_count, y_count),
-            &mut points,
-            y_count,
-        );
+        if rect.size() <= PARALLEL_MIN_CELLS {
+            self.inspect_region(x_interval, y_interval, rect, &mut points, y_count);
+        } else if self.may_vanish(x_interval, y_interval) {
+            let quadrants: Vec<Vec<(u32, u32, Certainty)>> = rect
+                .subdivide()
+                .into_par_iter()
+                .filter(|sub_rect| sub_rect.size() >= 1)
+                .map(|sub_rect| {
+                    let (sub_x, sub_y) =
+                        FInt::get_subinterval(x_interval, y_interval, rect, sub_rect);
+                    self.inspect_region_parallel(sub_x, sub_y, sub_rect, y_count)
+                })
+                .collect();
+            points = quadrants.concat();
+        }
         points
     }
 
@@ -68,18 +226,14 @@ impl XYPolyDraw {
         x_interval: FInt,
         y_interval: FInt,
         rect: Rectangle,
-        points: &mut Vec<(u32, u32)>,
+        points: &mut Vec<(u32, u32, Certainty)>,
         y_count: u32,
     ) {
         // Evaluate polynomial for the region
-        let value = self.xy_poly.evaluate(x_interval, y_interval);
-        if value == FInt::new(0.0) {
+        if self.may_vanish(x_interval, y_interval) {
             if rect.size() == 1 {
-                if self
-                    .xy_poly
-                    .likely_contains_zero_check_corners_and_center(x_interval, y_interval)
-                {
-                    points.push((rect.x0, y_count - rect.y0 - 1));
+                if let Some(certainty) = self.classify_cell(x_interval, y_interval) {
+                    points.push((rect.x0, y_count - rect.y0 - 1, certainty));
                 }
             } else {
                 // Subdivide the region
@@ -94,19 +248,225 @@ impl XYPolyDraw {
         }
     }
 
+    /// Finds the cells of several curves, e.g. the factors of a reducible curve, in one
+    /// subdivision of the view: a region is split while any of the curves may pass
+    /// through it. Returns the cells of each curve, in the order of `drawers`.
+    pub fn get_curve_points_of_each(
+        drawers: &[XYPolyDraw],
+        x_interval: FInt,
+        y_interval: FInt,
+        x_count: u32,
+        y_count: u32,
+    ) -> Vec<Vec<(u32, u32)>> {
+        let _timer = metrics::CURVE_DRAWING.start();
+        let mut points = vec![Vec::new(); drawers.len()];
+        let all: Vec<usize> = (0..drawers.len()).collect();
+        let rect = Rectangle::new(0, 0, x_count, y_count);
+        Self::inspect_region_of_each(
+            drawers,
+            &all,
+            x_interval,
+            y_interval,
+            rect,
+            &mut points,
+            y_count,
+        );
+        points
+    }
+
+    fn inspect_region_of_each(
+        drawers: &[XYPolyDraw],
+        candidates: &[usize],
+        x_interval: FInt,
+        y_interval: FInt,
+        rect: Rectangle,
+        points: &mut [Vec<(u32, u32)>],
+        y_count: u32,
+    ) {
+        let candidates: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|&i| drawers[i].may_vanish(x_interval, y_interval))
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+        if rect.size() == 1 {
+            for i in candidates {
+                if drawers[i].classify_cell(x_interval, y_interval).is_some() {
+                    points[i].push((rect.x0, y_count - rect.y0 - 1));
+                }
+            }
+        } else {
+            for sub_rect in rect.subdivide() {
+                if sub_rect.size() >= 1 {
+                    let (sub_x, sub_y) =
+                        FInt::get_subinterval(x_interval, y_interval, rect, sub_rect);
+                    Self::inspect_region_of_each(
+                        drawers,
+                        &candidates,
+                        sub_x,
+                        sub_y,
+                        sub_rect,
+                        points,
+                        y_count,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Exact number of points where the curve crosses the segment {x} x y_interval,
+    /// or None if the intervals are too wide to tell
+    pub fn column_crossings(&self, x: f64, y_interval: FInt) -> Option<u32> {
+        let poly_y = self.xy_poly.at_fixed_x(FInt::new_with_bounds(x, x));
+        if poly_y.0.is_empty() {
+            return None;
+        }
+        SturmSequence::new(&poly_y).count_roots(y_interval.lower_bound(), y_interval.upper_bound())
+    }
+
+    fn crosses_vertical_edges(&self, x_interval: FInt, y_interval: FInt) -> bool {
+        [x_interval.lower_bound(), x_interval.upper_bound()]
+            .into_iter()
+            .any(|x| {
+                self.column_crossings(x, y_interval)
+                    .is_some_and(|count| count > 0)
+            })
+    }
+
+    /// Tells whether the curve passes through a cell that the interval evaluation
+    /// couldn't exclude, or None if it most likely doesn't. With refinement, the cell
+    /// is split as a quadtree, one level at a time: only the quadrants without a sign
+    /// change at their samples whose interval evaluation still contains zero are split
+    /// again, so that a sign change found on a coarse level stops the search.
+    fn classify_cell(&self, x_interval: FInt, y_interval: FInt) -> Option<Certainty> {
+        let mut cells = vec![(x_interval, y_interval)];
+        for depth in 0..=self.max_refinement_depth {
+            let mut likely = false;
+            let mut undecided = Vec::new();
+            for (x, y) in cells {
+                match self.sign_change(x, y) {
+                    SignChange::Certain => return Some(Certainty::Certain),
+                    _ if self.column_crossings && self.crosses_vertical_edges(x, y) => {
+                        return Some(Certainty::Certain)
+                    }
+                    SignChange::Likely => likely = true,
+                    SignChange::None => {}
+                }
+                undecided.push((x, y));
+            }
+            if depth == self.max_refinement_depth {
+                return likely.then_some(Certainty::Uncertain);
+            }
+            cells = undecided
+                .into_iter()
+                .flat_map(|(x, y)| Self::quadrants(x, y))
+                .filter(|&(x, y)| self.may_vanish(x, y))
+                .collect();
+            if cells.is_empty() {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn quadrants(x_interval: FInt, y_interval: FInt) -> [(FInt, FInt); 4] {
+        let x_mid = x_interval.midpoint();
+        let y_mid = y_interval.midpoint();
+        let left = FInt::new_with_bounds(x_interval.lower_bound(), x_mid);
+        let right = FInt::new_with_bounds(x_mid, x_interval.upper_bound());
+        let bottom = FInt::new_with_bounds(y_interval.lower_bound(), y_mid);
+        let top = FInt::new_with_bounds(y_mid, y_interval.upper_bound());
+        [(left, bottom), (left, top), (right, bottom), (right, top)]
+    }
+
+    /// Returns the plotted points (on a grid `scale` times coarser than the curve cells)
+    /// that have no provable curve cell in or next to them
+    pub fn get_uncertain_points(
+        points: &[(u32, u32, Color)],
+        curve_points: &[(u32, u32, Certainty)],
+        scale: u32,
+    ) -> Vec<(u32, u32)> {
+        let mut certain = HashSet::new();
+        for &(x, y, certainty) in curve_points {
+            if certainty == Certainty::Certain {
+                let (cx, cy) = (x / scale, y / scale);
+                for nx in cx.saturating_sub(1)..=cx + 1 {
+                    for ny in cy.saturating_sub(1)..=cy + 1 {
+                        certain.insert((nx, ny));
+                    }
+                }
+            }
+        }
+        points
+            .iter()
+            .map(|&(x, y, _)| (x, y))
+            .filter(|cell| !certain.contains(cell))
+            .collect()
+    }
+
     pub fn get_curve_points_smoothed(
         &self,
         curve_points: Vec<(u32, u32)>,
         x_count: u32,
         y_count: u32,
     ) -> Vec<(u32, u32, Color)> {
-        // Set up intensity map
-        let mut intensities: HashMap<(u32, u32), f64> = HashMap::new();
+        self.get_curve_points_colored(curve_points, x_count, y_count, Color::new(255, 0, 0))
+    }
+
+    /// Like `get_curve_points_smoothed`, fading from `color` on the curve to white
+    pub fn get_curve_points_colored(
+        &self,
+        curve_points: Vec<(u32, u32)>,
+        x_count: u32,
+        y_count: u32,
+        color: Color,
+    ) -> Vec<(u32, u32, Color)> {
         let white = Color::new(255, 255, 255);
-        let red = Color::new(255, 0, 0);
 
-        // Calculate intensities for each point and its neighborhood
-        for (x, y) in curve_points {
+        // Calculate intensities for each point and its neighborhood; chunks of points
+        // get separate maps, which are merged keeping the maximum
+        let intensities = match &self.thread_pool {
+            Some(pool) => pool.install(|| {
+                curve_points
+                    .par_chunks(PARALLEL_MIN_CELLS as usize)
+                    .map(|chunk| Self::get_intensities(chunk, x_count, y_count))
+                    .reduce(HashMap::new, Self::merge_intensities)
+            }),
+            None => Self::get_intensities(&curve_points, x_count, y_count),
+        };
+
+        // Sum intensities for coarse grid
+        let mut intensity_sums: HashMap<(u32, u32), f64> = HashMap::new();
+        for ((x, y), intensity) in intensities {
+            let coarse_x = x / 4;
+            let coarse_y = y / 4;
+            *intensity_sums.entry((coarse_x, coarse_y)).or_insert(0.0) += intensity;
+        }
+
+        // Find maximum intensity for normalization
+        let max_intensity = intensity_sums
+            .values()
+            .fold(0.0f64, |max, &val| max.max(val));
+
+        // Convert to colors
+        intensity_sums
+            .into_iter()
+            .map(|((x, y), intensity)| {
+                let t = intensity / max_intensity;
+                (x, y, Color::interpolate(white, color, t))
+            })
+            .collect()
+    }
+
+    fn get_intensities(
+        curve_points: &[(u32, u32)],
+        x_count: u32,
+        y_count: u32,
+    ) -> HashMap<(u32, u32), f64> {
+        let mut intensities: HashMap<(u32, u32), f64> = HashMap::new();
+        for &(x, y) in curve_points {
             for dx in -5..=5 {
                 for dy in -5..=5 {
                     let dist_sq = (dx * dx + dy * dy) as f64;
@@ -124,26 +484,192 @@ impl XYPolyDraw {
                 }
             }
         }
+        intensities
+    }
 
-        // Sum intensities for coarse grid
-        let mut intensity_sums: HashMap<(u32, u32), f64> = HashMap::new();
-        for ((x, y), intensity) in intensities {
-            let coarse_x = x / 4;
-            let coarse_y = y / 4;
-            *intensity_sums.entry((coarse_x, coarse_y)).or_insert(0.0) += intensity;
+    fn merge_intensities(
+        mut intensities: HashMap<(u32, u32), f64>,
+        other: HashMap<(u32, u32), f64>,
+    ) -> HashMap<(u32, u32), f64> {
+        for (cell, intensity) in other {
+            intensities
+                .entry(cell)
+                .and_modify(|e| *e = (*e).max(intensity))
+                .or_insert(intensity);
         }
+        intensities
+    }
 
-        // Find maximum intensity for normalization
-        let max_intensity = intensity_sums
-            .values()
-            .fold(0.0f64, |max, &val| max.max(val));
+    /// Orders the detected cells into connected polylines by following the curve.
+    /// The cells are first thinned to a one-cell-wide skeleton, then followed through
+    /// 8-connected neighbours. Chains start at endpoints (cells with the fewest neighbours)
+    /// and closed curves repeat their first cell at the end.
+    pub fn trace_polylines(curve_points: &[(u32, u32)]) -> Vec<Vec<(u32, u32)>> {
+        let mut remaining = Self::thin(curve_points.iter().copied().collect());
 
-        // Convert to colors
-        intensity_sums
-            .into_iter()
-            .map(|((x, y), intensity)| {
-                let t = intensity / max_intensity;
-                (x, y, Color::interpolate(white, red, t))
+        // Visit start candidates in order of increasing neighbour count, so open
+        // curves are traced from one of their ends
+        let mut starts: Vec<(usize, (u32, u32))> = remaining
+            .iter()
+            .map(|&cell| (Self::neighbours(cell, &remaining).count(), cell))
+            .collect();
+        starts.sort_by_key(|&(count, (x, y))| (count, y, x));
+
+        let mut polylines = Vec::new();
+        for (_, start) in starts {
+            if !remaining.remove(&start) {
+                continue;
+            }
+            let mut chain = vec![start];
+            let mut current = start;
+            let mut direction: Option<(i64, i64)> = None;
+            loop {
+                let candidates: Vec<(u32, u32)> = Self::neighbours(current, &remaining).collect();
+                // Prefer the candidate that continues the current direction, then 4-neighbours
+                let next = candidates.iter().copied().max_by_key(|&(nx, ny)| {
+                    let step = (nx as i64 - current.0 as i64, ny as i64 - current.1 as i64);
+                    let straightness = direction.map_or(0, |(dx, dy)| dx * step.0 + dy * step.1);
+                    let axis_aligned = step.0 == 0 || step.1 == 0;
+                    (straightness, axis_aligned, std::cmp::Reverse((ny, nx)))
+                });
+                let Some(next) = next else {
+                    break;
+                };
+                for cell in candidates {
+                    remaining.remove(&cell);
+                }
+                direction = Some((
+                    next.0 as i64 - current.0 as i64,
+                    next.1 as i64 - current.1 as i64,
+                ));
+                chain.push(next);
+                current = next;
+            }
+
+            // Close the loop if the path came back next to where it started
+            let closes = chain.len() > 3
+                && current.0.abs_diff(start.0) <= 2
+                && current.1.abs_diff(start.1) <= 2;
+            if closes {
+                chain.push(start);
+            }
+            polylines.push(chain);
+        }
+        polylines
+    }
+
+    /// Zhang-Suen thinning: repeatedly peels boundary cells that are not needed
+    /// to keep the set connected
+    fn thin(mut cells: HashSet<(u32, u32)>) -> HashSet<(u32, u32)> {
+        // Neighbours clockwise from north; y grows downwards like in the plot
+        const RING: [(i64, i64); 8] = [
+            (0, -1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+        ];
+        loop {
+            let mut changed = false;
+            for pass in 0..2 {
+                let to_remove: Vec<(u32, u32)> = cells
+                    .iter()
+                    .copied()
+                    .filter(|&(x, y)| {
+                        let p = RING.map(|(dx, dy)| {
+                            match (u32::try_from(x as i64 + dx), u32::try_from(y as i64 + dy)) {
+                                (Ok(nx), Ok(ny)) => cells.contains(&(nx, ny)),
+                                _ => false,
+                            }
+                        });
+                        let count = p.iter().filter(|&&set| set).count();
+                        let transitions = (0..8).filter(|&i| !p[i] && p[(i + 1) % 8]).count();
+                        // p[0] = N, p[2] = E, p[4] = S, p[6] = W
+                        let corner_clear = if pass == 0 {
+                            !(p[2] && p[4] && (p[0] || p[6]))
+                        } else {
+                            !(p[0] && p[6] && (p[2] || p[4]))
+                        };
+                        (2..=6).contains(&count) && transitions == 1 && corner_clear
+                    })
+                    .collect();
+                changed |= !to_remove.is_empty();
+                for cell in to_remove {
+                    cells.remove(&cell);
+                }
+            }
+            if !changed {
+                return cells;
+            }
+        }
+    }
+
+    fn neighbours<'a>(
+        (x, y): (u32, u32),
+        cells: &'a HashSet<(u32, u32)>,
+    ) -> impl Iterator<Item = (u32, u32)> + 'a {
+        (-1i64..=1)
+            .flat_map(move |dy| (-1i64..=1).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| dx != 0 || dy != 0)
+            .filter_map(move |(dx, dy)| {
+                let nx = u32::try_from(x as i64 + dx).ok()?;
+                let ny = u32::try_from(y as i64 + dy).ok()?;
+                cells.contains(&(nx, ny)).then_some((nx, ny))
+            })
+    }
+
+    /// Draws traced polylines on a grid `scale` times coarser than the one they were traced on
+    pub fn get_curve_points_traced(
+        polylines: &[Vec<(u32, u32)>],
+        scale: u32,
+    ) -> Vec<(u32, u32, Color)> {
+        let red = Color::new(255, 0, 0);
+        let mut seen = HashSet::new();
+        polylines
+            .iter()
+            .flatten()
+            .map(|&(x, y)| (x / scale, y / scale))
+            .filter(|&cell| seen.insert(cell))
+            .map(|(x, y)| (x, y, red))
+            .collect()
+    }
+
+    /// Converts polylines in plot coordinates into SVG path data, one path per chain.
+    /// Vertices closer than `min_step` to the previous kept vertex are dropped, and the
+    /// remaining ones are joined by quadratic curves through the midpoints of consecutive
+    /// vertices, which smooths out the staircase of the underlying cells.
+    pub fn to_svg_paths(polylines: &[Vec<(f64, f64)>], min_step: f64) -> Vec<String> {
+        polylines
+            .iter()
+            .filter(|chain| chain.len() >= 2)
+            .map(|chain| {
+                let closed = chain.first() == chain.last();
+                let last = chain[chain.len() - 1];
+                let mut vertices = vec![chain[0]];
+                for &(x, y) in &chain[1..chain.len() - 1] {
+                    let (px, py) = vertices[vertices.len() - 1];
+                    if (x - px).hypot(y - py) >= min_step {
+                        vertices.push((x, y));
+                    }
+                }
+                vertices.push(last);
+
+                let mut path = format!("M{:.2},{:.2}", vertices[0].0, vertices[0].1);
+                for w in vertices[1..].windows(2) {
+                    let (mx, my) = ((w[0].0 + w[1].0) / 2.0, (w[0].1 + w[1].1) / 2.0);
+                    path.push_str(&format!(
+                        " Q{:.2},{:.2} {:.2},{:.2}",
+                        w[0].0, w[0].1, mx, my
+                    ));
+                }
+                path.push_str(&format!(" L{:.2},{:.2}", last.0, last.1));
+                if closed {
+                    path.push_str(" Z");
+                }
+                path
             })
             .collect()
     }
@@ -165,13 +691,23 @@ impl XYPolyDraw {
         let smoothed_points = self.get_curve_points_smoothed(points, width, height);
         info!("Generated {} smoothed points", smoothed_points.len());
 
-        // Create BMP file
+        Self::write_bmp(&smoothed_points, width / 4, height / 4, filename)
+    }
+
+    /// Writes colored points to a 24-bit BMP file; pixels without a point are white
+    pub fn write_bmp(
+        points: &[(u32, u32, Color)],
+        width: u32,
+        height: u32,
+        filename: &str,
+    ) -> std::io::Result<()> {
         let mut file = File::create(filename)?;
-        let width = width / 4;
-        let height = height / 4;
+
+        // Each row is padded to a multiple of 4 bytes
+        let row_padding = (4 - (3 * width) % 4) % 4;
 
         // BMP header
-        let file_size = 54 + 3 * width * height; // 54 bytes header + 3 bytes per pixel
+        let file_size = 54 + (3 * width + row_padding) * height; // 54 bytes header + padded rows
         let header = [
             0x42,
             0x4D,                     // "BM"
@@ -232,8 +768,8 @@ impl XYPolyDraw {
 
         // Create a map of colors for each pixel
         let mut colors: HashMap<(u32, u32), Color> = HashMap::new();
-        for (x, y, color) in smoothed_points {
-            colors.insert((x, y), color);
+        for (x, y, color) in points {
+            colors.insert((*x, *y), *color);
         }
 
         // Write pixel data (bottom-up)
@@ -247,6 +783,7 @@ impl XYPolyDraw {
                     file.write_all(&[255, 255, 255])?;
                 }
             }
+            file.write_all(&[0u8; 3][..row_padding as usize])?;
         }
 
         info!(
@@ -257,9 +794,182 @@ impl XYPolyDraw {
 
         Ok(())
     }
+
+    /// RGB bytes of the image, top row first; pixels without a point are white
+    pub fn to_rgb_pixels(points: &[(u32, u32, Color)], width: u32, height: u32) -> Vec<u8> {
+        let mut pixels = vec![255u8; 3 * width as usize * height as usize];
+        Self::put_points(&mut pixels, points, width, height);
+        pixels
+    }
+
+    fn put_points(pixels: &mut [u8], points: &[(u32, u32, Color)], width: u32, height: u32) {
+        for &(x, y, color) in points {
+            if x < width && y < height {
+                let offset = 3 * (y as usize * width as usize + x as usize);
+                pixels[offset..offset + 3].copy_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+    }
+
+    fn encode_rgb_png(
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, png::EncodingError> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(pixels)?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+
+    /// Encodes colored points as an 8-bit RGB PNG image
+    pub fn encode_png(
+        points: &[(u32, u32, Color)],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, png::EncodingError> {
+        Self::encode_rgb_png(&Self::to_rgb_pixels(points, width, height), width, height)
+    }
+
+    /// Draws the curve over `view` as a PNG image of `width` x `height` pixels
+    pub fn render_png(
+        &self,
+        width: u32,
+        height: u32,
+        view: &PlotView,
+        style: &PlotStyle,
+    ) -> Result<Vec<u8>, png::EncodingError> {
+        let points =
+            self.get_curve_points(view.x_interval(), view.y_interval(), width * 4, height * 4);
+        let points = self.get_curve_points_smoothed(points, width * 4, height * 4);
+        Self::render_points_png(&points, width, height, view, style)
+    }
+
+    /// Encodes plotted points as a PNG image, over the grid lines and axes of `view`
+    pub fn render_points_png(
+        points: &[(u32, u32, Color)],
+        width: u32,
+        height: u32,
+        view: &PlotView,
+        style: &PlotStyle,
+    ) -> Result<Vec<u8>, png::EncodingError> {
+        let background = style.background;
+        let mut pixels =
+            [background.r, background.g, background.b].repeat(width as usize * height as usize);
+        let mut put = |x: u32, y: u32, color: Color| {
+            let offset = 3 * (y as usize * width as usize + x as usize);
+            pixels[offset..offset + 3].copy_from_slice(&[color.r, color.g, color.b]);
+        };
+        let column = |x: f64| view.column(x, width);
+        let row = |y: f64| view.row(y, height);
+
+        if let Some(color) = style.grid {
+            let step = style.grid_step.unwrap_or_else(|| view.grid_step());
+            let lines = |min: f64, max: f64| {
+                ((min / step).ceil() as i64..=(max / step).floor() as i64)
+                    .map(move |k| k as f64 * step)
+            };
+            for x in lines(view.x_min, view.x_max).filter_map(column) {
+                (0..height).for_each(|y| put(x, y, color));
+            }
+            for y in lines(view.y_min, view.y_max).filter_map(row) {
+                (0..width).for_each(|x| put(x, y, color));
+            }
+        }
+        if let Some(color) = style.axes {
+            if let Some(x) = column(0.0) {
+                (0..height).for_each(|y| put(x, y, color));
+            }
+            if let Some(y) = row(0.0) {
+                (0..width).for_each(|x| put(x, y, color));
+            }
+        }
+
+        Self::put_points(&mut pixels, points, width, height);
+        Self::encode_rgb_png(&pixels, width, height)
+    }
+}
+
+/// Rectangle of the plane covered by a plot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlotView {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+impl PlotView {
+    pub fn x_interval(&self) -> FInt {
+        FInt::new_with_bounds(self.x_min, self.x_max)
+    }
+
+    pub fn y_interval(&self) -> FInt {
+        FInt::new_with_bounds(self.y_min, self.y_max)
+    }
+
+    /// The pixel column of `x` in a plot `width` pixels wide, None outside of the view
+    pub fn column(&self, x: f64, width: u32) -> Option<u32> {
+        let column = ((x - self.x_min) / (self.x_max - self.x_min) * width as f64).floor();
+        (0.0..width as f64)
+            .contains(&column)
+            .then_some(column as u32)
+    }
+
+    /// The pixel row of `y` in a plot `height` pixels high, counted from the top
+    pub fn row(&self, y: f64, height: u32) -> Option<u32> {
+        let row = ((self.y_max - y) / (self.y_max - self.y_min) * height as f64).floor();
+        (0.0..height as f64).contains(&row).then_some(row as u32)
+    }
+
+    /// Distance between grid lines: 1, 2 or 5 times a power of 10, giving
+    /// about 10 lines along the longer side
+    pub fn grid_step(&self) -> f64 {
+        let span = (self.x_max - self.x_min).max(self.y_max - self.y_min) / 10.0;
+        let magnitude = 10f64.powf(span.log10().floor());
+        [1.0, 2.0, 5.0, 10.0]
+            .into_iter()
+            .map(|k| k * magnitude)
+            .find(|&step| step >= span)
+            .unwrap_or(10.0 * magnitude)
+    }
+}
+
+/// Colors of a rendered plot; the grid and the axes are left out when their color is None
+#[derive(Clone, Copy, Debug)]
+pub struct PlotStyle {
+    pub background: Color,
+    pub grid: Option<Color>,
+    /// Distance between grid lines, chosen from the view if None
+    pub grid_step: Option<f64>,
+    pub axes: Option<Color>,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+impl Default for PlotStyle {
+    fn default() -> Self {
+        PlotStyle {
+            background: Color::new(255, 255, 255),
+            grid: Some(Color::new(230, 230, 230)),
+            grid_step: None,
+            axes: Some(Color::new(128, 128, 128)),
+        }
+    }
+}
+
+/// Whether the curve provably passes through a cell, or the interval evaluation
+/// could not rule it out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Certainty {
+    Certain,
+    Uncertain,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -271,6 +981,23 @@ impl Color {
         Color { r, g, b }
     }
 
+    /// Color of the factor with the given index in plots of reducible curves;
+    /// the first one is the red of single curves, and the palette repeats after 8 factors
+    pub fn of_factor(index: usize) -> Color {
+        const PALETTE: [(u8, u8, u8); 8] = [
+            (255, 0, 0),
+            (0, 90, 255),
+            (0, 160, 60),
+            (255, 140, 0),
+            (150, 0, 200),
+            (0, 170, 170),
+            (200, 0, 120),
+            (120, 90, 0),
+        ];
+        let (r, g, b) = PALETTE[index % PALETTE.len()];
+        Color::new(r, g, b)
+    }
+
     pub fn interpolate(start: Color, end: Color, t: f64) -> Color {
         Color {
             r: (start.r as f64 + (end.r as f64 - start.r as f64) * t) as u8,
@@ -329,4 +1056,309 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_trace_polylines_thick_segment() {
+        // A horizontal band three cells high
+        let cells: Vec<(u32, u32)> = (0..10).flat_map(|x| (4..7).map(move |y| (x, y))).collect();
+        let polylines = XYPolyDraw::trace_polylines(&cells);
+
+        assert_eq!(polylines.len(), 1);
+        let xs: Vec<u32> = polylines[0].iter().map(|&(x, _)| x).collect();
+        assert!(xs.windows(2).all(|w| w[0] < w[1]), "{:?}", polylines[0]);
+        assert!(xs[0] <= 1 && xs[xs.len() - 1] >= 7, "{:?}", polylines[0]);
+    }
+
+    #[test]
+    fn test_trace_polylines_circle() {
+        let circle = XYPoly::new(vec![
+            XPoly::new(vec![FInt::new(-1.0), FInt::new(0.0), FInt::new(1.0)]),
+            XPoly::new(vec![FInt::new(0.0)]),
+            XPoly::new(vec![FInt::new(1.0)]),
+        ]);
+        let drawer = XYPolyDraw::new(circle);
+        let points = drawer.get_curve_points(
+            FInt::new_with_bounds(-1.5, 1.5),
+            FInt::new_with_bounds(-1.5, 1.5),
+            64,
+            64,
+        );
+        let polylines = XYPolyDraw::trace_polylines(&points);
+
+        assert_eq!(polylines.len(), 1);
+        let chain = &polylines[0];
+        assert_eq!(chain.first(), chain.last());
+        for w in chain.windows(2) {
+            assert!(w[0].0.abs_diff(w[1].0) <= 2 && w[0].1.abs_diff(w[1].1) <= 2);
+        }
+        // Every detected cell is close to the traced path
+        for &(x, y) in &points {
+            assert!(chain
+                .iter()
+                .any(|&(cx, cy)| cx.abs_diff(x) <= 2 && cy.abs_diff(y) <= 2));
+        }
+    }
+
+    #[test]
+    fn test_double_double_curve_points() {
+        use crate::poly::{Poly, PolyConversion};
+
+        let circle = Poly::new("a^2 + b^2 - 1").unwrap();
+        let drawer = XYPolyDraw::new(circle.as_xy_poly(0, 1).unwrap());
+        let dd_drawer = XYPolyDraw::new(circle.as_xy_poly(0, 1).unwrap())
+            .with_double_double(DDXYPoly::from_poly(&circle, 0, 1).unwrap());
+        let region = FInt::new_with_bounds(-1.5, 1.5);
+        // Double-double intervals only drop cells, and a well-conditioned curve keeps all of them
+        assert_eq!(
+            dd_drawer.get_classified_curve_points(region, region, 32, 32),
+            drawer.get_classified_curve_points(region, region, 32, 32)
+        );
+    }
+
+    #[test]
+    fn test_refinement_finds_thin_features() {
+        use crate::poly::{Poly, PolyConversion};
+
+        // A circle of radius 0.05 around (0.25, 0.25), inside a single grid cell
+        // and away from the samples at its corners and center
+        let oval = Poly::new("400*a^2 + 400*b^2 - 200*a - 200*b + 49")
+            .unwrap()
+            .as_xy_poly(0, 1)
+            .unwrap();
+        let region = FInt::new_with_bounds(-1.5, 1.5);
+        let points =
+            XYPolyDraw::new(oval.clone()).get_classified_curve_points(region, region, 8, 8);
+        assert!(points.is_empty());
+
+        let points = XYPolyDraw::new(oval)
+            .with_refinement(3)
+            .get_classified_curve_points(region, region, 8, 8);
+        assert_eq!(points, vec![(4, 3, Certainty::Certain)]);
+    }
+
+    #[test]
+    fn test_column_crossings() {
+        use crate::poly::{Poly, PolyConversion};
+
+        // A narrow parabola with a vertical tangent at (0.25, 0.1), which enters and
+        // leaves the cell [0, 0.375] x [0, 0.375] through its right edge
+        let parabola = Poly::new("400*b^2 - 80*b - 4*a + 5")
+            .unwrap()
+            .as_xy_poly(0, 1)
+            .unwrap();
+        let drawer = XYPolyDraw::new(parabola.clone());
+        let cell = FInt::new_with_bounds(0.0, 0.375);
+        assert_eq!(drawer.column_crossings(0.375, cell), Some(2));
+        assert_eq!(drawer.column_crossings(0.0, cell), Some(0));
+        assert_eq!(
+            drawer.column_crossings(0.25, FInt::new_with_bounds(0.2, 0.3)),
+            Some(0)
+        );
+
+        let region = FInt::new_with_bounds(-1.5, 1.5);
+        let points = drawer.get_classified_curve_points(region, region, 8, 8);
+        assert!(!points.iter().any(|&(x, y, _)| (x, y) == (4, 3)));
+
+        let points = XYPolyDraw::new(parabola)
+            .with_column_crossings()
+            .get_classified_curve_points(region, region, 8, 8);
+        assert!(points.contains(&(4, 3, Certainty::Certain)));
+    }
+
+    #[test]
+    fn test_parallel_curve_points() {
+        let circle = XYPoly::new(vec![
+            XPoly::new(vec![FInt::new(-1.0), FInt::new(0.0), FInt::new(1.0)]),
+            XPoly::new(vec![FInt::new(0.0)]),
+            XPoly::new(vec![FInt::new(1.0)]),
+        ]);
+        let region = FInt::new_with_bounds(-1.5, 1.5);
+        let drawer = XYPolyDraw::new(circle.clone());
+        let parallel_drawer = XYPolyDraw::new(circle).with_threads(4);
+        let points = drawer.get_classified_curve_points(region, region, 256, 256);
+        assert_eq!(
+            parallel_drawer.get_classified_curve_points(region, region, 256, 256),
+            points
+        );
+
+        let points: Vec<(u32, u32)> = points.iter().map(|&(x, y, _)| (x, y)).collect();
+        let sorted = |mut points: Vec<(u32, u32, Color)>| {
+            points.sort_by_key(|&(x, y, _)| (x, y));
+            points
+        };
+        let smoothed = sorted(drawer.get_curve_points_smoothed(points.clone(), 256, 256));
+        let parallel_smoothed = sorted(parallel_drawer.get_curve_points_smoothed(points, 256, 256));
+        assert_eq!(smoothed.len(), parallel_smoothed.len());
+        // Intensities are summed in hash map order, so colors may differ by rounding
+        for (&(x, y, c), &(px, py, pc)) in smoothed.iter().zip(&parallel_smoothed) {
+            assert_eq!((x, y), (px, py));
+            assert!(c.g.abs_diff(pc.g) <= 1 && c.b.abs_diff(pc.b) <= 1);
+        }
+    }
+
+    #[test]
+    fn test_thread_count() {
+        let pool = shared_pool(3).unwrap();
+        assert_eq!(pool.current_num_threads(), 3);
+        // At most 3 threads take part in the work, however much there is
+        let workers: HashSet<thread::ThreadId> = pool.install(|| {
+            (0..10000)
+                .into_par_iter()
+                .map(|_| thread::current().id())
+                .collect()
+        });
+        assert!(!workers.is_empty() && workers.len() <= 3);
+        // Drawings with the same number of threads share the pool
+        assert!(std::ptr::eq(shared_pool(3).unwrap(), pool));
+
+        // No more threads than cores, and none but the caller's for one
+        let circle = XYPoly::new(vec![XPoly::new(vec![FInt::new(1.0)])]);
+        let threads = |requested| {
+            XYPolyDraw::new(circle.clone())
+                .with_threads(requested)
+                .thread_pool
+                .map_or(1, ThreadPool::current_num_threads)
+        };
+        assert_eq!(threads(1), 1);
+        assert_eq!(threads(usize::MAX), max_threads());
+    }
+
+    #[test]
+    fn test_to_svg_paths() {
+        let polylines = vec![
+            vec![(0.0, 0.0), (0.25, 0.25), (1.0, 0.0), (2.0, 1.0)],
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)],
+            vec![(5.0, 5.0)],
+        ];
+        let paths = XYPolyDraw::to_svg_paths(&polylines, 0.5);
+        assert_eq!(
+            paths,
+            vec![
+                "M0.00,0.00 Q1.00,0.00 1.50,0.50 L2.00,1.00",
+                "M0.00,0.00 Q1.00,0.00 1.00,0.50 Q1.00,1.00 0.50,0.50 L0.00,0.00 Z",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classified_curve_points() {
+        // x^2 + y^2 - 1 has sign changes along the circle
+        let circle = XYPoly::new(vec![
+            XPoly::new(vec![FInt::new(-1.0), FInt::new(0.0), FInt::new(1.0)]),
+            XPoly::new(vec![FInt::new(0.0)]),
+            XPoly::new(vec![FInt::new(1.0)]),
+        ]);
+        let points = XYPolyDraw::new(circle).get_classified_curve_points(
+            FInt::new_with_bounds(-1.5, 1.5),
+            FInt::new_with_bounds(-1.5, 1.5),
+            16,
+            16,
+        );
+        assert!(points.iter().any(|&(_, _, c)| c == Certainty::Certain));
+
+        // x^2 + y^2 only touches zero at the origin, which is never a sign change
+        let point = XYPoly::new(vec![
+            XPoly::new(vec![FInt::new(0.0), FInt::new(0.0), FInt::new(1.0)]),
+            XPoly::new(vec![FInt::new(0.0)]),
+            XPoly::new(vec![FInt::new(1.0)]),
+        ]);
+        let points = XYPolyDraw::new(point).get_classified_curve_points(
+            FInt::new_with_bounds(-1.0, 1.0),
+            FInt::new_with_bounds(-1.0, 1.0),
+            4,
+            4,
+        );
+        assert!(!points.is_empty());
+        assert!(points.iter().all(|&(_, _, c)| c == Certainty::Uncertain));
+
+        let plotted = [(0, 0, Color::new(255, 0, 0)), (5, 5, Color::new(255, 0, 0))];
+        let uncertain = XYPolyDraw::get_uncertain_points(
+            &plotted,
+            &[(2, 3, Certainty::Certain), (20, 20, Certainty::Uncertain)],
+            4,
+        );
+        assert_eq!(uncertain, vec![(5, 5)]);
+    }
+
+    #[test]
+    fn test_curve_points_of_each() {
+        // The lines y = 0.3 and x = -0.3, and x^2 + y^2 + 1, which has no real points
+        let horizontal = XYPoly::new(vec![XPoly::new(vec![FInt::new(-0.3), FInt::new(1.0)])]);
+        let vertical = XYPoly::new(vec![
+            XPoly::new(vec![FInt::new(0.3)]),
+            XPoly::new(vec![FInt::new(1.0)]),
+        ]);
+        let empty = XYPoly::new(vec![
+            XPoly::new(vec![FInt::new(1.0), FInt::new(0.0), FInt::new(1.0)]),
+            XPoly::new(vec![FInt::new(0.0)]),
+            XPoly::new(vec![FInt::new(1.0)]),
+        ]);
+        let drawers = [horizontal, vertical, empty].map(XYPolyDraw::new);
+        let (x_interval, y_interval) = (
+            FInt::new_with_bounds(-1.0, 1.0),
+            FInt::new_with_bounds(-1.0, 1.0),
+        );
+        let points = XYPolyDraw::get_curve_points_of_each(&drawers, x_interval, y_interval, 16, 16);
+        assert_eq!(points.len(), 3);
+        // Each curve gets the same cells as when drawn alone
+        for (drawer, points) in drawers.iter().zip(&points) {
+            let mut alone = drawer.get_curve_points(x_interval, y_interval, 16, 16);
+            let mut points = points.clone();
+            alone.sort();
+            points.sort();
+            assert_eq!(points, alone);
+        }
+        assert!(points[0].iter().all(|&(_, y)| y == 5));
+        assert!(points[1].iter().all(|&(x, _)| x == 5));
+        assert!(points[2].is_empty());
+        assert_eq!(Color::of_factor(0), Color::new(255, 0, 0));
+        assert_eq!(Color::of_factor(8), Color::of_factor(0));
+    }
+
+    #[test]
+    fn test_encode_png() {
+        let points = [(1, 0, Color::new(255, 0, 0)), (0, 1, Color::new(0, 0, 255))];
+        let bytes = XYPolyDraw::encode_png(&points, 2, 2).unwrap();
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (2, 2));
+        assert_eq!(
+            pixels,
+            vec![255, 255, 255, 255, 0, 0, 0, 0, 255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn test_render_points_png() {
+        let view = PlotView {
+            x_min: -1.0,
+            x_max: 3.0,
+            y_min: -2.0,
+            y_max: 2.0,
+        };
+        assert_eq!(view.grid_step(), 0.5);
+        let style = PlotStyle {
+            grid_step: Some(2.0),
+            ..PlotStyle::default()
+        };
+        let red = Color::new(255, 0, 0);
+        let bytes = XYPolyDraw::render_points_png(&[(3, 3, red)], 4, 4, &view, &style).unwrap();
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        let pixel = |x: usize, y: usize| {
+            let offset = 3 * (4 * y + x);
+            Color::new(pixels[offset], pixels[offset + 1], pixels[offset + 2])
+        };
+        // The y axis is column 1 and the x axis row 2; grid lines at x = 2 and y = 2 are
+        // column 3 and row 0, and the one at y = -2 is just below the view
+        let (axes, grid) = (style.axes.unwrap(), style.grid.unwrap());
+        assert_eq!(pixel(1, 0), axes);
+        assert_eq!(pixel(0, 2), axes);
+        assert_eq!(pixel(3, 1), grid);
+        assert_eq!(pixel(0, 0), grid);
+        assert_eq!(pixel(0, 3), style.background);
+        assert_eq!(pixel(3, 3), red);
+    }
 }
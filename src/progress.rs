@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A stage reached while solving for a locus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum Progress {
+    /// The scene was translated into `count` polynomial equations
+    Equations { count: usize },
+    /// The system was split into `count` systems of irreducible equations
+    Systems { count: usize },
+    /// `var` is being eliminated from `polys` equations with `terms` terms in total
+    EliminatingVariable {
+        var: String,
+        polys: usize,
+        terms: usize,
+    },
+    /// The eliminated equation with `terms` terms is being factored
+    Factoring { terms: usize },
+    /// The eliminated equation has `factors` irreducible factors
+    Factored { factors: usize },
+    /// Factor `index` (1-based) of `count` is being checked against the system
+    CheckingFactor { index: usize, count: usize },
+    /// The curve is being rasterized
+    Plotting,
+    /// The computation ended, successfully if `error` is empty
+    Finished { error: Option<String> },
+}
+
+type Reporter = Rc<dyn Fn(Progress)>;
+
+thread_local! {
+    static REPORTER: RefCell<Option<Reporter>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, passing every progress event reported on this thread to `reporter`
+pub fn with_progress<R>(reporter: impl Fn(Progress) + 'static, f: impl FnOnce() -> R) -> R {
+    let _restore =
+        RestoreReporter(REPORTER.with(|current| current.replace(Some(Rc::new(reporter)))));
+    f()
+}
+
+/// Puts the previous reporter back when `with_progress` returns, also if `f` panics
+struct RestoreReporter(Option<Reporter>);
+
+impl Drop for RestoreReporter {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        REPORTER.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Reports a progress event. `progress` is only evaluated if someone is listening.
+pub fn report(progress: impl FnOnce() -> Progress) {
+    let reporter = REPORTER.with(|current| current.borrow().clone());
+    if let Some(reporter) = reporter {
+        reporter(progress());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_progress() {
+        // Without a reporter, events are not even constructed
+        report(|| unreachable!());
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = events.clone();
+        let result = with_progress(
+            move |progress| sink.borrow_mut().push(progress),
+            || {
+                report(|| Progress::Equations { count: 3 });
+                report(|| Progress::Plotting);
+                42
+            },
+        );
+        assert_eq!(result, 42);
+        assert_eq!(
+            *events.borrow(),
+            vec![Progress::Equations { count: 3 }, Progress::Plotting]
+        );
+        report(|| unreachable!());
+
+        assert_eq!(
+            serde_json::to_value(Progress::Factored { factors: 2 }).unwrap(),
+            serde_json::json!({"stage": "factored", "factors": 2})
+        );
+    }
+
+    #[test]
+    fn test_with_progress_restores_after_panic() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = events.clone();
+        with_progress(
+            move |progress| sink.borrow_mut().push(progress),
+            || {
+                let result = std::panic::catch_unwind(|| {
+                    with_progress(|_| {}, || panic!("computation failed"))
+                });
+                assert!(result.is_err());
+                report(|| Progress::Plotting);
+            },
+        );
+        assert_eq!(*events.borrow(), vec![Progress::Plotting]);
+        report(|| unreachable!());
+    }
+}
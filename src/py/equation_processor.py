@@ -295,12 +295,6 @@ class RationalValue(Value):
         equations.append(f"{d}*{self} - {n}")
 
 
-def sqrt(value: Value) -> Value:
-    return value.non_integer_valued_operation(
-        lambda a, b: f"{a} - {b}^2", lambda a: math.sqrt(a)
-    )
-
-
 class Point:
     def __init__(self, x: Value, y: Value):
         self.x = x
@@ -331,6 +325,15 @@ class Midpoint(Point):
         super().__init__((point1.x + point2.x) / i(2), (point1.y + point2.y) / i(2))
 
 
+class PointOnSegmentRatio(Point):
+    def __init__(self, point1: Point, point2: Point, n: int, d: int):
+        # point1 + n/d (point2 - point1) = ((d - n) point1 + n point2) / d
+        super().__init__(
+            (i(d - n) * point1.x + i(n) * point2.x) / i(d),
+            (i(d - n) * point1.y + i(n) * point2.y) / i(d),
+        )
+
+
 class IntersectionPoint(Point):
     def __init__(self, line1: "Line", line2: "Line"):
         # Let line1 := (x - a) * n = 0, line2 := (x - b) * m = 0
@@ -341,6 +344,38 @@ class IntersectionPoint(Point):
         super().__init__(line1.o.x + n_prime.x * factor, line1.o.y + n_prime.y * factor)
 
 
+class LineIntersection(Point):
+    def __init__(self, line1: "Line", line2: "Line"):
+        # Unlike IntersectionPoint, no division by n1 x n2 is needed: the coordinates
+        # are new variables bound by (p - o1) * n1 = 0 and (p - o2) * n2 = 0
+        def float_coords() -> tuple[float, float]:
+            o1x, o1y = line1.o.x.float_initial_as_float(), line1.o.y.float_initial_as_float()
+            n1x, n1y = line1.n.x.float_initial_as_float(), line1.n.y.float_initial_as_float()
+            o2x, o2y = line2.o.x.float_initial_as_float(), line2.o.y.float_initial_as_float()
+            n2x, n2y = line2.n.x.float_initial_as_float(), line2.n.y.float_initial_as_float()
+            c1 = o1x * n1x + o1y * n1y
+            c2 = o2x * n2x + o2y * n2y
+            det = n1x * n2y - n1y * n2x
+            return ((c1 * n2y - c2 * n1y) / det, (n1x * c2 - n2x * c1) / det)
+
+        x_float = maybe_float_initial(lambda: float_coords()[0])
+        y_float = maybe_float_initial(lambda: float_coords()[1])
+        super().__init__(
+            Value(
+                next_var(),
+                initial=Value(next_var(), float_initial=x_float),
+                float_initial=x_float,
+            ),
+            Value(
+                next_var(),
+                initial=Value(next_var(), float_initial=y_float),
+                float_initial=y_float,
+            ),
+        )
+        line1.contains(self)
+        line2.contains(self)
+
+
 class Projection(Point):
     def __init__(self, point: Point, line: "Line"):
         # proj = a - n ((a - p) * n) / (n * n) (p = point, (a, n) = line)
@@ -443,6 +478,26 @@ class PpBisector(Line):
         super().__init__(a + (b - a) / i(2), b - a)
 
 
+class AngleBisector(Line):
+    def __init__(self, a: Point, vertex: Point, b: Point):
+        # The bisector of the angle a-vertex-b runs along e_a + e_b, the unit vectors
+        # towards a and b. The lengths are square roots of squared distances, which are
+        # only fixed up to sign, so the equations describe both bisectors.
+        e_a = (a - vertex) / (a - vertex).length()
+        e_b = (b - vertex) / (b - vertex).length()
+        super().__init__(vertex, (e_a + e_b).rotated90())
+
+
+class LLAngleBisector(Line):
+    def __init__(self, line1: Line, line2: Line):
+        # Points p of the bisector are equidistant from both lines:
+        # (p - o1) * n1 / |n1| = (p - o2) * n2 / |n2|
+        super().__init__(
+            IntersectionPoint(line1, line2),
+            line1.n / line1.n.length() - line2.n / line2.n.length(),
+        )
+
+
 class PpToLine(Line):
     def __init__(self, point: Point, line: Line):
         super().__init__(point, line.n.rotated90())
@@ -453,13 +508,42 @@ class PlToLine(Line):
         super().__init__(point, line.n)
 
 
+class Curve:
+    # The curve f(x, y) = 0, given by f and its partial derivatives
+    def __init__(
+        self,
+        f: Callable[[Value, Value], Value],
+        f_x: Callable[[Value, Value], Value],
+        f_y: Callable[[Value, Value], Value],
+    ):
+        self.f = f
+        self.f_x = f_x
+        self.f_y = f_y
+
+    def contains(self, p: Point):
+        v = self.f(p.x, p.y)
+        equations.append(f"{v}")
+        if v.initial is not None:
+            equations.append(f"{v.initial}")
+
+    def gradient(self, p: Point) -> Vector:
+        return Vector(self.f_x(p.x, p.y), self.f_y(p.x, p.y))
+
+
+class TangentLine(Line):
+    def __init__(self, curve: Curve, point: Point):
+        # The point lies on the curve (f = 0), and the gradient of f is normal to the tangent
+        curve.contains(point)
+        super().__init__(point, curve.gradient(point))
+
+
 def d(a: Point | Line, b: Point | Line) -> Value:
     if isinstance(a, Line) and isinstance(b, Point):
         return a.distance_to_point(b)
     if isinstance(a, Point) and isinstance(b, Line):
         return b.distance_to_point(a)
     if isinstance(a, Point) and isinstance(b, Point):
-        return sqrt(d_sqr(a, b))
+        return radical(d_sqr(a, b))
     raise Exception("d() cannot be called with two lines")
 
 
@@ -473,6 +557,21 @@ def d_sqr(a: Point | Line, b: Point | Line) -> Value:
     raise Exception("d() cannot be called with two lines")
 
 
+def radical(x: Value) -> Value:
+    # A new variable r with r^2 = x; unlike sqrt(), no rational power is involved
+    return x.non_integer_valued_operation(
+        lambda a, b: f"{a} - {b}^2", lambda a: math.sqrt(a)
+    )
+
+
+def distance_sum(point: Point, *foci: Point) -> Value:
+    # Sum of the distances r_k from point to the foci, with r_k^2 = d_sqr(focus_k, point)
+    result = d(foci[0], point)
+    for focus in foci[1:]:
+        result = result + d(focus, point)
+    return result
+
+
 def cot(a: Vector, b: Vector) -> Value:
     return (a * b) / (a.x * b.y - a.y * b.x)
 
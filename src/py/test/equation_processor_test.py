@@ -6,15 +6,23 @@ import traceback
 sys.path.append(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
 
 from equation_processor import (
+    AngleBisector,
+    Curve,
     FixedPoint,
     FreePoint,
     LineAB,
+    LineIntersection,
+    LLAngleBisector,
+    PointOnSegmentRatio,
+    TangentLine,
     d_sqr,
+    distance_sum,
     i,
     is_constant,
     d,
     Value,
     equations,
+    compute_float_initial,
     current_var,
     next_var,
     sqrt,
@@ -358,6 +366,80 @@ def scenario1():
 
 scenarios.append(scenario1)
 
+
+def scenario2():
+    # Points equidistant from the arms of a right angle
+    bisector = AngleBisector(FixedPoint(1, 0), FixedPoint(0, 0), FixedPoint(0, 1))
+    X = FreePoint(1, 1)
+    bisector.contains(X)
+    lines_bisector = LLAngleBisector(
+        LineAB(FixedPoint(0, 0), FixedPoint(1, 0)),
+        LineAB(FixedPoint(0, 0), FixedPoint(0, 1)),
+    )
+    lines_bisector.contains(X)
+
+
+scenarios.append(scenario2)
+
+
+def scenario3():
+    # Tangent to the circle x^2 + y^2 = 25 at a point sliding on it
+    circle = Curve(
+        lambda x, y: x ** i(2) + y ** i(2) + i(-25),
+        lambda x, y: i(2) * x,
+        lambda x, y: i(2) * y,
+    )
+    P = FreePoint(3, 4)
+    tangent = TangentLine(circle, P)
+    X = FreePoint(7, 1)
+    tangent.contains(X)
+
+
+scenarios.append(scenario3)
+
+
+def scenario4():
+    # A point at 1/3 of a segment between a fixed and a free point
+    compute_float_initial[0] = True
+    try:
+        A = FixedPoint(0, 0)
+        B = FreePoint(3, 6)
+        P = PointOnSegmentRatio(A, B, 1, 3)
+    finally:
+        compute_float_initial[0] = False
+    if (P.x.float_initial, P.y.float_initial) != (1.0, 2.0):
+        raise Exception(f"Unexpected initial values: {P}")
+
+
+scenarios.append(scenario4)
+
+
+def scenario5():
+    # Intersection of two lines rotating around fixed points
+    compute_float_initial[0] = True
+    try:
+        line1 = LineAB(FixedPoint(0, 0), FreePoint(1, 1))
+        line2 = LineAB(FixedPoint(4, 0), FreePoint(3, 1))
+        X = LineIntersection(line1, line2)
+    finally:
+        compute_float_initial[0] = False
+    if (X.x.float_initial, X.y.float_initial) != (2.0, 2.0):
+        raise Exception(f"Unexpected initial values: {X}")
+
+
+scenarios.append(scenario5)
+
+
+def scenario6():
+    # Ellipse: one radical per focus and no rational powers
+    X = FreePoint(0, 2)
+    is_constant(distance_sum(X, FixedPoint(-1, 0), FixedPoint(1, 0)))
+    if any("^1" in eq for eq in equations):
+        raise Exception(f"Unexpected equations: {equations}")
+
+
+scenarios.append(scenario6)
+
 if __name__ == "__main__":
     failures = run_tests()
 
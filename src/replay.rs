@@ -0,0 +1,130 @@
+//! Replays the elimination order of a saved trace. Moving a fixed point of a scene
+//! changes the coefficients of its system, but usually not which variables occur in
+//! which equations, so the variables can be eliminated in the order found the first
+//! time instead of searching for the next one at every step. `with_replay` installs
+//! the order on the current thread; once the system no longer matches it, the search
+//! of the `EliminationOrder` takes over again.
+
+use std::cell::RefCell;
+
+use crate::poly::{Poly, Var, VarSet};
+use crate::trace::TraceStep;
+
+struct ReplayState {
+    vars: Vec<Var>,
+    /// Index of the next variable of `vars` to eliminate
+    next: usize,
+    diverged: bool,
+}
+
+thread_local! {
+    static REPLAY: RefCell<Option<ReplayState>> = const { RefCell::new(None) };
+}
+
+/// The variables eliminated in a trace, in order, from its `Pivot` steps
+pub fn elimination_order(steps: &[TraceStep]) -> Result<Vec<Var>, String> {
+    steps
+        .iter()
+        .filter_map(|step| match step {
+            TraceStep::Pivot { var, .. } => Some(var),
+            _ => None,
+        })
+        .map(|var| {
+            Poly::parse_var(var)
+                .map_err(|e| format!("Invalid variable {} in the trace: {}", var, e))
+        })
+        .collect()
+}
+
+/// Runs `f`, eliminating the variables in the order `vars` as long as the system
+/// matches it. Also returns whether the whole order was followed, None without one.
+pub fn with_replay<R>(vars: Option<&[Var]>, f: impl FnOnce() -> R) -> (R, Option<bool>) {
+    let Some(vars) = vars else {
+        return (f(), None);
+    };
+    let previous = REPLAY.with(|replay| {
+        replay.replace(Some(ReplayState {
+            vars: vars.to_vec(),
+            next: 0,
+            diverged: false,
+        }))
+    });
+    let result = f();
+    let state = REPLAY.with(|replay| replay.replace(previous));
+    let followed = state.is_some_and(|state| !state.diverged && state.next == state.vars.len());
+    (result, Some(followed))
+}
+
+/// The next variable of the replayed order, if it is one of the `candidates` left to
+/// eliminate. None without a replay, and from the first variable that is not.
+pub fn next_var(candidates: &VarSet) -> Option<Var> {
+    REPLAY.with(|replay| {
+        let mut replay = replay.borrow_mut();
+        let state = replay.as_mut().filter(|state| !state.diverged)?;
+        match state.vars.get(state.next) {
+            Some(&v) if candidates.contains(v) => {
+                state.next += 1;
+                Some(v)
+            }
+            _ => {
+                state.diverged = true;
+                None
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let candidates = |vars: &[Var]| {
+            let mut set = VarSet::new();
+            for &v in vars {
+                set.insert(v);
+            }
+            set
+        };
+        assert_eq!(next_var(&candidates(&[2])), None);
+
+        let (vars, followed) = with_replay(Some(&[3, 2]), || {
+            vec![next_var(&candidates(&[2, 3])), next_var(&candidates(&[2]))]
+        });
+        assert_eq!(vars, vec![Some(3), Some(2)]);
+        assert_eq!(followed, Some(true));
+
+        // Once a variable is missing, the order is dropped
+        let (vars, followed) = with_replay(Some(&[3, 2]), || {
+            vec![next_var(&candidates(&[2, 4])), next_var(&candidates(&[2]))]
+        });
+        assert_eq!(vars, vec![None, None]);
+        assert_eq!(followed, Some(false));
+
+        // Variables left over
+        let ((), followed) = with_replay(Some(&[3, 2]), || {
+            next_var(&candidates(&[3]));
+        });
+        assert_eq!(followed, Some(false));
+        assert_eq!(with_replay(None, || 1), (1, None));
+
+        let steps = vec![
+            TraceStep::Pivot {
+                var: "c".to_string(),
+                equation: "c^2 - a".to_string(),
+            },
+            TraceStep::Resultant {
+                var: "c".to_string(),
+                poly1: "c - b".to_string(),
+                poly2: "c^2 - a".to_string(),
+                resultant: "b^2 - a".to_string(),
+            },
+            TraceStep::Pivot {
+                var: "b1".to_string(),
+                equation: "b1 - a".to_string(),
+            },
+        ];
+        assert_eq!(elimination_order(&steps), Ok(vec![2, 27]));
+    }
+}
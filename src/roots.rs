@@ -0,0 +1,338 @@
+//! Isolation of the real roots of univariate polynomials with integer coefficients.
+//! The square-free part of a polynomial is computed exactly; its real roots are then
+//! separated by bisection, bounding the number of roots in each interval with
+//! Descartes' rule of signs (the Vincent-Collins-Akritas method) in `FInt`
+//! arithmetic, and the isolating intervals are refined on the sign of the polynomial.
+
+use serde::{Deserialize, Serialize};
+
+use crate::fint::FInt;
+use crate::poly::{detect_overflow, Poly, PolyConversion, PolyError, PolyOperations, Var};
+use crate::x_poly::XPoly;
+
+/// Width, relative to the magnitude of its endpoints, below which an interval is no
+/// longer split: rounding errors then hide the signs that would separate its roots
+const MIN_RELATIVE_WIDTH: f64 = 1e-13;
+/// Relative width to which `real_roots` refines the isolating intervals
+const REFINED_RELATIVE_WIDTH: f64 = 1e-12;
+/// Points tried in turn to split an interval, as fractions of its width, so that an
+/// interval is not split at a root like 0 where the sign of the polynomial is unknown
+const SPLIT_POINTS: [f64; 5] = [0.5, 0.47, 0.53, 0.41, 0.59];
+/// Intervals examined by one isolation, after which the undecided ones are reported
+/// as they are
+const MAX_INTERVALS: usize = 100_000;
+
+/// A real root of a polynomial, inside an open interval
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RealRoot {
+    pub interval: FInt,
+    /// Whether the interval provably holds exactly one root. If not, rounding errors
+    /// left it unclear whether it holds one root, several close ones or none.
+    pub isolated: bool,
+}
+
+/// The square-free part of poly as a polynomial in v: poly divided by its gcd with its
+/// derivative, computed with a primitive pseudo-remainder sequence. It has the roots
+/// of poly, each with multiplicity 1.
+pub fn square_free_part(poly: &Poly, v: Var) -> Result<Poly, PolyError> {
+    detect_overflow(|| {
+        let derivative = poly.get_derivative(v);
+        if derivative == Poly::Constant(0) {
+            return poly.clone();
+        }
+        let mut a = poly.primitive_part(v);
+        let mut b = derivative.primitive_part(v);
+        while b != Poly::Constant(0) {
+            let (_, remainder) = a.pseudo_divide(&b, v);
+            a = b;
+            b = remainder.primitive_part(v);
+        }
+        // a is the gcd of poly and its derivative, up to a constant factor
+        if a.get_degree(v) == 0 {
+            poly.clone()
+        } else {
+            poly.divide_exact(&a).unwrap_or_else(|| poly.clone())
+        }
+    })
+}
+
+/// Isolating intervals of the distinct real roots in (low, high) of poly, a polynomial
+/// in v alone, in increasing order
+pub fn isolate_real_roots(
+    poly: &Poly,
+    v: Var,
+    low: f64,
+    high: f64,
+) -> Result<Vec<RealRoot>, String> {
+    let p = square_free_x_poly(poly, v)?;
+    Ok(isolate(&p, low, high))
+}
+
+/// Isolating intervals of all the distinct real roots of poly, a polynomial in v alone,
+/// in increasing order
+pub fn isolate_all_real_roots(poly: &Poly, v: Var) -> Result<Vec<RealRoot>, String> {
+    let p = square_free_x_poly(poly, v)?;
+    let bound = p.root_bound();
+    if !bound.is_finite() {
+        return Err("The roots of the polynomial could not be bounded".to_string());
+    }
+    Ok(isolate(&p, -bound, bound))
+}
+
+/// The distinct real roots in (low, high) of poly, a polynomial in v alone, in
+/// increasing order, refined to intervals about 1e-12 times as wide as their magnitude
+pub fn real_roots(poly: &Poly, v: Var, low: f64, high: f64) -> Result<Vec<RealRoot>, String> {
+    let p = square_free_x_poly(poly, v)?;
+    Ok(isolate(&p, low, high)
+        .into_iter()
+        .map(|root| {
+            let magnitude = root.interval.abs_bound().max(1.0);
+            refine_root(&p, root, REFINED_RELATIVE_WIDTH * magnitude)
+        })
+        .collect())
+}
+
+/// Narrows the interval of an isolated root of poly, a polynomial in v alone, by
+/// bisection until it is at most `width` wide, or rounding errors hide the sign of
+/// poly at its midpoint. Roots that are not isolated are returned as they are.
+pub fn refine(poly: &Poly, v: Var, root: &RealRoot, width: f64) -> Result<RealRoot, String> {
+    let p = square_free_x_poly(poly, v)?;
+    Ok(refine_root(&p, *root, width))
+}
+
+/// The square-free part of poly as an `XPoly`. If it overflows, poly is used as it is,
+/// and its multiple roots are not isolated.
+fn square_free_x_poly(poly: &Poly, v: Var) -> Result<XPoly, String> {
+    if *poly == Poly::Constant(0) {
+        return Err("Every number is a root of the zero polynomial".to_string());
+    }
+    square_free_part(poly, v)
+        .unwrap_or_else(|_| poly.clone())
+        .as_x_poly(v)
+}
+
+/// The sign of p at x, None if rounding errors hide it
+fn sign_at(p: &XPoly, x: f64) -> Option<f64> {
+    let value = p.evaluate(FInt::new_with_bounds(x, x));
+    if value.lower_bound() > 0.0 {
+        Some(1.0)
+    } else if value.upper_bound() < 0.0 {
+        Some(-1.0)
+    } else {
+        None
+    }
+}
+
+/// Replaces the coefficients of c(x) with those of c(x + s)
+fn taylor_shift(c: &mut [FInt], s: FInt) {
+    for i in 0..c.len().saturating_sub(1) {
+        for j in (i..c.len() - 1).rev() {
+            c[j] = c[j] + s * c[j + 1];
+        }
+    }
+}
+
+/// Descartes' bound on the number of roots of p in (a, b): the sign variations of the
+/// coefficients of (1 + x)^n p((a + b x) / (1 + x)), whose positive roots are the
+/// images of the roots of p in (a, b). It is exact when it is 0 or 1, and has the
+/// parity of the number of roots otherwise. None if the sign of a coefficient is
+/// hidden by rounding errors.
+fn descartes_bound(p: &XPoly, a: f64, b: f64) -> Option<usize> {
+    let point = |x: f64| FInt::new_with_bounds(x, x);
+    // p(a + (b - a) x)
+    let mut c = p.0.clone();
+    taylor_shift(&mut c, point(a));
+    let width = point(b) - point(a);
+    let mut power = point(1.0);
+    for coefficient in c.iter_mut() {
+        *coefficient = *coefficient * power;
+        power = power * width;
+    }
+    // x^n q(1 / x), then shifted by 1
+    c.reverse();
+    taylor_shift(&mut c, point(1.0));
+
+    let mut variations = 0;
+    let mut previous_sign = None;
+    for coefficient in c {
+        let sign = if coefficient.lower_bound() > 0.0 {
+            1.0
+        } else if coefficient.upper_bound() < 0.0 {
+            -1.0
+        } else if coefficient.lower_bound() == 0.0 && coefficient.upper_bound() == 0.0 {
+            continue;
+        } else {
+            return None;
+        };
+        if previous_sign.is_some_and(|previous| previous != sign) {
+            variations += 1;
+        }
+        previous_sign = Some(sign);
+    }
+    Some(variations)
+}
+
+/// Isolates the roots of the square-free polynomial p in (low, high) by bisection
+fn isolate(p: &XPoly, low: f64, high: f64) -> Vec<RealRoot> {
+    let mut roots = Vec::new();
+    if p.get_degree() == 0 || low >= high {
+        return roots;
+    }
+    let mut intervals = vec![(low, high)];
+    let mut examined = 0;
+    while let Some((a, b)) = intervals.pop() {
+        examined += 1;
+        let interval = FInt::new_with_bounds(a, b);
+        if p.evaluate(interval) != FInt::zero() {
+            continue;
+        }
+        let bound = if examined <= MAX_INTERVALS {
+            descartes_bound(p, a, b)
+        } else {
+            None
+        };
+        match bound {
+            Some(0) => continue,
+            Some(1) => {
+                roots.push(RealRoot {
+                    interval,
+                    isolated: true,
+                });
+                continue;
+            }
+            _ => {}
+        }
+        let magnitude = a.abs().max(b.abs()).max(1.0);
+        let split = if b - a > MIN_RELATIVE_WIDTH * magnitude && examined <= MAX_INTERVALS {
+            SPLIT_POINTS
+                .iter()
+                .map(|t| a + t * (b - a))
+                .find(|&m| sign_at(p, m).is_some())
+        } else {
+            None
+        };
+        match split {
+            Some(m) => {
+                intervals.push((m, b));
+                intervals.push((a, m));
+            }
+            None => roots.push(RealRoot {
+                interval,
+                isolated: false,
+            }),
+        }
+    }
+    roots.sort_by(|r, s| {
+        r.interval
+            .lower_bound()
+            .total_cmp(&s.interval.lower_bound())
+    });
+    roots
+}
+
+/// Narrows an isolated root of the square-free polynomial p by bisection
+fn refine_root(p: &XPoly, root: RealRoot, width: f64) -> RealRoot {
+    if !root.isolated {
+        return root;
+    }
+    let (mut a, mut b) = (root.interval.lower_bound(), root.interval.upper_bound());
+    // The root is simple, so p changes its sign there
+    let lower_sign = match (sign_at(p, a), sign_at(p, b)) {
+        (Some(sign), _) => sign,
+        (None, Some(sign)) => -sign,
+        (None, None) => return root,
+    };
+    while b - a > width {
+        let m = 0.5 * (a + b);
+        if m <= a || m >= b {
+            break;
+        }
+        match sign_at(p, m) {
+            Some(sign) if sign == lower_sign => a = m,
+            Some(_) => b = m,
+            None => break,
+        }
+    }
+    RealRoot {
+        interval: FInt::new_with_bounds(a, b),
+        isolated: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly(s: &str) -> Poly {
+        Poly::parse_equation(s).unwrap()
+    }
+
+    fn assert_roots(roots: &[RealRoot], expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?}", roots);
+        for (root, &x) in roots.iter().zip(expected) {
+            assert!(root.isolated, "{:?}", root);
+            assert!(
+                root.interval.lower_bound() - 1e-9 <= x && x <= root.interval.upper_bound() + 1e-9,
+                "{:?} doesn't contain {}",
+                root,
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn test_square_free_part() {
+        let p = poly("(a - 1)^3 * (a + 2)");
+        let square_free = square_free_part(&p, 0).unwrap();
+        assert!(square_free.is_proportional(&poly("(a - 1) * (a + 2)"), &mut None));
+        assert_eq!(square_free_part(&poly("a^2 + 1"), 0), Ok(poly("a^2 + 1")));
+    }
+
+    #[test]
+    fn test_isolate_real_roots() {
+        // Multiple roots count once, and 0 is the first split point tried
+        let p = poly("(a^2 - 2) * (a - 1)^2 * a");
+        let sqrt2 = 2f64.sqrt();
+        assert_roots(
+            &isolate_all_real_roots(&p, 0).unwrap(),
+            &[-sqrt2, 0.0, 1.0, sqrt2],
+        );
+        assert_roots(&isolate_real_roots(&p, 0, 0.5, 3.0).unwrap(), &[1.0, sqrt2]);
+        assert!(isolate_all_real_roots(&poly("a^2 + 1"), 0)
+            .unwrap()
+            .is_empty());
+        assert!(isolate_all_real_roots(&poly("5"), 0).unwrap().is_empty());
+
+        // Roots 1e-6 apart
+        let close = poly("(1000000*a - 1000001) * (a - 1)");
+        assert_roots(
+            &isolate_all_real_roots(&close, 0).unwrap(),
+            &[1.0, 1.000001],
+        );
+
+        // Wilkinson's polynomial of degree 10
+        let wilkinson = (1..=10)
+            .map(|k| poly(&format!("a - {}", k)))
+            .reduce(|product, factor| product.multiply(&factor))
+            .unwrap();
+        let expected: Vec<f64> = (1..=10).map(f64::from).collect();
+        assert_roots(&isolate_all_real_roots(&wilkinson, 0).unwrap(), &expected);
+
+        assert!(isolate_all_real_roots(&poly("a*b - 1"), 0).is_err());
+        assert!(isolate_all_real_roots(&poly("0"), 0).is_err());
+    }
+
+    #[test]
+    fn test_refine() {
+        let p = poly("a^3 - 2");
+        let roots = real_roots(&p, 0, -10.0, 10.0).unwrap();
+        let cbrt2 = 2f64.cbrt();
+        assert_roots(&roots, &[cbrt2]);
+        assert!(roots[0].interval.upper_bound() - roots[0].interval.lower_bound() <= 1e-11);
+
+        let isolated = isolate_real_roots(&p, 0, 0.0, 4.0).unwrap();
+        let refined = refine(&p, 0, &isolated[0], 1e-3).unwrap();
+        assert!(refined.interval.upper_bound() - refined.interval.lower_bound() <= 1e-3);
+        assert_roots(&[refined], &[cbrt2]);
+    }
+}
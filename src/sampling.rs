@@ -0,0 +1,498 @@
+//! Numeric check of the factors of an eliminated equation.
+//!
+//! Real solutions of the original system are found with Newton's method from random
+//! starting points. The system usually has one equation less than it has variables, so
+//! every step is the minimum-norm correction, which moves the point onto the nearby part
+//! of the solution curve. A factor of the eliminated equation that vanishes at the x and y
+//! of some solution belongs to the locus; a factor vanishing at none of them is most
+//! likely extraneous, e.g. introduced by a resultant.
+//!
+//! `Sampler::trace` follows the solutions from a known one instead, moving one variable
+//! at a time, which approximates a locus whose equation can't be found. `Sampler::follow`
+//! moves along the tangent of the solution curve, so it also passes the points where the
+//! curve turns back in every single variable.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use rand::Rng;
+
+use crate::poly::{Poly, PolyOperations, Var, VarSet};
+
+/// Starting points are drawn from [-SAMPLE_RADIUS, SAMPLE_RADIUS] in every variable
+const SAMPLE_RADIUS: f64 = 5.0;
+const MAX_NEWTON_STEPS: usize = 60;
+/// Residual of a converged solution, relative to the magnitude of the terms
+const SOLUTION_TOLERANCE: f64 = 1e-10;
+/// Value of a factor considered zero, relative to the magnitude of its terms
+const FACTOR_TOLERANCE: f64 = 1e-7;
+/// Points this far from the origin are considered diverged
+const DIVERGENCE_LIMIT: f64 = 1e8;
+/// Length of a projected direction below which the curve has no tangent along it
+const TANGENT_TOLERANCE: f64 = 1e-9;
+
+/// Solutions along a curve of solutions, in order, see `Sampler::follow`
+#[derive(Debug, Clone)]
+pub struct SolutionPath {
+    pub solutions: Vec<HashMap<Var, f64>>,
+    /// The path came back to its first solution, so the curve is closed
+    pub closed: bool,
+}
+
+/// Real solutions of a polynomial system, found by Newton's method
+pub struct Sampler<'a> {
+    polys: &'a [Rc<Poly>],
+    vars: Vec<Var>,
+    /// jacobian[i][j] is the derivative of polys[i] by vars[j]
+    jacobian: Vec<Vec<Poly>>,
+}
+
+impl<'a> Sampler<'a> {
+    pub fn new(polys: &'a [Rc<Poly>]) -> Self {
+        let mut var_set = VarSet::new();
+        for poly in polys {
+            poly.fill_in_variables(&mut var_set);
+        }
+        let vars: Vec<Var> = var_set.iter().collect();
+        let jacobian = polys
+            .iter()
+            .map(|p| vars.iter().map(|&v| p.get_derivative(v)).collect())
+            .collect();
+        Self {
+            polys,
+            vars,
+            jacobian,
+        }
+    }
+
+    /// Solutions reached from `attempts` random starting points. Starting points from
+    /// which Newton's method diverges or stalls at a singular point give no solution.
+    pub fn sample(&self, attempts: usize, rng: &mut impl Rng) -> Vec<HashMap<Var, f64>> {
+        (0..attempts)
+            .filter_map(|_| {
+                let start: Vec<f64> = self
+                    .vars
+                    .iter()
+                    .map(|_| rng.random_range(-SAMPLE_RADIUS..SAMPLE_RADIUS))
+                    .collect();
+                self.solve(start)
+            })
+            .collect()
+    }
+
+    /// Solutions reached by moving `parameter` away from its value in `start` by `step`
+    /// at a time, up to `steps` times in both directions, and solving for the other
+    /// variables from the previous solution. Tracing stops in a direction where Newton's
+    /// method fails, e.g. where the solutions turn back in `parameter`.
+    pub fn trace(
+        &self,
+        start: &HashMap<Var, f64>,
+        parameter: Var,
+        step: f64,
+        steps: usize,
+    ) -> Vec<HashMap<Var, f64>> {
+        let Some(column) = self.vars.iter().position(|&v| v == parameter) else {
+            return Vec::new();
+        };
+        let start: Vec<f64> = self
+            .vars
+            .iter()
+            .map(|v| start.get(v).copied().unwrap_or(0.0))
+            .collect();
+        let mut solutions = Vec::new();
+        for direction in [1.0, -1.0] {
+            let mut point = start.clone();
+            for _ in 0..steps {
+                point[column] += direction * step;
+                match self.newton(point, &[column]) {
+                    Some(solution) => {
+                        solutions.push(self.values_at(&solution));
+                        point = solution;
+                    }
+                    None => break,
+                }
+            }
+        }
+        solutions
+    }
+
+    /// Solutions along the curve of solutions through `start`, for a system with one
+    /// degree of freedom, spaced `step` apart. Each step goes along the tangent of the curve
+    /// and returns to it orthogonally to the tangent (pseudo-arclength continuation). The
+    /// path starts out increasing `direction`, and unless it closes, it is extended in the
+    /// other direction from `start` as well, with at most `steps` steps each way. None if
+    /// `direction` can't change along the curve at `start`.
+    pub fn follow(
+        &self,
+        start: &HashMap<Var, f64>,
+        direction: Var,
+        step: f64,
+        steps: usize,
+    ) -> Option<SolutionPath> {
+        let column = self.vars.iter().position(|&v| v == direction)?;
+        let start: Vec<f64> = self
+            .vars
+            .iter()
+            .map(|v| start.get(v).copied().unwrap_or(0.0))
+            .collect();
+        let mut unit = vec![0.0; self.vars.len()];
+        unit[column] = 1.0;
+        let tangent = self.tangent(&start, &unit)?;
+        let (forward, closed) = self.continue_path(&start, tangent.clone(), step, steps);
+        let backward = if closed {
+            Vec::new()
+        } else {
+            let reversed = tangent.iter().map(|t| -t).collect();
+            self.continue_path(&start, reversed, step, steps).0
+        };
+        let solutions = backward
+            .iter()
+            .rev()
+            .chain(std::iter::once(&start))
+            .chain(&forward)
+            .map(|point| self.values_at(point))
+            .collect();
+        Some(SolutionPath { solutions, closed })
+    }
+
+    /// A solution with the variables of `fixed` set to their values there, solving for
+    /// the others from their values in `start`
+    pub fn solve_with_fixed(
+        &self,
+        start: &HashMap<Var, f64>,
+        fixed: &HashMap<Var, f64>,
+    ) -> Option<HashMap<Var, f64>> {
+        let point: Vec<f64> = self
+            .vars
+            .iter()
+            .map(|v| fixed.get(v).or(start.get(v)).copied().unwrap_or(0.0))
+            .collect();
+        let columns: Vec<usize> = (0..self.vars.len())
+            .filter(|&j| fixed.contains_key(&self.vars[j]))
+            .collect();
+        let mut values = self.values_at(&self.newton(point, &columns)?);
+        // Variables outside of the equations take any value
+        values.extend(fixed);
+        Some(values)
+    }
+
+    fn solve(&self, point: Vec<f64>) -> Option<HashMap<Var, f64>> {
+        self.newton(point, &[])
+            .map(|solution| self.values_at(&solution))
+    }
+
+    /// Newton's method from `point`, keeping the variables in the `fixed` columns unchanged
+    fn newton(&self, mut point: Vec<f64>, fixed: &[usize]) -> Option<Vec<f64>> {
+        for _ in 0..MAX_NEWTON_STEPS {
+            let values = self.values_at(&point);
+            let (residuals, converged) = self.residuals(&values);
+            if converged {
+                return Some(point);
+            }
+            // Without their columns, the minimum-norm step doesn't move the fixed variables
+            let jacobian = self.jacobian_at(&values, fixed);
+            let step = minimum_norm_step(&jacobian, &residuals)?;
+            for (x, dx) in point.iter_mut().zip(step) {
+                *x -= dx;
+            }
+            if point
+                .iter()
+                .any(|x| !x.is_finite() || x.abs() > DIVERGENCE_LIMIT)
+            {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Points after `start` along the curve, and whether the curve led back to `start`
+    fn continue_path(
+        &self,
+        start: &[f64],
+        mut tangent: Vec<f64>,
+        step: f64,
+        steps: usize,
+    ) -> (Vec<Vec<f64>>, bool) {
+        let mut path = Vec::new();
+        let mut point = start.to_vec();
+        for i in 0..steps {
+            let predicted: Vec<f64> = point
+                .iter()
+                .zip(&tangent)
+                .map(|(x, t)| x + step * t)
+                .collect();
+            let Some(next) = self.correct(predicted, &tangent) else {
+                break;
+            };
+            // Projecting the previous tangent keeps the direction of travel
+            let Some(next_tangent) = self.tangent(&next, &tangent) else {
+                break;
+            };
+            let distance = next
+                .iter()
+                .zip(start)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f64>()
+                .sqrt();
+            if i > 1 && distance < step / 2.0 {
+                return (path, true);
+            }
+            path.push(next.clone());
+            point = next;
+            tangent = next_tangent;
+        }
+        (path, false)
+    }
+
+    /// Unit tangent of the solution curve at `point`: the projection of `direction` on
+    /// the kernel of the Jacobian, normalized. None if the projection vanishes.
+    fn tangent(&self, point: &[f64], direction: &[f64]) -> Option<Vec<f64>> {
+        let jacobian = self.jacobian_at(&self.values_at(point), &[]);
+        let along: Vec<f64> = jacobian
+            .iter()
+            .map(|row| row.iter().zip(direction).map(|(a, b)| a * b).sum())
+            .collect();
+        let correction = minimum_norm_step(&jacobian, &along)?;
+        let projected: Vec<f64> = direction
+            .iter()
+            .zip(correction)
+            .map(|(d, c)| d - c)
+            .collect();
+        let norm = projected.iter().map(|x| x * x).sum::<f64>().sqrt();
+        (norm > TANGENT_TOLERANCE).then(|| projected.iter().map(|x| x / norm).collect())
+    }
+
+    /// Newton's method from `predicted`, keeping the point on the hyperplane through
+    /// `predicted` orthogonal to `tangent`
+    fn correct(&self, mut point: Vec<f64>, tangent: &[f64]) -> Option<Vec<f64>> {
+        for _ in 0..MAX_NEWTON_STEPS {
+            let values = self.values_at(&point);
+            let (mut residuals, converged) = self.residuals(&values);
+            if converged {
+                return Some(point);
+            }
+            // The steps are orthogonal to the tangent, so the point stays on the hyperplane
+            let mut jacobian = self.jacobian_at(&values, &[]);
+            jacobian.push(tangent.to_vec());
+            residuals.push(0.0);
+            let step = minimum_norm_step(&jacobian, &residuals)?;
+            for (x, dx) in point.iter_mut().zip(step) {
+                *x -= dx;
+            }
+            if point
+                .iter()
+                .any(|x| !x.is_finite() || x.abs() > DIVERGENCE_LIMIT)
+            {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// The Jacobian at the point, with zero columns for the variables in `fixed`
+    fn jacobian_at(&self, values: &HashMap<Var, f64>, fixed: &[usize]) -> Vec<Vec<f64>> {
+        self.jacobian
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, d)| {
+                        if fixed.contains(&j) {
+                            0.0
+                        } else {
+                            evaluate(d, values).0
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn values_at(&self, point: &[f64]) -> HashMap<Var, f64> {
+        self.vars
+            .iter()
+            .copied()
+            .zip(point.iter().copied())
+            .collect()
+    }
+
+    /// Values of the equations, and whether all of them vanish within the tolerance
+    fn residuals(&self, values: &HashMap<Var, f64>) -> (Vec<f64>, bool) {
+        let mut converged = true;
+        let residuals = self
+            .polys
+            .iter()
+            .map(|p| {
+                let (value, magnitude) = evaluate(p, values);
+                converged &= value.abs() <= SOLUTION_TOLERANCE * magnitude.max(1.0);
+                value
+            })
+            .collect();
+        (residuals, converged)
+    }
+}
+
+/// Whether `poly` vanishes at the point, up to the rounding errors of its terms
+pub fn vanishes_at(poly: &Poly, values: &HashMap<Var, f64>) -> bool {
+    let (value, magnitude) = evaluate(poly, values);
+    value.abs() <= FACTOR_TOLERANCE * magnitude
+}
+
+/// Value of the polynomial and the sum of the absolute values of its terms
+fn evaluate(poly: &Poly, values: &HashMap<Var, f64>) -> (f64, f64) {
+    match poly {
+        Poly::Constant(c) => (*c as f64, (*c as f64).abs()),
+        Poly::Nested(v, coefficients) => {
+            let x = values.get(v).copied().unwrap_or(0.0);
+            coefficients
+                .iter()
+                .rev()
+                .fold((0.0, 0.0), |(value, magnitude), c| {
+                    let (c_value, c_magnitude) = evaluate(c, values);
+                    (value * x + c_value, magnitude * x.abs() + c_magnitude)
+                })
+        }
+    }
+}
+
+/// The shortest dx with J dx = f, that is J^T (J J^T)^-1 f, or None if J J^T is singular
+fn minimum_norm_step(jacobian: &[Vec<f64>], f: &[f64]) -> Option<Vec<f64>> {
+    let n = jacobian.len();
+    let mut system: Vec<Vec<f64>> = jacobian
+        .iter()
+        .zip(f)
+        .map(|(row_i, &f_i)| {
+            let mut row: Vec<f64> = jacobian
+                .iter()
+                .map(|row_j| row_i.iter().zip(row_j).map(|(a, b)| a * b).sum())
+                .collect();
+            row.push(f_i);
+            row
+        })
+        .collect();
+    let scale = system
+        .iter()
+        .flat_map(|row| row[..n].iter())
+        .fold(0.0f64, |m, x| m.max(x.abs()));
+
+    // Gaussian elimination with partial pivoting
+    for k in 0..n {
+        let pivot_row =
+            (k..n).max_by(|&a, &b| system[a][k].abs().total_cmp(&system[b][k].abs()))?;
+        if system[pivot_row][k].abs() <= 1e-12 * scale {
+            return None;
+        }
+        system.swap(k, pivot_row);
+        let (upper, lower) = system.split_at_mut(k + 1);
+        let pivot = &upper[k];
+        for row in lower.iter_mut() {
+            let factor = row[k] / pivot[k];
+            for (entry, &pivot_entry) in row[k..].iter_mut().zip(&pivot[k..]) {
+                *entry -= factor * pivot_entry;
+            }
+        }
+    }
+    let mut z = vec![0.0; n];
+    for k in (0..n).rev() {
+        let sum: f64 = (k + 1..n).map(|j| system[k][j] * z[j]).sum();
+        z[k] = (system[k][n] - sum) / system[k][k];
+    }
+
+    let m = jacobian.first().map_or(0, |row| row.len());
+    Some(
+        (0..m)
+            .map(|j| jacobian.iter().zip(&z).map(|(row, z_i)| row[j] * z_i).sum())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_trace_circle() {
+        // Moving a along the circle a^2 + b^2 = 25 from (3, 4) stops at a = 5, where
+        // the circle turns back
+        let polys = vec![Rc::new(Poly::new("a^2 + b^2 - 25").unwrap())];
+        let start = HashMap::from([(0, 3.0), (1, 4.0)]);
+        let solutions = Sampler::new(&polys).trace(&start, 0, 0.5, 20);
+        let a_values: Vec<f64> = solutions.iter().map(|s| s[&0]).collect();
+        assert!(a_values.contains(&4.5) && a_values.contains(&-4.5));
+        assert!(a_values.iter().all(|a| a.abs() <= 5.0));
+        for solution in &solutions {
+            assert!(solution[&1] >= 0.0);
+            assert!(vanishes_at(&polys[0], solution));
+        }
+    }
+
+    #[test]
+    fn test_follow_circle() {
+        // Following a^2 + b^2 = 25 from (3, 4) goes around the whole circle
+        let polys = vec![Rc::new(Poly::new("a^2 + b^2 - 25").unwrap())];
+        let sampler = Sampler::new(&polys);
+        let start = HashMap::from([(0, 3.0), (1, 4.0)]);
+        let path = sampler.follow(&start, 0, 0.1, 1000).unwrap();
+        assert!(path.closed);
+        assert!((path.solutions.len() as f64 - 10.0 * std::f64::consts::TAU * 5.0).abs() < 2.0);
+        assert_eq!(path.solutions[0], start);
+        // a increases first, going clockwise
+        assert!(path.solutions[1][&0] > 3.0 && path.solutions[1][&1] < 4.0);
+        assert!(path.solutions.iter().any(|s| s[&1] < -4.9));
+        for solution in &path.solutions {
+            assert!(vanishes_at(&polys[0], solution));
+        }
+
+        // The parabola b = a^2 is open: the path runs from one end to the other through start
+        let polys = vec![Rc::new(Poly::new("b - a^2").unwrap())];
+        let start = HashMap::from([(0, 1.0), (1, 1.0)]);
+        let path = Sampler::new(&polys).follow(&start, 0, 0.1, 20).unwrap();
+        assert!(!path.closed);
+        assert_eq!(path.solutions.len(), 41);
+        assert_eq!(path.solutions[20], start);
+        assert!(path.solutions.windows(2).all(|w| w[0][&0] < w[1][&0]));
+
+        // b can't change at the top of the circle
+        let polys = vec![Rc::new(Poly::new("a^2 + b^2 - 25").unwrap())];
+        let top = HashMap::from([(0, 0.0), (1, 5.0)]);
+        assert!(Sampler::new(&polys).follow(&top, 1, 0.1, 10).is_none());
+    }
+
+    #[test]
+    fn test_solve_with_fixed() {
+        // Fixing a = 3 on the circle a^2 + b^2 = 25 leaves b = 4 near b = 1
+        let polys = vec![Rc::new(Poly::new("a^2 + b^2 - 25").unwrap())];
+        let sampler = Sampler::new(&polys);
+        let start = HashMap::from([(0, 0.0), (1, 1.0)]);
+        let solution = sampler
+            .solve_with_fixed(&start, &HashMap::from([(0, 3.0), (2, 7.0)]))
+            .unwrap();
+        assert_eq!(solution[&0], 3.0);
+        assert!((solution[&1] - 4.0).abs() < 1e-9);
+        assert_eq!(solution[&2], 7.0);
+        assert!(sampler
+            .solve_with_fixed(&start, &HashMap::from([(0, 6.0)]))
+            .is_none());
+    }
+
+    #[test]
+    fn test_sample_circle_points() {
+        // The point (c, d) on the unit circle, with a = c + d and b = c - d
+        let polys: Vec<Rc<Poly>> = ["c^2 + d^2 - 1", "a - c - d", "b - c + d"]
+            .iter()
+            .map(|p| Rc::new(Poly::new(p).unwrap()))
+            .collect();
+        let mut rng = StdRng::seed_from_u64(1);
+        let solutions = Sampler::new(&polys).sample(16, &mut rng);
+        assert!(solutions.len() >= 8);
+        // The locus is a^2 + b^2 = 2, and the factor a + b - 5 is extraneous
+        let locus = Poly::new("a^2 + b^2 - 2").unwrap();
+        let extraneous = Poly::new("a + b - 5").unwrap();
+        for solution in &solutions {
+            assert!(vanishes_at(&locus, solution));
+            assert!(!vanishes_at(&extraneous, solution));
+        }
+    }
+}
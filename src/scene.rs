@@ -1,69 +1,752 @@
 use indexmap::IndexMap;
 use log::info;
+use regex::Regex;
 use sea_orm::prelude::*;
 use sea_orm::DatabaseConnection;
+use sea_orm::{IntoActiveModel, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::{HashSet, VecDeque};
-
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::budget::Budget;
+use crate::curve_analysis::{self, BoundingBox, SingularPoint};
+use crate::db::CurveCacheModel;
+use crate::db::SceneActiveModel;
 use crate::db::SceneEntity;
+use crate::db::SceneModel;
 use crate::db::SceneObjectEntity;
 use crate::db::SceneObjectModel;
-use crate::fint::FInt;
-use crate::poly::Poly;
+use crate::dd_int::DDXYPoly;
+use crate::dof_analysis::{self, DegreesOfFreedom};
+use crate::elimination::EliminationOrder;
+use crate::equation_compiler::{EquationCompiler, InitialConfiguration};
+use crate::equation_source::{EquationSource, PythonEquationSource};
+use crate::expression_checker::{Diagnostic, ExpressionChecker, ExpressionType};
+use crate::gp_pari_service::{TaskLimits, MAX_STACK_SIZE, MAX_TASK_TIMEOUT};
 use crate::poly::PolyConversion;
-use crate::poly_draw::{Color, XYPolyDraw};
-use crate::scene_object::{ObjectType, SceneError, SceneObject};
+use crate::poly::{Poly, PolyFormat, Var};
+use crate::poly_draw::{Color, PlotView, XYPolyDraw};
+use crate::progress::{self, Progress};
+use crate::sampling::Sampler;
+use crate::scene_object::tangent_line::CurveFunctions;
+use crate::scene_object::{ObjectStyle, ObjectType, SceneError, SceneObject};
 use crate::scene_utils::SceneUtils;
+use crate::variables::{self, VariableRegistry};
+use crate::warning::{Warning, WarningCode};
+
+/// Steps of the numeric fallback per diagonal of the view
+const NUMERIC_STEPS_PER_VIEW: usize = 1000;
+/// Steps of the numeric fallback in each direction of each parameter
+const NUMERIC_STEPS: usize = 4000;
+
+/// Largest unit length of the coordinates of `CenteredEquation`
+const MAX_CENTERED_SCALE: i64 = 1_000_000;
+
+/// Traced configurations nearest to a point that `Scene::configuration_at` starts from
+const CONFIGURATION_SEEDS: usize = 5;
+/// Steps along the motion of the scene that `Scene::animate` computes per view diagonal
+const ANIMATION_STEPS_PER_VIEW: usize = 200;
+/// Space that `View::fitting` leaves around the curve on each side, as a fraction of
+/// its width or height
+const VIEW_MARGIN: f64 = 0.1;
+/// Smallest width and height that `View::fitting` shows, for curves reduced to a point
+/// or a segment
+const MIN_VIEW_EXTENT: f64 = 1.0;
+
+/// Values of the variables of the system of a scene in one of its configurations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Configuration {
+    /// Values by variable name, like "a"
+    pub values: BTreeMap<String, f64>,
+    /// Positions of the point objects, by object name
+    pub points: BTreeMap<String, (f64, f64)>,
+    /// The objects and coordinates the variables stand for
+    pub variables: VariableRegistry,
+}
+
+impl Configuration {
+    fn new(values: &HashMap<Var, f64>, variables: &VariableRegistry) -> Self {
+        Self {
+            values: values
+                .iter()
+                .map(|(&var, &value)| (Poly::var_to_string(var), value))
+                .collect(),
+            points: Self::points(values, variables),
+            variables: variables.clone(),
+        }
+    }
+
+    /// Positions of the point objects whose coordinates are both among the values
+    fn points(
+        values: &HashMap<Var, f64>,
+        variables: &VariableRegistry,
+    ) -> BTreeMap<String, (f64, f64)> {
+        let mut coordinates: BTreeMap<String, (Option<f64>, Option<f64>)> = BTreeMap::new();
+        for (&var, &value) in values {
+            let Some(origin) = variables.get(var).filter(|origin| !origin.auxiliary) else {
+                continue;
+            };
+            let entry = coordinates.entry(origin.object.clone()).or_default();
+            match origin.coordinate.as_deref() {
+                Some("x") => entry.0 = Some(value),
+                Some("y") => entry.1 = Some(value),
+                _ => {}
+            }
+        }
+        coordinates
+            .into_iter()
+            .filter_map(|(object, coordinates)| match coordinates {
+                (Some(x), Some(y)) => Some((object, (x, y))),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Positions of the points of a scene along its one-dimensional motion, see
+/// `Scene::animate`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Animation {
+    /// Positions of the point objects in each frame, by object name. Points whose
+    /// coordinates are constants, like fixed points, are left out.
+    pub frames: Vec<BTreeMap<String, (f64, f64)>>,
+    /// The motion returns to where it started, so the frames can be played in a loop
+    pub closed: bool,
+}
+
+/// The equations of a scene with the values of its variables in the initial
+/// configuration, see `Scene::numeric_system`
+struct NumericSystem {
+    configuration: InitialConfiguration,
+    polys: Vec<Rc<Poly>>,
+    /// Coordinates of the free and sliding points, which are moved to trace the locus
+    parameters: BTreeSet<Var>,
+}
+
+impl NumericSystem {
+    /// Configurations reached from the initial one by moving each parameter in turn
+    fn trace(&self, step: f64) -> Vec<HashMap<Var, f64>> {
+        let sampler = Sampler::new(&self.polys);
+        self.parameters
+            .iter()
+            .flat_map(|&parameter| {
+                sampler.trace(&self.configuration.values, parameter, step, NUMERIC_STEPS)
+            })
+            .map(|solution| self.complete(solution))
+            .collect()
+    }
+
+    /// Adds the variables outside of the equations, at their initial values
+    fn complete(&self, mut solution: HashMap<Var, f64>) -> HashMap<Var, f64> {
+        for (&var, &value) in &self.configuration.values {
+            solution.entry(var).or_insert(value);
+        }
+        solution
+    }
+
+    /// The x and y variables of the point of a locus
+    fn locus_vars(&self, locus_name: &str) -> Result<(Var, Var), SceneError> {
+        let plot = self
+            .configuration
+            .plots
+            .iter()
+            .find(|p| p.name == locus_name)
+            .ok_or_else(|| SceneError::ObjectNotFound(locus_name.to_string()))?;
+        SceneUtils::parse_plot_vars(plot)
+    }
+}
+
+fn locus_point(solution: &HashMap<Var, f64>, (x_var, y_var): (Var, Var)) -> Option<(f64, f64)> {
+    Some((*solution.get(&x_var)?, *solution.get(&y_var)?))
+}
 
 #[derive(Debug)]
 pub struct PlotData {
     pub points: Vec<(u32, u32, Color)>,
+    /// Points that the curve may miss: interval evaluation could not prove a sign change near them
+    pub uncertain_points: Vec<(u32, u32)>,
+    /// Each factor of a reducible curve drawn in its own color, only with
+    /// `SceneOptions::color_factors`
+    pub factor_plots: Vec<FactorPlot>,
+    /// Ordered curve chains in plot coordinates, only with `SmoothingAlgorithm::Trace`
+    pub polylines: Option<Vec<Vec<(f64, f64)>>>,
     pub equation: String,
-    pub formatted_equations: Vec<String>,
+    pub formatted_equations: Vec<FormattedEquation>,
+    pub frame_equation: Option<String>,
+    pub frame_formatted_equations: Option<Vec<String>>,
+    /// The equation in coordinates centered on the view, only with
+    /// `SceneOptions::centered_scale`
+    pub centered_equation: Option<CenteredEquation>,
+    /// Rational parametrization of the curve, only with `SceneOptions::parametrize`
+    pub parametrization: Option<Parametrization>,
+    /// Nodes, cusps and isolated points of the curve inside the view, in world
+    /// coordinates, only with `SceneOptions::singular_points`
+    pub singular_points: Vec<SingularPoint>,
+    pub warnings: Vec<Warning>,
+    pub kind: LocusKind,
+    /// The points were found numerically, see `SceneOptions::fallback_numeric`
+    pub approximate: bool,
+    /// Style of the locus object
+    pub style: ObjectStyle,
 }
 
+/// Points of one factor of the curve and their color, for the legend
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactorPlot {
+    pub color: Color,
+    pub equation: FormattedEquation,
+    pub points: Vec<(u32, u32, Color)>,
+}
+
+/// Equation of a curve factor as plain text, LaTeX and MathML
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormattedEquation {
+    pub text: String,
+    pub latex: String,
+    pub mathml: String,
+}
+
+impl FormattedEquation {
+    pub fn new(poly: &Poly, x_var: Var, y_var: Var) -> Self {
+        Self {
+            text: poly.as_formatted_equation(x_var, y_var),
+            latex: poly.to_latex(x_var, y_var),
+            mathml: poly.to_mathml(x_var, y_var),
+        }
+    }
+}
+
+/// Equation of a curve in coordinates centered on the view, which has smaller coefficients
+/// than the world equation far from the origin. The world point (x, y) has the coordinates
+/// ((x - offset.0) / scale, (y - offset.1) / scale).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CenteredEquation {
+    /// The center of the view, rounded to integers
+    pub offset: (i64, i64),
+    pub scale: i64,
+    pub equation: String,
+    pub formatted_equations: Vec<FormattedEquation>,
+}
+
+impl CenteredEquation {
+    pub fn new(
+        curve: &CurveEquationAndFactors,
+        x_var: Var,
+        y_var: Var,
+        view: &View,
+        scale: i64,
+    ) -> Self {
+        let offset = (view.center.x.round() as i64, view.center.y.round() as i64);
+        // x -> offset.0 + scale * x and y -> offset.1 + scale * y
+        let to_centered =
+            |poly: &Poly| SceneUtils::to_frame(poly, x_var, y_var, offset, (scale, 0));
+        Self {
+            offset,
+            scale,
+            equation: PolyFormat::new().format(&to_centered(&curve.curve_equation)),
+            formatted_equations: curve
+                .factors
+                .iter()
+                .map(|factor| FormattedEquation::new(&to_centered(factor), x_var, y_var))
+                .collect(),
+        }
+    }
+}
+
+/// Rational parametrization (x(t), y(t)) of a curve, with `parameter` in place of t
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Parametrization {
+    pub parameter: String,
+    pub x: String,
+    pub y: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct View {
     pub center: Center,
     pub diagonal: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl View {
+    /// Rectangle of the plane shown in a plot of `width` x `height` pixels
+    pub fn plot_view(&self, width: u32, height: u32) -> PlotView {
+        // Logical bounds: wl and hl, with wl^2 + hl^2 = diagonal^2 and hl / wl = height / width = ratio
+        // wl = diagonal * sqrt(1 / (1 + ratio^2))
+        // hl = wl * ratio
+        let ratio = height as f64 / width as f64;
+        let wl = self.diagonal * (1.0 / (1.0 + ratio * ratio)).sqrt();
+        let hl = ratio * wl;
+        PlotView {
+            x_min: self.center.x - 0.5 * wl,
+            x_max: self.center.x + 0.5 * wl,
+            y_min: self.center.y - 0.5 * hl,
+            y_max: self.center.y + 0.5 * hl,
+        }
+    }
+
+    /// The view centered on a bounding box that shows all of it, with a margin, in a
+    /// plot of `width` x `height` pixels
+    pub fn fitting(bounding_box: &BoundingBox, width: u32, height: u32) -> View {
+        let ratio = height as f64 / width as f64;
+        let w = (bounding_box.x_max - bounding_box.x_min).max(MIN_VIEW_EXTENT);
+        let h = (bounding_box.y_max - bounding_box.y_min).max(MIN_VIEW_EXTENT);
+        // The smallest wl >= w with hl = wl * ratio >= h, see `plot_view`
+        let wl = (1.0 + 2.0 * VIEW_MARGIN) * w.max(h / ratio);
+        View {
+            center: Center {
+                x: 0.5 * (bounding_box.x_min + bounding_box.x_max),
+                y: 0.5 * (bounding_box.y_min + bounding_box.y_max),
+            },
+            diagonal: wl * (1.0 + ratio * ratio).sqrt(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Center {
     pub x: f64,
     pub y: f64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Plot {
     pub name: String,
     pub x: String,
     pub y: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CurveEquationAndFactors {
     pub curve_equation: Poly,
     pub factors: Vec<Poly>,
+    pub warnings: Vec<Warning>,
+    /// For each irreducible system, the equations its variables were eliminated with,
+    /// in order; None for curves that were not solved for, e.g. cached ones
+    #[serde(default)]
+    pub triangular_sets: Option<Vec<Vec<EliminatedVariable>>>,
+}
+
+/// A variable eliminated from a system and the equation it was eliminated with, which
+/// only depends on the variables eliminated after it, x and y. Given their values, its
+/// roots are the values of the variable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EliminatedVariable {
+    pub var: Var,
+    pub equation: Poly,
+}
+
+impl CurveEquationAndFactors {
+    /// What the real solutions of the curve equation in x_var and y_var look like
+    pub fn kind(&self, x_var: Var, y_var: Var) -> LocusKind {
+        curve_analysis::classify_locus(self, x_var, y_var)
+    }
+}
+
+/// Shape of the set of real points of a locus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LocusKind {
+    /// No real point satisfies the system: the curve equation is a nonzero constant,
+    /// or none of its factors has real zeros
+    Empty,
+    /// Every point satisfies the system: the curve equation is 0
+    FullPlane,
+    /// Finitely many points, in world coordinates
+    Points {
+        points: Vec<(f64, f64)>,
+    },
+    Curve,
+}
+
+/// Curve of a locus together with the variables of its x and y coordinates
+#[derive(Debug, Clone)]
+pub struct LocusCurve {
+    pub x_var: Var,
+    pub y_var: Var,
+    pub curve: CurveEquationAndFactors,
+}
+
+/// User coordinate frame: origin at the point `origin`, x-axis pointing towards `axis`
+/// (or along the world x-axis if not given). Both must be fixed points.
+/// The frame is not normalized: the unit length along both axes is |origin axis|.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub origin: String,
+    pub axis: Option<String>,
+}
+
+/// Origin and x-axis direction of a frame in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameVectors {
+    pub origin: (i64, i64),
+    pub axis: (i64, i64),
+}
+
+/// Algorithm used to eliminate the auxiliary variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EliminationMethod {
+    /// Pairwise pseudo-remainder sequences (resultants), see `Elimination`
+    Resultant,
+    /// Groebner basis with respect to a lex order, see `groebner::eliminate`
+    Groebner,
+}
+
+impl FromStr for EliminationMethod {
+    type Err = SceneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "resultant" => Ok(EliminationMethod::Resultant),
+            "groebner" => Ok(EliminationMethod::Groebner),
+            _ => Err(SceneError::InvalidProperties(format!(
+                "Unknown elimination method: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// How the detected curve cells are turned into the plotted points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmoothingAlgorithm {
+    /// Blur the cells into an intensity map on a coarser grid
+    Intensity,
+    /// Follow the curve into ordered polylines, see `XYPolyDraw::trace_polylines`
+    Trace,
+}
+
+impl FromStr for SmoothingAlgorithm {
+    type Err = SceneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "intensity" => Ok(SmoothingAlgorithm::Intensity),
+            "trace" => Ok(SmoothingAlgorithm::Trace),
+            _ => Err(SceneError::InvalidProperties(format!(
+                "Unknown smoothing algorithm: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// How the cells that the curve passes through are found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveTracer {
+    /// Subdivide the view down to the cells of the supersampled grid
+    Grid,
+    /// Also split grid cells without a detected sign change up to `max_depth`
+    /// times, see `XYPolyDraw::with_refinement`
+    Adaptive { max_depth: u32 },
+    /// Grid cells, also kept when the curve crosses their vertical edges, as counted
+    /// exactly by `XYPolyDraw::with_column_crossings`; for curves with vertical tangents
+    Columns,
+}
+
+impl CurveTracer {
+    pub const DEFAULT_MAX_DEPTH: u32 = 4;
+    /// Every level multiplies the work on undecided cells by up to 4
+    pub const MAX_DEPTH_LIMIT: u32 = 6;
+
+    pub fn new(name: &str, max_depth: Option<u32>) -> Result<Self, SceneError> {
+        match name {
+            "grid" => Ok(CurveTracer::Grid),
+            "columns" => Ok(CurveTracer::Columns),
+            "adaptive" => {
+                let max_depth = max_depth.unwrap_or(Self::DEFAULT_MAX_DEPTH);
+                if max_depth > Self::MAX_DEPTH_LIMIT {
+                    return Err(SceneError::InvalidProperties(format!(
+                        "Maximum refinement depth {} exceeds {}",
+                        max_depth,
+                        Self::MAX_DEPTH_LIMIT
+                    )));
+                }
+                Ok(CurveTracer::Adaptive { max_depth })
+            }
+            _ => Err(SceneError::InvalidProperties(format!(
+                "Unknown curve tracer: {}",
+                name
+            ))),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SceneOptions {
     pub reduce_factors: bool,
+    pub frame: Option<Frame>,
+    pub elimination_method: EliminationMethod,
+    /// How the next variable to eliminate is chosen by the resultant method
+    pub elimination_order: EliminationOrder,
+    /// Reuse elimination chains computed by previous plots (see `Elimination::incremental`)
+    pub incremental: bool,
+    pub smoothing: SmoothingAlgorithm,
+    /// Try to find a rational parametrization of irreducible curves
+    pub parametrize: bool,
+    pub tracer: CurveTracer,
+    /// Number of threads rasterizing the curve, 1 to draw on the calling thread
+    pub threads: usize,
+    /// Search the view for singular points of the curve, see `curve_analysis`
+    pub singular_points: bool,
+    /// Also draw each factor of a reducible curve separately, in its own color
+    pub color_factors: bool,
+    /// Compute the determinants eliminating univariate variables modulo primes,
+    /// see `modular_determinant`
+    pub modular_determinants: bool,
+    /// Timeout and stack limit of the Pari/GP tasks, e.g. for slow factorizations
+    pub pari_limits: TaskLimits,
+    /// If the elimination times out, plot points of the locus found numerically
+    /// instead, see `Scene::plot_numeric_locus`
+    pub fallback_numeric: bool,
+    /// Limits of the elimination, within those of the server (see `budget`)
+    pub budget: Budget,
+    /// Also give the equation in coordinates centered on the view, with this unit
+    /// length, see `CenteredEquation`
+    pub centered_scale: Option<i64>,
+    /// Eliminate the variables in this order, read from a saved trace, instead of
+    /// searching for each one (see `replay`)
+    pub replay: Option<Vec<Var>>,
 }
 
 impl Default for SceneOptions {
     fn default() -> Self {
         Self {
             reduce_factors: false,
+            frame: None,
+            elimination_method: EliminationMethod::Resultant,
+            elimination_order: EliminationOrder::MinDegree,
+            incremental: false,
+            smoothing: SmoothingAlgorithm::Intensity,
+            parametrize: false,
+            tracer: CurveTracer::Grid,
+            threads: 1,
+            singular_points: false,
+            color_factors: false,
+            modular_determinants: false,
+            pari_limits: TaskLimits::default(),
+            fallback_numeric: false,
+            budget: Budget::default(),
+            centered_scale: None,
+            replay: None,
         }
     }
 }
 
 impl SceneOptions {
     pub fn new(reduce_factors: bool) -> Self {
-        Self { reduce_factors }
+        Self {
+            reduce_factors,
+            frame: None,
+            elimination_method: EliminationMethod::Resultant,
+            elimination_order: EliminationOrder::MinDegree,
+            incremental: false,
+            smoothing: SmoothingAlgorithm::Intensity,
+            parametrize: false,
+            tracer: CurveTracer::Grid,
+            threads: 1,
+            singular_points: false,
+            color_factors: false,
+            modular_determinants: false,
+            pari_limits: TaskLimits::default(),
+            fallback_numeric: false,
+            budget: Budget::default(),
+            centered_scale: None,
+            replay: None,
+        }
+    }
+}
+
+/// Options of the plots of a scene, stored with it (see `SceneModel::options`).
+/// The parameters of plot requests override them, and the options that are set
+/// nowhere keep the defaults of `SceneOptions`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoredOptions {
+    pub reduce_factors: Option<bool>,
+    pub elimination_method: Option<String>,
+    /// "min_degree", "degree_product" or "brown"
+    pub elimination_order: Option<String>,
+    pub incremental: Option<bool>,
+    pub smoothing: Option<String>,
+    pub parametrize: Option<bool>,
+    /// "grid", "adaptive" or "columns", see `CurveTracer`
+    pub tracer: Option<String>,
+    /// Refinements of the adaptive tracer
+    pub max_depth: Option<u32>,
+    pub singular_points: Option<bool>,
+    pub color_factors: Option<bool>,
+    pub modular_determinants: Option<bool>,
+    /// Timeout of each Pari/GP task in seconds
+    pub pari_timeout: Option<u64>,
+    /// Largest Pari/GP stack in megabytes
+    pub pari_stack_mb: Option<u64>,
+    pub fallback_numeric: Option<bool>,
+    pub max_total_degree: Option<u32>,
+    pub max_terms: Option<usize>,
+    pub max_elimination_steps: Option<usize>,
+    /// Also give the equation in coordinates centered on the view
+    pub centered: Option<bool>,
+    /// Unit length of the centered coordinates, 1 by default
+    pub centered_scale: Option<i64>,
+}
+
+impl StoredOptions {
+    /// These options, with the ones they leave unset taken from `defaults`
+    pub fn or(self, defaults: &StoredOptions) -> StoredOptions {
+        StoredOptions {
+            reduce_factors: self.reduce_factors.or(defaults.reduce_factors),
+            elimination_method: self
+                .elimination_method
+                .or_else(|| defaults.elimination_method.clone()),
+            elimination_order: self
+                .elimination_order
+                .or_else(|| defaults.elimination_order.clone()),
+            incremental: self.incremental.or(defaults.incremental),
+            smoothing: self.smoothing.or_else(|| defaults.smoothing.clone()),
+            parametrize: self.parametrize.or(defaults.parametrize),
+            tracer: self.tracer.or_else(|| defaults.tracer.clone()),
+            max_depth: self.max_depth.or(defaults.max_depth),
+            singular_points: self.singular_points.or(defaults.singular_points),
+            color_factors: self.color_factors.or(defaults.color_factors),
+            modular_determinants: self.modular_determinants.or(defaults.modular_determinants),
+            pari_timeout: self.pari_timeout.or(defaults.pari_timeout),
+            pari_stack_mb: self.pari_stack_mb.or(defaults.pari_stack_mb),
+            fallback_numeric: self.fallback_numeric.or(defaults.fallback_numeric),
+            max_total_degree: self.max_total_degree.or(defaults.max_total_degree),
+            max_terms: self.max_terms.or(defaults.max_terms),
+            max_elimination_steps: self
+                .max_elimination_steps
+                .or(defaults.max_elimination_steps),
+            centered: self.centered.or(defaults.centered),
+            centered_scale: self.centered_scale.or(defaults.centered_scale),
+        }
+    }
+
+    /// Checks the options and applies them to the default `SceneOptions`
+    pub fn to_scene_options(&self) -> Result<SceneOptions, SceneError> {
+        let mut options = SceneOptions::new(self.reduce_factors.unwrap_or(false));
+        if let Some(method) = &self.elimination_method {
+            options.elimination_method = EliminationMethod::from_str(method)?;
+        }
+        if let Some(order) = &self.elimination_order {
+            options.elimination_order =
+                EliminationOrder::from_str(order).map_err(SceneError::InvalidProperties)?;
+        }
+        options.incremental = self.incremental.unwrap_or(false);
+        if let Some(smoothing) = &self.smoothing {
+            options.smoothing = SmoothingAlgorithm::from_str(smoothing)?;
+        }
+        options.parametrize = self.parametrize.unwrap_or(false);
+        if let Some(tracer) = &self.tracer {
+            options.tracer = CurveTracer::new(tracer, self.max_depth)?;
+        }
+        options.singular_points = self.singular_points.unwrap_or(false);
+        options.color_factors = self.color_factors.unwrap_or(false);
+        options.modular_determinants = self.modular_determinants.unwrap_or(false);
+        if let Some(seconds) = self.pari_timeout {
+            let timeout = Duration::from_secs(seconds);
+            if timeout.is_zero() || timeout > MAX_TASK_TIMEOUT {
+                return Err(SceneError::InvalidProperties(format!(
+                    "Pari/GP timeout must be between 1 and {} seconds",
+                    MAX_TASK_TIMEOUT.as_secs()
+                )));
+            }
+            options.pari_limits.timeout = Some(timeout);
+        }
+        if let Some(megabytes) = self.pari_stack_mb {
+            let max_stack_size = megabytes.saturating_mul(1 << 20);
+            if max_stack_size > MAX_STACK_SIZE {
+                return Err(SceneError::InvalidProperties(format!(
+                    "Pari/GP stack size exceeds {} MB",
+                    MAX_STACK_SIZE >> 20
+                )));
+            }
+            options.pari_limits.max_stack_size = Some(max_stack_size);
+        }
+        options.fallback_numeric = self.fallback_numeric.unwrap_or(false);
+        options.budget = Budget {
+            max_total_degree: self.max_total_degree,
+            max_terms: self.max_terms,
+            max_elimination_steps: self.max_elimination_steps,
+        };
+        if self.centered == Some(true) {
+            let scale = self.centered_scale.unwrap_or(1);
+            if !(1..=MAX_CENTERED_SCALE).contains(&scale) {
+                return Err(SceneError::InvalidProperties(format!(
+                    "Scale of centered coordinates must be between 1 and {}",
+                    MAX_CENTERED_SCALE
+                )));
+            }
+            options.centered_scale = Some(scale);
+        }
+        Ok(options)
+    }
+}
+
+/// Direct dependencies and dependents of the objects of a scene
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    /// Objects in scene order, with the objects they reference
+    dependencies: IndexMap<String, Vec<String>>,
+    dependents: HashMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    pub fn new(objects: &IndexMap<String, SceneObject>) -> Self {
+        let mut dependencies = IndexMap::new();
+        let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, object) in objects {
+            let mut direct = object.get_dependencies();
+            direct.sort_by_key(|dependency| objects.get_index_of(dependency));
+            direct.dedup();
+            for dependency in &direct {
+                dependents
+                    .entry(dependency.clone())
+                    .or_default()
+                    .push(name.clone());
+            }
+            dependencies.insert(name.clone(), direct);
+        }
+        Self {
+            dependencies,
+            dependents,
+        }
+    }
+
+    /// Objects referenced by `name`, in scene order
+    pub fn dependencies(&self, name: &str) -> &[String] {
+        self.dependencies.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Objects referencing `name`, in scene order
+    pub fn dependents(&self, name: &str) -> &[String] {
+        self.dependents.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// `name` and the objects that depend on it directly or indirectly, in scene order
+    pub fn transitive_dependents(&self, name: &str) -> Vec<String> {
+        if !self.dependencies.contains_key(name) {
+            return vec![name.to_string()];
+        }
+        let mut found = HashSet::from([name]);
+        let mut queue = VecDeque::from([name]);
+        while let Some(current) = queue.pop_front() {
+            for dependent in self.dependents(current) {
+                if found.insert(dependent) {
+                    queue.push_back(dependent);
+                }
+            }
+        }
+        self.dependencies
+            .keys()
+            .filter(|name| found.contains(name.as_str()))
+            .cloned()
+            .collect()
     }
 }
 
@@ -73,6 +756,18 @@ pub struct Scene {
     pub objects: IndexMap<String, SceneObject>,
     pub view: View,
     pub options: SceneOptions,
+    /// Version of the objects when they were loaded, see `SceneModel::bump_version`.
+    /// Each change to the objects moves it to the next version.
+    pub version: i32,
+    /// Version of the scene at the last change of each object
+    object_versions: HashMap<String, i32>,
+    /// Styles of the objects that don't have the default style
+    styles: HashMap<String, ObjectStyle>,
+    /// Gives the equations of the loci to solve for
+    equation_source: Arc<dyn EquationSource>,
+    /// Curves of the loci that tangent lines touch, by locus and the code of the
+    /// objects before the tangent line, see `to_python_with_curves`
+    tangent_curves: Mutex<HashMap<(String, String), CurveFunctions>>,
 }
 
 impl Scene {
@@ -85,9 +780,73 @@ impl Scene {
                 diagonal: 25.0,
             },
             options,
+            version: 0,
+            object_versions: HashMap::new(),
+            styles: HashMap::new(),
+            equation_source: Arc::new(PythonEquationSource),
+            tangent_curves: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Takes the equations of the loci from `source` instead of the objects
+    #[cfg(test)]
+    pub fn with_equation_source(mut self, source: impl EquationSource + 'static) -> Self {
+        self.equation_source = Arc::new(source);
+        self
+    }
+
+    /// Version of the scene at the last change of an object
+    pub fn object_version(&self, name: &str) -> i32 {
+        self.object_versions.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn style(&self, name: &str) -> ObjectStyle {
+        self.styles.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Changes how an object is drawn. This is a change of the scene like any other,
+    /// but the curves don't depend on it.
+    pub async fn set_style(
+        &mut self,
+        db: &DatabaseConnection,
+        name: &str,
+        style: ObjectStyle,
+    ) -> Result<(), SceneError> {
+        if !self.objects.contains_key(name) {
+            return Err(SceneError::ObjectNotFound(name.to_string()));
+        }
+        style.validate()?;
+
+        let version = self.next_version(db).await?;
+        SceneObjectModel::update_style(db, self.id, name, &style, version).await?;
+
+        self.object_versions.insert(name.to_string(), version);
+        if style.is_default() {
+            self.styles.remove(name);
+        } else {
+            self.styles.insert(name.to_string(), style);
+        }
+        Ok(())
+    }
+
+    /// Fails with `VersionConflict` unless the scene is at the `expected` version, if any
+    pub fn check_version(&self, expected: Option<i32>) -> Result<(), SceneError> {
+        match expected {
+            Some(expected) if expected != self.version => Err(SceneError::VersionConflict {
+                expected,
+                current: self.version,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Moves the scene to the version of a change, failing if it was changed since it
+    /// was loaded
+    async fn next_version(&mut self, db: &impl ConnectionTrait) -> Result<i32, SceneError> {
+        self.version = SceneModel::bump_version(db, self.id, self.version).await?;
+        Ok(self.version)
+    }
+
     pub async fn add_object(
         &mut self,
         db: &DatabaseConnection,
@@ -104,13 +863,251 @@ impl Scene {
         }
 
         // Save to database
-        SceneObjectModel::save_object(db, self.id, &name, object_type, properties).await?;
+        let version = self.next_version(db).await?;
+        SceneObjectModel::save_object(
+            db,
+            self.id,
+            &name,
+            object_type,
+            properties,
+            &ObjectStyle::default(),
+            version,
+        )
+        .await?;
+        CurveCacheModel::invalidate(db, self.id).await?;
 
         // Save to memory
+        self.object_versions.insert(name.clone(), version);
         self.objects.insert(name, scene_object);
         Ok(())
     }
 
+    /// Adds several objects with their styles in one transaction, in the given order,
+    /// e.g. to import a construction. Each object can depend on the scene's objects and
+    /// on the ones before it in the batch. Nothing is added if any of them is invalid.
+    pub async fn add_objects(
+        &mut self,
+        db: &DatabaseConnection,
+        objects: Vec<(String, ObjectType, Value, ObjectStyle)>,
+    ) -> Result<(), SceneError> {
+        let mut added: IndexMap<String, SceneObject> = IndexMap::new();
+        for (name, object_type, properties, style) in &objects {
+            if self.objects.contains_key(name) || added.contains_key(name) {
+                return Err(SceneError::InvalidProperties(format!(
+                    "Object {} already exists",
+                    name
+                )));
+            }
+            let scene_object = SceneObject::from_properties(*object_type, properties.clone())?;
+            for dependency in scene_object.get_dependencies() {
+                if !self.objects.contains_key(&dependency) && !added.contains_key(&dependency) {
+                    return Err(SceneError::DependencyNotFound(dependency));
+                }
+            }
+            style.validate()?;
+            added.insert(name.clone(), scene_object);
+        }
+
+        let txn = db
+            .begin()
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        let version = SceneModel::bump_version(&txn, self.id, self.version).await?;
+        for (name, object_type, properties, style) in &objects {
+            SceneObjectModel::save_object(
+                &txn,
+                self.id,
+                name,
+                *object_type,
+                properties.clone(),
+                style,
+                version,
+            )
+            .await?;
+        }
+        txn.commit()
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        self.version = version;
+        CurveCacheModel::invalidate(db, self.id).await?;
+
+        for (name, _, _, style) in objects {
+            self.object_versions.insert(name.clone(), version);
+            if !style.is_default() {
+                self.styles.insert(name, style);
+            }
+        }
+        self.objects.extend(added);
+        Ok(())
+    }
+
+    /// Replaces the definition of an object, keeping its position in the scene.
+    /// Objects only depend on earlier ones, so the new dependencies must precede it.
+    /// The objects that reference it must stay valid: the ones using it as a point or
+    /// a line need a value of the same type, and their expressions that type checked
+    /// before must still type check.
+    pub async fn update_object(
+        &mut self,
+        db: &DatabaseConnection,
+        name: &str,
+        object_type: ObjectType,
+        properties: Value,
+    ) -> Result<(), SceneError> {
+        let index = self
+            .objects
+            .get_index_of(name)
+            .ok_or_else(|| SceneError::ObjectNotFound(name.to_string()))?;
+        let scene_object = SceneObject::from_properties(object_type, properties.clone())?;
+
+        let dependents = self.collect_dependent_objects(name);
+        for dependency in scene_object.get_dependencies() {
+            match self.objects.get_index_of(&dependency) {
+                Some(dependency_index) if dependency_index < index => {}
+                Some(_) if dependency == name => {
+                    return Err(SceneError::DependencyCycle(format!(
+                        "{} would reference itself",
+                        name
+                    )))
+                }
+                Some(_) if dependents.contains(&dependency) => {
+                    return Err(SceneError::DependencyCycle(format!(
+                        "{} would depend on {}, which depends on it",
+                        name, dependency
+                    )))
+                }
+                Some(_) => {
+                    return Err(SceneError::InvalidProperties(format!(
+                        "{} can only depend on objects defined before it, not on {}",
+                        name, dependency
+                    )))
+                }
+                None => return Err(SceneError::DependencyNotFound(dependency)),
+            }
+        }
+
+        let mut objects = self.objects.clone();
+        objects[index] = scene_object;
+        self.check_dependents(name, &objects)?;
+
+        let version = self.next_version(db).await?;
+        SceneObjectModel::update_object(db, self.id, name, object_type, properties, version)
+            .await?;
+        CurveCacheModel::invalidate(db, self.id).await?;
+
+        self.object_versions.insert(name.to_string(), version);
+        self.objects = objects;
+        Ok(())
+    }
+
+    /// Checks that the objects referencing `name` are still valid in `updated_objects`,
+    /// where its definition has changed (see `update_object`)
+    fn check_dependents(
+        &self,
+        name: &str,
+        updated_objects: &IndexMap<String, SceneObject>,
+    ) -> Result<(), SceneError> {
+        let (old_object_type, new_object_type) = (
+            self.objects[name].get_type(),
+            updated_objects[name].get_type(),
+        );
+        let old_type = ExpressionType::of_object(old_object_type);
+        let new_type = ExpressionType::of_object(new_object_type);
+        let old_checker = ExpressionChecker::new(&self.objects);
+        let new_checker = ExpressionChecker::new(updated_objects);
+
+        for (dependent_name, dependent) in &self.objects {
+            if !dependent.get_dependencies().iter().any(|d| d == name) {
+                continue;
+            }
+            let expressions = dependent.get_expressions();
+            let used_in_expressions = expressions.iter().any(|expression| {
+                SceneUtils::extract_identifiers(&expression.to_string())
+                    .object_names
+                    .iter()
+                    .any(|n| n == name)
+            });
+            if !used_in_expressions && old_type != new_type {
+                return Err(SceneError::InvalidProperties(format!(
+                    "{} is used by {}, so it can't change from {} to {}",
+                    name, dependent_name, old_object_type, new_object_type
+                )));
+            }
+            for expression in expressions {
+                let expected_type = Some(ExpressionType::Scalar);
+                if old_checker.check(expression, expected_type).is_err() {
+                    // Only report what the update breaks
+                    continue;
+                }
+                if let Err(diagnostics) = new_checker.check(expression, expected_type) {
+                    let messages: Vec<String> =
+                        diagnostics.into_iter().map(|d| d.message).collect();
+                    return Err(SceneError::InvalidProperties(format!(
+                        "Expression {} of {} would become invalid: {}",
+                        expression,
+                        dependent_name,
+                        messages.join("; ")
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Renames an object and rewrites the references to it in the objects that
+    /// depend on it. Returns the names of the rewritten objects.
+    pub async fn rename_object(
+        &mut self,
+        db: &DatabaseConnection,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<Vec<String>, SceneError> {
+        if !self.objects.contains_key(old_name) {
+            return Err(SceneError::ObjectNotFound(old_name.to_string()));
+        }
+        if !Regex::new(r"^[a-zA-Z_]\w*$").unwrap().is_match(new_name) {
+            return Err(SceneError::InvalidProperties(format!(
+                "Invalid object name: {}",
+                new_name
+            )));
+        }
+        if self.objects.contains_key(new_name) {
+            return Err(SceneError::InvalidProperties(format!(
+                "Object {} already exists",
+                new_name
+            )));
+        }
+
+        let mut objects = IndexMap::new();
+        let mut dependents = Vec::new();
+        for (name, obj) in &self.objects {
+            if name == old_name {
+                objects.insert(new_name.to_string(), obj.clone());
+            } else if obj.get_dependencies().iter().any(|d| d == old_name) {
+                let renamed = obj.rename_reference(old_name, new_name)?;
+                dependents.push((name.clone(), renamed.get_properties()));
+                objects.insert(name.clone(), renamed);
+            } else {
+                objects.insert(name.clone(), obj.clone());
+            }
+        }
+
+        let version = self.next_version(db).await?;
+        SceneObjectModel::rename_object(db, self.id, old_name, new_name, &dependents, version)
+            .await?;
+        CurveCacheModel::invalidate(db, self.id).await?;
+
+        self.object_versions.remove(old_name);
+        self.object_versions.insert(new_name.to_string(), version);
+        if let Some(style) = self.styles.remove(old_name) {
+            self.styles.insert(new_name.to_string(), style);
+        }
+        for (name, _) in &dependents {
+            self.object_versions.insert(name.clone(), version);
+        }
+        self.objects = objects;
+        Ok(dependents.into_iter().map(|(name, _)| name).collect())
+    }
+
     pub async fn delete_object(
         &mut self,
         db: &DatabaseConnection,
@@ -120,11 +1117,15 @@ impl Scene {
         let mut objects_to_delete = self.collect_dependent_objects(name);
 
         // Delete all dependent objects from database in a single call
+        self.next_version(db).await?;
         SceneObjectModel::delete_objects(db, self.id, &objects_to_delete).await?;
+        CurveCacheModel::invalidate(db, self.id).await?;
 
         // Delete all objects from memory
         for obj_name in &objects_to_delete {
             self.objects.shift_remove(obj_name);
+            self.object_versions.remove(obj_name);
+            self.styles.remove(obj_name);
         }
 
         // Remove the target object from the list (we'll handle it separately)
@@ -133,6 +1134,82 @@ impl Scene {
         Ok(objects_to_delete)
     }
 
+    /// Copies the objects and the view into a new scene, named `name` or after this
+    /// scene. Returns the id and the name of the copy.
+    pub async fn duplicate(
+        &self,
+        db: &DatabaseConnection,
+        name: Option<String>,
+    ) -> Result<(i32, String), SceneError> {
+        let scene_model = SceneEntity::find_by_id(self.id)
+            .one(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?
+            .ok_or(SceneError::SceneNotFound(self.id))?;
+        // Scene names are unique among the scenes of a user, who also owns the copy
+        let names: HashSet<String> = SceneEntity::find()
+            .filter(match scene_model.user_id {
+                Some(user_id) => crate::db::SceneColumn::UserId.eq(user_id),
+                None => crate::db::SceneColumn::UserId.is_null(),
+            })
+            .all(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .map(|scene| scene.name)
+            .collect();
+        let name = match name.filter(|name| !name.is_empty()) {
+            Some(name) if names.contains(&name) => {
+                return Err(SceneError::InvalidProperties(format!(
+                    "Scene name already exists: {}",
+                    name
+                )))
+            }
+            Some(name) => name,
+            None => (1..)
+                .map(|i| match i {
+                    1 => format!("{} (copy)", scene_model.name),
+                    _ => format!("{} (copy {})", scene_model.name, i),
+                })
+                .find(|name| !names.contains(name))
+                .unwrap(),
+        };
+
+        let txn = db
+            .begin()
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        let copy = SceneActiveModel {
+            name: Set(name.clone()),
+            user_id: Set(scene_model.user_id),
+            view: Set(serde_json::to_string(&self.view)
+                .map_err(|e| SceneError::DatabaseError(e.to_string()))?),
+            options: Set(scene_model.options.clone()),
+            ..<SceneActiveModel as ActiveModelBehavior>::new()
+        }
+        .insert(&txn)
+        .await
+        .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+        // Objects are inserted in scene order, which is the order they are loaded in
+        for (object_name, obj) in &self.objects {
+            SceneObjectModel::save_object(
+                &txn,
+                copy.id,
+                object_name,
+                obj.get_type(),
+                obj.get_properties(),
+                &self.style(object_name),
+                0,
+            )
+            .await?;
+        }
+        txn.commit()
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
+
+        Ok((copy.id, name))
+    }
+
     pub async fn delete_scene(&mut self, db: &DatabaseConnection) -> Result<(), SceneError> {
         // Delete all scene objects from database (cascade will handle this automatically)
         // But we'll also delete them explicitly to be sure
@@ -155,33 +1232,13 @@ impl Scene {
         Ok(())
     }
 
-    /// Recursively collect all objects that depend on the given object
-    pub fn collect_dependent_objects(&self, target_name: &str) -> Vec<String> {
-        let mut to_delete = HashSet::new();
-        let mut queue = VecDeque::new();
-
-        // Start with the target object
-        queue.push_back(target_name.to_string());
-
-        while let Some(current_name) = queue.pop_front() {
-            if to_delete.contains(&current_name) {
-                continue; // Already processed
-            }
-
-            to_delete.insert(current_name.clone());
-
-            // Find all objects that depend on the current object
-            for (obj_name, obj) in &self.objects {
-                if !to_delete.contains(obj_name) {
-                    let dependencies = obj.get_dependencies();
-                    if dependencies.contains(&current_name) {
-                        queue.push_back(obj_name.clone());
-                    }
-                }
-            }
-        }
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph::new(&self.objects)
+    }
 
-        to_delete.into_iter().collect()
+    /// The given object and all objects that depend on it, in scene order
+    pub fn collect_dependent_objects(&self, target_name: &str) -> Vec<String> {
+        self.dependency_graph().transitive_dependents(target_name)
     }
 
     pub async fn load_objects_and_view(
@@ -195,26 +1252,53 @@ impl Scene {
             .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
 
         self.objects.clear();
+        self.object_versions.clear();
+        self.styles.clear();
         for db_scene_object in db_scene_objects {
             let scene_object = db_scene_object.get_scene_object()?;
+            let style = db_scene_object.get_style()?;
+            if !style.is_default() {
+                self.styles
+                    .insert(db_scene_object.object_name.clone(), style);
+            }
+            self.object_versions
+                .insert(db_scene_object.object_name.clone(), db_scene_object.version);
             self.objects
                 .insert(db_scene_object.object_name, scene_object);
         }
-        self.view = self.get_view(db).await?;
+        let scene_model = SceneEntity::find_by_id(self.id)
+            .one(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?
+            .ok_or(SceneError::SceneNotFound(self.id))?;
+        self.view = serde_json::from_str(&scene_model.view)
+            .map_err(|e| SceneError::DatabaseError(format!("Failed to parse view JSON: {}", e)))?;
+        self.version = scene_model.version;
         Ok(())
     }
 
-    pub async fn get_view(&self, db: &DatabaseConnection) -> Result<View, SceneError> {
+    pub async fn save_view(
+        &mut self,
+        db: &DatabaseConnection,
+        view: View,
+    ) -> Result<(), SceneError> {
         let scene_model = SceneEntity::find_by_id(self.id)
             .one(db)
             .await
             .map_err(|e| SceneError::DatabaseError(e.to_string()))?
-            .ok_or_else(|| SceneError::DatabaseError("Scene not found".to_string()))?;
-
-        let view: View = serde_json::from_str(&scene_model.view)
-            .map_err(|e| SceneError::DatabaseError(format!("Failed to parse view JSON: {}", e)))?;
+            .ok_or(SceneError::SceneNotFound(self.id))?;
+
+        let mut active_model = scene_model.into_active_model();
+        active_model.view =
+            Set(serde_json::to_string(&view)
+                .map_err(|e| SceneError::DatabaseError(e.to_string()))?);
+        active_model
+            .update(db)
+            .await
+            .map_err(|e| SceneError::DatabaseError(e.to_string()))?;
 
-        Ok(view)
+        self.view = view;
+        Ok(())
     }
 
     pub fn to_python(&self) -> String {
@@ -225,11 +1309,98 @@ impl Scene {
             .join("\n")
     }
 
+    /// Like `to_python`, but first solves for the loci that tangent lines touch,
+    /// so that their curve equations can be used as constraints. Each curve is solved
+    /// once per scene, from the code of the objects before the tangent line, which
+    /// already has the curves of earlier tangent lines.
+    pub fn to_python_with_curves(&self) -> Result<String, SceneError> {
+        let mut lines = Vec::new();
+        for (name, obj) in self.objects.iter() {
+            match obj {
+                SceneObject::TangentLine(tangent) => {
+                    let curve = self.get_locus_curve(&tangent.locus, lines.join("\n"))?;
+                    lines.push(tangent.to_python(name, Some(&curve)));
+                }
+                _ => lines.push(obj.to_python(name)),
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Solves for the curve of a locus in the scene given by `code`. Objects only
+    /// depend on earlier ones, so the objects before a tangent line include everything
+    /// its locus needs.
+    fn get_locus_curve(
+        &self,
+        locus_name: &str,
+        code: String,
+    ) -> Result<CurveFunctions, SceneError> {
+        let key = (locus_name.to_string(), code);
+        if let Some(curve) = self.lock_tangent_curves().get(&key) {
+            return Ok(curve.clone());
+        }
+        let (equations, plots, registry) = SceneUtils::to_equations_with_variables(key.1.clone())?;
+        let plot = plots
+            .iter()
+            .find(|p| p.name == locus_name)
+            .ok_or_else(|| SceneError::DependencyNotFound(locus_name.to_string()))?;
+        let curve = variables::with_registry(&registry, || {
+            SceneUtils::get_curve_equation_and_factors(
+                equations.iter().map(|s| s.as_str()).collect(),
+                plot,
+                self.options.clone(),
+            )
+        })?
+        .curve_equation;
+        let (x_var, y_var) = SceneUtils::parse_plot_vars(plot)?;
+        let curve = SceneUtils::to_curve_functions(&curve, x_var, y_var);
+        self.lock_tangent_curves().insert(key, curve.clone());
+        Ok(curve)
+    }
+
+    fn lock_tangent_curves(&self) -> MutexGuard<'_, HashMap<(String, String), CurveFunctions>> {
+        self.tangent_curves
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
     pub fn evaluate_initial_values(
         &self,
         expressions: &Vec<String>,
     ) -> Result<Vec<f64>, SceneError> {
-        SceneUtils::evaluate_initial_values(&self.to_python(), expressions)
+        SceneUtils::evaluate_initial_values(&self.to_python_with_curves()?, expressions)
+    }
+
+    /// Degrees of freedom of the constraints at the initial values of the objects, and
+    /// which free and sliding points they keep in place
+    pub fn degrees_of_freedom(&self) -> Result<DegreesOfFreedom, SceneError> {
+        let configuration =
+            EquationCompiler::compile_at_initial_values(&self.to_python_with_curves()?)
+                .map_err(|e| SceneError::InvalidEquation(e.to_string()))?;
+        dof_analysis::degrees_of_freedom(&configuration, &self.movable_points())
+    }
+
+    /// Names of the free and sliding points, which the user can drag
+    fn movable_points(&self) -> Vec<String> {
+        self.objects
+            .iter()
+            .filter(|(_, obj)| {
+                matches!(
+                    obj,
+                    SceneObject::FreePoint(_) | SceneObject::SlidingPoint(_)
+                )
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Parses and type checks an expression against the objects of the scene
+    pub fn check_expression(
+        &self,
+        expression: &str,
+        expected_type: Option<ExpressionType>,
+    ) -> Result<ExpressionType, Vec<Diagnostic>> {
+        ExpressionChecker::new(&self.objects).check(expression, expected_type)
     }
 
     pub fn validate_expression(&self, expression: String) -> Vec<String> {
@@ -259,7 +1430,16 @@ impl Scene {
         }
 
         // Validate function names
-        let allowed_function_names = vec!["sqrt", "d", "d_sqr", "cot", "Point", "Line", "Vector"];
+        let allowed_function_names = vec![
+            "sqrt",
+            "d",
+            "d_sqr",
+            "distance_sum",
+            "cot",
+            "Point",
+            "Line",
+            "Vector",
+        ];
         for function_name in &identifiers.function_names {
             if !allowed_function_names.contains(&function_name.as_str()) {
                 messages.push(format!(
@@ -290,27 +1470,140 @@ impl Scene {
         messages
     }
 
+    /// Hash of everything the curve equations of the loci depend on: the objects
+    /// and the elimination options. Cached curves are stored under this key, so it
+    /// is a SHA-256 digest of their JSON, which doesn't change between builds. The
+    /// budget is left out: a curve found within one limit is found within any other.
+    pub fn curve_hash(&self) -> String {
+        let objects: Vec<Value> = self
+            .objects
+            .iter()
+            .map(|(name, obj)| json!([name, obj.get_type().to_string(), obj.get_properties()]))
+            .collect();
+        let options = &self.options;
+        let key = json!({
+            "objects": objects,
+            "reduce_factors": options.reduce_factors,
+            "elimination_method": format!("{:?}", options.elimination_method),
+            "elimination_order": format!("{:?}", options.elimination_order),
+            "incremental": options.incremental,
+            "modular_determinants": options.modular_determinants,
+            "replay": options.replay,
+        });
+        Sha256::digest(key.to_string().as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// The objects and coordinates the variables of the equations of the scene stand for
+    pub fn variable_registry(&self) -> Result<VariableRegistry, SceneError> {
+        let (_, _, registry) = self.equation_source.equations(self)?;
+        Ok(registry)
+    }
+
     pub fn solve_and_plot(
         &self,
         locus_name: &str,
         width: u32,
         height: u32,
     ) -> Result<PlotData, SceneError> {
+        let locus_curve = match self.solve_locus_curve(locus_name) {
+            Err(e) if self.options.fallback_numeric && e.is_timeout() => {
+                info!("Plotting locus {} numerically: {}", locus_name, e);
+                return self.plot_numeric_locus(locus_name, width, height);
+            }
+            result => result?,
+        };
+        self.plot_locus_curve(locus_name, &locus_curve, width, height)
+    }
+
+    /// Approximate plot of a locus without its curve equation. Starting from the
+    /// initial configuration, each coordinate of the free and sliding points is moved
+    /// in turn while the constraints are solved numerically (see `Sampler::trace`), and
+    /// the positions of the locus point are drawn. Only the part of the locus connected
+    /// to the initial configuration is found.
+    pub fn plot_numeric_locus(
+        &self,
+        locus_name: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<PlotData, SceneError> {
+        let system = self.numeric_system()?;
+        let locus_vars = system.locus_vars(locus_name)?;
+        progress::report(|| Progress::Plotting);
+        let plot_view = self.view.plot_view(width, height);
+        let solutions = system.trace(self.view.diagonal / NUMERIC_STEPS_PER_VIEW as f64);
+        let pixels: HashSet<(u32, u32)> = solutions
+            .iter()
+            .filter_map(|solution| {
+                let (x, y) = locus_point(solution, locus_vars)?;
+                Some((plot_view.column(x, width)?, plot_view.row(y, height)?))
+            })
+            .collect();
+        let solutions = solutions.len();
+        info!(
+            "Numeric plot of locus {}: {} solutions, {} points",
+            locus_name,
+            solutions,
+            pixels.len()
+        );
+        let red = Color::new(255, 0, 0);
+        let mut points: Vec<(u32, u32, Color)> = pixels
+            .into_iter()
+            .map(|(column, row)| (column, row, red))
+            .collect();
+        points.sort_by_key(|&(column, row, _)| (row, column));
+
+        Ok(PlotData {
+            points,
+            uncertain_points: Vec::new(),
+            factor_plots: Vec::new(),
+            polylines: (self.options.smoothing == SmoothingAlgorithm::Trace).then(Vec::new),
+            equation: String::new(),
+            formatted_equations: Vec::new(),
+            frame_equation: None,
+            frame_formatted_equations: None,
+            centered_equation: None,
+            parametrization: None,
+            singular_points: Vec::new(),
+            warnings: vec![Warning::new(
+                WarningCode::NumericApproximation,
+                format!(
+                    "The curve equation was not found in time, showing {} numerically found points",
+                    solutions
+                ),
+            )],
+            kind: LocusKind::Curve,
+            approximate: true,
+            style: self.style(locus_name),
+        })
+    }
+
+    /// Eliminates the auxiliary variables to find the curve equation of a locus.
+    /// This is the expensive, view-independent part of `solve_and_plot`.
+    pub fn solve_locus_curve(&self, locus_name: &str) -> Result<LocusCurve, SceneError> {
         // Convert plot to equations
-        let (equations, plots) = SceneUtils::to_equations(self.to_python())?;
+        let (equations, plots, registry) = self.equation_source.equations(self)?;
         info!(
             "Found {} equations and {} plots",
             equations.len(),
             plots.len()
         );
-        let plot = plots.iter().find(|p| p.name == locus_name).unwrap();
-
-        // Get curve equation and factors
-        let curve_equation_and_factors = SceneUtils::get_curve_equation_and_factors(
-            equations.iter().map(|s| s.as_str()).collect(),
-            plot,
-            self.options.clone(),
-        )
+        let plot = plots
+            .iter()
+            .find(|p| p.name == locus_name)
+            .ok_or_else(|| SceneError::DependencyNotFound(locus_name.to_string()))?;
+
+        // Get curve equation and factors, naming the variables after the objects
+        // in errors and logs
+        let curve_equation_and_factors = variables::with_registry(&registry, || {
+            SceneUtils::get_curve_equation_and_factors(
+                equations.iter().map(|s| s.as_str()).collect(),
+                plot,
+                self.options.clone(),
+            )
+        })
         .map_err(|e| SceneError::InvalidEquation(e.to_string()))?;
 
         info!(
@@ -319,59 +1612,463 @@ impl Scene {
         );
 
         let (x_var, y_var) = SceneUtils::parse_plot_vars(plot)?;
-        // Convert to XYPoly
-        let xy_poly = curve_equation_and_factors
-            .curve_equation
-            .as_xy_poly(x_var, y_var)
-            .map_err(|e| SceneError::InvalidEquation(e.to_string()))?;
-        info!("XYPoly: {}", xy_poly);
+        Ok(LocusCurve {
+            x_var,
+            y_var,
+            curve: curve_equation_and_factors,
+        })
+    }
 
-        // Create drawer
-        let drawer = XYPolyDraw::new(xy_poly);
+    /// A configuration of the scene that puts the point of a locus at (x, y), e.g. to
+    /// show the construction at a point of the curve. The constraints are solved with
+    /// (x, y) fixed, starting from the traced configurations whose locus point is nearest
+    /// to (x, y), then from the initial one. None if none of them converges, typically
+    /// because (x, y) is not on the locus.
+    pub fn configuration_at(
+        &self,
+        locus_name: &str,
+        (x, y): (f64, f64),
+    ) -> Result<Option<Configuration>, SceneError> {
+        let system = self.numeric_system()?;
+        let (x_var, y_var) = system.locus_vars(locus_name)?;
+        let distance = |solution: &HashMap<Var, f64>| {
+            locus_point(solution, (x_var, y_var))
+                .map_or(f64::INFINITY, |(sx, sy)| (sx - x).hypot(sy - y))
+        };
+        let mut seeds = system.trace(self.view.diagonal / NUMERIC_STEPS_PER_VIEW as f64);
+        seeds.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        seeds.truncate(CONFIGURATION_SEEDS);
+        seeds.push(system.configuration.values.clone());
+
+        let sampler = Sampler::new(&system.polys);
+        let target = HashMap::from([(x_var, x), (y_var, y)]);
+        let solution = seeds
+            .iter()
+            .find_map(|seed| sampler.solve_with_fixed(seed, &target));
+        Ok(solution.map(|solution| {
+            Configuration::new(&system.complete(solution), &system.configuration.variables)
+        }))
+    }
 
-        // Logical bounds: wl and hl, with wl^2 + hl^2 = diagonal^2 and hl / wl = height / width = ratio
-        // wl = diagonal * sqrt(1 / (1 + ratio^2))
-        // hl = wl * ratio
-        let ratio = height as f64 / width as f64;
-        let wl = self.view.diagonal * (1.0 / (1.0 + ratio * ratio)).sqrt();
-        let hl = ratio * wl;
+    /// Positions of the points of the scene in `steps` frames evenly spaced along its
+    /// motion, when the constraints leave it one degree of freedom, like a free point
+    /// on a circle. The motion is followed numerically from the initial configuration
+    /// (see `Sampler::follow`); a closed motion is sampled once around, an open one from
+    /// end to end, where the constraints have no solution nearby or the path gets too
+    /// long. None if the scene has more or fewer degrees of freedom, or none of the free
+    /// and sliding points moves.
+    pub fn animate(&self, steps: usize) -> Result<Option<Animation>, SceneError> {
+        let system = self.numeric_system()?;
+        let dof = dof_analysis::degrees_of_freedom(&system.configuration, &self.movable_points())?;
+        if dof.degrees_of_freedom != 1 {
+            return Ok(None);
+        }
+        let sampler = Sampler::new(&system.polys);
+        let step = self.view.diagonal / ANIMATION_STEPS_PER_VIEW as f64;
+        let Some(path) = system.parameters.iter().find_map(|&parameter| {
+            sampler.follow(&system.configuration.values, parameter, step, NUMERIC_STEPS)
+        }) else {
+            return Ok(None);
+        };
+        info!(
+            "Animation: {} solutions, closed: {}",
+            path.solutions.len(),
+            path.closed
+        );
+
+        // The last frame of a closed motion would repeat the first one
+        let last = path.solutions.len() - 1;
+        let frames = (0..steps)
+            .map(|i| {
+                let index = if path.closed {
+                    i * path.solutions.len() / steps
+                } else {
+                    (i * last).checked_div(steps - 1).unwrap_or(0)
+                };
+                let values = system.complete(path.solutions[index].clone());
+                Configuration::points(&values, &system.configuration.variables)
+            })
+            .collect();
+        Ok(Some(Animation {
+            frames,
+            closed: path.closed,
+        }))
+    }
+
+    /// The system of the scene at its initial configuration, to solve numerically for
+    /// its configurations
+    fn numeric_system(&self) -> Result<NumericSystem, SceneError> {
+        let configuration =
+            EquationCompiler::compile_at_initial_values(&self.to_python_with_curves()?)
+                .map_err(|e| SceneError::InvalidEquation(e.to_string()))?;
+        let polys = configuration
+            .equations
+            .iter()
+            .map(|s| {
+                Poly::parse_equation(s)
+                    .map(Rc::new)
+                    .map_err(|e| SceneError::InvalidEquation(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let movable_points = self.movable_points();
+        let parameters = configuration
+            .values
+            .keys()
+            .copied()
+            .filter(|&var| {
+                configuration.variables.get(var).is_some_and(|origin| {
+                    movable_points.contains(&origin.object)
+                        && origin.coordinate.is_some()
+                        && !origin.auxiliary
+                })
+            })
+            .collect();
+        Ok(NumericSystem {
+            configuration,
+            polys,
+            parameters,
+        })
+    }
+
+    /// Redraws already solved locus curves after the view was panned or zoomed.
+    /// Only the rasterization is repeated, not the elimination.
+    pub fn replot(
+        &mut self,
+        view: View,
+        locus_curves: &[(String, LocusCurve)],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<(String, PlotData)>, SceneError> {
+        self.view = view;
+        locus_curves
+            .iter()
+            .map(|(locus_name, locus_curve)| {
+                let plot_data = self.plot_locus_curve(locus_name, locus_curve, width, height)?;
+                Ok((locus_name.clone(), plot_data))
+            })
+            .collect()
+    }
+
+    /// Draws the solved curve of the locus `locus_name` in the current view
+    pub fn plot_locus_curve(
+        &self,
+        locus_name: &str,
+        locus_curve: &LocusCurve,
+        width: u32,
+        height: u32,
+    ) -> Result<PlotData, SceneError> {
+        let (x_var, y_var) = (locus_curve.x_var, locus_curve.y_var);
+        let curve_equation_and_factors = &locus_curve.curve;
+        progress::report(|| Progress::Plotting);
+        let kind = curve_equation_and_factors.kind(x_var, y_var);
+        if kind != LocusKind::Curve {
+            return Ok(self.plot_degenerate_locus(locus_name, locus_curve, kind, width, height));
+        }
+        let drawer = self
+            .curve_drawer(&curve_equation_and_factors.curve_equation, x_var, y_var)?
+            .with_threads(self.options.threads);
+
+        let plot_view = self.view.plot_view(width, height);
         info!(
-            "Logical bounds: wl: {}, hl: {} for width = {} and height = {}",
-            wl, hl, width, height
+            "Logical bounds: {:?} for width = {} and height = {}",
+            plot_view, width, height
         );
 
-        let points = drawer.get_curve_points(
-            FInt::new_with_bounds(self.view.center.x - 0.5 * wl, self.view.center.x + 0.5 * wl),
-            FInt::new_with_bounds(self.view.center.y - 0.5 * hl, self.view.center.y + 0.5 * hl),
+        let classified_points = drawer.get_classified_curve_points(
+            plot_view.x_interval(),
+            plot_view.y_interval(),
             width * 4,
             height * 4,
         );
-        info!("Points: {}", points.len());
+        info!("Points: {}", classified_points.len());
+        let points: Vec<(u32, u32)> = classified_points.iter().map(|&(x, y, _)| (x, y)).collect();
 
-        // Get curve points
-        let points = drawer.get_curve_points_smoothed(points, width * 4, height * 4);
+        let (points, polylines) = match self.options.smoothing {
+            SmoothingAlgorithm::Intensity => {
+                let points = drawer.get_curve_points_smoothed(points, width * 4, height * 4);
+                (points, None)
+            }
+            SmoothingAlgorithm::Trace => {
+                let polylines = XYPolyDraw::trace_polylines(&points);
+                info!("Traced polylines: {}", polylines.len());
+                let points = XYPolyDraw::get_curve_points_traced(&polylines, 4);
+                // Cell centers, scaled down to the plot size
+                let polylines = polylines
+                    .iter()
+                    .map(|chain| {
+                        chain
+                            .iter()
+                            .map(|&(x, y)| ((x as f64 + 0.5) / 4.0, (y as f64 + 0.5) / 4.0))
+                            .collect()
+                    })
+                    .collect();
+                (points, Some(polylines))
+            }
+        };
         info!("Smoothed points: {}", points.len());
+        let uncertain_points = XYPolyDraw::get_uncertain_points(&points, &classified_points, 4);
 
-        let equation_str = format!("{}", curve_equation_and_factors.curve_equation);
-        let formatted_equations: Vec<String> = curve_equation_and_factors
+        let equation_str = PolyFormat::new().format(&curve_equation_and_factors.curve_equation);
+        let formatted_equations: Vec<FormattedEquation> = curve_equation_and_factors
             .factors
             .iter()
-            .map(|factor| factor.as_formatted_equation(x_var, y_var))
+            .map(|factor| FormattedEquation::new(factor, x_var, y_var))
             .collect();
 
+        let (frame_equation, frame_formatted_equations) = match &self.options.frame {
+            Some(frame) => {
+                let FrameVectors { origin, axis } = self.get_frame_vectors(frame)?;
+                let frame_equation = SceneUtils::to_frame(
+                    &curve_equation_and_factors.curve_equation,
+                    x_var,
+                    y_var,
+                    origin,
+                    axis,
+                );
+                let frame_formatted_equations = curve_equation_and_factors
+                    .factors
+                    .iter()
+                    .map(|factor| {
+                        SceneUtils::to_frame(factor, x_var, y_var, origin, axis)
+                            .as_formatted_equation(x_var, y_var)
+                    })
+                    .collect();
+                (
+                    Some(PolyFormat::new().format(&frame_equation)),
+                    Some(frame_formatted_equations),
+                )
+            }
+            None => (None, None),
+        };
+
+        let centered_equation = self.options.centered_scale.map(|scale| {
+            CenteredEquation::new(curve_equation_and_factors, x_var, y_var, &self.view, scale)
+        });
+
+        let mut warnings = curve_equation_and_factors.warnings.clone();
+        let parametrization = match curve_equation_and_factors.factors.as_slice() {
+            [factor] if self.options.parametrize => {
+                match SceneUtils::get_rational_parametrization(factor, x_var, y_var) {
+                    Ok(parametrization) => parametrization,
+                    Err(e) => {
+                        warnings.push(Warning::new(
+                            WarningCode::ParametrizationFailed,
+                            format!("Failed to parametrize the curve: {}", e),
+                        ));
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let singular_points = if self.options.singular_points {
+            match curve_analysis::find_singular_points(
+                &curve_equation_and_factors.curve_equation,
+                x_var,
+                y_var,
+                (plot_view.x_min, plot_view.x_max),
+                (plot_view.y_min, plot_view.y_max),
+            ) {
+                Ok(singular_points) => {
+                    info!("Singular points: {}", singular_points.len());
+                    singular_points
+                }
+                Err(e) => {
+                    warnings.push(Warning::new(
+                        WarningCode::SingularPointsSkipped,
+                        format!("Singular points were not searched for: {}", e),
+                    ));
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        let factor_plots =
+            if self.options.color_factors && curve_equation_and_factors.factors.len() > 1 {
+                self.plot_factors(locus_curve, &plot_view, width, height)?
+            } else {
+                Vec::new()
+            };
+
         Ok(PlotData {
             points,
+            uncertain_points,
+            factor_plots,
+            polylines,
             equation: equation_str,
             formatted_equations,
+            frame_equation,
+            frame_formatted_equations,
+            centered_equation,
+            parametrization,
+            singular_points,
+            warnings,
+            kind,
+            approximate: false,
+            style: self.style(locus_name),
         })
     }
+
+    /// Plot of a locus that is not a curve: nothing is drawn for an empty locus or the
+    /// whole plane, and isolated points are drawn as 3x3 squares
+    fn plot_degenerate_locus(
+        &self,
+        locus_name: &str,
+        locus_curve: &LocusCurve,
+        kind: LocusKind,
+        width: u32,
+        height: u32,
+    ) -> PlotData {
+        let (x_var, y_var) = (locus_curve.x_var, locus_curve.y_var);
+        let curve = &locus_curve.curve;
+        let plot_view = self.view.plot_view(width, height);
+        let red = Color::new(255, 0, 0);
+        let mut points = Vec::new();
+        let message = match &kind {
+            LocusKind::Empty => "The locus has no real points".to_string(),
+            LocusKind::FullPlane => "The locus is the whole plane".to_string(),
+            LocusKind::Points {
+                points: locus_points,
+            } => {
+                for &(x, y) in locus_points {
+                    if let (Some(column), Some(row)) =
+                        (plot_view.column(x, width), plot_view.row(y, height))
+                    {
+                        for dx in column.saturating_sub(1)..=(column + 1).min(width - 1) {
+                            for dy in row.saturating_sub(1)..=(row + 1).min(height - 1) {
+                                points.push((dx, dy, red));
+                            }
+                        }
+                    }
+                }
+                format!("The locus consists of {} points", locus_points.len())
+            }
+            LocusKind::Curve => unreachable!("curves are plotted by plot_locus_curve"),
+        };
+        info!("{}", message);
+        let mut warnings = curve.warnings.clone();
+        warnings.push(Warning::new(WarningCode::DegenerateLocus, message));
+        PlotData {
+            points,
+            uncertain_points: Vec::new(),
+            factor_plots: Vec::new(),
+            polylines: (self.options.smoothing == SmoothingAlgorithm::Trace).then(Vec::new),
+            equation: PolyFormat::new().format(&curve.curve_equation),
+            formatted_equations: curve
+                .factors
+                .iter()
+                .map(|factor| FormattedEquation::new(factor, x_var, y_var))
+                .collect(),
+            frame_equation: None,
+            frame_formatted_equations: None,
+            centered_equation: None,
+            parametrization: None,
+            singular_points: Vec::new(),
+            warnings,
+            kind,
+            approximate: false,
+            style: self.style(locus_name),
+        }
+    }
+
+    /// Rasterizer of the curve poly = 0 with the tracer of the scene options
+    fn curve_drawer(&self, poly: &Poly, x_var: Var, y_var: Var) -> Result<XYPolyDraw, SceneError> {
+        let xy_poly = poly
+            .as_xy_poly(x_var, y_var)
+            .map_err(|e| SceneError::InvalidEquation(e.to_string()))?;
+        info!("XYPoly: {}", xy_poly);
+        let mut drawer = XYPolyDraw::new(xy_poly);
+        if DDXYPoly::is_needed_for(poly) {
+            info!("Using double-double intervals for the curve points");
+            let dd_poly =
+                DDXYPoly::from_poly(poly, x_var, y_var).map_err(SceneError::InvalidEquation)?;
+            drawer = drawer.with_double_double(dd_poly);
+        }
+        match self.options.tracer {
+            CurveTracer::Grid => {}
+            CurveTracer::Adaptive { max_depth } => drawer = drawer.with_refinement(max_depth),
+            CurveTracer::Columns => drawer = drawer.with_column_crossings(),
+        }
+        Ok(drawer)
+    }
+
+    /// Draws each factor of a reducible curve in its own color, see `Color::of_factor`
+    fn plot_factors(
+        &self,
+        locus_curve: &LocusCurve,
+        plot_view: &PlotView,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<FactorPlot>, SceneError> {
+        let (x_var, y_var) = (locus_curve.x_var, locus_curve.y_var);
+        let factors = &locus_curve.curve.factors;
+        let drawers = factors
+            .iter()
+            .map(|factor| self.curve_drawer(factor, x_var, y_var))
+            .collect::<Result<Vec<_>, _>>()?;
+        let points = XYPolyDraw::get_curve_points_of_each(
+            &drawers,
+            plot_view.x_interval(),
+            plot_view.y_interval(),
+            width * 4,
+            height * 4,
+        );
+        Ok(factors
+            .iter()
+            .zip(drawers.iter().zip(points))
+            .enumerate()
+            .map(|(i, (factor, (drawer, points)))| {
+                let color = Color::of_factor(i);
+                FactorPlot {
+                    color,
+                    equation: FormattedEquation::new(factor, x_var, y_var),
+                    points: drawer.get_curve_points_colored(points, width * 4, height * 4, color),
+                }
+            })
+            .collect())
+    }
+
+    /// Returns the origin and the x-axis direction of the frame in world coordinates
+    pub fn get_frame_vectors(&self, frame: &Frame) -> Result<FrameVectors, SceneError> {
+        let origin = self.get_fixed_point_coordinates(&frame.origin)?;
+        let axis = match &frame.axis {
+            Some(axis_name) => {
+                let axis_point = self.get_fixed_point_coordinates(axis_name)?;
+                (axis_point.0 - origin.0, axis_point.1 - origin.1)
+            }
+            None => (1, 0),
+        };
+        if axis == (0, 0) {
+            return Err(SceneError::InvalidProperties(
+                "Frame axis point coincides with the origin".to_string(),
+            ));
+        }
+        Ok(FrameVectors { origin, axis })
+    }
+
+    fn get_fixed_point_coordinates(&self, name: &str) -> Result<(i64, i64), SceneError> {
+        match self.objects.get(name) {
+            Some(SceneObject::FixedPoint(point)) => Ok((point.x, point.y)),
+            Some(_) => Err(SceneError::InvalidProperties(format!(
+                "Frame point {} must be a fixed point",
+                name
+            ))),
+            None => Err(SceneError::ObjectNotFound(name.to_string())),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::{SceneActiveModel, SceneEntity, SceneObjectEntity};
-    use crate::service::{config, AppState, CreateSceneRequest, SceneInfo};
+    use crate::api::v1::scenes::{CreateSceneRequest, SceneInfo};
+    use crate::api::{config, AppState};
+    use crate::db::{
+        CurveCacheEntity, SceneActiveModel, SceneEntity, SceneObjectEntity, UserEntity, UserModel,
+    };
     use sea_orm::ActiveValue::Set;
     use sea_orm::{Database, Schema};
     use serde_json::json;
@@ -387,6 +2084,14 @@ mod tests {
             .await
             .unwrap();
         let stmt = schema.create_table_from_entity(SceneObjectEntity);
+        db.execute(db.get_database_backend().build(&stmt))
+            .await
+            .unwrap();
+        let stmt = schema.create_table_from_entity(CurveCacheEntity);
+        db.execute(db.get_database_backend().build(&stmt))
+            .await
+            .unwrap();
+        let stmt = schema.create_table_from_entity(UserEntity);
         db.execute(db.get_database_backend().build(&stmt))
             .await
             .unwrap();
@@ -477,6 +2182,229 @@ mod tests {
         assert!(scene.objects.get("P2").is_some());
     }
 
+    #[tokio::test]
+    async fn test_curve_cache_invalidation() {
+        let db = setup_test_db().await;
+        let mut scene = Scene::new(1, SceneOptions::default());
+        scene
+            .add_object(
+                &db,
+                "A".to_string(),
+                ObjectType::FreePoint,
+                json!({"value": "1, 2"}),
+            )
+            .await
+            .unwrap();
+        scene
+            .add_object(
+                &db,
+                "L1".to_string(),
+                ObjectType::Locus,
+                json!({"point": "A"}),
+            )
+            .await
+            .unwrap();
+        let hash = scene.curve_hash();
+        assert_eq!(hash, scene.curve_hash());
+        assert_eq!(hash.len(), 64);
+
+        let mut options = SceneOptions::new(true);
+        options.smoothing = SmoothingAlgorithm::Trace;
+        let mut other = Scene::new(1, options);
+        other.objects = scene.objects.clone();
+        assert_ne!(other.curve_hash(), hash);
+        // Options that only affect the drawing keep the hash
+        other.options = SceneOptions::default();
+        other.options.smoothing = SmoothingAlgorithm::Trace;
+        other.options.threads = 4;
+        assert_eq!(other.curve_hash(), hash);
+        let changes: [fn(&mut SceneOptions); 4] = [
+            |options| options.elimination_order = EliminationOrder::Brown,
+            |options| options.incremental = true,
+            |options| options.modular_determinants = true,
+            |options| options.replay = Some(vec![2, 3]),
+        ];
+        for change in changes {
+            other.options = SceneOptions::default();
+            change(&mut other.options);
+            assert_ne!(other.curve_hash(), hash);
+        }
+
+        let locus_curve = LocusCurve {
+            x_var: 0,
+            y_var: 1,
+            curve: CurveEquationAndFactors {
+                curve_equation: Poly::new("a - b").unwrap(),
+                factors: vec![Poly::new("a - b").unwrap()],
+                warnings: vec![],
+                triangular_sets: None,
+            },
+        };
+        let entry = CurveCacheModel::new_entry(1, "L1", &hash, &locus_curve).unwrap();
+        CurveCacheModel::save_curve(&db, entry).await.unwrap();
+        let cached = CurveCacheModel::find_curve(&db, 1, "L1", &hash)
+            .await
+            .unwrap();
+        assert!(cached.is_some());
+
+        // Changing the scene drops its cached curves
+        scene
+            .add_object(
+                &db,
+                "B".to_string(),
+                ObjectType::FixedPoint,
+                json!({"value": "0, 0"}),
+            )
+            .await
+            .unwrap();
+        assert_ne!(scene.curve_hash(), hash);
+        let cached = CurveCacheModel::find_curve(&db, 1, "L1", &hash)
+            .await
+            .unwrap();
+        assert!(cached.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_update_object() {
+        let db = setup_test_db().await;
+        let mut scene = Scene::new(1, SceneOptions::default());
+        for (name, object_type, properties) in [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("B", ObjectType::FixedPoint, json!({"value": "4, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "1, 2"})),
+            (
+                "L1",
+                ObjectType::LineAB,
+                json!({"point1": "A", "point2": "X"}),
+            ),
+            ("I1", ObjectType::Invariant, json!({"formula": "d(A, X)"})),
+        ] {
+            scene
+                .add_object(&db, name.to_string(), object_type, properties)
+                .await
+                .unwrap();
+        }
+
+        // X stays a point, so L1 and I1 remain valid
+        scene
+            .update_object(
+                &db,
+                "X",
+                ObjectType::Midpoint,
+                json!({"point1": "A", "point2": "B"}),
+            )
+            .await
+            .unwrap();
+        scene.load_objects_and_view(&db).await.unwrap();
+        assert_eq!(scene.objects.get_index_of("X"), Some(2));
+        assert_eq!(scene.objects["X"].get_type(), ObjectType::Midpoint);
+
+        // A line can't stand in for a point
+        let result = scene
+            .update_object(
+                &db,
+                "X",
+                ObjectType::LineAB,
+                json!({"point1": "A", "point2": "B"}),
+            )
+            .await;
+        assert!(matches!(result, Err(SceneError::InvalidProperties(_))));
+
+        // Changing the formula of the invariant breaks nothing, but only earlier
+        // objects may be referenced
+        scene
+            .update_object(
+                &db,
+                "I1",
+                ObjectType::Invariant,
+                json!({"formula": "d(B, X)"}),
+            )
+            .await
+            .unwrap();
+        let result = scene
+            .update_object(
+                &db,
+                "A",
+                ObjectType::Midpoint,
+                json!({"point1": "B", "point2": "B"}),
+            )
+            .await;
+        assert!(matches!(result, Err(SceneError::InvalidProperties(_))));
+        for (point1, point2) in [("A", "L1"), ("X", "B")] {
+            let result = scene
+                .update_object(
+                    &db,
+                    "X",
+                    ObjectType::Midpoint,
+                    json!({"point1": point1, "point2": point2}),
+                )
+                .await;
+            assert!(matches!(result, Err(SceneError::DependencyCycle(_))));
+        }
+        let result = scene
+            .update_object(&db, "Y", ObjectType::FreePoint, json!({"value": "1, 1"}))
+            .await;
+        assert!(matches!(result, Err(SceneError::ObjectNotFound(_))));
+
+        scene.load_objects_and_view(&db).await.unwrap();
+        assert_eq!(
+            scene.objects["I1"].get_properties(),
+            json!({"formula": "d(B, X)"})
+        );
+        assert_eq!(scene.objects["X"].get_type(), ObjectType::Midpoint);
+    }
+
+    #[tokio::test]
+    async fn test_rename_object() {
+        let db = setup_test_db().await;
+        let mut scene = Scene::new(1, SceneOptions::default());
+        for (name, object_type, properties) in [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "1, 2"})),
+            (
+                "L1",
+                ObjectType::LineAB,
+                json!({"point1": "A", "point2": "X"}),
+            ),
+            (
+                "I1",
+                ObjectType::Invariant,
+                json!({"formula": "d(A, X) + A.x + X.x"}),
+            ),
+            ("locusA", ObjectType::Locus, json!({"point": "X"})),
+        ] {
+            scene
+                .add_object(&db, name.to_string(), object_type, properties)
+                .await
+                .unwrap();
+        }
+
+        let mut rewritten = scene.rename_object(&db, "X", "Y").await.unwrap();
+        rewritten.sort();
+        assert_eq!(rewritten, vec!["I1", "L1", "locusA"]);
+
+        for scene_objects in [scene.objects.clone(), {
+            scene.load_objects_and_view(&db).await.unwrap();
+            scene.objects.clone()
+        }] {
+            let names: Vec<&str> = scene_objects.keys().map(|n| n.as_str()).collect();
+            assert_eq!(names, vec!["A", "Y", "L1", "I1", "locusA"]);
+            assert_eq!(
+                scene_objects["L1"].get_properties(),
+                json!({"point1": "A", "point2": "Y"})
+            );
+            assert_eq!(
+                scene_objects["I1"].get_properties(),
+                json!({"formula": "d(A, Y) + A.x + Y.x"})
+            );
+            assert_eq!(scene_objects["locusA"].get_dependencies(), vec!["Y"]);
+        }
+
+        for (old_name, new_name) in [("Y", "A"), ("Y", "1B"), ("Z", "B")] {
+            assert!(scene.rename_object(&db, old_name, new_name).await.is_err());
+        }
+    }
+
     #[tokio::test]
     async fn test_recursive_dependency_deletion() {
         let db = setup_test_db().await;
@@ -597,6 +2525,122 @@ mod tests {
         assert!(scene.objects.contains_key("P3"));
     }
 
+    #[tokio::test]
+    async fn test_dependency_graph() {
+        use actix_web::{http::StatusCode, test, web, App};
+        let db = setup_test_db().await;
+        let mut scene = Scene::new(1, SceneOptions::default());
+        let objects = [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("B", ObjectType::FixedPoint, json!({"value": "4, 0"})),
+            (
+                "M",
+                ObjectType::Midpoint,
+                json!({"point1": "B", "point2": "A"}),
+            ),
+            (
+                "l",
+                ObjectType::LineAB,
+                json!({"point1": "A", "point2": "M"}),
+            ),
+        ];
+        let batch = objects
+            .into_iter()
+            .map(|(name, object_type, properties)| {
+                (
+                    name.to_string(),
+                    object_type,
+                    properties,
+                    ObjectStyle::default(),
+                )
+            })
+            .collect();
+        scene.add_objects(&db, batch).await.unwrap();
+
+        let graph = scene.dependency_graph();
+        assert_eq!(graph.dependencies("M"), ["A", "B"]);
+        assert_eq!(graph.dependents("A"), ["M", "l"]);
+        assert!(graph.dependents("l").is_empty());
+        assert_eq!(graph.transitive_dependents("B"), ["B", "M", "l"]);
+        assert_eq!(scene.collect_dependent_objects("X"), ["X"]);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new(db).await))
+                .configure(config),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/api/v1/scenes/1/objects?include=dependencies")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body[2]["name"], "M");
+        assert_eq!(body[2]["object_type"], "Midpoint");
+        assert_eq!(body[2]["dependencies"], json!(["A", "B"]));
+        assert_eq!(body[2]["dependents"], json!(["l"]));
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/scenes/1/objects")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.as_array().unwrap().len(), 4);
+        assert!(body[2].get("dependencies").is_none());
+        let req = test::TestRequest::get()
+            .uri("/api/v1/scenes/1/objects?include=styles")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_delete_dry_run() {
+        use actix_web::{test, web, App};
+        let db = setup_test_db().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new(db).await))
+                .configure(config),
+        )
+        .await;
+        for (name, object_type, properties) in [
+            ("A", "FixedPoint", json!({"value": "0, 0"})),
+            ("B", "FixedPoint", json!({"value": "4, 0"})),
+            ("M", "Midpoint", json!({"point1": "A", "point2": "B"})),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/api/v1/scenes/1/objects")
+                .set_json(json!({
+                    "name": name,
+                    "object_type": object_type,
+                    "properties": properties
+                }))
+                .to_request();
+            test::call_service(&app, req).await;
+        }
+
+        let delete = |uri: &str| test::TestRequest::delete().uri(uri).to_request();
+        let resp =
+            test::call_service(&app, delete("/api/v1/scenes/1/objects/A?dry_run=true")).await;
+        assert_eq!(resp.headers().get("ETag").unwrap(), "\"3\"");
+        let body: Vec<String> = test::read_body_json(resp).await;
+        assert_eq!(body, ["M"]);
+        let req = test::TestRequest::get()
+            .uri("/api/v1/scenes/1/objects")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.as_array().unwrap().len(), 3);
+
+        let body: Vec<String> =
+            test::call_and_read_body_json(&app, delete("/api/v1/scenes/1/objects/A")).await;
+        assert_eq!(body, ["M"]);
+        let req = test::TestRequest::get()
+            .uri("/api/v1/scenes/1/objects")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body[0]["name"], "B");
+        assert_eq!(body.as_array().unwrap().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_python_generation() {
         let db = setup_test_db().await;
@@ -706,7 +2750,89 @@ is_constant(d(P1, P2))"#;
         // Create app state
         let app_state = AppState::new(db).await;
 
-        // Create test app
+        // Create test app
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(app_state))
+                .configure(config),
+        )
+        .await;
+
+        // Test creating scene with name
+        let create_request = CreateSceneRequest {
+            name: Some("My Test Scene".to_string()),
+        };
+        let req = test::TestRequest::post()
+            .uri("/scenes")
+            .set_json(&create_request)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body.get("id").is_some());
+        assert_eq!(body.get("name").unwrap().as_str().unwrap(), "My Test Scene");
+
+        // Test creating scene without name (should get default name)
+        let create_request = CreateSceneRequest { name: None };
+        let req = test::TestRequest::post()
+            .uri("/scenes")
+            .set_json(&create_request)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let scene_id = body.get("id").unwrap().as_i64().unwrap();
+        let scene_name = body.get("name").unwrap().as_str().unwrap();
+        assert_eq!(scene_name, format!("Scene {}", scene_id));
+
+        // Test creating scene with empty name (should get default name)
+        let create_request = CreateSceneRequest {
+            name: Some("".to_string()),
+        };
+        let req = test::TestRequest::post()
+            .uri("/scenes")
+            .set_json(&create_request)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let scene_id = body.get("id").unwrap().as_i64().unwrap();
+        let scene_name = body.get("name").unwrap().as_str().unwrap();
+        assert_eq!(scene_name, format!("Scene {}", scene_id));
+    }
+
+    #[tokio::test]
+    async fn test_clone_scene_via_rest() {
+        use actix_web::{test, web, App};
+        let db = setup_test_db().await;
+        let mut scene = Scene::new(1, SceneOptions::default());
+        for (name, object_type, properties) in [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "1, 2"})),
+            (
+                "L1",
+                ObjectType::LineAB,
+                json!({"point1": "A", "point2": "X"}),
+            ),
+        ] {
+            scene
+                .add_object(&db, name.to_string(), object_type, properties)
+                .await
+                .unwrap();
+        }
+        let view = View {
+            center: Center { x: 3.0, y: -1.0 },
+            diagonal: 10.0,
+        };
+        scene.save_view(&db, view).await.unwrap();
+
+        let app_state = AppState::new(db.clone()).await;
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(app_state))
@@ -714,53 +2840,124 @@ is_constant(d(P1, P2))"#;
         )
         .await;
 
-        // Test creating scene with name
-        let create_request = CreateSceneRequest {
-            name: Some("My Test Scene".to_string()),
-        };
         let req = test::TestRequest::post()
-            .uri("/scenes")
-            .set_json(&create_request)
+            .uri("/scenes/1/clone")
             .to_request();
-
         let resp = test::call_service(&app, req).await;
         assert!(resp.status().is_success());
-
         let body: serde_json::Value = test::read_body_json(resp).await;
-        assert!(body.get("id").is_some());
-        assert_eq!(body.get("name").unwrap().as_str().unwrap(), "My Test Scene");
+        assert_eq!(body["id"], 2);
+        assert_eq!(body["name"], "Test Scene (copy)");
 
-        // Test creating scene without name (should get default name)
-        let create_request = CreateSceneRequest { name: None };
         let req = test::TestRequest::post()
-            .uri("/scenes")
-            .set_json(&create_request)
+            .uri("/scenes/1/clone")
+            .set_json(&CreateSceneRequest {
+                name: Some("Branch".to_string()),
+            })
             .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["name"], "Branch");
+        let req = test::TestRequest::post()
+            .uri("/scenes/1/clone")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["name"], "Test Scene (copy 2)");
+        let req = test::TestRequest::post()
+            .uri("/scenes/1/clone")
+            .set_json(&CreateSceneRequest {
+                name: Some("Branch".to_string()),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        // The copy is independent of the original
+        scene.delete_object(&db, "L1").await.unwrap();
+        let mut copy = Scene::new(2, SceneOptions::default());
+        copy.load_objects_and_view(&db).await.unwrap();
+        let names: Vec<&str> = copy.objects.keys().map(|n| n.as_str()).collect();
+        assert_eq!(names, vec!["A", "X", "L1"]);
+        assert_eq!(
+            copy.objects["L1"].get_properties(),
+            json!({"point1": "A", "point2": "X"})
+        );
+        assert_eq!((copy.view.center.x, copy.view.diagonal), (3.0, 10.0));
 
+        let req = test::TestRequest::post()
+            .uri("/scenes/9/clone")
+            .to_request();
         let resp = test::call_service(&app, req).await;
-        assert!(resp.status().is_success());
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
 
-        let body: serde_json::Value = test::read_body_json(resp).await;
-        let scene_id = body.get("id").unwrap().as_i64().unwrap();
-        let scene_name = body.get("name").unwrap().as_str().unwrap();
-        assert_eq!(scene_name, format!("Scene {}", scene_id));
+    #[tokio::test]
+    async fn test_scene_options_via_rest() {
+        use crate::api::v1::plots::PlotQuery;
+        use actix_web::{test, web, App};
+        let db = setup_test_db().await;
+        let app_state = AppState::new(db.clone()).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(app_state.clone()))
+                .configure(config),
+        )
+        .await;
 
-        // Test creating scene with empty name (should get default name)
-        let create_request = CreateSceneRequest {
-            name: Some("".to_string()),
-        };
-        let req = test::TestRequest::post()
-            .uri("/scenes")
-            .set_json(&create_request)
+        let req = test::TestRequest::get()
+            .uri("/scenes/1/options")
             .to_request();
+        let body: StoredOptions = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body, StoredOptions::default());
 
+        let req = test::TestRequest::put()
+            .uri("/scenes/1/options")
+            .set_json(json!({"elimination_method": "groebner", "singular_points": true}))
+            .to_request();
         let resp = test::call_service(&app, req).await;
         assert!(resp.status().is_success());
+        let req = test::TestRequest::get()
+            .uri("/scenes/1/options")
+            .to_request();
+        let body: StoredOptions = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.elimination_method.as_deref(), Some("groebner"));
+        assert_eq!(body.singular_points, Some(true));
+
+        // Invalid options are not stored
+        for options in [
+            json!({"smoothing": "blur"}),
+            json!({"pari_timeout": 0}),
+            json!({"colour": 1}),
+        ] {
+            let req = test::TestRequest::put()
+                .uri("/scenes/1/options")
+                .set_json(options)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+        let req = test::TestRequest::put()
+            .uri("/scenes/9/options")
+            .set_json(json!({}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
 
-        let body: serde_json::Value = test::read_body_json(resp).await;
-        let scene_id = body.get("id").unwrap().as_i64().unwrap();
-        let scene_name = body.get("name").unwrap().as_str().unwrap();
-        assert_eq!(scene_name, format!("Scene {}", scene_id));
+        // Plot requests use the stored options unless their query overrides them
+        let query = web::Query::<PlotQuery>::from_query("singular_points=false").unwrap();
+        let options = app_state.scene_options(1, &query).await.unwrap();
+        assert_eq!(options.elimination_method, EliminationMethod::Groebner);
+        assert!(!options.singular_points);
+
+        // Copies of the scene keep its options
+        let req = test::TestRequest::post()
+            .uri("/scenes/1/clone")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/scenes/{}/options", body["id"]))
+            .to_request();
+        let copy: StoredOptions = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(copy.elimination_method.as_deref(), Some("groebner"));
     }
 
     #[tokio::test]
@@ -797,6 +2994,365 @@ is_constant(d(P1, P2))"#;
         assert_eq!(body[1].name, "Scene 2");
     }
 
+    #[tokio::test]
+    async fn test_versioned_api_errors() {
+        use actix_web::{test, web, App};
+        let db = setup_test_db().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new(db).await))
+                .configure(config),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/v1/scenes").to_request();
+        let body: Vec<SceneInfo> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.len(), 1);
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/scenes/999")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "scene_not_found");
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/scenes/1/objects")
+            .set_json(serde_json::json!({
+                "name": "M",
+                "object_type": "Midpoint",
+                "properties": {"point1": "A", "point2": "B"}
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "dependency_not_found");
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/scenes/1/objects:batch")
+            .set_json(serde_json::json!([
+                {"name": "A", "object_type": "FixedPoint", "properties": {"value": "0, 0"}},
+                {"name": "M", "object_type": "Midpoint", "properties": {"point1": "A", "point2": "B"}}
+            ]))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "dependency_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_add_objects() {
+        let db = setup_test_db().await;
+        let scene_id = SceneEntity::find().one(&db).await.unwrap().unwrap().id;
+        let mut scene = Scene::new(scene_id, SceneOptions::default());
+        scene
+            .add_object(
+                &db,
+                "A".to_string(),
+                ObjectType::FixedPoint,
+                json!({"value": "0, 0"}),
+            )
+            .await
+            .unwrap();
+
+        // M depends on B, defined after it
+        let batch = |m_point: &str| {
+            vec![
+                (
+                    "M".to_string(),
+                    ObjectType::Midpoint,
+                    json!({"point1": "A", "point2": m_point}),
+                    ObjectStyle::default(),
+                ),
+                (
+                    "B".to_string(),
+                    ObjectType::FixedPoint,
+                    json!({"value": "4, 2"}),
+                    ObjectStyle {
+                        show_label: Some(false),
+                        ..ObjectStyle::default()
+                    },
+                ),
+            ]
+        };
+        let result = scene.add_objects(&db, batch("B")).await;
+        assert!(matches!(result, Err(SceneError::DependencyNotFound(name)) if name == "B"));
+        scene.load_objects_and_view(&db).await.unwrap();
+        assert_eq!(scene.objects.len(), 1);
+
+        scene.add_objects(&db, batch("A")).await.unwrap();
+        assert_eq!(
+            scene.objects.keys().collect::<Vec<_>>(),
+            vec!["A", "M", "B"]
+        );
+        scene.load_objects_and_view(&db).await.unwrap();
+        assert_eq!(
+            scene.objects.keys().collect::<Vec<_>>(),
+            vec!["A", "M", "B"]
+        );
+        assert_eq!(scene.style("B").show_label, Some(false));
+        assert!(scene.style("M").is_default());
+
+        let result = scene.add_objects(&db, batch("A")).await;
+        assert!(matches!(result, Err(SceneError::InvalidProperties(_))));
+        scene.load_objects_and_view(&db).await.unwrap();
+        assert_eq!(scene.objects.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_version_conflicts() {
+        use actix_web::{http::StatusCode, test, web, App};
+        let db = setup_test_db().await;
+
+        // Two copies of the scene loaded at version 0: the second change is stale
+        let mut first = Scene::new(1, SceneOptions::default());
+        first.load_objects_and_view(&db).await.unwrap();
+        let mut second = Scene::new(1, SceneOptions::default());
+        second.load_objects_and_view(&db).await.unwrap();
+        first
+            .add_object(
+                &db,
+                "A".to_string(),
+                ObjectType::FixedPoint,
+                json!({"value": "0, 0"}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(first.version, 1);
+        let result = second.delete_object(&db, "A").await;
+        assert!(matches!(
+            result,
+            Err(SceneError::VersionConflict {
+                expected: 0,
+                current: 1
+            })
+        ));
+        second.load_objects_and_view(&db).await.unwrap();
+        assert_eq!(second.objects.len(), 1);
+        assert_eq!(second.object_version("A"), 1);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new(db).await))
+                .configure(config),
+        )
+        .await;
+        let add_point = |version: &str, name: &str| {
+            test::TestRequest::post()
+                .uri("/api/v1/scenes/1/objects")
+                .insert_header(("If-Match", version.to_string()))
+                .set_json(json!({
+                    "name": name,
+                    "object_type": "FixedPoint",
+                    "properties": {"value": "1, 2"}
+                }))
+                .to_request()
+        };
+        let resp = test::call_service(&app, add_point("\"1\"", "B")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["version"], 2);
+
+        // A client that didn't see B gets the current scene back
+        let resp = test::call_service(&app, add_point("\"1\"", "C")).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "version_conflict");
+        assert_eq!(body["current"]["version"], 2);
+        assert_eq!(body["current"]["objects"][1]["name"], "B");
+
+        let resp = test::call_service(&app, add_point("two", "C")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let req = test::TestRequest::get()
+            .uri("/api/v1/scenes/1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+    }
+
+    #[tokio::test]
+    async fn test_object_styles() {
+        use actix_web::{http::StatusCode, test, web, App};
+        let db = setup_test_db().await;
+        let mut scene = Scene::new(1, SceneOptions::default());
+        scene
+            .add_object(
+                &db,
+                "A".to_string(),
+                ObjectType::FixedPoint,
+                json!({"value": "0, 0"}),
+            )
+            .await
+            .unwrap();
+        let blue = ObjectStyle {
+            color: Some("#00f".to_string()),
+            ..ObjectStyle::default()
+        };
+        scene.set_style(&db, "A", blue.clone()).await.unwrap();
+        assert_eq!(scene.version, 2);
+        assert_eq!(scene.object_version("A"), 2);
+        let result = scene
+            .set_style(
+                &db,
+                "A",
+                ObjectStyle {
+                    stroke_width: Some(-1.0),
+                    ..ObjectStyle::default()
+                },
+            )
+            .await;
+        assert!(matches!(result, Err(SceneError::InvalidProperties(_))));
+
+        // The style moves with the object and is copied with the scene
+        scene.rename_object(&db, "A", "B").await.unwrap();
+        scene.load_objects_and_view(&db).await.unwrap();
+        assert_eq!(scene.style("B"), blue);
+        assert!(scene.style("A").is_default());
+        let (copy_id, _) = scene.duplicate(&db, None).await.unwrap();
+        let mut copy = Scene::new(copy_id, SceneOptions::default());
+        copy.load_objects_and_view(&db).await.unwrap();
+        assert_eq!(copy.style("B"), blue);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new(db).await))
+                .configure(config),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/api/v1/scenes/1/objects")
+            .set_json(json!({
+                "name": "C",
+                "object_type": "FixedPoint",
+                "properties": {"value": "1, 2"},
+                "style": {"description": "Center", "show_label": false}
+            }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            body["style"],
+            json!({"description": "Center", "show_label": false})
+        );
+
+        let req = test::TestRequest::put()
+            .uri("/api/v1/scenes/1/objects/B/style")
+            .set_json(json!({}))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["style"], json!({}));
+        let req = test::TestRequest::put()
+            .uri("/api/v1/scenes/1/objects/B/style")
+            .set_json(json!({"color": "blue"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/scenes/1")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let styles: Vec<&serde_json::Value> = body["objects"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|object| &object["style"])
+            .collect();
+        assert_eq!(
+            styles,
+            vec![
+                &json!({}),
+                &json!({"description": "Center", "show_label": false})
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scene_ownership() {
+        use actix_web::{http::StatusCode, test, web, App};
+        let db = setup_test_db().await;
+        let (_, alice) = UserModel::create(&db, "alice", false).await.unwrap();
+        let (_, bob) = UserModel::create(&db, "bob", false).await.unwrap();
+        let (_, admin) = UserModel::create(&db, "admin", true).await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(
+                    AppState::new(db.clone()).await.requiring_auth(),
+                ))
+                .configure(config),
+        )
+        .await;
+        let get = |uri: &str, token: &str| {
+            test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request()
+        };
+
+        let req = test::TestRequest::get().uri("/api/v1/scenes").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = test::call_service(&app, get("/api/v1/scenes", "guess")).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/scenes")
+            .insert_header(("Authorization", format!("Bearer {}", alice)))
+            .set_json(json!({"name": "Ellipses"}))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let uri = format!("/api/v1/scenes/{}", body["id"]);
+
+        let resp = test::call_service(&app, get(&uri, &alice)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = test::call_service(&app, get(&uri, &admin)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        // Bob can't tell the scene from a missing one, nor the one without an owner,
+        // also with a percent-encoded id
+        let encoded_id: String = body["id"]
+            .to_string()
+            .bytes()
+            .map(|byte| format!("%{:02X}", byte))
+            .collect();
+        let encoded_uri = format!("/api/v1/scenes/{}/objects", encoded_id);
+        let resp = test::call_service(&app, get(&encoded_uri, &alice)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        for uri in [uri.as_str(), "/api/v1/scenes/1", encoded_uri.as_str()] {
+            let resp = test::call_service(&app, get(uri, &bob)).await;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        }
+        let body: Vec<SceneInfo> =
+            test::call_and_read_body_json(&app, get("/api/v1/scenes", &bob)).await;
+        assert!(body.is_empty());
+        let body: Vec<SceneInfo> =
+            test::call_and_read_body_json(&app, get("/api/v1/scenes", &alice)).await;
+        assert_eq!(body.len(), 1);
+
+        let resp = test::call_service(&app, get("/api/v1/admin/scenes", &bob)).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body: serde_json::Value =
+            test::call_and_read_body_json(&app, get("/api/v1/admin/scenes", &admin)).await;
+        assert_eq!(body[0]["owner"], serde_json::Value::Null);
+        assert_eq!(body[1]["owner"], "alice");
+
+        // Without authentication, anonymous requests only see the scenes without an owner
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::new(db).await))
+                .configure(config),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/api/v1/scenes").to_request();
+        let body: Vec<SceneInfo> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1]);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn test_delete_scene() {
         let db = setup_test_db().await;
@@ -813,9 +3369,17 @@ is_constant(d(P1, P2))"#;
         let point_props = json!({
             "value": "10, 20"
         });
-        SceneObjectModel::save_object(&db, scene2.id, "P1", ObjectType::FixedPoint, point_props)
-            .await
-            .unwrap();
+        SceneObjectModel::save_object(
+            &db,
+            scene2.id,
+            "P1",
+            ObjectType::FixedPoint,
+            point_props,
+            &ObjectStyle::default(),
+            0,
+        )
+        .await
+        .unwrap();
 
         // Verify the scene and its object exist
         let scene_exists = SceneEntity::find_by_id(scene2.id)
@@ -864,6 +3428,260 @@ is_constant(d(P1, P2))"#;
         assert!(object_exists);
     }
 
+    #[test]
+    fn test_plot_colored_factors() {
+        // The lines a = 1 and b = 0
+        let factors = vec![Poly::new("a - 1").unwrap(), Poly::new("b").unwrap()];
+        let locus_curve = LocusCurve {
+            x_var: 0,
+            y_var: 1,
+            curve: CurveEquationAndFactors {
+                curve_equation: Poly::new("a*b - b").unwrap(),
+                factors,
+                warnings: vec![],
+                triangular_sets: None,
+            },
+        };
+        let mut scene = Scene::new(1, SceneOptions::default());
+        let plot_data = scene.plot_locus_curve("L", &locus_curve, 40, 40).unwrap();
+        assert!(plot_data.factor_plots.is_empty());
+
+        scene.options.color_factors = true;
+        let plot_data = scene.plot_locus_curve("L", &locus_curve, 40, 40).unwrap();
+        let [vertical, horizontal] = plot_data.factor_plots.as_slice() else {
+            panic!("Expected two factor plots");
+        };
+        assert_eq!(
+            (vertical.color, horizontal.color),
+            (Color::of_factor(0), Color::of_factor(1))
+        );
+        assert_eq!(horizontal.equation, plot_data.formatted_equations[1]);
+        // The brightest points lie on the line of their factor, a = 1 in column 22
+        // and b = 0 in row 20
+        let brightest = |factor_plot: &FactorPlot| -> Vec<(u32, u32)> {
+            factor_plot
+                .points
+                .iter()
+                .filter(|point| point.2 == factor_plot.color)
+                .map(|&(x, y, _)| (x, y))
+                .collect()
+        };
+        let (vertical_points, horizontal_points) = (brightest(vertical), brightest(horizontal));
+        assert!(!vertical_points.is_empty() && !horizontal_points.is_empty());
+        assert!(vertical_points.iter().all(|&(x, _)| x.abs_diff(22) <= 1));
+        assert!(horizontal_points.iter().all(|&(_, y)| y.abs_diff(20) <= 1));
+    }
+
+    #[test]
+    fn test_plot_numeric_locus() {
+        // X moves on the circle of radius 5 around A
+        let mut scene = Scene::new(1, SceneOptions::default());
+        let objects = [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "3, 4"})),
+            ("I", ObjectType::Invariant, json!({"formula": "d(A, X)"})),
+            ("L", ObjectType::Locus, json!({"point": "X"})),
+        ];
+        for (name, object_type, properties) in objects {
+            let object = SceneObject::from_properties(object_type, properties).unwrap();
+            scene.objects.insert(name.to_string(), object);
+        }
+        let style = ObjectStyle {
+            color: Some("#008000".to_string()),
+            ..ObjectStyle::default()
+        };
+        scene.styles.insert("L".to_string(), style.clone());
+        let plot_data = scene.plot_numeric_locus("L", 40, 40).unwrap();
+        assert!(plot_data.approximate);
+        assert_eq!(plot_data.style, style);
+        assert_eq!(
+            plot_data.warnings[0].code,
+            WarningCode::NumericApproximation
+        );
+        assert!(plot_data.points.len() > 30);
+        // Pixel centers are at most a pixel off the circle
+        let plot_view = scene.view.plot_view(40, 40);
+        let pixel = (plot_view.x_max - plot_view.x_min) / 40.0;
+        for &(column, row, _) in &plot_data.points {
+            let x = plot_view.x_min + (column as f64 + 0.5) * pixel;
+            let y = plot_view.y_max - (row as f64 + 0.5) * pixel;
+            assert!((x.hypot(y) - 5.0).abs() < pixel, "{:?}", (x, y));
+        }
+    }
+
+    #[test]
+    fn test_configuration_at() {
+        // M is the midpoint of B and X, with X on the circle of radius 5 around A
+        let mut scene = Scene::new(1, SceneOptions::default());
+        let objects = [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("B", ObjectType::FixedPoint, json!({"value": "10, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "3, 4"})),
+            ("I", ObjectType::Invariant, json!({"formula": "d(A, X)"})),
+            (
+                "M",
+                ObjectType::Midpoint,
+                json!({"point1": "B", "point2": "X"}),
+            ),
+            ("L", ObjectType::Locus, json!({"point": "M"})),
+        ];
+        for (name, object_type, properties) in objects {
+            let object = SceneObject::from_properties(object_type, properties).unwrap();
+            scene.objects.insert(name.to_string(), object);
+        }
+        let configuration = scene.configuration_at("L", (5.0, 2.5)).unwrap().unwrap();
+        let near = |(x, y): (f64, f64), (ex, ey): (f64, f64)| {
+            (x - ex).abs() < 1e-9 && (y - ey).abs() < 1e-9
+        };
+        assert!(near(configuration.points["X"], (0.0, 5.0)));
+        assert!(near(configuration.points["M"], (5.0, 2.5)));
+        assert!(!configuration.points.contains_key("A"));
+        assert_eq!(
+            configuration.values[&Poly::var_to_string(0)],
+            configuration.points["X"].0
+        );
+
+        // Points off the circle of radius 2.5 around (5, 0) are not on the locus
+        assert!(scene.configuration_at("L", (5.0, 4.0)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_tangent_curves_solved_once() {
+        // Factoring the curve needs Pari/GP
+        if crate::gp_pari_service::get_gp_pari_pool().is_err() {
+            return;
+        }
+        // Two tangent lines to the circle of radius 5 around A
+        let mut scene = Scene::new(1, SceneOptions::default());
+        let objects = [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "3, 4"})),
+            ("I", ObjectType::Invariant, json!({"formula": "d(A, X)"})),
+            ("L", ObjectType::Locus, json!({"point": "X"})),
+            (
+                "T1",
+                ObjectType::TangentLine,
+                json!({"locus": "L", "point": "X"}),
+            ),
+            (
+                "T2",
+                ObjectType::TangentLine,
+                json!({"locus": "L", "point": "X"}),
+            ),
+        ];
+        for (name, object_type, properties) in objects {
+            let object = SceneObject::from_properties(object_type, properties).unwrap();
+            scene.objects.insert(name.to_string(), object);
+        }
+        let code = scene.to_python_with_curves().unwrap();
+        assert!(code.contains("T1 = TangentLine(Curve("));
+        assert!(code.contains("T2 = TangentLine(Curve("));
+        // One curve for the objects before each tangent line, reused afterwards
+        assert_eq!(scene.lock_tangent_curves().len(), 2);
+        assert_eq!(scene.to_python_with_curves().unwrap(), code);
+        assert_eq!(scene.lock_tangent_curves().len(), 2);
+    }
+
+    #[test]
+    fn test_animate() {
+        // X goes around the circle of radius 5 around A, dragging the midpoint M of B and X
+        let mut scene = Scene::new(1, SceneOptions::default());
+        let objects = [
+            ("A", ObjectType::FixedPoint, json!({"value": "0, 0"})),
+            ("B", ObjectType::FixedPoint, json!({"value": "10, 0"})),
+            ("X", ObjectType::FreePoint, json!({"value": "3, 4"})),
+            ("I", ObjectType::Invariant, json!({"formula": "d(A, X)"})),
+            (
+                "M",
+                ObjectType::Midpoint,
+                json!({"point1": "B", "point2": "X"}),
+            ),
+        ];
+        for (name, object_type, properties) in objects {
+            let object = SceneObject::from_properties(object_type, properties).unwrap();
+            scene.objects.insert(name.to_string(), object);
+        }
+        let animation = scene.animate(12).unwrap().unwrap();
+        assert!(animation.closed);
+        assert_eq!(animation.frames.len(), 12);
+        assert_eq!(animation.frames[0]["X"], (3.0, 4.0));
+        for frame in &animation.frames {
+            let (x, y) = frame["X"];
+            let (mx, my) = frame["M"];
+            assert!((x.hypot(y) - 5.0).abs() < 1e-9);
+            assert!((mx - (x + 10.0) / 2.0).abs() < 1e-9 && (my - y / 2.0).abs() < 1e-9);
+        }
+        // The frames go around the circle once
+        assert!(animation.frames.iter().any(|frame| frame["X"].0 < -4.0));
+        assert!(animation.frames.iter().any(|frame| frame["X"].1 < -4.0));
+
+        // Without the invariant, X moves in two dimensions
+        scene.objects.shift_remove("I");
+        assert!(scene.animate(12).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_view_fitting() {
+        let bounding_box = BoundingBox {
+            x_min: -1.0,
+            x_max: 3.0,
+            y_min: 0.0,
+            y_max: 2.0,
+        };
+        for (width, height) in [(800, 600), (300, 900), (500, 500)] {
+            let view = View::fitting(&bounding_box, width, height);
+            assert_eq!(view.center, Center { x: 1.0, y: 1.0 });
+            let plot_view = view.plot_view(width, height);
+            assert!(plot_view.x_min < -1.0 && plot_view.x_max > 3.0);
+            assert!(plot_view.y_min < 0.0 && plot_view.y_max > 2.0);
+        }
+        // The 4 x 2 box is as wide as a view of the same shape, margins aside
+        let plot_view = View::fitting(&bounding_box, 400, 200).plot_view(400, 200);
+        assert!((plot_view.x_max - plot_view.x_min - 4.8).abs() < 1e-9);
+
+        let point = BoundingBox {
+            x_min: 2.0,
+            x_max: 2.0,
+            y_min: 5.0,
+            y_max: 5.0,
+        };
+        assert!(View::fitting(&point, 100, 100).diagonal > 1.0);
+    }
+
+    #[test]
+    fn test_centered_equation() {
+        use crate::poly::PolyOperations;
+        // Circle of radius 5 around (1000, -2000) and the line through its center x = y + 3000
+        let circle = Poly::new("a^2 + b^2 - 2000*a + 4000*b + 4999975").unwrap();
+        let line = Poly::new("a - b - 3000").unwrap();
+        let curve = CurveEquationAndFactors {
+            curve_equation: circle.multiply(&line),
+            factors: vec![circle, line],
+            warnings: vec![],
+            triangular_sets: None,
+        };
+        let view = View {
+            center: Center {
+                x: 1000.3,
+                y: -1999.6,
+            },
+            diagonal: 30.0,
+        };
+        let centered = CenteredEquation::new(&curve, 0, 1, &view, 1);
+        assert_eq!(centered.offset, (1000, -2000));
+        assert_eq!(centered.formatted_equations[0].text, "x² + y² = 25");
+        assert_eq!(centered.formatted_equations[1].text, "x - y = 0");
+        assert_eq!(
+            Poly::new(&centered.equation).unwrap(),
+            Poly::new("a^3 - a^2*b + a*b^2 - b^3 - 25*a + 25*b").unwrap()
+        );
+
+        // In units of 5, the circle has radius 1
+        let centered = CenteredEquation::new(&curve, 0, 1, &view, 5);
+        assert_eq!(centered.scale, 5);
+        assert_eq!(centered.formatted_equations[0].text, "x² + y² = 1");
+    }
+
     #[test]
     fn test_float_parsing_logic() {
         // Test the float parsing logic that would be used in evaluate_initial_values
@@ -978,6 +3796,7 @@ is_constant(d(P1, P2))"#;
             "B.length()",
             "Point(1, 2)",
             "A.x + B.y + 5",
+            "distance_sum(B, A, Point(0, 0))",
         ];
 
         for expression in valid_expressions {
@@ -1,19 +1,27 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::str::FromStr;
 use thiserror::Error;
 
+use crate::budget::BudgetExceeded;
+use crate::poly::PolyError;
+use crate::scene_utils::SceneUtils;
+
 // Module declarations for split files
+pub mod angle_bisector;
 pub mod computed_point;
 pub mod fixed_point;
 pub mod free_point;
 pub mod intersection_point;
 pub mod invariant;
 pub mod line_ab;
+pub mod line_intersection;
 pub mod locus;
 pub mod midpoint;
 pub mod pl_to_line;
+pub mod point_on_segment_ratio;
 pub mod point_to_line_distance_invariant;
 pub mod pp_bisector;
 pub mod pp_to_line;
@@ -21,19 +29,23 @@ pub mod projection;
 pub mod reflection;
 pub mod scaled_vector_point;
 pub mod sliding_point;
+pub mod tangent_line;
 pub mod two_line_angle_invariant;
 pub mod two_point_distance_invariant;
 
 // Re-export the structs from the modules
+use angle_bisector::AngleBisector;
 use computed_point::ComputedPoint;
 use fixed_point::FixedPoint;
 use free_point::FreePoint;
 use intersection_point::IntersectionPoint;
 use invariant::Invariant;
 use line_ab::LineAB;
+use line_intersection::LineIntersection;
 use locus::Locus;
 use midpoint::Midpoint;
 use pl_to_line::PlToLine;
+use point_on_segment_ratio::PointOnSegmentRatio;
 use point_to_line_distance_invariant::PointToLineDistanceInvariant;
 use pp_bisector::PpBisector;
 use pp_to_line::PpToLine;
@@ -41,6 +53,7 @@ use projection::Projection;
 use reflection::Reflection;
 use scaled_vector_point::ScaledVectorPoint;
 use sliding_point::SlidingPoint;
+use tangent_line::TangentLine;
 use two_line_angle_invariant::TwoLineAngleInvariant;
 use two_point_distance_invariant::TwoPointDistanceInvariant;
 
@@ -54,12 +67,54 @@ pub enum SceneError {
     InvalidPointFormat(String),
     #[error("Object not found: {0}")]
     ObjectNotFound(String),
+    #[error("Scene not found: {0}")]
+    SceneNotFound(i32),
     #[error("Referenced object not found: {0}")]
     DependencyNotFound(String),
     #[error("Database error: {0}")]
     DatabaseError(String),
     #[error("Invalid equation: {0}")]
     InvalidEquation(String),
+    #[error("{0}")]
+    Arithmetic(#[from] PolyError),
+    #[error("Scene was changed: it is at version {current}, not {expected}")]
+    VersionConflict { expected: i32, current: i32 },
+    /// An object would depend on itself, directly or through other objects
+    #[error("Dependency cycle: {0}")]
+    DependencyCycle(String),
+    /// The Python equation processor failed to run or gave unexpected output
+    #[error("{0}")]
+    Python(String),
+    /// A Pari/GP or Singular task failed
+    #[error("{0}")]
+    Pari(String),
+    /// A Pari/GP or Singular task gave up after its timeout
+    #[error("{0}")]
+    EliminationTimeout(String),
+    /// The computation was stopped, e.g. because its job was cancelled
+    #[error("The computation was cancelled")]
+    Cancelled,
+    /// The computation hit a limit of its budget, see `budget::with_budget`
+    #[error("{0}")]
+    BudgetExceeded(#[from] BudgetExceeded),
+}
+
+impl SceneError {
+    /// Error of a failed Pari/GP or Singular task, from its message
+    pub fn from_task_error(message: String) -> Self {
+        if message.contains("timed out") {
+            SceneError::EliminationTimeout(message)
+        } else if message.contains("cancelled") {
+            SceneError::Cancelled
+        } else {
+            SceneError::Pari(message)
+        }
+    }
+
+    /// Whether a Pari/GP or Singular task gave up after its timeout
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, SceneError::EliminationTimeout(_))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -67,7 +122,9 @@ pub enum SceneObject {
     FixedPoint(FixedPoint),
     FreePoint(FreePoint),
     Midpoint(Midpoint),
+    PointOnSegmentRatio(PointOnSegmentRatio),
     IntersectionPoint(IntersectionPoint),
+    LineIntersection(LineIntersection),
     SlidingPoint(SlidingPoint),
     Projection(Projection),
     Reflection(Reflection),
@@ -75,8 +132,10 @@ pub enum SceneObject {
     ComputedPoint(ComputedPoint),
     LineAB(LineAB),
     PpBisector(PpBisector),
+    AngleBisector(AngleBisector),
     PpToLine(PpToLine),
     PlToLine(PlToLine),
+    TangentLine(TangentLine),
     Parameter,
     TwoPointDistanceInvariant(TwoPointDistanceInvariant),
     PointToLineDistanceInvariant(PointToLineDistanceInvariant),
@@ -91,9 +150,15 @@ impl SceneObject {
             ObjectType::FixedPoint => Ok(SceneObject::FixedPoint(FixedPoint::new(properties)?)),
             ObjectType::FreePoint => Ok(SceneObject::FreePoint(FreePoint::new(properties)?)),
             ObjectType::Midpoint => Ok(SceneObject::Midpoint(Midpoint::new(properties)?)),
+            ObjectType::PointOnSegmentRatio => Ok(SceneObject::PointOnSegmentRatio(
+                PointOnSegmentRatio::new(properties)?,
+            )),
             ObjectType::IntersectionPoint => Ok(SceneObject::IntersectionPoint(
                 IntersectionPoint::new(properties)?,
             )),
+            ObjectType::LineIntersection => Ok(SceneObject::LineIntersection(
+                LineIntersection::new(properties)?,
+            )),
             ObjectType::SlidingPoint => {
                 Ok(SceneObject::SlidingPoint(SlidingPoint::new(properties)?))
             }
@@ -107,8 +172,12 @@ impl SceneObject {
             }
             ObjectType::LineAB => Ok(SceneObject::LineAB(LineAB::new(properties)?)),
             ObjectType::PpBisector => Ok(SceneObject::PpBisector(PpBisector::new(properties)?)),
+            ObjectType::AngleBisector => {
+                Ok(SceneObject::AngleBisector(AngleBisector::new(properties)?))
+            }
             ObjectType::PpToLine => Ok(SceneObject::PpToLine(PpToLine::new(properties)?)),
             ObjectType::PlToLine => Ok(SceneObject::PlToLine(PlToLine::new(properties)?)),
+            ObjectType::TangentLine => Ok(SceneObject::TangentLine(TangentLine::new(properties)?)),
             ObjectType::Parameter => Ok(SceneObject::Parameter),
             ObjectType::TwoPointDistanceInvariant => Ok(SceneObject::TwoPointDistanceInvariant(
                 TwoPointDistanceInvariant::new(properties)?,
@@ -131,7 +200,9 @@ impl SceneObject {
             SceneObject::FixedPoint(_) => ObjectType::FixedPoint,
             SceneObject::FreePoint(_) => ObjectType::FreePoint,
             SceneObject::Midpoint(_) => ObjectType::Midpoint,
+            SceneObject::PointOnSegmentRatio(_) => ObjectType::PointOnSegmentRatio,
             SceneObject::IntersectionPoint(_) => ObjectType::IntersectionPoint,
+            SceneObject::LineIntersection(_) => ObjectType::LineIntersection,
             SceneObject::SlidingPoint(_) => ObjectType::SlidingPoint,
             SceneObject::Projection(_) => ObjectType::Projection,
             SceneObject::Reflection(_) => ObjectType::Reflection,
@@ -139,8 +210,10 @@ impl SceneObject {
             SceneObject::ComputedPoint(_) => ObjectType::ComputedPoint,
             SceneObject::LineAB(_) => ObjectType::LineAB,
             SceneObject::PpBisector(_) => ObjectType::PpBisector,
+            SceneObject::AngleBisector(_) => ObjectType::AngleBisector,
             SceneObject::PpToLine(_) => ObjectType::PpToLine,
             SceneObject::PlToLine(_) => ObjectType::PlToLine,
+            SceneObject::TangentLine(_) => ObjectType::TangentLine,
             SceneObject::Parameter => ObjectType::Parameter,
             SceneObject::TwoPointDistanceInvariant(_) => ObjectType::TwoPointDistanceInvariant,
             SceneObject::PointToLineDistanceInvariant(_) => {
@@ -157,7 +230,9 @@ impl SceneObject {
             SceneObject::FixedPoint(p) => p.get_properties(),
             SceneObject::FreePoint(p) => p.get_properties(),
             SceneObject::Midpoint(m) => m.get_properties(),
+            SceneObject::PointOnSegmentRatio(p) => p.get_properties(),
             SceneObject::IntersectionPoint(p) => p.get_properties(),
+            SceneObject::LineIntersection(p) => p.get_properties(),
             SceneObject::SlidingPoint(p) => p.get_properties(),
             SceneObject::Projection(p) => p.get_properties(),
             SceneObject::Reflection(p) => p.get_properties(),
@@ -165,8 +240,10 @@ impl SceneObject {
             SceneObject::ComputedPoint(p) => p.get_properties(),
             SceneObject::LineAB(l) => l.get_properties(),
             SceneObject::PpBisector(p) => p.get_properties(),
+            SceneObject::AngleBisector(a) => a.get_properties(),
             SceneObject::PpToLine(p) => p.get_properties(),
             SceneObject::PlToLine(p) => p.get_properties(),
+            SceneObject::TangentLine(t) => t.get_properties(),
             SceneObject::Parameter => Value::Null,
             SceneObject::TwoPointDistanceInvariant(t) => t.get_properties(),
             SceneObject::PointToLineDistanceInvariant(p) => p.get_properties(),
@@ -181,7 +258,9 @@ impl SceneObject {
             SceneObject::FixedPoint(p) => p.to_python(name),
             SceneObject::FreePoint(p) => p.to_python(name),
             SceneObject::Midpoint(m) => m.to_python(name),
+            SceneObject::PointOnSegmentRatio(p) => p.to_python(name),
             SceneObject::IntersectionPoint(p) => p.to_python(name),
+            SceneObject::LineIntersection(p) => p.to_python(name),
             SceneObject::SlidingPoint(p) => p.to_python(name),
             SceneObject::Projection(p) => p.to_python(name),
             SceneObject::Reflection(p) => p.to_python(name),
@@ -189,12 +268,11 @@ impl SceneObject {
             SceneObject::ComputedPoint(p) => p.to_python(name),
             SceneObject::LineAB(l) => l.to_python(name),
             SceneObject::PpBisector(p) => p.to_python(name),
+            SceneObject::AngleBisector(a) => a.to_python(name),
             SceneObject::PpToLine(p) => p.to_python(name),
             SceneObject::PlToLine(p) => p.to_python(name),
-            SceneObject::Parameter => format!(
-                "{} = Value(next_var(), initial=0, float_initial=maybe_float_initial(lambda: 0.0))",
-                name
-            ),
+            SceneObject::TangentLine(t) => t.to_python(name, None),
+            SceneObject::Parameter => format!("{} = new_var(0)", name),
             SceneObject::TwoPointDistanceInvariant(t) => t.to_python(name),
             SceneObject::PointToLineDistanceInvariant(p) => p.to_python(name),
             SceneObject::TwoLineAngleInvariant(t) => t.to_python(name),
@@ -203,12 +281,44 @@ impl SceneObject {
         }
     }
 
+    /// Scalar expressions in the properties of the object, which are checked
+    /// with `ExpressionChecker`
+    pub fn get_expressions(&self) -> Vec<&str> {
+        match self {
+            SceneObject::ScaledVectorPoint(p) => vec![&p.k],
+            SceneObject::ComputedPoint(p) => vec![&p.x_expr, &p.y_expr],
+            SceneObject::Invariant(i) => vec![&i.formula],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The same object with its references to `old_name` changed to `new_name`,
+    /// both in properties naming objects and in expressions
+    pub fn rename_reference(&self, old_name: &str, new_name: &str) -> Result<Self, SceneError> {
+        let expressions = self.get_expressions();
+        let mut properties = self.get_properties();
+        if let Value::Object(map) = &mut properties {
+            for value in map.values_mut() {
+                if let Value::String(s) = value {
+                    if s == old_name {
+                        *s = new_name.to_string();
+                    } else if expressions.contains(&s.as_str()) {
+                        *s = SceneUtils::rename_object_reference(s, old_name, new_name);
+                    }
+                }
+            }
+        }
+        SceneObject::from_properties(self.get_type(), properties)
+    }
+
     pub fn get_dependencies(&self) -> Vec<String> {
         match self {
             SceneObject::FixedPoint(p) => p.get_dependencies(),
             SceneObject::FreePoint(p) => p.get_dependencies(),
             SceneObject::Midpoint(m) => m.get_dependencies(),
+            SceneObject::PointOnSegmentRatio(p) => p.get_dependencies(),
             SceneObject::IntersectionPoint(p) => p.get_dependencies(),
+            SceneObject::LineIntersection(p) => p.get_dependencies(),
             SceneObject::SlidingPoint(p) => p.get_dependencies(),
             SceneObject::Projection(p) => p.get_dependencies(),
             SceneObject::Reflection(p) => p.get_dependencies(),
@@ -216,8 +326,10 @@ impl SceneObject {
             SceneObject::ComputedPoint(p) => p.get_dependencies(),
             SceneObject::LineAB(l) => l.get_dependencies(),
             SceneObject::PpBisector(p) => p.get_dependencies(),
+            SceneObject::AngleBisector(a) => a.get_dependencies(),
             SceneObject::PpToLine(p) => p.get_dependencies(),
             SceneObject::PlToLine(p) => p.get_dependencies(),
+            SceneObject::TangentLine(t) => t.get_dependencies(),
             SceneObject::Parameter => Vec::new(),
             SceneObject::TwoPointDistanceInvariant(t) => t.get_dependencies(),
             SceneObject::PointToLineDistanceInvariant(p) => p.get_dependencies(),
@@ -233,7 +345,9 @@ pub enum ObjectType {
     FixedPoint,
     FreePoint,
     Midpoint,
+    PointOnSegmentRatio,
     IntersectionPoint,
+    LineIntersection,
     SlidingPoint,
     Projection,
     Reflection,
@@ -241,8 +355,10 @@ pub enum ObjectType {
     ComputedPoint,
     LineAB,
     PpBisector,
+    AngleBisector,
     PpToLine,
     PlToLine,
+    TangentLine,
     Parameter,
     TwoPointDistanceInvariant,
     PointToLineDistanceInvariant,
@@ -259,7 +375,9 @@ impl FromStr for ObjectType {
             "FixedPoint" => Ok(ObjectType::FixedPoint),
             "FreePoint" => Ok(ObjectType::FreePoint),
             "Midpoint" => Ok(ObjectType::Midpoint),
+            "PointOnSegmentRatio" => Ok(ObjectType::PointOnSegmentRatio),
             "IntersectionPoint" => Ok(ObjectType::IntersectionPoint),
+            "LineIntersection" => Ok(ObjectType::LineIntersection),
             "SlidingPoint" => Ok(ObjectType::SlidingPoint),
             "Projection" => Ok(ObjectType::Projection),
             "Reflection" => Ok(ObjectType::Reflection),
@@ -267,8 +385,10 @@ impl FromStr for ObjectType {
             "ComputedPoint" => Ok(ObjectType::ComputedPoint),
             "LineAB" => Ok(ObjectType::LineAB),
             "PpBisector" => Ok(ObjectType::PpBisector),
+            "AngleBisector" => Ok(ObjectType::AngleBisector),
             "PpToLine" => Ok(ObjectType::PpToLine),
             "PlToLine" => Ok(ObjectType::PlToLine),
+            "TangentLine" => Ok(ObjectType::TangentLine),
             "Parameter" => Ok(ObjectType::Parameter),
             "TwoPointDistanceInvariant" => Ok(ObjectType::TwoPointDistanceInvariant),
             "PointToLineDistanceInvariant" => Ok(ObjectType::PointToLineDistanceInvariant),
@@ -286,7 +406,9 @@ impl Display for ObjectType {
             ObjectType::FixedPoint => "FixedPoint".to_string(),
             ObjectType::FreePoint => "FreePoint".to_string(),
             ObjectType::Midpoint => "Midpoint".to_string(),
+            ObjectType::PointOnSegmentRatio => "PointOnSegmentRatio".to_string(),
             ObjectType::IntersectionPoint => "IntersectionPoint".to_string(),
+            ObjectType::LineIntersection => "LineIntersection".to_string(),
             ObjectType::SlidingPoint => "SlidingPoint".to_string(),
             ObjectType::Projection => "Projection".to_string(),
             ObjectType::Reflection => "Reflection".to_string(),
@@ -294,8 +416,10 @@ impl Display for ObjectType {
             ObjectType::ComputedPoint => "ComputedPoint".to_string(),
             ObjectType::LineAB => "LineAB".to_string(),
             ObjectType::PpBisector => "PpBisector".to_string(),
+            ObjectType::AngleBisector => "AngleBisector".to_string(),
             ObjectType::PpToLine => "PpToLine".to_string(),
             ObjectType::PlToLine => "PlToLine".to_string(),
+            ObjectType::TangentLine => "TangentLine".to_string(),
             ObjectType::Parameter => "Parameter".to_string(),
             ObjectType::TwoPointDistanceInvariant => "TwoPointDistanceInvariant".to_string(),
             ObjectType::PointToLineDistanceInvariant => "PointToLineDistanceInvariant".to_string(),
@@ -307,6 +431,50 @@ impl Display for ObjectType {
     }
 }
 
+/// How the frontend draws an object. Every field is optional, and the frontend
+/// chooses the ones that are not set. Stored as JSON next to the properties.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObjectStyle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// CSS color in the form #rgb or #rrggbb
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_label: Option<bool>,
+    /// Width of lines and curves in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+}
+
+impl ObjectStyle {
+    pub fn is_default(&self) -> bool {
+        *self == ObjectStyle::default()
+    }
+
+    pub fn validate(&self) -> Result<(), SceneError> {
+        if let Some(color) = &self.color {
+            let digits = color.strip_prefix('#').unwrap_or_default();
+            if !matches!(digits.len(), 3 | 6) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(SceneError::InvalidProperties(format!(
+                    "Invalid color: {}",
+                    color
+                )));
+            }
+        }
+        if let Some(stroke_width) = self.stroke_width {
+            if !(stroke_width > 0.0 && stroke_width.is_finite()) {
+                return Err(SceneError::InvalidProperties(format!(
+                    "Invalid stroke width: {}",
+                    stroke_width
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -398,6 +566,16 @@ mod tests {
         assert_eq!(obj.get_type(), ObjectType::IntersectionPoint);
         assert_eq!(obj.get_properties(), props);
 
+        let props = json!({
+            "line1": "L1",
+            "line2": "L2"
+        });
+        let obj = SceneObject::from_properties(ObjectType::AngleBisector, props.clone()).unwrap();
+        assert!(matches!(obj, SceneObject::AngleBisector(_)));
+        assert_eq!(obj.get_type(), ObjectType::AngleBisector);
+        assert_eq!(obj.get_properties(), props);
+        assert_eq!(obj.get_dependencies(), vec!["L1", "L2"]);
+
         let locus = Locus {
             point: "P1".to_string(),
         };
@@ -764,4 +942,22 @@ mod tests {
         actual.sort();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_object_style() {
+        let style: ObjectStyle =
+            serde_json::from_value(json!({"color": "#f80", "stroke_width": 2.5})).unwrap();
+        assert!(style.validate().is_ok());
+        assert!(!style.is_default());
+        assert_eq!(
+            serde_json::to_value(&style).unwrap(),
+            json!({"color": "#f80", "stroke_width": 2.5})
+        );
+        assert!(serde_json::from_value::<ObjectStyle>(json!({"colour": "red"})).is_err());
+
+        for invalid in [json!({"color": "red"}), json!({"stroke_width": 0.0})] {
+            let style: ObjectStyle = serde_json::from_value(invalid).unwrap();
+            assert!(style.validate().is_err());
+        }
+    }
 }
@@ -0,0 +1,143 @@
+use crate::scene_object::SceneError;
+use serde_json::json;
+use serde_json::Value;
+
+/// Bisector of an angle, given either by three points (the angle at `vertex`)
+/// or by two intersecting lines
+#[derive(Debug, Clone, PartialEq)]
+pub enum AngleBisector {
+    Points {
+        point1: String,
+        vertex: String,
+        point2: String,
+    },
+    Lines {
+        line1: String,
+        line2: String,
+    },
+}
+
+impl AngleBisector {
+    pub fn new(properties: Value) -> Result<Self, SceneError> {
+        let field = |name: &str| {
+            properties[name]
+                .as_str()
+                .map(|s| s.to_string())
+                .ok_or_else(|| SceneError::InvalidProperties(format!("Missing '{}' field", name)))
+        };
+
+        if properties.get("line1").is_some() {
+            Ok(AngleBisector::Lines {
+                line1: field("line1")?,
+                line2: field("line2")?,
+            })
+        } else {
+            Ok(AngleBisector::Points {
+                point1: field("point1")?,
+                vertex: field("vertex")?,
+                point2: field("point2")?,
+            })
+        }
+    }
+
+    pub fn get_properties(&self) -> Value {
+        match self {
+            AngleBisector::Points {
+                point1,
+                vertex,
+                point2,
+            } => json!({
+                "point1": point1,
+                "vertex": vertex,
+                "point2": point2
+            }),
+            AngleBisector::Lines { line1, line2 } => json!({
+                "line1": line1,
+                "line2": line2
+            }),
+        }
+    }
+
+    fn point_to_python(point: &str) -> String {
+        if point.contains(',') {
+            let coords: Vec<&str> = point.split(',').collect();
+            format!("FixedPoint({}, {})", coords[0].trim(), coords[1].trim())
+        } else {
+            point.to_string()
+        }
+    }
+
+    pub fn to_python(&self, name: &str) -> String {
+        match self {
+            AngleBisector::Points {
+                point1,
+                vertex,
+                point2,
+            } => format!(
+                "{} = AngleBisector({}, {}, {})",
+                name,
+                Self::point_to_python(point1),
+                Self::point_to_python(vertex),
+                Self::point_to_python(point2)
+            ),
+            AngleBisector::Lines { line1, line2 } => {
+                format!("{} = LLAngleBisector({}, {})", name, line1, line2)
+            }
+        }
+    }
+
+    pub fn get_dependencies(&self) -> Vec<String> {
+        match self {
+            // Only named points are dependencies, not coordinates
+            AngleBisector::Points {
+                point1,
+                vertex,
+                point2,
+            } => [point1, vertex, point2]
+                .into_iter()
+                .filter(|point| !point.contains(','))
+                .cloned()
+                .collect(),
+            AngleBisector::Lines { line1, line2 } => vec![line1.clone(), line2.clone()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angle_bisector_points() {
+        let props = json!({
+            "point1": "A",
+            "vertex": "0, 0",
+            "point2": "B"
+        });
+        let bisector = AngleBisector::new(props.clone()).unwrap();
+        assert_eq!(bisector.get_properties(), props);
+        assert_eq!(
+            bisector.to_python("L1"),
+            "L1 = AngleBisector(A, FixedPoint(0, 0), B)"
+        );
+        assert_eq!(bisector.get_dependencies(), vec!["A", "B"]);
+
+        let result = AngleBisector::new(json!({ "point1": "A", "point2": "B" }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_angle_bisector_lines() {
+        let props = json!({
+            "line1": "L1",
+            "line2": "L2"
+        });
+        let bisector = AngleBisector::new(props.clone()).unwrap();
+        assert_eq!(bisector.get_properties(), props);
+        assert_eq!(bisector.to_python("L3"), "L3 = LLAngleBisector(L1, L2)");
+        assert_eq!(bisector.get_dependencies(), vec!["L1", "L2"]);
+
+        let result = AngleBisector::new(json!({ "line1": "L1" }));
+        assert!(result.is_err());
+    }
+}
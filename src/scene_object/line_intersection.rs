@@ -0,0 +1,71 @@
+use crate::scene_object::SceneError;
+use serde_json::json;
+use serde_json::Value;
+
+/// Intersection of two lines, defined implicitly by the equations of both lines
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineIntersection {
+    pub line1: String,
+    pub line2: String,
+}
+
+impl LineIntersection {
+    pub fn new(properties: Value) -> Result<Self, SceneError> {
+        let line1 = properties["line1"]
+            .as_str()
+            .ok_or_else(|| SceneError::InvalidProperties("Missing 'line1' field".to_string()))?
+            .to_string();
+        let line2 = properties["line2"]
+            .as_str()
+            .ok_or_else(|| SceneError::InvalidProperties("Missing 'line2' field".to_string()))?
+            .to_string();
+
+        Ok(LineIntersection { line1, line2 })
+    }
+
+    pub fn get_properties(&self) -> Value {
+        json!({
+            "line1": self.line1,
+            "line2": self.line2
+        })
+    }
+
+    pub fn to_python(&self, name: &str) -> String {
+        format!(
+            "{} = LineIntersection({}, {})",
+            name, self.line1, self.line2
+        )
+    }
+
+    pub fn get_dependencies(&self) -> Vec<String> {
+        vec![self.line1.clone(), self.line2.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_intersection() {
+        let props = json!({
+            "line1": "L1",
+            "line2": "L2"
+        });
+        let intersection = LineIntersection::new(props).unwrap();
+        assert_eq!(intersection.line1, "L1");
+        assert_eq!(intersection.line2, "L2");
+        assert_eq!(
+            intersection.get_properties(),
+            json!({
+                "line1": "L1",
+                "line2": "L2"
+            })
+        );
+        assert_eq!(intersection.to_python("X"), "X = LineIntersection(L1, L2)");
+
+        let result = LineIntersection::new(json!({ "line1": "L1" }));
+        assert!(result.is_err());
+        assert_eq!(intersection.get_dependencies(), vec!["L1", "L2"]);
+    }
+}
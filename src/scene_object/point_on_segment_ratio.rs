@@ -0,0 +1,137 @@
+use crate::scene_object::SceneError;
+use serde_json::json;
+use serde_json::Value;
+
+/// The point point1 + p/q * (point2 - point1), with the ratio p/q given as "p/q" or "p"
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointOnSegmentRatio {
+    pub point1: String,
+    pub point2: String,
+    pub numerator: i64,
+    pub denominator: i64,
+}
+
+impl PointOnSegmentRatio {
+    pub fn new(properties: Value) -> Result<Self, SceneError> {
+        let point1 = properties["point1"]
+            .as_str()
+            .ok_or_else(|| SceneError::InvalidProperties("Missing 'point1' field".to_string()))?
+            .to_string();
+        let point2 = properties["point2"]
+            .as_str()
+            .ok_or_else(|| SceneError::InvalidProperties("Missing 'point2' field".to_string()))?
+            .to_string();
+        let ratio = properties["ratio"]
+            .as_str()
+            .ok_or_else(|| SceneError::InvalidProperties("Missing 'ratio' field".to_string()))?;
+        let (numerator, denominator) = Self::parse_ratio(ratio)?;
+
+        Ok(PointOnSegmentRatio {
+            point1,
+            point2,
+            numerator,
+            denominator,
+        })
+    }
+
+    fn parse_ratio(ratio: &str) -> Result<(i64, i64), SceneError> {
+        let invalid = || SceneError::InvalidProperties(format!("Invalid ratio: {}", ratio));
+        let (numerator, denominator) = match ratio.split_once('/') {
+            Some((p, q)) => (p.trim(), q.trim()),
+            None => (ratio.trim(), "1"),
+        };
+        let numerator: i64 = numerator.parse().map_err(|_| invalid())?;
+        let denominator: i64 = denominator.parse().map_err(|_| invalid())?;
+        if denominator <= 0 {
+            return Err(invalid());
+        }
+        Ok((numerator, denominator))
+    }
+
+    pub fn get_properties(&self) -> Value {
+        json!({
+            "point1": self.point1,
+            "point2": self.point2,
+            "ratio": format!("{}/{}", self.numerator, self.denominator)
+        })
+    }
+
+    pub fn to_python(&self, name: &str) -> String {
+        let point1 = if self.point1.contains(',') {
+            let coords: Vec<&str> = self.point1.split(',').collect();
+            format!("FixedPoint({}, {})", coords[0].trim(), coords[1].trim())
+        } else {
+            self.point1.clone()
+        };
+
+        let point2 = if self.point2.contains(',') {
+            let coords: Vec<&str> = self.point2.split(',').collect();
+            format!("FixedPoint({}, {})", coords[0].trim(), coords[1].trim())
+        } else {
+            self.point2.clone()
+        };
+
+        format!(
+            "{} = PointOnSegmentRatio({}, {}, {}, {})",
+            name, point1, point2, self.numerator, self.denominator
+        )
+    }
+
+    pub fn get_dependencies(&self) -> Vec<String> {
+        let mut dependencies = Vec::new();
+
+        // Add point1 if it's a named point (not coordinates)
+        if !self.point1.contains(',') {
+            dependencies.push(self.point1.clone());
+        }
+
+        // Add point2 if it's a named point (not coordinates)
+        if !self.point2.contains(',') {
+            dependencies.push(self.point2.clone());
+        }
+
+        dependencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_on_segment_ratio() {
+        let props = json!({
+            "point1": "A",
+            "point2": "0, 3",
+            "ratio": " 1 / 3"
+        });
+        let point = PointOnSegmentRatio::new(props).unwrap();
+        assert_eq!((point.numerator, point.denominator), (1, 3));
+        assert_eq!(
+            point.get_properties(),
+            json!({
+                "point1": "A",
+                "point2": "0, 3",
+                "ratio": "1/3"
+            })
+        );
+        assert_eq!(
+            point.to_python("P"),
+            "P = PointOnSegmentRatio(A, FixedPoint(0, 3), 1, 3)"
+        );
+        assert_eq!(point.get_dependencies(), vec!["A"]);
+
+        let point =
+            PointOnSegmentRatio::new(json!({"point1": "A", "point2": "B", "ratio": "-2"})).unwrap();
+        assert_eq!((point.numerator, point.denominator), (-2, 1));
+    }
+
+    #[test]
+    fn test_point_on_segment_ratio_invalid() {
+        for ratio in ["1/0", "1/-2", "a/b", ""] {
+            let props = json!({"point1": "A", "point2": "B", "ratio": ratio});
+            assert!(PointOnSegmentRatio::new(props).is_err(), "{}", ratio);
+        }
+        assert!(PointOnSegmentRatio::new(json!({"point1": "A", "point2": "B"})).is_err());
+    }
+}
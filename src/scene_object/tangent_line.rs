@@ -0,0 +1,88 @@
+use crate::scene_object::SceneError;
+use serde_json::json;
+use serde_json::Value;
+
+/// Line tangent to the curve of a locus at `point`, which is constrained to lie on the curve
+#[derive(Debug, Clone, PartialEq)]
+pub struct TangentLine {
+    pub locus: String,
+    pub point: String,
+}
+
+/// Python lambdas of x and y evaluating a curve equation F and its partial derivatives
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveFunctions {
+    pub f: String,
+    pub f_x: String,
+    pub f_y: String,
+}
+
+impl TangentLine {
+    pub fn new(properties: Value) -> Result<Self, SceneError> {
+        let locus = properties["locus"]
+            .as_str()
+            .ok_or_else(|| SceneError::InvalidProperties("Missing 'locus' field".to_string()))?
+            .to_string();
+        let point = properties["point"]
+            .as_str()
+            .ok_or_else(|| SceneError::InvalidProperties("Missing 'point' field".to_string()))?
+            .to_string();
+
+        Ok(TangentLine { locus, point })
+    }
+
+    pub fn get_properties(&self) -> Value {
+        json!({
+            "locus": self.locus,
+            "point": self.point
+        })
+    }
+
+    /// The curve equation is only known after solving for the locus, so without it
+    /// the generated code reports the missing curve when run
+    pub fn to_python(&self, name: &str, curve: Option<&CurveFunctions>) -> String {
+        match curve {
+            Some(curve) => format!(
+                "{} = TangentLine(Curve({}, {}, {}), {})",
+                name, curve.f, curve.f_x, curve.f_y, self.point
+            ),
+            None => format!(
+                "raise Exception(\"The curve of locus {} is not computed\")",
+                self.locus
+            ),
+        }
+    }
+
+    pub fn get_dependencies(&self) -> Vec<String> {
+        vec![self.locus.clone(), self.point.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tangent_line() {
+        let props = json!({
+            "locus": "L1",
+            "point": "P"
+        });
+        let tangent = TangentLine::new(props.clone()).unwrap();
+        assert_eq!(tangent.get_properties(), props);
+        assert_eq!(tangent.get_dependencies(), vec!["L1", "P"]);
+
+        let curve = CurveFunctions {
+            f: "lambda x, y: x - y".to_string(),
+            f_x: "lambda x, y: i(1)".to_string(),
+            f_y: "lambda x, y: i(-1)".to_string(),
+        };
+        assert_eq!(
+            tangent.to_python("T", Some(&curve)),
+            "T = TangentLine(Curve(lambda x, y: x - y, lambda x, y: i(1), lambda x, y: i(-1)), P)"
+        );
+        assert!(tangent.to_python("T", None).starts_with("raise Exception"));
+
+        assert!(TangentLine::new(json!({ "locus": "L1" })).is_err());
+    }
+}
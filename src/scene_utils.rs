@@ -1,7 +1,25 @@
-use crate::elimination::Elimination;
-use crate::poly::{Poly, PolyOperations, SingleOutResult};
-use crate::scene::{CurveEquationAndFactors, Plot, SceneOptions};
+use crate::budget;
+use crate::cancellation;
+use crate::cas::CasBackend;
+use crate::determinant;
+use crate::elimination::{Elimination, FactorCheck};
+use crate::equation_compiler::EquationCompiler;
+use crate::modular_determinant;
+use crate::poly::{
+    checked, detect_overflow, Poly, PolyError, PolyOperations, RatPoly, Rational, SingleOutResult,
+    Var, VarSet,
+};
+use crate::progress::{self, Progress};
+use crate::replay;
+use crate::scene::{
+    CurveEquationAndFactors, EliminatedVariable, EliminationMethod, Parametrization, Plot,
+    SceneOptions,
+};
+use crate::scene_object::tangent_line::CurveFunctions;
 use crate::scene_object::SceneError;
+use crate::trace::{self, TraceStep};
+use crate::variables::{self, VariableRegistry};
+use crate::warning::{Warning, WarningCode};
 use gcd::Gcd;
 use log::info;
 use regex::Regex;
@@ -23,6 +41,48 @@ pub struct SceneUtils;
 impl SceneUtils {
     pub fn to_equations(
         python_expressions: String,
+    ) -> Result<(Vec<String>, Vec<Plot>), SceneError> {
+        Self::to_equations_with_variables(python_expressions)
+            .map(|(equations, plots, _)| (equations, plots))
+    }
+
+    /// Like `to_equations`, also returning the objects the variables come from.
+    /// The Python equation processor doesn't report them, so the registry is empty
+    /// when falling back to it.
+    pub fn to_equations_with_variables(
+        python_expressions: String,
+    ) -> Result<(Vec<String>, Vec<Plot>, VariableRegistry), SceneError> {
+        match EquationCompiler::compile(&python_expressions) {
+            Ok(result) => Ok(result),
+            Err(e) if e.is_unsupported() => {
+                info!("Falling back to the Python equation processor: {}", e);
+                let (equations, plots) = Self::to_equations_python(python_expressions)?;
+                Ok((equations, plots, VariableRegistry::new()))
+            }
+            Err(e) => Err(SceneError::InvalidEquation(e.to_string())),
+        }
+    }
+
+    /// Version of python3, after checking that it can import the equation processor
+    pub fn python_version() -> Result<String, SceneError> {
+        let output = Command::new("python3")
+            .current_dir(Path::new("src/py"))
+            .arg("-c")
+            .arg("import equation_processor, platform; print(platform.python_version())")
+            .output()
+            .map_err(|e| SceneError::Python(format!("Failed to run Python: {}", e)))?;
+        if !output.status.success() {
+            return Err(SceneError::Python(format!(
+                "Failed to import the equation processor: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Runs the scene code with src/py/equation_processor.py
+    pub fn to_equations_python(
+        python_expressions: String,
     ) -> Result<(Vec<String>, Vec<Plot>), SceneError> {
         let python_code = format!(
             "from equation_processor import *\n{}\n\n# Print all equations\nfor eq in equations:\n    print(eq)\nprint()\n# Print all plots\nfor plot in plots:\n    print(plot)",
@@ -36,7 +96,7 @@ impl SceneUtils {
             .arg("-c")
             .arg(python_code)
             .output()
-            .map_err(|e| SceneError::DatabaseError(format!("Failed to run Python: {}", e)))?;
+            .map_err(|e| SceneError::Python(format!("Failed to run Python: {}", e)))?;
         println!("output status: {:?}", output.status);
         println!(
             "output stdout:\n{}",
@@ -50,7 +110,7 @@ impl SceneUtils {
         }
 
         if !output.status.success() {
-            return Err(SceneError::DatabaseError(format!(
+            return Err(SceneError::Python(format!(
                 "Python execution failed: {}",
                 String::from_utf8_lossy(&output.stderr)
             )));
@@ -88,34 +148,50 @@ impl SceneUtils {
         equations: Vec<&str>,
         plot: &Plot,
         options: SceneOptions,
+    ) -> Result<CurveEquationAndFactors, SceneError> {
+        // A coefficient that overflowed anywhere makes the curve wrong, and a limit of
+        // the budget hit anywhere leaves it unfinished
+        budget::with_budget(options.budget, || {
+            detect_overflow(|| Self::solve_for_curve(equations, plot, options))
+        })??
+    }
+
+    fn solve_for_curve(
+        equations: Vec<&str>,
+        plot: &Plot,
+        options: SceneOptions,
     ) -> Result<CurveEquationAndFactors, SceneError> {
         // Convert equations to polynomials
         let mut polys: Vec<Rc<Poly>> = equations
             .into_iter()
             .map(|s| {
-                Rc::new(
-                    Poly::new(s)
-                        .map_err(|e| SceneError::InvalidEquation(e.to_string()))
-                        .unwrap(),
-                )
+                Poly::parse_equation(s)
+                    .map(Rc::new)
+                    .map_err(|e| SceneError::InvalidEquation(e.to_string()))
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, _>>()?;
+
+        trace::record(|| TraceStep::InitialSystem {
+            equations: trace::to_strings(&polys),
+        });
 
         // Convert x and y to variable indices
         let (x_var, y_var) = Self::parse_plot_vars(plot)?;
 
         // Collect all variables used in polynomials
-        let mut vars = [false; 256];
+        let mut vars = VarSet::new();
         for poly in &polys {
             poly.fill_in_variables(&mut vars);
         }
 
+        // Equations of the variables substituted away, shared by all systems
+        let mut substitutions = Vec::new();
+
         // Process each variable that's not x or y
-        for (v, has_var) in vars.iter().enumerate() {
-            if *has_var && v != x_var as usize && v != y_var as usize {
+        for v in vars.iter() {
+            if v != x_var && v != y_var {
                 // Get single_out results for all polynomials
-                let results: Vec<SingleOutResult> =
-                    polys.iter().map(|p| p.single_out(v as u8)).collect();
+                let results: Vec<SingleOutResult> = polys.iter().map(|p| p.single_out(v)).collect();
 
                 // Find a linear result to use for substitution
                 let mut linear_idx = None;
@@ -133,6 +209,14 @@ impl SceneUtils {
 
                 // If we found a linear result, use it to substitute in other polynomials
                 if let (Some(idx), Some(poly)) = (linear_idx, linear_poly) {
+                    trace::record(|| TraceStep::Substitution {
+                        var: Poly::var_to_string(v),
+                        equation: trace::to_string(&polys[idx]),
+                    });
+                    substitutions.push(EliminatedVariable {
+                        var: v,
+                        equation: (*polys[idx]).clone(),
+                    });
                     let mut new_polys = Vec::new();
                     for (i, result) in results.iter().enumerate() {
                         if i == idx {
@@ -144,7 +228,7 @@ impl SceneUtils {
                             }
                             SingleOutResult::Linear(_, _) | SingleOutResult::Nonlinear => {
                                 new_polys.push(Rc::new(polys[i].substitute_linear(
-                                    v as u8,
+                                    v,
                                     poly.clone(),
                                     linear_k,
                                 )));
@@ -165,29 +249,61 @@ impl SceneUtils {
                 .join("\n")
         );
 
-        let systems = Self::split_into_irreducible_systems(polys);
+        trace::record(|| TraceStep::ReducedSystem {
+            equations: trace::to_strings(&polys),
+        });
+
+        progress::report(|| Progress::Equations { count: polys.len() });
+        let mut warnings = Vec::new();
+        let systems = Self::split_into_irreducible_systems(polys, &mut warnings);
+        trace::record(|| TraceStep::Systems {
+            systems: systems
+                .iter()
+                .map(|system| trace::to_strings(system))
+                .collect(),
+        });
+        progress::report(|| Progress::Systems {
+            count: systems.len(),
+        });
 
         // Handle possible errors returned from eliminate_and_factor
         let mut all_factors = Vec::new();
-        for system in systems {
-            let factors = Self::eliminate_and_factor(system, x_var, y_var, &options)?;
-            all_factors.extend(factors);
-        }
-
-        // Compute unique factors using is_proportional
-        let mut unique_factors = Vec::new();
-        for factor in all_factors {
-            let mut is_duplicate = false;
-            for existing_factor in &unique_factors {
-                let mut factor_option = None;
-                if factor.is_proportional(existing_factor, &mut factor_option) {
-                    is_duplicate = true;
-                    break;
-                }
-            }
-            if !is_duplicate {
-                unique_factors.push(factor);
+        let mut triangular_sets = Vec::new();
+        let (solved, replayed) = replay::with_replay(options.replay.as_deref(), || {
+            for system in systems {
+                let mut triangular_set = substitutions.clone();
+                let factors = Self::eliminate_and_factor(
+                    system,
+                    x_var,
+                    y_var,
+                    &options,
+                    &mut warnings,
+                    &mut triangular_set,
+                )?;
+                all_factors.extend(factors);
+                triangular_sets.push(triangular_set);
             }
+            Ok::<_, SceneError>(())
+        });
+        solved?;
+        if replayed == Some(false) {
+            warnings.push(Warning::new(
+                WarningCode::ReplayDiverged,
+                "The system does not match the replayed elimination order, which was only \
+                 followed in part"
+                    .to_string(),
+            ));
+        }
+
+        // Factors found in several systems are kept once, also if they differ by a constant
+        let mut seen = HashSet::new();
+        let mut unique_factors: Vec<Poly> = all_factors
+            .into_iter()
+            .filter(|factor| seen.insert(factor.normalized()))
+            .collect();
+        // A system solved by the whole plane covers the curves of all others
+        if unique_factors.contains(&Poly::Constant(0)) {
+            unique_factors = vec![Poly::Constant(0)];
         }
 
         // Compute the final equation by multiplying all factors
@@ -200,14 +316,23 @@ impl SceneUtils {
         for factor in unique_factors.iter().skip(1) {
             equation = equation.multiply(factor);
         }
+        trace::record(|| TraceStep::Curve {
+            equation: trace::to_string(&equation),
+            factors: trace::to_strings(&unique_factors),
+        });
 
         Ok(CurveEquationAndFactors {
             curve_equation: equation,
             factors: unique_factors,
+            warnings,
+            triangular_sets: Some(triangular_sets),
         })
     }
 
-    pub fn split_into_irreducible_systems(polys: Vec<Rc<Poly>>) -> Vec<Vec<Rc<Poly>>> {
+    pub fn split_into_irreducible_systems(
+        polys: Vec<Rc<Poly>>,
+        warnings: &mut Vec<Warning>,
+    ) -> Vec<Vec<Rc<Poly>>> {
         if polys.is_empty() {
             return vec![];
         }
@@ -232,8 +357,12 @@ impl SceneUtils {
                         factors.into_iter().map(|p| Rc::new(p)).collect();
                     factored_polys.push(rc_factors);
                 }
-                Err(_) => {
+                Err(e) => {
                     // If factoring fails, treat the polynomial as irreducible
+                    warnings.push(Warning::new(
+                        WarningCode::FactoringFailed,
+                        format!("Failed to factor {}: {}", poly, e),
+                    ));
                     factored_polys.push(vec![poly.clone()]);
                 }
             }
@@ -271,17 +400,33 @@ impl SceneUtils {
         }
     }
 
+    /// Fails once the token installed by `cancellation::with_cancellation` is cancelled,
+    /// at the checkpoints between the stages of `eliminate_and_factor`
+    fn check_cancelled() -> Result<(), SceneError> {
+        if cancellation::is_cancelled() {
+            Err(SceneError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Eliminates all variables but x and y from the system, and factors the equation
+    /// left. The variables eliminated are appended to `triangular_set` with their
+    /// equations, in order, except with Gröbner bases, which only give the equation left.
     pub fn eliminate_and_factor(
         polys: Vec<Rc<Poly>>,
-        x_var: u8,
-        y_var: u8,
+        x_var: Var,
+        y_var: Var,
         options: &SceneOptions,
+        warnings: &mut Vec<Warning>,
+        triangular_set: &mut Vec<EliminatedVariable>,
     ) -> Result<Vec<Poly>, SceneError> {
         let mut polys = polys;
         let mut reduction_step = 0;
 
         // Eliminate variables that are present in univariate polynomials
         loop {
+            Self::check_cancelled()?;
             // Find the first polynomial that is univariate and matches Nested(v, _) with v != x_var and v != y_var
             let mut found_univariate = false;
             let mut uni_poly_index = 0;
@@ -309,6 +454,10 @@ impl SceneUtils {
             // Create new list with eliminated variables
             let mut new_polys = Vec::new();
             let uni_poly = polys[uni_poly_index].clone();
+            triangular_set.push(EliminatedVariable {
+                var: uni_var,
+                equation: (*uni_poly).clone(),
+            });
 
             for (i, poly) in polys.clone().into_iter().enumerate() {
                 if i == uni_poly_index {
@@ -317,12 +466,14 @@ impl SceneUtils {
                 }
 
                 // Check if this polynomial contains the variable v
-                let mut vars = [false; 256];
-                poly.fill_in_variables(&mut vars);
-
                 if poly.get_degree(uni_var) > 0 {
                     // Polynomial contains the variable, eliminate it
-                    let eliminated = Self::eliminate_univariate(poly, uni_poly.clone(), uni_var);
+                    let eliminated = Self::eliminate_univariate(
+                        poly,
+                        uni_poly.clone(),
+                        uni_var,
+                        options.modular_determinants,
+                    )?;
                     new_polys.push(eliminated);
                 } else {
                     // Polynomial doesn't contain the variable, keep it as is
@@ -346,13 +497,22 @@ impl SceneUtils {
             info!("No reduction possible");
         }
 
-        let mut elimination = Elimination::new(&polys, x_var, y_var, options.reduce_factors);
+        let polys = Self::simplify_by_gcd(polys);
+        Self::check_cancelled()?;
+
+        if options.elimination_method == EliminationMethod::Groebner {
+            return Self::eliminate_with_groebner_basis(polys, x_var, y_var, warnings);
+        }
+
+        let mut elimination = Elimination::new(&polys, x_var, y_var, options.reduce_factors)
+            .with_strategy(options.elimination_order);
+        elimination.incremental = options.incremental;
         loop {
             match elimination.get_var_to_eliminate() {
                 Some(var_search_result) => {
                     info!(
                         "--- Eliminating variable {} from\n{}",
-                        Poly::var_to_string(var_search_result.var),
+                        variables::describe(var_search_result.var),
                         elimination
                             .polys
                             .iter()
@@ -365,7 +525,55 @@ impl SceneUtils {
                 None => break,
             }
         }
-        let polys = elimination.polys.clone();
+        if elimination.is_cancelled() {
+            return Err(SceneError::Cancelled);
+        }
+        if let Some(exceeded) = budget::exceeded() {
+            return Err(exceeded.into());
+        }
+        info!(
+            "Eliminated variables in the order {} ({:?})",
+            elimination
+                .eliminated_vars()
+                .iter()
+                .map(|&v| variables::describe(v))
+                .collect::<Vec<String>>()
+                .join(", "),
+            options.elimination_order
+        );
+        if elimination.incremental {
+            info!(
+                "Reused {} cached elimination chains",
+                elimination.cache_hits
+            );
+        }
+        triangular_set.extend(elimination.pivots().into_iter().map(|(var, poly)| {
+            EliminatedVariable {
+                var,
+                equation: (*poly).clone(),
+            }
+        }));
+        let mut polys = elimination.polys.clone();
+
+        // Equations left in x and y only have the same real zeros as the sum of their
+        // squares, typically a single point
+        let only_in_x_and_y = |poly: &Rc<Poly>| {
+            let mut vars = poly.get_variables();
+            vars.remove(x_var);
+            vars.remove(y_var);
+            vars.first().is_none()
+        };
+        if polys.len() > 1 && polys.iter().all(only_in_x_and_y) {
+            info!(
+                "{} equations left in x and y, using the sum of their squares",
+                polys.len()
+            );
+            let mut sum_of_squares = Poly::Constant(0);
+            for poly in &polys {
+                sum_of_squares += &poly.multiply(poly);
+            }
+            polys = vec![Rc::new(sum_of_squares)];
+        }
 
         // Check if we have exactly one polynomial left
         if polys.len() != 1 {
@@ -376,21 +584,36 @@ impl SceneUtils {
         }
 
         // Verify the remaining polynomial only depends on x and y
-        let mut vars = [false; 256];
-        polys[0].fill_in_variables(&mut vars);
-        for (v, has_var) in vars.iter().enumerate() {
-            if *has_var && v != x_var as usize && v != y_var as usize {
-                return Err(SceneError::InvalidEquation(format!(
-                    "Remaining equation depends on variable {}",
-                    (v as u8 + b'a') as char
-                )));
-            }
+        let mut vars = polys[0].get_variables();
+        vars.remove(x_var);
+        vars.remove(y_var);
+        if let Some(v) = vars.first() {
+            return Err(SceneError::InvalidEquation(format!(
+                "Remaining equation depends on variable {}",
+                variables::describe(v)
+            )));
         }
         let mut result = polys[0].clone();
         Rc::make_mut(&mut result).reduce_coefficients_if_above(1);
-        let factors = result
-            .factor()
-            .map_err(|e| SceneError::InvalidEquation(e))?;
+        match *result {
+            // Every point of the plane is a solution
+            Poly::Constant(0) => return Ok(vec![Poly::Constant(0)]),
+            // There are no solutions
+            Poly::Constant(_) => return Ok(vec![]),
+            _ => {}
+        }
+        Self::check_cancelled()?;
+        progress::report(|| Progress::Factoring {
+            terms: result.to_terms().len(),
+        });
+        let factors = result.factor().map_err(SceneError::from_task_error)?;
+        trace::record(|| TraceStep::Factored {
+            equation: trace::to_string(&result),
+            factors: trace::to_strings(&factors),
+        });
+        progress::report(|| Progress::Factored {
+            factors: factors.len(),
+        });
 
         let mut product_factors = Vec::new();
 
@@ -405,24 +628,73 @@ impl SceneUtils {
             );
         }
         let mut unchecked_factors = Vec::new();
-        for factor in factors {
-            match elimination.check_factor(&factor) {
-                Ok(true) => {
+        // Sampled only if some factor can't be checked modulo a prime
+        let mut solutions = None;
+        let factor_count = factors.len();
+        for (i, factor) in factors.into_iter().enumerate() {
+            Self::check_cancelled()?;
+            progress::report(|| Progress::CheckingFactor {
+                index: i + 1,
+                count: factor_count,
+            });
+            let check = match elimination.check_factor(&factor) {
+                check @ (Ok(FactorCheck::Inconclusive) | Err(_)) => {
+                    let solutions = solutions
+                        .get_or_insert_with(|| elimination.sample_solutions(&mut rand::rng()));
+                    match elimination.check_factor_numerically(&factor, solutions) {
+                        FactorCheck::Inconclusive => check,
+                        numeric_check => {
+                            info!("Numeric check of factor {}: {}", factor, numeric_check);
+                            Ok(numeric_check)
+                        }
+                    }
+                }
+                check => check,
+            };
+            trace::record(|| TraceStep::FactorDecision {
+                factor: trace::to_string(&factor),
+                kept: matches!(check, Ok(FactorCheck::Verified)),
+                // Unchecked factors are only used if no factor is verified
+                reason: match &check {
+                    Ok(check) => check.to_string(),
+                    Err(e) => format!("Failed to check: {}", e),
+                },
+            });
+            match check {
+                Ok(FactorCheck::Verified) => {
                     product_factors.push(factor);
                 }
-                Ok(false) => {
-                    info!("Skipping factor {}", factor);
+                Ok(check) => {
+                    info!("Skipping factor {}: {}", factor, check);
+                    let code = if check == FactorCheck::Inconclusive {
+                        WarningCode::VerificationInconclusive
+                    } else {
+                        WarningCode::FactorSkipped
+                    };
+                    warnings.push(Warning::new(
+                        code,
+                        format!("Skipping factor {}: {}", factor, check),
+                    ));
                 }
                 Err(e) => {
                     info!("Failed to check factor {}: {}", factor, e);
+                    warnings.push(Warning::new(
+                        WarningCode::FactorUnchecked,
+                        format!("Failed to check factor {}: {}", factor, e),
+                    ));
                     unchecked_factors.push(factor);
                 }
             }
         }
+        warnings.append(&mut elimination.warnings);
 
         if unchecked_factors.len() > 0 {
             if product_factors.len() == 0 {
                 info!("Using unchecked factors as if they were verified");
+                warnings.push(Warning::new(
+                    WarningCode::UncheckedFactorsUsed,
+                    "Using unchecked factors as if they were verified".to_string(),
+                ));
                 product_factors = unchecked_factors;
             } else {
                 return Err(SceneError::InvalidEquation(
@@ -434,7 +706,133 @@ impl SceneUtils {
         Ok(product_factors)
     }
 
-    fn eliminate_univariate(poly: Rc<Poly>, uni_poly: Rc<Poly>, uni_var: u8) -> Rc<Poly> {
+    /// Drops the equations implied by other equations: zero polynomials, constant
+    /// multiples of other polynomials and polynomials divisible by another one (checked
+    /// with `reduce_by_gcd`). Symmetric constructions often give such duplicates.
+    /// A common factor dividing neither polynomial is kept, as dividing it out would
+    /// lose the solutions where it vanishes.
+    fn simplify_by_gcd(polys: Vec<Rc<Poly>>) -> Vec<Rc<Poly>> {
+        let mut polys = polys;
+        let initial_count = polys.len();
+        // Dropping a polynomial only leaves fewer that could imply the earlier ones,
+        // so the scan goes on from the same index
+        let mut i = 0;
+        while i < polys.len() {
+            if Self::is_implied(&polys, i) {
+                info!("Dropping implied equation {}", polys[i]);
+                polys.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        if polys.len() < initial_count {
+            info!(
+                "Simplified the system from {} to {} equations",
+                initial_count,
+                polys.len()
+            );
+        }
+        polys
+    }
+
+    /// Whether the zeros of polys[i] contain the zeros of another polynomial
+    fn is_implied(polys: &[Rc<Poly>], i: usize) -> bool {
+        let poly = &polys[i];
+        if **poly == Poly::Constant(0) {
+            return true;
+        }
+        for (j, divisor) in polys.iter().enumerate() {
+            if i == j || matches!(**divisor, Poly::Constant(_)) {
+                continue;
+            }
+            if j < i && poly.is_proportional(divisor, &mut None) {
+                return true;
+            }
+            // A divisor can't have a variable of higher degree than the polynomial
+            if divisor
+                .get_variables()
+                .iter()
+                .any(|v| divisor.get_degree(v) > poly.get_degree(v))
+            {
+                continue;
+            }
+            let reduction = Poly::reduce_by_gcd(poly.clone(), divisor.clone());
+            if matches!(*reduction.reduced2, Poly::Constant(c) if c != 0) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Eliminates the auxiliary variables using a Groebner basis, computed by the
+    /// selected CAS backend. The elimination ideal contains no extraneous components,
+    /// so the factors don't need to be checked.
+    fn eliminate_with_groebner_basis(
+        polys: Vec<Rc<Poly>>,
+        x_var: Var,
+        y_var: Var,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Vec<Poly>, SceneError> {
+        let backend = crate::cas::backend();
+        let generators = backend
+            .eliminate(&polys, x_var, y_var)
+            .map_err(SceneError::from_task_error)?;
+        trace::record(|| TraceStep::GroebnerBasis {
+            equations: trace::to_strings(&generators),
+        });
+        if generators.len() > 1 {
+            warnings.push(Warning::new(
+                WarningCode::ExtraGenerators,
+                format!(
+                    "Elimination ideal has {} generators, using their gcd",
+                    generators.len()
+                ),
+            ));
+        }
+        let mut result = Self::curve_of_generators(&*backend, generators)?;
+        result.reduce_coefficients_if_above(1);
+        info!("Groebner elimination result: {}", result);
+        let factors = result.factor().map_err(SceneError::from_task_error)?;
+        trace::record(|| TraceStep::Factored {
+            equation: trace::to_string(&result),
+            factors: trace::to_strings(&factors),
+        });
+        Ok(factors)
+    }
+
+    /// The one-dimensional part of the elimination ideal: the gcd of its generators.
+    /// The other generators only cut out finitely many points of the curve.
+    fn curve_of_generators(
+        backend: &dyn CasBackend,
+        generators: Vec<Poly>,
+    ) -> Result<Poly, SceneError> {
+        let mut generators = generators.into_iter();
+        let Some(mut curve) = generators.next() else {
+            return Err(SceneError::InvalidEquation(
+                "Elimination ideal is empty: the locus is not a curve".to_string(),
+            ));
+        };
+        for generator in generators {
+            curve = backend
+                .gcd(&curve, &generator)
+                .map_err(SceneError::from_task_error)?;
+        }
+        if matches!(curve, Poly::Constant(_)) {
+            return Err(SceneError::InvalidEquation(
+                "The generators of the elimination ideal have no common factor: \
+                 the locus is finitely many points"
+                    .to_string(),
+            ));
+        }
+        Ok(curve)
+    }
+
+    fn eliminate_univariate(
+        poly: Rc<Poly>,
+        uni_poly: Rc<Poly>,
+        uni_var: Var,
+        modular: bool,
+    ) -> Result<Rc<Poly>, PolyError> {
         let uni_coeffs = if let Poly::Nested(_, coeffs) = &*uni_poly {
             coeffs
                 .iter()
@@ -449,39 +847,33 @@ impl SceneUtils {
         } else {
             vec![0]
         };
-        let projections = Self::express_in_basis(poly, &uni_coeffs, uni_var);
+        let projections = Self::express_in_basis(poly, &uni_coeffs, uni_var)?;
         let (mut reduced_projections, new_coeffs) = Self::remove_gaps(projections, &uni_coeffs);
         while reduced_projections.len() < new_coeffs.len() - 1 {
             reduced_projections.push(Rc::new(Poly::Constant(0)));
         }
-        Self::reduce_using_projections(reduced_projections, new_coeffs)
+        Self::reduce_using_projections(reduced_projections, new_coeffs, modular)
     }
 
-    fn express_in_basis(poly: Rc<Poly>, uni_coeffs: &Vec<i64>, uni_var: u8) -> Vec<Rc<Poly>> {
+    fn express_in_basis(
+        poly: Rc<Poly>,
+        uni_coeffs: &Vec<i64>,
+        uni_var: Var,
+    ) -> Result<Vec<Rc<Poly>>, PolyError> {
         let d = uni_coeffs.len() as u32 - 1;
-        let d_poly = poly.get_degree(uni_var);
-
         let lc = uni_coeffs[d as usize];
 
-        // If d_poly >= d, multiply poly by lc^(d_poly - d + 1)
-        let adjusted_poly = if d_poly >= d {
-            let mut result = Poly::Constant(0);
-            let multiplier = lc.pow(d_poly - d + 1);
-            result.add_poly_scaled(&*poly, multiplier);
-            &Rc::new(result)
-        } else {
-            &poly
-        };
-
         // Initially, Sum u_power_coeffs[j] u^j = -lc * u^d
         let mut u_power_coeffs = uni_coeffs[0..(d as usize)].to_vec();
 
+        // The projections are computed over the rationals and scaled to integers at the end
         let mut projections = Vec::new();
-        let u_components = adjusted_poly.decompose(uni_var);
+        let u_components = poly.decompose(uni_var);
         // poly Sum c_i u^i, with c_i = u_components[i]
         for (i, u_component) in u_components.into_iter().enumerate() {
+            let u_component = RatPoly::from_poly(&u_component);
             if (i as u32) < d {
-                projections.push(u_component.clone());
+                projections.push(u_component);
             } else {
                 // Update the formula for lc^{i - d + 1} u^i in terms of 1, u,.., u^{d-1}
                 // Note that lc * u^d = -k0 - k1 u - .. - k_{d-1} u^{d-1}
@@ -491,26 +883,37 @@ impl SceneUtils {
                     // multiply the identity by lc * u to find -lc^{i - d + 1} u^i
                     let mut new_u_power_coeffs = vec![0; d as usize];
                     for j in 0..((d as usize) - 1) {
-                        new_u_power_coeffs[j + 1] = u_power_coeffs[j] * lc;
+                        new_u_power_coeffs[j + 1] = checked(u_power_coeffs[j].overflowing_mul(lc));
                     }
                     for j in 0..(d as usize) {
-                        new_u_power_coeffs[j] -= u_power_coeffs[(d - 1) as usize] * uni_coeffs[j];
+                        let product = checked(
+                            u_power_coeffs[(d - 1) as usize].overflowing_mul(uni_coeffs[j]),
+                        );
+                        new_u_power_coeffs[j] =
+                            checked(new_u_power_coeffs[j].overflowing_sub(product));
                     }
                     u_power_coeffs = new_u_power_coeffs;
                 }
                 // lc^{i - d + 1} * u^i = -u_power_coeffs,
                 // thus projections[j] -= u_power_coeffs[j] * c_i / lc^{i - d + 1}
-                let lc_degree = lc.pow((i as u32) - d + 1);
-                let mut scaled_u_component = u_component.clone();
-                Rc::make_mut(&mut scaled_u_component).apply_to_coefficients(|c| c / lc_degree);
+                // A wrapped power may even be 0, which can't be a denominator
+                let (lc_degree, overflowed) = (lc as i128).overflowing_pow((i as u32) - d + 1);
+                if overflowed || lc_degree == 0 {
+                    return Err(PolyError::Overflow);
+                }
                 for j in 0..d {
-                    Rc::make_mut(&mut projections[j as usize])
-                        .add_poly_scaled(&scaled_u_component, -u_power_coeffs[j as usize]);
+                    projections[j as usize].add_scaled(
+                        &u_component,
+                        Rational::new(-u_power_coeffs[j as usize] as i128, lc_degree),
+                    );
                 }
             }
         }
 
-        projections
+        Ok(RatPoly::to_polys(&projections)
+            .into_iter()
+            .map(Rc::new)
+            .collect())
     }
 
     fn remove_gaps(projections: Vec<Rc<Poly>>, uni_coeffs: &Vec<i64>) -> (Vec<Rc<Poly>>, Vec<i64>) {
@@ -574,13 +977,17 @@ impl SceneUtils {
         (new_projections, new_coeffs)
     }
 
-    fn reduce_using_projections(projections: Vec<Rc<Poly>>, uni_coeffs: Vec<i64>) -> Rc<Poly> {
+    fn reduce_using_projections(
+        projections: Vec<Rc<Poly>>,
+        uni_coeffs: Vec<i64>,
+        modular: bool,
+    ) -> Result<Rc<Poly>, PolyError> {
         // Get the matrices separately
         let mut i_matrix = Self::get_i_matrix(&uni_coeffs);
         let mut p_matrix = Self::get_p_matrix(&projections);
 
         // Perform Gaussian elimination
-        let mut reduced_p_matrix = Self::gauss_elimination(&mut i_matrix, &mut p_matrix);
+        let mut reduced_p_matrix = Self::gauss_elimination(&mut i_matrix, &mut p_matrix)?;
 
         // Reduce each row by common GCD
         for row in reduced_p_matrix.iter_mut() {
@@ -594,18 +1001,37 @@ impl SceneUtils {
         }
 
         // Compute the determinant of the reduced matrix
-        Self::compute_determinant_poly(&reduced_p_matrix)
+        if modular {
+            match modular_determinant::determinant(&reduced_p_matrix) {
+                Ok(determinant) => return Ok(Rc::new(determinant)),
+                Err(e) => info!("Computing the determinant exactly: {}", e),
+            }
+        }
+        Ok(Self::compute_determinant_poly(&reduced_p_matrix))
     }
 
+    /// Column elimination zeroing the rows of i_matrix, applied to p_matrix as well.
+    /// Uses fraction-free Bareiss steps: each new column is divided by the previous
+    /// pivot, which keeps the entries equal to minors of the original matrix instead
+    /// of accumulating the multipliers. Fails with `PolyError::Overflow` once an entry
+    /// no longer fits, since the divisions are then no longer exact.
     fn gauss_elimination(
         i_matrix: &mut Vec<Vec<i64>>,
         p_matrix: &mut Vec<Vec<Rc<Poly>>>,
+    ) -> Result<Vec<Vec<Rc<Poly>>>, PolyError> {
+        detect_overflow(|| Self::bareiss_steps(i_matrix, p_matrix))
+    }
+
+    fn bareiss_steps(
+        i_matrix: &mut [Vec<i64>],
+        p_matrix: &mut [Vec<Rc<Poly>>],
     ) -> Vec<Vec<Rc<Poly>>> {
         let d = p_matrix.len(); // p_matrix has d rows
         let matrix_size = 2 * d - 1;
 
         // Initialize remaining_columns: all columns are available initially
         let mut remaining_columns = vec![true; matrix_size];
+        let mut previous_pivot: i64 = 1;
 
         // Loop for i = 0, ..., d - 2 (Gaussian elimination on i_matrix)
         for i in 0..(d - 1) {
@@ -630,35 +1056,36 @@ impl SceneUtils {
 
             // Mark this column as used
             remaining_columns[pivot_col] = false;
+            let pivot_val = i_matrix[i][pivot_col];
 
             // For each remaining column k, perform elimination
             for k in 0..matrix_size {
-                if remaining_columns[k] && i_matrix[i][k] != 0 {
-                    // Compute the multiplier: we want to eliminate i_matrix[i][k]
-                    // using i_matrix[i][pivot_col] as the pivot
-                    let pivot_val = i_matrix[i][pivot_col];
-                    let target_val = i_matrix[i][k];
-
-                    // Find LCM to avoid division
-                    let gcd = pivot_val.unsigned_abs().gcd(target_val.unsigned_abs()) as i64;
-                    let pivot_mult = pivot_val / gcd;
-                    let target_mult = target_val / gcd;
-
-                    // Apply the linear combination to all rows
-                    for l in (i + 1)..(d - 1) {
-                        // Update i_matrix
-                        i_matrix[l][k] =
-                            pivot_mult * i_matrix[l][k] - target_mult * i_matrix[l][pivot_col];
-                    }
-                    for l in 0..d {
-                        // Update p_matrix
-                        let mut new_poly = Poly::Constant(0);
-                        new_poly.add_poly_scaled(&*p_matrix[l][k], pivot_mult);
-                        new_poly.add_poly_scaled(&*p_matrix[l][pivot_col], -target_mult);
-                        p_matrix[l][k] = Rc::new(new_poly);
-                    }
+                if !remaining_columns[k] {
+                    continue;
                 }
+                // column_k = (pivot_val * column_k - target_val * column_pivot) / previous_pivot,
+                // with all divisions exact. Columns whose row i is already zero still
+                // need the step to stay consistent with the others.
+                let target_val = i_matrix[i][k];
+                for l in (i + 1)..(d - 1) {
+                    let value = pivot_val as i128 * i_matrix[l][k] as i128
+                        - target_val as i128 * i_matrix[l][pivot_col] as i128;
+                    let quotient = value / previous_pivot as i128;
+                    i_matrix[l][k] = checked((quotient as i64, i64::try_from(quotient).is_err()));
+                }
+                for l in 0..d {
+                    let mut new_poly = Poly::Constant(0);
+                    new_poly.add_poly_scaled(&*p_matrix[l][k], pivot_val);
+                    new_poly.add_poly_scaled(&*p_matrix[l][pivot_col], -target_val);
+                    // Only inexact after an overflow, which is reported instead
+                    let new_poly = new_poly
+                        .divide_exact(&Poly::Constant(previous_pivot))
+                        .unwrap_or_else(|| checked((Poly::Constant(0), true)));
+                    p_matrix[l][k] = Rc::new(new_poly);
+                }
+                i_matrix[i][k] = 0;
             }
+            previous_pivot = pivot_val;
         }
 
         // Remove deleted columns from p_matrix before returning
@@ -690,64 +1117,7 @@ impl SceneUtils {
     }
 
     fn compute_determinant_poly(matrix: &Vec<Vec<Rc<Poly>>>) -> Rc<Poly> {
-        let n = matrix.len();
-        if n == 0 {
-            return Rc::new(Poly::Constant(0));
-        }
-        if n == 1 {
-            return matrix[0][0].clone();
-        }
-        if n == 2 {
-            // For 2x2 matrix: det = a*d - b*c
-            let a = &matrix[0][0];
-            let b = &matrix[0][1];
-            let c = &matrix[1][0];
-            let d = &matrix[1][1];
-
-            let ad = a.multiply(d);
-            let bc = b.multiply(c);
-            let mut result = ad;
-            result.add_poly_scaled(&bc, -1);
-            return Rc::new(result);
-        }
-
-        // For larger matrices, use cofactor expansion along the first row
-        let mut determinant = Poly::Constant(0);
-
-        for j in 0..n {
-            let cofactor = if j % 2 == 0 { 1 } else { -1 };
-            let minor = Self::compute_minor_poly(matrix, 0, j);
-            let cofactor_poly = Self::compute_determinant_poly(&minor);
-
-            let term = matrix[0][j].multiply(&*cofactor_poly);
-
-            determinant.add_poly_scaled(&term, cofactor);
-        }
-
-        Rc::new(determinant)
-    }
-
-    fn compute_minor_poly(
-        matrix: &Vec<Vec<Rc<Poly>>>,
-        row: usize,
-        col: usize,
-    ) -> Vec<Vec<Rc<Poly>>> {
-        let n = matrix.len();
-        let mut minor = Vec::new();
-
-        for i in 0..n {
-            if i != row {
-                let mut minor_row = Vec::new();
-                for j in 0..n {
-                    if j != col {
-                        minor_row.push(matrix[i][j].clone());
-                    }
-                }
-                minor.push(minor_row);
-            }
-        }
-
-        minor
+        determinant::determinant(matrix)
     }
 
     fn reduce_by_common_gcd(polys: &mut Vec<Rc<Poly>>) {
@@ -812,7 +1182,173 @@ impl SceneUtils {
         p_matrix
     }
 
-    pub fn parse_plot_vars(plot: &Plot) -> Result<(u8, u8), SceneError> {
+    /// Expresses the equation in a frame with the given origin and x-axis direction:
+    /// x = ox + ax * X - ay * Y, y = oy + ay * X + ax * Y, with X and Y written as x and y
+    pub fn to_frame(
+        poly: &Poly,
+        x_var: Var,
+        y_var: Var,
+        origin: (i64, i64),
+        axis: (i64, i64),
+    ) -> Poly {
+        // Temporary variables for X and Y that don't clash with x and y
+        let mut temp_vars = (0..).filter(|v| *v != x_var && *v != y_var);
+        let fx = temp_vars.next().unwrap();
+        let fy = temp_vars.next().unwrap();
+        let var_poly = |v: Var| {
+            Poly::Nested(
+                v,
+                vec![Rc::new(Poly::Constant(0)), Rc::new(Poly::Constant(1))],
+            )
+        };
+
+        let mut x_expr = Poly::Constant(origin.0);
+        x_expr.add_poly_scaled(&var_poly(fx), axis.0);
+        x_expr.add_poly_scaled(&var_poly(fy), -axis.1);
+        let mut y_expr = Poly::Constant(origin.1);
+        y_expr.add_poly_scaled(&var_poly(fx), axis.1);
+        y_expr.add_poly_scaled(&var_poly(fy), axis.0);
+
+        let mut result = poly
+            .substitute_linear(x_var, Rc::new(x_expr), 1)
+            .substitute_linear(y_var, Rc::new(y_expr), 1)
+            .substitute_linear(fx, Rc::new(var_poly(x_var)), 1)
+            .substitute_linear(fy, Rc::new(var_poly(y_var)), 1);
+        result.reduce_coefficients_if_above(1);
+        result
+    }
+
+    /// Renders a polynomial in x_var and y_var as a Python lambda of x and y
+    /// for the equation processor
+    pub fn to_python_function(poly: &Poly, x_var: Var, y_var: Var) -> String {
+        let terms: Vec<String> = poly
+            .to_terms()
+            .iter()
+            .map(|term| {
+                let mut factors = vec![format!("i({})", term.constant)];
+                for &(var, degree) in &term.vars {
+                    let name = match var {
+                        v if v == x_var => "x",
+                        v if v == y_var => "y",
+                        _ => unreachable!("Curve equations only depend on x and y"),
+                    };
+                    factors.push(match degree {
+                        1 => name.to_string(),
+                        _ => format!("{} ** i({})", name, degree),
+                    });
+                }
+                factors.join(" * ")
+            })
+            .collect();
+        let body = if terms.is_empty() {
+            "i(0)".to_string()
+        } else {
+            terms.join(" + ")
+        };
+        format!("lambda x, y: {}", body)
+    }
+
+    /// Python functions of the curve and its partial derivatives, for `TangentLine`
+    pub fn to_curve_functions(curve: &Poly, x_var: Var, y_var: Var) -> CurveFunctions {
+        CurveFunctions {
+            f: Self::to_python_function(curve, x_var, y_var),
+            f_x: Self::to_python_function(&curve.get_derivative(x_var), x_var, y_var),
+            f_y: Self::to_python_function(&curve.get_derivative(y_var), x_var, y_var),
+        }
+    }
+
+    /// Tries to find a rational parametrization (x(t), y(t)) of the irreducible curve
+    /// poly = 0 using Pari/GP. The curve is parametrized by the pencil of lines through
+    /// a rational point of multiplicity d - 1, where d is the total degree: any point off
+    /// a line, a point of a conic found with qfsolve, or a rational singular point of
+    /// maximal multiplicity for higher degrees. Returns None if no such point exists,
+    /// which includes all curves of positive genus.
+    pub fn get_rational_parametrization(
+        poly: &Poly,
+        x_var: Var,
+        y_var: Var,
+    ) -> Result<Option<Parametrization>, String> {
+        let degree = poly
+            .to_terms()
+            .iter()
+            .map(|term| term.vars.iter().map(|(_, d)| d).sum::<u32>())
+            .max()
+            .unwrap_or(0);
+        if degree == 0 {
+            return Ok(None);
+        }
+        let x = Poly::var_to_string(x_var);
+        let y = Poly::var_to_string(y_var);
+        let parameter = ["t", "s", "u", "v"]
+            .into_iter()
+            .find(|name| *name != x && *name != y)
+            .unwrap()
+            .to_string();
+
+        let task = Self::parametrization_task(&format!("{:#}", poly), degree, &x, &y, &parameter);
+        let output_lines = crate::gp_pari_service::get_gp_pari_pool()?.run_task(task)?;
+        match output_lines.as_slice() {
+            [none] if none.trim() == "None" => Ok(None),
+            [x_expr, y_expr] => Ok(Some(Parametrization {
+                parameter,
+                x: x_expr.trim().to_string(),
+                y: y_expr.trim().to_string(),
+            })),
+            _ => Err(format!(
+                "Unexpected output from Pari/GP: {:?}",
+                output_lines
+            )),
+        }
+    }
+
+    fn parametrization_task(poly: &str, degree: u32, x: &str, y: &str, parameter: &str) -> String {
+        // Helper variables have two-letter names, which never clash with scene variables
+        format!(
+            "{{my(pF = {poly}, pd = {degree}, pP = 0, pH, pM, pv, pg, pc, pr, pD, pE, ph, pG, pA, pB);
+if(pd == 1, foreach([[0, 0], [1, 0], [0, 1]], pc,
+    if(pP == 0 && substvec(pF, [{x}, {y}], pc) != 0, pP = pc)));
+if(pd == 2,
+    pH = 'pz^2 * substvec(pF, [{x}, {y}], [{x} / 'pz, {y} / 'pz]);
+    pv = [{x}, {y}, 'pz];
+    pM = matrix(3, 3, ii, jj, deriv(deriv(pH, pv[ii]), pv[jj]));
+    pv = qfsolve(pM);
+    if(type(pv) == \"t_COL\",
+        if(pv[3] != 0, pP = [pv[1] / pv[3], pv[2] / pv[3]],
+            foreach([[0, 0], [1, 0], [0, 1]], pc, if(pP == 0,
+                pg = substvec(pF, [{x}, {y}], [pc[1] + 'ps * pv[1], pc[2] + 'ps * pv[2]]);
+                if(polcoef(pg, 1, 'ps) != 0,
+                    pr = -polcoef(pg, 0, 'ps) / polcoef(pg, 1, 'ps);
+                    pP = [pc[1] + pr * pv[1], pc[2] + pr * pv[2]])))))));
+if(pd >= 3,
+    pD = List();
+    for(kk = 0, pd - 2, for(ii = 0, kk,
+        pg = pF;
+        for(jj = 1, ii, pg = deriv(pg, {x}));
+        for(jj = 1, kk - ii, pg = deriv(pg, {y}));
+        listput(pD, pg)));
+    pE = vector(pd - 1, ii, pD[#pD - pd + 1 + ii]);
+    pr = 0;
+    for(ii = 1, #pE, for(jj = ii + 1, #pE,
+        if(pr == 0, pr = polresultant(pE[ii], pE[jj], {y}))));
+    if(type(pr) == \"t_POL\", foreach(nfroots(, pr), px,
+        ph = 0;
+        for(ii = 1, #pD, ph = gcd(ph, subst(pD[ii], {x}, px)));
+        if(type(ph) == \"t_POL\", foreach(nfroots(, ph), py,
+            if(pP == 0, pP = [px, py]))))));
+if(pP == 0, print(\"None\"),
+    pG = substvec(pF, [{x}, {y}], [pP[1] + 'pu, pP[2] + '{parameter} * 'pu]);
+    pA = polcoef(pG, pd - 1, 'pu);
+    pB = polcoef(pG, pd, 'pu);
+    if(pB == 0 || substvec(pF, [{x}, {y}], [pP[1] - pA / pB, pP[2] - '{parameter} * pA / pB]) != 0,
+        print(\"None\"),
+        print(pP[1] - pA / pB);
+        print(pP[2] - '{parameter} * pA / pB)));
+print(\"Done\")}}"
+        )
+        .replace('\n', " ")
+    }
+
+    pub fn parse_plot_vars(plot: &Plot) -> Result<(Var, Var), SceneError> {
         let x_var =
             Poly::parse_var(&plot.x).map_err(|e| SceneError::InvalidEquation(e.to_string()))?;
         let y_var =
@@ -823,6 +1359,27 @@ impl SceneUtils {
     pub fn evaluate_initial_values(
         python_expressions: &String,
         expressions: &Vec<String>,
+    ) -> Result<Vec<f64>, SceneError> {
+        let mut compiler = EquationCompiler::new(true);
+        let result = compiler.run(python_expressions).and_then(|_| {
+            expressions
+                .iter()
+                .map(|s| compiler.evaluate_float(&Self::prepare_expression(s)))
+                .collect::<Result<Vec<f64>, _>>()
+        });
+        match result {
+            Ok(values) => Ok(values),
+            Err(e) if e.is_unsupported() => {
+                info!("Falling back to the Python equation processor: {}", e);
+                Self::evaluate_initial_values_python(python_expressions, expressions)
+            }
+            Err(e) => Err(SceneError::InvalidEquation(e.to_string())),
+        }
+    }
+
+    fn evaluate_initial_values_python(
+        python_expressions: &String,
+        expressions: &Vec<String>,
     ) -> Result<Vec<f64>, SceneError> {
         let prepared_expressions = expressions
             .iter()
@@ -842,7 +1399,7 @@ impl SceneUtils {
             .arg("-c")
             .arg(python_code)
             .output()
-            .map_err(|e| SceneError::DatabaseError(format!("Failed to run Python: {}", e)))?;
+            .map_err(|e| SceneError::Python(format!("Failed to run Python: {}", e)))?;
         println!("output status: {:?}", output.status);
         println!(
             "output stdout:\n{}",
@@ -856,7 +1413,7 @@ impl SceneUtils {
         }
 
         if !output.status.success() {
-            return Err(SceneError::DatabaseError(format!(
+            return Err(SceneError::Python(format!(
                 "Python execution failed: {}",
                 String::from_utf8_lossy(&output.stderr)
             )));
@@ -876,7 +1433,7 @@ impl SceneUtils {
             match trimmed_line.parse::<f64>() {
                 Ok(value) => values.push(value),
                 Err(_) => {
-                    return Err(SceneError::DatabaseError(format!(
+                    return Err(SceneError::Python(format!(
                         "Failed to parse line {} as float: '{}'",
                         line_num + 1,
                         trimmed_line
@@ -900,25 +1457,8 @@ impl SceneUtils {
             let start = mat.start();
             let end = mat.end();
 
-            // Check what precedes the identifier (ignoring whitespace)
-            let mut preceded_by_dot = false;
-            if start > 0 {
-                let before_identifier = &expression[..start];
-                let trimmed_before = before_identifier.trim_end();
-                if !trimmed_before.is_empty() {
-                    preceded_by_dot = trimmed_before.ends_with('.');
-                }
-            }
-
-            // Check what follows the identifier (ignoring whitespace)
-            let mut followed_by_paren = false;
-            if end < expression.len() {
-                let after_identifier = &expression[end..];
-                let trimmed_after = after_identifier.trim_start();
-                if !trimmed_after.is_empty() {
-                    followed_by_paren = trimmed_after.starts_with('(');
-                }
-            }
+            let (preceded_by_dot, followed_by_paren) =
+                Self::identifier_context(expression, start, end);
 
             // Categorize the identifier
             if followed_by_paren && !preceded_by_dot {
@@ -956,6 +1496,31 @@ impl SceneUtils {
         }
     }
 
+    /// Whether the identifier at start..end of the expression is preceded by a dot
+    /// and whether it is followed by an opening parenthesis (ignoring whitespace)
+    fn identifier_context(expression: &str, start: usize, end: usize) -> (bool, bool) {
+        let preceded_by_dot = expression[..start].trim_end().ends_with('.');
+        let followed_by_paren = expression[end..].trim_start().starts_with('(');
+        (preceded_by_dot, followed_by_paren)
+    }
+
+    /// Replaces the references to the object `old_name` in an expression, leaving
+    /// fields, methods and functions of the same name alone
+    pub fn rename_object_reference(expression: &str, old_name: &str, new_name: &str) -> String {
+        let re = Regex::new(r"\b[a-zA-Z_]\w*\b").unwrap();
+        re.replace_all(expression, |captures: &regex::Captures| {
+            let mat = captures.get(0).unwrap();
+            let (preceded_by_dot, followed_by_paren) =
+                Self::identifier_context(expression, mat.start(), mat.end());
+            if mat.as_str() == old_name && !preceded_by_dot && !followed_by_paren {
+                new_name.to_string()
+            } else {
+                mat.as_str().to_string()
+            }
+        })
+        .to_string()
+    }
+
     pub fn prepare_expression(expression: &String) -> String {
         let formula = expression.replace("^", "**");
         // Use regex to find standalone integers and wrap them with i()
@@ -1024,6 +1589,28 @@ mod tests {
         assert_eq!(plots[0].y, "b");
     }
 
+    #[test]
+    fn test_line_intersection_equations() {
+        // Lines through fixed points and a point sliding on the x-axis
+        let python_expressions = [
+            "T = FreePoint(2, 0)",
+            "is_zero(T.y)",
+            "L1 = LineAB(FixedPoint(0, 1), T)",
+            "L2 = PpToLine(FixedPoint(0, 0), L1)",
+            "X = LineIntersection(L1, L2)",
+            "plot(\"P1\", X)",
+        ]
+        .join("\n");
+        let (equations, plots) = SceneUtils::to_equations(python_expressions).unwrap();
+
+        // The intersection is a pair of new variables bound by both line equations
+        assert_eq!(plots.len(), 1);
+        assert!(equations.len() > 4, "{:?}", equations);
+        let (x, y) = (plots[0].x.clone(), plots[0].y.clone());
+        assert!(equations.iter().filter(|e| e.contains(&x)).count() >= 2);
+        assert!(equations.iter().filter(|e| e.contains(&y)).count() >= 2);
+    }
+
     #[test]
     fn test_parse_plot_vars() {
         let plot = Plot {
@@ -1060,6 +1647,19 @@ mod tests {
         assert_eq!(result.method_names, Vec::<String>::new());
     }
 
+    #[test]
+    fn test_rename_object_reference() {
+        assert_eq!(
+            SceneUtils::rename_object_reference("d(A, X) + A.x - AB.y", "A", "P"),
+            "d(P, X) + P.x - AB.y"
+        );
+        // Fields, methods and functions keep their names
+        assert_eq!(
+            SceneUtils::rename_object_reference("x.x + d(x, d) - d . d ()", "d", "e"),
+            "x.x + d(x, e) - e . d ()"
+        );
+    }
+
     #[test]
     fn test_extract_identifiers_field_access() {
         let expression = "B.y + A.x".to_string();
@@ -1146,14 +1746,14 @@ mod tests {
     #[test]
     fn test_split_into_irreducible_systems_empty() {
         let polys: Vec<Rc<Poly>> = vec![];
-        let result = SceneUtils::split_into_irreducible_systems(polys);
+        let result = SceneUtils::split_into_irreducible_systems(polys, &mut Vec::new());
         assert_eq!(result, vec![] as Vec<Vec<Rc<Poly>>>);
     }
 
     #[test]
     fn test_split_into_irreducible_systems_single_poly() {
         let polys = vec![Rc::new(Poly::new("x^2 - 1").unwrap())];
-        let result = SceneUtils::split_into_irreducible_systems(polys);
+        let result = SceneUtils::split_into_irreducible_systems(polys, &mut Vec::new());
 
         // Should return systems with factors of x^2 - 1 = (x-1)(x+1)
         assert_eq!(result.len(), 2);
@@ -1179,7 +1779,7 @@ mod tests {
             Rc::new(Poly::new("x^2 - 1").unwrap()), // (x-1)(x+1)
             Rc::new(Poly::new("y^2 - 4").unwrap()), // (y-2)(y+2)
         ];
-        let result = SceneUtils::split_into_irreducible_systems(polys);
+        let result = SceneUtils::split_into_irreducible_systems(polys, &mut Vec::new());
 
         // Should return 2 * 2 = 4 combinations
         assert_eq!(result.len(), 4);
@@ -1212,7 +1812,7 @@ mod tests {
             Rc::new(Poly::new("x^2 + 1").unwrap()), // Irreducible over reals
             Rc::new(Poly::new("y^2 - 1").unwrap()), // (y-1)(y+1)
         ];
-        let result = SceneUtils::split_into_irreducible_systems(polys);
+        let result = SceneUtils::split_into_irreducible_systems(polys, &mut Vec::new());
 
         // Should handle factoring failure gracefully
         assert!(!result.is_empty());
@@ -1228,7 +1828,7 @@ mod tests {
         // Test case 1: uni_coeffs = [-2, 3, 1] (a^2 + 3*a - 2), poly = b*a + b^2
         let uni_coeffs = vec![-2, 3, 1]; // coefficients of a^2 + 3*a - 2
         let poly = Rc::new(Poly::new("b*a + b^2").unwrap());
-        let result = SceneUtils::express_in_basis(poly, &uni_coeffs, 0); // variable 'a'
+        let result = SceneUtils::express_in_basis(poly, &uni_coeffs, 0).unwrap(); // variable 'a'
 
         assert_eq!(result.len(), 2);
         assert_eq!(result[0], Rc::new(Poly::new("b^2").unwrap())); // constant term
@@ -1236,7 +1836,7 @@ mod tests {
 
         // Test case 2: uni_coeffs = [-2, 3, 1] (a^2 + 3*a - 2), poly = b*a^2
         let poly = Rc::new(Poly::new("b*a^2").unwrap());
-        let result = SceneUtils::express_in_basis(poly, &uni_coeffs, 0);
+        let result = SceneUtils::express_in_basis(poly, &uni_coeffs, 0).unwrap();
 
         assert_eq!(result.len(), 2);
         assert_eq!(result[0], Rc::new(Poly::new("2*b").unwrap())); // constant term
@@ -1244,11 +1844,19 @@ mod tests {
 
         // Test case 3: uni_coeffs = [-2, 3, 1] (a^2 + 3*a - 2), poly = b*a^3
         let poly = Rc::new(Poly::new("b*a^3").unwrap());
-        let result = SceneUtils::express_in_basis(poly, &uni_coeffs, 0);
+        let result = SceneUtils::express_in_basis(poly, &uni_coeffs, 0).unwrap();
 
         assert_eq!(result.len(), 2);
         assert_eq!(result[0], Rc::new(Poly::new("-6*b").unwrap())); // constant term
         assert_eq!(result[1], Rc::new(Poly::new("11*b").unwrap())); // coefficient of a
+
+        // (2^32)^4 wraps around to 0 in i128
+        let uni_coeffs = vec![1, 1 << 32];
+        let poly = Rc::new(Poly::new("b*a^4").unwrap());
+        assert_eq!(
+            SceneUtils::express_in_basis(poly, &uni_coeffs, 0),
+            Err(PolyError::Overflow)
+        );
     }
 
     #[test]
@@ -1295,13 +1903,44 @@ mod tests {
         let uni_coeffs = vec![-2, 3, 1]; // coefficients of a^2 + 3*a - 2
         let projections = vec![Rc::new(Poly::Constant(5)), Rc::new(Poly::Constant(7))];
 
-        let result = SceneUtils::reduce_using_projections(projections, uni_coeffs);
+        let result = SceneUtils::reduce_using_projections(projections, uni_coeffs, false).unwrap();
 
         // The result should be a polynomial representing the determinant
         // For this simple case, we expect a constant polynomial
         assert!(matches!(*result, Poly::Constant(_)));
     }
 
+    #[test]
+    fn test_compute_determinant_poly() {
+        // Vandermonde matrix, whose determinant is the product of the differences
+        let vars = ["a", "b", "c", "d"];
+        let matrix: Vec<Vec<Rc<Poly>>> = vars
+            .iter()
+            .map(|v| {
+                let powers = [
+                    "1".to_string(),
+                    v.to_string(),
+                    format!("{}^2", v),
+                    format!("{}^3", v),
+                ];
+                powers
+                    .iter()
+                    .map(|p| Rc::new(Poly::new(p).unwrap()))
+                    .collect()
+            })
+            .collect();
+        let determinant = SceneUtils::compute_determinant_poly(&matrix);
+
+        let mut expected = Poly::Constant(1);
+        for i in 0..4 {
+            for j in (i + 1)..4 {
+                let difference = Poly::new(&format!("{} - {}", vars[j], vars[i])).unwrap();
+                expected = expected.multiply(&difference);
+            }
+        }
+        assert_eq!(*determinant, expected);
+    }
+
     #[test]
     fn test_get_i_matrix() {
         // Test case: d = 2, uni_coeffs = [-2, 3, 1] (size d + 1 = 3)
@@ -1359,7 +1998,7 @@ mod tests {
             ],
         ]; // 2 rows, 3 columns
 
-        let reduced_p_matrix = SceneUtils::gauss_elimination(&mut i_matrix, &mut p_matrix);
+        let reduced_p_matrix = SceneUtils::gauss_elimination(&mut i_matrix, &mut p_matrix).unwrap();
 
         // The reduced p_matrix should have the same number of rows but fewer columns
         assert_eq!(reduced_p_matrix.len(), 2);
@@ -1372,6 +2011,105 @@ mod tests {
             reduced_p_matrix[1],
             vec![Rc::new(Poly::Constant(14)), Rc::new(Poly::Constant(-16))]
         );
+
+        // Entries that no longer fit are reported instead of truncated
+        let mut i_matrix = vec![vec![1, 2, 0, 0, 0], vec![i64::MAX, 0, 1, 0, 0]];
+        let mut p_matrix = vec![vec![Rc::new(Poly::Constant(1)); 5]; 3];
+        assert_eq!(
+            SceneUtils::gauss_elimination(&mut i_matrix, &mut p_matrix),
+            Err(PolyError::Overflow)
+        );
+    }
+
+    /// The column elimination with LCM scaling that Bareiss steps replaced
+    fn gauss_elimination_lcm(
+        i_matrix: &mut [Vec<i64>],
+        p_matrix: &mut [Vec<Rc<Poly>>],
+    ) -> Vec<Vec<Rc<Poly>>> {
+        let d = p_matrix.len();
+        let matrix_size = 2 * d - 1;
+        let mut remaining_columns = vec![true; matrix_size];
+        for i in 0..(d - 1) {
+            let pivot_col = (0..matrix_size)
+                .filter(|&j| remaining_columns[j] && i_matrix[i][j] != 0)
+                .min_by_key(|&j| i_matrix[i][j].abs())
+                .unwrap();
+            remaining_columns[pivot_col] = false;
+            for k in 0..matrix_size {
+                if remaining_columns[k] && i_matrix[i][k] != 0 {
+                    let pivot_val = i_matrix[i][pivot_col];
+                    let target_val = i_matrix[i][k];
+                    let gcd = pivot_val.unsigned_abs().gcd(target_val.unsigned_abs()) as i64;
+                    let pivot_mult = pivot_val / gcd;
+                    let target_mult = target_val / gcd;
+                    for row in i_matrix[(i + 1)..(d - 1)].iter_mut() {
+                        row[k] = pivot_mult * row[k] - target_mult * row[pivot_col];
+                    }
+                    for row in p_matrix.iter_mut() {
+                        let mut new_poly = Poly::Constant(0);
+                        new_poly.add_poly_scaled(&row[k], pivot_mult);
+                        new_poly.add_poly_scaled(&row[pivot_col], -target_mult);
+                        row[k] = Rc::new(new_poly);
+                    }
+                }
+            }
+        }
+        p_matrix
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(j, _)| remaining_columns[*j])
+                    .map(|(_, p)| p.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gauss_elimination_matches_lcm_scaling() {
+        let cases: Vec<(Vec<i64>, Vec<&str>)> = vec![
+            (vec![-2, 0, 3, 1, 1], vec!["b", "c", "b*c - 1", "2"]),
+            (vec![5, -3, 0, 2], vec!["b^2 + 1", "3*c", "b - c"]),
+            (vec![7, 0, 0, 0, 0, 3], vec!["b", "0", "c^2", "1", "b*c"]),
+        ];
+        for (uni_coeffs, projections) in cases {
+            let projections: Vec<Rc<Poly>> = projections
+                .iter()
+                .map(|p| Rc::new(Poly::new(p).unwrap()))
+                .collect();
+            let determinant = |lcm: bool| {
+                let mut i_matrix = SceneUtils::get_i_matrix(&uni_coeffs);
+                let mut p_matrix = SceneUtils::get_p_matrix(&projections);
+                let reduced = if lcm {
+                    gauss_elimination_lcm(&mut i_matrix, &mut p_matrix)
+                } else {
+                    SceneUtils::gauss_elimination(&mut i_matrix, &mut p_matrix).unwrap()
+                };
+                // Compared after the GCD reductions of reduce_using_projections
+                let mut reduced = reduced;
+                for row in reduced.iter_mut() {
+                    SceneUtils::reduce_by_common_gcd(row);
+                }
+                let mut reduced = SceneUtils::transpose_matrix(&reduced);
+                for row in reduced.iter_mut() {
+                    SceneUtils::reduce_by_common_gcd(row);
+                }
+                let mut max_coefficient = 0;
+                for p in reduced.iter().flatten() {
+                    p.observe_coefficients(|c| max_coefficient = max_coefficient.max(c.abs()));
+                }
+                (
+                    SceneUtils::compute_determinant_poly(&reduced),
+                    max_coefficient,
+                )
+            };
+            let (bareiss, bareiss_max) = determinant(false);
+            let (lcm, lcm_max) = determinant(true);
+            assert!(bareiss.is_proportional(&lcm, &mut None));
+            assert_ne!(*bareiss, Poly::Constant(0));
+            assert!(bareiss_max <= lcm_max);
+        }
     }
 
     #[test]
@@ -1488,36 +2226,21 @@ mod tests {
     }
 
     #[test]
-    fn test_compute_minor_poly() {
-        let matrix = vec![
-            vec![
-                Rc::new(Poly::Constant(1)),
-                Rc::new(Poly::Constant(2)),
-                Rc::new(Poly::Constant(3)),
-            ],
-            vec![
-                Rc::new(Poly::Constant(4)),
-                Rc::new(Poly::Constant(5)),
-                Rc::new(Poly::Constant(6)),
-            ],
-            vec![
-                Rc::new(Poly::Constant(7)),
-                Rc::new(Poly::Constant(8)),
-                Rc::new(Poly::Constant(9)),
-            ],
-        ];
-        let minor = SceneUtils::compute_minor_poly(&matrix, 0, 0);
-
-        // Should be 2x2 matrix
-        assert_eq!(minor.len(), 2);
-        assert_eq!(minor[0].len(), 2);
-        assert_eq!(minor[1].len(), 2);
-
-        // Check values (removing row 0, col 0)
-        assert_eq!(*minor[0][0], Poly::Constant(5));
-        assert_eq!(*minor[0][1], Poly::Constant(6));
-        assert_eq!(*minor[1][0], Poly::Constant(8));
-        assert_eq!(*minor[1][1], Poly::Constant(9));
+    fn test_to_frame() {
+        // Translation: circle through the new origin
+        let poly = Poly::new("a^2 + b^2 - 25").unwrap();
+        let result = SceneUtils::to_frame(&poly, 0, 1, (3, 4), (1, 0));
+        assert_eq!(result, Poly::new("a^2 + 6*a + b^2 + 8*b").unwrap());
+
+        // Rotation by 90 degrees: x = -Y, y = X
+        let poly = Poly::new("a - 1").unwrap();
+        let result = SceneUtils::to_frame(&poly, 0, 1, (0, 0), (0, 1));
+        assert_eq!(result, Poly::new("-b - 1").unwrap());
+
+        // Unnormalized axis scales the coordinates: x = 2X, y = 2Y
+        let poly = Poly::new("a^2 + b^2 - 4").unwrap();
+        let result = SceneUtils::to_frame(&poly, 0, 1, (0, 0), (2, 0));
+        assert_eq!(result, Poly::new("a^2 + b^2 - 1").unwrap());
     }
 
     #[test]
@@ -1526,15 +2249,134 @@ mod tests {
         let uni_poly = Rc::new(Poly::new("2*a^2 - 1").unwrap());
         let poly = Rc::new(Poly::new("a^3*b + a^2*c - a").unwrap());
 
-        let result = SceneUtils::eliminate_univariate(poly, uni_poly, 0); // uni_var = 0 for 'a'
+        let result =
+            SceneUtils::eliminate_univariate(poly.clone(), uni_poly.clone(), 0, false).unwrap(); // uni_var = 0 for 'a'
 
-        assert_eq!(format!("{}", *result), "-4 + 2*c^2 + 4*b - b^2");
+        assert_eq!(format!("{}", *result), "4 - 2*c^2 - 4*b + b^2");
+        // Modulo primes the same determinant comes out
+        assert_eq!(
+            SceneUtils::eliminate_univariate(poly, uni_poly, 0, true).unwrap(),
+            result
+        );
 
         let uni_poly = Rc::new(Poly::new("2*a^3 - 1").unwrap());
         let poly = Rc::new(Poly::new("a^2*b + c").unwrap());
 
-        let result = SceneUtils::eliminate_univariate(poly, uni_poly, 0); // uni_var = 0 for 'a'
+        let result =
+            SceneUtils::eliminate_univariate(poly.clone(), uni_poly.clone(), 0, false).unwrap(); // uni_var = 0 for 'a'
 
         assert_eq!(format!("{}", *result), "4*c^3 + b^3");
+        assert_eq!(
+            SceneUtils::eliminate_univariate(poly, uni_poly, 0, true).unwrap(),
+            result
+        );
+    }
+
+    #[test]
+    fn test_curve_of_generators() {
+        let backend = crate::cas::NativeBackend;
+        let generators = |equations: &[&str]| -> Vec<Poly> {
+            equations.iter().map(|e| Poly::new(e).unwrap()).collect()
+        };
+        // A circle and an isolated point of the elimination ideal
+        let curve = SceneUtils::curve_of_generators(
+            &backend,
+            generators(&["a^3 + a*b^2 - a - 2*a^2 - 2*b^2 + 2", "a^2*b + b^3 - b"]),
+        )
+        .unwrap();
+        assert!(curve.is_proportional(&Poly::new("a^2 + b^2 - 1").unwrap(), &mut None));
+
+        // Generators without a common factor only leave points
+        assert!(matches!(
+            SceneUtils::curve_of_generators(&backend, generators(&["a - 2", "b"])),
+            Err(SceneError::InvalidEquation(_))
+        ));
+        assert!(SceneUtils::curve_of_generators(&backend, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_simplify_by_gcd() {
+        let polys: Vec<Rc<Poly>> = ["a*c - b", "0", "2*b - 2*a*c", "c^2 + d", "b*c - 1"]
+            .iter()
+            .map(|p| Rc::new(Poly::new(p).unwrap()))
+            .collect();
+        let simplified = SceneUtils::simplify_by_gcd(polys.clone());
+        assert_eq!(
+            simplified,
+            vec![polys[0].clone(), polys[3].clone(), polys[4].clone()]
+        );
+    }
+
+    #[test]
+    fn test_eliminate_and_factor_cancelled() {
+        use crate::cancellation::{with_cancellation, CancellationToken};
+
+        let polys: Vec<Rc<Poly>> = ["a + a*c^2 - 1 + c^2", "b + b*c^2 - 2*c"]
+            .iter()
+            .map(|p| Rc::new(Poly::new(p).unwrap()))
+            .collect();
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = with_cancellation(token, || {
+            SceneUtils::eliminate_and_factor(
+                polys,
+                0,
+                1,
+                &SceneOptions::default(),
+                &mut vec![],
+                &mut vec![],
+            )
+        });
+        assert!(matches!(result, Err(SceneError::Cancelled)));
+    }
+
+    #[test]
+    fn test_get_rational_parametrization() {
+        // Constants have nothing to parametrize and don't need Pari/GP
+        let constant = Poly::new("3").unwrap();
+        assert_eq!(
+            SceneUtils::get_rational_parametrization(&constant, 0, 1),
+            Ok(None)
+        );
+        if crate::gp_pari_service::get_gp_pari_pool().is_err() {
+            return;
+        }
+
+        // A circle and a nodal cubic are rational, the parameter avoids the curve variables
+        for equation in ["a^2 + b^2 - 1", "b^2 - a^3 - a^2"] {
+            let poly = Poly::new(equation).unwrap();
+            let parametrization = SceneUtils::get_rational_parametrization(&poly, 0, 1)
+                .unwrap()
+                .unwrap();
+            assert_eq!(parametrization.parameter, "t");
+        }
+        let poly = Poly::new("t^2 + s^2 - 1").unwrap();
+        let parametrization = SceneUtils::get_rational_parametrization(&poly, 19, 18)
+            .unwrap()
+            .unwrap();
+        assert_eq!(parametrization.parameter, "u");
+
+        // A smooth cubic has genus 1
+        let elliptic = Poly::new("b^2 - a^3 - a - 1").unwrap();
+        assert_eq!(
+            SceneUtils::get_rational_parametrization(&elliptic, 0, 1),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_to_curve_functions() {
+        let curve = Poly::new("a^2 + b^2 - 25").unwrap();
+        let functions = SceneUtils::to_curve_functions(&curve, 0, 1);
+        assert_eq!(
+            functions.f,
+            "lambda x, y: i(-25) + i(1) * y ** i(2) + i(1) * x ** i(2)"
+        );
+        assert_eq!(functions.f_x, "lambda x, y: i(2) * x");
+        assert_eq!(functions.f_y, "lambda x, y: i(2) * y");
+        assert_eq!(
+            SceneUtils::to_python_function(&Poly::Constant(0), 0, 1),
+            "lambda x, y: i(0)"
+        );
     }
 }
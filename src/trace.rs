@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::cell::RefCell;
+
+use crate::poly::{Poly, PolyFormat};
+
+/// A step of solving a system for a curve, recorded by `with_trace`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum TraceStep {
+    /// The equations of the system as given
+    InitialSystem { equations: Vec<String> },
+    /// `var` was solved for in the linear `equation` and substituted into the others
+    Substitution { var: String, equation: String },
+    /// The system after the substitutions, without the equations not constraining x and y
+    ReducedSystem { equations: Vec<String> },
+    /// The reduced system was split into systems of irreducible equations
+    Systems { systems: Vec<Vec<String>> },
+    /// `var` is eliminated next, by remainder chains starting from `equation`, the one of
+    /// the lowest degree in it. The order of these steps can be replayed, see `replay`.
+    Pivot { var: String, equation: String },
+    /// `var` was eliminated from `poly1` and `poly2` by a remainder chain ending in `resultant`
+    Resultant {
+        var: String,
+        poly1: String,
+        poly2: String,
+        resultant: String,
+    },
+    /// The generators of the elimination ideal in x and y found by a Groebner basis
+    GroebnerBasis { equations: Vec<String> },
+    /// The equation left after elimination and its irreducible factors
+    Factored {
+        equation: String,
+        factors: Vec<String>,
+    },
+    /// A factor was kept as part of the curve or dropped, and why
+    FactorDecision {
+        factor: String,
+        kept: bool,
+        reason: String,
+    },
+    /// The curve equation, the product of the distinct factors kept from all systems
+    Curve {
+        equation: String,
+        factors: Vec<String>,
+    },
+}
+
+thread_local! {
+    static STEPS: RefCell<Option<Vec<TraceStep>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, collecting the steps recorded on this thread
+pub fn with_trace<R>(f: impl FnOnce() -> R) -> (R, Vec<TraceStep>) {
+    let previous = STEPS.with(|steps| steps.replace(Some(Vec::new())));
+    let result = f();
+    let steps = STEPS.with(|steps| steps.replace(previous));
+    (result, steps.unwrap_or_default())
+}
+
+/// Records a step. `step` is only evaluated inside `with_trace`.
+pub fn record(step: impl FnOnce() -> TraceStep) {
+    let tracing = STEPS.with(|steps| steps.borrow().is_some());
+    if tracing {
+        let step = step();
+        STEPS.with(|steps| {
+            if let Some(steps) = steps.borrow_mut().as_mut() {
+                steps.push(step);
+            }
+        });
+    }
+}
+
+/// A polynomial of a step, as formatted for users by `PolyFormat`
+pub fn to_string(poly: &Poly) -> String {
+    PolyFormat::new().format(poly)
+}
+
+/// Strings of the polynomials of a system, for the steps
+pub fn to_strings<T: Borrow<Poly>>(polys: &[T]) -> Vec<String> {
+    polys.iter().map(|poly| to_string(poly.borrow())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_trace() {
+        // Outside of with_trace, steps are not even constructed
+        record(|| unreachable!());
+
+        let (result, steps) = with_trace(|| {
+            record(|| TraceStep::InitialSystem {
+                equations: to_strings(&[Poly::new("b + a^2").unwrap()]),
+            });
+            // A nested trace gets its own steps
+            let ((), inner) =
+                with_trace(|| record(|| TraceStep::ReducedSystem { equations: vec![] }));
+            assert_eq!(inner.len(), 1);
+            42
+        });
+        assert_eq!(result, 42);
+        assert_eq!(
+            steps,
+            vec![TraceStep::InitialSystem {
+                equations: vec!["a^2 + b".to_string()]
+            }]
+        );
+        record(|| unreachable!());
+
+        assert_eq!(
+            serde_json::to_value(TraceStep::Substitution {
+                var: "c".to_string(),
+                equation: "c - a".to_string()
+            })
+            .unwrap(),
+            serde_json::json!({"step": "substitution", "var": "c", "equation": "c - a"})
+        );
+    }
+}
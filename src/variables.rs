@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::poly::{Poly, Var};
+
+/// The scene quantity a polynomial variable stands for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariableOrigin {
+    /// Name of the object whose definition introduced the variable
+    pub object: String,
+    /// Coordinate of the object, like "x" or "n.y"; None for the value of a scalar
+    /// object and for auxiliary variables
+    pub coordinate: Option<String>,
+    /// The variable is an intermediate result of the definition of the object
+    pub auxiliary: bool,
+}
+
+impl fmt::Display for VariableOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.coordinate, self.auxiliary) {
+            (_, true) => write!(f, "auxiliary variable of {}", self.object),
+            (Some(coordinate), false) => write!(f, "{}.{}", self.object, coordinate),
+            (None, false) => write!(f, "{}", self.object),
+        }
+    }
+}
+
+/// Origins of the variables of a system, serialized as a map from the variable names
+/// used in the equations ("a", "b", ..., "a1", ...) to the origins
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VariableRegistry {
+    origins: BTreeMap<Var, VariableOrigin>,
+}
+
+impl VariableRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the origin of `var`, unless it already has one: a variable belongs to
+    /// the first object that introduced it
+    pub fn insert(&mut self, var: Var, origin: VariableOrigin) {
+        self.origins.entry(var).or_insert(origin);
+    }
+
+    pub fn get(&self, var: Var) -> Option<&VariableOrigin> {
+        self.origins.get(&var)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+
+    /// The name of the variable followed by its origin if known, like "c (A.x)"
+    pub fn describe(&self, var: Var) -> String {
+        match self.get(var) {
+            Some(origin) => format!("{} ({})", Poly::var_to_string(var), origin),
+            None => Poly::var_to_string(var),
+        }
+    }
+}
+
+impl Serialize for VariableRegistry {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.origins
+                .iter()
+                .map(|(&var, origin)| (Poly::var_to_string(var), origin)),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for VariableRegistry {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let origins = BTreeMap::<String, VariableOrigin>::deserialize(deserializer)?;
+        let origins = origins
+            .into_iter()
+            .map(|(name, origin)| {
+                Poly::parse_var(&name)
+                    .map(|var| (var, origin))
+                    .map_err(serde::de::Error::custom)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(VariableRegistry { origins })
+    }
+}
+
+thread_local! {
+    static REGISTRY: RefCell<Option<Rc<VariableRegistry>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, describing the variables in the messages of this thread with `registry`
+pub fn with_registry<R>(registry: &VariableRegistry, f: impl FnOnce() -> R) -> R {
+    let previous = REGISTRY.with(|current| current.replace(Some(Rc::new(registry.clone()))));
+    let result = f();
+    REGISTRY.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// The variable for errors, warnings and logs: its name, followed by the scene quantity
+/// it stands for inside `with_registry`
+pub fn describe(var: Var) -> String {
+    REGISTRY.with(|current| match current.borrow().as_ref() {
+        Some(registry) => registry.describe(var),
+        None => Poly::var_to_string(var),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let mut registry = VariableRegistry::new();
+        let origin = |object: &str, coordinate: Option<&str>, auxiliary| VariableOrigin {
+            object: object.to_string(),
+            coordinate: coordinate.map(str::to_string),
+            auxiliary,
+        };
+        registry.insert(0, origin("A", Some("x"), false));
+        registry.insert(5, origin("M", None, true));
+        registry.insert(27, origin("t", None, false));
+        // The first origin wins
+        registry.insert(0, origin("B", Some("x"), false));
+
+        assert_eq!(describe(0), "a");
+        with_registry(&registry, || {
+            assert_eq!(describe(0), "a (A.x)");
+            assert_eq!(describe(5), "f (auxiliary variable of M)");
+            assert_eq!(describe(27), "b1 (t)");
+            assert_eq!(describe(1), "b");
+        });
+        assert_eq!(describe(5), "f");
+
+        let json = serde_json::to_value(&registry).unwrap();
+        assert_eq!(
+            json["a"],
+            serde_json::json!({"object": "A", "coordinate": "x", "auxiliary": false})
+        );
+        assert_eq!(json["b1"]["object"], "t");
+        let parsed: VariableRegistry = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, registry);
+    }
+}
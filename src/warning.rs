@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+/// Machine-readable kind of a non-fatal condition encountered while solving a locus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningCode {
+    /// Factoring failed and the polynomial was treated as irreducible
+    FactoringFailed,
+    /// A factor did not satisfy the original system and was dropped
+    FactorSkipped,
+    /// A factor could not be checked against the original system
+    FactorUnchecked,
+    /// Unchecked factors were used because no factor could be verified
+    UncheckedFactorsUsed,
+    /// The numeric check of a factor was inconclusive
+    VerificationInconclusive,
+    /// Coefficients were divided by their common factor to keep them small
+    CoefficientsReduced,
+    /// The elimination ideal has several generators and their gcd was used
+    ExtraGenerators,
+    /// Pari/GP failed while looking for a rational parametrization
+    ParametrizationFailed,
+    /// The singular points of the curve were not searched for
+    SingularPointsSkipped,
+    /// The inflection points of a curve factor were not searched for
+    InflectionPointsSkipped,
+    /// The genus of a curve factor could not be computed
+    GenusUnknown,
+    /// The extent of a curve could not be computed
+    ExtentUnknown,
+    /// The locus is empty, the whole plane or finitely many points instead of a curve
+    DegenerateLocus,
+    /// The locus was plotted from numerical solutions, without a curve equation
+    NumericApproximation,
+    /// The system did not match the replayed elimination order, see `replay`
+    ReplayDiverged,
+}
+
+/// A non-fatal condition reported alongside a successful result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Warning {
+    pub code: WarningCode,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(code: WarningCode, message: String) -> Self {
+        Self { code, message }
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_warning_serialization() {
+        let warning = Warning::new(WarningCode::FactorSkipped, "Skipping factor a".to_string());
+        assert_eq!(
+            serde_json::to_value(&warning).unwrap(),
+            json!({"code": "factor_skipped", "message": "Skipping factor a"})
+        );
+    }
+}
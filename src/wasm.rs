@@ -0,0 +1,29 @@
+//! JavaScript API of the algebra core, for builds with `--no-default-features --features wasm`.
+//! Requests and responses are JSON strings, see `engine`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::engine::{self, EliminationRequest, PlotRequest};
+
+fn parse<'a, T: serde::Deserialize<'a>>(request: &'a str) -> Result<T, JsError> {
+    serde_json::from_str(request).map_err(|e| JsError::new(&format!("Invalid request: {}", e)))
+}
+
+fn to_json<T: serde::Serialize>(response: &T) -> Result<String, JsError> {
+    serde_json::to_string(response).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// `{"equations": [..], "x": "a", "y": "b"}` to `{"equation", "factors", "warnings"}`
+#[wasm_bindgen]
+pub fn eliminate(request: &str) -> Result<String, JsError> {
+    let request: EliminationRequest = parse(request)?;
+    to_json(&engine::eliminate_request(&request).map_err(|e| JsError::new(&e))?)
+}
+
+/// `{"equation", "x", "y", "x_min", "x_max", "y_min", "y_max", "width", "height"}`
+/// to `{"polylines": [[[x, y], ..], ..]}` in pixel coordinates
+#[wasm_bindgen]
+pub fn plot(request: &str) -> Result<String, JsError> {
+    let request: PlotRequest = parse(request)?;
+    to_json(&engine::plot_request(&request).map_err(|e| JsError::new(&e))?)
+}
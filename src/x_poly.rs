@@ -1,12 +1,13 @@
 use crate::fint::{FInt, ZERO_FINT};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::ops::{Add, Index, IndexMut, Mul, Sub};
 
 const NEWTON_MAX_ATTEMPTS: usize = 20;
 const NEWTON_MAX_ITERATIONS: usize = 100;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct XPoly(pub Vec<FInt>);
 
 impl XPoly {
@@ -48,7 +49,12 @@ impl XPoly {
             let term_poly = XPoly::new(term);
 
             quotient = &quotient + &term_poly;
+            let degree = remainder.get_degree();
             remainder = &remainder - &(&term_poly * divisor);
+            // The leading coefficient cancels, but may be left as an interval around 0
+            // that `cleanup` keeps
+            remainder.0.truncate(degree);
+            remainder.cleanup();
         }
 
         (quotient, remainder)
@@ -314,6 +320,25 @@ impl XPoly {
 
         roots
     }
+
+    /// Cauchy's bound on the absolute values of the roots, 1 + max |a_i / a_n|: all roots
+    /// lie strictly inside (-bound, bound). Infinite if the leading coefficient may be 0.
+    pub fn root_bound(&self) -> f64 {
+        let Some((leading, rest)) = self.0.split_last() else {
+            return 0.0;
+        };
+        let leading = if leading.lower_bound() > 0.0 {
+            leading.lower_bound()
+        } else if leading.upper_bound() < 0.0 {
+            -leading.upper_bound()
+        } else {
+            return f64::INFINITY;
+        };
+        1.0 + rest
+            .iter()
+            .map(|c| c.abs_bound() / leading)
+            .fold(0.0, f64::max)
+    }
 }
 
 impl fmt::Display for XPoly {
@@ -399,8 +424,66 @@ impl IndexMut<usize> for XPoly {
     }
 }
 
+/// Sturm sequence of a polynomial, counting its distinct real roots in intervals
+/// without finding them
+pub struct SturmSequence(Vec<XPoly>);
+
+impl SturmSequence {
+    pub fn new(poly: &XPoly) -> Self {
+        SturmSequence(poly.sturm_sequence())
+    }
+
+    /// Number of sign changes in the sequence at x, or None if the signs can't be told
+    /// from the interval evaluation. A value that may be 0 inside the sequence is skipped:
+    /// its neighbours have opposite signs there, so it adds one change with either sign,
+    /// as long as their signs are known.
+    fn sign_changes_at(&self, x: f64) -> Option<u32> {
+        let x = FInt::new_with_bounds(x, x);
+        let mut changes = 0;
+        let mut prev_positive = None;
+        let mut prev_skipped = false;
+        for poly in self.0.iter().filter(|poly| !poly.0.is_empty()) {
+            let value = poly.evaluate(x);
+            let positive = if value.lower_bound() > 0.0 {
+                true
+            } else if value.upper_bound() < 0.0 {
+                false
+            } else if prev_positive.is_some() && !prev_skipped {
+                prev_skipped = true;
+                continue;
+            } else {
+                return None;
+            };
+            if prev_positive.is_some_and(|prev| prev != positive) {
+                changes += 1;
+            }
+            prev_positive = Some(positive);
+            prev_skipped = false;
+        }
+        (!prev_skipped).then_some(changes)
+    }
+
+    /// Exact number of distinct roots in (low, high], or None if the intervals are too
+    /// wide to tell, e.g. when a root is (nearly) at an end
+    pub fn count_roots(&self, low: f64, high: f64) -> Option<u32> {
+        let (at_low, at_high) = (self.sign_changes_at(low)?, self.sign_changes_at(high)?);
+        at_low.checked_sub(at_high)
+    }
+}
+
+/// Result of sampling a polynomial at the corners and the center of a region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignChange {
+    /// All samples have the same definite sign
+    None,
+    /// Some samples could not be told apart from zero
+    Likely,
+    /// Samples of both definite signs were found
+    Certain,
+}
+
 // Definition of XYPoly(polys): p(x, y) = Sum polys[i](y) * x^i
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct XYPoly(pub Vec<XPoly>);
 
 impl XYPoly {
@@ -447,6 +530,86 @@ impl XYPoly {
         XYPoly::new(flipped_coeffs)
     }
 
+    /// The constant polynomial c
+    pub fn constant(c: FInt) -> XYPoly {
+        XYPoly::new(vec![XPoly::new(vec![c])])
+    }
+
+    /// The polynomial a * x + b * y + c
+    pub fn linear(a: FInt, b: FInt, c: FInt) -> XYPoly {
+        let mut poly = XYPoly::new(vec![XPoly::new(vec![c, b]), XPoly::new(vec![a])]);
+        poly.cleanup();
+        poly
+    }
+
+    /// Removes the zero coefficients of the highest powers of x
+    pub fn cleanup(&mut self) {
+        for poly in self.0.iter_mut() {
+            poly.cleanup();
+        }
+        while self.0.last().is_some_and(|poly| poly.0.is_empty()) {
+            self.0.pop();
+        }
+    }
+
+    pub fn degree_in_x(&self) -> usize {
+        self.0.len().max(1) - 1
+    }
+
+    pub fn degree_in_y(&self) -> usize {
+        self.0.iter().map(XPoly::get_degree).max().unwrap_or(0)
+    }
+
+    /// The largest i + j of the terms x^i * y^j
+    pub fn total_degree(&self) -> usize {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, poly)| !poly.0.is_empty())
+            .map(|(i, poly)| i + poly.get_degree())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The polynomial p(a_x * x + b_x * y + c_x, a_y * x + b_y * y + c_y) for
+    /// x_form = [a_x, b_x, c_x] and y_form = [a_y, b_y, c_y]. Applying the view
+    /// transform of a plot this way once is cheaper than for every evaluation.
+    pub fn substitute_affine(&self, x_form: [FInt; 3], y_form: [FInt; 3]) -> XYPoly {
+        let [a, b, c] = x_form;
+        let x_value = XYPoly::linear(a, b, c);
+        let [a, b, c] = y_form;
+        let y_value = XYPoly::linear(a, b, c);
+
+        // Horner's scheme in x, and in y for each coefficient
+        let mut result = XYPoly::new(vec![]);
+        for poly in self.0.iter().rev() {
+            let mut coefficient = XYPoly::new(vec![]);
+            for &c in poly.0.iter().rev() {
+                coefficient = &(&coefficient * &y_value) + &XYPoly::constant(c);
+            }
+            result = &(&result * &x_value) + &coefficient;
+        }
+        result
+    }
+
+    /// The polynomial p(x + dx, y + dy), whose curve is that of p moved by (-dx, -dy)
+    pub fn translate(&self, dx: FInt, dy: FInt) -> XYPoly {
+        let one = FInt::new(1.0);
+        self.substitute_affine([one, ZERO_FINT, dx], [ZERO_FINT, one, dy])
+    }
+
+    /// The polynomial p(sx * x, sy * y), whose curve is that of p shrunk by sx and sy
+    pub fn scale(&self, sx: FInt, sy: FInt) -> XYPoly {
+        self.substitute_affine([sx, ZERO_FINT, ZERO_FINT], [ZERO_FINT, sy, ZERO_FINT])
+    }
+
+    /// The polynomial p(x cos(angle) - y sin(angle), x sin(angle) + y cos(angle)),
+    /// whose curve is that of p rotated by -angle around the origin
+    pub fn rotate(&self, angle: f64) -> XYPoly {
+        let (sin, cos) = (FInt::new(angle.sin()), FInt::new(angle.cos()));
+        self.substitute_affine([cos, sin.negate(), ZERO_FINT], [sin, cos, ZERO_FINT])
+    }
+
     pub fn evaluate(&self, x: FInt, y: FInt) -> FInt {
         let mut result = FInt::new(0.0);
         let mut x_power = FInt::new(1.0);
@@ -459,11 +622,7 @@ impl XYPoly {
         result
     }
 
-    pub fn likely_contains_zero_check_corners_and_center(
-        &self,
-        x_region: FInt,
-        y_region: FInt,
-    ) -> bool {
+    pub fn sign_change_at_corners_and_center(&self, x_region: FInt, y_region: FInt) -> SignChange {
         // Check all four corners of the rectangle plus its center: if all of then are positive
         // (or all are negative), it isn't likely that there is any point in the region for which
         // the polynomial is zero.
@@ -476,16 +635,29 @@ impl XYPoly {
         ];
         let mut all_positive = true;
         let mut all_negative = true;
+        let mut any_positive = false;
+        let mut any_negative = false;
         for (x, y) in corners {
             let value = self.evaluate(FInt::new(x), FInt::new(y));
-            if !value.always_positive() {
+            if value.always_positive() {
+                any_positive = true;
+            } else {
                 all_positive = false;
             }
-            if !value.negate().always_positive() {
+            if value.negate().always_positive() {
+                any_negative = true;
+            } else {
                 all_negative = false;
             }
         }
-        !(all_positive || all_negative)
+        if all_positive || all_negative {
+            SignChange::None
+        } else if any_positive && any_negative {
+            // The polynomial is continuous, so it vanishes somewhere in the region
+            SignChange::Certain
+        } else {
+            SignChange::Likely
+        }
     }
 
     fn compute_determinant(matrix: &mut [Vec<XPoly>]) -> XPoly {
@@ -604,6 +776,22 @@ impl XYPoly {
         }
     }
 
+    /// The polynomial p(x, y) in y for the given x
+    pub fn at_fixed_x(&self, x: FInt) -> XPoly {
+        let degree = self.degree_in_y();
+        let mut coeffs = vec![ZERO_FINT; degree + 1];
+        let mut x_power = FInt::new_with_bounds(1.0, 1.0);
+        for term_poly_in_y in &self.0 {
+            for (coeff, &c) in coeffs.iter_mut().zip(&term_poly_in_y.0) {
+                *coeff = *coeff + c * x_power;
+            }
+            x_power = x_power * x;
+        }
+        let mut poly_y = XPoly::new(coeffs);
+        poly_y.cleanup();
+        poly_y
+    }
+
     pub fn points_at_fixed_x(&self, x: f64, y_low: f64, y_high: f64) -> Vec<FInt> {
         // Evaluate the polynomial at fixed x to get a polynomial in y
         let mut poly_y = XPoly::new(vec![]);
@@ -641,6 +829,59 @@ impl fmt::Debug for XYPoly {
     }
 }
 
+impl Add for &XYPoly {
+    type Output = XYPoly;
+
+    fn add(self, other: &XYPoly) -> XYPoly {
+        let mut result = self.clone();
+        for (i, poly) in other.0.iter().enumerate() {
+            if i < result.0.len() {
+                result.0[i] = &result.0[i] + poly;
+            } else {
+                result.0.push(poly.clone());
+            }
+        }
+        result.cleanup();
+        result
+    }
+}
+
+impl Sub for &XYPoly {
+    type Output = XYPoly;
+
+    fn sub(self, other: &XYPoly) -> XYPoly {
+        let mut result = self.clone();
+        for (i, poly) in other.0.iter().enumerate() {
+            if i < result.0.len() {
+                result.0[i] = &result.0[i] - poly;
+            } else {
+                result.0.push(&XPoly::new(vec![]) - poly);
+            }
+        }
+        result.cleanup();
+        result
+    }
+}
+
+impl Mul for &XYPoly {
+    type Output = XYPoly;
+
+    fn mul(self, other: &XYPoly) -> XYPoly {
+        if self.0.is_empty() || other.0.is_empty() {
+            return XYPoly::new(vec![]);
+        }
+        let mut result = vec![XPoly::new(vec![]); self.0.len() + other.0.len() - 1];
+        for (i, a) in self.0.iter().enumerate() {
+            for (j, b) in other.0.iter().enumerate() {
+                result[i + j] = &result[i + j] + &(a * b);
+            }
+        }
+        let mut poly = XYPoly::new(result);
+        poly.cleanup();
+        poly
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use approx::relative_eq;
@@ -691,6 +932,25 @@ mod tests {
         relative_eq!(remainder.midpoint(), 17.0);
     }
 
+    #[test]
+    fn test_xy_poly_serde() {
+        // 1 + 2*y + x*[0.5, 1.5]
+        let poly = XYPoly::new(vec![
+            XPoly::new(vec![
+                FInt::new_with_bounds(1.0, 1.0),
+                FInt::new_with_bounds(2.0, 2.0),
+            ]),
+            XPoly::new(vec![FInt::new_with_bounds(0.5, 1.5)]),
+        ]);
+        let json = serde_json::to_string(&poly).unwrap();
+        assert_eq!(json, "[[[1.0,1.0],[2.0,2.0]],[[0.5,1.5]]]");
+        let parsed: XYPoly = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.0.len(), 2);
+        assert_eq!(parsed.0[1][0].lower_bound(), 0.5);
+        assert_eq!(parsed.0[1][0].upper_bound(), 1.5);
+        assert!(serde_json::from_str::<XYPoly>("[[[2.0,1.0]]]").is_err());
+    }
+
     #[test]
     fn test_xy_poly_evaluate() {
         // Test constant polynomial
@@ -747,6 +1007,66 @@ mod tests {
         assert_eq!(format!("{:?}", result), "3x^2");
     }
 
+    #[test]
+    fn test_xypoly_arithmetic() {
+        let xpoly =
+            |coefficients: &[f64]| XPoly::new(coefficients.iter().map(|&c| FInt::new(c)).collect());
+        // 1 + 2y + 3x + xy^2 and x - y
+        let p = XYPoly::new(vec![xpoly(&[1.0, 2.0]), xpoly(&[3.0, 0.0, 1.0])]);
+        let q = XYPoly::new(vec![xpoly(&[0.0, -1.0]), xpoly(&[1.0])]);
+        assert_eq!(
+            (p.degree_in_x(), p.degree_in_y(), p.total_degree()),
+            (1, 2, 3)
+        );
+        assert_eq!((&p * &q).total_degree(), 4);
+        assert!((&p - &p).0.is_empty());
+
+        for (x, y) in [(2.0, -3.0), (0.5, 0.25), (-1.0, 4.0)] {
+            let (x, y) = (FInt::new(x), FInt::new(y));
+            let (p_value, q_value) = (p.evaluate(x, y), q.evaluate(x, y));
+            assert!((&p + &q).evaluate(x, y).almost_equals(p_value + q_value));
+            assert!((&p - &q).evaluate(x, y).almost_equals(p_value - q_value));
+            assert!((&p * &q).evaluate(x, y).almost_equals(p_value * q_value));
+        }
+    }
+
+    #[test]
+    fn test_xypoly_affine_substitution() {
+        // x^2 + xy - 2y^2 + 3
+        let p = XYPoly::new(vec![
+            XPoly::new(vec![FInt::new(3.0), FInt::zero(), FInt::new(-2.0)]),
+            XPoly::new(vec![FInt::zero(), FInt::new(1.0)]),
+            XPoly::new(vec![FInt::new(1.0)]),
+        ]);
+        let (dx, dy) = (FInt::new(1.5), FInt::new(-2.0));
+        let translated = p.translate(dx, dy);
+        let scaled = p.scale(dx, dy);
+        let angle = 0.3;
+        let rotated = p.rotate(angle);
+        // The view transform of a 100 x 50 plot of [-2, 2] x [-1, 1]
+        let view = p.substitute_affine(
+            [FInt::new(0.04), FInt::zero(), FInt::new(-2.0)],
+            [FInt::zero(), FInt::new(0.04), FInt::new(-1.0)],
+        );
+        assert_eq!(translated.total_degree(), 2);
+        assert_eq!(view.total_degree(), 2);
+
+        for (x, y) in [(2.0, -3.0), (0.5, 0.25), (37.0, 12.0)] {
+            let (fx, fy) = (FInt::new(x), FInt::new(y));
+            assert!(translated
+                .evaluate(fx, fy)
+                .almost_equals(p.evaluate(fx + dx, fy + dy)));
+            assert!(scaled
+                .evaluate(fx, fy)
+                .almost_equals(p.evaluate(fx * dx, fy * dy)));
+            let (sin, cos) = angle.sin_cos();
+            let expected = p.evaluate(FInt::new(x * cos - y * sin), FInt::new(x * sin + y * cos));
+            assert!(rotated.evaluate(fx, fy).almost_equals(expected));
+            let expected = p.evaluate(FInt::new(-2.0 + 0.04 * x), FInt::new(-1.0 + 0.04 * y));
+            assert!(view.evaluate(fx, fy).almost_equals(expected));
+        }
+    }
+
     #[test]
     fn test_resultant_linear() {
         // Test case 1: Simple polynomials
@@ -951,6 +1271,19 @@ mod tests {
         ]);
         let gcd = p1.gcd(&p2);
         assert_eq!(gcd.to_string("x"), "-1 + x^2");
+
+        // 256 x^2 (x^2 - 2) (4x^2 + 1)^2, whose divisions leave leading coefficients
+        // that are intervals around 0
+        let p = XPoly::new(
+            [0.0, 0.0, -512.0, 0.0, -3840.0, 0.0, -6144.0, 0.0, 4096.0]
+                .into_iter()
+                .map(FInt::new)
+                .collect(),
+        );
+        let gcd = p.gcd(&p.get_derivative());
+        assert_eq!(gcd.get_degree(), 3);
+        assert!(gcd.evaluate(FInt::new(0.0)).abs_bound() < 1e-9);
+        assert_eq!(p.get_roots(-2.0, 2.0).len(), 3);
     }
 
     #[test]
@@ -1032,6 +1365,50 @@ mod tests {
         assert!(roots.iter().any(|r| r.abs_bound() < 1e-12));
     }
 
+    #[test]
+    fn test_sturm_sequence() {
+        // (x - 1) * (x - 2) * (x + 3) = x^3 - 7x + 6
+        let p = XPoly::new(vec![
+            FInt::new(6.0),
+            FInt::new(-7.0),
+            FInt::new(0.0),
+            FInt::new(1.0),
+        ]);
+        let sturm = SturmSequence::new(&p);
+        assert_eq!(sturm.count_roots(-5.0, 5.0), Some(3));
+        assert_eq!(sturm.count_roots(0.0, 1.5), Some(1));
+        assert_eq!(sturm.count_roots(-2.5, 0.5), Some(0));
+        assert_eq!(sturm.count_roots(1.5, 2.0), None);
+
+        // p(3, y) of 3x + y^2 - 2x^2 - 1, i.e. y^2 - 10, with roots +-3.16
+        let xy_poly = XYPoly::new(vec![
+            XPoly::new(vec![FInt::new(-1.0), FInt::new(0.0), FInt::new(1.0)]),
+            XPoly::new(vec![FInt::new(3.0)]),
+            XPoly::new(vec![FInt::new(-2.0)]),
+        ]);
+        let poly_y = xy_poly.at_fixed_x(FInt::new(3.0));
+        assert_eq!(poly_y.get_degree(), 2);
+        assert_eq!(SturmSequence::new(&poly_y).count_roots(0.0, 3.0), Some(0));
+        assert_eq!(SturmSequence::new(&poly_y).count_roots(-4.0, 4.0), Some(2));
+    }
+
+    #[test]
+    fn test_root_bound() {
+        // 2x^3 - 6x + 1 has roots near -1.81, 0.17 and 1.64
+        let p = XPoly::new(vec![
+            FInt::new(1.0),
+            FInt::new(-6.0),
+            FInt::new(0.0),
+            FInt::new(2.0),
+        ]);
+        let bound = p.root_bound();
+        assert!((bound - 4.0).abs() < 1e-12);
+        assert_eq!(p.get_roots(-bound, bound).len(), 3);
+        assert!((XPoly::new(vec![FInt::new(5.0)]).root_bound() - 1.0).abs() < 1e-12);
+        let uncertain = XPoly(vec![FInt::new(1.0), FInt::new_with_bounds(-1e-9, 1e-9)]);
+        assert_eq!(uncertain.root_bound(), f64::INFINITY);
+    }
+
     #[test]
     fn test_fixed_points() {
         // Test points on circle x^2 + y^2 - 1 = 0